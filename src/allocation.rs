@@ -0,0 +1,58 @@
+use crate::config::AllocationConfig;
+
+/// Splits a bankroll that can't fund every market at full size. Each market gets a budget in
+/// proportion to its weight, and its order size shrinks to what the budget buys. A bankroll
+/// that covers every market leaves sizes alone, and markets not configured take weight 1.
+pub struct Allocator {
+    config: AllocationConfig,
+    /// (ticker, weight key) of every market the strategy trades, e.g. ("BTC", "BTC:15m")
+    markets: Vec<(String, String)>,
+}
+
+impl Allocator {
+    pub fn new(config: AllocationConfig, markets: Vec<(String, String)>) -> Self {
+        Self { config, markets }
+    }
+
+    fn weight(&self, asset: &str, key: &str) -> f64 {
+        let find = |wanted: &str| self.config.weights.iter().find(|(k, _)| k.eq_ignore_ascii_case(wanted)).map(|(_, w)| *w);
+        find(key).or_else(|| find(asset)).unwrap_or(1.0)
+    }
+
+    /// Shares per side for `asset` out of `bankroll`, where a full-size pair of `shares` costs
+    /// `pair_cost`. Rounded down to hundredths of a share; 0 when the budget buys none.
+    pub fn shares(&self, asset: &str, shares: f64, pair_cost: f64, bankroll: f64) -> f64 {
+        if !self.config.enabled || pair_cost <= 0.0 || bankroll >= pair_cost * self.markets.len() as f64 {
+            return shares;
+        }
+        let Some((_, key)) = self.markets.iter().find(|(a, _)| a == asset) else {
+            return shares;
+        };
+        let total: f64 = self.markets.iter().map(|(a, k)| self.weight(a, k)).sum();
+        let budget = bankroll.max(0.0) * self.weight(asset, key) / total;
+        let scale = (budget / pair_cost).min(1.0);
+        (shares * scale * 100.0).floor() / 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn splits_a_short_bankroll_by_weight() {
+        let config = AllocationConfig {
+            enabled: true,
+            weights: BTreeMap::from([("BTC".to_string(), 2.0), ("ETH:15m".to_string(), 1.0), ("SOL".to_string(), 1.0)]),
+        };
+        let markets = ["BTC", "ETH", "SOL"].map(|a| (a.to_string(), format!("{}:15m", a))).to_vec();
+        let allocator = Allocator::new(config, markets);
+        // Three pairs of 10 shares at $9 need $27
+        assert_eq!(allocator.shares("BTC", 10.0, 9.0, 27.0), 10.0);
+        assert_eq!(allocator.shares("BTC", 10.0, 9.0, 18.0), 10.0);
+        assert_eq!(allocator.shares("ETH", 10.0, 9.0, 18.0), 5.0);
+        assert_eq!(allocator.shares("SOL", 10.0, 9.0, 12.0), 3.33);
+        assert_eq!(allocator.shares("SOL", 10.0, 9.0, 0.0), 0.0);
+    }
+}
@@ -0,0 +1,402 @@
+use crate::ledger::{self, LedgerEntry, Rationale};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Per-period PnL, switch-interval and sizing statistics of the bot's own trading, from its ledger.
+#[derive(Debug, Clone, Default)]
+pub struct LedgerAnalysis {
+    pub rows: usize,
+    /// Periods with realized PnL
+    pub periods: usize,
+    pub winning_periods: usize,
+    pub losing_periods: usize,
+    pub total_pnl: f64,
+    pub period_pnl: Option<Summary>,
+    pub pnl_by_market: BTreeMap<String, f64>,
+    /// Market periods where both outcomes were bought, and where only one was
+    pub both_sides: usize,
+    pub one_side: usize,
+    /// Seconds between a buy on one outcome and the next buy on the other outcome of the same market
+    pub switch_secs: Option<Summary>,
+    pub shares: Option<Summary>,
+    /// Price × shares per buy
+    pub notional: Option<Summary>,
+    pub pair_cost: Option<Summary>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Summary {
+    pub count: usize,
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub max: f64,
+}
+
+impl Summary {
+    fn of(mut values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        Some(Self {
+            count: values.len(),
+            min: values[0],
+            median: values[values.len() / 2],
+            mean: values.iter().sum::<f64>() / values.len() as f64,
+            max: values[values.len() - 1],
+        })
+    }
+}
+
+/// Which ledger rows to analyze.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisFilter {
+    pub account: Option<String>,
+    /// Unix seconds, inclusive
+    pub since: Option<i64>,
+    /// Unix seconds, exclusive
+    pub until: Option<i64>,
+}
+
+impl AnalysisFilter {
+    /// Buy, sell and resolution rows that pass the filter.
+    fn select<'a>(&self, entries: &'a [LedgerEntry]) -> Vec<&'a LedgerEntry> {
+        entries
+            .iter()
+            .filter(|e| matches!(e.kind.as_str(), "buy" | "sell" | "resolution"))
+            .filter(|e| self.account.is_none() || e.account == self.account)
+            .filter(|e| self.since.is_none_or(|t| e.timestamp >= t) && self.until.is_none_or(|t| e.timestamp < t))
+            .collect()
+    }
+}
+
+/// Ledger files under `paths`: files as given, and files in directories (recursively) whose
+/// name matches `pattern` (`*` and `?` wildcards), in name order.
+pub fn ledger_files(paths: &[PathBuf], pattern: &str) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+                let entry = entry?;
+                let name = entry.file_name().to_str().unwrap_or("");
+                if entry.file_type().is_file() && wildcard_match(pattern, name) {
+                    files.push(entry.into_path());
+                }
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    Ok(files)
+}
+
+/// Rows of every file, in file order.
+pub fn read_ledgers(files: &[PathBuf]) -> Result<Vec<LedgerEntry>> {
+    let mut entries = Vec::new();
+    for file in files {
+        entries.extend(ledger::read_entries(file)?);
+    }
+    Ok(entries)
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    fn matches(p: &[char], n: &[char]) -> bool {
+        match p.split_first() {
+            None => n.is_empty(),
+            Some(('*', rest)) => (0..=n.len()).any(|i| matches(rest, &n[i..])),
+            Some(('?', rest)) => !n.is_empty() && matches(rest, &n[1..]),
+            Some((c, rest)) => n.first() == Some(c) && matches(rest, &n[1..]),
+        }
+    }
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    matches(&p, &n)
+}
+
+/// Unix seconds for an RFC 3339 timestamp, or a local date/time in `tz` given as
+/// `YYYY-MM-DD`, `YYYY-MM-DD HH:MM[:SS]` or `YYYY-MM-DDTHH:MM[:SS]`.
+pub fn parse_time(s: &str, tz: Tz) -> Result<i64> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.timestamp());
+    }
+    let local = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+        .or_else(|| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))
+        .with_context(|| format!("Invalid date/time {:?} (expected RFC 3339 or YYYY-MM-DD[ HH:MM[:SS]])", s))?;
+    // A time skipped by a DST jump has no instant; take the first one after it
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest())
+        .map(|t| t.timestamp())
+        .with_context(|| format!("{} does not exist in {}", s, tz))
+}
+
+/// Analyzes buy, sell and resolution rows that pass `filter`. Rollups repeat PnL already on
+/// other rows and pending resolutions carry none, so both are skipped.
+pub fn analyze(entries: &[LedgerEntry], filter: &AnalysisFilter) -> LedgerAnalysis {
+    let rows = filter.select(entries);
+    let mut analysis = LedgerAnalysis { rows: rows.len(), ..Default::default() };
+
+    let mut by_period: BTreeMap<i64, f64> = BTreeMap::new();
+    // (condition ID, period) -> buys as (timestamp, outcome)
+    let mut buys: BTreeMap<(&str, i64), Vec<(i64, &str)>> = BTreeMap::new();
+    let (mut shares, mut notional, mut pair_cost) = (Vec::new(), Vec::new(), Vec::new());
+    for entry in &rows {
+        if let Some(pnl) = entry.pnl {
+            *by_period.entry(entry.period_timestamp).or_default() += pnl;
+            *analysis.pnl_by_market.entry(entry.market.clone()).or_default() += pnl;
+            analysis.total_pnl += pnl;
+        }
+        if entry.kind == "buy" {
+            buys.entry((&entry.condition_id, entry.period_timestamp)).or_default().push((entry.timestamp, &entry.side));
+            shares.push(entry.size);
+            notional.push(entry.price * entry.size);
+            pair_cost.push(entry.cost_per_pair);
+        }
+    }
+
+    analysis.periods = by_period.len();
+    analysis.winning_periods = by_period.values().filter(|p| **p > 0.0).count();
+    analysis.losing_periods = by_period.values().filter(|p| **p < 0.0).count();
+    analysis.period_pnl = Summary::of(by_period.into_values().collect());
+
+    let mut switches = Vec::new();
+    for legs in buys.values_mut() {
+        legs.sort();
+        if legs.iter().any(|(_, outcome)| *outcome != legs[0].1) {
+            analysis.both_sides += 1;
+        } else {
+            analysis.one_side += 1;
+        }
+        for pair in legs.windows(2) {
+            if pair[0].1 != pair[1].1 {
+                switches.push((pair[1].0 - pair[0].0) as f64);
+            }
+        }
+    }
+    analysis.switch_secs = Summary::of(switches);
+    analysis.shares = Summary::of(shares);
+    analysis.notional = Summary::of(notional);
+    analysis.pair_cost = Summary::of(pair_cost);
+    analysis
+}
+
+impl LedgerAnalysis {
+    pub fn print(&self) {
+        let line = |label: &str, s: &Option<Summary>, unit: &str| match s {
+            Some(s) => eprintln!(
+                "   {:<21} {}{:.2} min | {}{:.2} median | {}{:.2} mean | {}{:.2} max (n={})",
+                label, unit, s.min, unit, s.median, unit, s.mean, unit, s.max, s.count
+            ),
+            None => eprintln!("   {:<21} -", label),
+        };
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("🔍 Ledger analysis ({} rows)", self.rows);
+        eprintln!("   periods with PnL       {} ({} up / {} down)", self.periods, self.winning_periods, self.losing_periods);
+        eprintln!("   total PnL             ${:.2}", self.total_pnl);
+        line("PnL per period", &self.period_pnl, "$");
+        for (market, pnl) in &self.pnl_by_market {
+            eprintln!("   {:<5}                 ${:.2}", market, pnl);
+        }
+        eprintln!("   both sides / one side  {} / {}", self.both_sides, self.one_side);
+        line("switch interval (s)", &self.switch_secs, "");
+        line("shares per buy", &self.shares, "");
+        line("notional per buy", &self.notional, "$");
+        line("pair cost", &self.pair_cost, "$");
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+}
+
+/// Realized PnL of the positions attributed to one rule.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RuleStats {
+    pub positions: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub pnl: f64,
+}
+
+impl RuleStats {
+    fn add(&mut self, pnl: f64) {
+        self.positions += 1;
+        self.wins += (pnl > 0.0) as usize;
+        self.losses += (pnl < 0.0) as usize;
+        self.pnl += pnl;
+    }
+}
+
+/// Realized PnL per position (market period), attributed to the rules behind it from the
+/// `rationale`/`entry` tags on ledger rows. None collects rows written before rules were tagged.
+#[derive(Debug, Clone, Default)]
+pub struct RuleAttribution {
+    /// By the rule that opened the position
+    pub by_entry: BTreeMap<Option<Rationale>, RuleStats>,
+    /// By the first sell rule that fired on the position, or resolution when it was held
+    pub by_exit: BTreeMap<Option<Rationale>, RuleStats>,
+}
+
+#[derive(Default)]
+struct Position {
+    entry: Option<Rationale>,
+    exit: Option<Option<Rationale>>,
+    pnl: Option<f64>,
+}
+
+/// Attributes the realized PnL of rows that pass `filter` to the rules behind them.
+pub fn attribute_rules(entries: &[LedgerEntry], filter: &AnalysisFilter) -> RuleAttribution {
+    let mut rows = filter.select(entries);
+    rows.sort_by_key(|e| e.timestamp);
+    let mut positions: BTreeMap<(Option<&str>, &str, i64), Position> = BTreeMap::new();
+    for entry in rows {
+        let position = positions.entry((entry.account.as_deref(), &entry.condition_id, entry.period_timestamp)).or_default();
+        position.entry = position.entry.or(entry.entry);
+        if matches!(entry.kind.as_str(), "sell" | "resolution") {
+            position.exit.get_or_insert(entry.rationale);
+        }
+        if let Some(pnl) = entry.pnl {
+            *position.pnl.get_or_insert(0.0) += pnl;
+        }
+    }
+    let mut attribution = RuleAttribution::default();
+    for position in positions.into_values() {
+        if let Some(pnl) = position.pnl {
+            attribution.by_entry.entry(position.entry).or_default().add(pnl);
+            attribution.by_exit.entry(position.exit.flatten()).or_default().add(pnl);
+        }
+    }
+    attribution
+}
+
+/// Setting that turns a rule off, for rules that can be.
+fn rule_setting(rule: Rationale) -> Option<&'static str> {
+    match rule {
+        Rationale::PreOrder => Some("strategy.pre_orders_enabled = false"),
+        Rationale::MidMarket => Some("strategy.signal.mid_market_enabled = false"),
+        Rationale::Plugin => Some("strategy.plugin.enabled = false"),
+        Rationale::SellOpposite => Some("strategy.sell_opposite_enabled = false"),
+        Rationale::Danger => Some("strategy.signal.one_side_buy_risk_management = \"none\""),
+        Rationale::SpikeSell => Some("strategy.spike_sell.enabled = false"),
+        Rationale::Shutdown => Some("strategy.flatten_on_shutdown = false"),
+        Rationale::Resolution => None,
+    }
+}
+
+impl RuleAttribution {
+    pub fn is_empty(&self) -> bool {
+        self.by_entry.is_empty() && self.by_exit.is_empty()
+    }
+
+    pub fn print(&self) {
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("🧭 Realized PnL by rule");
+        for (title, by_rule) in [("Entry rule", &self.by_entry), ("Exit rule", &self.by_exit)] {
+            eprintln!("   {:<18} {:>9} {:>5} {:>6} {:>9} {:>8}", title, "positions", "won", "lost", "PnL", "mean");
+            for (rule, s) in by_rule {
+                let name = rule.map_or("untagged", |r| r.label());
+                let hint = match rule.and_then(rule_setting) {
+                    Some(setting) if s.pnl < 0.0 => format!("  ⚠️  losing; to disable: {}", setting),
+                    _ => String::new(),
+                };
+                eprintln!("   {:<18} {:>9} {:>5} {:>6} {:>9.2} {:>8.2}{}",
+                    name, s.positions, s.wins, s.losses, s.pnl, s.pnl / s.positions as f64, hint);
+            }
+        }
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(kind: &str, condition_id: &str, rationale: Rationale, entry: Rationale, pnl: Option<f64>) -> LedgerEntry {
+        LedgerEntry {
+            kind: kind.to_string(),
+            condition_id: condition_id.to_string(),
+            pnl,
+            rationale: Some(rationale),
+            entry: Some(entry),
+            ..LedgerEntry::default()
+        }
+    }
+
+    #[test]
+    fn parses_rfc3339_and_local_times() {
+        let ny = chrono_tz::America::New_York;
+        assert_eq!(parse_time("2026-01-15T14:30:00Z", ny).unwrap(), 1768487400);
+        assert_eq!(parse_time("2026-01-15T09:30:00-05:00", chrono_tz::UTC).unwrap(), 1768487400);
+        // Local forms are read in `tz` (EST in January)
+        assert_eq!(parse_time("2026-01-15", ny).unwrap(), 1768453200);
+        for s in ["2026-01-15 09:30", "2026-01-15T09:30", "2026-01-15 09:30:00", "2026-01-15T09:30:00"] {
+            assert_eq!(parse_time(s, ny).unwrap(), 1768487400, "{}", s);
+        }
+        assert_eq!(parse_time("2026-01-15 09:30:15", ny).unwrap(), 1768487415);
+        assert_eq!(parse_time("2026-01-15 14:30", chrono_tz::UTC).unwrap(), 1768487400);
+
+        // 02:30 is skipped when clocks spring forward: the hour after it, 03:30 EDT
+        assert_eq!(parse_time("2026-03-08 02:30", ny).unwrap(), 1772955000);
+        // 01:30 happens twice when they fall back: the first, in EDT
+        assert_eq!(parse_time("2026-11-01 01:30", ny).unwrap(), 1793511000);
+        assert_eq!(parse_time("2026-11-01T01:30:00-05:00", ny).unwrap(), 1793514600);
+
+        for s in ["", "yesterday", "2026-13-01", "2026-01-32", "2026-01-15 25:00", "15/01/2026", "2026-01-15T09:30:00+25:00"] {
+            assert!(parse_time(s, ny).is_err(), "{:?}", s);
+        }
+    }
+
+    #[test]
+    fn finds_ledger_files_by_wildcard() {
+        assert!(wildcard_match("ledger-*.csv", "ledger-2026-01.csv"));
+        assert!(wildcard_match("ledger-*.csv", "ledger-.csv"));
+        assert!(!wildcard_match("ledger-*.csv", "ledger-2026-01.csv.gz"));
+        assert!(wildcard_match("ledger-2026-??.csv", "ledger-2026-02.csv"));
+        assert!(!wildcard_match("ledger-2026-??.csv", "ledger-2026-2.csv"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("?", ""));
+        assert!(wildcard_match("a*b*c", "aXXbYc"));
+
+        let dir = std::env::temp_dir().join(format!("ledger-files-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("archive")).unwrap();
+        for name in ["ledger-2026-02.csv", "ledger-2026-01.csv", "ledger-old.csv", "notes.txt", "archive/ledger-2025-12.csv"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files.iter().map(|f| f.strip_prefix(&dir).unwrap_or(f).to_string_lossy().into_owned()).collect()
+        };
+        assert_eq!(
+            names(ledger_files(std::slice::from_ref(&dir), "ledger-????-??.csv").unwrap()),
+            ["archive/ledger-2025-12.csv", "ledger-2026-01.csv", "ledger-2026-02.csv"]
+        );
+        assert_eq!(names(ledger_files(std::slice::from_ref(&dir), "*.txt").unwrap()), ["notes.txt"]);
+        // Files given by name are read whatever the pattern
+        assert_eq!(names(ledger_files(&[dir.join("notes.txt")], "*.csv").unwrap()), ["notes.txt"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn attributes_position_pnl_to_entry_and_first_exit_rule() {
+        use Rationale::*;
+        let entries = vec![
+            // Pre-order pair: loser sold, winner resolved — one position
+            row("buy", "a", PreOrder, PreOrder, None),
+            row("sell", "a", SellOpposite, PreOrder, Some(-1.0)),
+            row("resolution", "a", Resolution, PreOrder, Some(3.0)),
+            // Mid-market lone fill sold on danger
+            row("buy", "b", MidMarket, MidMarket, None),
+            row("sell", "b", Danger, MidMarket, Some(-0.5)),
+            // Untagged row from an older ledger
+            LedgerEntry { kind: "resolution".to_string(), condition_id: "c".to_string(), pnl: Some(1.0), ..LedgerEntry::default() },
+        ];
+        let rules = attribute_rules(&entries, &AnalysisFilter::default());
+        assert_eq!(rules.by_entry[&Some(PreOrder)], RuleStats { positions: 1, wins: 1, losses: 0, pnl: 2.0 });
+        assert_eq!(rules.by_entry[&Some(MidMarket)], RuleStats { positions: 1, wins: 0, losses: 1, pnl: -0.5 });
+        assert_eq!(rules.by_entry[&None].pnl, 1.0);
+        assert_eq!(rules.by_exit[&Some(SellOpposite)].pnl, 2.0);
+        assert_eq!(rules.by_exit[&Some(Danger)].pnl, -0.5);
+        assert!(!rules.by_exit.contains_key(&Some(Resolution)));
+    }
+}
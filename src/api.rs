@@ -1,29 +1,55 @@
+use crate::apierror::{ApiError, ApiResult};
+use crate::config::{NetworkConfig, RemoteSignerConfig};
+use crate::metrics::metrics;
 use crate::models::*;
+use crate::orderqueue::{OrderPriority, OrderQueue};
+use crate::orderrules::OrderRules;
+use crate::replay::Replay;
 use anyhow::{Context, Result};
 use reqwest::Client;
 use serde_json::Value;
 use std::str::FromStr;
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
-use hex;
+use base64::Engine as _;
 use log::{warn, error};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::Future;
 
 // Official SDK imports for proper order signing
 use polymarket_client_sdk::clob::{Client as ClobClient, Config as ClobConfig};
-use polymarket_client_sdk::clob::types::{Side, OrderType, OrderStatusType, SignatureType};
+use polymarket_client_sdk::clob::types::{Side, OrderType, OrderStatusType, SignatureType, SignedOrder};
+use polymarket_client_sdk::clob::types::request::{BalanceAllowanceRequest, OrdersRequest, TradesRequest};
+use polymarket_client_sdk::clob::types::TraderSide;
 use polymarket_client_sdk::POLYGON;
-use alloy::signers::local::LocalSigner;
-use alloy::signers::Signer as _;
+use polymarket_client_sdk::auth::{Credentials, Normal, Uuid};
+use polymarket_client_sdk::auth::state::Authenticated;
+use polymarket_client_sdk::error::Error as SdkError;
+use alloy::signers::local::{LocalSigner, PrivateKeySigner};
+use alloy::signers::Signer;
+use alloy::network::{EthereumWallet, TxSigner};
+use alloy::consensus::SignableTransaction;
 use alloy::primitives::Address as AlloyAddress;
-use alloy::primitives::{Address, B256, U256, Bytes};
+use alloy::primitives::{Address, B256, U256, Bytes, ChainId, Signature};
 use alloy::primitives::keccak256;
 use alloy::providers::{Provider, ProviderBuilder};
-use alloy::rpc::types::eth::TransactionRequest;
+use alloy::rpc::types::eth::{TransactionReceipt, TransactionRequest};
 use alloy::sol;
 use alloy_sol_types::SolCall;
 
+/// Polygon USDC.e, the CLOB's collateral
+const USDC_ADDRESS: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+const RPC_URL: &str = "https://polygon-rpc.com";
+/// Polymarket Proxy Wallet Factory (MagicLink users) – execute via factory.proxy([call])
+const PROXY_WALLET_FACTORY: &str = "0xaB45c5A4B0c941a2F231C04C3f49182e1A254052";
+
 sol! {
+    interface IERC20 {
+        function transfer(address to, uint256 amount) external returns (bool);
+    }
+
     interface IConditionalTokens {
         function redeemPositions(
             address collateralToken,
@@ -31,6 +57,10 @@ sol! {
             bytes32 conditionId,
             uint256[] indexSets
         ) external;
+
+        function payoutDenominator(bytes32 conditionId) external view returns (uint256);
+
+        function payoutNumerators(bytes32 conditionId, uint256 index) external view returns (uint256);
     }
 }
 
@@ -50,79 +80,413 @@ fn parse_token_id_to_u256(s: &str) -> Result<U256> {
     }
 }
 
+/// reqwest client with the `network` proxy, timeouts and pool settings.
+pub fn http_client(network: &NetworkConfig) -> Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(std::time::Duration::from_millis(network.request_timeout_ms))
+        .connect_timeout(std::time::Duration::from_millis(network.connect_timeout_ms))
+        .pool_max_idle_per_host(network.pool_max_idle_per_host)
+        .pool_idle_timeout(std::time::Duration::from_secs(network.pool_idle_timeout_secs));
+    if let Some(proxy) = &network.proxy {
+        builder = builder.proxy(reqwest::Proxy::all(proxy).context(format!("Invalid network.proxy {}", proxy))?);
+    }
+    builder.build().context("Failed to build HTTP client")
+}
+
+/// Signs orders, API key derivation and transactions for the trading wallet: with the private
+/// key in memory, or through a signing service that keeps the key (`remote_signer`).
+#[derive(Clone)]
+pub enum WalletSigner {
+    Local(PrivateKeySigner),
+    Remote(RemoteSigner),
+}
+
+impl WalletSigner {
+    pub fn address(&self) -> Address {
+        match self {
+            Self::Local(signer) => signer.address(),
+            Self::Remote(signer) => signer.address,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Signer for WalletSigner {
+    async fn sign_hash(&self, hash: &B256) -> alloy::signers::Result<Signature> {
+        match self {
+            Self::Local(signer) => signer.sign_hash(hash).await,
+            Self::Remote(signer) => signer.sign_hash(hash).await.map_err(|e| alloy::signers::Error::other(format!("{:#}", e))),
+        }
+    }
+
+    fn address(&self) -> Address {
+        WalletSigner::address(self)
+    }
+
+    fn chain_id(&self) -> Option<ChainId> {
+        match self {
+            Self::Local(signer) => signer.chain_id(),
+            Self::Remote(signer) => signer.chain_id,
+        }
+    }
+
+    fn set_chain_id(&mut self, chain_id: Option<ChainId>) {
+        match self {
+            Self::Local(signer) => signer.set_chain_id(chain_id),
+            Self::Remote(signer) => signer.chain_id = chain_id,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl TxSigner<Signature> for WalletSigner {
+    fn address(&self) -> Address {
+        WalletSigner::address(self)
+    }
+
+    async fn sign_transaction(&self, tx: &mut dyn SignableTransaction<Signature>) -> alloy::signers::Result<Signature> {
+        if let Self::Local(signer) = self {
+            return signer.sign_transaction(tx).await;
+        }
+        if let Some(chain_id) = self.chain_id() {
+            if !tx.set_chain_id_checked(chain_id) {
+                return Err(alloy::signers::Error::TransactionChainIdMismatch { signer: chain_id, tx: tx.chain_id().unwrap_or_default() });
+            }
+        }
+        self.sign_hash(&tx.signature_hash()).await
+    }
+}
+
+/// Asks a JSON-RPC service for each signature: `method` is called with the wallet address and
+/// the 32-byte hash, and answers with the 65-byte signature. The key never reaches the bot, so
+/// the service can sit in front of a hardware wallet or a KMS.
+#[derive(Clone)]
+pub struct RemoteSigner {
+    client: Client,
+    url: String,
+    method: String,
+    address: Address,
+    chain_id: Option<ChainId>,
+}
+
+impl RemoteSigner {
+    pub fn new(config: &RemoteSignerConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(std::time::Duration::from_millis(config.timeout_ms))
+            .build()
+            .context("Failed to build HTTP client for the remote signer")?;
+        Ok(Self {
+            client,
+            url: config.url.clone(),
+            method: config.method.clone(),
+            address: Address::from_str(&config.address).context(format!("Invalid remote_signer.address {}", config.address))?,
+            chain_id: None,
+        })
+    }
+
+    async fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": self.method,
+            "params": [self.address.to_string(), hash.to_string()],
+        });
+        let response: Value = self.client.post(&self.url).json(&request).send().await
+            .context(format!("Remote signer at {} did not answer", self.url))?
+            .error_for_status()
+            .context("Remote signer returned an error status")?
+            .json()
+            .await
+            .context("Remote signer sent a response that is not JSON")?;
+        if let Some(error) = response.get("error") {
+            anyhow::bail!("Remote signer refused to sign: {}", error);
+        }
+        let signature = response.get("result").and_then(Value::as_str)
+            .ok_or_else(|| anyhow::anyhow!("Remote signer response has no signature: {}", response))?;
+        verified_signature(signature, hash, self.address)
+    }
+}
+
+/// The hex `signature`, checked to be `address`'s signature of `hash`, so a service signing
+/// with the wrong key fails here instead of at the exchange.
+fn verified_signature(signature: &str, hash: &B256, address: Address) -> Result<Signature> {
+    let signature = Signature::from_str(signature).context("Remote signer returned a malformed signature")?;
+    let signed_by = signature.recover_address_from_prehash(hash).context("Remote signer returned an invalid signature")?;
+    anyhow::ensure!(signed_by == address, "Remote signer signed with {} instead of {}", signed_by, address);
+    Ok(signature)
+}
+
 pub struct PolymarketApi {
     client: Client,
     gamma_url: String,
     clob_url: String,
+    data_url: String,
     api_key: Option<String>,
     api_secret: Option<String>,
     api_passphrase: Option<String>,
     private_key: Option<String>,
+    /// Signs in place of `private_key` when set
+    remote_signer: Option<RemoteSigner>,
     proxy_wallet_address: Option<String>,
     signature_type: Option<u8>,
     authenticated: Arc<tokio::sync::Mutex<bool>>,
+    throttle: Option<Throttle>,
+    /// Order submissions, let through by priority
+    order_queue: OrderQueue,
+    /// Sign orders but don't post them (`--paper-live`)
+    paper_live: bool,
+    /// Exchange nonce signed into every order; orders under an older nonce are void on-chain
+    order_nonce: u64,
+    /// token_id -> tick and minimum size, with when they were fetched
+    order_rules: std::sync::Mutex<HashMap<String, (std::time::Instant, OrderRules)>>,
+    /// Gamma markets by slug and CLOB market details by condition ID, with when they were fetched
+    market_cache: std::sync::Mutex<MarketCache>,
+    /// How long cached market metadata is reused; zero turns the cache off
+    market_cache_ttl: std::time::Duration,
+    /// L2 API credentials: from config, else derived at the first authenticated request, and
+    /// re-derived from the private key when the CLOB rejects them
+    credentials: std::sync::Mutex<Option<Credentials>>,
+    /// Sign L1/L2 auth headers with the CLOB's clock; turned on once a re-authentication was needed
+    server_time_auth: AtomicBool,
+    /// Re-authentications in a row that did not get requests through
+    reauth_failures: AtomicU32,
+    /// No authenticated request goes out before this, after a 429
+    backoff_until: std::sync::Mutex<Option<tokio::time::Instant>>,
+    /// Market data comes from recorded snapshots instead of the network (`replay`)
+    replay: Option<Arc<Replay>>,
+}
+
+type AuthenticatedClient = ClobClient<Authenticated<Normal>>;
+
+/// Failed re-authentications in a row before an alert is raised.
+const REAUTH_ALERT_AFTER: u32 = 3;
+
+/// How long to hold back after a 429 that came without `Retry-After`.
+const RATE_LIMIT_BACKOFF: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Seconds before its signed expiration that the CLOB stops matching a GTD order.
+const GTD_SECURITY_SECS: i64 = 60;
+
+/// How long a token's order rules are reused before the book is asked again.
+const ORDER_RULES_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Market metadata that doesn't change within a period: token IDs, question, end date.
+#[derive(Default)]
+struct MarketCache {
+    by_slug: HashMap<String, (std::time::Instant, Market)>,
+    by_condition: HashMap<String, (std::time::Instant, MarketDetails)>,
+}
+
+impl MarketCache {
+    /// Drops entries older than `ttl`, so markets of past periods don't pile up.
+    fn prune(&mut self, ttl: std::time::Duration) {
+        self.by_slug.retain(|_, (fetched, _)| fetched.elapsed() < ttl);
+        self.by_condition.retain(|_, (fetched, _)| fetched.elapsed() < ttl);
+    }
+}
+
+/// Spaces out a client's authenticated requests to a maximum rate.
+struct Throttle {
+    interval: std::time::Duration,
+    next: tokio::sync::Mutex<tokio::time::Instant>,
+}
+
+impl Throttle {
+    /// Waits for the next free slot; callers queue behind each other.
+    async fn wait(&self) {
+        let mut next = self.next.lock().await;
+        let now = tokio::time::Instant::now();
+        if *next > now {
+            tokio::time::sleep_until(*next).await;
+        }
+        *next = (*next).max(now) + self.interval;
+    }
 }
 
 impl PolymarketApi {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         gamma_url: String,
         clob_url: String,
+        data_url: String,
         api_key: Option<String>,
         api_secret: Option<String>,
         api_passphrase: Option<String>,
         private_key: Option<String>,
         proxy_wallet_address: Option<String>,
         signature_type: Option<u8>,
+        network: &NetworkConfig,
     ) -> Self {
-        let client = Client::builder()
-            .timeout(std::time::Duration::from_secs(10))
-            .build()
-            .expect("Failed to create HTTP client");
+        let client = http_client(network).expect("Failed to create HTTP client");
+        let configured = match (&api_key, &api_secret, &api_passphrase) {
+            (Some(key), Some(secret), Some(passphrase)) => Uuid::parse_str(key)
+                .ok()
+                .map(|key| Credentials::new(key, secret.clone(), passphrase.clone())),
+            _ => None,
+        };
         
         Self {
             client,
             gamma_url,
             clob_url,
+            data_url,
             api_key,
             api_secret,
             api_passphrase,
             private_key,
+            remote_signer: None,
             proxy_wallet_address,
             signature_type,
             authenticated: Arc::new(tokio::sync::Mutex::new(false)),
+            throttle: None,
+            order_queue: OrderQueue::new(network.max_orders_in_flight),
+            paper_live: false,
+            order_nonce: 0,
+            order_rules: std::sync::Mutex::new(HashMap::new()),
+            market_cache: std::sync::Mutex::new(MarketCache::default()),
+            market_cache_ttl: std::time::Duration::ZERO,
+            credentials: std::sync::Mutex::new(configured),
+            server_time_auth: AtomicBool::new(false),
+            reauth_failures: AtomicU32::new(0),
+            backoff_until: std::sync::Mutex::new(None),
+            replay: None,
+        }
+    }
+
+    /// Orders through this client are built and signed as usual, then logged instead of posted.
+    pub fn with_paper_live(mut self) -> Self {
+        self.paper_live = true;
+        self
+    }
+
+    /// Signs orders, API key derivation and transactions through `signer` instead of a private key.
+    pub fn with_remote_signer(mut self, signer: RemoteSigner) -> Self {
+        self.remote_signer = Some(signer);
+        self
+    }
+
+    /// Signs orders with `nonce` (`polymarket.order_nonce`) instead of 0.
+    pub fn with_order_nonce(mut self, nonce: u64) -> Self {
+        self.order_nonce = nonce;
+        self
+    }
+
+    /// Reuses market metadata for `ttl` (`discovery.market_cache_secs`) instead of asking Gamma
+    /// and the CLOB again on every lookup.
+    pub fn with_market_cache(mut self, ttl: std::time::Duration) -> Self {
+        self.market_cache_ttl = ttl;
+        self
+    }
+
+    /// Forgets cached metadata of the market with `condition_id`; called when its period rolls
+    /// over.
+    pub fn invalidate_market(&self, condition_id: &str) {
+        let mut cache = self.market_cache.lock().unwrap();
+        cache.by_condition.remove(condition_id);
+        cache.by_slug.retain(|_, (_, market)| market.condition_id != condition_id);
+    }
+
+    /// Market reads through this client (markets, prices, books, last trades, server time) are
+    /// answered from `replay` at the replay clock's time.
+    pub fn with_replay(mut self, replay: Arc<Replay>) -> Self {
+        self.replay = Some(replay);
+        self
+    }
+
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_deref()
+    }
+
+    /// Limits orders, cancels, order status checks, redemptions and balance reads through this
+    /// client to `per_sec` requests per second.
+    pub fn with_rate_limit(mut self, per_sec: f64) -> Self {
+        self.throttle = Some(Throttle {
+            interval: std::time::Duration::from_secs_f64(1.0 / per_sec),
+            next: tokio::sync::Mutex::new(tokio::time::Instant::now()),
+        });
+        self
+    }
+
+    async fn throttle(&self) {
+        let backoff = *self.backoff_until.lock().unwrap();
+        if let Some(until) = backoff.filter(|until| *until > tokio::time::Instant::now()) {
+            tokio::time::sleep_until(until).await;
+        }
+        if let Some(throttle) = &self.throttle {
+            throttle.wait().await;
+        }
+    }
+
+    /// Counts a finished call in the metrics. A 429 holds back the next authenticated calls for
+    /// the server's `Retry-After`, or `RATE_LIMIT_BACKOFF` without one.
+    fn observe<T>(&self, endpoint: &str, result: &ApiResult<T>) {
+        metrics().observe_api(endpoint, result);
+        if let Err(ApiError::RateLimited { retry_after, .. }) = result {
+            let wait = retry_after.unwrap_or(RATE_LIMIT_BACKOFF);
+            warn!("🐢 Rate limited on {} — holding authenticated requests for {:.1}s", endpoint, wait.as_secs_f64());
+            *self.backoff_until.lock().unwrap() = Some(tokio::time::Instant::now() + wait);
         }
     }
     
     // Authenticate with Polymarket CLOB API
     pub async fn authenticate(&self) -> Result<()> {
-        let private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for authentication. Please set private_key in config.json"))?;
-        let signer = LocalSigner::from_str(private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
+        if self.proxy_wallet_address.is_some() && matches!(self.signature_type, Some(0) | None) {
+            warn!("Proxy_wallet_address is set but signature_type is EOA. Defaulting to Proxy.");
+        }
+        self.with_reauth(|| self.clob_client()).await?;
+
+        *self.authenticated.lock().await = true;
         
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
+        eprintln!("   ✓ Successfully authenticated with Polymarket CLOB API");
+        match &self.remote_signer {
+            Some(remote) => eprintln!("   ✓ Remote signer: {} signing for {}", remote.url, remote.address),
+            None => eprintln!("   ✓ Private key: Valid"),
+        }
+        eprintln!("   ✓ API credentials: Valid");
+        if let Some(proxy_addr) = &self.proxy_wallet_address {
+            eprintln!("   ✓ Proxy wallet: {}", proxy_addr);
+        } else {
+            eprintln!("   ✓ Trading account: EOA (signer's account)");
+        }
+        Ok(())
+    }
+
+    /// The wallet's signer on Polygon: the remote signer when one is configured, else the
+    /// private key.
+    fn signer(&self) -> Result<WalletSigner> {
+        let signer = match (&self.remote_signer, &self.private_key) {
+            (Some(remote), _) => WalletSigner::Remote(remote.clone()),
+            (None, Some(private_key)) => WalletSigner::Local(
+                LocalSigner::from_str(private_key)
+                    .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?,
+            ),
+            (None, None) => anyhow::bail!("Signing needs a wallet. Please set private_key, private_key_file or remote_signer in config.json"),
+        };
+        Ok(signer.with_chain_id(Some(POLYGON)))
+    }
+
+    /// Signer and authenticated CLOB client for this account. Uses the cached API credentials;
+    /// without them the SDK creates or derives a set from the private key, which is cached.
+    async fn clob_client(&self) -> Result<(WalletSigner, AuthenticatedClient)> {
+        let signer = self.signer()?;
+
+        let config = ClobConfig::builder().use_server_time(self.server_time_auth.load(Ordering::Relaxed)).build();
+        let mut auth_builder = ClobClient::new(&self.clob_url, config)
             .context("Failed to create CLOB client")?
             .authentication_builder(&signer);
-        
+
         if let Some(proxy_addr) = &self.proxy_wallet_address {
             let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
                 .context(format!("Failed to parse proxy_wallet_address: {}. Ensure it's a valid Ethereum address.", proxy_addr))?;
-            
             auth_builder = auth_builder.funder(funder_address);
-            
+
             let sig_type = match self.signature_type {
                 Some(1) => SignatureType::Proxy,
                 Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => {
-                    warn!("Proxy_wallet_address is set but signature_type is EOA. Defaulting to Proxy.");
-                    SignatureType::Proxy
-                },
+                Some(0) | None => SignatureType::Proxy, // Default to Proxy when proxy wallet is set
                 Some(n) => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
             };
-            
             auth_builder = auth_builder.signature_type(sig_type);
-            eprintln!("Using proxy wallet: {} (signature type: {:?})", proxy_addr, sig_type);
         } else if let Some(sig_type_num) = self.signature_type {
             // If signature type is set but no proxy wallet, validate it's EOA
             let sig_type = match sig_type_num {
@@ -132,25 +496,90 @@ impl PolymarketApi {
             };
             auth_builder = auth_builder.signature_type(sig_type);
         }
-        
-        let _client = auth_builder
+
+        let cached = self.credentials.lock().unwrap().clone();
+        if let Some(credentials) = cached {
+            auth_builder = auth_builder.credentials(credentials);
+        }
+        let client = auth_builder
             .authenticate()
             .await
             .context("Failed to authenticate with CLOB API. Check your API credentials (api_key, api_secret, api_passphrase) and private_key.")?;
-        
-        *self.authenticated.lock().await = true;
-        
-        eprintln!("   ✓ Successfully authenticated with Polymarket CLOB API");
-        eprintln!("   ✓ Private key: Valid");
-        eprintln!("   ✓ API credentials: Valid");
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            eprintln!("   ✓ Proxy wallet: {}", proxy_addr);
-        } else {
-            eprintln!("   ✓ Trading account: EOA (private key account)");
+        *self.credentials.lock().unwrap() = Some(client.credentials().clone());
+        Ok((signer, client))
+    }
+
+    /// Runs an authenticated request. When the CLOB answers 401/403 (expired or revoked API key,
+    /// or auth headers stamped with a drifting clock), the API credentials are derived afresh
+    /// from the private key, auth headers switch to the CLOB's clock, and the request is tried
+    /// once more. Repeated failures raise an alert.
+    async fn with_reauth<T, F, Fut>(&self, request: F) -> ApiResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let first = request().await.map_err(ApiError::classify);
+        match &first {
+            Ok(_) => {
+                self.reauth_failures.store(0, Ordering::Relaxed);
+                return first;
+            }
+            Err(e @ ApiError::Auth(_)) => {
+                warn!("🔐 CLOB rejected our credentials ({}) — re-deriving the API key from the private key", e);
+            }
+            Err(_) => return first,
+        }
+        let retried = match self.reauthenticate().await {
+            Ok(()) => request().await.map_err(ApiError::classify),
+            Err(reauth) => Err(ApiError::Auth(format!("{:#}", reauth))),
+        };
+        match &retried {
+            Err(e @ ApiError::Auth(_)) => self.note_reauth_failure(e),
+            _ => {
+                self.reauth_failures.store(0, Ordering::Relaxed);
+                metrics().inc_reauth("ok");
+                if retried.is_ok() {
+                    log::info!("🔐 Re-authenticated with the CLOB");
+                }
+            }
+        }
+        retried
+    }
+
+    /// Derives the account's API credentials from the private key, using the CLOB's clock for
+    /// the L1 signature, and caches them for the following requests.
+    async fn reauthenticate(&self) -> Result<()> {
+        let signer = self.signer().context("Re-authentication failed")?;
+        self.server_time_auth.store(true, Ordering::Relaxed);
+        let client = ClobClient::new(&self.clob_url, ClobConfig::builder().use_server_time(true).build())
+            .context("Re-authentication failed: could not create CLOB client")?;
+        let credentials = client.derive_api_key(&signer, None).await;
+        let credentials = match credentials {
+            Ok(c) => Ok(c),
+            Err(_) => client.create_api_key(&signer, None).await,
         }
+        .map_err(|e| anyhow::anyhow!("Re-authentication failed: {}", e))?;
+        *self.credentials.lock().unwrap() = Some(credentials);
         Ok(())
     }
 
+    fn note_reauth_failure(&self, e: &ApiError) {
+        metrics().inc_reauth("failed");
+        let failures = self.reauth_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures < REAUTH_ALERT_AFTER {
+            warn!("🔐 Re-authentication did not help ({} in a row): {}", failures, e);
+            return;
+        }
+        tracing::error!(
+            target: crate::logging::EVENTS,
+            event = "auth_failed",
+            failures,
+            error = %e,
+        );
+        error!("🚨 ALERT: CLOB authentication has failed {} times in a row: {}", failures, e);
+        error!("   Orders, cancels and balance reads will keep failing. Check private_key, proxy_wallet_address, signature_type and the host clock.");
+    }
+
     /// Generate HMAC-SHA256 signature for authenticated requests
     fn generate_signature(
         &self,
@@ -164,7 +593,7 @@ impl PolymarketApi {
         
         let message = format!("{}{}{}{}", method, path, body, timestamp);
         
-        let secret_bytes = match base64::decode(secret) {
+        let secret_bytes = match base64::engine::general_purpose::STANDARD.decode(secret) {
             Ok(bytes) => bytes,
             Err(_) => {
                 secret.as_bytes().to_vec()
@@ -193,10 +622,7 @@ impl PolymarketApi {
             return Ok(request);
         }
 
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let timestamp = crate::clock::now() as u64;
         
         let signature = self.generate_signature(method, path, body, timestamp)?;
         
@@ -210,15 +636,31 @@ impl PolymarketApi {
     }
 
     // Get market by slug (e.g., "btc-updown-15m-1767726000")
-    pub async fn get_market_by_slug(&self, slug: &str) -> Result<Market> {
+    // Cached for `market_cache_ttl`, so `active` and `closed` may lag by that much
+    pub async fn get_market_by_slug(&self, slug: &str) -> ApiResult<Market> {
+        if let Some((fetched, market)) = self.market_cache.lock().unwrap().by_slug.get(slug) {
+            if fetched.elapsed() < self.market_cache_ttl {
+                return Ok(market.clone());
+            }
+        }
+        let result = self.get_market_by_slug_inner(slug).await.map_err(ApiError::classify);
+        self.observe("market_by_slug", &result);
+        if let (Ok(market), false) = (&result, self.market_cache_ttl.is_zero()) {
+            let mut cache = self.market_cache.lock().unwrap();
+            cache.prune(self.market_cache_ttl);
+            cache.by_slug.insert(slug.to_string(), (std::time::Instant::now(), market.clone()));
+        }
+        result
+    }
+
+    async fn get_market_by_slug_inner(&self, slug: &str) -> Result<Market> {
         let url = format!("{}/events/slug/{}", self.gamma_url, slug);
         
         let response = self.client.get(&url).send().await
             .context(format!("Failed to fetch market by slug: {}", slug))?;
         
-        let status = response.status();
-        if !status.is_success() {
-            anyhow::bail!("Failed to fetch market by slug: {} (status: {})", slug, status);
+        if !response.status().is_success() {
+            return Err(http_error(&response, &format!("Failed to fetch market by slug: {}", slug)));
         }
         
         let json: Value = response.json().await
@@ -256,8 +698,287 @@ impl PolymarketApi {
         Ok(orderbook)
     }
 
+    /// Tick and minimum order size for `token_id`, from its order book and cached for
+    /// `ORDER_RULES_TTL`. Falls back to the usual 0.01 tick, with no minimum, if the book can't
+    /// be read.
+    pub async fn order_rules(&self, token_id: &str) -> OrderRules {
+        if let Some((fetched, rules)) = self.order_rules.lock().unwrap().get(token_id) {
+            if fetched.elapsed() < ORDER_RULES_TTL {
+                return *rules;
+            }
+        }
+        match self.get_orderbook(token_id).await {
+            Ok(book) => self.observe_book(token_id, &book),
+            Err(e) => {
+                warn!("Could not read order rules for {}: {} — assuming a 0.01 tick", token_id, e);
+                OrderRules::default()
+            }
+        }
+    }
+
+    /// Refreshes the cached order rules of `token_id` from a book fetched elsewhere, so a tick
+    /// change seen by the monitor applies to the next order straight away.
+    pub fn observe_book(&self, token_id: &str, book: &OrderBook) -> OrderRules {
+        let default = OrderRules::default();
+        let rules = OrderRules {
+            tick_size: book.tick_size.filter(|t| *t > rust_decimal::Decimal::ZERO).unwrap_or(default.tick_size),
+            min_order_size: book.min_order_size.unwrap_or(default.min_order_size),
+        };
+        let previous = self.order_rules.lock().unwrap().insert(token_id.to_string(), (std::time::Instant::now(), rules));
+        if let Some((_, old)) = previous.filter(|(_, old)| old.tick_size != rules.tick_size) {
+            log::debug!("Tick size for {} changed from {} to {}", token_id, old.tick_size.normalize(), rules.tick_size.normalize());
+        }
+        rules
+    }
+
+    /// Order books for several tokens in one `POST /books`, keyed by token id. Tokens the CLOB
+    /// has no book for are missing from the map.
+    pub async fn get_order_books(&self, token_ids: &[String]) -> ApiResult<HashMap<String, OrderBook>> {
+        if let Some(replay) = &self.replay {
+            return Ok(replay.books(token_ids));
+        }
+        let result = self.get_order_books_inner(token_ids).await.map_err(ApiError::classify);
+        self.observe("books", &result);
+        result
+    }
+
+    async fn get_order_books_inner(&self, token_ids: &[String]) -> Result<HashMap<String, OrderBook>> {
+        let url = format!("{}/books", self.clob_url);
+        let body: Vec<Value> = token_ids.iter().map(|id| serde_json::json!({ "token_id": id })).collect();
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .context("Failed to fetch orderbooks")?;
+
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch orderbooks"));
+        }
+
+        let books: Vec<Value> = response
+            .json()
+            .await
+            .context("Failed to parse orderbooks response")?;
+
+        let mut by_token = HashMap::new();
+        for book in books {
+            let Some(token_id) = book.get("asset_id").and_then(|id| id.as_str()).map(str::to_string) else {
+                continue;
+            };
+            let orderbook: OrderBook = serde_json::from_value(book)
+                .context(format!("Failed to parse orderbook for {}", token_id))?;
+            by_token.insert(token_id, orderbook);
+        }
+        Ok(by_token)
+    }
+
+    /// Prices for several (token id, side) pairs in one `POST /prices`, keyed by token id, then
+    /// side ("BUY" or "SELL").
+    pub async fn get_prices(&self, requests: &[(String, &str)]) -> ApiResult<HashMap<String, HashMap<String, rust_decimal::Decimal>>> {
+        let result = self.get_prices_inner(requests).await.map_err(ApiError::classify);
+        self.observe("prices", &result);
+        result
+    }
+
+    async fn get_prices_inner(&self, requests: &[(String, &str)]) -> Result<HashMap<String, HashMap<String, rust_decimal::Decimal>>> {
+        let url = format!("{}/prices", self.clob_url);
+        let body: Vec<Value> = requests
+            .iter()
+            .map(|(id, side)| serde_json::json!({ "token_id": id, "side": side }))
+            .collect();
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .context("Failed to fetch prices")?;
+
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch prices"));
+        }
+
+        // {"<token_id>": {"BUY": "0.50", "SELL": "0.52"}, ...}
+        let json: HashMap<String, HashMap<String, Value>> = response
+            .json()
+            .await
+            .context("Failed to parse prices response")?;
+
+        Ok(json
+            .into_iter()
+            .map(|(token_id, sides)| {
+                let sides = sides.into_iter().filter_map(|(side, p)| Some((side, decimal_value(&p)?))).collect();
+                (token_id, sides)
+            })
+            .collect())
+    }
+
+    /// The CLOB's clock, in unix seconds (`/time`).
+    pub async fn get_server_time(&self) -> ApiResult<i64> {
+        if self.replay.is_some() {
+            return Ok(crate::clock::now());
+        }
+        let result = self.get_server_time_inner().await.map_err(ApiError::classify);
+        self.observe("time", &result);
+        result
+    }
+
+    async fn get_server_time_inner(&self) -> Result<i64> {
+        let url = format!("{}/time", self.clob_url);
+        let response = self.client.get(&url).send().await.context("Failed to fetch server time")?;
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch server time"));
+        }
+        let body = response.text().await.context("Failed to read server time")?;
+        body.trim().parse().context(format!("Invalid server time {:?}", body.trim()))
+    }
+
+    /// Price of the most recent trade in a token (CLOB `/last-trade-price`).
+    pub async fn get_last_trade_price(&self, token_id: &str) -> ApiResult<rust_decimal::Decimal> {
+        if let Some(replay) = &self.replay {
+            return replay.last_trade_price(token_id);
+        }
+        let result = self.get_last_trade_price_inner(token_id).await.map_err(ApiError::classify);
+        self.observe("last_trade_price", &result);
+        result
+    }
+
+    async fn get_last_trade_price_inner(&self, token_id: &str) -> Result<rust_decimal::Decimal> {
+        let url = format!("{}/last-trade-price", self.clob_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .query(&[("token_id", token_id)])
+            .send()
+            .await
+            .context("Failed to fetch last trade price")?;
+
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch last trade price"));
+        }
+
+        let json: Value = response
+            .json()
+            .await
+            .context("Failed to parse last trade price response")?;
+
+        json.get("price")
+            .and_then(decimal_value)
+            .ok_or_else(|| anyhow::anyhow!("Invalid last trade price response format"))
+    }
+
+    /// Last trade prices for several tokens in one `POST /last-trades-prices`, keyed by token id.
+    /// Tokens that have never traded are missing from the map.
+    pub async fn get_last_trade_prices(&self, token_ids: &[String]) -> ApiResult<HashMap<String, rust_decimal::Decimal>> {
+        let result = self.get_last_trade_prices_inner(token_ids).await.map_err(ApiError::classify);
+        self.observe("last_trades_prices", &result);
+        result
+    }
+
+    async fn get_last_trade_prices_inner(&self, token_ids: &[String]) -> Result<HashMap<String, rust_decimal::Decimal>> {
+        let url = format!("{}/last-trades-prices", self.clob_url);
+        let body: Vec<Value> = token_ids.iter().map(|id| serde_json::json!({ "token_id": id })).collect();
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .context("Failed to fetch last trade prices")?;
+
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch last trade prices"));
+        }
+
+        // [{"token_id": "...", "price": "0.52", "side": "BUY"}, ...]
+        let json: Vec<Value> = response
+            .json()
+            .await
+            .context("Failed to parse last trade prices response")?;
+
+        Ok(json
+            .iter()
+            .filter_map(|t| {
+                let token_id = t.get("token_id")?.as_str()?.to_string();
+                Some((token_id, decimal_value(t.get("price")?)?))
+            })
+            .collect())
+    }
+
+    /// Markets of Gamma events tagged `tag_slug` that end between `end_min` and `end_max`
+    /// (unix seconds), as raw JSON.
+    pub async fn get_markets_ending_between(&self, tag_slug: &str, end_min: i64, end_max: i64) -> ApiResult<Vec<Value>> {
+        let result = self.get_markets_ending_between_inner(tag_slug, end_min, end_max).await.map_err(ApiError::classify);
+        self.observe("events", &result);
+        result
+    }
+
+    async fn get_markets_ending_between_inner(&self, tag_slug: &str, end_min: i64, end_max: i64) -> Result<Vec<Value>> {
+        let url = format!("{}/events", self.gamma_url);
+        let iso = |ts: i64| {
+            chrono::DateTime::from_timestamp(ts, 0)
+                .map(|t| t.format("%Y-%m-%dT%H:%M:%SZ").to_string())
+                .unwrap_or_default()
+        };
+        let (end_min, end_max) = (iso(end_min), iso(end_max));
+        let params = [
+            ("tag_slug", tag_slug),
+            ("end_date_min", end_min.as_str()),
+            ("end_date_max", end_max.as_str()),
+            ("limit", "100"),
+        ];
+
+        let response = self.client.get(&url).query(&params).send().await
+            .context("Failed to fetch events")?;
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch events"));
+        }
+        let events: Vec<Value> = response.json().await
+            .context("Failed to parse events response")?;
+
+        Ok(events
+            .iter()
+            .filter_map(|e| e.get("markets").and_then(|m| m.as_array()))
+            .flatten()
+            .cloned()
+            .collect())
+    }
+
     /// Get market details by condition ID
-    pub async fn get_market(&self, condition_id: &str) -> Result<MarketDetails> {
+    pub async fn get_market(&self, condition_id: &str) -> ApiResult<MarketDetails> {
+        if let Some(replay) = &self.replay {
+            return replay.market_details(condition_id);
+        }
+        let result = self.get_market_inner(condition_id).await.map_err(ApiError::classify);
+        self.observe("market", &result);
+        if let (Ok(details), false) = (&result, self.market_cache_ttl.is_zero()) {
+            let mut cache = self.market_cache.lock().unwrap();
+            cache.prune(self.market_cache_ttl);
+            cache.by_condition.insert(condition_id.to_string(), (std::time::Instant::now(), details.clone()));
+        }
+        result
+    }
+
+    /// Like `get_market`, but answered from the cache while it's fresh. For token IDs and other
+    /// fixed metadata; resolution checks go through `get_market`.
+    pub async fn get_market_metadata(&self, condition_id: &str) -> ApiResult<MarketDetails> {
+        if self.replay.is_none() {
+            if let Some((fetched, details)) = self.market_cache.lock().unwrap().by_condition.get(condition_id) {
+                if fetched.elapsed() < self.market_cache_ttl {
+                    return Ok(details.clone());
+                }
+            }
+        }
+        self.get_market(condition_id).await
+    }
+
+    async fn get_market_inner(&self, condition_id: &str) -> Result<MarketDetails> {
         let url = format!("{}/markets/{}", self.clob_url, condition_id);
 
         let response = self
@@ -267,10 +988,8 @@ impl PolymarketApi {
             .await
             .context(format!("Failed to fetch market for condition_id: {}", condition_id))?;
 
-        let status = response.status();
-        
-        if !status.is_success() {
-            anyhow::bail!("Failed to fetch market (status: {})", status);
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch market"));
         }
 
         let json_text = response.text().await
@@ -285,8 +1004,68 @@ impl PolymarketApi {
         Ok(market)
     }
 
+    /// Reads the resolution of a binary condition straight from the ConditionalTokens contract:
+    /// the payout fraction of each outcome slot (Up = 0, Down = 1), or `None` while
+    /// `payoutDenominator` is still zero (not reported on-chain yet). Used when the CLOB
+    /// hasn't flagged a winner.
+    pub async fn get_onchain_payouts(&self, condition_id: &str) -> ApiResult<Option<[f64; 2]>> {
+        let result = self.get_onchain_payouts_inner(condition_id).await.map_err(ApiError::classify);
+        self.observe("payout_numerators", &result);
+        result
+    }
+
+    async fn get_onchain_payouts_inner(&self, condition_id: &str) -> Result<Option<[f64; 2]>> {
+        const CTF_CONTRACT: &str = "0x4d97dcd97ec945f40cf65f87097ace5ea0476045";
+        const RPC_URL: &str = "https://polygon-rpc.com";
+
+        let ctf_address = AlloyAddress::from_str(CTF_CONTRACT).context("Failed to parse CTF contract address")?;
+        let condition_id_b256 = B256::from_str(condition_id.strip_prefix("0x").unwrap_or(condition_id))
+            .context(format!("Failed to parse condition_id as B256: {}", condition_id))?;
+        let provider = ProviderBuilder::new()
+            .connect(RPC_URL)
+            .await
+            .context("Failed to connect to Polygon RPC")?;
+        let view = |calldata: Vec<u8>| {
+            TransactionRequest::default()
+                .to(ctf_address)
+                .input(Bytes::from(calldata).into())
+        };
+
+        let denominator_call = IConditionalTokens::payoutDenominatorCall { conditionId: condition_id_b256 };
+        let raw = provider.call(view(denominator_call.abi_encode())).await
+            .context("Failed to call payoutDenominator")?;
+        let denominator = IConditionalTokens::payoutDenominatorCall::abi_decode_returns(&raw)
+            .context("Failed to decode payoutDenominator")?;
+        if denominator.is_zero() {
+            return Ok(None);
+        }
+
+        let mut payouts = [0.0; 2];
+        for (index, payout) in payouts.iter_mut().enumerate() {
+            let numerator_call = IConditionalTokens::payoutNumeratorsCall {
+                conditionId: condition_id_b256,
+                index: U256::from(index),
+            };
+            let raw = provider.call(view(numerator_call.abi_encode())).await
+                .context("Failed to call payoutNumerators")?;
+            let numerator = IConditionalTokens::payoutNumeratorsCall::abi_decode_returns(&raw)
+                .context("Failed to decode payoutNumerators")?;
+            *payout = f64::from(numerator) / f64::from(denominator);
+        }
+        Ok(Some(payouts))
+    }
+
     // Get price for a token (for trading)
-    pub async fn get_price(&self, token_id: &str, side: &str) -> Result<rust_decimal::Decimal> {
+    pub async fn get_price(&self, token_id: &str, side: &str) -> ApiResult<rust_decimal::Decimal> {
+        if let Some(replay) = &self.replay {
+            return replay.price(token_id, side);
+        }
+        let result = self.get_price_inner(token_id, side).await.map_err(ApiError::classify);
+        self.observe("price", &result);
+        result
+    }
+
+    async fn get_price_inner(&self, token_id: &str, side: &str) -> Result<rust_decimal::Decimal> {
         let url = format!("{}/price", self.clob_url);
         let params = [
             ("side", side),
@@ -303,9 +1082,8 @@ impl PolymarketApi {
             .await
             .context("Failed to fetch price")?;
 
-        let status = response.status();
-        if !status.is_success() {
-            anyhow::bail!("Failed to fetch price (status: {})", status);
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch price"));
         }
 
         let json: serde_json::Value = response
@@ -325,7 +1103,52 @@ impl PolymarketApi {
         Ok(price)
     }
 
+    /// Price series for a token from the CLOB `/prices-history` endpoint at 1-minute fidelity.
+    /// `interval` is one of the endpoint's windows: "1h", "6h", "1d", "1w", "1m" or "max".
+    pub async fn get_price_history(&self, token_id: &str, interval: &str) -> ApiResult<Vec<PricePoint>> {
+        let result = self.get_price_history_inner(token_id, interval).await.map_err(ApiError::classify);
+        self.observe("prices_history", &result);
+        result
+    }
+
+    async fn get_price_history_inner(&self, token_id: &str, interval: &str) -> Result<Vec<PricePoint>> {
+        let url = format!("{}/prices-history", self.clob_url);
+        let params = [
+            ("market", token_id),
+            ("interval", interval),
+            ("fidelity", "1"),
+        ];
+
+        let response = self
+            .client
+            .get(&url)
+            .query(&params)
+            .send()
+            .await
+            .context("Failed to fetch price history")?;
+
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Failed to fetch price history"));
+        }
+
+        let json: Value = response
+            .json()
+            .await
+            .context("Failed to parse price history response")?;
+
+        let history = json.get("history")
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Invalid price history response format"))?;
+        let points: Vec<PricePoint> = serde_json::from_value(history)
+            .context("Failed to parse price history points")?;
+
+        log::debug!("Fetched {} price points for token {} (interval={})", points.len(), token_id, interval);
+
+        Ok(points)
+    }
+
     // Get best bid/ask prices for a token (from orderbook)
+    #[allow(dead_code)]
     pub async fn get_best_price(&self, token_id: &str) -> Result<Option<TokenPrice>> {
         let orderbook = self.get_orderbook(token_id).await?;
         
@@ -343,48 +1166,27 @@ impl PolymarketApi {
         }
     }
 
+    /// Runs an order submission (`place_order`, `place_market_order`) once the order queue has
+    /// a slot for it, so exits and orders on markets about to end go out first under contention.
+    pub async fn queued<T>(&self, priority: OrderPriority, submit: impl Future<Output = T>) -> T {
+        let _slot = self.order_queue.acquire(priority).await;
+        submit.await
+    }
+
     // Place an order
-    pub async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse> {
-        let private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for order signing. Please set private_key in config.json"))?;
-        
-        let signer = LocalSigner::from_str(private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
-        
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
-            .context("Failed to create CLOB client")?
-            .authentication_builder(&signer);
-        
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
-                .context(format!("Failed to parse proxy_wallet_address: {}. Ensure it's a valid Ethereum address.", proxy_addr))?;
-            
-            auth_builder = auth_builder.funder(funder_address);
-            
-            let sig_type = match self.signature_type {
-                Some(1) => SignatureType::Proxy,
-                Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => SignatureType::Proxy, // Default to Proxy when proxy wallet is set
-                Some(n) => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            
-            auth_builder = auth_builder.signature_type(sig_type);
-        } else if let Some(sig_type_num) = self.signature_type {
-            // If signature type is set but no proxy wallet, validate it's EOA
-            let sig_type = match sig_type_num {
-                0 => SignatureType::Eoa,
-                1 | 2 => anyhow::bail!("signature_type {} requires proxy_wallet_address to be set", sig_type_num),
-                n => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
+    pub async fn place_order(&self, order: &OrderRequest) -> ApiResult<OrderResponse> {
+        self.throttle().await;
+        let result = self.with_reauth(|| self.place_order_inner(order)).await;
+        self.observe("order", &result);
+        match &result {
+            Ok(_) => metrics().inc_orders_placed(&order.side),
+            Err(_) => metrics().inc_orders_rejected(),
         }
-        
-        // Create CLOB client with authentication
-        let client = auth_builder
-            .authenticate()
-            .await
-            .context("Failed to authenticate with CLOB API. Check your API credentials.")?;
+        result
+    }
+
+    async fn place_order_inner(&self, order: &OrderRequest) -> Result<OrderResponse> {
+        let (signer, client) = self.clob_client().await?;
         
         let side = match order.side.as_str() {
             "BUY" => Side::Buy,
@@ -392,35 +1194,54 @@ impl PolymarketApi {
             _ => anyhow::bail!("Invalid order side: {}. Must be 'BUY' or 'SELL'", order.side),
         };
         
-        let price = rust_decimal::Decimal::from_str(&order.price)
+        let requested_price = rust_decimal::Decimal::from_str(&order.price)
             .context(format!("Failed to parse price: {}", order.price))?;
-        let size = rust_decimal::Decimal::from_str(&order.size)
+        let requested_size = rust_decimal::Decimal::from_str(&order.size)
             .context(format!("Failed to parse size: {}", order.size))?;
-        
+
+        let rules = self.order_rules(&order.token_id).await;
+        let price = rules.snap_price(requested_price, matches!(side, Side::Buy), false);
+        let size = rules.snap_size(requested_size).map_err(|e| ApiError::InvalidOrder(e.to_string()))?;
+        if price != requested_price.normalize() || size != requested_size.normalize() {
+            log::info!("   Snapped order to tick {} / lot 0.01: {} @ {} -> {} @ {}",
+                rules.tick_size.normalize(), requested_size, requested_price, size, price);
+        }
+
         eprintln!("📤 Creating and posting order: {} {} {} @ {}", 
-              order.side, order.size, order.token_id, order.price);
+              order.side, size, order.token_id, price);
 
         let token_id_u256 = parse_token_id_to_u256(&order.token_id)
             .context(format!("Failed to parse token_id as U256: {}", order.token_id))?;
 
-        let order_builder = client
+        let mut order_builder = client
             .limit_order()
             .token_id(token_id_u256)
             .size(size)
             .price(price)
-            .side(side);
+            .side(side)
+            .nonce(self.order_nonce);
+        if let Some(expires_at) = order.expires_at {
+            // The CLOB stops matching a GTD order a security threshold before its expiration
+            let expiration = chrono::DateTime::from_timestamp(expires_at + GTD_SECURITY_SECS, 0)
+                .ok_or_else(|| anyhow::anyhow!("Invalid order expiry {}", expires_at))?;
+            order_builder = order_builder.order_type(OrderType::GTD).expiration(expiration);
+        }
         
         let signed_order = client.sign(&signer, order_builder.build().await?)
             .await
             .context("Failed to sign order")?;
         
+        if self.paper_live {
+            return self.paper_post(&signed_order, &order.side, &order.token_id, size * price).await;
+        }
+
         // Post order and capture detailed error information
         let response = match client.post_order(signed_order).await {
             Ok(resp) => resp,
             Err(e) => {
                 // Log the full error details for debugging
                 error!("❌ Failed to post order. Error details: {:?}", e);
-                anyhow::bail!(
+                let message = format!(
                     "Failed to post order: {}\n\
                     \n\
                     Troubleshooting:\n\
@@ -431,6 +1252,7 @@ impl PolymarketApi {
                     5. Verify the order size meets minimum requirements",
                     e
                 );
+                return Err(order_error(e, message));
             }
         };
         
@@ -438,7 +1260,7 @@ impl PolymarketApi {
         if !response.success {
             let error_msg = response.error_msg.as_deref().unwrap_or("Unknown error");
             error!("❌ Order rejected by API: {}", error_msg);
-            anyhow::bail!(
+            return Err(ApiError::rejected(error_msg, format!(
                 "Order was rejected: {}\n\
                 \n\
                 Order details:\n\
@@ -452,8 +1274,8 @@ impl PolymarketApi {
                 2. Invalid token ID or market closed\n\
                 3. Price out of range\n\
                 4. Size below minimum or above maximum",
-                error_msg, order.token_id, order.side, order.size, order.price
-            );
+                error_msg, order.token_id, order.side, size, price
+            )).into());
         }
         
         // Convert SDK response to our OrderResponse format
@@ -461,6 +1283,8 @@ impl PolymarketApi {
             order_id: Some(response.order_id.clone()),
             status: response.status.to_string(),
             message: Some(format!("Order placed successfully. Order ID: {}", response.order_id)),
+            matched_shares: None,
+            matched_usd: None,
         };
         
         eprintln!("✅ Order placed successfully! Order ID: {}", response.order_id);
@@ -474,47 +1298,28 @@ impl PolymarketApi {
         token_id: &str,
         amount: f64,
         side: &str,
-        order_type: Option<&str>, // "FOK" or "FAK", defaults to FOK
+        order_type: Option<&str>, // "FOK" or "FAK", defaults to FOK
+        worst_price: Option<f64>, // highest price a buy pays, lowest a sell takes
+    ) -> ApiResult<OrderResponse> {
+        self.throttle().await;
+        let result = self.with_reauth(|| self.place_market_order_inner(token_id, amount, side, order_type, worst_price)).await;
+        self.observe("market_order", &result);
+        match &result {
+            Ok(_) => metrics().inc_orders_placed(side),
+            Err(_) => metrics().inc_orders_rejected(),
+        }
+        result
+    }
+
+    async fn place_market_order_inner(
+        &self,
+        token_id: &str,
+        amount: f64,
+        side: &str,
+        order_type: Option<&str>,
+        worst_price: Option<f64>,
     ) -> Result<OrderResponse> {
-        let private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for order signing. Please set private_key in config.json"))?;
-        
-        let signer = LocalSigner::from_str(private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
-        
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
-            .context("Failed to create CLOB client")?
-            .authentication_builder(&signer);
-        
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
-                .context(format!("Failed to parse proxy_wallet_address: {}. Ensure it's a valid Ethereum address.", proxy_addr))?;
-            
-            auth_builder = auth_builder.funder(funder_address);
-            
-            let sig_type = match self.signature_type {
-                Some(1) => SignatureType::Proxy,
-                Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => SignatureType::Proxy, // Default to Proxy when proxy wallet is set
-                Some(n) => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            
-            auth_builder = auth_builder.signature_type(sig_type);
-        } else if let Some(sig_type_num) = self.signature_type {
-            // If signature type is set but no proxy wallet, validate it's EOA
-            let sig_type = match sig_type_num {
-                0 => SignatureType::Eoa,
-                1 | 2 => anyhow::bail!("signature_type {} requires proxy_wallet_address to be set", sig_type_num),
-                n => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
-        }
-        
-        let client = auth_builder
-            .authenticate()
-            .await
-            .context("Failed to authenticate with CLOB API. Check your API credentials.")?;
+        let (signer, client) = self.clob_client().await?;
         
         let side_enum = match side {
             "BUY" => Side::Buy,
@@ -528,12 +1333,14 @@ impl PolymarketApi {
             _ => OrderType::FOK, // Default to FOK
         };
         
-        use rust_decimal::{Decimal, RoundingStrategy};
-        use rust_decimal::prelude::*;
+        use rust_decimal::Decimal;
+        use rust_decimal_macros::dec;
         
-        let amount_decimal = Decimal::from_f64_retain(amount)
-            .ok_or_else(|| anyhow::anyhow!("Failed to convert amount to Decimal"))?
-            .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero);
+        let rules = self.order_rules(token_id).await;
+        let amount_decimal = rules.snap_size(
+            Decimal::from_f64_retain(amount).ok_or_else(|| anyhow::anyhow!("Failed to convert amount to Decimal"))?,
+        )
+        .map_err(|e| ApiError::InvalidOrder(e.to_string()))?;
         
         eprintln!("📤 Creating and posting MARKET order: {} {} {} (type: {:?})", 
               side, amount_decimal, token_id, order_type_enum);
@@ -554,59 +1361,60 @@ impl PolymarketApi {
         let token_id_u256 = parse_token_id_to_u256(token_id)
             .context(format!("Failed to parse token_id as U256: {}", token_id))?;
 
+        // Sells go out slightly under the bid for immediate execution; both sides are snapped
+        // toward crossing the spread so the market's tick never leaves them resting
+        let is_buy = matches!(side_enum, Side::Buy);
+        let final_price = if is_buy {
+            rules.snap_price(market_price, true, true)
+        } else {
+            let adjusted = rules.snap_price(market_price * dec!(0.995), false, true);
+            if adjusted != market_price {
+                eprintln!("   ⚠️  Adjusting SELL price from ${:.4} to ${:.4} for immediate execution", market_price, adjusted);
+            }
+            adjusted
+        };
+        // A marketable limit at the worst price: the book may have moved since the caller
+        // decided, and the order must not chase it
+        let final_price = match worst_price.and_then(Decimal::from_f64_retain) {
+            Some(worst) => {
+                let worst = rules.snap_price(worst, is_buy, false);
+                let bounded = if is_buy { final_price.min(worst) } else { final_price.max(worst) };
+                if bounded != final_price {
+                    eprintln!("   🛡️  {} price ${:.4} is past the ${:.4} limit — capping the order there", side, market_price, worst);
+                }
+                bounded
+            }
+            None => final_price,
+        };
+
         let order_builder = client
             .limit_order()
             .token_id(token_id_u256)
             .size(amount_decimal)
-            .price(market_price)
-            .side(side_enum);
+            .price(final_price)
+            .side(side_enum)
+            .nonce(self.order_nonce)
+            .order_type(order_type_enum);
         
         let signed_order = client.sign(&signer, order_builder.build().await?)
             .await
             .context("Failed to sign market order")?;
         
-        let final_price = if matches!(side_enum, Side::Sell) {
-            let price_f64 = f64::try_from(market_price).unwrap_or(0.0);
-            let adjusted_f64 = price_f64 * 0.995;
-            let rounded_f64 = (adjusted_f64 * 100.0).round() / 100.0;
-            let final_f64 = rounded_f64.max(0.01);
-            Decimal::from_f64_retain(final_f64)
-                .ok_or_else(|| anyhow::anyhow!("Failed to convert adjusted price to Decimal"))?
-                .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
-        } else {
-            // For BUY orders, also ensure 2 decimal places
-            market_price.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
-        };
-        
-        // If price was adjusted, rebuild the order
-        let signed_order = if matches!(side_enum, Side::Sell) && final_price != market_price {
-            let final_price_f64 = f64::try_from(final_price).unwrap_or(0.0);
-            let market_price_f64 = f64::try_from(market_price).unwrap_or(0.0);
-            eprintln!("   ⚠️  Adjusting SELL price from ${:.4} to ${:.4} for immediate execution", market_price_f64, final_price_f64);
-            let adjusted_builder = client
-                .limit_order()
-                .token_id(token_id_u256)
-                .size(amount_decimal)
-                .price(final_price)
-                .side(side_enum);
-            client.sign(&signer, adjusted_builder.build().await?)
-                .await
-                .context("Failed to sign adjusted market order")?
-        } else {
-            signed_order
-        };
-        
         // Log detailed order info before posting
         let final_price_f64 = f64::try_from(final_price).unwrap_or(0.0);
         eprintln!("   📋 Order details: Side={}, Size={}, Price=${:.4}, Token={}", 
               side, amount_decimal, final_price_f64, token_id);
         
+        if self.paper_live {
+            return self.paper_post(&signed_order, side, token_id, amount_decimal * final_price).await;
+        }
+
         let response = match client.post_order(signed_order).await {
             Ok(resp) => resp,
             Err(e) => {
                 // Log the full error for debugging
                 error!("❌ SDK post_order error: {:?}", e);
-                anyhow::bail!(
+                let message = format!(
                     "Failed to post market order: {:?}\n\
                     \n\
                     Order details:\n\
@@ -623,10 +1431,17 @@ impl PolymarketApi {
                     5. Check if order size meets minimum requirements",
                     e, side, token_id, amount_decimal, final_price_f64
                 );
+                return Err(order_error(e, message));
             }
         };
         
-        // Convert SDK response to our OrderResponse format
+        // Convert SDK response to our OrderResponse format. Buys make USDC and take shares,
+        // sells the other way round
+        let (shares, usd) = if is_buy {
+            (response.taking_amount, response.making_amount)
+        } else {
+            (response.making_amount, response.taking_amount)
+        };
         let order_response = OrderResponse {
             order_id: Some(response.order_id.clone()),
             status: response.status.to_string(),
@@ -635,6 +1450,8 @@ impl PolymarketApi {
             } else {
                 response.error_msg.clone()
             },
+            matched_shares: f64::try_from(shares).ok(),
+            matched_usd: f64::try_from(usd).ok(),
         };
         
         if response.success {
@@ -642,7 +1459,7 @@ impl PolymarketApi {
             Ok(order_response)
         } else {
             let error_msg = response.error_msg.as_deref().unwrap_or("Unknown error");
-            anyhow::bail!(
+            Err(ApiError::rejected(error_msg, format!(
                 "Market order failed: {}\n\
                 Order ID: {}\n\
                 Token ID: {}\n\
@@ -661,49 +1478,47 @@ impl PolymarketApi {
                 side,
                 amount_decimal,
                 final_price_f64
-            );
+            )).into())
         }
     }
     
-    /// Cancel an order by order ID
-    pub async fn cancel_order(&self, order_id: &str) -> Result<()> {
-        let _private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for order cancellation. Please set private_key in config.json"))?;
-        
-        let signer = LocalSigner::from_str(_private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
-        
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
-            .context("Failed to create CLOB client")?
-            .authentication_builder(&signer);
-        
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
-                .context(format!("Failed to parse proxy_wallet_address: {}. Ensure it's a valid Ethereum address.", proxy_addr))?;
-            
-            auth_builder = auth_builder.funder(funder_address);
-            
-            let sig_type = match self.signature_type {
-                Some(1) => SignatureType::Proxy,
-                Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => SignatureType::Proxy,
-                Some(n) => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
-        } else if let Some(sig_type_num) = self.signature_type {
-            let sig_type = match sig_type_num {
-                0 => SignatureType::Eoa,
-                1 | 2 => anyhow::bail!("signature_type {} requires proxy_wallet_address to be set", sig_type_num),
-                n => anyhow::bail!("Invalid signature_type: {}. Must be 0 (EOA), 1 (Proxy), or 2 (GnosisSafe)", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
+    /// Paper-live stand-in for `post_order`: logs the signed order body that would have been
+    /// posted, warns when a buy's notional exceeds the USDC balance, and acknowledges the order
+    /// with a `PAPER-` ID.
+    async fn paper_post(&self, signed_order: &SignedOrder, side: &str, token_id: &str, notional: rust_decimal::Decimal) -> Result<OrderResponse> {
+        let body = serde_json::to_string(signed_order).context("Failed to serialize signed order")?;
+        log::info!("📝 PAPER-LIVE: signed {} order for {} (${} notional), not posted: {}", side, token_id, notional.round_dp(4), body);
+        if side == "BUY" {
+            let notional = f64::try_from(notional).unwrap_or(0.0);
+            match self.get_usdc_balance_inner().await {
+                Ok(balance) if balance < notional => warn!(
+                    "⚠️ PAPER-LIVE: USDC balance ${:.2} would not cover this order (${:.2}) — it would be rejected",
+                    balance, notional
+                ),
+                Ok(balance) => log::info!("   USDC balance ${:.2} covers ${:.2}", balance, notional),
+                Err(e) => warn!("⚠️ PAPER-LIVE: could not read the USDC balance: {}", e),
+            }
         }
-        
-        let client = auth_builder
-            .authenticate()
-            .await
-            .context("Failed to authenticate with CLOB API. Check your API credentials.")?;
+        let order_id = format!("PAPER-{}-{}", side, crate::clock::now_ms());
+        Ok(OrderResponse {
+            order_id: Some(order_id.clone()),
+            status: "PAPER".to_string(),
+            message: Some(format!("Order signed but not posted (paper-live). Order ID: {}", order_id)),
+            matched_shares: None,
+            matched_usd: None,
+        })
+    }
+
+    /// Cancel an order by order ID
+    pub async fn cancel_order(&self, order_id: &str) -> ApiResult<()> {
+        self.throttle().await;
+        let result = self.with_reauth(|| self.cancel_order_inner(order_id)).await;
+        self.observe("cancel_order", &result);
+        result
+    }
+
+    async fn cancel_order_inner(&self, order_id: &str) -> Result<()> {
+        let (_, client) = self.clob_client().await?;
         
         client.cancel_order(order_id).await
             .context(format!("Failed to cancel order {}", order_id))?;
@@ -711,44 +1526,117 @@ impl PolymarketApi {
         Ok(())
     }
 
-    /// Check if both Up and Down orders are filled (production mode: verify via CLOB API).
-    /// Returns Ok((up_filled, down_filled)). Order not found or API error is treated as not filled.
-    pub async fn are_both_orders_filled(&self, up_order_id: &str, down_order_id: &str) -> Result<(bool, bool)> {
-        let _private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key required to check order status"))?;
+    /// USDC collateral available to trade, in dollars.
+    pub async fn get_usdc_balance(&self) -> ApiResult<f64> {
+        self.throttle().await;
+        let result = self.with_reauth(|| self.get_usdc_balance_inner()).await;
+        self.observe("balance", &result);
+        result
+    }
 
-        let signer = LocalSigner::from_str(_private_key)
-            .context("Failed to create signer from private key")?
-            .with_chain_id(Some(POLYGON));
+    async fn get_usdc_balance_inner(&self) -> Result<f64> {
+        let (_, client) = self.clob_client().await?;
+        let response = client.balance_allowance(BalanceAllowanceRequest::default()).await
+            .context("Failed to fetch balance")?;
+        // Reported in USDC base units (6 decimals)
+        let units = f64::from_str(&response.balance.to_string()).context("Failed to parse balance")?;
+        Ok(units / 1_000_000.0)
+    }
 
-        let mut auth_builder = ClobClient::new(&self.clob_url, ClobConfig::default())
-            .context("Failed to create CLOB client")?
-            .authentication_builder(&signer);
+    /// The account's resting orders, all markets.
+    pub async fn get_open_orders(&self) -> ApiResult<Vec<AccountOrder>> {
+        self.throttle().await;
+        let result = self.with_reauth(|| self.get_open_orders_inner()).await;
+        self.observe("orders", &result);
+        result
+    }
 
-        if let Some(proxy_addr) = &self.proxy_wallet_address {
-            let funder_address = AlloyAddress::parse_checksummed(proxy_addr, None)
-                .context(format!("Failed to parse proxy_wallet_address: {}", proxy_addr))?;
-            auth_builder = auth_builder.funder(funder_address);
-            let sig_type = match self.signature_type {
-                Some(1) => SignatureType::Proxy,
-                Some(2) => SignatureType::GnosisSafe,
-                Some(0) | None => SignatureType::Proxy,
-                Some(n) => anyhow::bail!("Invalid signature_type: {}", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
-        } else if let Some(sig_type_num) = self.signature_type {
-            let sig_type = match sig_type_num {
-                0 => SignatureType::Eoa,
-                1 | 2 => anyhow::bail!("signature_type {} requires proxy_wallet_address", sig_type_num),
-                n => anyhow::bail!("Invalid signature_type: {}", n),
-            };
-            auth_builder = auth_builder.signature_type(sig_type);
+    async fn get_open_orders_inner(&self) -> Result<Vec<AccountOrder>> {
+        let (_, client) = self.clob_client().await?;
+        let request = OrdersRequest::builder().build();
+        let mut orders = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = client.orders(&request, cursor).await.context("Failed to fetch open orders")?;
+            orders.extend(page.data.into_iter().map(|o| AccountOrder {
+                order_id: o.id,
+                condition_id: format!("{:?}", o.market),
+                created_at: o.created_at.timestamp(),
+            }));
+            // "LTE=" (base64 "-1") marks the last page
+            if page.next_cursor.is_empty() || page.next_cursor == "LTE=" {
+                break;
+            }
+            cursor = Some(page.next_cursor);
         }
+        Ok(orders)
+    }
 
-        let client = auth_builder
-            .authenticate()
-            .await
-            .context("Failed to authenticate with CLOB API")?;
+    /// The account's fills matched after `after` (unix seconds), oldest pages first as the CLOB
+    /// returns them. Maker fills are read from the matched maker orders that belong to us.
+    pub async fn get_trades(&self, after: i64) -> ApiResult<Vec<AccountFill>> {
+        self.throttle().await;
+        let result = self.with_reauth(|| self.get_trades_inner(after)).await;
+        self.observe("trades", &result);
+        result
+    }
+
+    async fn get_trades_inner(&self, after: i64) -> Result<Vec<AccountFill>> {
+        let (_, client) = self.clob_client().await?;
+
+        let request = TradesRequest::builder().after(after).build();
+        let to_f64 = |d: rust_decimal::Decimal| f64::from_str(&d.to_string()).unwrap_or(0.0);
+        let mut fills = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = client.trades(&request, cursor).await.context("Failed to fetch trades")?;
+            for trade in page.data {
+                let base = AccountFill {
+                    trade_id: trade.id.clone(),
+                    condition_id: format!("{:?}", trade.market),
+                    token_id: trade.asset_id.to_string(),
+                    outcome: trade.outcome.clone(),
+                    side: trade.side.to_string(),
+                    size: to_f64(trade.size),
+                    price: to_f64(trade.price),
+                    timestamp: trade.match_time.timestamp(),
+                    status: trade.status.to_string(),
+                };
+                if trade.trader_side == TraderSide::Maker {
+                    for order in trade.maker_orders.iter().filter(|o| o.owner == trade.owner) {
+                        fills.push(AccountFill {
+                            token_id: order.asset_id.to_string(),
+                            outcome: order.outcome.clone(),
+                            side: order.side.to_string(),
+                            size: to_f64(order.matched_amount),
+                            price: to_f64(order.price),
+                            ..base.clone()
+                        });
+                    }
+                } else {
+                    fills.push(base);
+                }
+            }
+            // "LTE=" (base64 "-1") marks the last page
+            if page.next_cursor.is_empty() || page.next_cursor == "LTE=" {
+                break;
+            }
+            cursor = Some(page.next_cursor);
+        }
+        Ok(fills)
+    }
+
+    /// Check if both Up and Down orders are filled (production mode: verify via CLOB API).
+    /// Returns Ok((up_filled, down_filled)). Order not found or API error is treated as not filled.
+    pub async fn are_both_orders_filled(&self, up_order_id: &str, down_order_id: &str) -> ApiResult<(bool, bool)> {
+        self.throttle().await;
+        let result = self.with_reauth(|| self.are_both_orders_filled_inner(up_order_id, down_order_id)).await;
+        self.observe("order_status", &result);
+        result
+    }
+
+    async fn are_both_orders_filled_inner(&self, up_order_id: &str, down_order_id: &str) -> Result<(bool, bool)> {
+        let (_, client) = self.clob_client().await?;
 
         let up_filled = client.order(up_order_id).await
             .ok()
@@ -812,21 +1700,58 @@ impl PolymarketApi {
         Ok(order_response)
     }
 
+    /// Shares `wallet` currently holds in each outcome token of `condition_id`, from the data API.
+    pub async fn get_positions(&self, wallet: &str, condition_id: &str) -> ApiResult<Vec<DataApiPosition>> {
+        let result = self.get_positions_inner(wallet, Some(condition_id)).await.map_err(ApiError::classify);
+        self.observe("positions", &result);
+        result
+    }
+
+    async fn get_positions_inner(&self, wallet: &str, condition_id: Option<&str>) -> Result<Vec<DataApiPosition>> {
+        let url = format!("{}/positions", self.data_url);
+        let user = if wallet.starts_with("0x") {
+            wallet.to_string()
+        } else {
+            format!("0x{}", wallet)
+        };
+        let mut params = vec![("user", user.as_str()), ("sizeThreshold", "0"), ("limit", "500")];
+        if let Some(condition_id) = condition_id {
+            params.push(("market", condition_id));
+        }
+        let response = self.client
+            .get(&url)
+            .query(&params)
+            .send()
+            .await
+            .context("Failed to fetch positions")?;
+        if !response.status().is_success() {
+            return Err(http_error(&response, "Data API request for positions failed"));
+        }
+        response.json().await.context("Failed to parse positions response")
+    }
+
+    /// Every position `wallet` holds, from the data API.
+    pub async fn get_wallet_positions(&self, wallet: &str) -> ApiResult<Vec<DataApiPosition>> {
+        let result = self.get_positions_inner(wallet, None).await.map_err(ApiError::classify);
+        self.observe("positions", &result);
+        result
+    }
+
     pub async fn get_redeemable_positions(&self, wallet: &str) -> Result<Vec<String>> {
-        let url = "https://data-api.polymarket.com/positions";
+        let url = format!("{}/positions", self.data_url);
         let user = if wallet.starts_with("0x") {
             wallet.to_string()
         } else {
             format!("0x{}", wallet)
         };
         let response = self.client
-            .get(url)
+            .get(&url)
             .query(&[("user", user.as_str()), ("redeemable", "true"), ("limit", "500")])
             .send()
             .await
             .context("Failed to fetch redeemable positions")?;
         if !response.status().is_success() {
-            anyhow::bail!("Data API returned {} for redeemable positions", response.status());
+            return Err(http_error(&response, "Data API request for redeemable positions failed"));
         }
         let positions: Vec<Value> = response.json().await.unwrap_or_default();
         let mut condition_ids: Vec<String> = positions
@@ -854,22 +1779,10 @@ impl PolymarketApi {
         _token_id: &str,
         outcome: &str,
     ) -> Result<RedeemResponse> {
-        let private_key = self.private_key.as_ref()
-            .ok_or_else(|| anyhow::anyhow!("Private key is required for order signing. Please set private_key in config.json"))?;
-        
-        let signer = LocalSigner::from_str(private_key)
-            .context("Failed to create signer from private key. Ensure private_key is a valid hex string.")?
-            .with_chain_id(Some(POLYGON));
+        self.throttle().await;
+        let signer = self.signer()?;
         
-        let parse_address_hex = |s: &str| -> Result<Address> {
-            let hex_str = s.strip_prefix("0x").unwrap_or(s);
-            let bytes = hex::decode(hex_str).context("Invalid hex in address")?;
-            let len= bytes.len();
-            let arr: [u8; 20] = bytes.try_into().map_err(|_| anyhow::anyhow!("Address must be 20 bytes, got {}", len))?;
-            Ok(Address::from(arr))
-        };
-
-        let collateral_token = parse_address_hex("0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174")
+        let collateral_token = parse_address(USDC_ADDRESS)
             .context("Failed to parse USDC address")?;
 
         let condition_id_clean = condition_id.strip_prefix("0x").unwrap_or(condition_id);
@@ -886,11 +1799,8 @@ impl PolymarketApi {
               condition_id, outcome, index_set);
         
         const CTF_CONTRACT: &str = "0x4d97dcd97ec945f40cf65f87097ace5ea0476045";
-        const RPC_URL: &str = "https://polygon-rpc.com";
-        // Polymarket Proxy Wallet Factory (MagicLink users) – execute via factory.proxy([call])
-        const PROXY_WALLET_FACTORY: &str = "0xaB45c5A4B0c941a2F231C04C3f49182e1A254052";
         
-        let ctf_address = parse_address_hex(CTF_CONTRACT)
+        let ctf_address = parse_address(CTF_CONTRACT)
             .context("Failed to parse CTF contract address")?;
         
         let parent_collection_id = B256::ZERO;
@@ -918,14 +1828,88 @@ impl PolymarketApi {
         };
         let redeem_calldata = redeem_call.abi_encode();
         
-        let (tx_to, tx_data, gas_limit, used_safe_redemption) = if use_proxy && sig_type == 2 {
-            // Gnosis Safe: create Safe tx (redeemPositions), sign with EOA, execute via Safe.execTransaction
+        let (receipt, used_safe_redemption) = self.send_wallet_call(&signer, ctf_address, redeem_calldata, "redemption", 300_000).await?;
+        let tx_hash = receipt.transaction_hash;
+        
+        // When using Gnosis Safe, the outer tx can succeed while the inner CTF redeemPositions reverts.
+        // Detect inner failure by checking for CTF PayoutRedemption event in logs.
+        if used_safe_redemption {
+            let payout_redemption_topic = keccak256(
+                b"PayoutRedemption(address,address,bytes32,bytes32,uint256[],uint256)"
+            );
+            let logs = receipt.logs();
+            let ctf_has_payout = logs.iter().any(|log| {
+                log.address() == ctf_address && log.topics().first().map(|t| t.as_slice()) == Some(payout_redemption_topic.as_slice())
+            });
+            if !ctf_has_payout {
+                anyhow::bail!(
+                    "Redemption tx was mined but the inner redeem reverted (no PayoutRedemption from CTF). \
+                    Check that the Safe holds the winning tokens and conditionId/indexSet are correct. Tx: {:?}",
+                    tx_hash
+                );
+            }
+        }
+        
+        let redeem_response = RedeemResponse {
+            success: true,
+            message: Some(format!("Successfully redeemed tokens. Transaction: {:?}", tx_hash)),
+            transaction_hash: Some(format!("{:?}", tx_hash)),
+            amount_redeemed: None,
+        };
+        eprintln!("Successfully redeemed winning tokens!");
+        eprintln!("Transaction hash: {:?}", tx_hash);
+        if let Some(block_number) = receipt.block_number {
+            eprintln!("Block number: {}", block_number);
+        }
+        Ok(redeem_response)
+    }
+
+    /// Sends `amount_usd` USDC from the trading wallet to `to`; returns the transaction hash.
+    pub async fn transfer_usdc(&self, to: &str, amount_usd: f64) -> Result<String> {
+        self.throttle().await;
+        let signer = self.signer()?;
+        let usdc = parse_address(USDC_ADDRESS)?;
+        let transfer_call = IERC20::transferCall {
+            to: parse_address(to).context("Invalid transfer address")?,
+            // USDC has 6 decimals
+            amount: U256::from((amount_usd * 1e6).floor() as u64),
+        };
+        let (receipt, via_safe) = self.send_wallet_call(&signer, usdc, transfer_call.abi_encode(), "USDC transfer", 100_000).await?;
+        let tx_hash = receipt.transaction_hash;
+        if via_safe {
+            let transfer_topic = keccak256(b"Transfer(address,address,uint256)");
+            let transferred = receipt.logs().iter().any(|log| {
+                log.address() == usdc && log.topics().first().map(|t| t.as_slice()) == Some(transfer_topic.as_slice())
+            });
+            if !transferred {
+                anyhow::bail!("USDC transfer tx was mined but the inner transfer reverted (no Transfer event). Tx: {:?}", tx_hash);
+            }
+        }
+        Ok(format!("{:?}", tx_hash))
+    }
+
+    /// Sends `calldata` to `target` from the trading wallet: through the Gnosis Safe or the proxy
+    /// wallet factory when `proxy_wallet_address` is set, else from the signer's EOA with a
+    /// `direct_gas` limit. Returns the receipt and whether it went through the Safe, whose outer
+    /// transaction can succeed while the inner call reverts.
+    async fn send_wallet_call(
+        &self,
+        signer: &WalletSigner,
+        target: Address,
+        calldata: Vec<u8>,
+        what: &str,
+        direct_gas: u64,
+    ) -> Result<(TransactionReceipt, bool)> {
+        let use_proxy = self.proxy_wallet_address.is_some();
+        let sig_type = self.signature_type.unwrap_or(1);
+        let (tx_to, tx_data, gas_limit, via_safe) = if use_proxy && sig_type == 2 {
+            // Gnosis Safe: create Safe tx (the call), sign with EOA, execute via Safe.execTransaction
             // Matches redeem.ts redeemPositionsViaSafe() using Safe SDK (createTransaction -> signTransaction -> executeTransaction)
             let safe_address_str = self.proxy_wallet_address.as_deref()
-                .ok_or_else(|| anyhow::anyhow!("proxy_wallet_address required for Safe redemption"))?;
-            let safe_address = parse_address_hex(safe_address_str)
+                .ok_or_else(|| anyhow::anyhow!("proxy_wallet_address required for a Safe {}", what))?;
+            let safe_address = parse_address(safe_address_str)
                 .context("Failed to parse proxy_wallet_address (Safe address)")?;
-            eprintln!("   Using Gnosis Safe (proxy): signing and executing redemption via Safe.execTransaction");
+            eprintln!("   Using Gnosis Safe (proxy): signing and executing {} via Safe.execTransaction", what);
             // 1) Get Safe nonce
             let nonce_selector = keccak256("nonce()".as_bytes());
             let nonce_calldata: Vec<u8> = nonce_selector.as_slice()[..4].to_vec();
@@ -948,7 +1932,7 @@ impl PolymarketApi {
             let get_tx_hash_selector = keccak256(get_tx_hash_sig.as_bytes()).as_slice()[..4].to_vec();
             let zero_addr = [0u8; 32];
             let mut to_enc = [0u8; 32];
-            to_enc[12..].copy_from_slice(ctf_address.as_slice());
+            to_enc[12..].copy_from_slice(target.as_slice());
             let data_offset_get_hash = U256::from(32u32 * 10u32); // 320: data starts after 10 param words
             let mut get_tx_hash_calldata = Vec::new();
             get_tx_hash_calldata.extend_from_slice(&get_tx_hash_selector);
@@ -962,8 +1946,8 @@ impl PolymarketApi {
             get_tx_hash_calldata.extend_from_slice(&zero_addr);
             get_tx_hash_calldata.extend_from_slice(&zero_addr);
             get_tx_hash_calldata.extend_from_slice(&nonce.to_be_bytes::<32>());
-            get_tx_hash_calldata.extend_from_slice(&U256::from(redeem_calldata.len()).to_be_bytes::<32>());
-            get_tx_hash_calldata.extend_from_slice(&redeem_calldata);
+            get_tx_hash_calldata.extend_from_slice(&U256::from(calldata.len()).to_be_bytes::<32>());
+            get_tx_hash_calldata.extend_from_slice(&calldata);
             let get_tx_hash_tx = TransactionRequest::default()
                 .to(safe_address)
                 .input(Bytes::from(get_tx_hash_calldata).into());
@@ -1011,7 +1995,7 @@ impl PolymarketApi {
             let exec_sig = "execTransaction(address,uint256,bytes,uint8,uint256,uint256,uint256,address,address,bytes)";
             let exec_selector = keccak256(exec_sig.as_bytes()).as_slice()[..4].to_vec();
             let data_offset = 32u32 * 10u32; // 320: first dynamic param starts after 10 words
-            let sigs_offset = data_offset + 32 + redeem_calldata.len() as u32; // offset to signatures bytes
+            let sigs_offset = data_offset + 32 + calldata.len() as u32; // offset to signatures bytes
             let mut exec_calldata = Vec::new();
             exec_calldata.extend_from_slice(&exec_selector);
             exec_calldata.extend_from_slice(&to_enc);
@@ -1024,22 +2008,22 @@ impl PolymarketApi {
             exec_calldata.extend_from_slice(&zero_addr);
             exec_calldata.extend_from_slice(&zero_addr);
             exec_calldata.extend_from_slice(&U256::from(sigs_offset).to_be_bytes::<32>());
-            exec_calldata.extend_from_slice(&U256::from(redeem_calldata.len()).to_be_bytes::<32>());
-            exec_calldata.extend_from_slice(&redeem_calldata);
+            exec_calldata.extend_from_slice(&U256::from(calldata.len()).to_be_bytes::<32>());
+            exec_calldata.extend_from_slice(&calldata);
             exec_calldata.extend_from_slice(&U256::from(safe_sig_bytes.len()).to_be_bytes::<32>());
             exec_calldata.extend_from_slice(&safe_sig_bytes);
             (safe_address, exec_calldata, 400_000u64, true)
         } else if use_proxy && sig_type == 1 {
             // Polymarket Proxy: execute via Proxy Wallet Factory – factory.proxy([(typeCode, to, value, data)])
             // Refs: https://docs.polymarket.com/developers/proxy-wallet, Polymarket/examples examples/proxyWallet/redeem.ts
-            eprintln!("   Using proxy wallet: sending redemption via Proxy Wallet Factory");
-            let factory_address = parse_address_hex(PROXY_WALLET_FACTORY)
+            eprintln!("   Using proxy wallet: sending {} via Proxy Wallet Factory", what);
+            let factory_address = parse_address(PROXY_WALLET_FACTORY)
                 .context("Failed to parse Proxy Wallet Factory address")?;
             // ABI: proxy((uint8 typeCode, address to, uint256 value, bytes data)[] calls)
             let selector = keccak256("proxy((uint8,address,uint256,bytes)[])".as_bytes());
             let proxy_selector = &selector.as_slice()[..4];
-            // Encode one call: typeCode=1 (Call), to=CTF, value=0, data=redeem_calldata
-            let mut proxy_calldata = Vec::with_capacity(4 + 32 * 3 + 128 + 32 + redeem_calldata.len());
+            // Encode one call: typeCode=1 (Call), to=target, value=0, data=calldata
+            let mut proxy_calldata = Vec::with_capacity(4 + 32 * 3 + 128 + 32 + calldata.len());
             proxy_calldata.extend_from_slice(proxy_selector);
             // offset to array (params start at byte 4) = 32
             proxy_calldata.extend_from_slice(&U256::from(32u32).to_be_bytes::<32>());
@@ -1051,27 +2035,27 @@ impl PolymarketApi {
             let mut type_code = [0u8; 32];
             type_code[31] = 1;
             proxy_calldata.extend_from_slice(&type_code);
-            // to = ctf_address (32 bytes, left-padded)
+            // to = target (32 bytes, left-padded)
             let mut to_bytes = [0u8; 32];
-            to_bytes[12..].copy_from_slice(ctf_address.as_slice());
+            to_bytes[12..].copy_from_slice(target.as_slice());
             proxy_calldata.extend_from_slice(&to_bytes);
             // value = 0
             proxy_calldata.extend_from_slice(&U256::ZERO.to_be_bytes::<32>());
             // offset to bytes (from start of tuple) = 128
             proxy_calldata.extend_from_slice(&U256::from(128u32).to_be_bytes::<32>());
             // bytes: length then data
-            let data_len = redeem_calldata.len();
+            let data_len = calldata.len();
             proxy_calldata.extend_from_slice(&U256::from(data_len).to_be_bytes::<32>());
-            proxy_calldata.extend_from_slice(&redeem_calldata);
+            proxy_calldata.extend_from_slice(&calldata);
             (factory_address, proxy_calldata, 400_000u64, false)
         } else {
-            // EOA or no proxy: send redeemPositions directly to CTF (tokens must be in EOA)
-            eprintln!("   Sending redemption from EOA to CTF contract");
-            (ctf_address, redeem_calldata, 300_000, false)
+            // EOA or no proxy: send the call directly (funds must be in EOA)
+            eprintln!("   Sending {} from EOA", what);
+            (target, calldata, direct_gas, false)
         };
         
         let provider = ProviderBuilder::new()
-            .wallet(signer.clone())
+            .wallet(EthereumWallet::from(signer.clone()))
             .connect(RPC_URL)
             .await
             .context("Failed to connect to Polygon RPC")?;
@@ -1087,7 +2071,7 @@ impl PolymarketApi {
         let pending_tx = match provider.send_transaction(tx_request).await {
             Ok(tx) => tx,
             Err(e) => {
-                let err_msg = format!("Failed to send redeem transaction: {}", e);
+                let err_msg = format!("Failed to send {} transaction: {}", what, e);
                 eprintln!("   {}", err_msg);
                 anyhow::bail!("{}", err_msg);
             }
@@ -1101,39 +2085,109 @@ impl PolymarketApi {
             .context("Failed to get transaction receipt")?;
         
         if !receipt.status() {
-            anyhow::bail!("Redemption transaction failed. Transaction hash: {:?}", tx_hash);
-        }
-        
-        // When using Gnosis Safe, the outer tx can succeed while the inner CTF redeemPositions reverts.
-        // Detect inner failure by checking for CTF PayoutRedemption event in logs.
-        if used_safe_redemption {
-            let payout_redemption_topic = keccak256(
-                b"PayoutRedemption(address,address,bytes32,bytes32,uint256[],uint256)"
-            );
-            let logs = receipt.logs();
-            let ctf_has_payout = logs.iter().any(|log| {
-                log.address() == ctf_address && log.topics().first().map(|t| t.as_slice()) == Some(payout_redemption_topic.as_slice())
-            });
-            if !ctf_has_payout {
-                anyhow::bail!(
-                    "Redemption tx was mined but the inner redeem reverted (no PayoutRedemption from CTF). \
-                    Check that the Safe holds the winning tokens and conditionId/indexSet are correct. Tx: {:?}",
-                    tx_hash
-                );
-            }
+            anyhow::bail!("The {} transaction failed. Transaction hash: {:?}", what, tx_hash);
         }
-        
-        let redeem_response = RedeemResponse {
-            success: true,
-            message: Some(format!("Successfully redeemed tokens. Transaction: {:?}", tx_hash)),
-            transaction_hash: Some(format!("{:?}", tx_hash)),
-            amount_redeemed: None,
+        Ok((receipt, via_safe))
+    }
+}
+
+/// Polygon address from hex, with or without the 0x prefix.
+pub fn parse_address(s: &str) -> Result<Address> {
+    let hex_str = s.strip_prefix("0x").unwrap_or(s);
+    let bytes = hex::decode(hex_str).context("Invalid hex in address")?;
+    let len = bytes.len();
+    let arr: [u8; 20] = bytes.try_into().map_err(|_| anyhow::anyhow!("Address must be 20 bytes, got {}", len))?;
+    Ok(Address::from(arr))
+}
+
+/// A CLOB price field, which may come as a string or a number.
+/// A failed `post_order` as a classified error reported with `message`. A refusal the CLOB
+/// doesn't put down to auth, limits, balance or the market is about the order itself.
+fn order_error(e: SdkError, message: String) -> anyhow::Error {
+    match ApiError::classify(e.into()) {
+        ApiError::Other(reason) => ApiError::rejected(&reason, message),
+        classified => classified.with_message(message),
+    }
+    .into()
+}
+
+/// A failed plain HTTP request as a classified error, with the server's `Retry-After` on 429.
+fn http_error(response: &reqwest::Response, what: &str) -> anyhow::Error {
+    let status = response.status();
+    ApiError::from_response(status, response.headers().get(reqwest::header::RETRY_AFTER), format!("{} (status: {})", what, status)).into()
+}
+
+fn decimal_value(value: &Value) -> Option<rust_decimal::Decimal> {
+    match value {
+        Value::String(s) => rust_decimal::Decimal::from_str(s).ok(),
+        Value::Number(n) => rust_decimal::Decimal::from_str(&n.to_string()).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mockapi::{MockServer, TEST_PRIVATE_KEY};
+    use alloy::consensus::TxEip1559;
+
+    #[tokio::test]
+    async fn remote_signatures_are_checked_against_the_wallet() {
+        let server = MockServer::start().await.unwrap();
+        let local = PrivateKeySigner::from_str(TEST_PRIVATE_KEY).unwrap();
+        let remote = |address: &str, method: &str| {
+            RemoteSigner::new(&RemoteSignerConfig { method: method.to_string(), ..server.remote_signer(address) }).unwrap()
         };
-        eprintln!("Successfully redeemed winning tokens!");
-        eprintln!("Transaction hash: {:?}", tx_hash);
-        if let Some(block_number) = receipt.block_number {
-            eprintln!("Block number: {}", block_number);
-        }
-        Ok(redeem_response)
+        let hash = keccak256(b"order");
+
+        let signature = remote(&local.address().to_string(), "sign_hash").sign_hash(&hash).await.unwrap();
+        assert_eq!(signature, local.sign_hash(&hash).await.unwrap());
+
+        let other = remote("0x70997970C51812dc3A010C7d01b50e0d17dc79C8", "sign_hash").sign_hash(&hash).await;
+        assert!(format!("{:#}", other.unwrap_err()).contains("instead of"));
+        let refused = remote(&local.address().to_string(), "eth_sign").sign_hash(&hash).await;
+        assert!(format!("{:#}", refused.unwrap_err()).contains("method not found"));
+        assert!(verified_signature("0x1234", &hash, local.address()).is_err());
+    }
+
+    #[tokio::test]
+    async fn remote_transactions_are_signed_for_polygon() {
+        let server = MockServer::start().await.unwrap();
+        let local = PrivateKeySigner::from_str(TEST_PRIVATE_KEY).unwrap().with_chain_id(Some(POLYGON));
+        let remote = WalletSigner::Remote(RemoteSigner::new(&server.remote_signer(&local.address().to_string())).unwrap())
+            .with_chain_id(Some(POLYGON));
+        let unsigned = TxEip1559 { chain_id: POLYGON, nonce: 3, gas_limit: 100_000, to: Address::ZERO.into(), ..Default::default() };
+
+        let (mut by_remote, mut by_local) = (unsigned.clone(), unsigned);
+        let signature = remote.sign_transaction(&mut by_remote).await.unwrap();
+        assert_eq!(signature, local.sign_transaction(&mut by_local).await.unwrap());
+        assert_eq!(signature.recover_address_from_prehash(&by_remote.signature_hash()).unwrap(), local.address());
+
+        let mut other_chain = TxEip1559 { chain_id: 1, ..by_local };
+        assert!(remote.sign_transaction(&mut other_chain).await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_429_holds_requests_back_then_the_rate_limit_resumes() {
+        let server = MockServer::start().await.unwrap();
+        let api = server.api().with_rate_limit(2.0);
+        let start = tokio::time::Instant::now();
+        let waited = || start.elapsed().as_millis();
+
+        api.throttle().await;
+        api.throttle().await;
+        assert_eq!(waited(), 500);
+
+        let limited: ApiResult<()> = Err(ApiError::RateLimited { retry_after: Some(std::time::Duration::from_secs(3)), message: "429".to_string() });
+        api.observe("order", &limited);
+        api.throttle().await;
+        assert_eq!(waited(), 3500);
+
+        // Back to two a second once the backoff has passed; a 429 without Retry-After waits RATE_LIMIT_BACKOFF
+        api.throttle().await;
+        assert_eq!(waited(), 4000);
+        api.observe("order", &Err::<(), _>(ApiError::RateLimited { retry_after: None, message: "429".to_string() }));
+        api.throttle().await;
+        assert_eq!(waited(), 4000 + RATE_LIMIT_BACKOFF.as_millis());
     }
 }
@@ -0,0 +1,235 @@
+use std::time::Duration;
+
+/// Why a `PolymarketApi` call failed, so callers can react per class instead of matching on
+/// messages. Each variant carries the full error message for logging.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// Credentials rejected (401/403), including after re-deriving them
+    Auth(String),
+    /// 429; `retry_after` is the server's `Retry-After` when it sent one
+    RateLimited { retry_after: Option<Duration>, message: String },
+    /// Not enough USDC (buys) or shares (sells), or allowance too low
+    InsufficientBalance(String),
+    /// The market is closed, resolved or not taking orders
+    MarketClosed(String),
+    /// The CLOB refused the order itself: price out of range or off tick, size below the
+    /// minimum, unknown token, FOK not fillable
+    InvalidOrder(String),
+    /// Timeout, connection failure or 5xx
+    Network(String),
+    /// Anything else: unexpected responses, local configuration problems
+    Other(String),
+}
+
+pub type ApiResult<T> = std::result::Result<T, ApiError>;
+
+/// Messages the CLOB returns for rejected orders, lowercased, by class.
+const INSUFFICIENT_BALANCE: &[&str] = &["not enough balance", "insufficient", "allowance"];
+const MARKET_CLOSED: &[&str] = &["market is closed", "market closed", "not yet ready", "market not found", "orderbook does not exist"];
+const NETWORK: &[&str] = &["error sending request", "timed out", "timeout", "connection", "dns error"];
+
+impl ApiError {
+    /// Classifies an error from an API call. Errors already classified keep their class; the
+    /// rest are classified by HTTP status where one is reported, then by message.
+    pub fn classify(e: anyhow::Error) -> ApiError {
+        let e = match e.downcast::<ApiError>() {
+            Ok(classified) => return classified,
+            Err(e) => e,
+        };
+        let message = format!("{:#}", e);
+        match http_status(&e, &message) {
+            Some(401 | 403) => ApiError::Auth(message),
+            Some(429) => ApiError::RateLimited { retry_after: None, message },
+            Some(408) => ApiError::Network(message),
+            Some(code) if code >= 500 => ApiError::Network(message),
+            _ if e.chain().any(|c| c.downcast_ref::<reqwest::Error>().is_some_and(|r| r.is_timeout() || r.is_connect())) => {
+                ApiError::Network(message)
+            }
+            _ => Self::from_message(message),
+        }
+    }
+
+    /// Classifies a non-success HTTP response to a plain request.
+    pub fn from_response(status: reqwest::StatusCode, retry_after: Option<&reqwest::header::HeaderValue>, message: String) -> ApiError {
+        match status.as_u16() {
+            401 | 403 => ApiError::Auth(message),
+            429 => ApiError::RateLimited {
+                retry_after: retry_after.and_then(|v| v.to_str().ok()?.trim().parse().ok()).map(Duration::from_secs),
+                message,
+            },
+            408 => ApiError::Network(message),
+            code if code >= 500 => ApiError::Network(message),
+            _ => ApiError::Other(message),
+        }
+    }
+
+    /// Classifies an order the CLOB refused by its `reason`; a refusal that is not about the
+    /// connection, balance or the market is about the order itself. `message` is what gets
+    /// reported.
+    pub fn rejected(reason: &str, message: String) -> ApiError {
+        match Self::from_message(reason.to_string()) {
+            ApiError::Other(_) => ApiError::InvalidOrder(message),
+            classified => classified.with_message(message),
+        }
+    }
+
+    /// The same class of error, reported with `message`.
+    pub fn with_message(self, message: String) -> ApiError {
+        match self {
+            ApiError::Auth(_) => ApiError::Auth(message),
+            ApiError::RateLimited { retry_after, .. } => ApiError::RateLimited { retry_after, message },
+            ApiError::InsufficientBalance(_) => ApiError::InsufficientBalance(message),
+            ApiError::MarketClosed(_) => ApiError::MarketClosed(message),
+            ApiError::InvalidOrder(_) => ApiError::InvalidOrder(message),
+            ApiError::Network(_) => ApiError::Network(message),
+            ApiError::Other(_) => ApiError::Other(message),
+        }
+    }
+
+    fn from_message(message: String) -> ApiError {
+        let lower = message.to_lowercase();
+        let matches = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+        if matches(NETWORK) {
+            ApiError::Network(message)
+        } else if matches(INSUFFICIENT_BALANCE) {
+            ApiError::InsufficientBalance(message)
+        } else if matches(MARKET_CLOSED) {
+            ApiError::MarketClosed(message)
+        } else {
+            ApiError::Other(message)
+        }
+    }
+
+    /// Metrics label
+    pub fn label(&self) -> &'static str {
+        match self {
+            ApiError::Auth(_) => "auth",
+            ApiError::RateLimited { .. } => "rate_limited",
+            ApiError::InsufficientBalance(_) => "insufficient_balance",
+            ApiError::MarketClosed(_) => "market_closed",
+            ApiError::InvalidOrder(_) => "invalid_order",
+            ApiError::Network(_) => "network",
+            ApiError::Other(_) => "other",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::Auth(m)
+            | ApiError::RateLimited { message: m, .. }
+            | ApiError::InsufficientBalance(m)
+            | ApiError::MarketClosed(m)
+            | ApiError::InvalidOrder(m)
+            | ApiError::Network(m)
+            | ApiError::Other(m) => m,
+        }
+    }
+
+    /// Worth retrying the same request later (the next tick); the others fail the same way again.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, ApiError::RateLimited { .. } | ApiError::Network(_))
+    }
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// HTTP status of a failed call: from the CLOB SDK's status error, else from the
+/// "(status: 429 …)" / "error(429 …)" our messages and the SDK's carry.
+fn http_status(e: &anyhow::Error, message: &str) -> Option<u16> {
+    use polymarket_client_sdk::error::{Error as SdkError, Status as SdkStatus};
+    let typed = e.chain().find_map(|cause| {
+        cause
+            .downcast_ref::<SdkError>()
+            .and_then(|e| e.downcast_ref::<SdkStatus>())
+            .or_else(|| cause.downcast_ref::<SdkStatus>())
+            .map(|s| s.status_code.as_u16())
+    });
+    typed.or_else(|| {
+        ["(status: ", "error("].iter().find_map(|prefix| {
+            let rest = &message[message.find(prefix)? + prefix.len()..];
+            rest.get(..3)?.parse().ok()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn failed_calls_are_classified_by_status_then_message() {
+        let cases = [
+            ("Order failed (status: 401 Unauthorized): invalid api key", "auth"),
+            ("error(403 Forbidden): ip blocked", "auth"),
+            ("Order failed (status: 429 Too Many Requests): slow down", "rate_limited"),
+            ("Order failed (status: 503 Service Unavailable)", "network"),
+            ("error sending request for url (https://clob.polymarket.com/order)", "network"),
+            ("not enough balance / allowance", "insufficient_balance"),
+            ("the market is closed", "market_closed"),
+            ("orderbook does not exist for token", "market_closed"),
+            ("unexpected response shape", "other"),
+        ];
+        for (message, label) in cases {
+            assert_eq!(ApiError::classify(anyhow::anyhow!(message)).label(), label, "{}", message);
+        }
+    }
+
+    #[test]
+    fn classified_errors_keep_their_class() {
+        let e = anyhow::Error::new(ApiError::InvalidOrder("size below minimum".to_string())).context("placing order");
+        assert_eq!(ApiError::classify(e), ApiError::InvalidOrder("size below minimum".to_string()));
+    }
+
+    #[test]
+    fn responses_are_classified_by_status() {
+        let limited = ApiError::from_response(StatusCode::TOO_MANY_REQUESTS, Some(&HeaderValue::from_static("7")), "429".to_string());
+        assert_eq!(limited, ApiError::RateLimited { retry_after: Some(Duration::from_secs(7)), message: "429".to_string() });
+        let cases = [
+            (StatusCode::UNAUTHORIZED, "auth"),
+            (StatusCode::FORBIDDEN, "auth"),
+            (StatusCode::TOO_MANY_REQUESTS, "rate_limited"),
+            (StatusCode::REQUEST_TIMEOUT, "network"),
+            (StatusCode::BAD_GATEWAY, "network"),
+            (StatusCode::BAD_REQUEST, "other"),
+            (StatusCode::NOT_FOUND, "other"),
+        ];
+        for (status, label) in cases {
+            assert_eq!(ApiError::from_response(status, None, status.to_string()).label(), label, "{}", status);
+        }
+    }
+
+    #[test]
+    fn refused_orders_are_invalid_unless_the_reason_says_otherwise() {
+        let cases = [
+            ("invalid tick size", "invalid_order"),
+            ("order couldn't be fully filled, FOK orders are fully filled or killed", "invalid_order"),
+            ("not enough balance / allowance", "insufficient_balance"),
+            ("market not found", "market_closed"),
+            ("request timed out", "network"),
+        ];
+        for (reason, label) in cases {
+            let e = ApiError::rejected(reason, format!("Order rejected: {}", reason));
+            assert_eq!((e.label(), e.message()), (label, format!("Order rejected: {}", reason).as_str()), "{}", reason);
+        }
+    }
+
+    #[test]
+    fn only_rate_limits_and_network_errors_are_transient() {
+        let transient = |e: ApiError| e.is_transient();
+        assert!(transient(ApiError::Network(String::new())));
+        assert!(transient(ApiError::RateLimited { retry_after: None, message: String::new() }));
+        assert!(!transient(ApiError::Auth(String::new())));
+        assert!(!transient(ApiError::InsufficientBalance(String::new())));
+        assert!(!transient(ApiError::MarketClosed(String::new())));
+        assert!(!transient(ApiError::InvalidOrder(String::new())));
+        assert!(!transient(ApiError::Other(String::new())));
+    }
+}
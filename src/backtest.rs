@@ -0,0 +1,577 @@
+use crate::blackout::Blackouts;
+use crate::bookdiff::{BookDecoder, RecordedLine};
+use crate::config::{FeeConfig, FillModelConfig, StrategyConfig};
+use crate::fees::FeeSchedule;
+use crate::fill::FillModel;
+use crate::models::MarketSnapshot;
+use crate::signals::{self, MarketSignal, OneSideRiskMode};
+use crate::strategy::MARKET_DURATION_SECS;
+use crate::trend::{self, PriceHistory};
+use anyhow::{Context, Result};
+use flate2::read::MultiGzDecoder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Fill model seed for backtests and replays when `fill_model.seed` is not set.
+pub const DEFAULT_FILL_SEED: u64 = 42;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Up,
+    Down,
+}
+
+/// What a strategy asks the backtest engine to do after seeing a snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Rest limit buys of `shares` on both outcomes of the market starting at `period_start`
+    PlaceOrders { period_start: i64, up_price: f64, down_price: f64, shares: f64 },
+    /// Sell a filled outcome at market and cancel any unfilled order in the same period
+    Sell { period_start: i64, outcome: Outcome },
+}
+
+/// Backtest view of one leg (outcome) of a position.
+#[derive(Debug, Clone, Default)]
+pub struct Leg {
+    pub order_price: Option<f64>,
+    pub filled: bool,
+    /// Fully sold; partial sells leave this false until the rest fills
+    pub sold: bool,
+    pub sold_shares: f64,
+    pub proceeds: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Position {
+    pub up: Leg,
+    pub down: Leg,
+    /// Shares per side
+    pub shares: f64,
+    pub one_side_since: Option<i64>,
+    /// When the orders were placed, Unix milliseconds
+    pub placed_ms: i64,
+    /// A sell completed; no further actions for this period
+    pub exited: bool,
+}
+
+impl Position {
+    pub fn leg(&self, outcome: Outcome) -> &Leg {
+        match outcome {
+            Outcome::Up => &self.up,
+            Outcome::Down => &self.down,
+        }
+    }
+
+    fn leg_mut(&mut self, outcome: Outcome) -> &mut Leg {
+        match outcome {
+            Outcome::Up => &mut self.up,
+            Outcome::Down => &mut self.down,
+        }
+    }
+
+    pub fn one_side_filled(&self) -> Option<Outcome> {
+        match (self.up.filled, self.down.filled) {
+            (true, false) => Some(Outcome::Up),
+            (false, true) => Some(Outcome::Down),
+            _ => None,
+        }
+    }
+}
+
+/// Decision logic that can be replayed over recorded snapshots.
+pub trait Strategy {
+    /// `positions` holds this asset's positions keyed by period start.
+    fn on_snapshot(&mut self, snapshot: &MarketSnapshot, positions: &BTreeMap<i64, Position>) -> Vec<Action>;
+}
+
+/// The live pre-limit strategy's rules, expressed over snapshots.
+pub struct PreLimitModel {
+    config: StrategyConfig,
+    fees: FeeSchedule,
+    blackouts: Blackouts,
+    history: HashMap<String, PriceHistory>,
+}
+
+impl PreLimitModel {
+    pub fn new(config: StrategyConfig) -> Self {
+        let fees = FeeSchedule::new(config.fees.clone());
+        let blackouts = Blackouts::new(&config.blackouts).unwrap_or_default();
+        Self { config, fees, blackouts, history: HashMap::new() }
+    }
+
+    fn in_blackout(&self, period_start: i64) -> bool {
+        self.blackouts.overlapping(period_start, period_start + MARKET_DURATION_SECS).is_some()
+    }
+
+    /// Settings in `config` that turn on live rules this model doesn't replay: spike sells,
+    /// momentum confirmation, streak sizing, balance allocation, correlation limits and the
+    /// kill switch's loss limits. A backtest of such a config leaves those rules out.
+    pub fn unmodelled(config: &StrategyConfig) -> Vec<&'static str> {
+        let risk = &config.risk;
+        [
+            ("spike_sell.enabled", config.spike_sell.enabled),
+            ("signal.momentum.enabled", config.signal.momentum.enabled),
+            ("streak_sizing.enabled", config.streak_sizing.enabled),
+            ("allocation.enabled", config.allocation.enabled),
+            ("risk.correlation_groups", !risk.correlation_groups.is_empty()),
+            ("risk.daily_loss_limit_usd", risk.daily_loss_limit_usd.is_some_and(|limit| limit > 0.0)),
+            ("risk.max_drawdown_usd", risk.max_drawdown_usd.is_some_and(|limit| limit > 0.0)),
+        ]
+        .into_iter()
+        .filter_map(|(setting, enabled)| enabled.then_some(setting))
+        .collect()
+    }
+}
+
+/// Warns when `config` enables rules the backtester doesn't model, so the result isn't taken
+/// for the live strategy's.
+pub fn warn_unmodelled(config: &StrategyConfig) {
+    let unmodelled = PreLimitModel::unmodelled(config);
+    if !unmodelled.is_empty() {
+        log::warn!("⚠️ The backtester doesn't model {} — the result leaves these rules out", unmodelled.join(", "));
+    }
+}
+
+impl Strategy for PreLimitModel {
+    fn on_snapshot(&mut self, snapshot: &MarketSnapshot, positions: &BTreeMap<i64, Position>) -> Vec<Action> {
+        let cfg = &self.config;
+        let now = snapshot.timestamp_secs();
+        let period = snapshot.period_start;
+        let next_period = period + MARKET_DURATION_SECS;
+        let time_until_next = next_period - now;
+        let (up_price, down_price) = (snapshot.up.price, snapshot.down.price);
+        let history = self.history.entry(snapshot.asset.clone()).or_default();
+        history.push(period, snapshot.timestamp_ms, up_price, cfg.signal.trend.window.max(cfg.volatility_sizing.window));
+        let signal = signals::with_trend(
+            signals::evaluate_place_signal(&cfg.signal, up_price, down_price, time_until_next.max(0)),
+            history.trend(&cfg.signal.trend),
+        );
+        let shares = trend::volatility_scaled_shares(
+            &cfg.volatility_sizing,
+            cfg.shares,
+            history.std_dev(cfg.volatility_sizing.window),
+        );
+        let current = positions.get(&period);
+        let mut actions = Vec::new();
+
+        let needs_danger_handling = current.is_some_and(|p| !p.exited && p.one_side_filled().is_some());
+        if time_until_next <= (cfg.place_order_before_mins * 60) as i64 {
+            if cfg.pre_orders_enabled
+                && !positions.contains_key(&next_period)
+                && !needs_danger_handling
+                && signal == MarketSignal::Good
+                && !self.in_blackout(next_period)
+                && self.fees.pair_ev(signals::EVEN_ODDS, cfg.price_limit, cfg.price_limit, shares) > cfg.ev_min_usd
+            {
+                actions.push(Action::PlaceOrders {
+                    period_start: next_period,
+                    up_price: cfg.price_limit,
+                    down_price: cfg.price_limit,
+                    shares,
+                });
+            }
+        } else if cfg.signal.mid_market_enabled
+            && current.is_none()
+            && time_until_next >= (cfg.signal.danger_time_passed * 60) as i64
+            && signal == MarketSignal::Good
+            && !self.in_blackout(period)
+        {
+            let (up, down) = signals::mid_market_prices(&cfg.signal, up_price, down_price);
+            let (up, down) = (round_price(up), round_price(down));
+            let odds = signals::implied_probabilities(up_price, down_price);
+            if self.fees.pair_ev(odds, up, down, shares) > cfg.ev_min_usd {
+                actions.push(Action::PlaceOrders { period_start: period, up_price: up, down_price: down, shares });
+            }
+        }
+
+        let Some(pos) = current else {
+            return actions;
+        };
+        if pos.exited {
+            return actions;
+        }
+
+        if pos.up.filled && pos.down.filled {
+            let loser = if !cfg.sell_opposite_enabled {
+                None
+            } else if up_price >= cfg.sell_opposite_above {
+                Some(Outcome::Down)
+            } else if down_price >= cfg.sell_opposite_above {
+                Some(Outcome::Up)
+            } else {
+                None
+            };
+            if let Some(loser) = loser {
+                if time_until_next / 60 <= cfg.sell_opposite_time_remaining as i64 {
+                    actions.push(Action::Sell { period_start: period, outcome: loser });
+                }
+            }
+        } else if let Some(filled) = pos.one_side_filled() {
+            let should_exit = match signals::one_side_risk_mode(&cfg.signal) {
+                OneSideRiskMode::Price => {
+                    let price = if filled == Outcome::Up { up_price } else { down_price };
+                    signals::is_danger_signal(&cfg.signal, price)
+                }
+                OneSideRiskMode::Time => pos
+                    .one_side_since
+                    .is_some_and(|t| now - t >= (cfg.signal.danger_time_passed * 60) as i64),
+                OneSideRiskMode::None => false,
+            };
+            if should_exit {
+                actions.push(Action::Sell { period_start: period, outcome: filled });
+            }
+        }
+        actions
+    }
+}
+
+fn round_price(price: f64) -> f64 {
+    ((price * 100.0).round() / 100.0).clamp(0.01, 0.99)
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestSettings {
+    pub fill_model: FillModelConfig,
+    pub fees: FeeConfig,
+    /// `strategy.slippage_tolerance`: sells fill no lower than the quote less this
+    pub slippage_tolerance: f64,
+}
+
+/// Result of one traded period.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeriodResult {
+    pub asset: String,
+    pub period_start: i64,
+    pub pnl: f64,
+    pub both_filled: bool,
+    /// Sold out of a filled side before resolution
+    pub exited: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub snapshots: usize,
+    pub periods_traded: usize,
+    pub both_filled: usize,
+    pub one_side_exits: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub total_pnl: f64,
+    pub max_drawdown: f64,
+    pub pnl_by_asset: BTreeMap<String, f64>,
+    /// Every traded period in settlement order
+    #[serde(default)]
+    pub periods: Vec<PeriodResult>,
+}
+
+impl BacktestReport {
+    pub fn win_rate(&self) -> f64 {
+        let n = self.wins + self.losses;
+        if n == 0 { 0.0 } else { self.wins as f64 / n as f64 }
+    }
+
+    pub fn print(&self) {
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("📊 Backtest result");
+        eprintln!("   snapshots replayed     {}", self.snapshots);
+        eprintln!("   periods traded         {}", self.periods_traded);
+        eprintln!("   both sides filled      {}", self.both_filled);
+        eprintln!("   one-side exits         {}", self.one_side_exits);
+        eprintln!("   wins / losses          {} / {} ({:.1}%)", self.wins, self.losses, self.win_rate() * 100.0);
+        eprintln!("   total PnL             ${:.2}", self.total_pnl);
+        eprintln!("   max drawdown          ${:.2}", self.max_drawdown);
+        for (asset, pnl) in &self.pnl_by_asset {
+            eprintln!("   {:<5}                 ${:.2}", asset, pnl);
+        }
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+
+    /// Writes the report as JSON, the result file `backtest compare` reads.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let file = std::fs::File::create(path).context(format!("Failed to create {}", path.display()))?;
+        serde_json::to_writer_pretty(std::io::BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path).context(format!("Failed to open {}", path.display()))?;
+        serde_json::from_reader(BufReader::new(file)).context(format!("Invalid backtest result {}", path.display()))
+    }
+}
+
+/// Per-asset replay state.
+#[derive(Default)]
+struct AssetBook {
+    positions: BTreeMap<i64, Position>,
+    last_prices: HashMap<i64, (f64, f64)>,
+}
+
+/// Replays `snapshots` (any order) through `strategy`. Limit buys fill when the quoted price
+/// reaches the limit, sells go through the fill model against the snapshot's bids, and held
+/// shares resolve to the side with the higher final quoted price. With
+/// `fill_model.latency_ms_simulated`, buys can't fill until that long after they were placed
+/// and sells fill against the market as recorded that long after the decision.
+pub fn run<S: Strategy>(strategy: &mut S, snapshots: &mut [MarketSnapshot], settings: &BacktestSettings) -> BacktestReport {
+    snapshots.sort_by_key(|s| s.timestamp_ms);
+    // Misses are random; fix the seed so repeated runs (and optimize trials) are comparable
+    let mut fill_config = settings.fill_model.clone();
+    fill_config.seed = fill_config.seed.or(Some(DEFAULT_FILL_SEED));
+    let fees = FeeSchedule::new(settings.fees.clone());
+    let mut fills = FillModel::new(fill_config, fees.clone());
+    let mut books: HashMap<String, AssetBook> = HashMap::new();
+    let mut report = BacktestReport { snapshots: snapshots.len(), ..Default::default() };
+    let mut equity = 0.0;
+    let mut peak = 0.0;
+
+    let latency_ms = settings.fill_model.latency_ms_simulated as i64;
+    for (i, snapshot) in snapshots.iter().enumerate() {
+        // Glitch books the live bot would have refused
+        if snapshot.invalid.is_some() {
+            continue;
+        }
+        let now = snapshot.timestamp_secs();
+        let book = books.entry(snapshot.asset.clone()).or_default();
+        book.last_prices.insert(snapshot.period_start, (snapshot.up.price, snapshot.down.price));
+
+        // Resolve periods that have ended for this asset
+        let ended: Vec<i64> = book
+            .positions
+            .keys()
+            .copied()
+            .filter(|p| p + MARKET_DURATION_SECS <= now)
+            .collect();
+        for period in ended {
+            let pos = book.positions.remove(&period).unwrap();
+            let prices = book.last_prices.remove(&period);
+            let pnl = settle(&pos, prices, &fees);
+            record_settlement(&mut report, &snapshot.asset, period, &pos, pnl);
+            equity += pnl;
+            peak = f64::max(peak, equity);
+            report.max_drawdown = f64::max(report.max_drawdown, peak - equity);
+        }
+
+        // Fills for resting orders in this snapshot's period
+        if let Some(pos) = book.positions.get_mut(&snapshot.period_start).filter(|p| p.placed_ms + latency_ms <= snapshot.timestamp_ms) {
+            for (outcome, price) in [(Outcome::Up, snapshot.up.price), (Outcome::Down, snapshot.down.price)] {
+                let leg = pos.leg_mut(outcome);
+                if let Some(limit) = leg.order_price {
+                    if !leg.filled && (price <= limit || (price - limit).abs() < 0.001) {
+                        leg.filled = true;
+                    }
+                }
+            }
+            if pos.one_side_filled().is_some() && pos.one_side_since.is_none() {
+                pos.one_side_since = Some(now);
+            }
+        }
+
+        for action in strategy.on_snapshot(snapshot, &book.positions) {
+            match action {
+                Action::PlaceOrders { period_start, up_price, down_price, shares } => {
+                    book.positions.entry(period_start).or_insert_with(|| Position {
+                        up: Leg { order_price: Some(up_price), ..Default::default() },
+                        down: Leg { order_price: Some(down_price), ..Default::default() },
+                        shares,
+                        placed_ms: snapshot.timestamp_ms,
+                        ..Default::default()
+                    });
+                }
+                Action::Sell { period_start, outcome } => {
+                    if period_start != snapshot.period_start {
+                        continue;
+                    }
+                    let Some(pos) = book.positions.get_mut(&period_start) else { continue };
+                    let at_fill = after_latency(&snapshots[i..], latency_ms);
+                    let token = match outcome {
+                        Outcome::Up => &at_fill.up,
+                        Outcome::Down => &at_fill.down,
+                    };
+                    let shares = pos.shares;
+                    let leg = pos.leg_mut(outcome);
+                    if !leg.filled || leg.sold {
+                        continue;
+                    }
+                    // A miss leaves the position open; the strategy asks again on the next snapshot
+                    let decided = match outcome {
+                        Outcome::Up => snapshot.up.price,
+                        Outcome::Down => snapshot.down.price,
+                    };
+                    let Some(fill) = fills.market_sell(&token.bids, token.price, shares - leg.sold_shares, decided - settings.slippage_tolerance) else {
+                        continue;
+                    };
+                    leg.sold_shares += fill.shares;
+                    leg.proceeds += fill.proceeds();
+                    leg.sold = fill.complete;
+                    // Cancel whatever is still resting
+                    for leg in [&mut pos.up, &mut pos.down] {
+                        if !leg.filled {
+                            leg.order_price = None;
+                        }
+                    }
+                    pos.exited = fill.complete;
+                }
+            }
+        }
+    }
+
+    // Settle whatever is still open at the end of the data
+    for (asset, mut book) in books {
+        for (period, pos) in std::mem::take(&mut book.positions) {
+            let pnl = settle(&pos, book.last_prices.get(&period).copied(), &fees);
+            record_settlement(&mut report, &asset, period, &pos, pnl);
+            equity += pnl;
+            peak = f64::max(peak, equity);
+            report.max_drawdown = f64::max(report.max_drawdown, peak - equity);
+        }
+    }
+    report.total_pnl = equity;
+    report
+}
+
+/// The market of `later[0]` as recorded `latency_ms` after it: its latest valid snapshot by
+/// then among `later`, which runs on from `later[0]` in time order.
+fn after_latency(later: &[MarketSnapshot], latency_ms: i64) -> &MarketSnapshot {
+    let decided = &later[0];
+    later
+        .iter()
+        .take_while(|s| s.timestamp_ms <= decided.timestamp_ms + latency_ms)
+        .filter(|s| s.condition_id == decided.condition_id && s.invalid.is_none())
+        .last()
+        .unwrap_or(decided)
+}
+
+/// PnL of a position at resolution, after maker fees on the buys. Without price data the outcome
+/// is unknown and held legs pay nothing.
+fn settle(pos: &Position, final_prices: Option<(f64, f64)>, fees: &FeeSchedule) -> f64 {
+    let shares = pos.shares;
+    let winner = final_prices.map(|(up, down)| if up >= down { Outcome::Up } else { Outcome::Down });
+    let mut pnl = 0.0;
+    for outcome in [Outcome::Up, Outcome::Down] {
+        let leg = pos.leg(outcome);
+        if !leg.filled {
+            continue;
+        }
+        let price = leg.order_price.unwrap_or(0.0);
+        pnl -= price * shares + fees.maker_fee(price, shares);
+        pnl += leg.proceeds;
+        if winner == Some(outcome) {
+            pnl += shares - leg.sold_shares;
+        }
+    }
+    pnl
+}
+
+fn record_settlement(report: &mut BacktestReport, asset: &str, period_start: i64, pos: &Position, pnl: f64) {
+    if !pos.up.filled && !pos.down.filled {
+        return;
+    }
+    report.periods_traded += 1;
+    if pos.up.filled && pos.down.filled {
+        report.both_filled += 1;
+    } else if pos.exited {
+        report.one_side_exits += 1;
+    }
+    if pnl > 0.0 {
+        report.wins += 1;
+    } else {
+        report.losses += 1;
+    }
+    *report.pnl_by_asset.entry(asset.to_string()).or_default() += pnl;
+    report.periods.push(PeriodResult {
+        asset: asset.to_string(),
+        period_start,
+        pnl,
+        both_filled: pos.up.filled && pos.down.filled,
+        exited: pos.exited,
+    });
+}
+
+/// Loads snapshots from NDJSON files (optionally gzipped), or from every such file under a directory.
+pub fn load_snapshots(paths: &[PathBuf]) -> Result<Vec<MarketSnapshot>> {
+    let mut snapshots = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            for entry in walkdir::WalkDir::new(path).sort_by_file_name() {
+                let entry = entry?;
+                if entry.file_type().is_file() && is_snapshot_file(entry.path()) {
+                    read_snapshot_file(entry.path(), &mut snapshots)?;
+                }
+            }
+        } else {
+            read_snapshot_file(path, &mut snapshots)?;
+        }
+    }
+    Ok(snapshots)
+}
+
+fn is_snapshot_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    [".ndjson", ".jsonl", ".ndjson.gz", ".jsonl.gz"].iter().any(|ext| name.ends_with(ext))
+}
+
+fn read_snapshot_file(path: &Path, out: &mut Vec<MarketSnapshot>) -> Result<()> {
+    let file = std::fs::File::open(path).context(format!("Failed to open {}", path.display()))?;
+    let reader: Box<dyn BufRead> = if path.extension().is_some_and(|e| e == "gz") {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    let mut books = BookDecoder::default();
+    for (i, line) in reader.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                // Recorder was stopped mid-period; everything before the missing trailer is intact
+                log::debug!("{} ends without a gzip trailer", path.display());
+                break;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let line: RecordedLine = serde_json::from_str(&line)
+            .context(format!("Invalid snapshot at {}:{}", path.display(), i + 1))?;
+        let snapshot = books.decode(line).context(format!("Invalid snapshot at {}:{}", path.display(), i + 1))?;
+        out.push(snapshot);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(timestamp_ms: i64, condition_id: &str, up_price: f64) -> MarketSnapshot {
+        serde_json::from_value(serde_json::json!({
+            "timestamp_ms": timestamp_ms, "asset": "BTC", "condition_id": condition_id, "period_start": 0,
+            "up": { "token_id": "up", "price": up_price }, "down": { "token_id": "down", "price": 1.0 - up_price },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn lists_enabled_rules_it_does_not_model() {
+        let mut config = crate::config::Config::default().strategy;
+        assert!(PreLimitModel::unmodelled(&config).is_empty());
+        config.spike_sell.enabled = true;
+        config.risk.daily_loss_limit_usd = Some(0.0);
+        config.risk.max_drawdown_usd = Some(50.0);
+        assert_eq!(PreLimitModel::unmodelled(&config), vec!["spike_sell.enabled", "risk.max_drawdown_usd"]);
+    }
+
+    #[test]
+    fn sells_fill_against_the_market_as_recorded_after_the_latency() {
+        let snapshots = vec![
+            snapshot(1_000, "0xa", 0.50),
+            snapshot(1_200, "0xb", 0.10),
+            snapshot(1_400, "0xa", 0.45),
+            snapshot(1_700, "0xa", 0.40),
+        ];
+        assert_eq!(after_latency(&snapshots, 0).up.price, 0.50);
+        assert_eq!(after_latency(&snapshots, 500).up.price, 0.45);
+        assert_eq!(after_latency(&snapshots, 5_000).up.price, 0.40);
+    }
+}
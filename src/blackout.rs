@@ -0,0 +1,169 @@
+use crate::config::BlackoutConfig;
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, TimeZone, Timelike};
+use chrono_tz::America::New_York;
+
+/// No-trade windows: each starts whenever its cron expression matches a minute in ET and lasts
+/// `duration_mins`. A market whose period overlaps a window gets no new buys.
+#[derive(Debug, Clone, Default)]
+pub struct Blackouts {
+    windows: Vec<Window>,
+}
+
+#[derive(Debug, Clone)]
+struct Window {
+    label: String,
+    schedule: Cron,
+    duration_secs: i64,
+}
+
+impl Blackouts {
+    pub fn new(configs: &[BlackoutConfig]) -> Result<Self> {
+        let windows = configs
+            .iter()
+            .map(|c| {
+                let schedule = Cron::parse(&c.cron).with_context(|| format!("blackout {:?}", c.label()))?;
+                Ok(Window { label: c.label().to_string(), schedule, duration_secs: c.duration_mins as i64 * 60 })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { windows })
+    }
+
+    /// Label of a window overlapping `[start, end)` (unix seconds), if any.
+    pub fn overlapping(&self, start: i64, end: i64) -> Option<&str> {
+        self.windows.iter().find(|w| w.overlaps(start, end)).map(|w| w.label.as_str())
+    }
+}
+
+impl Window {
+    /// A window starting at minute `t` overlaps when `start - duration < t < end`.
+    fn overlaps(&self, start: i64, end: i64) -> bool {
+        let first = (start - self.duration_secs).div_euclid(60) + 1;
+        let last = (end - 1).div_euclid(60);
+        (first..=last).any(|minute| self.schedule.matches(minute * 60))
+    }
+}
+
+/// Five-field cron expression (minute, hour, day of month, month, day of week with 0 or 7 for
+/// Sunday). Fields take `*`, numbers, `a-b` ranges, `/n` steps and comma lists. As in cron, when
+/// both day fields are restricted a day matching either one counts.
+#[derive(Debug, Clone)]
+pub struct Cron {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days: Vec<bool>,
+    months: Vec<bool>,
+    weekdays: Vec<bool>,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cron {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            bail!("cron expression {:?} must have 5 fields (minute hour day month weekday)", expr);
+        };
+        let mut weekdays = parse_field(weekday, 0, 7).context("day of week")?;
+        // 7 is Sunday too
+        weekdays[0] |= weekdays[7];
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).context("minute")?,
+            hours: parse_field(hour, 0, 23).context("hour")?,
+            days: parse_field(day, 1, 31).context("day of month")?,
+            months: parse_field(month, 1, 12).context("month")?,
+            weekdays,
+            any_day: *day == "*",
+            any_weekday: *weekday == "*",
+        })
+    }
+
+    /// Whether the minute containing unix time `ts` matches, read in ET.
+    pub fn matches(&self, ts: i64) -> bool {
+        let Some(t) = New_York.timestamp_opt(ts, 0).single() else {
+            return false;
+        };
+        let day = self.days[t.day() as usize];
+        let weekday = self.weekdays[t.weekday().num_days_from_sunday() as usize];
+        let day_matches = match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        };
+        self.minutes[t.minute() as usize] && self.hours[t.hour() as usize] && self.months[t.month() as usize] && day_matches
+    }
+}
+
+/// Which values in `min..=max` a field selects, indexed by value.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>> {
+    let mut selected = vec![false; max as usize + 1];
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().with_context(|| format!("bad step in {:?}", part))?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            bail!("step of 0 in {:?}", part);
+        }
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (parse_value(a, part)?, parse_value(b, part)?),
+                None => {
+                    let value = parse_value(range, part)?;
+                    // "5/15" runs from 5 to the end, like "5-max/15"
+                    (value, if part.contains('/') { max } else { value })
+                }
+            },
+        };
+        if from < min || to > max || from > to {
+            bail!("{:?} is outside {}-{}", part, min, max);
+        }
+        for value in (from..=to).step_by(step as usize) {
+            selected[value as usize] = true;
+        }
+    }
+    Ok(selected)
+}
+
+fn parse_value(value: &str, part: &str) -> Result<u32> {
+    value.parse().with_context(|| format!("bad value in {:?}", part))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2026-01-06 14:00:00 ET, a Tuesday
+    const TUESDAY_2PM: i64 = 1767726000;
+
+    fn blackouts(cron: &str, duration_mins: u64) -> Blackouts {
+        Blackouts::new(&[BlackoutConfig { label: Some("test".to_string()), cron: cron.to_string(), duration_mins }]).unwrap()
+    }
+
+    #[test]
+    fn parses_fields_in_eastern_time() {
+        let fomc = Cron::parse("0 14 * * 2,3").unwrap();
+        assert!(fomc.matches(TUESDAY_2PM));
+        assert!(fomc.matches(TUESDAY_2PM + 59));
+        assert!(!fomc.matches(TUESDAY_2PM + 60));
+        assert!(!fomc.matches(TUESDAY_2PM + 5 * 24 * 3600));
+        assert!(Cron::parse("*/15 8-9 * 1 0-7").unwrap().matches(TUESDAY_2PM - 6 * 3600 + 45 * 60));
+        // Day of month or day of week when both are restricted
+        assert!(Cron::parse("0 14 1 * 2").unwrap().matches(TUESDAY_2PM));
+        assert!(!Cron::parse("0 14 1 * 3").unwrap().matches(TUESDAY_2PM));
+        for bad in ["0 14 * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+            assert!(Cron::parse(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn blocks_periods_overlapping_a_window() {
+        // 08:30-09:00 ET, 14:00 ET on Jan 6 shifted back 5.5 hours
+        let cpi = blackouts("30 8 * * *", 30);
+        let release = TUESDAY_2PM - 5 * 3600 - 30 * 60;
+        assert_eq!(cpi.overlapping(release - 900, release), None);
+        assert_eq!(cpi.overlapping(release - 60, release + 840), Some("test"));
+        assert_eq!(cpi.overlapping(release + 15 * 60, release + 30 * 60), Some("test"));
+        assert_eq!(cpi.overlapping(release + 30 * 60, release + 45 * 60), None);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::models::{MarketSnapshot, PriceLevel, TokenSnapshot};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Changed levels of one token's book since the line before: the new size at each price that
+/// changed, 0 where a level went away.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelChanges {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bids: Vec<PriceLevel>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub asks: Vec<PriceLevel>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BookDiff {
+    pub up: LevelChanges,
+    pub down: LevelChanges,
+}
+
+/// One line of a snapshot recording. With `book_diff`, the snapshot's books are left out and
+/// the diff holds their changes since the previous line of the same file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedLine {
+    #[serde(flatten)]
+    pub snapshot: MarketSnapshot,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book_diff: Option<BookDiff>,
+}
+
+/// (bids, asks) of a token, best-first
+type Book = (Vec<PriceLevel>, Vec<PriceLevel>);
+
+fn book(token: &TokenSnapshot) -> Book {
+    (token.bids.clone(), token.asks.clone())
+}
+
+/// Writes one recording file's books in full every `full_every` lines and as changes against
+/// the line before in between; 1 writes every book in full.
+pub struct BookEncoder {
+    full_every: u32,
+    since_full: u32,
+    /// Up and Down books of the previous line
+    last: Option<(Book, Book)>,
+}
+
+impl BookEncoder {
+    pub fn new(full_every: u32) -> Self {
+        Self { full_every, since_full: 0, last: None }
+    }
+
+    pub fn encode(&mut self, snapshot: &MarketSnapshot) -> RecordedLine {
+        let books = (book(&snapshot.up), book(&snapshot.down));
+        let mut line = RecordedLine { snapshot: snapshot.clone(), book_diff: None };
+        match &self.last {
+            Some((up, down)) if self.since_full + 1 < self.full_every => {
+                self.since_full += 1;
+                line.book_diff = Some(BookDiff {
+                    up: LevelChanges { bids: changes(&up.0, &books.0.0), asks: changes(&up.1, &books.0.1) },
+                    down: LevelChanges { bids: changes(&down.0, &books.1.0), asks: changes(&down.1, &books.1.1) },
+                });
+                for token in [&mut line.snapshot.up, &mut line.snapshot.down] {
+                    token.bids.clear();
+                    token.asks.clear();
+                }
+            }
+            _ => self.since_full = 0,
+        }
+        self.last = Some(books);
+        line
+    }
+}
+
+/// Rebuilds the full snapshots of one recording file from its lines, read in order.
+#[derive(Default)]
+pub struct BookDecoder {
+    last: Option<(Book, Book)>,
+}
+
+impl BookDecoder {
+    pub fn decode(&mut self, line: RecordedLine) -> Result<MarketSnapshot> {
+        let mut snapshot = line.snapshot;
+        if let Some(diff) = line.book_diff {
+            let Some((up, down)) = &self.last else {
+                bail!("book changes with no full book before them");
+            };
+            (snapshot.up.bids, snapshot.up.asks) = (applied(&up.0, &diff.up.bids, true), applied(&up.1, &diff.up.asks, false));
+            (snapshot.down.bids, snapshot.down.asks) = (applied(&down.0, &diff.down.bids, true), applied(&down.1, &diff.down.asks, false));
+        }
+        self.last = Some((book(&snapshot.up), book(&snapshot.down)));
+        Ok(snapshot)
+    }
+}
+
+/// Levels of `new` that aren't in `old` as they are, then the prices of `old` gone from `new` at size 0.
+fn changes(old: &[PriceLevel], new: &[PriceLevel]) -> Vec<PriceLevel> {
+    let mut changed: Vec<PriceLevel> = new.iter().filter(|l| !old.contains(l)).copied().collect();
+    changed.extend(old.iter().filter(|o| !new.iter().any(|l| l.price == o.price)).map(|o| PriceLevel { price: o.price, size: 0.0 }));
+    changed
+}
+
+/// `levels` with `changes` applied, best-first: highest price first for bids, lowest for asks.
+fn applied(levels: &[PriceLevel], changes: &[PriceLevel], bids: bool) -> Vec<PriceLevel> {
+    let mut levels: Vec<PriceLevel> = levels.iter().filter(|l| !changes.iter().any(|c| c.price == l.price)).copied().collect();
+    levels.extend(changes.iter().filter(|c| c.size > 0.0));
+    if bids {
+        levels.sort_by(|a, b| b.price.total_cmp(&a.price));
+    } else {
+        levels.sort_by(|a, b| a.price.total_cmp(&b.price));
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(timestamp_ms: i64, up_bids: &[(f64, f64)], up_asks: &[(f64, f64)]) -> MarketSnapshot {
+        let levels = |levels: &[(f64, f64)]| levels.iter().map(|&(price, size)| serde_json::json!({ "price": price, "size": size })).collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({
+            "timestamp_ms": timestamp_ms, "asset": "BTC", "condition_id": "0xa", "period_start": 0,
+            "up": { "token_id": "up", "price": 0.55, "bids": levels(up_bids), "asks": levels(up_asks) },
+            "down": { "token_id": "down", "price": 0.46, "bids": levels(&[(0.44, 50.0)]) },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn diffs_rebuild_the_recorded_books() {
+        let snapshots = [
+            snapshot(0, &[(0.54, 100.0), (0.53, 200.0)], &[(0.55, 80.0)]),
+            snapshot(1000, &[(0.54, 60.0), (0.53, 200.0)], &[(0.55, 80.0), (0.56, 10.0)]),
+            snapshot(2000, &[(0.53, 200.0), (0.52, 5.0)], &[(0.56, 10.0)]),
+            snapshot(3000, &[(0.53, 150.0)], &[]),
+        ];
+        let mut encoder = BookEncoder::new(3);
+        let lines: Vec<RecordedLine> = snapshots
+            .iter()
+            .map(|s| serde_json::from_str(&serde_json::to_string(&encoder.encode(s)).unwrap()).unwrap())
+            .collect();
+        let diffs: Vec<bool> = lines.iter().map(|l| l.book_diff.is_some()).collect();
+        assert_eq!(diffs, [false, true, true, false]);
+        assert_eq!(lines[1].book_diff.as_ref().unwrap().up.bids, vec![PriceLevel { price: 0.54, size: 60.0 }]);
+        assert!(lines[1].snapshot.up.asks.is_empty() && lines[1].book_diff.as_ref().unwrap().down == LevelChanges::default());
+
+        let mut decoder = BookDecoder::default();
+        for (line, expected) in lines.iter().zip(&snapshots) {
+            let decoded = decoder.decode(line.clone()).unwrap();
+            assert_eq!(decoded.up.bids, expected.up.bids);
+            assert_eq!(decoded.up.asks, expected.up.asks);
+            assert_eq!(decoded.down.bids, expected.down.bids);
+        }
+        assert!(BookDecoder::default().decode(lines[1].clone()).is_err());
+    }
+}
@@ -0,0 +1,324 @@
+use crate::api::PolymarketApi;
+use crate::config::{CalibrationConfig, Config, MarketDuration};
+use crate::feed::MarketFeed;
+use crate::models::MarketSnapshot;
+use crate::monitor::MarketMonitor;
+use crate::scheduler::PeriodScheduler;
+use crate::signals;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration, Instant};
+
+/// Name the calibration recorder runs under.
+pub const TASK: &str = "calibration";
+
+/// A checkpoint is read up to this long after it passes; later prices are from another time.
+const CHECKPOINT_GRACE_SECS: i64 = 30;
+const POLL_SECS: u64 = 2;
+const RESOLUTION_CHECK_SECS: u64 = 30;
+/// Periods still unresolved this long after they end are dropped.
+const GIVE_UP_SECS: i64 = 24 * 60 * 60;
+
+/// The market-implied Up probability of one resolved 15m period at the configured times
+/// before it ended, and how it resolved. One JSON line per period in `calibration.path`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalibrationRecord {
+    pub market: String,
+    pub condition_id: String,
+    pub period_timestamp: i64,
+    /// Seconds before the period end -> implied Up probability
+    pub checkpoints: BTreeMap<i64, f64>,
+    pub up_won: bool,
+}
+
+struct OpenPeriod {
+    record: CalibrationRecord,
+    up_token_id: String,
+}
+
+/// Periods being sampled or waiting on their resolution, by (asset, period start).
+struct Tracker {
+    checkpoints: Vec<i64>,
+    open: HashMap<(String, i64), OpenPeriod>,
+}
+
+impl Tracker {
+    fn new(checkpoints: Vec<i64>) -> Self {
+        Self { checkpoints, open: HashMap::new() }
+    }
+
+    /// Checkpoints of the period starting at `period_start` that are due at `now` and not read yet.
+    fn due(&self, asset: &str, period_start: i64, now: i64) -> Vec<i64> {
+        let end = period_start + MarketDuration::M15.nominal_secs();
+        let taken = self.open.get(&(asset.to_string(), period_start)).map(|p| &p.record.checkpoints);
+        self.checkpoints
+            .iter()
+            .copied()
+            .filter(|c| (end - c..end - c + CHECKPOINT_GRACE_SECS).contains(&now))
+            .filter(|c| !taken.is_some_and(|t| t.contains_key(c)))
+            .collect()
+    }
+
+    /// Notes the implied Up probability of `snapshot` for the `due` checkpoints.
+    fn observe(&mut self, snapshot: &MarketSnapshot, due: &[i64]) {
+        let mid = |t: &crate::models::TokenSnapshot| t.mid.unwrap_or(t.price);
+        let (up, _) = signals::implied_probabilities(mid(&snapshot.up), mid(&snapshot.down));
+        let period = self.open.entry((snapshot.asset.clone(), snapshot.period_start)).or_insert_with(|| OpenPeriod {
+            record: CalibrationRecord {
+                market: snapshot.asset.clone(),
+                condition_id: snapshot.condition_id.clone(),
+                period_timestamp: snapshot.period_start,
+                checkpoints: BTreeMap::new(),
+                up_won: false,
+            },
+            up_token_id: snapshot.up.token_id.clone(),
+        });
+        for c in due {
+            period.record.checkpoints.insert(*c, up);
+        }
+    }
+
+    /// Periods that ended before `now`, dropping those given up on.
+    fn ended(&mut self, now: i64) -> Vec<((String, i64), String, String)> {
+        let end = |start: i64| start + MarketDuration::M15.nominal_secs();
+        self.open.retain(|(asset, start), _| {
+            let keep = now - end(*start) < GIVE_UP_SECS;
+            if !keep {
+                log::warn!("Calibration: {} period {} still unresolved after a day — dropped", asset, start);
+            }
+            keep
+        });
+        self.open
+            .iter()
+            .filter(|((_, start), _)| end(*start) <= now)
+            .map(|(key, p)| (key.clone(), p.record.condition_id.clone(), p.up_token_id.clone()))
+            .collect()
+    }
+
+    fn finish(&mut self, key: &(String, i64), up_won: bool) -> Option<CalibrationRecord> {
+        let mut record = self.open.remove(key)?.record;
+        record.up_won = up_won;
+        Some(record)
+    }
+}
+
+fn append(path: &Path, record: &CalibrationRecord) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
+/// Records of a calibration file, skipping malformed lines.
+pub fn read_records(path: &Path) -> Result<Vec<CalibrationRecord>> {
+    let file = std::fs::File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(e) if !line.trim().is_empty() => log::warn!("Skipping malformed calibration line: {}", e),
+            Err(_) => {}
+        }
+    }
+    Ok(records)
+}
+
+/// Samples every asset's current 15m market at each checkpoint in `calibration.checkpoints_secs`
+/// and appends the period to `calibration.path` once it resolves, until shutdown.
+pub async fn run(api: Arc<PolymarketApi>, feed: Arc<MarketFeed>, config: Config, scheduler: Arc<PeriodScheduler>, mut shutdown: watch::Receiver<bool>) {
+    let cfg: CalibrationConfig = config.calibration.clone();
+    let assets = config.assets_for(MarketDuration::M15);
+    let monitor = MarketMonitor::new(Arc::clone(&api), feed, &config);
+    let mut tracker = Tracker::new(cfg.checkpoints_secs.clone());
+    let mut last_resolution_check = Instant::now();
+    eprintln!("🎯 Recording implied-probability calibration to {}", cfg.path.display());
+    while !*shutdown.borrow() {
+        let now = crate::clock::now();
+        for asset in &assets {
+            let Some(period_start) = scheduler.current(asset, MarketDuration::M15) else { continue };
+            let due = tracker.due(asset, period_start, now);
+            if due.is_empty() {
+                continue;
+            }
+            match monitor.snapshot(asset, period_start).await {
+                Ok(snapshot) if snapshot.invalid.is_none() => tracker.observe(&snapshot, &due),
+                Ok(_) => {}
+                Err(e) => log::debug!("Calibration: no {} snapshot for period {}: {}", asset, period_start, e),
+            }
+        }
+
+        if last_resolution_check.elapsed() >= Duration::from_secs(RESOLUTION_CHECK_SECS) {
+            last_resolution_check = Instant::now();
+            for (key, condition_id, up_token_id) in tracker.ended(now) {
+                let Some(up_won) = up_won(&api, &condition_id, &up_token_id).await else { continue };
+                if let Some(record) = tracker.finish(&key, up_won) {
+                    if let Err(e) = append(&cfg.path, &record) {
+                        log::warn!("Failed to write calibration record: {}", e);
+                    }
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = sleep(Duration::from_secs(POLL_SECS)) => {}
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+/// Whether the Up token of `condition_id` won; `None` until the market reports a winner.
+pub async fn up_won(api: &PolymarketApi, condition_id: &str, up_token_id: &str) -> Option<bool> {
+    let market = match api.get_market(condition_id).await {
+        Ok(market) => market,
+        Err(e) => {
+            log::debug!("Failed to check {} for resolution: {}", condition_id, e);
+            return None;
+        }
+    };
+    market
+        .tokens
+        .iter()
+        .any(|t| t.winner)
+        .then(|| market.tokens.iter().any(|t| t.token_id == up_token_id && t.winner))
+}
+
+/// Periods whose favourite (the outcome priced at or above 0.5) was implied within one range.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Bin {
+    pub lo: f64,
+    pub hi: f64,
+    pub periods: usize,
+    pub implied_sum: f64,
+    pub wins: usize,
+}
+
+impl Bin {
+    fn add(&mut self, implied: f64, won: bool) {
+        self.periods += 1;
+        self.implied_sum += implied;
+        self.wins += won as usize;
+    }
+
+    pub fn implied(&self) -> f64 {
+        self.implied_sum / self.periods as f64
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.periods as f64
+    }
+}
+
+/// Calibration of the favourite's implied probability at one checkpoint.
+#[derive(Debug, Clone)]
+pub struct CheckpointCurve {
+    pub secs_before_end: i64,
+    pub bins: Vec<Bin>,
+    pub all: Bin,
+    /// Mean squared error of the implied Up probability against the outcome
+    pub brier: f64,
+}
+
+/// Calibration curves per checkpoint, favourites binned by implied probability into `bins`
+/// equal ranges over [0.5, 1]. A favourite that wins more often than priced was worth buying.
+pub fn curves(records: &[CalibrationRecord], bins: usize) -> Vec<CheckpointCurve> {
+    let bins = bins.max(1);
+    let mut by_checkpoint: BTreeMap<i64, CheckpointCurve> = BTreeMap::new();
+    for record in records {
+        for (&secs, &up) in &record.checkpoints {
+            let curve = by_checkpoint.entry(secs).or_insert_with(|| CheckpointCurve {
+                secs_before_end: secs,
+                bins: (0..bins)
+                    .map(|i| Bin { lo: 0.5 + 0.5 * i as f64 / bins as f64, hi: 0.5 + 0.5 * (i + 1) as f64 / bins as f64, ..Bin::default() })
+                    .collect(),
+                all: Bin { lo: 0.5, hi: 1.0, ..Bin::default() },
+                brier: 0.0,
+            });
+            let (implied, won) = if up >= 0.5 { (up, record.up_won) } else { (1.0 - up, !record.up_won) };
+            let i = (((implied - 0.5) / 0.5 * bins as f64) as usize).min(bins - 1);
+            curve.bins[i].add(implied, won);
+            curve.all.add(implied, won);
+            curve.brier += (up - if record.up_won { 1.0 } else { 0.0 }).powi(2);
+        }
+    }
+    let mut curves: Vec<CheckpointCurve> = by_checkpoint.into_values().rev().collect();
+    for curve in &mut curves {
+        curve.brier /= curve.all.periods as f64;
+    }
+    curves
+}
+
+pub fn print(curves: &[CheckpointCurve], periods: usize) {
+    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    eprintln!("🎯 Implied-probability calibration ({} resolved period(s))", periods);
+    for curve in curves {
+        let all = &curve.all;
+        eprintln!(
+            "   {}s before the end: favourite priced {:.3}, won {:.3} (edge {:+.3}) | Brier {:.4}",
+            curve.secs_before_end, all.implied(), all.win_rate(), all.win_rate() - all.implied(), curve.brier
+        );
+        eprintln!("   {:>11} {:>8} {:>8} {:>6} {:>7}", "favourite", "periods", "implied", "won", "edge");
+        for bin in curve.bins.iter().filter(|b| b.periods > 0) {
+            eprintln!(
+                "   {:.2}–{:.2}   {:>8} {:>8.3} {:>6.3} {:>+7.3}",
+                bin.lo, bin.hi, bin.periods, bin.implied(), bin.win_rate(), bin.win_rate() - bin.implied()
+            );
+        }
+    }
+    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenSnapshot;
+
+    fn token(id: &str, mid: f64) -> TokenSnapshot {
+        serde_json::from_value(serde_json::json!({ "token_id": id, "price": mid, "mid": mid })).unwrap()
+    }
+
+    #[test]
+    fn samples_checkpoints_once_and_bins_the_favourite() {
+        let mut tracker = Tracker::new(vec![300, 60]);
+        let start = 900 * 1000;
+        let end = start + 900;
+        assert!(tracker.due("BTC", start, end - 301).is_empty());
+        assert_eq!(tracker.due("BTC", start, end - 300), [300]);
+        let snapshot = |up: f64| MarketSnapshot {
+            timestamp_ms: 0,
+            asset: "BTC".to_string(),
+            condition_id: "0xc".to_string(),
+            period_start: start,
+            up: token("up", up),
+            down: token("down", 1.0 - up),
+            volume_24h: None,
+            invalid: None,
+        };
+        tracker.observe(&snapshot(0.62), &[300]);
+        assert!(tracker.due("BTC", start, end - 290).is_empty());
+        // Missed by more than the grace period: skipped rather than read late
+        assert!(tracker.due("BTC", start, end - 60 + CHECKPOINT_GRACE_SECS).is_empty());
+        tracker.observe(&snapshot(0.2), &[60]);
+        assert!(tracker.ended(end - 1).is_empty());
+        let (key, condition_id, up_token) = tracker.ended(end).remove(0);
+        assert_eq!((condition_id.as_str(), up_token.as_str()), ("0xc", "up"));
+        let record = tracker.finish(&key, false).unwrap();
+        assert_eq!(record.checkpoints, BTreeMap::from([(60, 0.2), (300, 0.62)]));
+
+        let curves = curves(&[record], 5);
+        // Up favoured at 0.62 five minutes out and lost; Down favoured at 0.8 a minute out and won
+        assert_eq!(curves.iter().map(|c| c.secs_before_end).collect::<Vec<_>>(), [300, 60]);
+        assert_eq!((curves[0].bins[1].periods, curves[0].all.wins), (1, 0));
+        assert_eq!((curves[1].bins[3].periods, curves[1].all.wins), (1, 1));
+        assert!((curves[1].brier - 0.04).abs() < 1e-9);
+    }
+}
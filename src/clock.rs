@@ -0,0 +1,112 @@
+use crate::api::PolymarketApi;
+use crate::config::ClockConfig;
+use crate::metrics::metrics;
+use anyhow::Result;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::time::{sleep, Duration};
+
+/// `/time` requests per check; the one with the shortest round trip is used.
+const SAMPLES: usize = 3;
+
+/// Milliseconds added to the local clock to get the exchange's. Zero unless the last check
+/// found more than `clock.warn_ms` of skew and `clock.correct` is on.
+static OFFSET_MS: AtomicI64 = AtomicI64::new(0);
+
+/// Recorded time a replay has reached, in Unix milliseconds; 0 outside replays.
+#[cfg(not(test))]
+static REPLAY_MS: AtomicI64 = AtomicI64::new(0);
+
+// Tests run in parallel threads; each has its own replay clock so a replay test doesn't move
+// time under the others.
+#[cfg(test)]
+thread_local! {
+    static REPLAY_MS: AtomicI64 = const { AtomicI64::new(0) };
+}
+
+#[cfg(not(test))]
+fn replay_ms() -> i64 {
+    REPLAY_MS.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+fn replay_ms() -> i64 {
+    REPLAY_MS.with(|ms| ms.load(Ordering::Relaxed))
+}
+
+/// Unix seconds on the exchange's clock. Period boundaries, time remaining, ledger and snapshot
+/// timestamps and request signatures use this rather than the system clock.
+pub fn now() -> i64 {
+    now_ms().div_euclid(1000)
+}
+
+pub fn now_ms() -> i64 {
+    match replay_ms() {
+        0 => chrono::Utc::now().timestamp_millis() + OFFSET_MS.load(Ordering::Relaxed),
+        replay => replay,
+    }
+}
+
+/// Stops the clock at `ms` (Unix milliseconds) for a replay, which moves it forward tick by
+/// tick; `now` then returns recorded time however long each tick takes to run.
+pub fn set_replay(ms: i64) {
+    #[cfg(not(test))]
+    REPLAY_MS.store(ms, Ordering::Relaxed);
+    #[cfg(test)]
+    REPLAY_MS.with(|replay| replay.store(ms, Ordering::Relaxed));
+}
+
+/// Exchange clock minus local clock, in milliseconds. `/time` reports whole seconds, so the
+/// estimate takes the server to be halfway through that second at the midpoint of the request:
+/// good to about ±0.5s plus half the round trip.
+pub async fn measure_skew(api: &PolymarketApi) -> Result<i64> {
+    let mut best: Option<(Duration, i64)> = None;
+    for _ in 0..SAMPLES {
+        let sent_ms = chrono::Utc::now().timestamp_millis();
+        let sent = Instant::now();
+        let server = api.get_server_time().await?;
+        let rtt = sent.elapsed();
+        let skew = server * 1000 + 500 - (sent_ms + rtt.as_millis() as i64 / 2);
+        if best.is_none_or(|(fastest, _)| rtt < fastest) {
+            best = Some((rtt, skew));
+        }
+    }
+    Ok(best.map_or(0, |(_, skew)| skew))
+}
+
+/// Measures the skew, warns when it exceeds `warn_ms` and, with `correct`, offsets `now` by
+/// it. Skew within `warn_ms` is treated as measurement noise and clears the offset.
+pub async fn check(api: &PolymarketApi, config: &ClockConfig) {
+    let skew = match measure_skew(api).await {
+        Ok(skew) => skew,
+        Err(e) => {
+            log::warn!("⚠️ Clock check against the exchange failed: {}", e);
+            return;
+        }
+    };
+    metrics().set_clock_skew(skew as f64 / 1000.0);
+    let offset = if skew.abs() > config.warn_ms as i64 {
+        log::warn!(
+            "⏰ Local clock is {:.1}s {} the exchange{}",
+            skew.abs() as f64 / 1000.0,
+            if skew > 0 { "behind" } else { "ahead of" },
+            if config.correct { " — offsetting period math to match" } else { "" }
+        );
+        if config.correct { skew } else { 0 }
+    } else {
+        log::debug!("Clock skew {}ms is within {}ms", skew, config.warn_ms);
+        0
+    };
+    if OFFSET_MS.swap(offset, Ordering::Relaxed) != offset && offset == 0 {
+        log::info!("⏰ Local clock is back in step with the exchange; offset cleared");
+    }
+}
+
+/// Re-checks the clock every `interval_mins`; runs until the process exits.
+pub async fn run(api: Arc<PolymarketApi>, config: ClockConfig) {
+    loop {
+        sleep(Duration::from_secs(config.interval_mins * 60)).await;
+        check(&api, &config).await;
+    }
+}
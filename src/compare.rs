@@ -0,0 +1,161 @@
+use crate::backtest::BacktestReport;
+use chrono::TimeZone;
+use chrono_tz::America::New_York;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One period's PnL in two backtest results; `None` where that run didn't trade it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeriodDelta {
+    pub asset: String,
+    pub period_start: i64,
+    pub baseline: Option<f64>,
+    pub candidate: Option<f64>,
+}
+
+impl PeriodDelta {
+    /// Candidate PnL less baseline PnL, an untraded period counting as 0.
+    pub fn delta(&self) -> f64 {
+        self.candidate.unwrap_or(0.0) - self.baseline.unwrap_or(0.0)
+    }
+}
+
+/// Two backtest results side by side, matched period by period.
+pub struct Comparison {
+    pub baseline: BacktestReport,
+    pub candidate: BacktestReport,
+    /// Every period either run traded, by period start then asset
+    pub periods: Vec<PeriodDelta>,
+}
+
+impl Comparison {
+    pub fn new(baseline: BacktestReport, candidate: BacktestReport) -> Self {
+        let mut periods: BTreeMap<(i64, String), PeriodDelta> = BTreeMap::new();
+        for (report, is_candidate) in [(&baseline, false), (&candidate, true)] {
+            for p in &report.periods {
+                let entry = periods.entry((p.period_start, p.asset.clone())).or_insert_with(|| PeriodDelta {
+                    asset: p.asset.clone(),
+                    period_start: p.period_start,
+                    baseline: None,
+                    candidate: None,
+                });
+                let pnl = if is_candidate { &mut entry.candidate } else { &mut entry.baseline };
+                *pnl.get_or_insert(0.0) += p.pnl;
+            }
+        }
+        Self { baseline, candidate, periods: periods.into_values().collect() }
+    }
+
+    /// Up to `n` periods whose PnL changed, largest change first.
+    pub fn largest(&self, n: usize) -> Vec<&PeriodDelta> {
+        let mut changed: Vec<&PeriodDelta> = self.periods.iter().filter(|p| p.delta().abs() >= 0.005).collect();
+        changed.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+        changed.truncate(n);
+        changed
+    }
+
+    /// Prints the aggregate stats and the `top` most divergent periods; with `every_period`, the
+    /// delta of every period in time order too.
+    pub fn print(&self, baseline_name: &str, candidate_name: &str, top: usize, every_period: bool) {
+        let (a, b) = (&self.baseline, &self.candidate);
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("🔀 Backtest comparison");
+        eprintln!("   baseline   {}", baseline_name);
+        eprintln!("   candidate  {}", candidate_name);
+        eprintln!("   {:<21} {:>10} {:>10} {:>10}", "", "baseline", "candidate", "delta");
+        let count = |label: &str, a: usize, b: usize| {
+            eprintln!("   {:<21} {:>10} {:>10} {:>+10}", label, a, b, b as i64 - a as i64);
+        };
+        let usd = |label: &str, a: f64, b: f64| {
+            eprintln!("   {:<21} {:>10.2} {:>10.2} {:>+10.2}", label, a, b, b - a);
+        };
+        count("periods traded", a.periods_traded, b.periods_traded);
+        count("both sides filled", a.both_filled, b.both_filled);
+        count("one-side exits", a.one_side_exits, b.one_side_exits);
+        eprintln!(
+            "   {:<21} {:>9.1}% {:>9.1}% {:>+8.1}pp",
+            "win rate",
+            a.win_rate() * 100.0,
+            b.win_rate() * 100.0,
+            (b.win_rate() - a.win_rate()) * 100.0
+        );
+        usd("total PnL ($)", a.total_pnl, b.total_pnl);
+        usd("max drawdown ($)", a.max_drawdown, b.max_drawdown);
+        let assets: BTreeSet<&String> = a.pnl_by_asset.keys().chain(b.pnl_by_asset.keys()).collect();
+        for asset in assets {
+            let pnl = |r: &BacktestReport| r.pnl_by_asset.get(asset).copied().unwrap_or(0.0);
+            usd(&format!("{} PnL ($)", asset), pnl(a), pnl(b));
+        }
+
+        let both = self.periods.iter().filter(|p| p.baseline.is_some() && p.candidate.is_some()).count();
+        let only_baseline = self.periods.iter().filter(|p| p.candidate.is_none()).count();
+        let only_candidate = self.periods.iter().filter(|p| p.baseline.is_none()).count();
+        let better = self.periods.iter().filter(|p| p.delta() >= 0.005).count();
+        let worse = self.periods.iter().filter(|p| p.delta() <= -0.005).count();
+        eprintln!("   periods in both        {} ({} baseline only, {} candidate only)", both, only_baseline, only_candidate);
+        eprintln!("   better / worse / same  {} / {} / {}", better, worse, self.periods.len() - better - worse);
+
+        if every_period && !self.periods.is_empty() {
+            eprintln!("🗓  Per-period PnL");
+            print_periods(self.periods.iter());
+        }
+        let largest = self.largest(top);
+        if !largest.is_empty() {
+            eprintln!("📐 Largest divergences");
+            print_periods(largest.into_iter());
+        }
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+}
+
+fn print_periods<'a>(periods: impl Iterator<Item = &'a PeriodDelta>) {
+    eprintln!("   {:<20} {:<5} {:>10} {:>10} {:>10}", "period (ET)", "asset", "baseline", "candidate", "delta");
+    let pnl = |pnl: Option<f64>| pnl.map(|v| format!("{:.2}", v)).unwrap_or_else(|| "-".to_string());
+    for p in periods {
+        eprintln!(
+            "   {:<20} {:<5} {:>10} {:>10} {:>+10.2}",
+            period_et(p.period_start),
+            p.asset,
+            pnl(p.baseline),
+            pnl(p.candidate),
+            p.delta()
+        );
+    }
+}
+
+pub fn period_et(period_start: i64) -> String {
+    New_York
+        .timestamp_opt(period_start, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| period_start.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::PeriodResult;
+
+    fn report(periods: &[(&str, i64, f64)]) -> BacktestReport {
+        let periods: Vec<PeriodResult> = periods
+            .iter()
+            .map(|&(asset, period_start, pnl)| PeriodResult { asset: asset.to_string(), period_start, pnl, both_filled: true, exited: false })
+            .collect();
+        BacktestReport { periods_traded: periods.len(), total_pnl: periods.iter().map(|p| p.pnl).sum(), periods, ..Default::default() }
+    }
+
+    #[test]
+    fn periods_are_matched_and_ranked_by_divergence() {
+        let baseline = report(&[("BTC", 900, 1.0), ("ETH", 900, -2.0), ("BTC", 1800, 0.5)]);
+        let candidate = report(&[("BTC", 900, 1.0), ("ETH", 900, 0.25), ("BTC", 2700, -4.0)]);
+        // Result files round-trip
+        let candidate: BacktestReport = serde_json::from_str(&serde_json::to_string(&candidate).unwrap()).unwrap();
+        let comparison = Comparison::new(baseline, candidate);
+
+        let keys: Vec<(i64, &str)> = comparison.periods.iter().map(|p| (p.period_start, p.asset.as_str())).collect();
+        assert_eq!(keys, [(900, "BTC"), (900, "ETH"), (1800, "BTC"), (2700, "BTC")]);
+        let largest: Vec<(i64, f64)> = comparison.largest(2).iter().map(|p| (p.period_start, p.delta())).collect();
+        assert_eq!(largest, [(2700, -4.0), (900, 2.25)]);
+        assert_eq!(comparison.largest(10).len(), 3);
+        assert_eq!(comparison.periods[2].candidate, None);
+    }
+}
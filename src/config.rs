@@ -1,6 +1,8 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -8,58 +10,1533 @@ pub struct Args {
     #[arg(short, long, default_value = "config.json")]
     pub config: PathBuf,
 
+    /// Overrides layered over the config, in order: a name reads `<config>.<name>.json` next to
+    /// the config (e.g. config.aggressive.json), a path ending in .json reads that file
+    #[arg(long = "profile", value_name = "PROFILE", value_delimiter = ',')]
+    pub profiles: Vec<String>,
+
     #[arg(long)]
     pub redeem: bool,
 
-    #[arg(long, requires = "redeem")]
-    pub condition_id: Option<String>,
+    #[arg(long, requires = "redeem")]
+    pub condition_id: Option<String>,
+
+    /// Clear a tripped daily-loss kill switch so the running bot resumes trading
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Show a live terminal dashboard instead of the scrolling log
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Read the passphrase of `private_key_file` from this file descriptor instead of asking
+    #[arg(long, value_name = "FD")]
+    pub passphrase_fd: Option<i32>,
+
+    /// Rehearse live trading: authenticate and sign real orders, but log them instead of posting
+    #[arg(long, conflicts_with = "redeem")]
+    pub paper_live: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Trade ledger tools
+    Ledger {
+        #[command(subcommand)]
+        action: LedgerCommand,
+    },
+    /// Replay recorded market snapshots through the strategy using the loaded config
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Backtest {
+        #[command(subcommand)]
+        action: Option<BacktestCommand>,
+        /// Snapshot files or directories (NDJSON)
+        #[arg(long, required = true, num_args = 1..)]
+        data: Vec<PathBuf>,
+        /// Price concession on sells when a snapshot has no book depth (overrides strategy.fill_model.fallback_slippage)
+        #[arg(long)]
+        slippage: Option<f64>,
+        /// Write the result, with every traded period's PnL, to this JSON file for `backtest compare`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Run the bot in simulation over recorded snapshots on recorded time, deterministically,
+    /// and optionally check its decisions against a golden ledger
+    Replay {
+        /// Snapshot files or directories (NDJSON)
+        #[arg(long, required = true, num_args = 1..)]
+        data: Vec<PathBuf>,
+        /// Ledger file the replay's decisions are written to (replaced on each run)
+        #[arg(short, long, default_value = "replay.jsonl")]
+        output: PathBuf,
+        /// Fail unless the decisions match this ledger file
+        #[arg(long)]
+        golden: Option<PathBuf>,
+    },
+    /// Backtest a grid (or random sample) of strategy parameter combinations
+    Optimize {
+        /// Snapshot files or directories (NDJSON)
+        #[arg(long, required = true, num_args = 1..)]
+        data: Vec<PathBuf>,
+        /// Parameter to sweep as name=v1,v2,... or name=start:end:step (repeatable),
+        /// e.g. --param price_limit=0.40:0.48:0.02 --param shares=5,10
+        #[arg(long = "param", required = true)]
+        params: Vec<String>,
+        /// Random search: sample this many combinations instead of the full grid
+        #[arg(long)]
+        random: Option<usize>,
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+        /// Overrides strategy.fill_model.fallback_slippage
+        #[arg(long)]
+        slippage: Option<f64>,
+        /// Number of best combinations to print
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Write every combination's results to this CSV file (each fold's with --walk-forward)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Walk-forward analysis: split the data into this many consecutive windows, optimize on
+        /// each and evaluate the best combination on the next
+        #[arg(long, value_name = "WINDOWS")]
+        walk_forward: Option<usize>,
+    },
+    /// Bootstrap resolved periods from the trade ledger into PnL, drawdown and risk-of-ruin estimates
+    MonteCarlo {
+        #[arg(long, default_value_t = 10_000)]
+        runs: usize,
+        /// Periods per simulated path (default: number of resolved periods in the ledger)
+        #[arg(long)]
+        periods: Option<usize>,
+        /// Loss in USD that counts as ruin
+        #[arg(long, default_value_t = 100.0)]
+        bankroll: f64,
+        #[arg(long, default_value_t = 42)]
+        seed: u64,
+    },
+    /// Calibration curve of the market-implied probabilities recorded with `calibration.enabled`
+    Calibration {
+        /// Calibration file to read (default: calibration.path)
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Equal ranges the favourite's implied probability (0.5 to 1) is split into
+        #[arg(long, default_value_t = 10)]
+        bins: usize,
+    },
+    /// Historical market data tools
+    History {
+        #[command(subcommand)]
+        action: HistoryCommand,
+    },
+    /// Print positions, PnL, timers and recent errors from the running bot
+    Status,
+    /// Compare the proxy wallet's positions with the trade ledger and suggest corrections
+    Reconcile {
+        /// Account from `accounts` to check (default: every account)
+        #[arg(long)]
+        account: Option<String>,
+        /// Append adjustment rows so the ledger matches the wallet
+        #[arg(long)]
+        apply: bool,
+    },
+    /// Encrypt a wallet private key into a passphrase-protected file for `private_key_file`
+    EncryptKey {
+        /// Key file to create
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Encrypt the config's private_key (of `--account` if given) instead of asking for one
+        #[arg(long)]
+        from_config: bool,
+        #[arg(long, requires = "from_config")]
+        account: Option<String>,
+    },
+    /// Stop the running bot from placing new orders on one market (positions are still managed)
+    Pause {
+        /// Asset ticker, e.g. BTC
+        asset: String,
+        #[arg(long, value_enum, default_value_t = MarketDuration::M15)]
+        timeframe: MarketDuration,
+    },
+    /// Let the running bot place orders on a paused market again
+    Resume {
+        asset: String,
+        #[arg(long, value_enum, default_value_t = MarketDuration::M15)]
+        timeframe: MarketDuration,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HistoryCommand {
+    /// Download past markets' Up/Down price series from the CLOB as snapshot files for backtesting
+    Fetch {
+        /// Assets to fetch (comma separated); defaults to every configured market with this timeframe
+        #[arg(long, value_delimiter = ',')]
+        assets: Vec<String>,
+        #[arg(long, value_enum, default_value_t = MarketDuration::M15)]
+        market: MarketDuration,
+        /// Number of closed periods to fetch per asset, counting back from the current one
+        #[arg(long, default_value_t = 96)]
+        periods: u32,
+        /// prices-history window: 1h, 6h, 1d, 1w, 1m or max
+        #[arg(long, default_value = "max")]
+        interval: String,
+        #[arg(short, long, default_value = "snapshots")]
+        output: PathBuf,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum MarketDuration {
+    #[value(name = "5m")]
+    #[serde(rename = "5m")]
+    M5,
+    #[value(name = "15m")]
+    #[serde(rename = "15m")]
+    M15,
+    #[value(name = "1h")]
+    #[serde(rename = "1h")]
+    H1,
+    #[value(name = "4h")]
+    #[serde(rename = "4h")]
+    H4,
+    /// Noon-to-noon ET markets named after the day they resolve
+    #[value(name = "1d")]
+    #[serde(rename = "1d")]
+    D1,
+}
+
+impl MarketDuration {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MarketDuration::M5 => "5m",
+            MarketDuration::M15 => "15m",
+            MarketDuration::H1 => "1h",
+            MarketDuration::H4 => "4h",
+            MarketDuration::D1 => "1d",
+        }
+    }
+
+    /// Usual period length; daily periods are an hour shorter or longer across DST changes.
+    pub fn nominal_secs(&self) -> i64 {
+        match self {
+            MarketDuration::M5 => 5 * 60,
+            MarketDuration::M15 => 15 * 60,
+            MarketDuration::H1 => 60 * 60,
+            MarketDuration::H4 => 4 * 60 * 60,
+            MarketDuration::D1 => 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BacktestCommand {
+    /// Compare two backtest result files (from `backtest --output`): aggregate stats side by
+    /// side, per-period PnL deltas and the periods that diverge most
+    Compare {
+        /// Result file of the reference run
+        baseline: PathBuf,
+        /// Result file of the run being evaluated
+        candidate: PathBuf,
+        /// Number of most divergent periods to print
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Also print every period's PnL in both runs, in time order
+        #[arg(long)]
+        periods: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LedgerCommand {
+    /// Export the trade ledger for analysis in pandas/Excel
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Per-period PnL, switch-interval and sizing statistics of the bot's own trades
+    Analyze {
+        #[command(flatten)]
+        rows: LedgerRows,
+    },
+    /// Realized PnL attributed to each strategy rule, with the setting that turns it off
+    Rules {
+        #[command(flatten)]
+        rows: LedgerRows,
+    },
+}
+
+/// Which ledger rows a `ledger` analysis reads.
+#[derive(clap::Args, Debug)]
+pub struct LedgerRows {
+    /// Only rows of this account (see `accounts`)
+    #[arg(long)]
+    pub account: Option<String>,
+    /// Ledger files or directories to read (default: ledger.path)
+    #[arg(long = "ledger", num_args = 1..)]
+    pub ledgers: Vec<PathBuf>,
+    /// File names to read from directories given to --ledger (`*` and `?` wildcards)
+    #[arg(long, default_value = "*.jsonl")]
+    pub pattern: String,
+    /// First row to include: RFC 3339, or YYYY-MM-DD[ HH:MM[:SS]] in profit.timezone
+    #[arg(long)]
+    pub since: Option<String>,
+    /// Rows from this time on are left out; same formats as --since
+    #[arg(long)]
+    pub until: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum ExportFormat {
+    Csv,
+    Parquet,
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub polymarket: PolymarketConfig,
+    pub strategy: StrategyConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub feed: FeedConfig,
+    #[serde(default)]
+    pub clock: ClockConfig,
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    #[serde(default)]
+    pub instance: InstanceConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub ledger: LedgerConfig,
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+    /// Up/Down markets to trade and record; defaults to BTC, ETH, SOL and XRP
+    #[serde(default = "default_markets")]
+    pub markets: Vec<MarketConfig>,
+    #[serde(default)]
+    pub discovery: DiscoveryConfig,
+    #[serde(default)]
+    pub control: ControlConfig,
+    #[serde(default)]
+    pub profit: ProfitConfig,
+    #[serde(default)]
+    pub report: ReportConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    #[serde(default)]
+    pub calibration: CalibrationConfig,
+    #[serde(default)]
+    pub dataset: DatasetConfig,
+    /// Trading accounts. Empty trades with the `polymarket` credentials; otherwise each account
+    /// runs its own copy of the strategy on the markets routed to it.
+    #[serde(default)]
+    pub accounts: Vec<AccountConfig>,
+}
+
+/// One set of trading credentials and the share of the markets it trades. API URLs still come
+/// from `polymarket`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountConfig {
+    /// Label used in logs, status, metrics and ledger rows
+    pub name: String,
+    pub api_key: Option<String>,
+    pub api_secret: Option<String>,
+    pub api_passphrase: Option<String>,
+    pub private_key: Option<String>,
+    /// Key file from `encrypt-key`, decrypted at startup in place of `private_key`
+    #[serde(default)]
+    pub private_key_file: Option<PathBuf>,
+    /// Signing service used instead of a private key (see `polymarket.remote_signer`)
+    #[serde(default)]
+    pub remote_signer: Option<RemoteSignerConfig>,
+    pub proxy_wallet_address: Option<String>,
+    pub signature_type: Option<u8>,
+    /// Assets this account trades (default: every configured market)
+    #[serde(default)]
+    pub assets: Vec<String>,
+    /// Fraction of `strategy.shares` this account places on each of its markets
+    #[serde(default = "default_size_fraction")]
+    pub size_fraction: f64,
+    /// Cap on authenticated requests (orders, cancels, redemptions, balance) per second
+    #[serde(default)]
+    pub max_requests_per_sec: Option<f64>,
+    /// Exchange nonce for this wallet's orders (see `polymarket.order_nonce`)
+    #[serde(default)]
+    pub order_nonce: u64,
+}
+
+/// Realized PnL windows summarized in the log and ledger when they close.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfitConfig {
+    /// Windows that get a rollup log line and ledger entry at each boundary
+    #[serde(default = "default_profit_rollups")]
+    pub rollups: Vec<ProfitWindow>,
+    /// IANA timezone that hourly and daily windows follow
+    #[serde(default = "default_profit_timezone")]
+    pub timezone: String,
+}
+
+impl Default for ProfitConfig {
+    fn default() -> Self {
+        Self {
+            rollups: default_profit_rollups(),
+            timezone: default_profit_timezone(),
+        }
+    }
+}
+
+impl ProfitConfig {
+    /// The configured timezone; validation rejects names chrono-tz doesn't know.
+    pub fn tz(&self) -> chrono_tz::Tz {
+        self.timezone.parse().unwrap_or(chrono_tz::America::New_York)
+    }
+}
+
+/// Reports written from the ledger when a profit window closes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_report_dir")]
+    pub dir: PathBuf,
+    #[serde(default)]
+    pub format: ReportFormat,
+    /// Windows that get a report when they close
+    #[serde(default = "default_report_windows")]
+    pub windows: Vec<ProfitWindow>,
+}
+
+impl Default for ReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_report_dir(),
+            format: ReportFormat::default(),
+            windows: default_report_windows(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+/// Implied-probability calibration: each 15m period's implied Up probability at fixed times
+/// before it ends, recorded with how it resolved for the `calibration` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalibrationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// JSON-lines file the resolved periods are appended to
+    #[serde(default = "default_calibration_path")]
+    pub path: PathBuf,
+    /// Seconds before the period end at which the market is sampled
+    #[serde(default = "default_calibration_checkpoints")]
+    pub checkpoints_secs: Vec<i64>,
+}
+
+impl Default for CalibrationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_calibration_path(),
+            checkpoints_secs: default_calibration_checkpoints(),
+        }
+    }
+}
+
+/// Feature dataset: a feature vector of each 15m market at fixed times into the period,
+/// labeled with how the period resolved, as training data for future strategies.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// JSON-lines file the labeled rows are appended to
+    #[serde(default = "default_dataset_path")]
+    pub path: PathBuf,
+    /// Seconds into the period at which a row is taken
+    #[serde(default = "default_dataset_offsets")]
+    pub offsets_secs: Vec<i64>,
+    /// Seconds between the price samples the trend and volatility features are read from
+    #[serde(default = "default_dataset_sample_secs")]
+    pub sample_secs: u64,
+    /// Add the underlying's spot candle progress, from `strategy.signal.momentum.spot_url`
+    #[serde(default)]
+    pub spot: bool,
+}
+
+impl Default for DatasetConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_dataset_path(),
+            offsets_secs: default_dataset_offsets(),
+            sample_secs: default_dataset_sample_secs(),
+            spot: false,
+        }
+    }
+}
+
+/// How amounts are written in logs, the status summary, reports and the TUI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisplayConfig {
+    /// Decimals of dollar amounts (balances, costs, PnL)
+    #[serde(default = "default_usd_decimals")]
+    pub usd_decimals: usize,
+    /// Decimals of share prices
+    #[serde(default = "default_price_decimals")]
+    pub price_decimals: usize,
+    /// Decimals of share counts
+    #[serde(default = "default_share_decimals")]
+    pub share_decimals: usize,
+    /// Position sizes as share counts, or as their dollar cost
+    #[serde(default)]
+    pub sizes: SizeUnits,
+    /// PnL in dollars, or per share of the position it was made on
+    #[serde(default)]
+    pub pnl: PnlUnits,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            usd_decimals: default_usd_decimals(),
+            price_decimals: default_price_decimals(),
+            share_decimals: default_share_decimals(),
+            sizes: SizeUnits::default(),
+            pnl: PnlUnits::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnits {
+    #[default]
+    Shares,
+    Usd,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PnlUnits {
+    #[default]
+    Usd,
+    PerShare,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfitWindow {
+    /// The 15m market period
+    Period,
+    Hourly,
+    Daily,
+}
+
+impl ProfitWindow {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProfitWindow::Period => "period",
+            ProfitWindow::Hourly => "hourly",
+            ProfitWindow::Daily => "daily",
+        }
+    }
+}
+
+/// How the market for an asset and period is found when it isn't pinned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveryConfig {
+    /// Look the market up among Gamma events ending with the period before building a slug
+    #[serde(default = "default_true")]
+    pub gamma_events: bool,
+    /// Gamma tag the recurring Up/Down events carry
+    #[serde(default = "default_discovery_tag_slug")]
+    pub tag_slug: String,
+    /// Seconds market metadata (token IDs, Gamma markets) is reused before being fetched again;
+    /// 0 fetches on every lookup
+    #[serde(default = "default_market_cache_secs")]
+    pub market_cache_secs: u64,
+    /// Wait before looking again for a market that wasn't found; doubles on every further miss
+    #[serde(default = "default_discovery_retry_secs")]
+    pub retry_secs: u64,
+    /// Cap on the wait between lookups of a missing market
+    #[serde(default = "default_discovery_max_retry_secs")]
+    pub max_retry_secs: u64,
+    /// Alert when the current period's market still isn't found this long after it started
+    #[serde(default = "default_missing_alert_after_secs")]
+    pub alert_after_secs: u64,
+}
+
+fn default_market_cache_secs() -> u64 {
+    300
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            gamma_events: true,
+            tag_slug: default_discovery_tag_slug(),
+            market_cache_secs: default_market_cache_secs(),
+            retry_secs: default_discovery_retry_secs(),
+            max_retry_secs: default_discovery_max_retry_secs(),
+            alert_after_secs: default_missing_alert_after_secs(),
+        }
+    }
+}
+
+/// Placeholders a slug template may use.
+pub const SLUG_PLACEHOLDERS: &[&str] = &["asset", "name", "ts", "month", "day", "hour", "ampm", "end_month", "end_day"];
+
+/// Kind of recurring market an entry of `markets` is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketFamily {
+    /// "Up or Down" markets with Up and Down outcomes
+    #[default]
+    UpDown,
+    /// "<asset> above $X" markets with Yes and No outcomes, one per strike. The strike nearest
+    /// the money when the period is first looked up is traded for the whole period.
+    Threshold,
+    /// One pinned binary market of any kind (sports, politics), traded as a single period that
+    /// lasts until the market ends. The first outcome is traded as Up, the second as Down.
+    Event,
+}
+
+/// One asset's recurring Up/Down or threshold markets.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketConfig {
+    /// Ticker used in logs, snapshot paths and (lowercased) as `{asset}` in slugs. Two entries
+    /// for the same coin need different tickers (e.g. `BTC` and `BTC-ABOVE`).
+    pub asset: String,
+    /// Long name for `{name}` in slugs (e.g. "bitcoin"); defaults to the lowercased ticker
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Timeframes this asset is traded and recorded on. The trading loop runs 15m markets.
+    #[serde(default = "default_market_timeframes")]
+    pub timeframes: Vec<MarketDuration>,
+    /// Slug templates per timeframe, replacing the built-in formats
+    #[serde(default)]
+    pub slug_templates: BTreeMap<MarketDuration, String>,
+    /// Pin one market by condition ID, skipping slug discovery. It is used for the period it
+    /// ends in.
+    #[serde(default)]
+    pub condition_id: Option<String>,
+    /// Pin one market by slug, skipping slug construction
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub family: MarketFamily,
+    /// Gamma tag of this market's events, instead of `discovery.tag_slug`. Threshold markets
+    /// need one unless pinned.
+    #[serde(default)]
+    pub tag_slug: Option<String>,
+    /// Spot pair whose 15m candle the market follows, for `strategy.signal.momentum`
+    /// (default `<ASSET>USDT`)
+    #[serde(default)]
+    pub spot_symbol: Option<String>,
+}
+
+impl MarketConfig {
+    pub fn new(asset: &str, name: &str) -> Self {
+        Self {
+            asset: asset.to_string(),
+            name: Some(name.to_string()),
+            timeframes: default_market_timeframes(),
+            slug_templates: BTreeMap::new(),
+            condition_id: None,
+            slug: None,
+            family: MarketFamily::UpDown,
+            tag_slug: None,
+            spot_symbol: None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.asset.to_lowercase())
+    }
+
+    pub fn spot_symbol(&self) -> String {
+        self.spot_symbol.clone().unwrap_or_else(|| format!("{}USDT", self.asset.to_uppercase()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorConfig {
+    /// Write every market snapshot to `snapshot_dir` as gzip NDJSON, one file per market and period
+    #[serde(default)]
+    pub record_snapshots: bool,
+    #[serde(default = "default_snapshot_dir")]
+    pub snapshot_dir: PathBuf,
+    #[serde(default = "default_snapshot_interval_ms")]
+    pub snapshot_interval_ms: u64,
+    /// Recorded books are written in full every this many snapshots of a market and as changes
+    /// since the previous snapshot in between; 1 writes every book in full
+    #[serde(default = "default_full_book_every")]
+    pub full_book_every: u32,
+    /// Up and Down asks summing below this can't be a real book
+    #[serde(default = "default_min_ask_sum")]
+    pub min_ask_sum: f64,
+    /// Up and Down asks summing above this can't be a real book
+    #[serde(default = "default_max_ask_sum")]
+    pub max_ask_sum: f64,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            record_snapshots: false,
+            snapshot_dir: default_snapshot_dir(),
+            snapshot_interval_ms: default_snapshot_interval_ms(),
+            full_book_every: default_full_book_every(),
+            min_ask_sum: default_min_ask_sum(),
+            max_ask_sum: default_max_ask_sum(),
+        }
+    }
+}
+
+fn default_min_ask_sum() -> f64 { 0.9 }
+fn default_full_book_every() -> u32 { 1 }
+fn default_max_ask_sum() -> f64 { 1.2 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_ledger_path")]
+    pub path: PathBuf,
+}
+
+impl Default for LedgerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: default_ledger_path(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    /// JSON-lines log with per-market context for downstream analysis. Unset disables it.
+    #[serde(default)]
+    pub json_file: Option<PathBuf>,
+    /// With `--tui`, raw console output (status banners, resolutions) is written here
+    #[serde(default = "default_tui_console_file")]
+    pub tui_console_file: PathBuf,
+    /// Also write each market's log lines to `{market_dir}/{asset}-{timeframe}.log`. Unset
+    /// disables it.
+    #[serde(default)]
+    pub market_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub rotation: LogRotationConfig,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            json_file: None,
+            tui_console_file: default_tui_console_file(),
+            market_dir: None,
+            rotation: LogRotationConfig::default(),
+        }
+    }
+}
+
+/// Rotation of the JSON log and per-market logs. A rotated file is renamed after the time it was rotated
+/// (`bot_events.20261016T120000000.jsonl`), optionally gzipped, and the oldest beyond `keep` are
+/// deleted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogRotationConfig {
+    /// Rotate before the file grows past this many MB; 0 turns size rotation off
+    #[serde(default = "default_log_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Also rotate at the first write of each UTC day
+    #[serde(default)]
+    pub daily: bool,
+    /// Rotated files to keep; 0 keeps them all
+    #[serde(default = "default_log_keep")]
+    pub keep: usize,
+    /// Gzip rotated files
+    #[serde(default)]
+    pub gzip: bool,
+}
+
+impl Default for LogRotationConfig {
+    fn default() -> Self {
+        Self {
+            max_size_mb: default_log_max_size_mb(),
+            daily: false,
+            keep: default_log_keep(),
+            gzip: false,
+        }
+    }
+}
+
+/// Outbound HTTP settings for the Gamma, CLOB and Data API client.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Proxy for every API request: `http://`, `https://` or `socks5://` (credentials go in the URL)
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Whole-request timeout
+    #[serde(default = "default_request_timeout_ms")]
+    pub request_timeout_ms: u64,
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    /// Idle connections kept open per host
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+    /// Idle connections are closed after this long
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub pool_idle_timeout_secs: u64,
+    /// Orders submitted at once; more wait in priority order (exits first, then nearest expiry)
+    #[serde(default = "default_max_orders_in_flight")]
+    pub max_orders_in_flight: usize,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            request_timeout_ms: default_request_timeout_ms(),
+            connect_timeout_ms: default_connect_timeout_ms(),
+            pool_max_idle_per_host: default_pool_max_idle_per_host(),
+            pool_idle_timeout_secs: default_pool_idle_timeout_secs(),
+            max_orders_in_flight: default_max_orders_in_flight(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_metrics_listen_addr")]
+    pub listen_addr: String,
+}
+
+/// Local control server: the `status` subcommand and the JSON management API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControlConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_control_listen_addr")]
+    pub listen_addr: String,
+    /// When set, every request must send `Authorization: Bearer <auth_token>`
+    #[serde(default)]
+    pub auth_token: Option<String>,
+    #[serde(default)]
+    pub signals: ExternalSignalConfig,
+}
+
+impl Default for ControlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            listen_addr: default_control_listen_addr(),
+            auth_token: None,
+            signals: ExternalSignalConfig::default(),
+        }
+    }
+}
+
+impl ControlConfig {
+    /// Whether `listen_addr` only accepts connections from this machine. Host names other than
+    /// `localhost` count as reachable from the network, whatever they resolve to.
+    pub fn is_loopback(&self) -> bool {
+        match self.listen_addr.parse::<std::net::SocketAddr>() {
+            Ok(addr) => addr.ip().is_loopback(),
+            Err(_) => self.listen_addr.rsplit_once(':').is_some_and(|(host, _)| host.eq_ignore_ascii_case("localhost")),
+        }
+    }
+}
+
+/// External signals: biases other systems post to `POST /signals` on the control server,
+/// consulted by the strategy until they expire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalSignalConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Longest a posted signal may last
+    #[serde(default = "default_signal_max_ttl_secs")]
+    pub max_ttl_secs: u64,
+}
+
+impl Default for ExternalSignalConfig {
+    fn default() -> Self {
+        Self { enabled: false, max_ttl_secs: default_signal_max_ttl_secs() }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen_addr: default_metrics_listen_addr(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrategyConfig {
+    pub price_limit: f64,
+    pub shares: f64,
+    pub place_order_before_mins: u64,
+    pub check_interval_ms: u64,
+    /// Place pre-orders on the next market; off leaves only mid-market orders
+    #[serde(default = "default_true")]
+    pub pre_orders_enabled: bool,
+    #[serde(default)]
+    pub simulation_mode: bool,
+    /// Authenticate, sign real orders and check the balance, but log orders instead of posting
+    /// them; fills, sells and resolutions follow the simulation. `--paper-live` turns it on.
+    #[serde(default)]
+    pub paper_live: bool,
+    /// Simulation mode only: USDC the simulated account starts with. Buys are paid from it and
+    /// refused once it runs short; sells and resolution payouts are credited. None = unlimited.
+    #[serde(default)]
+    pub starting_balance_usd: Option<f64>,
+    /// Pairs are bought only when their expected profit after fees, in USD, is above this
+    #[serde(default)]
+    pub ev_min_usd: f64,
+    /// No-trade windows: markets whose period overlaps one get no new buys
+    #[serde(default)]
+    pub blackouts: Vec<BlackoutConfig>,
+    #[serde(default)]
+    pub signal: SignalConfig,
+    /// Market sells never fill more than this below the price they were decided on; the order is
+    /// limited to that price instead of chasing the bid
+    #[serde(default = "default_slippage_tolerance")]
+    pub slippage_tolerance: f64,
+    /// Buys are not placed on prices requested longer ago than this, as when the tick stalled
+    /// between reading the market and ordering; 0 turns the check off
+    #[serde(default = "default_max_snapshot_age_ms")]
+    pub max_snapshot_age_ms: u64,
+    /// Sell the loser once both sides filled and the winner nears $1; off holds both to resolution
+    #[serde(default = "default_true")]
+    pub sell_opposite_enabled: bool,
+    #[serde(default = "default_sell_opposite_above")]
+    pub sell_opposite_above: f64,
+    #[serde(default = "default_sell_opposite_time_remaining")]
+    pub sell_opposite_time_remaining: u64,
+    #[serde(default)]
+    pub spike_sell: SpikeSellConfig,
+    #[serde(default)]
+    pub partial_fill: PartialFillConfig,
+    #[serde(default = "default_market_closure_check_interval_seconds")]
+    pub market_closure_check_interval_seconds: u64,
+    /// Seconds between market status summaries in the log
+    #[serde(default = "default_status_interval_secs")]
+    pub status_interval_secs: u64,
+    #[serde(default)]
+    pub risk: RiskConfig,
+    #[serde(default)]
+    pub rate_control: RateControlConfig,
+    #[serde(default)]
+    pub fill_model: FillModelConfig,
+    #[serde(default)]
+    pub fees: FeeConfig,
+    #[serde(default)]
+    pub volatility_sizing: VolatilitySizingConfig,
+    #[serde(default)]
+    pub streak_sizing: StreakSizingConfig,
+    #[serde(default)]
+    pub plugin: PluginConfig,
+    #[serde(default)]
+    pub allocation: AllocationConfig,
+    /// On shutdown, market-sell positions where only one side filled instead of leaving them open
+    #[serde(default)]
+    pub flatten_on_shutdown: bool,
+    /// Trade the threshold markets in `markets` (Yes as Up, No as Down) as well as Up/Down ones
+    #[serde(default)]
+    pub threshold_markets: bool,
+    /// Place buys as GTD orders that expire when their market's period ends, so none rests
+    /// into the next period
+    #[serde(default)]
+    pub expire_orders_at_period_end: bool,
+    #[serde(default)]
+    pub redeem: RedeemConfig,
+    #[serde(default)]
+    pub resolution: ResolutionConfig,
+    #[serde(default)]
+    pub fill_reconciliation: FillReconciliationConfig,
+    #[serde(default)]
+    pub treasury: TreasuryConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_stable_min")]
+    pub stable_min: f64,
+    #[serde(default = "default_stable_max")]
+    pub stable_max: f64,
+    #[serde(default = "default_clear_threshold")]
+    pub clear_threshold: f64,
+    #[serde(default = "default_clear_remaining_mins")]
+    pub clear_remaining_mins: u64,
+    #[serde(default = "default_danger_price")]
+    pub danger_price: f64,
+    #[serde(default = "default_danger_time_passed")]
+    pub danger_time_passed: u64,
+    #[serde(default = "default_one_side_buy_risk_management")]
+    pub one_side_buy_risk_management: String,
+    #[serde(default = "default_true")]
+    pub mid_market_enabled: bool,
+    /// Mid-market orders buy the cheaper side at its price and the other at this total minus it
+    #[serde(default = "default_mid_market_pair_total")]
+    pub mid_market_pair_total: f64,
+    #[serde(default)]
+    pub trend: TrendConfig,
+    #[serde(default)]
+    pub momentum: MomentumConfig,
+    #[serde(default)]
+    pub model: ModelConfig,
+}
+
+/// Trend filter: a market whose Up price is trending is likely to fill only one side, so its
+/// signal is treated as Bad.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendConfig {
+    #[serde(default)]
+    pub trend_mode: TrendMode,
+    /// Samples kept per market (and the EMA span)
+    #[serde(default = "default_trend_window")]
+    pub window: usize,
+    /// Minimum |slope| in price per minute that counts as a trend
+    #[serde(default = "default_trend_threshold")]
+    pub threshold: f64,
+    /// Regression mode: minimum |t-statistic| of the slope
+    #[serde(default = "default_trend_min_t_stat")]
+    pub min_t_stat: f64,
+    /// How isolated bad ticks are kept out of the slope
+    #[serde(default)]
+    pub outlier_filter: OutlierFilter,
+    /// Median filter: samples each price is the median of (odd)
+    #[serde(default = "default_median_span")]
+    pub median_span: usize,
+    /// Winsorizing: prices are clamped to this quantile of the window and its complement
+    #[serde(default = "default_winsor_quantile")]
+    pub winsor_quantile: f64,
+}
+
+impl Default for TrendConfig {
+    fn default() -> Self {
+        Self {
+            trend_mode: TrendMode::Off,
+            window: default_trend_window(),
+            threshold: default_trend_threshold(),
+            min_t_stat: default_trend_min_t_stat(),
+            outlier_filter: OutlierFilter::default(),
+            median_span: default_median_span(),
+            winsor_quantile: default_winsor_quantile(),
+        }
+    }
+}
+
+/// Momentum confirmation: sell-opposite commits to the winner only once the underlying's spot
+/// candle has moved its way by a meaningful part of a typical 15m range.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MomentumConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Binance-compatible REST API serving `/api/v3/klines`
+    #[serde(default = "default_spot_url")]
+    pub spot_url: String,
+    /// Closed 15m candles whose median high-low range is the typical range
+    #[serde(default = "default_momentum_lookback")]
+    pub lookback: usize,
+    /// Move from the candle's open, as a fraction of the typical range, that confirms a winner
+    #[serde(default = "default_min_range_fraction")]
+    pub min_range_fraction: f64,
+    /// How long fetched candles are reused
+    #[serde(default = "default_momentum_refresh_ms")]
+    pub refresh_ms: u64,
+}
+
+impl Default for MomentumConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            spot_url: default_spot_url(),
+            lookback: default_momentum_lookback(),
+            min_range_fraction: default_min_range_fraction(),
+            refresh_ms: default_momentum_refresh_ms(),
+        }
+    }
+}
+
+/// Side model: a user-provided ONNX model scores the probability that Up wins from the
+/// `dataset` features, and the mid-market EV gate uses it in place of the market-implied odds.
+/// Needs a build with `--features onnx`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_model_path")]
+    pub path: PathBuf,
+    /// Features fed to the model, in input order, by their `dataset` row names
+    #[serde(default = "default_model_features")]
+    pub features: Vec<String>,
+    /// Element of the first output holding the Up probability
+    #[serde(default)]
+    pub output_index: usize,
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_model_path(),
+            features: default_model_features(),
+            output_index: 0,
+        }
+    }
+}
+
+/// A recurring window around a volatile time (a CPI release, an FOMC decision) with no new buys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlackoutConfig {
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Cron expression in ET for when the window starts: minute hour day-of-month month day-of-week
+    pub cron: String,
+    /// Minutes the window lasts from each start
+    #[serde(default = "default_blackout_mins")]
+    pub duration_mins: u64,
+}
+
+impl BlackoutConfig {
+    pub fn label(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.cron)
+    }
+}
+
+fn default_blackout_mins() -> u64 { 15 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutlierFilter {
+    Off,
+    /// Each price replaced by the median of the `median_span` samples around it
+    #[default]
+    Median,
+    /// Prices clamped to the window's `winsor_quantile` and `1 - winsor_quantile` quantiles
+    Winsorize,
+}
+
+/// Splits the balance by weight when it can't fund a full-size pair on every traded market.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AllocationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Weight per ticker (`"BTC"`) or ticker and timeframe (`"BTC:15m"`, which wins); markets
+    /// not listed weigh 1. Event markets are keyed by ticker alone.
+    #[serde(default)]
+    pub weights: BTreeMap<String, f64>,
+}
+
+/// Scales `shares` down when the Up price has been volatile: choppy markets are the ones where
+/// only one side fills before the price runs away.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolatilitySizingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Snapshots in the rolling standard deviation
+    #[serde(default = "default_volatility_window")]
+    pub window: usize,
+    /// Standard deviation of the Up price at or below which full size is used
+    #[serde(default = "default_volatility_reference_std")]
+    pub reference_std: f64,
+    /// Smallest fraction of `shares` to trade, however volatile
+    #[serde(default = "default_volatility_min_scale")]
+    pub min_scale: f64,
+}
+
+impl Default for VolatilitySizingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window: default_volatility_window(),
+            reference_std: default_volatility_reference_std(),
+            min_scale: default_volatility_min_scale(),
+        }
+    }
+}
+
+/// Cuts a market's `shares` after a run of losing periods and wins it back a step at a time,
+/// so the bot trades smaller through regimes the strategy handles badly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreakSizingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Consecutive losing periods on a market that cut its size; every further run of this many cuts again
+    #[serde(default = "default_streak_losses")]
+    pub losses: u32,
+    /// Factor each cut multiplies the size by
+    #[serde(default = "default_streak_cut")]
+    pub cut: f64,
+    /// Fraction of full size added back per winning period, up to full size
+    #[serde(default = "default_streak_restore_step")]
+    pub restore_step: f64,
+    /// Smallest fraction of `shares` to trade, however long the streak
+    #[serde(default = "default_streak_min_scale")]
+    pub min_scale: f64,
+}
+
+impl Default for StreakSizingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            losses: default_streak_losses(),
+            cut: default_streak_cut(),
+            restore_step: default_streak_restore_step(),
+            min_scale: default_streak_min_scale(),
+        }
+    }
+}
+
+/// Strategy plugin: an external command that decides mid-market buys. It reads one JSON line
+/// per snapshot on stdin and answers each with one JSON action line on stdout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Program and arguments, e.g. ["python3", "strategy.py"]
+    #[serde(default)]
+    pub command: Vec<String>,
+    /// An answer that takes longer counts as no action, and the command is restarted
+    #[serde(default = "default_plugin_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Seconds between snapshots of one market sent to the plugin
+    #[serde(default = "default_plugin_interval_secs")]
+    pub interval_secs: u64,
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: Vec::new(),
+            timeout_ms: default_plugin_timeout_ms(),
+            interval_secs: default_plugin_interval_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrendMode {
+    #[default]
+    Off,
+    /// Last vs first sample of the window
+    Endpoints,
+    /// Exponential moving average of the sample-to-sample slope
+    Ema,
+    /// Least-squares slope with a t-test
+    Regression,
+}
+
+/// Without a `signal` block, gating and mid-market orders stay off and the thresholds are zero.
+impl Default for SignalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            stable_min: 0.0,
+            stable_max: 0.0,
+            clear_threshold: 0.0,
+            clear_remaining_mins: 0,
+            danger_price: 0.0,
+            danger_time_passed: 0,
+            one_side_buy_risk_management: String::new(),
+            mid_market_enabled: false,
+            mid_market_pair_total: default_mid_market_pair_total(),
+            trend: TrendConfig::default(),
+            momentum: MomentumConfig::default(),
+            model: ModelConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskConfig {
+    /// Max loss (realized + unrealized) per ET calendar day before the kill switch trips. Unset or 0 disables it.
+    #[serde(default)]
+    pub daily_loss_limit_usd: Option<f64>,
+    /// Max fall of realized PnL since start below its running peak before the kill switch trips.
+    /// Unset or 0 disables it.
+    #[serde(default)]
+    pub max_drawdown_usd: Option<f64>,
+    #[serde(default = "default_kill_switch_file")]
+    pub kill_switch_file: PathBuf,
+    /// Assets that tend to move together, each with a cap on their combined one-direction exposure
+    #[serde(default)]
+    pub correlation_groups: Vec<CorrelationGroup>,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            daily_loss_limit_usd: None,
+            max_drawdown_usd: None,
+            kill_switch_file: default_kill_switch_file(),
+            correlation_groups: Vec::new(),
+        }
+    }
+}
+
+/// Assets whose 15m markets usually resolve the same way (e.g. BTC and ETH). Holding Up on all of
+/// them is one bet, not several, so their same-direction exposure is capped together.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationGroup {
+    pub assets: Vec<String>,
+    /// Most USD that may be exposed to Up (or to Down) across the group's markets at once
+    pub max_same_side_usd: f64,
 }
 
+/// Limits on how often new orders go out, per strategy (account). 0 / unset turns a limit off.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RateControlConfig {
+    /// Minimum seconds between buys on the same market and outcome
+    #[serde(default)]
+    pub same_side_gap_secs: f64,
+    /// Minimum seconds between any two order decisions, across markets
+    #[serde(default)]
+    pub order_gap_secs: f64,
+    /// Most orders (each leg counts) per 15m period, across markets
+    #[serde(default)]
+    pub max_orders_per_period: Option<u32>,
+}
 
+/// Selling part of a held winner into the final-minute run-up instead of holding it all to
+/// resolution: the sold part is realized at once and skips the redemption transaction.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Config {
-    pub polymarket: PolymarketConfig,
-    pub strategy: StrategyConfig,
+pub struct SpikeSellConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Sell once the held side's price is at or above this
+    #[serde(default = "default_spike_sell_threshold")]
+    pub sell_threshold: f64,
+    /// ...within this many seconds of the period end
+    #[serde(default = "default_spike_sell_final_secs")]
+    pub final_secs: u64,
+    /// Share of the held position to sell; the rest is held to resolution
+    #[serde(default = "default_spike_sell_fraction")]
+    pub fraction: f64,
+}
+
+impl Default for SpikeSellConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sell_threshold: default_spike_sell_threshold(),
+            final_secs: default_spike_sell_final_secs(),
+            fraction: default_spike_sell_fraction(),
+        }
+    }
 }
 
+/// What happens to the rest of a market sell that filled only in part.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct StrategyConfig {
-    pub price_limit: f64,
-    pub shares: f64,
-    pub place_order_before_mins: u64,
-    pub check_interval_ms: u64,
+pub struct PartialFillConfig {
+    /// Sell the remainder again on the following ticks, at no less than the first attempt's
+    /// price floor (`slippage_tolerance` below its quote)
+    #[serde(default = "default_true")]
+    pub retry: bool,
+    /// How long after the first partial fill the remainder is retried; after that it is held
+    /// to resolution
+    #[serde(default = "default_partial_fill_window_secs")]
+    pub window_secs: u64,
+}
+
+impl Default for PartialFillConfig {
+    fn default() -> Self {
+        Self { retry: true, window_secs: default_partial_fill_window_secs() }
+    }
+}
+
+/// When resolved winning positions are redeemed on-chain. Redemptions are batched to save gas.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedeemConfig {
+    /// Redeem the queued positions once their payouts add up to this much. 0 redeems each one as it resolves.
+    #[serde(default = "default_min_redeem_usd")]
+    pub min_redeem_usd: f64,
+    /// ...or once the oldest queued position has waited this long
+    #[serde(default = "default_max_redeem_age_mins")]
+    pub max_redeem_age_mins: u64,
+}
+
+impl Default for RedeemConfig {
+    fn default() -> Self {
+        Self {
+            min_redeem_usd: default_min_redeem_usd(),
+            max_redeem_age_mins: default_max_redeem_age_mins(),
+        }
+    }
+}
+
+/// Keeps the capital at risk bounded: what the wallet holds above `target_bankroll_usd` raises an
+/// alert, or with `transfer` is sent to `cold_address`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreasuryConfig {
+    /// USDC balance above which the excess is alerted on or moved; None turns the rule off
     #[serde(default)]
-    pub simulation_mode: bool,
+    pub target_bankroll_usd: Option<f64>,
+    /// Transfer the excess instead of only alerting (needs `cold_address`)
     #[serde(default)]
-    pub signal: SignalConfig,
-    #[serde(default = "default_sell_opposite_above")]
-    pub sell_opposite_above: f64,
-    #[serde(default = "default_sell_opposite_time_remaining")]
-    pub sell_opposite_time_remaining: u64,
-    #[serde(default = "default_market_closure_check_interval_seconds")]
-    pub market_closure_check_interval_seconds: u64,
+    pub transfer: bool,
+    #[serde(default)]
+    pub cold_address: Option<String>,
+    /// Smallest excess worth alerting on or transferring
+    #[serde(default = "default_min_excess_usd")]
+    pub min_excess_usd: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct SignalConfig {
+impl Default for TreasuryConfig {
+    fn default() -> Self {
+        Self {
+            target_bankroll_usd: None,
+            transfer: false,
+            cold_address: None,
+            min_excess_usd: default_min_excess_usd(),
+        }
+    }
+}
+
+/// Retries for markets that have ended but not resolved yet (still open, or closed without a
+/// winner while the outcome is disputed).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolutionConfig {
+    /// First retry delay; doubles on every further attempt
+    #[serde(default = "default_resolution_retry_secs")]
+    pub retry_secs: u64,
+    /// Cap on the retry delay
+    #[serde(default = "default_resolution_max_retry_secs")]
+    pub max_retry_secs: u64,
+    /// Alert when a market is still unresolved this long after it ended
+    #[serde(default = "default_resolution_alert_after_mins")]
+    pub alert_after_mins: u64,
+    /// Read the winner from the ConditionalTokens payout numerators when the CLOB hasn't flagged one
+    #[serde(default = "default_true")]
+    pub onchain_fallback: bool,
+    /// What settles periods in simulation
+    #[serde(default)]
+    pub simulated_winner: SimulatedWinner,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SimulatedWinner {
+    /// The winner flagged by the API, as live
+    #[default]
+    Api,
+    /// The reference price at the period's start and end: the underlying's spot price, or the
+    /// final recorded quotes in a replay. Falls back to the API while it can't be read.
+    Reference,
+}
+
+impl Default for ResolutionConfig {
+    fn default() -> Self {
+        Self {
+            retry_secs: default_resolution_retry_secs(),
+            max_retry_secs: default_resolution_max_retry_secs(),
+            alert_after_mins: default_resolution_alert_after_mins(),
+            onchain_fallback: true,
+            simulated_winner: SimulatedWinner::default(),
+        }
+    }
+}
+
+/// Periodic check of the account's fills on the exchange against the ledger (live mode).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillReconciliationConfig {
     #[serde(default = "default_true")]
     pub enabled: bool,
-    #[serde(default = "default_stable_min")]
-    pub stable_min: f64,
-    #[serde(default = "default_stable_max")]
-    pub stable_max: f64,
-    #[serde(default = "default_clear_threshold")]
-    pub clear_threshold: f64,
-    #[serde(default = "default_clear_remaining_mins")]
-    pub clear_remaining_mins: u64,
-    #[serde(default = "default_danger_price")]
-    pub danger_price: f64,
-    #[serde(default = "default_danger_time_passed")]
-    pub danger_time_passed: u64,
-    #[serde(default = "default_one_side_buy_risk_management")]
-    pub one_side_buy_risk_management: String,
+    #[serde(default = "default_fill_reconciliation_interval_mins")]
+    pub interval_mins: u64,
+    /// Markets traded within this window are compared
+    #[serde(default = "default_fill_reconciliation_lookback_hours")]
+    pub lookback_hours: u64,
+}
+
+impl Default for FillReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_mins: default_fill_reconciliation_interval_mins(),
+            lookback_hours: default_fill_reconciliation_lookback_hours(),
+        }
+    }
+}
+
+/// How simulation mode and the backtester fill market sells.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FillModelConfig {
+    /// Off: every sell fills in full at the quoted price
     #[serde(default = "default_true")]
-    pub mid_market_enabled: bool,
+    pub enabled: bool,
+    /// Probability that a market sell attempt gets no fill and is retried on the next tick
+    #[serde(default = "default_fill_miss_rate")]
+    pub miss_rate: f64,
+    /// Price concession below the quote when no book depth is available
+    #[serde(default = "default_fill_fallback_slippage")]
+    pub fallback_slippage: f64,
+    /// Fixed RNG seed for reproducible misses
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Decision-to-book delay of simulated orders: sells fill against the book this long after
+    /// they were decided, and buys can't fill sooner than this after they were placed
+    #[serde(default)]
+    pub latency_ms_simulated: u64,
+}
+
+impl Default for FillModelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            miss_rate: default_fill_miss_rate(),
+            fallback_slippage: default_fill_fallback_slippage(),
+            seed: None,
+            latency_ms_simulated: 0,
+        }
+    }
+}
+
+/// Fee schedule, see `fees::FeeSchedule`. Defaults follow the 15m crypto markets:
+/// makers pay nothing, takers pay a curve peaking at about 1.56% of notional at 50¢.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeConfig {
+    #[serde(default)]
+    pub maker_fee_rate: f64,
+    #[serde(default = "default_taker_fee_rate")]
+    pub taker_fee_rate: f64,
+    #[serde(default = "default_fee_exponent")]
+    pub fee_exponent: f64,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            maker_fee_rate: 0.0,
+            taker_fee_rate: default_taker_fee_rate(),
+            fee_exponent: default_fee_exponent(),
+        }
+    }
 }
 
 fn default_true() -> bool { true }
@@ -71,59 +1548,890 @@ fn default_danger_price() -> f64 { 0.15 }
 fn default_danger_time_passed() -> u64 { 30 }
 fn default_one_side_buy_risk_management() -> String { "price".to_string() }
 fn default_sell_opposite_above() -> f64 { 0.95 }
+fn default_slippage_tolerance() -> f64 { 0.02 }
+fn default_max_snapshot_age_ms() -> u64 { 3000 }
 fn default_sell_opposite_time_remaining() -> u64 { 15 }
 fn default_market_closure_check_interval_seconds() -> u64 { 120 }
+fn default_status_interval_secs() -> u64 { 10 }
+fn default_mid_market_pair_total() -> f64 { 0.98 }
+fn default_trend_window() -> usize { 20 }
+fn default_spot_url() -> String { "https://api.binance.com".to_string() }
+fn default_momentum_lookback() -> usize { 96 }
+fn default_min_range_fraction() -> f64 { 0.3 }
+fn default_momentum_refresh_ms() -> u64 { 5000 }
+fn default_model_path() -> PathBuf { PathBuf::from("model.onnx") }
+fn default_model_features() -> Vec<String> {
+    ["implied_up", "up_change", "imbalance", "secs_into_period"].map(String::from).to_vec()
+}
+fn default_trend_threshold() -> f64 { 0.02 }
+fn default_trend_min_t_stat() -> f64 { 2.0 }
+fn default_median_span() -> usize { 3 }
+fn default_winsor_quantile() -> f64 { 0.2 }
+fn default_volatility_window() -> usize { 30 }
+fn default_volatility_reference_std() -> f64 { 0.02 }
+fn default_volatility_min_scale() -> f64 { 0.25 }
+fn default_streak_losses() -> u32 { 3 }
+fn default_streak_cut() -> f64 { 0.5 }
+fn default_streak_restore_step() -> f64 { 0.25 }
+fn default_streak_min_scale() -> f64 { 0.25 }
+fn default_plugin_timeout_ms() -> u64 { 1000 }
+fn default_signal_max_ttl_secs() -> u64 { 86400 }
+fn default_plugin_interval_secs() -> u64 { 5 }
+fn default_markets() -> Vec<MarketConfig> {
+    vec![
+        MarketConfig::new("BTC", "bitcoin"),
+        MarketConfig::new("ETH", "ethereum"),
+        MarketConfig::new("SOL", "solana"),
+        MarketConfig::new("XRP", "xrp"),
+    ]
+}
+fn default_market_timeframes() -> Vec<MarketDuration> { vec![MarketDuration::M15, MarketDuration::H1] }
+fn default_log_max_size_mb() -> u64 { 100 }
+fn default_log_keep() -> usize { 7 }
+fn default_discovery_tag_slug() -> String { "up-or-down".to_string() }
+fn default_discovery_retry_secs() -> u64 { 5 }
+fn default_discovery_max_retry_secs() -> u64 { 60 }
+fn default_missing_alert_after_secs() -> u64 { 120 }
+fn default_data_api_url() -> String { "https://data-api.polymarket.com".to_string() }
+fn default_kill_switch_file() -> PathBuf { PathBuf::from("kill_switch.halt") }
+fn default_spike_sell_threshold() -> f64 { 0.97 }
+fn default_spike_sell_final_secs() -> u64 { 120 }
+fn default_spike_sell_fraction() -> f64 { 0.5 }
+fn default_partial_fill_window_secs() -> u64 { 30 }
+fn default_min_redeem_usd() -> f64 { 10.0 }
+fn default_max_redeem_age_mins() -> u64 { 240 }
+/// File of profile `profile` for the config at `config`: `profile` itself when it names a .json
+/// file, otherwise `<config stem>.<profile>.json` beside the config.
+pub fn profile_path(config: &Path, profile: &str) -> PathBuf {
+    if profile.ends_with(".json") {
+        return PathBuf::from(profile);
+    }
+    let stem = config.file_stem().and_then(|s| s.to_str()).unwrap_or("config");
+    config.with_file_name(format!("{}.{}.json", stem, profile))
+}
+
+/// Merges `overrides` into `base`: objects key by key, recursively; anything else, arrays
+/// included, replaces what was there.
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// The first key path set in `overrides` that the loaded config (`loaded`, serialized back)
+/// doesn't have: a misspelt or removed setting that would otherwise be silently ignored.
+fn unknown_key(overrides: &Value, loaded: &Value, prefix: &str) -> Option<String> {
+    let (Value::Object(overrides), Value::Object(loaded)) = (overrides, loaded) else { return None };
+    overrides.iter().find_map(|(key, value)| {
+        let path = if prefix.is_empty() { key.clone() } else { format!("{}.{}", prefix, key) };
+        match loaded.get(key) {
+            Some(loaded) => unknown_key(value, loaded, &path),
+            // Unset optional settings aren't written back
+            None if value.is_null() => None,
+            None => Some(path),
+        }
+    })
+}
+
+fn default_resolution_retry_secs() -> u64 { 30 }
+fn default_resolution_max_retry_secs() -> u64 { 900 }
+fn default_resolution_alert_after_mins() -> u64 { 120 }
+fn default_min_excess_usd() -> f64 { 10.0 }
+fn default_fill_reconciliation_interval_mins() -> u64 { 15 }
+fn default_fill_reconciliation_lookback_hours() -> u64 { 24 }
+fn default_metrics_listen_addr() -> String { "127.0.0.1:9184".to_string() }
+fn default_control_listen_addr() -> String { "127.0.0.1:9185".to_string() }
+fn default_ledger_path() -> PathBuf { PathBuf::from("ledger.jsonl") }
+fn default_tui_console_file() -> PathBuf { PathBuf::from("console.log") }
+fn default_profit_rollups() -> Vec<ProfitWindow> { vec![ProfitWindow::Period, ProfitWindow::Daily] }
+fn default_profit_timezone() -> String { "America/New_York".to_string() }
+fn default_report_dir() -> PathBuf { PathBuf::from("reports") }
+fn default_calibration_path() -> PathBuf { PathBuf::from("calibration.jsonl") }
+fn default_calibration_checkpoints() -> Vec<i64> { vec![600, 300, 120, 60] }
+fn default_dataset_path() -> PathBuf { PathBuf::from("dataset.jsonl") }
+fn default_dataset_offsets() -> Vec<i64> { vec![180, 420, 720] }
+fn default_dataset_sample_secs() -> u64 { 15 }
+fn default_usd_decimals() -> usize { 2 }
+fn default_price_decimals() -> usize { 2 }
+fn default_share_decimals() -> usize { 2 }
+fn default_report_windows() -> Vec<ProfitWindow> { vec![ProfitWindow::Period, ProfitWindow::Daily] }
+fn default_snapshot_dir() -> PathBuf { PathBuf::from("snapshots") }
+fn default_snapshot_interval_ms() -> u64 { 1000 }
+fn default_fill_miss_rate() -> f64 { 0.05 }
+fn default_fill_fallback_slippage() -> f64 { 0.01 }
+fn default_taker_fee_rate() -> f64 { 0.25 }
+fn default_fee_exponent() -> f64 { 2.0 }
+fn default_size_fraction() -> f64 { 1.0 }
+fn default_request_timeout_ms() -> u64 { 10_000 }
+fn default_connect_timeout_ms() -> u64 { 5_000 }
+fn default_pool_max_idle_per_host() -> usize { 16 }
+fn default_pool_idle_timeout_secs() -> u64 { 90 }
+fn default_max_orders_in_flight() -> usize { 1 }
+
+/// Shared price and order book fetching for every strategy and the snapshot recorder.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedConfig {
+    /// A stale read waits this long for other reads before the batch goes out
+    #[serde(default = "default_batch_window_ms")]
+    pub batch_window_ms: u64,
+    /// Prices and books fetched within this long are served from the last batch
+    #[serde(default = "default_max_age_ms")]
+    pub max_age_ms: u64,
+    /// Where prices and books come from
+    #[serde(default)]
+    pub data_source: DataSource,
+    /// CLOB market channel
+    #[serde(default = "default_ws_url")]
+    pub ws_url: String,
+    /// The socket counts as stalled after this long without a frame (it is pinged every 10s)
+    #[serde(default = "default_ws_stale_ms")]
+    pub ws_stale_ms: u64,
+    /// Hybrid mode: how often REST prices are fetched to cross-check the socket
+    #[serde(default = "default_hybrid_check_ms")]
+    pub hybrid_check_ms: u64,
+    /// Hybrid mode: largest gap between a socket and a REST price before falling back to REST
+    #[serde(default = "default_max_divergence")]
+    pub max_divergence: f64,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            batch_window_ms: default_batch_window_ms(),
+            max_age_ms: default_max_age_ms(),
+            data_source: DataSource::default(),
+            ws_url: default_ws_url(),
+            ws_stale_ms: default_ws_stale_ms(),
+            hybrid_check_ms: default_hybrid_check_ms(),
+            max_divergence: default_max_divergence(),
+        }
+    }
+}
+
+fn default_batch_window_ms() -> u64 { 25 }
+fn default_max_age_ms() -> u64 { 1000 }
+fn default_ws_url() -> String { "wss://ws-subscriptions-clob.polymarket.com/ws/market".to_string() }
+fn default_ws_stale_ms() -> u64 { 30_000 }
+fn default_hybrid_check_ms() -> u64 { 5000 }
+fn default_max_divergence() -> f64 { 0.03 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    /// Batched REST polling
+    #[default]
+    Rest,
+    /// Books kept current by the CLOB market WebSocket; tokens it has no book for, or all
+    /// tokens while it is stalled, are read over REST
+    Websocket,
+    /// The WebSocket, cross-checked against low-rate REST polling, with a fallback to REST
+    /// while the two disagree or the socket stalls
+    Hybrid,
+}
+
+/// Check of the local clock against the exchange's, which period boundaries depend on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Minutes between checks after the one at startup
+    #[serde(default = "default_clock_interval_mins")]
+    pub interval_mins: u64,
+    /// Skew beyond this is logged as a warning (and corrected); less is treated as noise
+    #[serde(default = "default_clock_warn_ms")]
+    pub warn_ms: u64,
+    /// Offset period math by the measured skew
+    #[serde(default = "default_true")]
+    pub correct: bool,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_mins: default_clock_interval_mins(),
+            warn_ms: default_clock_warn_ms(),
+            correct: true,
+        }
+    }
+}
+
+fn default_clock_interval_mins() -> u64 { 10 }
+fn default_clock_warn_ms() -> u64 { 1000 }
+
+/// Liveness checks behind `GET /healthz`: the snapshot recorder, the strategy loops and the API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchdogConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// A market, strategy loop or API silent for this long counts as stuck
+    #[serde(default = "default_watchdog_stale_secs")]
+    pub stale_secs: u64,
+    #[serde(default = "default_watchdog_check_interval_secs")]
+    pub check_interval_secs: u64,
+    /// Exit with status 1 when a strategy loop or the API is stuck, for a supervisor to restart
+    #[serde(default)]
+    pub exit_on_stale: bool,
+    /// After a panic, pick a market or background task up again (a market on the next tick);
+    /// otherwise a panicked market is paused and a panicked task stays down
+    #[serde(default = "default_true")]
+    pub restart_on_panic: bool,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            stale_secs: default_watchdog_stale_secs(),
+            check_interval_secs: default_watchdog_check_interval_secs(),
+            exit_on_stale: false,
+            restart_on_panic: true,
+        }
+    }
+}
+
+fn default_watchdog_stale_secs() -> u64 { 180 }
+fn default_watchdog_check_interval_secs() -> u64 { 15 }
+
+/// Guards against two live instances trading one wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Lock file held while the bot runs; defaults to one per proxy wallet in the temp directory
+    #[serde(default)]
+    pub lock_file: Option<PathBuf>,
+    /// Also look for open orders on the account that this instance or its predecessor didn't post
+    #[serde(default = "default_true")]
+    pub exchange_check: bool,
+    /// How far back open orders count as another instance's
+    #[serde(default = "default_instance_recent_secs")]
+    pub recent_secs: u64,
+    #[serde(default)]
+    pub on_conflict: OnConflict,
+}
+
+impl Default for InstanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            lock_file: None,
+            exchange_check: true,
+            recent_secs: default_instance_recent_secs(),
+            on_conflict: OnConflict::default(),
+        }
+    }
+}
+
+/// What to do when another instance is found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnConflict {
+    /// Exit with an error
+    #[default]
+    Refuse,
+    /// Run with every market paused: status, metrics and recording, but no new orders
+    MonitorOnly,
+}
+
+fn default_instance_recent_secs() -> u64 { 900 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolymarketConfig {
     pub gamma_api_url: String,
     pub clob_api_url: String,
+    #[serde(default = "default_data_api_url")]
+    pub data_api_url: String,
     pub api_key: Option<String>,
     pub api_secret: Option<String>,
     pub api_passphrase: Option<String>,
     pub private_key: Option<String>,
+    /// Key file from `encrypt-key`, or a Web3 Secret Storage keystore (geth, `cast wallet`),
+    /// decrypted at startup in place of `private_key`
+    #[serde(default)]
+    pub private_key_file: Option<PathBuf>,
+    /// Signing service that holds the wallet key, used instead of a private key
+    #[serde(default)]
+    pub remote_signer: Option<RemoteSignerConfig>,
     pub proxy_wallet_address: Option<String>,
     pub signature_type: Option<u8>,
+    /// Exchange nonce signed into orders; raise it after cancelling everything on-chain with
+    /// `incrementNonce`, which voids orders signed under older nonces
+    #[serde(default)]
+    pub order_nonce: u64,
+}
+
+impl PolymarketConfig {
+    /// Whether this wallet can sign: a private key (set, or unlocked from `private_key_file`)
+    /// or a remote signer.
+    pub fn can_sign(&self) -> bool {
+        self.private_key.is_some() || self.remote_signer.is_some()
+    }
+}
+
+/// A service that keeps the wallet key (a hardware wallet bridge, a KMS proxy) and signs
+/// 32-byte hashes over JSON-RPC: orders, API key derivation and on-chain transactions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteSignerConfig {
+    /// JSON-RPC endpoint
+    pub url: String,
+    /// Wallet the service signs for; each signature must recover to it
+    pub address: String,
+    /// Method called with `[address, hash]`, answering with a 65-byte hex signature
+    #[serde(default = "default_remote_signer_method")]
+    pub method: String,
+    /// How long to wait for a signature, which may need a confirmation on the device
+    #[serde(default = "default_remote_signer_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl RemoteSignerConfig {
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !(self.url.starts_with("http://") || self.url.starts_with("https://")) {
+            problems.push(format!("url must be an http(s) URL (got {:?})", self.url));
+        }
+        if self.address.parse::<alloy::primitives::Address>().is_err() {
+            problems.push(format!("address is not a wallet address (got {:?})", self.address));
+        }
+        if self.method.trim().is_empty() {
+            problems.push("method must not be empty".to_string());
+        }
+        if self.timeout_ms == 0 {
+            problems.push("timeout_ms must be positive".to_string());
+        }
+        problems
+    }
+}
+
+/// How many of the ways to sign are configured for one wallet.
+fn key_sources(key: &Option<String>, file: &Option<PathBuf>, signer: &Option<RemoteSignerConfig>) -> usize {
+    [key.is_some(), file.is_some(), signer.is_some()].into_iter().filter(|&set| set).count()
 }
 
+fn default_remote_signer_method() -> String { "sign_hash".to_string() }
+fn default_remote_signer_timeout_ms() -> u64 { 60_000 }
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             polymarket: PolymarketConfig {
                 gamma_api_url: "https://gamma-api.polymarket.com".to_string(),
                 clob_api_url: "https://clob.polymarket.com".to_string(),
+                data_api_url: default_data_api_url(),
                 api_key: None,
                 api_secret: None,
                 api_passphrase: None,
                 private_key: None,
+                private_key_file: None,
+                remote_signer: None,
                 proxy_wallet_address: None,
                 signature_type: None,
+                order_nonce: 0,
             },
             strategy: StrategyConfig {
                 price_limit: 0.45,
                 shares: 5.0,
                 place_order_before_mins: 3,
                 check_interval_ms: 2000,
+                pre_orders_enabled: true,
                 simulation_mode: false,
+                paper_live: false,
+                starting_balance_usd: None,
+                ev_min_usd: 0.0,
+                blackouts: Vec::new(),
                 signal: SignalConfig::default(),
+                slippage_tolerance: default_slippage_tolerance(),
+                max_snapshot_age_ms: default_max_snapshot_age_ms(),
+                sell_opposite_enabled: true,
                 sell_opposite_above: 0.95,
                 sell_opposite_time_remaining: 15,
+                spike_sell: SpikeSellConfig::default(),
+                partial_fill: PartialFillConfig::default(),
                 market_closure_check_interval_seconds: 120,
+                status_interval_secs: default_status_interval_secs(),
+                risk: RiskConfig::default(),
+                rate_control: RateControlConfig::default(),
+                fill_model: FillModelConfig::default(),
+                fees: FeeConfig::default(),
+                volatility_sizing: VolatilitySizingConfig::default(),
+                streak_sizing: StreakSizingConfig::default(),
+                plugin: PluginConfig::default(),
+                allocation: AllocationConfig::default(),
+                flatten_on_shutdown: false,
+                threshold_markets: false,
+                expire_orders_at_period_end: false,
+                redeem: RedeemConfig::default(),
+                resolution: ResolutionConfig::default(),
+                fill_reconciliation: FillReconciliationConfig::default(),
+                treasury: TreasuryConfig::default(),
             },
+            metrics: MetricsConfig::default(),
+            network: NetworkConfig::default(),
+            feed: FeedConfig::default(),
+            clock: ClockConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            instance: InstanceConfig::default(),
+            logging: LoggingConfig::default(),
+            ledger: LedgerConfig::default(),
+            monitor: MonitorConfig::default(),
+            markets: default_markets(),
+            discovery: DiscoveryConfig::default(),
+            control: ControlConfig::default(),
+            profit: ProfitConfig::default(),
+            report: ReportConfig::default(),
+            display: DisplayConfig::default(),
+            calibration: CalibrationConfig::default(),
+            dataset: DatasetConfig::default(),
+            accounts: Vec::new(),
         }
     }
 }
 
 impl Config {
-    pub fn load(path: &PathBuf) -> anyhow::Result<Self> {
-        if path.exists() {
+    /// Reads `path`, writing the defaults there first if it doesn't exist, with each of
+    /// `profiles` merged over it in order.
+    pub fn load(path: &PathBuf, profiles: &[String]) -> anyhow::Result<Self> {
+        use anyhow::Context;
+        let mut layered: Value = if path.exists() {
             let content = std::fs::read_to_string(path)?;
-            Ok(serde_json::from_str(&content)?)
+            serde_json::from_str(&content)?
         } else {
             let config = Config::default();
             let content = serde_json::to_string_pretty(&config)?;
             std::fs::write(path, content)?;
-            Ok(config)
+            serde_json::to_value(config)?
+        };
+        for profile in profiles {
+            let profile_path = profile_path(path, profile);
+            let content = std::fs::read_to_string(&profile_path)
+                .with_context(|| format!("Failed to read profile {:?} from {}", profile, profile_path.display()))?;
+            let overrides: Value = serde_json::from_str(&content).with_context(|| format!("Invalid profile {}", profile_path.display()))?;
+            anyhow::ensure!(overrides.is_object(), "Profile {} is not a JSON object", profile_path.display());
+            merge(&mut layered, overrides.clone());
+            let config: Config = serde_json::from_value(layered.clone()).with_context(|| format!("Invalid setting in profile {}", profile_path.display()))?;
+            if let Some(key) = unknown_key(&overrides, &serde_json::to_value(&config)?, "") {
+                anyhow::bail!("Profile {} sets {}, which is not a setting", profile_path.display(), key);
+            }
+        }
+        let config: Config = serde_json::from_value(layered)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Tickers of the configured recurring markets listed on `duration`.
+    pub fn assets_for(&self, duration: MarketDuration) -> Vec<String> {
+        self.markets
+            .iter()
+            .filter(|m| m.family != MarketFamily::Event && m.timeframes.contains(&duration))
+            .map(|m| m.asset.clone())
+            .collect()
+    }
+
+    /// Tickers the trading loop trades: the 15m markets, leaving out threshold markets unless
+    /// `strategy.threshold_markets` opts into them.
+    pub fn traded_assets(&self) -> Vec<String> {
+        self.markets
+            .iter()
+            .filter(|m| m.timeframes.contains(&MarketDuration::M15))
+            .filter(|m| match m.family {
+                MarketFamily::UpDown => true,
+                MarketFamily::Threshold => self.strategy.threshold_markets,
+                MarketFamily::Event => false,
+            })
+            .map(|m| m.asset.clone())
+            .collect()
+    }
+
+    /// Names of the `event` markets, traded until they end rather than period by period.
+    pub fn event_assets(&self) -> Vec<String> {
+        self.markets
+            .iter()
+            .filter(|m| m.family == MarketFamily::Event)
+            .map(|m| m.asset.clone())
+            .collect()
+    }
+
+    /// Copy safe to hand out over the control API, with credentials masked.
+    pub fn redacted(&self) -> Config {
+        let mask = |v: &Option<String>| v.as_ref().map(|_| "***".to_string());
+        let mut config = self.clone();
+        config.polymarket.api_key = mask(&config.polymarket.api_key);
+        config.polymarket.api_secret = mask(&config.polymarket.api_secret);
+        config.polymarket.api_passphrase = mask(&config.polymarket.api_passphrase);
+        config.polymarket.private_key = mask(&config.polymarket.private_key);
+        config.control.auth_token = mask(&config.control.auth_token);
+        for account in &mut config.accounts {
+            account.api_key = mask(&account.api_key);
+            account.api_secret = mask(&account.api_secret);
+            account.api_passphrase = mask(&account.api_passphrase);
+            account.private_key = mask(&account.private_key);
+        }
+        config
+    }
+
+    /// The config `account`'s strategy runs with: its credentials, the markets routed to it and
+    /// `strategy.shares` scaled by its `size_fraction`.
+    pub fn for_account(&self, account: &AccountConfig) -> Config {
+        let mut config = self.clone();
+        config.polymarket.api_key = account.api_key.clone();
+        config.polymarket.api_secret = account.api_secret.clone();
+        config.polymarket.api_passphrase = account.api_passphrase.clone();
+        config.polymarket.private_key = account.private_key.clone();
+        config.polymarket.private_key_file = account.private_key_file.clone();
+        config.polymarket.remote_signer = account.remote_signer.clone();
+        config.polymarket.proxy_wallet_address = account.proxy_wallet_address.clone();
+        config.polymarket.signature_type = account.signature_type;
+        config.polymarket.order_nonce = account.order_nonce;
+        if !account.assets.is_empty() {
+            config.markets.retain(|m| account.assets.iter().any(|a| a.eq_ignore_ascii_case(&m.asset)));
+        }
+        config.strategy.shares = ((self.strategy.shares * account.size_fraction * 100.0).floor() / 100.0).max(0.01);
+        config.accounts = Vec::new();
+        config
+    }
+
+    /// Rejects strategy settings that would make the bot misbehave, listing every problem at once.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let s = &self.strategy;
+        let sig = &s.signal;
+        let mut errors = Vec::new();
+        let mut check = |ok: bool, msg: String| {
+            if !ok {
+                errors.push(msg);
+            }
+        };
+        let is_price = |p: f64| p > 0.0 && p < 1.0;
+
+        check(is_price(s.price_limit), format!("strategy.price_limit must be between 0 and 1 (got {})", s.price_limit));
+        check(s.shares > 0.0, format!("strategy.shares must be positive (got {})", s.shares));
+        check(s.check_interval_ms > 0, "strategy.check_interval_ms must be positive".to_string());
+        check(s.place_order_before_mins <= 15, format!("strategy.place_order_before_mins must be at most 15 (got {})", s.place_order_before_mins));
+        check((0.0..1.0).contains(&s.slippage_tolerance), format!("strategy.slippage_tolerance must be at least 0 and below 1 (got {})", s.slippage_tolerance));
+        check(is_price(s.sell_opposite_above), format!("strategy.sell_opposite_above must be between 0 and 1 (got {})", s.sell_opposite_above));
+        check(s.status_interval_secs > 0, "strategy.status_interval_secs must be positive".to_string());
+        check(sig.stable_min <= sig.stable_max, format!("strategy.signal.stable_min ({}) must not exceed stable_max ({})", sig.stable_min, sig.stable_max));
+        check((0.0..=1.0).contains(&sig.stable_min) && (0.0..=1.0).contains(&sig.stable_max), "strategy.signal.stable_min/stable_max must be within 0..1".to_string());
+        check((0.0..=1.0).contains(&sig.clear_threshold), format!("strategy.signal.clear_threshold must be within 0..1 (got {})", sig.clear_threshold));
+        check((0.0..=1.0).contains(&sig.danger_price), format!("strategy.signal.danger_price must be within 0..1 (got {})", sig.danger_price));
+        check(
+            sig.mid_market_pair_total > 0.0 && sig.mid_market_pair_total <= 1.0,
+            format!("strategy.signal.mid_market_pair_total must be in (0, 1] (got {})", sig.mid_market_pair_total),
+        );
+        check(sig.trend.window >= 3, format!("strategy.signal.trend.window must be at least 3 (got {})", sig.trend.window));
+        check(sig.trend.threshold >= 0.0, "strategy.signal.trend.threshold must not be negative".to_string());
+        check(
+            sig.trend.median_span % 2 == 1,
+            format!("strategy.signal.trend.median_span must be odd (got {})", sig.trend.median_span),
+        );
+        check(
+            (0.0..0.5).contains(&sig.trend.winsor_quantile),
+            format!("strategy.signal.trend.winsor_quantile must be in [0, 0.5) (got {})", sig.trend.winsor_quantile),
+        );
+        check(
+            (1..1000).contains(&sig.momentum.lookback),
+            format!("strategy.signal.momentum.lookback must be between 1 and 999 (got {})", sig.momentum.lookback),
+        );
+        check(sig.momentum.min_range_fraction >= 0.0, "strategy.signal.momentum.min_range_fraction must not be negative".to_string());
+        if sig.model.enabled {
+            check(!sig.model.features.is_empty(), "strategy.signal.model.features must not be empty".to_string());
+        }
+        for feature in &sig.model.features {
+            check(
+                crate::dataset::FEATURES.contains(&feature.as_str()),
+                format!("strategy.signal.model.features: unknown feature {:?} (expected one of {})", feature, crate::dataset::FEATURES.join(", ")),
+            );
+        }
+        check((0.0..=1.0).contains(&s.fill_model.miss_rate), format!("strategy.fill_model.miss_rate must be within 0..1 (got {})", s.fill_model.miss_rate));
+        check(s.fill_model.fallback_slippage >= 0.0, "strategy.fill_model.fallback_slippage must not be negative".to_string());
+        let vol = &s.volatility_sizing;
+        check(vol.window >= 2, format!("strategy.volatility_sizing.window must be at least 2 (got {})", vol.window));
+        check(vol.reference_std > 0.0, "strategy.volatility_sizing.reference_std must be positive".to_string());
+        check(vol.min_scale > 0.0 && vol.min_scale <= 1.0, format!("strategy.volatility_sizing.min_scale must be within (0, 1] (got {})", vol.min_scale));
+        let streak = &s.streak_sizing;
+        check(streak.losses >= 1, "strategy.streak_sizing.losses must be at least 1".to_string());
+        check(streak.cut > 0.0 && streak.cut < 1.0, format!("strategy.streak_sizing.cut must be within (0, 1) (got {})", streak.cut));
+        check(streak.restore_step > 0.0, "strategy.streak_sizing.restore_step must be positive".to_string());
+        check(streak.min_scale > 0.0 && streak.min_scale <= 1.0, format!("strategy.streak_sizing.min_scale must be within (0, 1] (got {})", streak.min_scale));
+        let plugin = &s.plugin;
+        check(!plugin.enabled || !plugin.command.is_empty(), "strategy.plugin.command is required when the plugin is enabled".to_string());
+        check(plugin.timeout_ms >= 1, "strategy.plugin.timeout_ms must be at least 1".to_string());
+        check(plugin.interval_secs >= 1, "strategy.plugin.interval_secs must be at least 1".to_string());
+        for (key, weight) in &s.allocation.weights {
+            check(weight.is_finite() && *weight > 0.0, format!("strategy.allocation.weights.{} must be positive (got {})", key, weight));
+            let asset = key.split(':').next().unwrap_or_default();
+            check(
+                self.markets.iter().any(|m| m.asset.eq_ignore_ascii_case(asset)),
+                format!("strategy.allocation.weights.{}: {} is not in markets", key, asset),
+            );
+        }
+        check(
+            s.fees.maker_fee_rate >= 0.0 && s.fees.taker_fee_rate >= 0.0 && s.fees.fee_exponent >= 0.0,
+            "strategy.fees rates and fee_exponent must not be negative".to_string(),
+        );
+        check(s.redeem.min_redeem_usd >= 0.0, "strategy.redeem.min_redeem_usd must not be negative".to_string());
+        check(s.redeem.max_redeem_age_mins > 0, "strategy.redeem.max_redeem_age_mins must be positive".to_string());
+        if let Some(target) = s.treasury.target_bankroll_usd {
+            check(target > 0.0, "strategy.treasury.target_bankroll_usd must be positive".to_string());
+        }
+        check(s.treasury.min_excess_usd >= 0.0, "strategy.treasury.min_excess_usd must not be negative".to_string());
+        if s.treasury.transfer {
+            check(s.treasury.target_bankroll_usd.is_some(), "strategy.treasury.transfer needs target_bankroll_usd".to_string());
+            let cold_ok = s.treasury.cold_address.as_deref().is_some_and(|a| crate::api::parse_address(a).is_ok());
+            check(cold_ok, "strategy.treasury.transfer needs a valid cold_address (0x + 40 hex digits)".to_string());
+        }
+        check(s.resolution.retry_secs > 0, "strategy.resolution.retry_secs must be positive".to_string());
+        check(
+            s.resolution.max_retry_secs >= s.resolution.retry_secs,
+            "strategy.resolution.max_retry_secs must be at least retry_secs".to_string(),
+        );
+        check(
+            self.discovery.max_retry_secs >= self.discovery.retry_secs,
+            "discovery.max_retry_secs must be at least retry_secs".to_string(),
+        );
+        check(s.ev_min_usd >= 0.0, format!("strategy.ev_min_usd must not be negative (got {})", s.ev_min_usd));
+        for blackout in &s.blackouts {
+            if let Err(e) = crate::blackout::Cron::parse(&blackout.cron) {
+                check(false, format!("strategy.blackouts {:?}: {:#}", blackout.label(), e));
+            }
+            check(
+                (1..=24 * 60).contains(&blackout.duration_mins),
+                format!("strategy.blackouts {:?}: duration_mins must be 1 to 1440", blackout.label()),
+            );
+        }
+        if let Some(balance) = s.starting_balance_usd {
+            check(balance > 0.0, format!("strategy.starting_balance_usd must be positive (got {})", balance));
+        }
+        if s.paper_live {
+            check(!s.simulation_mode, "strategy.paper_live and strategy.simulation_mode can't both be on".to_string());
+            let signs = if self.accounts.is_empty() {
+                let p = &self.polymarket;
+                p.private_key.is_some() || p.private_key_file.is_some() || p.remote_signer.is_some()
+            } else {
+                self.accounts.iter().all(|a| a.private_key.is_some() || a.private_key_file.is_some() || a.remote_signer.is_some())
+            };
+            check(
+                signs,
+                "strategy.paper_live needs a private_key, private_key_file or remote_signer (on every account) to sign orders".to_string(),
+            );
+        }
+        check(s.fill_reconciliation.interval_mins > 0, "strategy.fill_reconciliation.interval_mins must be positive".to_string());
+        check(s.fill_reconciliation.lookback_hours > 0, "strategy.fill_reconciliation.lookback_hours must be positive".to_string());
+        if s.partial_fill.retry {
+            check(s.partial_fill.window_secs > 0, "strategy.partial_fill.window_secs must be positive".to_string());
+        }
+        if s.spike_sell.enabled {
+            let spike = &s.spike_sell;
+            check(is_price(spike.sell_threshold), format!("strategy.spike_sell.sell_threshold must be between 0 and 1 (got {})", spike.sell_threshold));
+            check(spike.final_secs > 0, "strategy.spike_sell.final_secs must be positive".to_string());
+            check(
+                spike.fraction > 0.0 && spike.fraction <= 1.0,
+                format!("strategy.spike_sell.fraction must be above 0 and at most 1 (got {})", spike.fraction),
+            );
+        }
+        check(s.rate_control.same_side_gap_secs >= 0.0, "strategy.rate_control.same_side_gap_secs must not be negative".to_string());
+        check(s.rate_control.order_gap_secs >= 0.0, "strategy.rate_control.order_gap_secs must not be negative".to_string());
+        check(
+            s.rate_control.max_orders_per_period.is_none_or(|max| max >= 2),
+            "strategy.rate_control.max_orders_per_period must be at least 2 (one Up/Down pair)".to_string(),
+        );
+        let assets = [self.traded_assets(), self.event_assets()].concat();
+        for (i, group) in s.risk.correlation_groups.iter().enumerate() {
+            check(group.assets.len() >= 2, format!("strategy.risk.correlation_groups[{}] needs at least two assets", i));
+            check(
+                group.max_same_side_usd > 0.0,
+                format!("strategy.risk.correlation_groups[{}].max_same_side_usd must be positive (got {})", i, group.max_same_side_usd),
+            );
+            for asset in group.assets.iter().filter(|a| !assets.iter().any(|c| c.eq_ignore_ascii_case(a))) {
+                check(false, format!("strategy.risk.correlation_groups[{}] lists {} which is not a traded 15m market", i, asset));
+            }
+        }
+        if let Some(limit) = s.risk.daily_loss_limit_usd {
+            check(limit >= 0.0, format!("strategy.risk.daily_loss_limit_usd must not be negative (got {})", limit));
+        }
+        if let Some(limit) = s.risk.max_drawdown_usd {
+            check(limit >= 0.0, format!("strategy.risk.max_drawdown_usd must not be negative (got {})", limit));
+        }
+        check(
+            self.profit.timezone.parse::<chrono_tz::Tz>().is_ok(),
+            format!("profit.timezone {:?} is not a known IANA timezone", self.profit.timezone),
+        );
+        for c in &self.calibration.checkpoints_secs {
+            check((1..900).contains(c), format!("calibration.checkpoints_secs must be between 1 and 899 (got {})", c));
+        }
+        for o in &self.dataset.offsets_secs {
+            check((0..900).contains(o), format!("dataset.offsets_secs must be between 0 and 899 (got {})", o));
+        }
+        check(self.dataset.sample_secs >= 1, "dataset.sample_secs must be at least 1".to_string());
+        check(self.control.signals.max_ttl_secs >= 1, "control.signals.max_ttl_secs must be at least 1".to_string());
+        check(
+            !self.control.enabled || self.control.is_loopback() || self.control.auth_token.as_deref().is_some_and(|t| !t.is_empty()),
+            format!("control.listen_addr {} is reachable from other machines; set control.auth_token or listen on 127.0.0.1", self.control.listen_addr),
+        );
+        let d = &self.display;
+        for (name, decimals) in [("usd_decimals", d.usd_decimals), ("price_decimals", d.price_decimals), ("share_decimals", d.share_decimals)] {
+            check(decimals <= 8, format!("display.{} must be at most 8 (got {})", name, decimals));
+        }
+        check(
+            !self.report.enabled || self.ledger.enabled,
+            "report.enabled needs ledger.enabled, since reports are built from the ledger".to_string(),
+        );
+        let net = &self.network;
+        check(net.request_timeout_ms > 0, "network.request_timeout_ms must be positive".to_string());
+        check(net.connect_timeout_ms > 0, "network.connect_timeout_ms must be positive".to_string());
+        check(net.max_orders_in_flight > 0, "network.max_orders_in_flight must be at least 1".to_string());
+        if let Some(proxy) = &net.proxy {
+            check(reqwest::Proxy::all(proxy).is_ok(), format!("network.proxy {:?} is not a valid proxy URL", proxy));
+        }
+        check(self.feed.max_age_ms > 0, "feed.max_age_ms must be positive".to_string());
+        check(
+            0.0 < self.monitor.min_ask_sum && self.monitor.min_ask_sum < self.monitor.max_ask_sum,
+            format!(
+                "monitor.min_ask_sum ({}) must be positive and below monitor.max_ask_sum ({})",
+                self.monitor.min_ask_sum, self.monitor.max_ask_sum
+            ),
+        );
+        check(self.monitor.full_book_every >= 1, "monitor.full_book_every must be at least 1".to_string());
+        if self.feed.data_source != DataSource::Rest {
+            check(
+                self.feed.ws_url.starts_with("ws://") || self.feed.ws_url.starts_with("wss://"),
+                format!("feed.ws_url {:?} must be a ws:// or wss:// URL", self.feed.ws_url),
+            );
+            check(self.feed.ws_stale_ms > 0, "feed.ws_stale_ms must be positive".to_string());
+        }
+        if self.feed.data_source == DataSource::Hybrid {
+            check(self.feed.hybrid_check_ms > 0, "feed.hybrid_check_ms must be positive".to_string());
+            check(
+                self.feed.max_divergence > 0.0 && self.feed.max_divergence < 1.0,
+                format!("feed.max_divergence ({}) must be between 0 and 1", self.feed.max_divergence),
+            );
+        }
+        check(self.clock.interval_mins > 0, "clock.interval_mins must be positive".to_string());
+        check(self.watchdog.stale_secs > 0, "watchdog.stale_secs must be positive".to_string());
+        check(self.watchdog.check_interval_secs > 0, "watchdog.check_interval_secs must be positive".to_string());
+        let p = &self.polymarket;
+        check(
+            key_sources(&p.private_key, &p.private_key_file, &p.remote_signer) <= 1,
+            "polymarket: set one of private_key, private_key_file and remote_signer".to_string(),
+        );
+        if let Some(signer) = &p.remote_signer {
+            for problem in signer.problems() {
+                check(false, format!("polymarket.remote_signer: {}", problem));
+            }
+        }
+        let mut names = std::collections::HashSet::new();
+        for account in &self.accounts {
+            check(!account.name.trim().is_empty(), "accounts: name must not be empty".to_string());
+            check(
+                key_sources(&account.private_key, &account.private_key_file, &account.remote_signer) <= 1,
+                format!("accounts: {} sets more than one of private_key, private_key_file and remote_signer; keep one", account.name),
+            );
+            if let Some(signer) = &account.remote_signer {
+                for problem in signer.problems() {
+                    check(false, format!("accounts: {} remote_signer: {}", account.name, problem));
+                }
+            }
+            check(names.insert(account.name.as_str()), format!("accounts: {} is listed more than once", account.name));
+            check(
+                account.size_fraction > 0.0,
+                format!("accounts: {} size_fraction must be positive (got {})", account.name, account.size_fraction),
+            );
+            check(
+                account.max_requests_per_sec.is_none_or(|r| r > 0.0),
+                format!("accounts: {} max_requests_per_sec must be positive", account.name),
+            );
+            for asset in &account.assets {
+                check(
+                    self.markets.iter().any(|m| m.asset.eq_ignore_ascii_case(asset)),
+                    format!("accounts: {} routes {} but no such market is configured", account.name, asset),
+                );
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        for market in &self.markets {
+            check(!market.asset.trim().is_empty(), "markets: asset must not be empty".to_string());
+            check(seen.insert(market.asset.to_uppercase()), format!("markets: {} is listed more than once", market.asset));
+            check(
+                market.condition_id.is_none() || market.slug.is_none(),
+                format!("markets: {} pins both condition_id and slug; set one", market.asset),
+            );
+            if market.family == MarketFamily::Event {
+                check(
+                    market.condition_id.is_some() || market.slug.is_some(),
+                    format!("markets: event market {} needs a condition_id or slug", market.asset),
+                );
+            }
+            if market.family == MarketFamily::Threshold && market.condition_id.is_none() && market.slug.is_none() {
+                check(
+                    market.tag_slug.is_some() && self.discovery.gamma_events,
+                    format!("markets: threshold market {} needs a tag_slug and discovery.gamma_events, or a pinned market", market.asset),
+                );
+            }
+            for (duration, template) in &market.slug_templates {
+                for placeholder in template.split('{').skip(1).filter_map(|rest| rest.split_once('}')).map(|(p, _)| p) {
+                    check(
+                        SLUG_PLACEHOLDERS.contains(&placeholder),
+                        format!("markets: {} {} slug template has unknown placeholder {{{}}}", market.asset, duration.label(), placeholder),
+                    );
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            anyhow::bail!("Invalid configuration:\n  - {}", errors.join("\n  - "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn profiles_override_settings_key_by_key() {
+        let mut base = json!({ "strategy": { "shares": 5, "price_limit": 0.45, "signal": { "danger_price": 0.28 } }, "markets": [{ "asset": "BTC" }, { "asset": "ETH" }] });
+        merge(&mut base, json!({ "strategy": { "shares": 20, "signal": { "danger_price": 0.2 } }, "markets": [{ "asset": "SOL" }] }));
+        assert_eq!(base, json!({ "strategy": { "shares": 20, "price_limit": 0.45, "signal": { "danger_price": 0.2 } }, "markets": [{ "asset": "SOL" }] }));
+
+        let loaded = serde_json::to_value(Config::default()).unwrap();
+        assert_eq!(unknown_key(&json!({ "strategy": { "shares": 20, "signal": { "danger_price": 0.2 } } }), &loaded, ""), None);
+        assert_eq!(unknown_key(&json!({ "strategy": { "signal": { "danger_prise": 0.2 } } }), &loaded, "").as_deref(), Some("strategy.signal.danger_prise"));
+
+        assert_eq!(profile_path(Path::new("conf/config.json"), "sim"), PathBuf::from("conf/config.sim.json"));
+        assert_eq!(profile_path(Path::new("config.json"), "profiles/fast.json"), PathBuf::from("profiles/fast.json"));
+    }
+
+    #[test]
+    fn a_control_server_off_loopback_needs_a_token() {
+        let mut config = Config::default();
+        for addr in ["127.0.0.1:9185", "[::1]:9185", "localhost:9185"] {
+            config.control.listen_addr = addr.to_string();
+            assert!(config.validate().is_ok(), "{}", addr);
+        }
+        for addr in ["0.0.0.0:9185", "192.168.1.20:9185", "bot.example.com:9185"] {
+            config.control.listen_addr = addr.to_string();
+            let error = config.validate().unwrap_err().to_string();
+            assert!(error.contains("control.auth_token"), "{}: {}", addr, error);
         }
+        config.control.auth_token = Some(String::new());
+        assert!(config.validate().is_err());
+        config.control.auth_token = Some("s3cret".to_string());
+        assert!(config.validate().is_ok());
+        config.control.auth_token = None;
+        config.control.enabled = false;
+        assert!(config.validate().is_ok());
     }
 }
@@ -0,0 +1,641 @@
+use crate::config::{Config, ControlConfig, MarketDuration};
+use crate::display;
+use crate::external::{ExternalSignal, SignalPost};
+use crate::strategy::PreLimitStrategy;
+use crate::watchdog::health;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::Duration;
+
+/// Largest request body read, enough for any JSON the API takes
+const MAX_BODY: usize = 64 * 1024;
+
+/// How long a client gets to send its whole request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the running bot reports on `GET /status`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusReport {
+    /// Unix seconds
+    pub generated_at: i64,
+    pub simulation_mode: bool,
+    /// Kill switch tripped; no new orders until `--resume`
+    pub trading_halted: bool,
+    pub realized_pnl: f64,
+    pub daily_realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    /// Closed-out positions waiting for the market to resolve and be redeemed
+    pub pending_redemptions: usize,
+    /// Resolved winners waiting for the next redemption batch, and their payout
+    #[serde(default)]
+    pub redeem_queue: usize,
+    #[serde(default)]
+    pub redeem_queue_usd: f64,
+    /// USDC available to trade (summed over accounts); None until read, and in simulation
+    #[serde(default)]
+    pub usdc_balance: Option<f64>,
+    /// Per-account breakdown when `accounts` are configured
+    #[serde(default)]
+    pub accounts: Vec<AccountStatus>,
+    pub markets: Vec<MarketStatus>,
+}
+
+/// One account's share of a combined report.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountStatus {
+    pub name: String,
+    pub usdc_balance: Option<f64>,
+    pub realized_pnl: f64,
+    pub daily_realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub trading_halted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStatus {
+    pub asset: String,
+    /// Account trading this market, when `accounts` are configured
+    #[serde(default)]
+    pub account: Option<String>,
+    /// New orders suspended through `POST /markets/{asset}/pause`
+    #[serde(default)]
+    pub paused: bool,
+    /// Live signal posted through `POST /signals`
+    #[serde(default)]
+    pub signal: Option<ExternalSignal>,
+    pub position: Option<PositionStatus>,
+    pub unrealized_pnl: f64,
+    /// Seconds until the one-sided position is sold under the "time" risk mode
+    pub danger_exit_in_secs: Option<i64>,
+    /// Seconds until the pre-order window for the next period opens (0 when open)
+    pub next_orders_in_secs: i64,
+    pub last_error: Option<MarketError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PositionStatus {
+    pub condition_id: String,
+    pub period_start: i64,
+    pub shares: f64,
+    pub up_filled: bool,
+    pub down_filled: bool,
+    pub up_price: f64,
+    pub down_price: f64,
+    /// Up + Down price including fees
+    pub cost_per_pair: f64,
+    pub sold_side: Option<String>,
+    /// Merged, sold or otherwise finished; only waiting on resolution
+    pub closed: bool,
+    /// Payout minus cost (after fees) of the filled sides if Up / Down wins; None once closed
+    #[serde(default)]
+    pub pnl_if_up_wins: Option<f64>,
+    #[serde(default)]
+    pub pnl_if_down_wins: Option<f64>,
+}
+
+/// Most recent error while processing a market.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketError {
+    /// Unix seconds
+    pub at: i64,
+    pub message: String,
+}
+
+impl StatusReport {
+    /// Status across every running strategy: one per account, or just the default one.
+    pub async fn collect(strategies: &[Arc<PreLimitStrategy>]) -> StatusReport {
+        let mut reports = Vec::with_capacity(strategies.len());
+        for strategy in strategies {
+            reports.push((strategy.account().map(str::to_string), strategy.status().await));
+        }
+        if reports.len() == 1 && reports[0].0.is_none() {
+            return reports.remove(0).1;
+        }
+        let mut combined = StatusReport {
+            generated_at: Utc::now().timestamp(),
+            simulation_mode: reports.iter().any(|(_, r)| r.simulation_mode),
+            trading_halted: reports.iter().any(|(_, r)| r.trading_halted),
+            realized_pnl: 0.0,
+            daily_realized_pnl: 0.0,
+            unrealized_pnl: 0.0,
+            pending_redemptions: 0,
+            redeem_queue: 0,
+            redeem_queue_usd: 0.0,
+            usdc_balance: None,
+            accounts: Vec::new(),
+            markets: Vec::new(),
+        };
+        for (name, r) in reports {
+            combined.realized_pnl += r.realized_pnl;
+            combined.daily_realized_pnl += r.daily_realized_pnl;
+            combined.unrealized_pnl += r.unrealized_pnl;
+            combined.pending_redemptions += r.pending_redemptions;
+            combined.redeem_queue += r.redeem_queue;
+            combined.redeem_queue_usd += r.redeem_queue_usd;
+            if let Some(balance) = r.usdc_balance {
+                *combined.usdc_balance.get_or_insert(0.0) += balance;
+            }
+            combined.accounts.push(AccountStatus {
+                name: name.unwrap_or_default(),
+                usdc_balance: r.usdc_balance,
+                realized_pnl: r.realized_pnl,
+                daily_realized_pnl: r.daily_realized_pnl,
+                unrealized_pnl: r.unrealized_pnl,
+                trading_halted: r.trading_halted,
+            });
+            combined.markets.extend(r.markets);
+        }
+        combined
+    }
+
+    pub fn print(&self) {
+        let now = Utc::now().timestamp();
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("📋 Bot status{}", if self.simulation_mode { " (🎮 simulation)" } else { "" });
+        eprintln!("   realized PnL          {} (today {})", display::usd(self.realized_pnl), display::usd(self.daily_realized_pnl));
+        eprintln!("   unrealized PnL        {}", display::usd(self.unrealized_pnl));
+        eprintln!("   kill switch           {}", if self.trading_halted { "🛑 halted" } else { "off" });
+        eprintln!("   pending redemptions   {}", self.pending_redemptions);
+        eprintln!("   redeem queue          {} ({})", self.redeem_queue, display::usd(self.redeem_queue_usd));
+        if let Some(balance) = self.usdc_balance {
+            eprintln!("   USDC balance          {}", display::usd(balance));
+        }
+        for a in &self.accounts {
+            eprintln!(
+                "   account {:<12} realized {} (today {}) | unrealized {}{}{}",
+                a.name, display::usd(a.realized_pnl), display::usd(a.daily_realized_pnl), display::usd(a.unrealized_pnl),
+                a.usdc_balance.map(|b| format!(" | balance {}", display::usd(b))).unwrap_or_default(),
+                if a.trading_halted { " | 🛑 halted" } else { "" }
+            );
+        }
+        for m in &self.markets {
+            let asset = match &m.account {
+                Some(account) => format!("{}@{}", m.asset, account),
+                None => m.asset.clone(),
+            };
+            match &m.position {
+                Some(p) => eprintln!(
+                    "   {:<5} period {} | Up:{} Down:{} | {} | pair ${:.4} | unrealized {}{}",
+                    asset, p.period_start,
+                    if p.up_filled { "✓" } else { "✗" },
+                    if p.down_filled { "✓" } else { "✗" },
+                    display::size(p.shares, p.cost_per_pair), p.cost_per_pair, display::pnl(m.unrealized_pnl, p.shares),
+                    match (&p.sold_side, p.closed) {
+                        (Some(side), _) => format!(" | sold {}", side),
+                        (None, true) => " | closed".to_string(),
+                        (None, false) => String::new(),
+                    }
+                ),
+                None => eprintln!("   {:<5} no position", asset),
+            }
+            if m.paused {
+                eprintln!("         ⏸️  paused — no new orders");
+            }
+            if let Some(signal) = &m.signal {
+                eprintln!("         📡 external {:?} bias for {}s more{}", signal.bias, (signal.expires_at - now).max(0),
+                    signal.source.as_ref().map(|s| format!(" (from {})", s)).unwrap_or_default());
+            }
+            if let Some(secs) = m.danger_exit_in_secs {
+                eprintln!("         ⏱️  danger exit in {}s", secs);
+            }
+            if m.next_orders_in_secs > 0 {
+                eprintln!("         next pre-orders in {}s", m.next_orders_in_secs);
+            }
+            if let Some(e) = &m.last_error {
+                eprintln!("         ⚠️  last error {}s ago: {}", now - e.at, e.message);
+            }
+        }
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+}
+
+/// Serves the control endpoints on `listen_addr` until the process exits:
+///
+/// - `GET /status`, `GET /positions`, `GET /pnl`, `GET /config`
+/// - `GET /healthz` (no token needed): 503 when the watchdog finds a market, strategy loop or the API stuck
+/// - `POST /markets/{asset}/pause`, `POST /markets/{asset}/resume` (`?timeframe=` defaults to 15m)
+/// - `POST /redeem` (resolve and redeem finished positions now) or `POST /redeem?condition_id=0x...`
+///   (`&account=` picks the wallet when `accounts` are configured)
+/// - `GET /signals`, `POST /signals` (JSON `SignalPost` body), `DELETE /signals/{asset}` with
+///   `control.signals.enabled`
+///
+/// `strategies` holds one strategy per account; `bot_config` is what `GET /config` shows.
+pub async fn serve(config: ControlConfig, bot_config: Config, strategies: Vec<Arc<PreLimitStrategy>>) -> Result<()> {
+    let bot_config = Arc::new(bot_config.redacted());
+    let strategies = Arc::new(strategies);
+    let listener = TcpListener::bind(&config.listen_addr)
+        .await
+        .context(format!("Failed to bind control listener on {}", config.listen_addr))?;
+    log::info!("Control server listening on {}", config.listen_addr);
+    loop {
+        let (socket, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::warn!("Control listener accept failed: {}", e);
+                continue;
+            }
+        };
+        let strategies = Arc::clone(&strategies);
+        let bot_config = Arc::clone(&bot_config);
+        let auth_token = config.auth_token.clone();
+        tokio::spawn(async move {
+            handle(socket, &strategies, &bot_config, auth_token.as_deref(), READ_TIMEOUT).await;
+        });
+    }
+}
+
+/// Answers one connection. A client that hasn't sent its request within `read_timeout` gets a
+/// 408, so a silent connection doesn't hold its task.
+async fn handle(mut socket: TcpStream, strategies: &[Arc<PreLimitStrategy>], bot_config: &Config, auth_token: Option<&str>, read_timeout: Duration) {
+    let (status, body) = match tokio::time::timeout(read_timeout, read_request(&mut socket)).await {
+        Ok(request) => {
+            let local = socket.peer_addr().is_ok_and(|peer| peer.ip().is_loopback());
+            respond(strategies, bot_config, auth_token, local, &request).await
+        }
+        Err(_) => ("408 Request Timeout", error_body("request not received in time")),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = socket.write_all(response.as_bytes()).await;
+}
+
+/// `local` is whether the client connected from this machine. Without an `auth_token`, signals
+/// are only taken from local clients, whatever address the server listens on.
+async fn respond(strategies: &[Arc<PreLimitStrategy>], bot_config: &Config, auth_token: Option<&str>, local: bool, request: &str) -> (&'static str, String) {
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    // Liveness probes carry no token; /healthz reveals no positions or config
+    if request.starts_with("GET /healthz ") {
+        healthz(bot_config.watchdog.stale_secs)
+    } else if !authorized(request, auth_token) {
+        ("401 Unauthorized", error_body("missing or wrong bearer token"))
+    } else if auth_token.is_none() && !local && path.starts_with("/signals") {
+        ("403 Forbidden", error_body("signals are only taken from this machine unless control.auth_token is set"))
+    } else {
+        route(strategies, bot_config, request).await
+    }
+}
+
+/// Reads one request: the head, and the body up to its `Content-Length`.
+async fn read_request(socket: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = socket.read(&mut buf).await.unwrap_or(0);
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+        if let Some(head_end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            let head = String::from_utf8_lossy(&request[..head_end]);
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length").then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if request.len() >= head_end + 4 + length.min(MAX_BODY) {
+                break;
+            }
+        }
+        if request.len() > MAX_BODY + buf.len() {
+            break;
+        }
+    }
+    String::from_utf8_lossy(&request).into_owned()
+}
+
+async fn route(strategies: &[Arc<PreLimitStrategy>], bot_config: &Config, request: &str) -> (&'static str, String) {
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["status"]) => ok_json(&StatusReport::collect(strategies).await),
+        ("GET", ["positions"]) => ok_json(&StatusReport::collect(strategies).await.markets),
+        ("GET", ["pnl"]) => {
+            let report = StatusReport::collect(strategies).await;
+            let mut period_pnl = 0.0;
+            for strategy in strategies {
+                period_pnl += strategy.get_period_profit().await;
+            }
+            ok_json(&serde_json::json!({
+                "realized_pnl": report.realized_pnl,
+                "daily_realized_pnl": report.daily_realized_pnl,
+                "period_pnl": period_pnl,
+                "unrealized_pnl": report.unrealized_pnl,
+                "accounts": report.accounts,
+            }))
+        }
+        ("GET", ["state"]) => {
+            let mut states = Vec::with_capacity(strategies.len());
+            for strategy in strategies {
+                states.push(strategy.get_state().await);
+            }
+            ok_json(&states)
+        }
+        ("GET", ["config"]) => ok_json(bot_config),
+        ("POST", ["markets", asset, action @ ("pause" | "resume")]) => {
+            let paused = *action == "pause";
+            let timeframe = match query_param(query, "timeframe") {
+                Some(tf) => match MarketDuration::from_str(tf, true) {
+                    Ok(tf) => tf,
+                    Err(_) => return ("400 Bad Request", error_body(&format!("unknown timeframe {}", tf))),
+                },
+                None => MarketDuration::M15,
+            };
+            let mut found = false;
+            for strategy in strategies {
+                found |= strategy.set_paused(asset, timeframe, paused);
+            }
+            if found {
+                log::warn!("{} {} {} through the control API",
+                    if paused { "⏸️  Paused" } else { "▶️  Resumed" }, asset, timeframe.label());
+                ok_json(&serde_json::json!({
+                    "asset": asset.to_uppercase(),
+                    "timeframe": timeframe.label(),
+                    "paused": paused,
+                }))
+            } else {
+                ("404 Not Found", error_body(&format!("no {} {} market configured", asset, timeframe.label())))
+            }
+        }
+        ("POST", ["redeem"]) => {
+            let condition_id = query_param(query, "condition_id");
+            let targets: Vec<&Arc<PreLimitStrategy>> = match query_param(query, "account") {
+                Some(name) => strategies.iter().filter(|s| s.account() == Some(name)).collect(),
+                None if condition_id.is_some() && strategies.len() > 1 => {
+                    return ("400 Bad Request", error_body("pass &account= to redeem a condition with several accounts"));
+                }
+                None => strategies.iter().collect(),
+            };
+            if targets.is_empty() {
+                return ("404 Not Found", error_body("no such account"));
+            }
+            for strategy in targets {
+                if let Err(e) = strategy.redeem_now(condition_id).await {
+                    return ("400 Bad Request", error_body(&format!("{:#}", e)));
+                }
+            }
+            ok_json(&serde_json::json!({
+                "pending_redemptions": StatusReport::collect(strategies).await.pending_redemptions,
+            }))
+        }
+        ("GET" | "POST" | "DELETE", ["signals", ..]) if !bot_config.control.signals.enabled => {
+            ("404 Not Found", error_body("external signals are off (control.signals.enabled)"))
+        }
+        ("GET", ["signals"]) => {
+            let mut signals: Vec<ExternalSignal> = strategies.iter().flat_map(|s| s.external_signals()).collect();
+            // Every account trading a market keeps its own copy
+            signals.sort_by(|a, b| a.asset.cmp(&b.asset));
+            signals.dedup();
+            ok_json(&signals)
+        }
+        ("POST", ["signals"]) => {
+            let body = request.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+            let post: SignalPost = match serde_json::from_str(body) {
+                Ok(post) => post,
+                Err(e) => return ("400 Bad Request", error_body(&format!("invalid signal: {}", e))),
+            };
+            let mut stored = None;
+            for strategy in strategies {
+                match strategy.post_signal(&post) {
+                    Ok(Some(signal)) => stored = Some(signal),
+                    Ok(None) => {}
+                    Err(e) => return ("400 Bad Request", error_body(&format!("{:#}", e))),
+                }
+            }
+            match stored {
+                Some(signal) => {
+                    log::warn!("📡 {} | External {:?} bias for {}s{}", signal.asset, signal.bias, signal.expires_at - signal.received_at,
+                        signal.source.as_ref().map(|s| format!(" from {}", s)).unwrap_or_default());
+                    ok_json(&signal)
+                }
+                None => ("404 Not Found", error_body(&format!("no {} market configured", post.asset))),
+            }
+        }
+        ("DELETE", ["signals", asset]) => {
+            let mut cleared = false;
+            for strategy in strategies {
+                cleared |= strategy.clear_signal(asset);
+            }
+            if cleared {
+                log::warn!("📡 {} | External signal withdrawn", asset.to_uppercase());
+                ok_json(&serde_json::json!({ "asset": asset.to_uppercase(), "cleared": true }))
+            } else {
+                ("404 Not Found", error_body(&format!("no signal for {}", asset)))
+            }
+        }
+        _ => ("404 Not Found", error_body("not found")),
+    }
+}
+
+/// 200 while every watched market, strategy loop and the API are live, 503 otherwise.
+fn healthz(stale_secs: u64) -> (&'static str, String) {
+    let report = health().report(stale_secs);
+    match ok_json(&report) {
+        ("200 OK", body) if !report.healthy => ("503 Service Unavailable", body),
+        response => response,
+    }
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|kv| kv.strip_prefix(name)?.strip_prefix('='))
+}
+
+/// Whether the request carries `token` as its bearer token, compared in constant time.
+fn authorized(request: &str, token: Option<&str>) -> bool {
+    let Some(token) = token else { return true };
+    request.lines().any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.eq_ignore_ascii_case("authorization")
+                && value.trim().strip_prefix("Bearer ").is_some_and(|given| bool::from(given.as_bytes().ct_eq(token.as_bytes())))
+        })
+    })
+}
+
+fn ok_json<T: Serialize>(value: &T) -> (&'static str, String) {
+    match serde_json::to_string(value) {
+        Ok(body) => ("200 OK", body),
+        Err(e) => ("500 Internal Server Error", error_body(&e.to_string())),
+    }
+}
+
+fn error_body(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// Asks the running bot for its status.
+pub async fn fetch_status(config: &ControlConfig) -> Result<StatusReport> {
+    let body = call(config, reqwest::Method::GET, "/status").await?;
+    serde_json::from_value(body).context("Failed to parse status response")
+}
+
+/// Pauses or resumes new orders for one market of the running bot.
+pub async fn set_paused(config: &ControlConfig, asset: &str, timeframe: MarketDuration, paused: bool) -> Result<()> {
+    let action = if paused { "pause" } else { "resume" };
+    let path = format!("/markets/{}/{}?timeframe={}", asset, action, timeframe.label());
+    call(config, reqwest::Method::POST, &path).await.map(|_| ())
+}
+
+async fn call(config: &ControlConfig, method: reqwest::Method, path: &str) -> Result<serde_json::Value> {
+    let url = format!("http://{}{}", config.listen_addr, path);
+    let mut request = reqwest::Client::new().request(method, &url);
+    if let Some(token) = &config.auth_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .context(format!("No bot answering on {} (is it running with control.enabled?)", config.listen_addr))?;
+    let status = response.status();
+    let body: serde_json::Value = response.json().await.context("Failed to parse control response")?;
+    if !status.is_success() {
+        anyhow::bail!("{} ({})", body.get("error").and_then(|e| e.as_str()).unwrap_or("request failed"), status);
+    }
+    Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::feed::MarketFeed;
+    use crate::mockapi::MockServer;
+
+    const TOKEN: &str = "s3cret";
+
+    fn bot_config() -> Config {
+        let mut config = Config::default();
+        config.strategy.simulation_mode = true;
+        config.ledger.enabled = false;
+        config.strategy.risk.kill_switch_file = std::env::temp_dir().join(format!("polymarket-bot-control-{}.kill", std::process::id()));
+        config.control.auth_token = Some(TOKEN.to_string());
+        config
+    }
+
+    fn strategies(server: &MockServer, config: &Config) -> Vec<Arc<PreLimitStrategy>> {
+        let api = Arc::new(server.api());
+        let feed = Arc::new(MarketFeed::new(Arc::clone(&api), config));
+        vec![Arc::new(PreLimitStrategy::new(api, feed, config.clone()))]
+    }
+
+    fn request(method: &str, target: &str, token: Option<&str>) -> String {
+        let auth = token.map(|t| format!("Authorization: Bearer {}\r\n", t)).unwrap_or_default();
+        format!("{} {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n", method, target, auth)
+    }
+
+    fn btc_paused(body: &str) -> bool {
+        let report: StatusReport = serde_json::from_str(body).unwrap();
+        report.markets.iter().find(|m| m.asset == "BTC").unwrap().paused
+    }
+
+    #[test]
+    fn bearer_tokens_must_match_exactly() {
+        let with = |value: &str| format!("GET /status HTTP/1.1\r\nauthorization: {}\r\n\r\n", value);
+        assert!(authorized(&with("Bearer s3cret"), Some(TOKEN)));
+        for value in ["Bearer s3cre", "Bearer s3cret2", "Bearer S3CRET", "Basic s3cret", "s3cret", "Bearer "] {
+            assert!(!authorized(&with(value), Some(TOKEN)), "{}", value);
+        }
+        assert!(authorized(&request("GET", "/status", None), None));
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_token_except_healthz() {
+        let server = MockServer::start().await.unwrap();
+        let config = bot_config();
+        let strategies = strategies(&server, &config);
+
+        for token in [None, Some("wrong")] {
+            let (status, _) = respond(&strategies, &config, Some(TOKEN), false, &request("GET", "/status", token)).await;
+            assert_eq!(status, "401 Unauthorized");
+        }
+        let (status, _) = respond(&strategies, &config, Some(TOKEN), false, &request("GET", "/status", Some(TOKEN))).await;
+        assert_eq!(status, "200 OK");
+        let (status, _) = respond(&strategies, &config, Some(TOKEN), false, &request("GET", "/healthz", None)).await;
+        assert_ne!(status, "401 Unauthorized");
+    }
+
+    #[tokio::test]
+    async fn pause_and_resume_change_the_market_state() {
+        let server = MockServer::start().await.unwrap();
+        let config = bot_config();
+        let strategies = strategies(&server, &config);
+        let call = |method: &'static str, target: &'static str| {
+            let (strategies, config) = (&strategies, &config);
+            async move { respond(strategies, config, Some(TOKEN), false, &request(method, target, Some(TOKEN))).await }
+        };
+
+        assert!(!btc_paused(&call("GET", "/status").await.1));
+        let (status, body) = call("POST", "/markets/btc/pause").await;
+        assert_eq!(status, "200 OK");
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&body).unwrap()["paused"], true);
+        assert!(btc_paused(&call("GET", "/status").await.1));
+        assert_eq!(call("POST", "/markets/btc/resume").await.0, "200 OK");
+        assert!(!btc_paused(&call("GET", "/status").await.1));
+
+        assert_eq!(call("POST", "/markets/doge/pause").await.0, "404 Not Found");
+        assert_eq!(call("POST", "/markets/btc/pause?timeframe=2h").await.0, "400 Bad Request");
+        assert_eq!(call("GET", "/markets/btc/pause").await.0, "404 Not Found");
+        assert_eq!(call("GET", "/signals").await.0, "404 Not Found");
+        assert_eq!(call("GET", "/nothing").await.0, "404 Not Found");
+        assert_eq!(call("GET", "/pnl").await.0, "200 OK");
+    }
+
+    #[tokio::test]
+    async fn without_a_token_signals_are_only_taken_from_this_machine() {
+        let server = MockServer::start().await.unwrap();
+        let mut config = bot_config();
+        config.control.signals.enabled = true;
+        let strategies = strategies(&server, &config);
+        let post = |token: Option<&str>| {
+            let mut request = request("POST", "/signals", token);
+            request.push_str(r#"{"asset":"BTC","bias":"up","ttl_secs":60}"#);
+            request
+        };
+
+        let (status, _) = respond(&strategies, &config, None, false, &post(None)).await;
+        assert_eq!(status, "403 Forbidden");
+        let (status, _) = respond(&strategies, &config, None, false, &request("DELETE", "/signals/btc", None)).await;
+        assert_eq!(status, "403 Forbidden");
+        assert!(strategies[0].external_signals().is_empty());
+
+        let (status, _) = respond(&strategies, &config, None, true, &post(None)).await;
+        assert_eq!(status, "200 OK");
+        let (status, _) = respond(&strategies, &config, Some(TOKEN), false, &post(Some(TOKEN))).await;
+        assert_eq!(status, "200 OK");
+        assert_eq!(strategies[0].external_signals().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn silent_clients_time_out() {
+        let server = MockServer::start().await.unwrap();
+        let config = bot_config();
+        let strategies = strategies(&server, &config);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+
+        // Connected, but never sends a request
+        let answered = tokio::time::timeout(
+            Duration::from_secs(5),
+            handle(socket, &strategies, &config, Some(TOKEN), Duration::from_millis(50)),
+        )
+        .await;
+        assert!(answered.is_ok(), "the read was not cut off");
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 408"), "{}", response);
+    }
+}
@@ -0,0 +1,373 @@
+use crate::api::{self, PolymarketApi};
+use crate::calibration;
+use crate::config::{Config, DatasetConfig, MarketDuration, MarketFamily, TrendConfig};
+use crate::feed::MarketFeed;
+use crate::models::{MarketSnapshot, PriceLevel};
+use crate::momentum::SpotCandles;
+use crate::monitor::MarketMonitor;
+use crate::scheduler::PeriodScheduler;
+use crate::signals;
+use crate::trend::{PriceHistory, Trend};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration, Instant};
+
+/// Name the dataset recorder runs under.
+pub const TASK: &str = "dataset";
+
+/// An offset is read up to this long after it passes; later prices are from another time.
+const OFFSET_GRACE_SECS: i64 = 30;
+const POLL_SECS: u64 = 2;
+const RESOLUTION_CHECK_SECS: u64 = 30;
+/// Periods still unresolved this long after they end are dropped.
+const GIVE_UP_SECS: i64 = 24 * 60 * 60;
+/// Order book levels per side in the imbalance feature
+const BOOK_LEVELS: usize = 5;
+
+/// Names of the `Features` fields, as `strategy.signal.model.features` lists them.
+pub const FEATURES: &[&str] = &[
+    "secs_into_period", "up_price", "down_price", "up_mid", "implied_up", "up_spread", "up_change",
+    "trend", "up_std_dev", "imbalance", "volume_24h", "spot_progress",
+];
+
+/// Features of one 15m market at `secs_into_period`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Features {
+    pub secs_into_period: i64,
+    /// Quoted Up and Down prices the strategy trades on
+    pub up_price: f64,
+    pub down_price: f64,
+    pub up_mid: f64,
+    /// Up probability implied by the two mids
+    pub implied_up: f64,
+    /// Up best ask minus best bid
+    pub up_spread: Option<f64>,
+    /// Up mid now minus at the period's first sample
+    pub up_change: f64,
+    /// Up trend under `strategy.signal.trend` (always flat with `trend_mode` off)
+    pub trend: Trend,
+    /// Standard deviation of the Up mid over the period's samples so far
+    pub up_std_dev: Option<f64>,
+    /// (bid size - ask size) / total over the top levels of the Up book, in -1..1
+    pub imbalance: Option<f64>,
+    pub volume_24h: Option<f64>,
+    /// Spot candle move from its open in typical 15m ranges, when spot candles are read
+    pub spot_progress: Option<f64>,
+}
+
+impl Features {
+    /// The feature called `name` as a number (`trend` is 1 rising, -1 falling, 0 flat); `None`
+    /// when it is missing or unknown.
+    pub fn value(&self, name: &str) -> Option<f64> {
+        match name {
+            "secs_into_period" => Some(self.secs_into_period as f64),
+            "up_price" => Some(self.up_price),
+            "down_price" => Some(self.down_price),
+            "up_mid" => Some(self.up_mid),
+            "implied_up" => Some(self.implied_up),
+            "up_spread" => self.up_spread,
+            "up_change" => Some(self.up_change),
+            "trend" => Some(match self.trend {
+                Trend::Rising => 1.0,
+                Trend::Falling => -1.0,
+                Trend::Flat => 0.0,
+            }),
+            "up_std_dev" => self.up_std_dev,
+            "imbalance" => self.imbalance,
+            "volume_24h" => self.volume_24h,
+            "spot_progress" => self.spot_progress,
+            _ => None,
+        }
+    }
+}
+
+/// A market's features at one offset into a period, labeled with how the period resolved.
+/// One JSON line per row in `dataset.path`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetRow {
+    pub market: String,
+    pub condition_id: String,
+    pub period_timestamp: i64,
+    #[serde(flatten)]
+    pub features: Features,
+    pub up_won: bool,
+}
+
+struct Samples {
+    history: PriceHistory,
+    open_mid: f64,
+    last_sample: i64,
+}
+
+/// Up mid samples of each market period, taken every `sample_secs`, that the change, trend and
+/// volatility features are read from. The dataset recorder and the model gate both read
+/// features through one of these, so a model sees what it was trained on.
+pub struct FeatureSampler {
+    sample_secs: i64,
+    trend: TrendConfig,
+    periods: HashMap<(String, i64), Samples>,
+}
+
+impl FeatureSampler {
+    pub fn new(sample_secs: u64, trend: TrendConfig) -> Self {
+        Self { sample_secs: sample_secs as i64, trend, periods: HashMap::new() }
+    }
+
+    /// Whether a sample of the period starting at `period_start` is due at `now`.
+    pub fn sample_due(&self, asset: &str, period_start: i64, now: i64) -> bool {
+        let last = self.periods.get(&(asset.to_string(), period_start)).map(|s| s.last_sample);
+        last.is_none_or(|t| now - t >= self.sample_secs)
+    }
+
+    /// Adds the Up mid of `snapshot` to its period's samples and returns the market's features at `now`.
+    pub fn observe(&mut self, snapshot: &MarketSnapshot, now: i64, spot_progress: Option<f64>) -> Features {
+        let mid = |t: &crate::models::TokenSnapshot| t.mid.unwrap_or(t.price);
+        let (up_mid, down_mid) = (mid(&snapshot.up), mid(&snapshot.down));
+        let capacity = (MarketDuration::M15.nominal_secs() / self.sample_secs) as usize + 1;
+        let samples = self.periods.entry((snapshot.asset.clone(), snapshot.period_start)).or_insert_with(|| Samples {
+            history: PriceHistory::default(),
+            open_mid: up_mid,
+            last_sample: now,
+        });
+        samples.history.push(snapshot.period_start, snapshot.timestamp_ms, up_mid, capacity.max(self.trend.window));
+        samples.last_sample = now;
+        Features {
+            secs_into_period: now - snapshot.period_start,
+            up_price: snapshot.up.price,
+            down_price: snapshot.down.price,
+            up_mid,
+            implied_up: signals::implied_probabilities(up_mid, down_mid).0,
+            up_spread: snapshot.up.best_ask.zip(snapshot.up.best_bid).map(|(ask, bid)| ask - bid),
+            up_change: up_mid - samples.open_mid,
+            trend: samples.history.trend(&self.trend),
+            up_std_dev: samples.history.std_dev(capacity),
+            imbalance: imbalance(&snapshot.up.bids, &snapshot.up.asks),
+            volume_24h: snapshot.volume_24h,
+            spot_progress,
+        }
+    }
+
+    /// Forgets the samples of periods that ended by `now`.
+    pub fn prune(&mut self, now: i64) {
+        self.periods.retain(|(_, start), _| start + MarketDuration::M15.nominal_secs() > now);
+    }
+}
+
+struct OpenPeriod {
+    up_token_id: String,
+    rows: Vec<DatasetRow>,
+}
+
+/// Periods with rows waiting on their resolution, by (asset, period start).
+struct Tracker {
+    offsets: Vec<i64>,
+    sampler: FeatureSampler,
+    open: HashMap<(String, i64), OpenPeriod>,
+}
+
+impl Tracker {
+    fn new(cfg: &DatasetConfig, trend: TrendConfig) -> Self {
+        Self { offsets: cfg.offsets_secs.clone(), sampler: FeatureSampler::new(cfg.sample_secs, trend), open: HashMap::new() }
+    }
+
+    /// Offsets of the period starting at `period_start` that are due at `now` and not taken yet.
+    fn due(&self, asset: &str, period_start: i64, now: i64) -> Vec<i64> {
+        let period = self.open.get(&(asset.to_string(), period_start));
+        self.offsets
+            .iter()
+            .copied()
+            .filter(|o| (period_start + o..period_start + o + OFFSET_GRACE_SECS).contains(&now))
+            .filter(|o| !period.is_some_and(|p| p.rows.iter().any(|r| r.features.secs_into_period == *o)))
+            .collect()
+    }
+
+    fn sample_due(&self, asset: &str, period_start: i64, now: i64) -> bool {
+        self.sampler.sample_due(asset, period_start, now)
+    }
+
+    /// Adds `snapshot` to its period's samples and takes a row for each of the `due` offsets.
+    fn observe(&mut self, snapshot: &MarketSnapshot, now: i64, due: &[i64], spot_progress: Option<f64>) {
+        let features = self.sampler.observe(snapshot, now, spot_progress);
+        if due.is_empty() {
+            return;
+        }
+        let period = self
+            .open
+            .entry((snapshot.asset.clone(), snapshot.period_start))
+            .or_insert_with(|| OpenPeriod { up_token_id: snapshot.up.token_id.clone(), rows: Vec::new() });
+        for offset in due {
+            period.rows.push(DatasetRow {
+                market: snapshot.asset.clone(),
+                condition_id: snapshot.condition_id.clone(),
+                period_timestamp: snapshot.period_start,
+                features: Features { secs_into_period: *offset, ..features.clone() },
+                up_won: false,
+            });
+        }
+    }
+
+    /// Periods with rows that ended before `now`, dropping those given up on.
+    fn ended(&mut self, now: i64) -> Vec<((String, i64), String, String)> {
+        self.sampler.prune(now);
+        let end = |start: i64| start + MarketDuration::M15.nominal_secs();
+        self.open.retain(|(asset, start), _| {
+            let keep = now - end(*start) < GIVE_UP_SECS;
+            if !keep {
+                log::warn!("Dataset: {} period {} still unresolved after a day — dropped", asset, start);
+            }
+            keep
+        });
+        self.open
+            .iter()
+            .filter(|((_, start), _)| end(*start) <= now)
+            .map(|(key, p)| (key.clone(), p.rows[0].condition_id.clone(), p.up_token_id.clone()))
+            .collect()
+    }
+
+    fn finish(&mut self, key: &(String, i64), up_won: bool) -> Vec<DatasetRow> {
+        let mut rows = self.open.remove(key).map(|p| p.rows).unwrap_or_default();
+        for row in &mut rows {
+            row.up_won = up_won;
+        }
+        rows
+    }
+}
+
+/// (bid size - ask size) / total over the top `BOOK_LEVELS` of each side; `None` on an empty book.
+fn imbalance(bids: &[PriceLevel], asks: &[PriceLevel]) -> Option<f64> {
+    let depth = |levels: &[PriceLevel]| levels.iter().take(BOOK_LEVELS).map(|l| l.size).sum::<f64>();
+    let (bid, ask) = (depth(bids), depth(asks));
+    (bid + ask > 0.0).then(|| (bid - ask) / (bid + ask))
+}
+
+fn append(path: &Path, rows: &[DatasetRow]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    for row in rows {
+        writeln!(file, "{}", serde_json::to_string(row)?)?;
+    }
+    Ok(())
+}
+
+/// Samples every asset's current 15m market every `dataset.sample_secs`, takes a row at each of
+/// `dataset.offsets_secs` and appends the period's rows to `dataset.path` once it resolves,
+/// until shutdown.
+pub async fn run(api: Arc<PolymarketApi>, feed: Arc<MarketFeed>, config: Config, scheduler: Arc<PeriodScheduler>, mut shutdown: watch::Receiver<bool>) {
+    let cfg = config.dataset.clone();
+    let assets = config.assets_for(MarketDuration::M15);
+    let monitor = MarketMonitor::new(Arc::clone(&api), feed, &config);
+    let spot = cfg.spot.then(|| {
+        SpotCandles::new(api::http_client(&config.network).expect("Failed to create HTTP client"), config.strategy.signal.momentum.clone())
+    });
+    let spot_symbol = |asset: &str| {
+        config.markets.iter().find(|m| m.asset == asset && m.family == MarketFamily::UpDown).map(|m| m.spot_symbol())
+    };
+    let mut tracker = Tracker::new(&cfg, config.strategy.signal.trend.clone());
+    let mut last_resolution_check = Instant::now();
+    eprintln!("🧮 Recording the feature dataset to {}", cfg.path.display());
+    while !*shutdown.borrow() {
+        let now = crate::clock::now();
+        for asset in &assets {
+            let Some(period_start) = scheduler.current(asset, MarketDuration::M15) else { continue };
+            let due = tracker.due(asset, period_start, now);
+            if due.is_empty() && !tracker.sample_due(asset, period_start, now) {
+                continue;
+            }
+            let snapshot = match monitor.snapshot(asset, period_start).await {
+                Ok(snapshot) if snapshot.invalid.is_none() => snapshot,
+                Ok(_) => continue,
+                Err(e) => {
+                    log::debug!("Dataset: no {} snapshot for period {}: {}", asset, period_start, e);
+                    continue;
+                }
+            };
+            let spot_progress = match (&spot, spot_symbol(asset)) {
+                (Some(spot), Some(symbol)) if !due.is_empty() => spot.progress(&symbol, period_start).await,
+                _ => None,
+            };
+            tracker.observe(&snapshot, now, &due, spot_progress);
+        }
+
+        if last_resolution_check.elapsed() >= Duration::from_secs(RESOLUTION_CHECK_SECS) {
+            last_resolution_check = Instant::now();
+            for (key, condition_id, up_token_id) in tracker.ended(now) {
+                let Some(up_won) = calibration::up_won(&api, &condition_id, &up_token_id).await else { continue };
+                if let Err(e) = append(&cfg.path, &tracker.finish(&key, up_won)) {
+                    log::warn!("Failed to write dataset rows: {}", e);
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = sleep(Duration::from_secs(POLL_SECS)) => {}
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TokenSnapshot;
+
+    fn token(id: &str, mid: f64, bids: f64, asks: f64) -> TokenSnapshot {
+        serde_json::from_value(serde_json::json!({
+            "token_id": id, "price": mid + 0.01, "mid": mid, "best_bid": mid - 0.01, "best_ask": mid + 0.01,
+            "bids": [{ "price": mid - 0.01, "size": bids }], "asks": [{ "price": mid + 0.01, "size": asks }],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn rows_are_taken_at_offsets_and_labeled_on_resolution() {
+        let cfg = DatasetConfig { offsets_secs: vec![180, 420], sample_secs: 15, ..DatasetConfig::default() };
+        let mut tracker = Tracker::new(&cfg, TrendConfig::default());
+        let start = 900 * 1000;
+        let snapshot = |at: i64, up: f64| MarketSnapshot {
+            timestamp_ms: at * 1000,
+            asset: "BTC".to_string(),
+            condition_id: "0xc".to_string(),
+            period_start: start,
+            up: token("up", up, 30.0, 10.0),
+            down: token("down", 1.0 - up, 10.0, 10.0),
+            volume_24h: Some(1000.0),
+            invalid: None,
+        };
+        assert!(tracker.due("BTC", start, start + 179).is_empty());
+        assert!(tracker.sample_due("BTC", start, start + 5));
+        tracker.observe(&snapshot(start + 5, 0.5), start + 5, &[], None);
+        assert!(!tracker.sample_due("BTC", start, start + 19));
+
+        assert_eq!(tracker.due("BTC", start, start + 180), [180]);
+        tracker.observe(&snapshot(start + 180, 0.6), start + 180, &[180], Some(0.4));
+        assert!(tracker.due("BTC", start, start + 190).is_empty());
+        // The 420s offset was missed by more than the grace period
+        assert!(tracker.due("BTC", start, start + 420 + OFFSET_GRACE_SECS).is_empty());
+
+        assert!(tracker.ended(start + 899).is_empty());
+        let (key, condition_id, up_token) = tracker.ended(start + 900).remove(0);
+        assert_eq!((condition_id.as_str(), up_token.as_str()), ("0xc", "up"));
+        let rows = tracker.finish(&key, true);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].up_won);
+        let row = &rows[0].features;
+        assert_eq!(row.secs_into_period, 180);
+        assert!((row.up_change - 0.1).abs() < 1e-9);
+        assert!((row.implied_up - 0.6).abs() < 1e-9);
+        assert!((row.imbalance.unwrap() - 0.5).abs() < 1e-9);
+        assert!((row.up_spread.unwrap() - 0.02).abs() < 1e-9);
+        assert_eq!(row.value("spot_progress"), Some(0.4));
+        assert_eq!(row.value("trend"), Some(0.0));
+        assert!(row.up_std_dev.is_some());
+        assert!(FEATURES.iter().all(|f| f == &"volume_24h" || row.value(f).is_some()));
+    }
+}
@@ -1,115 +1,328 @@
 use crate::api::PolymarketApi;
+use crate::config::{Config, DiscoveryConfig, MarketConfig, MarketDuration, MarketFamily};
+use crate::models::{Market, MarketDetails, Threshold};
+use serde_json::Value;
+use crate::slug;
 use anyhow::Result;
-use chrono::{Datelike, TimeZone, Timelike};
+use chrono::{LocalResult, TimeZone, Timelike};
 use chrono_tz::America::New_York;
-use std::sync::Arc;
-
-pub const ASSET_TO_SLUG: &[(&str, &str)] = &[
-    ("BTC", "bitcoin"),
-    ("ETH", "ethereum"),
-    ("SOL", "solana"),
-    ("XRP", "xrp"),
-];
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 pub struct MarketDiscovery {
     api: Arc<PolymarketApi>,
+    markets: Vec<MarketConfig>,
+    config: DiscoveryConfig,
+    /// Slugs found through Gamma events, by (asset, timeframe, period start)
+    found: Mutex<HashMap<(String, MarketDuration, i64), String>>,
+    /// Condition IDs of the threshold markets picked, by (asset, timeframe, period start)
+    picked: Mutex<HashMap<(String, MarketDuration, i64), String>>,
+    /// Periods whose market couldn't be found yet, by (asset, timeframe, period start)
+    missing: Mutex<HashMap<(String, MarketDuration, i64), Missing>>,
+}
+
+/// Failed lookups of a period's market that isn't listed (yet). Lookups back off from
+/// `discovery.retry_secs`, doubling up to `discovery.max_retry_secs`.
+#[derive(Debug, Clone, Copy)]
+pub struct Missing {
+    pub attempts: u32,
+    /// Unix seconds of the first failed lookup
+    pub since: i64,
+    /// No lookup goes out before this
+    pub retry_at: i64,
+    /// The missing market has been alerted on
+    alerted: bool,
+}
+
+impl Missing {
+    pub fn retry_due(&self) -> bool {
+        crate::clock::now() >= self.retry_at
+    }
 }
 
 impl MarketDiscovery {
-    pub fn new(api: Arc<PolymarketApi>) -> Self {
-        Self { api }
-    }
-
-    pub fn build_1h_slug(asset_slug: &str, period_start_et: i64) -> String {
-        let dt_et = New_York.timestamp_opt(period_start_et, 0).single().unwrap();
-        let month_str = match dt_et.month() {
-            1 => "january",
-            2 => "february",
-            3 => "march",
-            4 => "april",
-            5 => "may",
-            6 => "june",
-            7 => "july",
-            8 => "august",
-            9 => "september",
-            10 => "october",
-            11 => "november",
-            12 => "december",
-            _ => "january",
-        };
-        let day = dt_et.day();
-        let hour24 = dt_et.hour();
-        let (hour12, am_pm) = match hour24 {
-            0 => (12, "am"),
-            1..=11 => (hour24, "am"),
-            12 => (12, "pm"),
-            _ => (hour24 - 12, "pm"),
+    pub fn new(api: Arc<PolymarketApi>, config: &Config) -> Self {
+        Self {
+            api,
+            markets: config.markets.clone(),
+            config: config.discovery.clone(),
+            found: Mutex::new(HashMap::new()),
+            picked: Mutex::new(HashMap::new()),
+            missing: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Slug of the `duration` market for `asset` starting at `period_start_et`, from the asset's
+    /// slug template or the built-in format. `None` if the asset is not configured.
+    pub fn slug(&self, asset: &str, duration: MarketDuration, period_start_et: i64) -> Option<String> {
+        let market = self.markets.iter().find(|m| m.asset.eq_ignore_ascii_case(asset))?;
+        Some(slug::render(slug::template(market, duration), market, duration, period_start_et))
+    }
+
+    /// The `duration` market for `asset` in the period starting at `period_start_et`. A market
+    /// pinned by `condition_id` or `slug` in the config is fetched directly and only returned for
+    /// the period it ends in. Otherwise the market is looked up among Gamma events ending with
+    /// the period, and only if that fails is its slug built from the template.
+    ///
+    /// Lookups of a period whose market wasn't found are backed off: until the next retry is due
+    /// they fail without a request.
+    pub async fn find_market(&self, asset: &str, duration: MarketDuration, period_start_et: i64) -> Result<Market> {
+        let config = self
+            .markets
+            .iter()
+            .find(|m| m.asset.eq_ignore_ascii_case(asset))
+            .ok_or_else(|| anyhow::anyhow!("{} is not in the markets config", asset))?;
+        if let Some(replay) = self.api.replay() {
+            return Ok(replay.market(asset, period_start_et)?);
+        }
+        let key = (config.asset.to_uppercase(), duration, period_start_et);
+        if let Some(missing) = self.missing.lock().unwrap().get(&key).filter(|m| !m.retry_due()) {
+            anyhow::bail!("no {} {} market listed for period {} after {} attempt(s), next try in {}s",
+                asset, duration.label(), period_start_et, missing.attempts, missing.retry_at - crate::clock::now());
+        }
+        let result = self.lookup_market(config, duration, period_start_et).await;
+        let now = crate::clock::now();
+        let mut missing = self.missing.lock().unwrap();
+        match &result {
+            Ok(market) => {
+                if let Some(m) = missing.remove(&key).filter(|m| m.alerted) {
+                    log::info!("✅ {} | {} market for period {} listed after {}s: {}",
+                        asset, duration.label(), period_start_et, now - m.since, market.condition_id);
+                }
+            }
+            Err(_) => {
+                missing.retain(|(_, _, period), _| *period > period_start_et - 2 * 24 * 60 * 60);
+                let m = missing.entry(key).or_insert(Missing { attempts: 0, since: now, retry_at: now, alerted: false });
+                let delay = self.config.retry_secs.saturating_mul(1 << m.attempts.min(16)).min(self.config.max_retry_secs);
+                m.attempts += 1;
+                m.retry_at = now + delay as i64;
+            }
+        }
+        result
+    }
+
+    /// Failed lookups of the period's market while it isn't found; `None` once it has been.
+    pub fn missing(&self, asset: &str, duration: MarketDuration, period_start_et: i64) -> Option<Missing> {
+        self.missing.lock().unwrap().get(&(asset.to_uppercase(), duration, period_start_et)).copied()
+    }
+
+    /// Marks the period's missing market as alerted on; true the first time only.
+    pub fn flag_missing_alert(&self, asset: &str, duration: MarketDuration, period_start_et: i64) -> bool {
+        let mut missing = self.missing.lock().unwrap();
+        match missing.get_mut(&(asset.to_uppercase(), duration, period_start_et)) {
+            Some(m) => !std::mem::replace(&mut m.alerted, true),
+            None => false,
+        }
+    }
+
+    async fn lookup_market(&self, config: &MarketConfig, duration: MarketDuration, period_start_et: i64) -> Result<Market> {
+        let asset = config.asset.as_str();
+        let market = if let Some(condition_id) = &config.condition_id {
+            market_of(self.api.get_market_metadata(condition_id).await?)
+        } else if let Some(slug) = &config.slug {
+            self.api.get_market_by_slug(slug).await?
+        } else if config.family == MarketFamily::Threshold {
+            let condition_id = self.pick_strike(config, duration, period_start_et).await?;
+            market_of(self.api.get_market_metadata(&condition_id).await?)
+        } else {
+            let slug = match self.find_slug_by_events(config, duration, period_start_et).await {
+                Ok(slug) => slug,
+                Err(e) => {
+                    log::debug!("Gamma events lookup for {} {}: {} — building slug", asset, duration.label(), e);
+                    self.slug(asset, duration, period_start_et).unwrap_or_default()
+                }
+            };
+            return Ok(self.api.get_market_by_slug(&slug).await?);
         };
-        format!(
-            "{}-up-or-down-{}-{}-{}{}-et",
-            asset_slug, month_str, day, hour12, am_pm
-        )
+
+        // Date-only or missing end dates can't be placed in a period; trust the lookup
+        if let Some(end) = market.end_timestamp() {
+            if end <= period_start_et || end > period_start_et + duration.nominal_secs() {
+                anyhow::bail!("{} market {} ends outside period {}", asset, market.condition_id, period_start_et);
+            }
+        }
+        Ok(market)
     }
 
+    /// The pinned market of an `event` entry, whatever its end time.
+    pub async fn find_event_market(&self, asset: &str) -> Result<Market> {
+        let config = self
+            .markets
+            .iter()
+            .find(|m| m.asset.eq_ignore_ascii_case(asset))
+            .ok_or_else(|| anyhow::anyhow!("{} is not in the markets config", asset))?;
+        if let Some(condition_id) = &config.condition_id {
+            Ok(market_of(self.api.get_market_metadata(condition_id).await?))
+        } else if let Some(slug) = &config.slug {
+            Ok(self.api.get_market_by_slug(slug).await?)
+        } else {
+            anyhow::bail!("event market {} pins neither a condition_id nor a slug", asset)
+        }
+    }
 
-    pub fn current_1h_period_start_et() -> i64 {
-        let now_utc = chrono::Utc::now();
-        let now_et = now_utc.with_timezone(&New_York);
+    /// Condition ID of the threshold market traded in the period: among the asset's markets that
+    /// end with it, the open one whose Yes price is nearest 0.5. Picked once per period, so
+    /// every lookup in the period returns the same strike.
+    async fn pick_strike(&self, market: &MarketConfig, duration: MarketDuration, period_start_et: i64) -> Result<String> {
+        let key = (market.asset.to_uppercase(), duration, period_start_et);
+        if let Some(condition_id) = self.picked.lock().unwrap().get(&key) {
+            return Ok(condition_id.clone());
+        }
 
-        let hour_start_et = New_York
-            .with_ymd_and_hms(
-                now_et.year(),
-                now_et.month(),
-                now_et.day(),
-                now_et.hour(),
-                0,
-                0,
-            )
-            .single()
-            .unwrap();
+        let end = period_start_et + duration.nominal_secs();
+        let slack = if duration == MarketDuration::D1 { 3600 } else { 60 };
+        let tag = market.tag_slug.as_deref().unwrap_or(&self.config.tag_slug);
+        let markets = self.api.get_markets_ending_between(tag, end - slack, end + slack).await?;
+        let prefixes = [format!("{}-", market.asset.to_lowercase()), format!("{}-", market.name())];
+        let (condition_id, threshold, yes) = markets
+            .iter()
+            .filter(|m| m.get("closed").and_then(Value::as_bool) != Some(true))
+            .filter(|m| m.get("active").and_then(Value::as_bool) != Some(false))
+            .filter_map(|m| {
+                let slug = m.get("slug")?.as_str()?;
+                if !prefixes.iter().any(|p| slug.starts_with(p.as_str())) {
+                    return None;
+                }
+                let threshold = Threshold::parse(m.get("question")?.as_str()?)?;
+                Some((m.get("conditionId")?.as_str()?.to_string(), threshold, yes_price(m)?))
+            })
+            .min_by(|a, b| (a.2 - 0.5).abs().total_cmp(&(b.2 - 0.5).abs()))
+            .ok_or_else(|| anyhow::anyhow!("no open {} threshold market with prices", market.asset))?;
+        log::info!("🎯 {} | Trading the {} ${} strike for the {} period {} (Yes at {:.2})",
+            market.asset, if threshold.above { "above" } else { "below" }, threshold.strike, duration.label(), period_start_et, yes);
 
-        hour_start_et.timestamp()
+        let mut picked = self.picked.lock().unwrap();
+        picked.retain(|(_, _, period), _| *period > period_start_et - 2 * 24 * 60 * 60);
+        picked.insert(key, condition_id.clone());
+        Ok(condition_id)
     }
 
-    /// 15m market slug format: btc-updown-15m-{period_start_timestamp}
-    pub fn build_15m_slug(asset_ticker: &str, period_start_et: i64) -> String {
-        let asset = asset_ticker.to_lowercase();
-        format!("{}-updown-15m-{}", asset, period_start_et)
+    /// Slug of the market among Gamma events tagged `discovery.tag_slug` that ends with the
+    /// period, whose slug starts with the asset's ticker or name and, when Gamma reports it,
+    /// whose event start time is the period start.
+    async fn find_slug_by_events(&self, market: &MarketConfig, duration: MarketDuration, period_start_et: i64) -> Result<String> {
+        if !self.config.gamma_events {
+            anyhow::bail!("disabled");
+        }
+        let key = (market.asset.to_uppercase(), duration, period_start_et);
+        if let Some(slug) = self.found.lock().unwrap().get(&key) {
+            return Ok(slug.clone());
+        }
+
+        let end = period_start_et + duration.nominal_secs();
+        // Daily periods are an hour off their nominal length across DST changes
+        let slack = if duration == MarketDuration::D1 { 3600 } else { 60 };
+        let tag = market.tag_slug.as_deref().unwrap_or(&self.config.tag_slug);
+        let markets = self.api.get_markets_ending_between(tag, end - slack, end + slack).await?;
+        let prefixes = [format!("{}-", market.asset.to_lowercase()), format!("{}-", market.name())];
+        let candidates: Vec<(String, Option<i64>)> = markets
+            .iter()
+            .filter_map(|m| {
+                let slug = m.get("slug")?.as_str()?;
+                prefixes.iter().any(|p| slug.starts_with(p.as_str())).then(|| {
+                    let start = m
+                        .get("eventStartTime")
+                        .and_then(|t| t.as_str())
+                        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                        .map(|t| t.timestamp());
+                    (slug.to_string(), start)
+                })
+            })
+            .collect();
+        let starting_now: Vec<&String> = candidates
+            .iter()
+            .filter(|(_, start)| start.is_some_and(|t| (t - period_start_et).abs() <= 60))
+            .map(|(slug, _)| slug)
+            .collect();
+        let slug = match (starting_now.as_slice(), candidates.as_slice()) {
+            ([slug], _) => (*slug).clone(),
+            // Without a start time, trust a lone candidate unless its slug names another period
+            ([], [(found, None)]) => match slug::period_start(slug::template(market, duration), market, duration, found, period_start_et) {
+                Some(other) if other != period_start_et => anyhow::bail!("{} is the market of period {}", found, other),
+                _ => found.clone(),
+            },
+            ([], []) => anyhow::bail!("no matching market"),
+            _ => anyhow::bail!("{} candidate markets, can't tell which is the {} one", candidates.len(), duration.label()),
+        };
+
+        let mut found = self.found.lock().unwrap();
+        found.retain(|(_, _, period), _| *period > period_start_et - 2 * 24 * 60 * 60);
+        found.insert(key, slug.clone());
+        Ok(slug)
     }
 
     /// Current 15-minute period start (ET), rounded down to :00, :15, :30, :45.
     pub fn current_15m_period_start_et() -> i64 {
-        let now_utc = chrono::Utc::now();
-        let now_et = now_utc.with_timezone(&New_York);
-        let minute = now_et.minute();
-        let minute_floor = (minute / 15) * 15;
-
-        let period_start_et = New_York
-            .with_ymd_and_hms(
-                now_et.year(),
-                now_et.month(),
-                now_et.day(),
-                now_et.hour(),
-                minute_floor,
-                0,
-            )
-            .single()
-            .unwrap();
-
-        period_start_et.timestamp()
+        Self::period_start_et(MarketDuration::M15, crate::clock::now())
+    }
+
+    /// Start of the `duration` period containing unix time `ts`. Periods are aligned to ET:
+    /// 5m/15m/1h on the clock, 4h at 12am/4am/…/8pm, and daily from noon to noon.
+    pub fn period_start_et(duration: MarketDuration, ts: i64) -> i64 {
+        // ET offsets are whole hours, so sub-daily clock periods up to 1h align on unix time too
+        match duration {
+            MarketDuration::M5 => return ts - ts.rem_euclid(5 * 60),
+            MarketDuration::M15 => return ts - ts.rem_euclid(15 * 60),
+            MarketDuration::H1 => return ts - ts.rem_euclid(60 * 60),
+            MarketDuration::H4 | MarketDuration::D1 => {}
+        }
+        let dt_et = New_York.timestamp_opt(ts, 0).single().unwrap();
+        let date = dt_et.date_naive();
+        let start = match duration {
+            MarketDuration::H4 => date.and_hms_opt(dt_et.hour() / 4 * 4, 0, 0),
+            _ if dt_et.hour() >= 12 => date.and_hms_opt(12, 0, 0),
+            _ => date.pred_opt().and_then(|d| d.and_hms_opt(12, 0, 0)),
+        }
+        .unwrap();
+        // On the fall-back night a boundary can occur twice; take the one not after `ts`
+        match New_York.from_local_datetime(&start) {
+            LocalResult::Single(t) => t.timestamp(),
+            LocalResult::Ambiguous(_, late) if late.timestamp() <= ts => late.timestamp(),
+            LocalResult::Ambiguous(early, _) => early.timestamp(),
+            // Skipped by spring-forward (never a 4h or noon boundary in ET, but be total)
+            LocalResult::None => ts - ts.rem_euclid(60 * 60),
+        }
+    }
+
+    /// Start of the period before the one starting at `period_start_et`.
+    pub fn previous_period_start_et(duration: MarketDuration, period_start_et: i64) -> i64 {
+        Self::period_start_et(duration, period_start_et - 1)
+    }
+
+    /// Start of the period after the one starting at `period_start_et`.
+    pub fn next_period_start_et(duration: MarketDuration, period_start_et: i64) -> i64 {
+        // 4h and daily periods stretch or shrink by an hour across DST changes
+        let slack = if duration.nominal_secs() >= 4 * 60 * 60 { 60 * 60 } else { 0 };
+        Self::period_start_et(duration, period_start_et + duration.nominal_secs() + slack)
+    }
+
+    /// Outcome names and token IDs of a market, in the order the CLOB lists them. Any number
+    /// of outcomes; `get_market_tokens` is the Up/Down case.
+    pub async fn get_market_outcomes(&self, condition_id: &str) -> Result<Vec<(String, String)>> {
+        let details = self.api.get_market_metadata(condition_id).await?;
+        Ok(details.tokens.into_iter().map(|t| (t.outcome, t.token_id)).collect())
+    }
+
+    /// Token IDs of a two-outcome market in listed order, for markets whose outcomes aren't
+    /// Up/Down (team names, Yes/No).
+    pub async fn get_event_tokens(&self, condition_id: &str) -> Result<(String, String)> {
+        match <[(String, String); 2]>::try_from(self.get_market_outcomes(condition_id).await?) {
+            Ok([(_, first), (_, second)]) => Ok((first, second)),
+            Err(outcomes) => anyhow::bail!("market {} has {} outcomes, not two", condition_id, outcomes.len()),
+        }
     }
 
     pub async fn get_market_tokens(&self, condition_id: &str) -> Result<(String, String)> {
-        let details = self.api.get_market(condition_id).await?;
         let mut up_token = None;
         let mut down_token = None;
 
-        for token in details.tokens {
-            let outcome = token.outcome.to_uppercase();
-            if outcome.contains("UP") || outcome == "1" {
-                up_token = Some(token.token_id);
-            } else if outcome.contains("DOWN") || outcome == "0" {
-                down_token = Some(token.token_id);
+        for (outcome, token_id) in self.get_market_outcomes(condition_id).await? {
+            let outcome = outcome.to_uppercase();
+            // Threshold markets trade Yes as Up and No as Down
+            if outcome.contains("UP") || outcome == "1" || outcome == "YES" {
+                up_token = Some(token_id);
+            } else if outcome.contains("DOWN") || outcome == "0" || outcome == "NO" {
+                down_token = Some(token_id);
             }
         }
 
@@ -119,3 +332,25 @@ impl MarketDiscovery {
         Ok((up, down))
     }
 }
+
+fn market_of(details: MarketDetails) -> Market {
+    Market {
+        condition_id: details.condition_id,
+        market_id: None,
+        question: details.question,
+        slug: String::new(),
+        end_date_iso: Some(details.end_date_iso),
+        active: details.active,
+        closed: details.closed,
+        volume_24h: None,
+    }
+}
+
+/// Yes price of a Gamma market, from `outcomePrices` (a JSON-encoded list, Yes first).
+fn yes_price(market: &Value) -> Option<f64> {
+    let prices: Vec<String> = match market.get("outcomePrices")? {
+        Value::String(encoded) => serde_json::from_str(encoded).ok()?,
+        list => serde_json::from_value(list.clone()).ok()?,
+    };
+    prices.first()?.parse().ok()
+}
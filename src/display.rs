@@ -0,0 +1,92 @@
+use crate::config::{DisplayConfig, PnlUnits, SizeUnits};
+use std::sync::OnceLock;
+
+static DISPLAY: OnceLock<DisplayConfig> = OnceLock::new();
+
+/// Sets how amounts are written for the rest of the run; called once at startup.
+pub fn init(config: &DisplayConfig) {
+    let _ = DISPLAY.set(config.clone());
+}
+
+fn config() -> &'static DisplayConfig {
+    DISPLAY.get_or_init(DisplayConfig::default)
+}
+
+/// A dollar amount: `$12.34`.
+pub fn usd(amount: f64) -> String {
+    config().usd(amount)
+}
+
+/// A share price: `$0.45`.
+pub fn price(price: f64) -> String {
+    config().price(price)
+}
+
+/// A share count, without units.
+pub fn shares(shares: f64) -> String {
+    config().shares(shares)
+}
+
+/// `shares` bought or sold at `price`: `20.00 shares`, or their cost with `display.sizes = "usd"`.
+pub fn size(shares: f64, price: f64) -> String {
+    config().size(shares, price)
+}
+
+/// PnL made on a position of `shares`: dollars, or dollars per share with
+/// `display.pnl = "per_share"` (dollars when there are no shares to divide by).
+pub fn pnl(pnl: f64, shares: f64) -> String {
+    config().pnl(pnl, shares)
+}
+
+impl DisplayConfig {
+    fn usd(&self, amount: f64) -> String {
+        format!("${:.*}", self.usd_decimals, amount)
+    }
+
+    fn price(&self, price: f64) -> String {
+        format!("${:.*}", self.price_decimals, price)
+    }
+
+    fn shares(&self, shares: f64) -> String {
+        format!("{:.*}", self.share_decimals, shares)
+    }
+
+    fn size(&self, shares: f64, price: f64) -> String {
+        match self.sizes {
+            SizeUnits::Shares => format!("{} shares", self.shares(shares)),
+            SizeUnits::Usd => self.usd(shares * price),
+        }
+    }
+
+    fn pnl(&self, pnl: f64, shares: f64) -> String {
+        match self.pnl {
+            PnlUnits::PerShare if shares > 0.0 => format!("{}/share", self.price(pnl / shares)),
+            _ => self.usd(pnl),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_follow_precision_and_units() {
+        let default = DisplayConfig::default();
+        assert_eq!(default.usd(-3.456), "$-3.46");
+        assert_eq!(default.size(20.0, 0.45), "20.00 shares");
+        assert_eq!(default.pnl(1.5, 20.0), "$1.50");
+
+        let native = DisplayConfig {
+            usd_decimals: 0,
+            price_decimals: 4,
+            share_decimals: 0,
+            sizes: SizeUnits::Usd,
+            pnl: PnlUnits::PerShare,
+        };
+        assert_eq!(native.price(0.45), "$0.4500");
+        assert_eq!(native.size(20.0, 0.45), "$9");
+        assert_eq!(native.pnl(1.5, 20.0), "$0.0750/share");
+        assert_eq!(native.pnl(1.5, 0.0), "$2");
+    }
+}
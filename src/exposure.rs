@@ -0,0 +1,126 @@
+use crate::fees::FeeSchedule;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Shares held of one outcome of a market, bought at `price`.
+#[derive(Debug, Clone)]
+pub struct Leg {
+    pub asset: String,
+    pub condition_id: String,
+    pub outcome: String,
+    pub shares: f64,
+    pub price: f64,
+}
+
+/// Capital deployed in one market and what it returns if either outcome wins.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketExposure {
+    pub asset: String,
+    pub condition_id: String,
+    /// Price paid plus maker fees for the shares still held
+    pub open_cost: f64,
+    /// PnL of the held shares under the worse and the better outcome
+    pub worst_case: f64,
+    pub best_case: f64,
+}
+
+/// Held positions across markets. Markets resolve independently, so the totals are the sums of
+/// each market's worst and best case.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Exposure {
+    pub markets: Vec<MarketExposure>,
+    pub open_cost: f64,
+    pub worst_case: f64,
+    pub best_case: f64,
+}
+
+/// Groups `legs` by market and nets the outcomes held in each.
+pub fn aggregate(legs: &[Leg], fees: &FeeSchedule) -> Exposure {
+    let mut by_market: BTreeMap<(&str, &str), Vec<&Leg>> = BTreeMap::new();
+    for leg in legs.iter().filter(|l| l.shares > 0.0) {
+        by_market.entry((&leg.asset, &leg.condition_id)).or_default().push(leg);
+    }
+    let mut exposure = Exposure::default();
+    for ((asset, condition_id), legs) in by_market {
+        let open_cost = legs.iter().fold(0.0, |total, l| total + l.price * l.shares + fees.maker_fee(l.price, l.shares));
+        let pnl_if = |winner: &str| {
+            legs.iter().filter(|l| l.outcome == winner).fold(0.0, |total, l| total + l.shares) - open_cost
+        };
+        let (up, down) = (pnl_if("Up"), pnl_if("Down"));
+        let market = MarketExposure {
+            asset: asset.to_string(),
+            condition_id: condition_id.to_string(),
+            open_cost,
+            worst_case: up.min(down),
+            best_case: up.max(down),
+        };
+        exposure.open_cost += market.open_cost;
+        exposure.worst_case += market.worst_case;
+        exposure.best_case += market.best_case;
+        exposure.markets.push(market);
+    }
+    exposure
+}
+
+impl Exposure {
+    /// Sums the exposures of several strategies (accounts).
+    pub fn combine(parts: impl IntoIterator<Item = Exposure>) -> Exposure {
+        parts.into_iter().fold(Exposure::default(), |mut total, part| {
+            total.open_cost += part.open_cost;
+            total.worst_case += part.worst_case;
+            total.best_case += part.best_case;
+            total.markets.extend(part.markets);
+            total
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FeeConfig;
+
+    fn leg(asset: &str, condition_id: &str, outcome: &str, shares: f64, price: f64) -> Leg {
+        Leg { asset: asset.to_string(), condition_id: condition_id.to_string(), outcome: outcome.to_string(), shares, price }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn markets_are_netted_alone_and_summed_across_assets() {
+        // Flat 1% maker fee
+        let fees = FeeSchedule::new(FeeConfig { maker_fee_rate: 0.01, taker_fee_rate: 0.0, fee_exponent: 0.0 });
+        let legs = [
+            leg("ETH", "0xe1", "Up", 5.0, 0.60),
+            leg("BTC", "0xb1", "Up", 10.0, 0.45),
+            leg("BTC", "0xb1", "Down", 10.0, 0.45),
+            // Another BTC market (the hourly one) is netted on its own
+            leg("BTC", "0xb2", "Up", 4.0, 0.50),
+            leg("BTC", "0xb2", "Down", 0.0, 0.50),
+        ];
+        let exposure = aggregate(&legs, &fees);
+
+        let markets: Vec<_> = exposure.markets.iter().map(|m| (m.asset.as_str(), m.condition_id.as_str())).collect();
+        assert_eq!(markets, [("BTC", "0xb1"), ("BTC", "0xb2"), ("ETH", "0xe1")]);
+        // (open cost, worst case, best case) per market
+        let expected = [(9.09, 0.91, 0.91), (2.02, -2.02, 1.98), (3.03, -3.03, 1.97)];
+        for (market, (open_cost, worst_case, best_case)) in exposure.markets.iter().zip(expected) {
+            assert_close(market.open_cost, open_cost);
+            assert_close(market.worst_case, worst_case);
+            assert_close(market.best_case, best_case);
+        }
+        assert_close(exposure.open_cost, 14.14);
+        assert_close(exposure.worst_case, -4.14);
+        assert_close(exposure.best_case, 4.86);
+
+        // A second account adds its markets to the totals
+        let other = aggregate(&[leg("SOL", "0x50", "Down", 10.0, 0.30)], &fees);
+        let total = Exposure::combine([exposure, other]);
+        assert_eq!(total.markets.len(), 4);
+        assert_close(total.open_cost, 14.14 + 3.03);
+        assert_close(total.worst_case, -4.14 - 3.03);
+        assert_close(total.best_case, 4.86 + 6.97);
+    }
+}
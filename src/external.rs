@@ -0,0 +1,163 @@
+use crate::config::ExternalSignalConfig;
+use crate::trend::Trend;
+use anyhow::{bail, Context, Result};
+use chrono::{Duration, NaiveTime, TimeZone};
+use chrono_tz::America::New_York;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Where an external system expects a market to go.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bias {
+    Up,
+    Down,
+    /// No expected move: overrides a measured trend so pairs may be bought
+    Flat,
+}
+
+impl Bias {
+    /// The Up trend the bias stands in for.
+    pub fn trend(self) -> Trend {
+        match self {
+            Bias::Up => Trend::Rising,
+            Bias::Down => Trend::Falling,
+            Bias::Flat => Trend::Flat,
+        }
+    }
+
+    /// Whether the bias goes against `up_wins`; a flat bias never does.
+    pub fn against(self, up_wins: bool) -> bool {
+        matches!((self, up_wins), (Bias::Up, false) | (Bias::Down, true))
+    }
+}
+
+/// End of a posted signal: unix seconds, or `HH:MM` in ET for the next time the clock shows it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Until {
+    At(i64),
+    Clock(String),
+}
+
+/// Body of `POST /signals`: a bias for one market, for `ttl_secs` or until `until`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SignalPost {
+    pub asset: String,
+    pub bias: Bias,
+    #[serde(default)]
+    pub ttl_secs: Option<u64>,
+    #[serde(default)]
+    pub until: Option<Until>,
+    /// Who sent it, for the log
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalSignal {
+    pub asset: String,
+    pub bias: Bias,
+    /// Unix seconds
+    pub received_at: i64,
+    pub expires_at: i64,
+    #[serde(default)]
+    pub source: Option<String>,
+}
+
+/// Signals posted for each market, kept until they expire or are replaced.
+#[derive(Debug, Default)]
+pub struct ExternalSignals {
+    max_ttl_secs: u64,
+    signals: HashMap<String, ExternalSignal>,
+}
+
+impl ExternalSignals {
+    pub fn new(config: &ExternalSignalConfig) -> Self {
+        Self { max_ttl_secs: config.max_ttl_secs, signals: HashMap::new() }
+    }
+
+    /// Stores `post` for `asset` (the configured spelling), replacing any earlier signal.
+    pub fn post(&mut self, asset: &str, post: &SignalPost, now: i64) -> Result<ExternalSignal> {
+        let expires_at = match (post.ttl_secs, &post.until) {
+            (Some(ttl), None) => now + ttl as i64,
+            (None, Some(Until::At(at))) => *at,
+            (None, Some(Until::Clock(clock))) => next_et(clock, now)?,
+            _ => bail!("pass one of ttl_secs or until"),
+        };
+        if expires_at <= now {
+            bail!("signal expires in the past");
+        }
+        if expires_at - now > self.max_ttl_secs as i64 {
+            bail!("signal lasts {}s, over control.signals.max_ttl_secs {}", expires_at - now, self.max_ttl_secs);
+        }
+        let signal = ExternalSignal {
+            asset: asset.to_string(),
+            bias: post.bias,
+            received_at: now,
+            expires_at,
+            source: post.source.clone(),
+        };
+        self.signals.insert(asset.to_string(), signal.clone());
+        Ok(signal)
+    }
+
+    /// `asset`'s signal, if one is live at `now`.
+    pub fn get(&self, asset: &str, now: i64) -> Option<&ExternalSignal> {
+        self.signals.get(asset).filter(|s| s.expires_at > now)
+    }
+
+    /// Every signal live at `now`, dropping the expired ones.
+    pub fn active(&mut self, now: i64) -> Vec<ExternalSignal> {
+        self.signals.retain(|_, s| s.expires_at > now);
+        let mut signals: Vec<_> = self.signals.values().cloned().collect();
+        signals.sort_by(|a, b| a.asset.cmp(&b.asset));
+        signals
+    }
+
+    /// Withdraws `asset`'s signal; whether there was one.
+    pub fn clear(&mut self, asset: &str) -> bool {
+        self.signals.remove(asset).is_some()
+    }
+}
+
+/// Unix seconds of the next time after `now` that the ET clock reads `hh_mm`.
+fn next_et(hh_mm: &str, now: i64) -> Result<i64> {
+    let time = NaiveTime::parse_from_str(hh_mm, "%H:%M").with_context(|| format!("until {:?} is not HH:MM", hh_mm))?;
+    let today = New_York.timestamp_opt(now, 0).single().context("time out of range")?.date_naive();
+    [today, today + Duration::days(1)]
+        .into_iter()
+        .filter_map(|day| New_York.from_local_datetime(&day.and_time(time)).earliest())
+        .map(|t| t.timestamp())
+        .find(|&t| t > now)
+        .with_context(|| format!("{} ET doesn't exist in the next day", hh_mm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(json: serde_json::Value) -> SignalPost {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn signals_expire_and_clock_times_are_read_in_et() {
+        let mut signals = ExternalSignals::new(&ExternalSignalConfig::default());
+        // 2025-01-15 14:00 ET
+        let now = 1_736_967_600;
+        let signal = signals.post("BTC", &post(serde_json::json!({ "asset": "btc", "bias": "up", "until": "14:30" })), now).unwrap();
+        assert_eq!(signal.expires_at, now + 1800);
+        assert_eq!(signals.get("BTC", now + 1799).map(|s| s.bias), Some(Bias::Up));
+        assert!(signals.get("BTC", now + 1800).is_none());
+        // Already past today: tomorrow's
+        let signal = signals.post("ETH", &post(serde_json::json!({ "asset": "eth", "bias": "down", "until": "13:00" })), now).unwrap();
+        assert_eq!(signal.expires_at, now + 23 * 3600);
+        assert_eq!(signals.active(now + 3600).len(), 1);
+
+        assert!(signals.post("BTC", &post(serde_json::json!({ "asset": "btc", "bias": "flat" })), now).is_err());
+        assert!(signals.post("BTC", &post(serde_json::json!({ "asset": "btc", "bias": "flat", "ttl_secs": 90_000 })), now).is_err());
+        assert!(signals.post("BTC", &post(serde_json::json!({ "asset": "btc", "bias": "flat", "until": now - 1 })), now).is_err());
+        assert!(Bias::Up.against(false) && !Bias::Flat.against(true));
+    }
+}
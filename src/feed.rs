@@ -0,0 +1,368 @@
+use crate::api::PolymarketApi;
+use crate::config::{Config, DataSource, FeedConfig};
+use crate::logging::EVENTS;
+use crate::metrics::metrics;
+use crate::models::OrderBook;
+use crate::wsfeed::WsFeed;
+use anyhow::Result;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::{watch, Notify};
+use tokio::time::{sleep, Duration};
+
+/// Tokens nobody has read for this long are dropped from the batch.
+const IDLE_SECS: u64 = 60;
+
+/// Shared price and order book fetcher. Strategies and the snapshot recorder read through it
+/// instead of calling the CLOB per token: a stale read wakes `run`, which waits
+/// `batch_window_ms` for other reads and then fetches every token in use with one
+/// `POST /prices`, plus one `POST /books` and one `POST /last-trades-prices` when something
+/// reads books or quotes.
+///
+/// With `data_source` `websocket` or `hybrid`, reads are served from the CLOB market WebSocket
+/// while it is live and has the token's book, and go through the batch otherwise.
+pub struct MarketFeed {
+    api: Arc<PolymarketApi>,
+    config: FeedConfig,
+    /// Longest a read waits on a batch before fetching the token itself
+    max_wait: Duration,
+    tokens: Mutex<HashMap<String, TokenEntry>>,
+    wake: Notify,
+    /// Number of the last finished batch
+    rounds: watch::Sender<u64>,
+    ws: Option<Arc<WsFeed>>,
+    /// Hybrid mode: the socket stalled or disagreed with REST, so reads skip it until it agrees again
+    fallen_back: AtomicBool,
+}
+
+/// The other side of the quote and the last trade, beyond the SELL price every read gets.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quote {
+    /// BUY-side price (CLOB /price)
+    pub bid: Option<Decimal>,
+    pub last_trade: Option<Decimal>,
+}
+
+#[derive(Clone, Copy)]
+enum Read {
+    Price,
+    Book,
+    Quote,
+}
+
+struct TokenEntry {
+    last_read: Instant,
+    last_book_read: Option<Instant>,
+    last_quote_read: Option<Instant>,
+    /// Last batch that tried to fetch this token, whether or not it succeeded
+    round: u64,
+    price: Option<(Decimal, Instant)>,
+    book: Option<(OrderBook, Instant)>,
+    quote: Option<(Quote, Instant)>,
+}
+
+impl MarketFeed {
+    pub fn new(api: Arc<PolymarketApi>, config: &Config) -> Self {
+        let max_wait = Duration::from_millis(config.feed.batch_window_ms + config.network.request_timeout_ms);
+        // Replays read recorded books
+        let ws = (config.feed.data_source != DataSource::Rest && api.replay().is_none())
+            .then(|| Arc::new(WsFeed::new(&config.feed)));
+        Self {
+            api,
+            config: config.feed.clone(),
+            max_wait,
+            tokens: Mutex::new(HashMap::new()),
+            wake: Notify::new(),
+            rounds: watch::channel(0).0,
+            ws,
+            fallen_back: AtomicBool::new(false),
+        }
+    }
+
+    /// The WebSocket feed if reads should use it; subscribes it to `token_id` either way.
+    fn live_ws(&self, token_id: &str) -> Option<&WsFeed> {
+        let ws = self.ws.as_deref()?;
+        ws.watch(token_id);
+        (ws.is_live() && !self.fallen_back.load(Ordering::Relaxed)).then_some(ws)
+    }
+
+    /// SELL-side price of `token_id`, as `PolymarketApi::get_price(token_id, "SELL")` returns it.
+    pub async fn price(&self, token_id: &str) -> Result<Decimal> {
+        if let Some(ask) = self.live_ws(token_id).and_then(|ws| ws.top(token_id)).and_then(|top| top.ask) {
+            return Ok(ask);
+        }
+        let read = self.read(token_id, Read::Price, |e| e.price.as_ref().map(|(p, at)| (*p, *at))).await;
+        match read {
+            Some(price) => Ok(price),
+            None => {
+                let price = self.api.get_price(token_id, "SELL").await?;
+                if let Some(entry) = self.tokens.lock().unwrap().get_mut(token_id) {
+                    entry.price = Some((price, Instant::now()));
+                }
+                Ok(price)
+            }
+        }
+    }
+
+    /// Bid and last trade of `token_id`; either is None if the CLOB has none.
+    pub async fn quote(&self, token_id: &str) -> Quote {
+        // The socket learns the last trade only when one happens; until then REST knows better
+        let top = self.live_ws(token_id).and_then(|ws| ws.top(token_id));
+        if let Some(top) = top.filter(|top| top.last_trade.is_some()) {
+            return Quote { bid: top.bid, last_trade: top.last_trade };
+        }
+        let read = self.read(token_id, Read::Quote, |e| e.quote.as_ref().map(|(q, at)| (*q, *at))).await;
+        if let Some(quote) = read {
+            return quote;
+        }
+        let (bid, last_trade) =
+            tokio::join!(self.api.get_price(token_id, "BUY"), self.api.get_last_trade_price(token_id));
+        let quote = Quote { bid: bid.ok(), last_trade: last_trade.ok() };
+        if let Some(entry) = self.tokens.lock().unwrap().get_mut(token_id) {
+            entry.quote = Some((quote, Instant::now()));
+        }
+        quote
+    }
+
+    pub async fn book(&self, token_id: &str) -> Result<OrderBook> {
+        let mut books = self.books(&[token_id.to_string()]).await?;
+        Ok(books.remove(0))
+    }
+
+    /// Order books of `token_ids`, in order, all from the same fetch so they describe the same
+    /// moment (the Up and Down sides of a market, say).
+    pub async fn books(&self, token_ids: &[String]) -> Result<Vec<OrderBook>> {
+        let from_ws: Option<Vec<OrderBook>> = token_ids
+            .iter()
+            .map(|id| self.live_ws(id).and_then(|ws| ws.book(id)))
+            .collect();
+        if let Some(books) = from_ws {
+            return Ok(books);
+        }
+        let reads = futures_util::future::join_all(token_ids.iter().map(|id| {
+            self.read(id, Read::Book, |e| e.book.as_ref().map(|(b, at)| ((b.clone(), *at), *at)))
+        }))
+        .await;
+        let cached: Option<Vec<(OrderBook, Instant)>> = reads.into_iter().collect();
+        if let Some(cached) = cached.filter(|c| c.iter().all(|(_, at)| *at == c[0].1)) {
+            return Ok(cached.into_iter().map(|(book, _)| book).collect());
+        }
+
+        let mut fetched = self.api.get_order_books(token_ids).await?;
+        let now = Instant::now();
+        let mut tokens = self.tokens.lock().unwrap();
+        token_ids
+            .iter()
+            .map(|id| {
+                let book = fetched.remove(id).ok_or_else(|| anyhow::anyhow!("No order book for {}", id))?;
+                if let Some(entry) = tokens.get_mut(id) {
+                    entry.book = Some((book.clone(), now));
+                }
+                Ok(book)
+            })
+            .collect()
+    }
+
+    /// The cached value if it is fresh; otherwise waits for the next batch to include the token.
+    /// None means the batch failed for it (or took too long) and the caller should fetch it.
+    async fn read<T>(&self, token_id: &str, kind: Read, cached: impl Fn(&TokenEntry) -> Option<(T, Instant)>) -> Option<T> {
+        // Replayed data is read straight from memory at recorded time; wall-clock batching and
+        // caching would make it depend on how fast the replay runs
+        if self.api.replay().is_some() {
+            return None;
+        }
+        let max_age = Duration::from_millis(self.config.max_age_ms);
+        let fresh = |entry: &TokenEntry| cached(entry).filter(|(_, at)| at.elapsed() < max_age).map(|(v, _)| v);
+        let mut rounds = self.rounds.subscribe();
+        let requested_in = *rounds.borrow_and_update();
+        {
+            let mut tokens = self.tokens.lock().unwrap();
+            let now = Instant::now();
+            let entry = tokens.entry(token_id.to_string()).or_insert_with(|| TokenEntry {
+                last_read: now,
+                last_book_read: None,
+                last_quote_read: None,
+                round: 0,
+                price: None,
+                book: None,
+                quote: None,
+            });
+            entry.last_read = now;
+            match kind {
+                Read::Price => {}
+                Read::Book => entry.last_book_read = Some(now),
+                Read::Quote => entry.last_quote_read = Some(now),
+            }
+            if let Some(value) = fresh(entry) {
+                return Some(value);
+            }
+        }
+        self.wake.notify_one();
+        let deadline = tokio::time::Instant::now() + self.max_wait;
+        loop {
+            match tokio::time::timeout_at(deadline, rounds.changed()).await {
+                Ok(Ok(())) => {}
+                _ => return None,
+            }
+            let tokens = self.tokens.lock().unwrap();
+            let entry = tokens.get(token_id)?;
+            if entry.round > requested_in {
+                return fresh(entry);
+            }
+        }
+    }
+
+    /// Fetch loop; runs until the process exits so shutdown can still read prices. Starts the
+    /// WebSocket feed, and in hybrid mode its cross-check, alongside.
+    pub async fn run(self: Arc<Self>) {
+        if let Some(ws) = &self.ws {
+            tokio::spawn(Arc::clone(ws).run());
+            if self.config.data_source == DataSource::Hybrid {
+                tokio::spawn(Arc::clone(&self).cross_check());
+            }
+        }
+        loop {
+            self.wake.notified().await;
+            sleep(Duration::from_millis(self.config.batch_window_ms)).await;
+            self.fetch_batch().await;
+        }
+    }
+
+    /// Hybrid mode: every `hybrid_check_ms`, compares the socket's best bid and ask for every
+    /// token it serves with REST prices. A stalled socket or a gap beyond `max_divergence`
+    /// switches reads to REST with an alert; once the socket is live and agrees again, reads
+    /// switch back.
+    async fn cross_check(self: Arc<Self>) {
+        let Some(ws) = self.ws.clone() else { return };
+        let mut ticker = tokio::time::interval(Duration::from_millis(self.config.hybrid_check_ms));
+        loop {
+            ticker.tick().await;
+            let problem = match ws.silent_for() {
+                None => continue,
+                Some(silent) if !ws.is_live() => Some(format!("WebSocket silent for {}s", silent.as_secs())),
+                Some(_) => match self.divergence(&ws).await {
+                    Ok(divergence) => divergence,
+                    Err(e) => {
+                        log::debug!("Feed cross-check skipped: {}", e);
+                        continue;
+                    }
+                },
+            };
+            let was_fallen_back = self.fallen_back.load(Ordering::Relaxed);
+            match problem {
+                Some(reason) if !was_fallen_back => {
+                    self.fallen_back.store(true, Ordering::Relaxed);
+                    metrics().inc_feed_fallback();
+                    log::error!("🚨 Feed: {} — falling back to REST", reason);
+                    tracing::error!(target: EVENTS, event = "feed_fallback", reason = %reason);
+                }
+                Some(reason) => log::debug!("Feed still on REST: {}", reason),
+                None if was_fallen_back => {
+                    self.fallen_back.store(false, Ordering::Relaxed);
+                    log::info!("📡 Feed: WebSocket is live and agrees with REST again — switching back");
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// The first socket price more than `max_divergence` off its REST price, described.
+    async fn divergence(&self, ws: &WsFeed) -> Result<Option<String>> {
+        let tops = ws.tops();
+        if tops.is_empty() {
+            return Ok(None);
+        }
+        let requests: Vec<(String, &str)> = tops
+            .iter()
+            .flat_map(|(id, _)| [(id.clone(), "SELL"), (id.clone(), "BUY")])
+            .collect();
+        let rest = self.api.get_prices(&requests).await?;
+        for (id, top) in &tops {
+            let Some(sides) = rest.get(id) else { continue };
+            for (side, ws_price) in [("SELL", top.ask), ("BUY", top.bid)] {
+                let (Some(ws_price), Some(rest_price)) = (ws_price, sides.get(side)) else { continue };
+                let gap = (ws_price - rest_price).abs().to_f64().unwrap_or(f64::MAX);
+                if gap > self.config.max_divergence {
+                    return Ok(Some(format!(
+                        "WebSocket {} price of {} is {} but REST says {}",
+                        side, &id[..id.len().min(16)], ws_price, rest_price
+                    )));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    async fn fetch_batch(&self) {
+        let idle = Duration::from_secs(IDLE_SECS);
+        let recent = |at: Option<Instant>| at.is_some_and(|at| at.elapsed() < idle);
+        let (token_ids, book_ids, quote_ids): (Vec<String>, Vec<String>, Vec<String>) = {
+            let mut tokens = self.tokens.lock().unwrap();
+            tokens.retain(|_, e| e.last_read.elapsed() < idle);
+            let with = |wanted: fn(&TokenEntry) -> Option<Instant>| {
+                tokens.iter().filter(|(_, e)| recent(wanted(e))).map(|(id, _)| id.clone()).collect()
+            };
+            (tokens.keys().cloned().collect(), with(|e| e.last_book_read), with(|e| e.last_quote_read))
+        };
+        if token_ids.is_empty() {
+            return;
+        }
+        let price_requests: Vec<(String, &str)> = token_ids
+            .iter()
+            .map(|id| (id.clone(), "SELL"))
+            .chain(quote_ids.iter().map(|id| (id.clone(), "BUY")))
+            .collect();
+        let books = async {
+            if book_ids.is_empty() {
+                Ok(HashMap::new())
+            } else {
+                self.api.get_order_books(&book_ids).await
+            }
+        };
+        let last_trades = async {
+            if quote_ids.is_empty() {
+                Ok(HashMap::new())
+            } else {
+                self.api.get_last_trade_prices(&quote_ids).await
+            }
+        };
+        let (prices, books, last_trades) = tokio::join!(self.api.get_prices(&price_requests), books, last_trades);
+        let prices = prices.unwrap_or_else(|e| {
+            log::debug!("Batch price fetch for {} tokens failed: {}", token_ids.len(), e);
+            HashMap::new()
+        });
+        let mut books = books.unwrap_or_else(|e| {
+            log::debug!("Batch order book fetch for {} tokens failed: {}", book_ids.len(), e);
+            HashMap::new()
+        });
+        // A failed last-trade batch leaves the quotes stale so readers fetch them themselves
+        let last_trades = last_trades.map_err(|e| {
+            log::debug!("Batch last trade fetch for {} tokens failed: {}", quote_ids.len(), e);
+        });
+
+        let round = *self.rounds.borrow() + 1;
+        let now = Instant::now();
+        {
+            let mut tokens = self.tokens.lock().unwrap();
+            for id in &token_ids {
+                let Some(entry) = tokens.get_mut(id) else { continue };
+                entry.round = round;
+                let sides = prices.get(id);
+                if let Some(price) = sides.and_then(|s| s.get("SELL")) {
+                    entry.price = Some((*price, now));
+                }
+                if let Some(book) = books.remove(id) {
+                    entry.book = Some((book, now));
+                }
+                if let (Some(bid), Ok(last_trades)) = (sides.and_then(|s| s.get("BUY")), &last_trades) {
+                    entry.quote = Some((Quote { bid: Some(*bid), last_trade: last_trades.get(id).copied() }, now));
+                }
+            }
+        }
+        self.rounds.send_replace(round);
+    }
+}
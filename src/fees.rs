@@ -0,0 +1,107 @@
+use crate::config::FeeConfig;
+
+/// Polymarket trading fees. A fill of `shares` at `price` pays
+/// `shares × price × rate × (price × (1 − price))^fee_exponent`,
+/// so an exponent of 0 is a flat rate on notional while the 15m crypto markets use a curve
+/// that peaks at 50¢ and vanishes near 0 and 1.
+#[derive(Debug, Clone)]
+pub struct FeeSchedule {
+    config: FeeConfig,
+}
+
+impl FeeSchedule {
+    pub fn new(config: FeeConfig) -> Self {
+        Self { config }
+    }
+
+    fn fee(&self, rate: f64, price: f64, shares: f64) -> f64 {
+        if rate <= 0.0 || shares <= 0.0 {
+            return 0.0;
+        }
+        let price = price.clamp(0.0, 1.0);
+        shares * price * rate * (price * (1.0 - price)).powf(self.config.fee_exponent)
+    }
+
+    /// Fee on a fill that takes liquidity (market sells).
+    pub fn taker_fee(&self, price: f64, shares: f64) -> f64 {
+        self.fee(self.config.taker_fee_rate, price, shares)
+    }
+
+    /// Fee on a resting limit order that gets filled (the pre-limit buys).
+    pub fn maker_fee(&self, price: f64, shares: f64) -> f64 {
+        self.fee(self.config.maker_fee_rate, price, shares)
+    }
+
+    /// Per-share cost of an Up + Down pair bought with resting limit orders, fees included.
+    pub fn pair_cost(&self, up_price: f64, down_price: f64) -> f64 {
+        up_price + down_price + self.maker_fee(up_price, 1.0) + self.maker_fee(down_price, 1.0)
+    }
+
+    /// Expected profit of a resting limit buy of `shares` at `price` on an outcome that wins
+    /// (paying $1 a share) with probability `p`, after the maker fee.
+    pub fn buy_ev(&self, p: f64, price: f64, shares: f64) -> f64 {
+        shares * (p - price) - self.maker_fee(price, shares)
+    }
+
+    /// Expected profit of buying `shares` of both outcomes, given their win probabilities.
+    pub fn pair_ev(&self, (p_up, p_down): (f64, f64), up_price: f64, down_price: f64, shares: f64) -> f64 {
+        self.buy_ev(p_up, up_price, shares) + self.buy_ev(p_down, down_price, shares)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(maker_fee_rate: f64, taker_fee_rate: f64, fee_exponent: f64) -> FeeSchedule {
+        FeeSchedule::new(FeeConfig { maker_fee_rate, taker_fee_rate, fee_exponent })
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-12, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn curve_peaks_at_half_and_vanishes_at_the_ends() {
+        let fees = FeeSchedule::new(FeeConfig::default());
+        // 100 × 0.5 × 0.25 × 0.25²
+        assert_close(fees.taker_fee(0.5, 100.0), 0.78125);
+        assert_close(fees.taker_fee(0.01, 100.0), 100.0 * 0.01 * 0.25 * 0.0099f64.powi(2));
+        assert_close(fees.taker_fee(0.99, 100.0), 100.0 * 0.99 * 0.25 * 0.0099f64.powi(2));
+        assert!(fees.taker_fee(0.01, 100.0) < fees.taker_fee(0.99, 100.0));
+        assert!(fees.taker_fee(0.99, 100.0) < fees.taker_fee(0.5, 100.0));
+        for price in [0.0, 1.0, 1.2, -0.1] {
+            assert_eq!(fees.taker_fee(price, 100.0), 0.0);
+        }
+        assert_eq!(fees.taker_fee(0.5, 0.0), 0.0);
+    }
+
+    #[test]
+    fn zero_exponent_is_a_flat_rate_on_notional() {
+        let fees = schedule(0.0, 0.02, 0.0);
+        assert_close(fees.taker_fee(0.3, 10.0), 0.06);
+        assert_close(fees.taker_fee(0.8, 10.0), 0.16);
+        assert_close(fees.taker_fee(0.5, 10.0), 0.1);
+    }
+
+    #[test]
+    fn makers_and_takers_pay_their_own_rate() {
+        // 10 × 0.4 × 0.24² = 0.2304 before the rate
+        let fees = schedule(0.1, 0.25, 2.0);
+        assert_close(fees.maker_fee(0.4, 10.0), 0.02304);
+        assert_close(fees.taker_fee(0.4, 10.0), 0.0576);
+        assert_eq!(FeeSchedule::new(FeeConfig::default()).maker_fee(0.4, 10.0), 0.0);
+    }
+
+    #[test]
+    fn pair_cost_and_ev_include_maker_fees() {
+        let fees = schedule(0.1, 0.25, 2.0);
+        // Up 0.45: 0.45 × 0.1 × 0.2475² = 0.00275653125; Down 0.50: 0.5 × 0.1 × 0.25² = 0.003125
+        assert_close(fees.pair_cost(0.45, 0.50), 0.95 + 0.00275653125 + 0.003125);
+        // Up: 10 × (0.55 − 0.45) − 0.0275653125; Down: 10 × (0.45 − 0.50) − 0.03125
+        assert_close(fees.buy_ev(0.55, 0.45, 10.0), 0.9724346875);
+        assert_close(fees.pair_ev((0.55, 0.45), 0.45, 0.50, 10.0), 0.9724346875 - 0.53125);
+        // Without fees a pair's EV is the probability mass less the pair price
+        assert_close(schedule(0.0, 0.0, 2.0).pair_ev((0.5, 0.5), 0.45, 0.50, 10.0), 0.5);
+    }
+}
@@ -0,0 +1,140 @@
+use crate::config::FillModelConfig;
+use crate::fees::FeeSchedule;
+use crate::models::PriceLevel;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Result of one simulated market (FAK) sell.
+#[derive(Debug, Clone, Copy)]
+pub struct SimFill {
+    pub shares: f64,
+    /// Volume-weighted fill price before fees
+    pub avg_price: f64,
+    pub fees: f64,
+    /// All requested shares were filled
+    pub complete: bool,
+}
+
+impl SimFill {
+    pub fn proceeds(&self) -> f64 {
+        self.shares * self.avg_price - self.fees
+    }
+}
+
+/// Simulates market sells against order book depth instead of assuming a full fill at the quote.
+///
+/// A sell walks the bids best-first and fills what is there (FAK), so a thin book gives a partial
+/// fill and a worse average price. Without depth it falls back to the quote minus
+/// `fallback_slippage`. Each attempt can also miss outright with probability `miss_rate`
+/// (the book moved before the order landed), and taker fees are charged on every level filled.
+pub struct FillModel {
+    config: FillModelConfig,
+    fees: FeeSchedule,
+    rng: StdRng,
+}
+
+impl FillModel {
+    pub fn new(config: FillModelConfig, fees: FeeSchedule) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self { config, fees, rng }
+    }
+
+    /// Returns `None` when the attempt misses. Nothing fills below `floor`, like a marketable
+    /// limit at that price.
+    pub fn market_sell(&mut self, bids: &[PriceLevel], quoted_price: f64, shares: f64, floor: f64) -> Option<SimFill> {
+        if !self.config.enabled {
+            let fees = self.fees.taker_fee(quoted_price, shares);
+            return Some(SimFill { shares, avg_price: quoted_price, fees, complete: true });
+        }
+        if self.config.miss_rate > 0.0 && self.rng.gen_bool(self.config.miss_rate.min(1.0)) {
+            return None;
+        }
+
+        let (filled, notional, fees) = if bids.is_empty() {
+            let price = (quoted_price - self.config.fallback_slippage).max(0.0);
+            if price < floor - 1e-9 {
+                return None;
+            }
+            (shares, shares * price, self.fees.taker_fee(price, shares))
+        } else {
+            let mut remaining = shares;
+            let (mut notional, mut fees) = (0.0, 0.0);
+            for level in bids.iter().take_while(|l| l.price >= floor - 1e-9) {
+                if remaining <= 0.0 {
+                    break;
+                }
+                let take = remaining.min(level.size);
+                notional += take * level.price;
+                fees += self.fees.taker_fee(level.price, take);
+                remaining -= take;
+            }
+            (shares - remaining.max(0.0), notional, fees)
+        };
+        if filled <= 0.0 {
+            return None;
+        }
+        Some(SimFill {
+            shares: filled,
+            avg_price: notional / filled,
+            fees,
+            complete: filled >= shares - 1e-9,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FeeConfig;
+
+    fn model(enabled: bool, miss_rate: f64) -> FillModel {
+        let config = FillModelConfig { enabled, miss_rate, fallback_slippage: 0.02, seed: Some(42), latency_ms_simulated: 0 };
+        // A flat 2% of notional, so fees are easy to check
+        FillModel::new(config, FeeSchedule::new(FeeConfig { maker_fee_rate: 0.0, taker_fee_rate: 0.02, fee_exponent: 0.0 }))
+    }
+
+    fn bids(levels: &[(f64, f64)]) -> Vec<PriceLevel> {
+        levels.iter().map(|&(price, size)| PriceLevel { price, size }).collect()
+    }
+
+    #[test]
+    fn sells_fill_what_the_book_holds_above_the_floor() {
+        let book = bids(&[(0.60, 5.0), (0.58, 10.0), (0.50, 100.0)]);
+        // (case, bids, quote, shares, floor, expected (shares, average price, complete)); None when nothing fills
+        type Case<'a> = (&'a str, &'a [PriceLevel], f64, f64, f64, Option<(f64, f64, bool)>);
+        let cases: [Case; 6] = [
+            ("matched at the top level", &book, 0.60, 5.0, 0.55, Some((5.0, 0.60, true))),
+            ("matched across levels", &book, 0.60, 10.0, 0.55, Some((10.0, 0.59, true))),
+            ("partial: the floor stops the walk", &book, 0.60, 20.0, 0.55, Some((15.0, (3.0 + 5.8) / 15.0, false))),
+            ("killed: no bid at the floor", &book, 0.60, 5.0, 0.61, None),
+            ("no depth: quote less slippage", &[], 0.60, 8.0, 0.55, Some((8.0, 0.58, true))),
+            ("no depth and the fallback is under the floor", &[], 0.60, 8.0, 0.59, None),
+        ];
+        for (case, levels, quoted, shares, floor, expected) in cases {
+            let fill = model(true, 0.0).market_sell(levels, quoted, shares, floor);
+            let got = fill.map(|f| (f.shares, f.avg_price, f.complete));
+            match (got, expected) {
+                (Some((s, p, c)), Some((es, ep, ec))) => {
+                    assert!((s - es).abs() < 1e-9 && (p - ep).abs() < 1e-9 && c == ec, "{}: {:?}", case, got);
+                    let fill = fill.unwrap();
+                    assert!((fill.fees - 0.02 * s * p).abs() < 1e-9, "{}: fees {}", case, fill.fees);
+                }
+                (None, None) => {}
+                _ => panic!("{}: got {:?}, expected {:?}", case, got, expected),
+            }
+        }
+    }
+
+    #[test]
+    fn misses_and_the_disabled_model() {
+        let book = bids(&[(0.60, 1.0)]);
+        assert!(model(true, 1.0).market_sell(&book, 0.60, 1.0, 0.0).is_none());
+        // Off: the whole size fills at the quote, thin book or not
+        let fill = model(false, 1.0).market_sell(&book, 0.62, 10.0, 0.0).unwrap();
+        assert_eq!((fill.shares, fill.avg_price, fill.complete), (10.0, 0.62, true));
+        assert!((fill.proceeds() - (6.2 - 0.124)).abs() < 1e-9);
+    }
+}
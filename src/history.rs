@@ -0,0 +1,139 @@
+use crate::api::PolymarketApi;
+use crate::config::{Config, MarketDuration};
+use crate::discovery::MarketDiscovery;
+use crate::models::{MarketSnapshot, PricePoint, TokenSnapshot};
+use crate::monitor::SnapshotRecorder;
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Counts reported by `history fetch`.
+#[derive(Debug, Default)]
+pub struct FetchSummary {
+    pub markets: usize,
+    pub snapshots: usize,
+    pub skipped_existing: usize,
+    pub missing: usize,
+}
+
+/// Backfills the last `periods` closed markets per asset from the CLOB price history and writes
+/// them as snapshot files (`{output}/{asset}/{period_start}.ndjson.gz`) the backtester can load.
+/// Periods that already have a file are skipped, so repeated runs only fetch what is new.
+pub async fn fetch(
+    api: Arc<PolymarketApi>,
+    config: &Config,
+    assets: &[String],
+    duration: MarketDuration,
+    periods: u32,
+    interval: &str,
+    output: &Path,
+) -> Result<FetchSummary> {
+    let discovery = MarketDiscovery::new(api.clone(), config);
+    let mut recorder = SnapshotRecorder::new(output.to_path_buf(), config.monitor.full_book_every);
+    let mut summary = FetchSummary::default();
+    let current_period = MarketDiscovery::period_start_et(duration, chrono::Utc::now().timestamp());
+
+    for asset in assets {
+        let asset = asset.to_uppercase();
+        let mut period_start = current_period;
+        for _ in 0..periods {
+            period_start = MarketDiscovery::previous_period_start_et(duration, period_start);
+            if SnapshotRecorder::path_for(output, &asset, period_start).exists() {
+                summary.skipped_existing += 1;
+                continue;
+            }
+            let Some(slug) = discovery.slug(&asset, duration, period_start) else {
+                anyhow::bail!("Unknown asset {} (add it to markets in the config)", asset);
+            };
+            match fetch_market(api.as_ref(), &discovery, &asset, duration, period_start, interval).await {
+                Ok((market, snapshots)) if snapshots.is_empty() => {
+                    log::debug!("No price history for {}", market);
+                    summary.missing += 1;
+                }
+                Ok((market, snapshots)) => {
+                    for snapshot in &snapshots {
+                        recorder.write(snapshot)?;
+                    }
+                    recorder.close_before(i64::MAX);
+                    summary.markets += 1;
+                    summary.snapshots += snapshots.len();
+                    eprintln!("   {} {} points from {}", asset, snapshots.len(), market);
+                }
+                Err(e) => {
+                    log::warn!("Skipping {}: {}", slug, e);
+                    summary.missing += 1;
+                }
+            }
+        }
+    }
+    Ok(summary)
+}
+
+async fn fetch_market(
+    api: &PolymarketApi,
+    discovery: &MarketDiscovery,
+    asset: &str,
+    duration: MarketDuration,
+    period_start: i64,
+    interval: &str,
+) -> Result<(String, Vec<MarketSnapshot>)> {
+    let market = discovery.find_market(asset, duration, period_start).await?;
+    let label = if market.slug.is_empty() { market.condition_id.clone() } else { market.slug.clone() };
+    let (up_token_id, down_token_id) = discovery.get_market_tokens(&market.condition_id).await?;
+    let up = api.get_price_history(&up_token_id, interval).await?;
+    let down = api.get_price_history(&down_token_id, interval).await?;
+    let template = MarketSnapshot {
+        timestamp_ms: 0,
+        asset: asset.to_string(),
+        condition_id: market.condition_id,
+        period_start,
+        up: token_snapshot(&up_token_id),
+        down: token_snapshot(&down_token_id),
+        volume_24h: None,
+        invalid: None,
+    };
+    Ok((label, merge_series(&template, &up, &down)))
+}
+
+fn token_snapshot(token_id: &str) -> TokenSnapshot {
+    TokenSnapshot {
+        token_id: token_id.to_string(),
+        price: 0.0,
+        best_bid: None,
+        best_ask: None,
+        bid: None,
+        mid: None,
+        last_trade_price: None,
+        bids: Vec::new(),
+        asks: Vec::new(),
+        tick_size: None,
+    }
+}
+
+/// Aligns the Up and Down series on their combined timestamps, carrying each side's last price
+/// forward. Points before both sides have a price are dropped.
+fn merge_series(template: &MarketSnapshot, up: &[PricePoint], down: &[PricePoint]) -> Vec<MarketSnapshot> {
+    let mut timeline: BTreeMap<i64, (Option<f64>, Option<f64>)> = BTreeMap::new();
+    for point in up {
+        timeline.entry(point.t).or_default().0 = Some(point.p);
+    }
+    for point in down {
+        timeline.entry(point.t).or_default().1 = Some(point.p);
+    }
+
+    let (mut last_up, mut last_down) = (None, None);
+    let mut snapshots = Vec::new();
+    for (t, (up_price, down_price)) in timeline {
+        last_up = up_price.or(last_up);
+        last_down = down_price.or(last_down);
+        if let (Some(up_price), Some(down_price)) = (last_up, last_down) {
+            let mut snapshot = template.clone();
+            snapshot.timestamp_ms = t * 1000;
+            snapshot.up.price = up_price;
+            snapshot.down.price = down_price;
+            snapshots.push(snapshot);
+        }
+    }
+    snapshots
+}
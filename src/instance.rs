@@ -0,0 +1,166 @@
+use crate::api::PolymarketApi;
+use crate::clock;
+use crate::config::{Config, OnConflict};
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// The lock file of the running instance, locked for as long as the process lives. Besides
+/// the PID it lists the orders this instance posted, so the next instance on the wallet can
+/// tell its predecessor's resting orders from those of another live instance.
+pub struct InstanceLock {
+    file: File,
+}
+
+enum Acquired {
+    Locked { lock: InstanceLock, known_orders: HashSet<String> },
+    Held { holder: String },
+}
+
+static LOCK: OnceLock<Mutex<InstanceLock>> = OnceLock::new();
+
+/// Notes an order this instance posted in the lock file; nothing when no lock is held.
+pub fn record_order(order_id: &str) {
+    if let Some(lock) = LOCK.get() {
+        let mut lock = lock.lock().unwrap();
+        if let Err(e) = writeln!(lock.file, "order {} {}", order_id, clock::now()) {
+            log::debug!("Failed to note order {} in the instance lock: {}", order_id, e);
+        }
+    }
+}
+
+/// Lock file for `config` when `instance.lock_file` isn't set: one per proxy wallet.
+fn default_path(config: &Config) -> PathBuf {
+    let wallet = config.polymarket.proxy_wallet_address.as_deref().unwrap_or("default").to_lowercase();
+    std::env::temp_dir().join(format!("polymarket-bot-{}.lock", wallet))
+}
+
+/// Locks `path`, keeping the orders its previous holder noted in the last `recent_secs`.
+fn acquire(path: &Path, recent_secs: u64) -> Result<Acquired> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .with_context(|| format!("Failed to open instance lock {}", path.display()))?;
+    let mut content = String::new();
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let _ = file.read_to_string(&mut content);
+            let holder = content
+                .lines()
+                .find_map(|l| l.strip_prefix("pid "))
+                .map_or_else(|| "another process".to_string(), |pid| format!("process {}", pid));
+            return Ok(Acquired::Held { holder });
+        }
+        Err(TryLockError::Error(e)) => return Err(e).with_context(|| format!("Failed to lock {}", path.display())),
+    }
+
+    file.read_to_string(&mut content)?;
+    let since = clock::now() - recent_secs as i64;
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|l| {
+            let mut fields = l.split(' ');
+            fields.next() == Some("order") && fields.nth(1).and_then(|t| t.parse::<i64>().ok()).is_some_and(|t| t >= since)
+        })
+        .collect();
+    let known_orders = kept.iter().filter_map(|l| l.split(' ').nth(1)).map(str::to_string).collect();
+
+    file.set_len(0)?;
+    file.seek(SeekFrom::Start(0))?;
+    writeln!(file, "pid {}", std::process::id())?;
+    for line in kept {
+        writeln!(file, "{}", line)?;
+    }
+    Ok(Acquired::Locked { lock: InstanceLock { file }, known_orders })
+}
+
+/// Open orders placed in the last `recent_secs` that neither this instance nor the previous
+/// holder of the lock posted, described for the log; `None` if there are none or the check fails.
+async fn foreign_orders(api: &PolymarketApi, known: &HashSet<String>, recent_secs: u64) -> Option<String> {
+    let since = clock::now() - recent_secs as i64;
+    match api.get_open_orders().await {
+        Ok(orders) => {
+            let foreign: Vec<_> = orders.iter().filter(|o| o.created_at >= since && !known.contains(&o.order_id)).collect();
+            (!foreign.is_empty()).then(|| {
+                format!("{} open order(s) placed in the last {}s by another client (first {} on {})",
+                    foreign.len(), recent_secs, foreign[0].order_id, foreign[0].condition_id)
+            })
+        }
+        Err(e) => {
+            log::warn!("Could not list open orders to check for another instance: {}", e);
+            None
+        }
+    }
+}
+
+/// Makes sure no other live instance trades the wallet: takes the instance lock, then looks
+/// for recent open orders it didn't post. Returns whether this instance has to stay
+/// monitor-only (`instance.on_conflict`); with `refuse`, a conflict is an error. Simulation
+/// and paper-live runs don't post orders and skip the check.
+pub async fn claim(config: &Config, api: &PolymarketApi) -> Result<bool> {
+    let cfg = &config.instance;
+    if !cfg.enabled || config.strategy.simulation_mode || config.strategy.paper_live || !config.polymarket.can_sign() {
+        return Ok(false);
+    }
+    let path = cfg.lock_file.clone().unwrap_or_else(|| default_path(config));
+    let conflict = match acquire(&path, cfg.recent_secs)? {
+        Acquired::Held { holder } => Some(format!("{} is locked by {}", path.display(), holder)),
+        Acquired::Locked { lock, known_orders } => {
+            let foreign = if cfg.exchange_check { foreign_orders(api, &known_orders, cfg.recent_secs).await } else { None };
+            // Keep the lock only when trading; a monitor-only instance leaves it to the other one
+            if foreign.is_none() {
+                let _ = LOCK.set(Mutex::new(lock));
+            }
+            foreign
+        }
+    };
+    let Some(conflict) = conflict else {
+        log::info!("🔒 Instance lock held: {}", path.display());
+        return Ok(false);
+    };
+    match cfg.on_conflict {
+        OnConflict::Refuse => anyhow::bail!(
+            "Another instance looks active on this wallet: {}. Stop it, or set instance.on_conflict to \"monitor_only\"",
+            conflict
+        ),
+        OnConflict::MonitorOnly => {
+            log::error!("🔒 Another instance looks active on this wallet: {} — running monitor-only, no new orders", conflict);
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_holder_is_refused_and_sees_the_pid() {
+        let path = std::env::temp_dir().join(format!("polymarket-bot-test-{}.lock", std::process::id()));
+        std::fs::write(&path, format!("pid 1\norder 0xold 1\norder 0xrecent {}\n", clock::now())).unwrap();
+
+        let Acquired::Locked { mut lock, known_orders } = acquire(&path, 900).unwrap() else {
+            panic!("lock should be free");
+        };
+        assert_eq!(known_orders, HashSet::from(["0xrecent".to_string()]));
+        writeln!(lock.file, "order 0xnew {}", clock::now()).unwrap();
+        match acquire(&path, 900).unwrap() {
+            Acquired::Held { holder } => assert_eq!(holder, format!("process {}", std::process::id())),
+            Acquired::Locked { .. } => panic!("lock should be held"),
+        }
+
+        drop(lock);
+        let Acquired::Locked { known_orders, .. } = acquire(&path, 900).unwrap() else {
+            panic!("lock should be released");
+        };
+        assert_eq!(known_orders, HashSet::from(["0xrecent".to_string(), "0xnew".to_string()]));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::config::Config;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use alloy::signers::local::PrivateKeySigner;
+use anyhow::{bail, Context, Result};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Environment variable read for the passphrase before asking on the terminal.
+pub const PASSPHRASE_ENV: &str = "POLYMARKET_KEY_PASSPHRASE";
+
+/// scrypt cost of new key files: N = 2^17 with r = 8 takes 128 MiB and about a second.
+const LOG_N: u8 = 17;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    /// Hex
+    pub salt: String,
+}
+
+/// A wallet private key encrypted with AES-256-GCM under a key derived from a passphrase by
+/// scrypt. The address is authenticated with the key, so it can be shown before unlocking.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub version: u32,
+    pub address: String,
+    pub scrypt: ScryptParams,
+    /// Hex
+    pub nonce: String,
+    /// Hex
+    pub ciphertext: String,
+}
+
+impl KeyFile {
+    pub fn encrypt(private_key: &str, passphrase: &str) -> Result<Self> {
+        Self::encrypt_with(private_key, passphrase, LOG_N)
+    }
+
+    fn encrypt_with(private_key: &str, passphrase: &str, log_n: u8) -> Result<Self> {
+        let address = PrivateKeySigner::from_str(private_key).context("Not a valid private key")?.address().to_string();
+        let (mut salt, mut nonce) = ([0u8; 16], [0u8; 12]);
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let scrypt = ScryptParams { log_n, r: 8, p: 1, salt: hex::encode(salt) };
+        let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &scrypt)?)?;
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: private_key.as_bytes(), aad: address.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+        Ok(Self { version: 1, address, scrypt, nonce: hex::encode(nonce), ciphertext: hex::encode(ciphertext) })
+    }
+
+    /// The private key; fails on a wrong passphrase or a file that was altered.
+    pub fn decrypt(&self, passphrase: &str) -> Result<String> {
+        if self.version != 1 {
+            bail!("Unsupported key file version {}", self.version);
+        }
+        let cipher = Aes256Gcm::new_from_slice(&derive_key(passphrase, &self.scrypt)?)?;
+        let nonce = hex::decode(&self.nonce).context("Invalid nonce")?;
+        anyhow::ensure!(nonce.len() == 12, "Invalid nonce");
+        let ciphertext = hex::decode(&self.ciphertext).context("Invalid ciphertext")?;
+        let key = cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: self.address.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("Wrong passphrase, or the key file was modified"))?;
+        String::from_utf8(key).context("Decrypted key is not text")
+    }
+
+    /// Writes the file, readable by its owner only; never replaces an existing one.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let file = options.open(path).context(format!("Failed to create {}", path.display()))?;
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+}
+
+/// A `private_key_file`: one written by `encrypt-key`, or a Web3 Secret Storage keystore as
+/// written by geth, Foundry's `cast wallet import` and most wallets' JSON export.
+enum StoredKey {
+    Encrypted(KeyFile),
+    Web3 { path: PathBuf, address: Option<String> },
+}
+
+impl StoredKey {
+    fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).context(format!("Failed to read key file {}", path.display()))?;
+        let value: serde_json::Value = serde_json::from_str(&content).context(format!("Invalid key file {}", path.display()))?;
+        if value.get("crypto").or_else(|| value.get("Crypto")).is_some() {
+            let address = value.get("address").and_then(|a| a.as_str()).map(|a| format!("0x{}", a.trim_start_matches("0x")));
+            return Ok(Self::Web3 { path: path.to_path_buf(), address });
+        }
+        serde_json::from_value(value).map(Self::Encrypted).context(format!("Invalid key file {}", path.display()))
+    }
+
+    fn address(&self) -> &str {
+        match self {
+            Self::Encrypted(file) => &file.address,
+            Self::Web3 { address, .. } => address.as_deref().unwrap_or("address not stored"),
+        }
+    }
+
+    fn decrypt(&self, passphrase: &str) -> Result<String> {
+        match self {
+            Self::Encrypted(file) => file.decrypt(passphrase),
+            Self::Web3 { path, .. } => {
+                let signer = PrivateKeySigner::decrypt_keystore(path, passphrase).context("Wrong password, or the keystore is damaged")?;
+                Ok(format!("0x{}", hex::encode(signer.to_bytes())))
+            }
+        }
+    }
+}
+
+fn derive_key(passphrase: &str, params: &ScryptParams) -> Result<[u8; 32]> {
+    let salt = hex::decode(&params.salt).context("Invalid salt")?;
+    let params = scrypt::Params::new(params.log_n, params.r, params.p, 32).map_err(|e| anyhow::anyhow!("Invalid scrypt parameters: {}", e))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), &salt, &params, &mut key).map_err(|e| anyhow::anyhow!("scrypt failed: {}", e))?;
+    Ok(key)
+}
+
+/// The passphrase from `POLYMARKET_KEY_PASSPHRASE`, else the first line read from file
+/// descriptor `fd`, else asked for on the terminal with `prompt`.
+pub fn passphrase(fd: Option<i32>, prompt: &str) -> Result<String> {
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(passphrase);
+    }
+    if let Some(fd) = fd {
+        return read_fd(fd);
+    }
+    rpassword::prompt_password(prompt)
+        .with_context(|| format!("No terminal to ask for the key passphrase; set {} or pass --passphrase-fd", PASSPHRASE_ENV))
+}
+
+/// A passphrase for a new key file: from the environment or `fd` as for `passphrase`, otherwise
+/// asked for twice on the terminal.
+pub fn new_passphrase(fd: Option<i32>) -> Result<String> {
+    let asks = std::env::var(PASSPHRASE_ENV).is_err() && fd.is_none();
+    let first = passphrase(fd, "🔑 New passphrase: ")?;
+    anyhow::ensure!(!first.is_empty(), "The passphrase must not be empty");
+    if asks {
+        anyhow::ensure!(passphrase(None, "🔑 Repeat it: ")? == first, "The passphrases don't match");
+    }
+    Ok(first)
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String> {
+    use std::io::BufRead;
+    use std::os::unix::io::FromRawFd;
+    // SAFETY: the descriptor was handed to us for this read and is closed once it's done
+    let file = unsafe { std::fs::File::from_raw_fd(fd) };
+    let mut line = String::new();
+    std::io::BufReader::new(file).read_line(&mut line).with_context(|| format!("Failed to read the passphrase from fd {}", fd))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<String> {
+    bail!("--passphrase-fd is only supported on Unix; set {} instead", PASSPHRASE_ENV)
+}
+
+/// Decrypts the `private_key_file` of the main wallet and of each account into its
+/// `private_key`, asking for the passphrase once for all of them. Web3 keystores are unlocked
+/// with their own password, which is the same passphrase.
+pub fn unlock(config: &mut Config, fd: Option<i32>) -> Result<()> {
+    let polymarket = &mut config.polymarket;
+    let slots: Vec<(PathBuf, &mut Option<String>)> = std::iter::once((polymarket.private_key_file.clone(), &mut polymarket.private_key))
+        .chain(config.accounts.iter_mut().map(|a| (a.private_key_file.clone(), &mut a.private_key)))
+        .filter_map(|(file, key)| Some((file?, key)))
+        .collect();
+    let mut passphrase_read: Option<String> = None;
+    for (path, key) in slots {
+        let file = StoredKey::load(&path)?;
+        let passphrase = match &passphrase_read {
+            Some(passphrase) => passphrase,
+            None => passphrase_read.insert(passphrase(fd, &format!("🔑 Passphrase for {} ({}): ", path.display(), file.address()))?),
+        };
+        let private_key = file.decrypt(passphrase).with_context(|| format!("Failed to unlock {}", path.display()))?;
+        let address = PrivateKeySigner::from_str(&private_key).context("Not a valid private key")?.address();
+        *key = Some(private_key);
+        eprintln!("🔓 Unlocked wallet {} from {}", address, path.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Anvil's first test account
+    const KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn keys_decrypt_with_their_passphrase_only() {
+        let file = KeyFile::encrypt_with(KEY, "correct horse", 10).unwrap();
+        assert_eq!(file.address, "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        assert!(!file.ciphertext.contains(&KEY[2..]));
+        let file: KeyFile = serde_json::from_str(&serde_json::to_string(&file).unwrap()).unwrap();
+        assert_eq!(file.decrypt("correct horse").unwrap(), KEY);
+        assert!(file.decrypt("wrong horse").is_err());
+        // The address can't be swapped for another wallet's
+        let relabelled = KeyFile { address: "0x70997970C51812dc3A010C7d01b50e0d17dc79C8".to_string(), ..file };
+        assert!(relabelled.decrypt("correct horse").is_err());
+        assert!(KeyFile::encrypt_with("not a key", "correct horse", 10).is_err());
+    }
+
+    #[test]
+    fn web3_keystores_are_read_as_key_files() {
+        let dir = std::env::temp_dir().join(format!("web3-keystore-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let secret = hex::decode(&KEY[2..]).unwrap();
+        PrivateKeySigner::encrypt_keystore(&dir, &mut rand::thread_rng(), secret, "correct horse", Some("wallet.json")).unwrap();
+        let stored = StoredKey::load(&dir.join("wallet.json")).unwrap();
+        assert!(matches!(stored, StoredKey::Web3 { .. }));
+        assert_eq!(stored.decrypt("correct horse").unwrap(), KEY);
+        assert!(stored.decrypt("wrong horse").is_err());
+
+        let ours = dir.join("ours.json");
+        KeyFile::encrypt_with(KEY, "correct horse", 10).unwrap().save(&ours).unwrap();
+        let stored = StoredKey::load(&ours).unwrap();
+        assert_eq!(stored.address(), "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266");
+        assert_eq!(stored.decrypt("correct horse").unwrap(), KEY);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,244 @@
+use crate::trend::Trend;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// One ledger row. Buys and sells are recorded when they happen; resolutions when a held
+/// position's market closes, or pending_resolution while it is disputed or late; rollups when
+/// a profit window (see `profit.rollups`) closes; adjustments when `reconcile --apply` corrects
+/// the shares held in an outcome to what the wallet holds.
+/// Rows are appended as JSON lines and exported on demand.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub timestamp: i64,
+    /// "buy", "sell", "resolution", "pending_resolution", "rollup" or "adjustment". Rollups summarize realized PnL already
+    /// recorded on other rows; leave them out when summing `pnl`. Adjustments carry a signed `size`
+    /// and no PnL.
+    pub kind: String,
+    pub market: String,
+    pub condition_id: String,
+    pub period_timestamp: i64,
+    /// Outcome traded ("Up"/"Down"), or the winning outcome for a resolution
+    pub side: String,
+    pub price: f64,
+    pub size: f64,
+    pub fees: f64,
+    /// Up + Down limit prices for the pair at decision time
+    pub cost_per_pair: f64,
+    /// Realized PnL (resolutions and sells only)
+    pub pnl: Option<f64>,
+    /// Account that traded, when `accounts` are configured
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Rule that produced this row; None on rollups and rows written before rules were tagged
+    #[serde(default)]
+    pub rationale: Option<Rationale>,
+    /// Rule that opened the position (`pre_order` or `mid_market`); the same as `rationale` on buys
+    #[serde(default)]
+    pub entry: Option<Rationale>,
+    /// Up price trend, Up and Down prices on the signal market and the pair's expected value
+    /// after fees when the position was opened
+    #[serde(default)]
+    pub trend: Option<Trend>,
+    #[serde(default)]
+    pub signal_up_price: Option<f64>,
+    #[serde(default)]
+    pub signal_down_price: Option<f64>,
+    #[serde(default)]
+    pub ev: Option<f64>,
+}
+
+/// Strategy rule behind a ledger row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rationale {
+    /// Pair at `price_limit` on the next market after a Good signal on the current one
+    PreOrder,
+    /// Pair on the current market after a Good signal (`signal.mid_market_enabled`)
+    MidMarket,
+    /// One side on the current market bought on the answer of `strategy.plugin`
+    Plugin,
+    /// Loser sold once both sides filled and the winner crossed `sell_opposite_above`
+    SellOpposite,
+    /// Lone fill sold on `danger_price` or `danger_time_passed`
+    Danger,
+    /// Part of the winner sold into a late spike (`spike_sell`)
+    SpikeSell,
+    /// Lone fill flattened at shutdown
+    Shutdown,
+    /// Held shares settled by the market's resolution
+    Resolution,
+}
+
+impl Rationale {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Rationale::PreOrder => "pre_order",
+            Rationale::MidMarket => "mid_market",
+            Rationale::Plugin => "plugin",
+            Rationale::SellOpposite => "sell_opposite",
+            Rationale::Danger => "danger",
+            Rationale::SpikeSell => "spike_sell",
+            Rationale::Shutdown => "shutdown",
+            Rationale::Resolution => "resolution",
+        }
+    }
+}
+
+/// Why a pair was bought and what the rule saw, carried with the position so every row it
+/// produces can be attributed to the entry rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decision {
+    pub rule: Rationale,
+    pub trend: Trend,
+    pub up_price: f64,
+    pub down_price: f64,
+    pub ev: f64,
+}
+
+impl LedgerEntry {
+    /// Tags the row with `rationale` and the inputs of the decision that opened the position.
+    pub fn tagged(mut self, rationale: Rationale, decision: Option<&Decision>) -> Self {
+        self.rationale = Some(rationale);
+        if let Some(d) = decision {
+            self.entry = Some(d.rule);
+            self.trend = Some(d.trend);
+            self.signal_up_price = Some(d.up_price);
+            self.signal_down_price = Some(d.down_price);
+            self.ev = Some(d.ev);
+        }
+        self
+    }
+}
+
+/// Append-only trade ledger backed by a JSON-lines file.
+pub struct Ledger {
+    path: PathBuf,
+    file: Mutex<Option<std::fs::File>>,
+}
+
+impl Ledger {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, file: Mutex::new(None) }
+    }
+
+    pub fn record(&self, entry: &LedgerEntry) {
+        if let Err(e) = self.append(entry) {
+            log::warn!("Failed to write ledger entry to {}: {}", self.path.display(), e);
+        }
+    }
+
+    /// Syncs written entries to disk.
+    pub fn flush(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            if let Err(e) = file.flush().and_then(|_| file.sync_all()) {
+                log::warn!("Failed to flush ledger {}: {}", self.path.display(), e);
+            }
+        }
+    }
+
+    fn append(&self, entry: &LedgerEntry) -> Result<()> {
+        let mut file = self.file.lock().unwrap();
+        if file.is_none() {
+            *file = Some(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .context(format!("Failed to open ledger {}", self.path.display()))?,
+            );
+        }
+        // One write per row, so strategies for several accounts can append to the same file
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        file.as_mut().unwrap().write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+pub fn read_entries(path: &Path) -> Result<Vec<LedgerEntry>> {
+    let file = std::fs::File::open(path).context(format!("Failed to open ledger {}", path.display()))?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: LedgerEntry = serde_json::from_str(&line)
+            .context(format!("Invalid ledger entry on line {}", i + 1))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+pub fn export_csv(entries: &[LedgerEntry], output: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(output).context(format!("Failed to create {}", output.display()))?;
+    for entry in entries {
+        writer.serialize(entry)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(feature = "parquet")]
+pub fn export_parquet(entries: &[LedgerEntry], output: &Path) -> Result<()> {
+    use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("timestamp", DataType::Int64, false),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("market", DataType::Utf8, false),
+        Field::new("condition_id", DataType::Utf8, false),
+        Field::new("period_timestamp", DataType::Int64, false),
+        Field::new("side", DataType::Utf8, false),
+        Field::new("price", DataType::Float64, false),
+        Field::new("size", DataType::Float64, false),
+        Field::new("fees", DataType::Float64, false),
+        Field::new("cost_per_pair", DataType::Float64, false),
+        Field::new("pnl", DataType::Float64, true),
+        Field::new("account", DataType::Utf8, true),
+        Field::new("rationale", DataType::Utf8, true),
+        Field::new("entry", DataType::Utf8, true),
+        Field::new("trend", DataType::Utf8, true),
+        Field::new("signal_up_price", DataType::Float64, true),
+        Field::new("signal_down_price", DataType::Float64, true),
+        Field::new("ev", DataType::Float64, true),
+    ]));
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from_iter_values(entries.iter().map(|e| e.timestamp))),
+        Arc::new(StringArray::from_iter_values(entries.iter().map(|e| e.kind.as_str()))),
+        Arc::new(StringArray::from_iter_values(entries.iter().map(|e| e.market.as_str()))),
+        Arc::new(StringArray::from_iter_values(entries.iter().map(|e| e.condition_id.as_str()))),
+        Arc::new(Int64Array::from_iter_values(entries.iter().map(|e| e.period_timestamp))),
+        Arc::new(StringArray::from_iter_values(entries.iter().map(|e| e.side.as_str()))),
+        Arc::new(Float64Array::from_iter_values(entries.iter().map(|e| e.price))),
+        Arc::new(Float64Array::from_iter_values(entries.iter().map(|e| e.size))),
+        Arc::new(Float64Array::from_iter_values(entries.iter().map(|e| e.fees))),
+        Arc::new(Float64Array::from_iter_values(entries.iter().map(|e| e.cost_per_pair))),
+        Arc::new(Float64Array::from(entries.iter().map(|e| e.pnl).collect::<Vec<_>>())),
+        Arc::new(StringArray::from(entries.iter().map(|e| e.account.as_deref()).collect::<Vec<_>>())),
+        Arc::new(StringArray::from(entries.iter().map(|e| e.rationale.map(|r| r.label())).collect::<Vec<_>>())),
+        Arc::new(StringArray::from(entries.iter().map(|e| e.entry.map(|r| r.label())).collect::<Vec<_>>())),
+        Arc::new(StringArray::from(entries.iter().map(|e| e.trend.map(|t| format!("{:?}", t).to_lowercase())).collect::<Vec<_>>())),
+        Arc::new(Float64Array::from(entries.iter().map(|e| e.signal_up_price).collect::<Vec<_>>())),
+        Arc::new(Float64Array::from(entries.iter().map(|e| e.signal_down_price).collect::<Vec<_>>())),
+        Arc::new(Float64Array::from(entries.iter().map(|e| e.ev).collect::<Vec<_>>())),
+    ];
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    let file = std::fs::File::create(output).context(format!("Failed to create {}", output.display()))?;
+    let mut writer = ArrowWriter::try_new(file, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+pub fn export_parquet(_entries: &[LedgerEntry], _output: &Path) -> Result<()> {
+    anyhow::bail!("Parquet export requires building with `--features parquet`")
+}
@@ -0,0 +1,509 @@
+use crate::config::{LogRotationConfig, LoggingConfig};
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::{filter_fn, EnvFilter};
+use tracing_subscriber::fmt::writer::{BoxMakeWriter, MakeWriter};
+use tracing_subscriber::fmt::{self, format, FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::{Context as LayerContext, Layer};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
+
+/// Target for structured events (`event = "..."` plus fields). They only go to the JSON log;
+/// the console keeps the existing human-readable messages.
+pub const EVENTS: &str = "events";
+
+/// Recent console log lines, kept in memory for the TUI instead of going to stderr.
+#[derive(Clone, Default)]
+pub struct ConsoleBuffer(Arc<Mutex<VecDeque<String>>>);
+
+impl ConsoleBuffer {
+    const CAPACITY: usize = 500;
+
+    /// The last `n` lines, oldest first.
+    pub fn tail(&self, n: usize) -> Vec<String> {
+        let lines = self.0.lock().unwrap();
+        lines.iter().skip(lines.len().saturating_sub(n)).cloned().collect()
+    }
+}
+
+impl io::Write for ConsoleBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut lines = self.0.lock().unwrap();
+        for line in String::from_utf8_lossy(buf).lines().filter(|l| !l.is_empty()) {
+            if lines.len() == Self::CAPACITY {
+                lines.pop_front();
+            }
+            lines.push_back(line.to_string());
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for ConsoleBuffer {
+    type Writer = ConsoleBuffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Installs the console layer and, if `json_file` is set, a JSON-lines layer that tags every
+/// record with the enclosing market span (market, condition_id, period_timestamp). With
+/// `market_dir`, console lines from a market span are also written to that market's own file.
+/// Existing `log::` macros are bridged into the same pipeline. With `buffer`, console lines
+/// are captured there instead of written to stderr. Hold the returned guard until exit.
+pub fn init(config: &LoggingConfig, buffer: Option<ConsoleBuffer>) -> Result<LogGuard> {
+    let mut guard = LogGuard(None);
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let writer = match buffer {
+        Some(buffer) => BoxMakeWriter::new(buffer),
+        None => BoxMakeWriter::new(std::io::stderr),
+    };
+    let console = fmt::layer()
+        .with_writer(writer)
+        .event_format(MessageOnly)
+        .with_filter(filter_fn(|meta| meta.target() != EVENTS));
+
+    let json_file = match &config.json_file {
+        Some(path) => Some(
+            RotatingFile::open(path, &config.rotation)
+                .context(format!("Failed to open JSON log file {}", path.display()))?,
+        ),
+        None => None,
+    };
+    let (mut json, mut markets) = (None, None);
+    if json_file.is_some() || config.market_dir.is_some() {
+        let has_json = json_file.is_some();
+        let (sender, writer) = spawn_writer(json_file, config.market_dir.clone(), config.rotation.clone());
+        if has_json {
+            json = Some(
+                fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(false)
+                    .with_writer(sender.clone()),
+            );
+        }
+        if config.market_dir.is_some() {
+            markets = Some(MarketFiles(sender.0.clone()));
+        }
+        guard.0 = Some((sender, writer));
+    }
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(console)
+        .with(json)
+        .with(markets)
+        .try_init()
+        .context("Failed to initialize logging")?;
+    Ok(guard)
+}
+
+/// Records queued for the log file thread before callers wait for it.
+const FILE_QUEUE: usize = 10_000;
+
+enum Record {
+    Json(Vec<u8>),
+    /// A line for `{market_dir}/{name}.log`
+    Market(String, Vec<u8>),
+    Stop,
+}
+
+/// Hands each JSON log record to the thread that owns the log files, so a caller never waits on
+/// disk or a rotation, only on a full queue.
+#[derive(Clone)]
+struct LogSender(SyncSender<Record>);
+
+impl io::Write for LogSender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .send(Record::Json(buf.to_vec()))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "log file writer has stopped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogSender {
+    type Writer = LogSender;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Starts the thread that writes queued records to the JSON log and to per-market files in
+/// `market_dir`, which are opened at their first line.
+fn spawn_writer(mut json: Option<RotatingFile>, market_dir: Option<PathBuf>, rotation: LogRotationConfig) -> (LogSender, JoinHandle<()>) {
+    let (sender, records) = std::sync::mpsc::sync_channel::<Record>(FILE_QUEUE);
+    let writer = std::thread::spawn(move || {
+        let mut markets: HashMap<String, Option<RotatingFile>> = HashMap::new();
+        let write = |file: &mut RotatingFile, bytes: &[u8]| {
+            if let Err(e) = file.write_all(bytes) {
+                eprintln!("Failed to write log {}: {}", file.path.display(), e);
+            }
+        };
+        for record in records.iter() {
+            match record {
+                Record::Json(bytes) => {
+                    if let Some(file) = json.as_mut() {
+                        write(file, &bytes);
+                    }
+                }
+                Record::Market(name, bytes) => {
+                    let Some(dir) = &market_dir else { continue };
+                    let file = markets.entry(name).or_insert_with_key(|name| {
+                        let path = dir.join(format!("{}.log", name));
+                        std::fs::create_dir_all(dir)
+                            .and_then(|_| RotatingFile::open(&path, &rotation))
+                            .map_err(|e| eprintln!("Failed to open market log {}: {}", path.display(), e))
+                            .ok()
+                    });
+                    if let Some(file) = file {
+                        write(file, &bytes);
+                    }
+                }
+                Record::Stop => break,
+            }
+        }
+        for file in json.iter_mut().chain(markets.values_mut().flatten()) {
+            let _ = file.flush();
+        }
+    });
+    (LogSender(sender), writer)
+}
+
+/// Keeps the log file thread running. Dropping it writes out the queued records and stops the
+/// thread; records logged after that are lost.
+#[must_use]
+pub struct LogGuard(Option<(LogSender, JoinHandle<()>)>);
+
+impl Drop for LogGuard {
+    fn drop(&mut self) {
+        if let Some((sender, writer)) = self.0.take() {
+            let _ = sender.0.send(Record::Stop);
+            let _ = writer.join();
+        }
+    }
+}
+
+/// The `{market}-{timeframe}` name a span logs under, from its `market` and `timeframe` fields.
+struct MarketName(String);
+
+#[derive(Default)]
+struct SpanFields {
+    market: Option<String>,
+    timeframe: Option<String>,
+}
+
+impl Visit for SpanFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "market" => self.market = Some(value.to_lowercase()),
+            "timeframe" => self.timeframe = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+/// The message of an event, then its other fields as `name=value`.
+#[derive(Default)]
+struct EventLine {
+    message: String,
+    fields: String,
+}
+
+impl Visit for EventLine {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{:?}", value),
+            // Added by the log bridge
+            name if name.starts_with("log.") => {}
+            name => self.fields.push_str(&format!(" {}={:?}", name, value)),
+        }
+    }
+}
+
+/// Copies console lines logged inside a market span to that market's file
+/// (`logging.market_dir`), with a timestamp and level.
+struct MarketFiles(SyncSender<Record>);
+
+impl<S> Layer<S> for MarketFiles
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: LayerContext<'_, S>) {
+        let mut fields = SpanFields::default();
+        attrs.record(&mut fields);
+        let (Some(market), Some(span)) = (fields.market, ctx.span(id)) else { return };
+        let name = match fields.timeframe {
+            Some(timeframe) => format!("{}-{}", market, timeframe),
+            None => market,
+        };
+        span.extensions_mut().insert(MarketName(name));
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: LayerContext<'_, S>) {
+        if event.metadata().target() == EVENTS {
+            return;
+        }
+        let Some(name) = ctx
+            .event_scope(event)
+            .and_then(|scope| scope.from_root().find_map(|span| span.extensions().get::<MarketName>().map(|m| m.0.clone())))
+        else {
+            return;
+        };
+        let mut line = EventLine::default();
+        event.record(&mut line);
+        let text = format!(
+            "{} {:<5} {}{}\n",
+            Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            event.metadata().level(),
+            line.message,
+            line.fields
+        );
+        let _ = self.0.send(Record::Market(name, text.into_bytes()));
+    }
+}
+
+/// Append-only log file that rotates by size and by day as `logging.rotation` says. Each JSON
+/// record is one write, so files split between records.
+struct RotatingFile {
+    path: PathBuf,
+    config: LogRotationConfig,
+    max_bytes: u64,
+    file: File,
+    size: u64,
+    /// UTC day the current file was started on
+    started_on: NaiveDate,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, config: &LogRotationConfig) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let metadata = file.metadata()?;
+        // A file left from an earlier day rotates at its first write when rotating daily
+        let started_on = metadata
+            .modified()
+            .ok()
+            .filter(|_| metadata.len() > 0)
+            .map(|t| DateTime::<Utc>::from(t).date_naive())
+            .unwrap_or_else(|| Utc::now().date_naive());
+        Ok(Self {
+            path: path.to_path_buf(),
+            config: config.clone(),
+            max_bytes: config.max_size_mb * 1024 * 1024,
+            file,
+            size: metadata.len(),
+            started_on,
+        })
+    }
+
+    /// Renames the current file after the time, starts a new one and trims the rotated files to
+    /// `keep`. Gzipping and trimming after it run on a thread of their own.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let now = Utc::now();
+        // Stamps keep the names in time order, so a clash moves on a millisecond
+        let mut at = now;
+        let mut rotated = rotated_path(&self.path, &at.format("%Y%m%dT%H%M%S%3f").to_string());
+        while rotated.exists() || gz_path(&rotated).exists() {
+            at += chrono::Duration::milliseconds(1);
+            rotated = rotated_path(&self.path, &at.format("%Y%m%dT%H%M%S%3f").to_string());
+        }
+        std::fs::rename(&self.path, &rotated)?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        self.started_on = now.date_naive();
+
+        let (path, keep) = (self.path.clone(), self.config.keep);
+        if self.config.gzip {
+            std::thread::spawn(move || {
+                if let Err(e) = gzip(&rotated) {
+                    eprintln!("Failed to gzip rotated log {}: {}", rotated.display(), e);
+                }
+                prune_rotated(&path, keep);
+            });
+        } else {
+            prune_rotated(&path, keep);
+        }
+        Ok(())
+    }
+}
+
+impl io::Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let full = self.max_bytes > 0 && self.size + buf.len() as u64 > self.max_bytes;
+        let new_day = self.config.daily && Utc::now().date_naive() != self.started_on;
+        if self.size > 0 && (full || new_day) {
+            // Logging from here would come back into this writer
+            if let Err(e) = self.rotate() {
+                eprintln!("Failed to rotate log {}: {}", self.path.display(), e);
+            }
+        }
+        let n = self.file.write(buf)?;
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// `dir/name.ext` -> `dir/name.{stamp}.ext`; a name without extension gets the stamp appended.
+fn rotated_path(path: &Path, stamp: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, stamp, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, stamp),
+    };
+    path.with_file_name(name)
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// Compresses `path` to `path.gz` and removes the original.
+fn gzip(path: &Path) -> io::Result<()> {
+    let mut encoder = GzEncoder::new(File::create(gz_path(path))?, Compression::default());
+    io::copy(&mut File::open(path)?, &mut encoder)?;
+    encoder.finish()?;
+    std::fs::remove_file(path)
+}
+
+/// Deletes the oldest rotated files of the log at `path` beyond `keep`; 0 keeps them all.
+fn prune_rotated(path: &Path, keep: usize) {
+    if keep == 0 {
+        return;
+    }
+    let dir = match path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        Some(dir) => dir,
+        None => Path::new("."),
+    };
+    let prefix = format!("{}.", path.file_stem().unwrap_or_default().to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    // Rotated names start with the stamp after the stem, so they sort oldest first
+    let mut rotated: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| *p != path)
+        .filter(|p| {
+            let name = p.file_name().unwrap_or_default().to_string_lossy();
+            let Some(rest) = name.strip_prefix(&prefix) else { return false };
+            rest.len() > 9 && rest.as_bytes()[..8].iter().all(u8::is_ascii_digit) && rest.as_bytes()[8] == b'T'
+        })
+        .collect();
+    rotated.sort();
+    for old in rotated.iter().take(rotated.len().saturating_sub(keep)) {
+        if let Err(e) = std::fs::remove_file(old) {
+            eprintln!("Failed to delete old log {}: {}", old.display(), e);
+        }
+    }
+}
+
+/// Console format: just the message, like the previous env_logger setup.
+struct MessageOnly;
+
+impl<S, N> FormatEvent<S, N> for MessageOnly
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: format::Writer<'_>,
+        event: &Event<'_>,
+    ) -> std::fmt::Result {
+        ctx.field_format().format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_by_size_and_keeps_the_newest() {
+        let dir = std::env::temp_dir().join(format!("log-rotation-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("events.jsonl");
+        let config = LogRotationConfig { max_size_mb: 1, daily: false, keep: 2, gzip: false };
+        let mut file = RotatingFile::open(&path, &config).unwrap();
+        file.max_bytes = 100;
+
+        let record = [b'x'; 59];
+        for _ in 0..8 {
+            file.write_all(&record).unwrap();
+            file.write_all(b"\n").unwrap();
+        }
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // One 60-byte record per file, so every record after the first rotates
+        assert_eq!(names.len(), 3, "{:?}", names);
+        assert_eq!(names[2], "events.jsonl");
+        assert!(names[0].starts_with("events.") && names[0].ends_with(".jsonl"));
+    }
+
+    #[test]
+    fn copies_market_span_lines_to_the_market_file() {
+        let dir = std::env::temp_dir().join(format!("market-logs-{}", std::process::id()));
+        let (sender, writer) = spawn_writer(None, Some(dir.clone()), LogRotationConfig::default());
+        let subscriber = tracing_subscriber::registry().with(MarketFiles(sender.0.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("outside any market");
+            let span = tracing::info_span!("market", market = "BTC", timeframe = "15m");
+            span.in_scope(|| {
+                tracing::warn!(shares = 5, "placing orders");
+                tracing::info!(target: EVENTS, event = "order_placed");
+            });
+        });
+        drop(LogGuard(Some((sender, writer))));
+
+        let names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        let text = std::fs::read_to_string(dir.join("btc-15m.log")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(names, vec!["btc-15m.log"]);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1, "{}", text);
+        assert!(lines[0].ends_with(" WARN  placing orders shares=5"), "{}", lines[0]);
+    }
+}
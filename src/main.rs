@@ -1,102 +1,399 @@
+mod allocation;
+mod analyze;
 mod api;
+mod apierror;
+mod backtest;
+mod blackout;
+mod bookdiff;
+mod calibration;
+mod clock;
+mod compare;
 mod config;
+mod control;
+mod dataset;
+mod exposure;
+mod external;
 mod models;
+mod momentum;
+mod monitor;
+mod montecarlo;
+mod optimize;
+mod orderqueue;
+mod orderrules;
+mod plugin;
+mod profit;
+mod ratecontrol;
+mod reconcile;
+mod replay;
+mod report;
 mod discovery;
+mod display;
+mod feed;
+mod wsfeed;
+mod fees;
+mod fill;
+mod history;
+mod instance;
+mod keystore;
+mod ledger;
+mod logging;
+mod metrics;
+#[cfg(test)]
+mod mockapi;
+mod model;
+mod risk;
+mod scheduler;
 mod signals;
+mod slug;
+mod stats;
 mod strategy;
+mod trend;
+mod tui;
+mod watchdog;
 
 
 use anyhow::Result;
 use clap::Parser;
-use config::{Args, Config};
-use std::io::Write;
+use config::{Args, BacktestCommand, Command, Config, ExportFormat, HistoryCommand, LedgerCommand, LedgerRows, MarketDuration};
 use std::sync::Arc;
 use api::PolymarketApi;
 use strategy::PreLimitStrategy;
 use log::warn;
 
+/// How long before the pre-order window an upcoming market is looked up.
+const PREDISCOVERY_MARGIN_SECS: i64 = 60;
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Info)
-        .format(|buf, record| {
-            writeln!(buf, "{}", record.args())
-        })
-        .init();
-
     let args = Args::parse();
-    let config = Config::load(&args.config)?;
+    let mut config = Config::load(&args.config, &args.profiles)?;
+    if args.paper_live {
+        config.strategy.paper_live = true;
+        config.validate()?;
+    }
+    let console = args.tui.then(logging::ConsoleBuffer::default);
+    let _log = logging::init(&config.logging, console.clone())?;
+    display::init(&config.display);
+
+    if let Some(command) = &args.command {
+        return run_command(command, &config, args.passphrase_fd).await;
+    }
+
+    if args.resume {
+        if risk::request_resume(&config.strategy.risk)? {
+            eprintln!("▶️  Kill switch cleared ({}). The running bot will resume trading on its next tick.",
+                config.strategy.risk.kill_switch_file.display());
+        } else {
+            eprintln!("Kill switch is not active (no {} found).", config.strategy.risk.kill_switch_file.display());
+        }
+        return Ok(());
+    }
+
+    keystore::unlock(&mut config, args.passphrase_fd)?;
+
     let shares = config.strategy.shares;
     let price = config.strategy.price_limit;
     let cost_per_side = shares * price;
     let payout_per_trade = cost_per_side * 2.0;
-    const N_ASSETS: u32 = 4;
-    let four_assets = (N_ASSETS as f64) * cost_per_side;
+    let n_assets = config.traded_assets().len();
+    let all_assets = n_assets as f64 * cost_per_side;
 
     eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     eprintln!("📋 Confirming configuration");
+    if !args.profiles.is_empty() {
+        eprintln!("   profiles               {}", args.profiles.join(" + "));
+    }
     eprintln!("   shares per side        {:.0}", shares);
     eprintln!("   ave price per share   ${:.2}", price);
+    let pair_cost = fees::FeeSchedule::new(config.strategy.fees.clone()).pair_cost(price, price);
+    eprintln!("   pair cost after fees  ${:.4}", pair_cost);
     eprintln!("   payout per trade      ${:.0} × 2 = ${:.0}", cost_per_side, payout_per_trade);
-    eprintln!("   {} assets              ${:.0}", N_ASSETS, four_assets);
+    eprintln!("   {} assets              ${:.0}", n_assets, all_assets);
     eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    if pair_cost >= 1.0 {
+        warn!("⚠️ Pair cost ${:.4} after fees is not below $1 — pre-orders will not be placed", pair_cost);
+    }
+    let rules = &config.strategy;
+    let disabled: Vec<&str> = [
+        ("pre-orders", rules.pre_orders_enabled),
+        ("mid-market orders", rules.signal.mid_market_enabled),
+        ("sell-opposite", rules.sell_opposite_enabled),
+    ]
+    .into_iter()
+    .filter_map(|(rule, enabled)| (!enabled).then_some(rule))
+    .collect();
+    if !disabled.is_empty() {
+        eprintln!("✂️  Reduced strategy: {} off", disabled.join(", "));
+    }
+    if !rules.pre_orders_enabled && !rules.signal.mid_market_enabled {
+        warn!("⚠️ Pre-orders and mid-market orders are both off — no new positions will be opened");
+    }
 
     eprintln!("🚀 Starting Polymarket Pre-Limit Order Bot");
     if config.strategy.simulation_mode {
         eprintln!("🎮 SIMULATION MODE ENABLED - No real orders will be placed");
         eprintln!("   Orders will match when prices hit ${:.2} or below", config.strategy.price_limit);
     }
-    eprintln!("📈 Strategy: Placing Up/Down limit orders at ${:.2} for 15m markets (BTC, ETH, SOL, XRP)", config.strategy.price_limit);
+    if config.strategy.paper_live {
+        eprintln!("📝 PAPER-LIVE MODE - Orders are signed with your credentials and checked against your balance, but not posted");
+        eprintln!("   Fills, sells and resolutions are simulated as in simulation mode");
+    }
+    eprintln!("📈 Strategy: Placing Up/Down limit orders at ${:.2} for 15m markets ({})",
+        config.strategy.price_limit, config.traded_assets().join(", "));
+    let events = config.event_assets();
+    if !events.is_empty() {
+        eprintln!("   🏟️  Event markets, traded until they end: {}", events.join(", "));
+    }
     if config.strategy.signal.enabled {
         eprintln!("   📡 Signal-based risk management: enabled (place on good signal, skip on bad, sell early on danger)");
     }
+    if let Some(limit) = config.strategy.risk.daily_loss_limit_usd.filter(|l| *l > 0.0) {
+        eprintln!("   🛑 Daily loss limit: ${:.2} (kill switch halts new orders and cancels resting ones)", limit);
+    }
+    if let Some(limit) = config.strategy.risk.max_drawdown_usd.filter(|l| *l > 0.0) {
+        eprintln!("   🛑 Max drawdown: ${:.2} below the realized PnL peak (kill switch)", limit);
+    }
 
-    let api = Arc::new(PolymarketApi::new(
-        config.polymarket.gamma_api_url.clone(),
-        config.polymarket.clob_api_url.clone(),
-        config.polymarket.api_key.clone(),
-        config.polymarket.api_secret.clone(),
-        config.polymarket.api_passphrase.clone(),
-        config.polymarket.private_key.clone(),
-        config.polymarket.proxy_wallet_address.clone(),
-        config.polymarket.signature_type,
-    ));
+    let api = Arc::new(build_api(&config)?);
 
     if args.redeem {
         run_redeem_only(api.as_ref(), &config, args.condition_id.as_deref()).await?;
         return Ok(());
     }
 
-    if config.polymarket.private_key.is_some() {
+    if config.polymarket.can_sign() {
         if let Err(e) = api.authenticate().await {
             log::error!("Authentication failed: {}", e);
             anyhow::bail!("Authentication failed. Please check your credentials.");
         }
-    } else {
-        log::warn!("⚠️ No private key provided. Bot will only be able to monitor markets.");
+    } else if config.accounts.is_empty() {
+        log::warn!("⚠️ No private key or remote signer provided. Bot will only be able to monitor markets.");
     }
+    let monitor_only = instance::claim(&config, &api).await?;
 
 
-    let market_closure_interval = config.strategy.market_closure_check_interval_seconds;
-    let strategy = Arc::new(PreLimitStrategy::new(api, config));
-    let strategy_for_closure = Arc::clone(&strategy);
+    if config.metrics.enabled {
+        let metrics_config = config.metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics::serve(metrics_config).await {
+                log::error!("Metrics server stopped: {}", e);
+            }
+        });
+    }
 
+    let (shutdown_tx, shutdown_rx) = tokio::sync::watch::channel(false);
+    let signal_tx = shutdown_tx.clone();
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(market_closure_interval));
-        loop {
-            interval.tick().await;
-            if let Err(e) = strategy_for_closure.check_market_closure().await {
-                warn!("Error checking market closure: {}", e);
+        shutdown_signal().await;
+        let _ = signal_tx.send(true);
+        eprintln!("\n🛑 Shutdown requested — finishing the current tick (Ctrl-C again to exit immediately)");
+        shutdown_signal().await;
+        std::process::exit(130);
+    });
+
+    if config.clock.enabled {
+        clock::check(&api, &config.clock).await;
+        tokio::spawn(clock::run(Arc::clone(&api), config.clock.clone()));
+    }
+
+    let feed = Arc::new(feed::MarketFeed::new(Arc::clone(&api), &config));
+    tokio::spawn(Arc::clone(&feed).run());
+
+    // Upcoming periods are announced a minute before their pre-order window opens
+    let lookahead_secs = (config.strategy.place_order_before_mins * 60) as i64 + PREDISCOVERY_MARGIN_SECS;
+    let scheduler = Arc::new(scheduler::PeriodScheduler::for_config(&config, lookahead_secs));
+    let period_events = scheduler.subscribe();
+    let restart_on_panic = config.watchdog.restart_on_panic;
+    {
+        let (scheduler, shutdown) = (Arc::clone(&scheduler), shutdown_rx.clone());
+        watchdog::spawn_supervised("period scheduler", restart_on_panic, move || {
+            let (scheduler, shutdown) = (Arc::clone(&scheduler), shutdown.clone());
+            async move { scheduler.run(shutdown).await }
+        });
+    }
+
+    let mut background = Vec::new();
+    if config.monitor.record_snapshots {
+        let monitor_config = config.monitor.clone();
+        let assets = config.assets_for(MarketDuration::M15);
+        let shutdown = shutdown_rx.clone();
+        let (api, feed, config, scheduler) = (Arc::clone(&api), Arc::clone(&feed), config.clone(), Arc::clone(&scheduler));
+        let restart = watchdog::health().restart_signal(monitor::RECORDER);
+        background.push(watchdog::spawn_supervised(monitor::RECORDER, restart_on_panic, move || {
+            let (api, feed, config, scheduler) = (Arc::clone(&api), Arc::clone(&feed), config.clone(), Arc::clone(&scheduler));
+            let (monitor_config, assets, shutdown, restart) = (monitor_config.clone(), assets.clone(), shutdown.clone(), Arc::clone(&restart));
+            async move {
+                loop {
+                    let recorder = monitor::MarketMonitor::new(Arc::clone(&api), Arc::clone(&feed), &config);
+                    tokio::select! {
+                        _ = recorder.run_recorder(monitor_config.clone(), assets.clone(), &scheduler, shutdown.clone()) => break,
+                        // Dropping the stuck run finishes its open snapshot files
+                        _ = restart.notified() => warn!("🔄 Restarting the snapshot recorder"),
+                    }
+                }
+            }
+        }));
+    }
+
+    if config.calibration.enabled {
+        let shutdown = shutdown_rx.clone();
+        let (api, feed, config, scheduler) = (Arc::clone(&api), Arc::clone(&feed), config.clone(), Arc::clone(&scheduler));
+        background.push(watchdog::spawn_supervised(calibration::TASK, restart_on_panic, move || {
+            let (api, feed, config, scheduler, shutdown) = (Arc::clone(&api), Arc::clone(&feed), config.clone(), Arc::clone(&scheduler), shutdown.clone());
+            calibration::run(api, feed, config, scheduler, shutdown)
+        }));
+    }
+
+    if config.dataset.enabled {
+        let shutdown = shutdown_rx.clone();
+        let (api, feed, config, scheduler) = (Arc::clone(&api), Arc::clone(&feed), config.clone(), Arc::clone(&scheduler));
+        background.push(watchdog::spawn_supervised(dataset::TASK, restart_on_panic, move || {
+            let (api, feed, config, scheduler, shutdown) = (Arc::clone(&api), Arc::clone(&feed), config.clone(), Arc::clone(&scheduler), shutdown.clone());
+            dataset::run(api, feed, config, scheduler, shutdown)
+        }));
+    }
+
+    if config.watchdog.enabled {
+        tokio::spawn(watchdog::run(config.watchdog.clone(), shutdown_rx.clone()));
+    }
+
+    let market_closure_interval = config.strategy.market_closure_check_interval_seconds;
+    let control_config = config.control.clone();
+    let dashboard_config = config.clone();
+    let strategies = build_strategies(&api, &feed, &config).await?;
+    if monitor_only {
+        for strategy in &strategies {
+            for asset in [config.traded_assets(), config.event_assets()].concat() {
+                strategy.set_paused(&asset, MarketDuration::M15, true);
+            }
+        }
+    }
+    let strategies_for_closure = strategies.clone();
+
+    if control_config.enabled {
+        let strategies_for_control = strategies.clone();
+        let bot_config = config.clone();
+        tokio::spawn(async move {
+            if let Err(e) = control::serve(control_config, bot_config, strategies_for_control).await {
+                log::error!("Control server stopped: {}", e);
             }
-            let total_profit = strategy_for_closure.get_total_profit().await;
-            let period_profit = strategy_for_closure.get_period_profit().await;
-            if total_profit != 0.0 || period_profit != 0.0 {
-                eprintln!("Current Profit - Period: ${:.2} | Total: ${:.2}", period_profit, total_profit);
+        });
+    }
+    let strategies_for_periods = Arc::new(strategies.clone());
+    tokio::spawn(scheduler::dispatch_latest(period_events, shutdown_rx.clone(), move |event| {
+        let strategies = Arc::clone(&strategies_for_periods);
+        async move {
+            if event.timeframe == MarketDuration::M15 && event.kind == scheduler::PeriodEventKind::Upcoming {
+                for strategy in strategies.iter() {
+                    strategy.prediscover(&event.asset, event.period_start).await;
+                }
             }
         }
-    });
+    }));
+    let closure_shutdown = shutdown_rx.clone();
+
+    background.push(watchdog::spawn_supervised("closure loop", restart_on_panic, move || {
+        let (strategies_for_closure, mut closure_shutdown) = (strategies_for_closure.clone(), closure_shutdown.clone());
+        async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(market_closure_interval));
+            while !*closure_shutdown.borrow() {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = closure_shutdown.changed() => break,
+                }
+                let mut exposures = Vec::new();
+                for strategy in &strategies_for_closure {
+                    if let Err(e) = strategy.reconcile_positions().await {
+                        warn!("Error reconciling positions: {}", e);
+                    }
+                    strategy.reconcile_fills().await;
+                    if let Err(e) = strategy.check_market_closure().await {
+                        warn!("Error checking market closure: {}", e);
+                    }
+                    strategy.refresh_balance().await;
+                    let exposure = strategy.exposure().await;
+                    metrics::metrics().set_exposure(strategy.account(), exposure.open_cost, exposure.worst_case, exposure.best_case);
+                    if !exposure.markets.is_empty() {
+                        let account = strategy.account().map(|a| format!(" [{}]", a)).unwrap_or_default();
+                        eprintln!(
+                            "💼 Exposure{} - {} market(s) | Open cost: {} | Worst case: {} | Best case: {}",
+                            account,
+                            exposure.markets.len(),
+                            display::usd(exposure.open_cost),
+                            display::usd(exposure.worst_case),
+                            display::usd(exposure.best_case)
+                        );
+                        for m in &exposure.markets {
+                            log::debug!(
+                                "   {} {}: cost ${:.2}, worst ${:.2}, best ${:.2}",
+                                m.asset,
+                                &m.condition_id[..m.condition_id.len().min(16)],
+                                m.open_cost,
+                                m.worst_case,
+                                m.best_case
+                            );
+                        }
+                    }
+                    exposures.push(exposure);
+                    let total_profit = strategy.get_total_profit().await;
+                    let period_profit = strategy.get_period_profit().await;
+                    if total_profit != 0.0 || period_profit != 0.0 {
+                        let account = strategy.account().map(|a| format!(" [{}]", a)).unwrap_or_default();
+                        let drawdown = strategy.drawdown().await;
+                        eprintln!("Current Profit{} - Period: {} | Total: {} | Drawdown: {} (max {})", account,
+                            display::usd(period_profit), display::usd(total_profit), display::usd(drawdown.current), display::usd(drawdown.max));
+                    }
+                }
+                if exposures.len() > 1 {
+                    let total = exposure::Exposure::combine(exposures);
+                    if !total.markets.is_empty() {
+                        eprintln!(
+                            "💼 Exposure (all accounts) - {} market(s) | Open cost: {} | Worst case: {} | Best case: {}",
+                            total.markets.len(),
+                            display::usd(total.open_cost),
+                            display::usd(total.worst_case),
+                            display::usd(total.best_case)
+                        );
+                    }
+                }
+            }
+        }
+    }));
+
+    // With the dashboard, the strategy stops only after the terminal has been handed back
+    let (dashboard, strategy_shutdown) = match console {
+        Some(console) => {
+            let (tx, rx) = tokio::sync::watch::channel(false);
+            let dashboard = tui::Dashboard::new(api, feed, &dashboard_config, strategies.clone(), console);
+            (Some(tokio::spawn(dashboard.run(shutdown_tx, tx))), rx)
+        }
+        None => (None, shutdown_rx),
+    };
+
+    let runs = strategies.iter().map(|s| s.run(strategy_shutdown.clone()));
+    let result = futures_util::future::join_all(runs).await.into_iter().collect::<Result<Vec<_>>>().map(|_| ());
+    for task in background {
+        let _ = task.await;
+    }
+    if let Some(dashboard) = dashboard {
+        dashboard.await??;
+    }
+    result
+}
 
-    strategy.run().await
+/// Resolves on Ctrl-C, or SIGTERM on Unix.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
 }
 
     
@@ -146,3 +443,284 @@ async fn run_redeem_only(
     Ok(())
 }
 
+fn build_api(config: &Config) -> Result<PolymarketApi> {
+    let mut api = PolymarketApi::new(
+        config.polymarket.gamma_api_url.clone(),
+        config.polymarket.clob_api_url.clone(),
+        config.polymarket.data_api_url.clone(),
+        config.polymarket.api_key.clone(),
+        config.polymarket.api_secret.clone(),
+        config.polymarket.api_passphrase.clone(),
+        config.polymarket.private_key.clone(),
+        config.polymarket.proxy_wallet_address.clone(),
+        config.polymarket.signature_type,
+        &config.network,
+    )
+    .with_order_nonce(config.polymarket.order_nonce)
+    .with_market_cache(std::time::Duration::from_secs(config.discovery.market_cache_secs));
+    if let Some(signer) = &config.polymarket.remote_signer {
+        api = api.with_remote_signer(api::RemoteSigner::new(signer)?);
+    }
+    Ok(if config.strategy.paper_live { api.with_paper_live() } else { api })
+}
+
+/// One strategy per entry in `accounts`, each with its own authenticated client, or a single
+/// strategy on `api` when no accounts are configured. All of them read market data through `feed`.
+async fn build_strategies(
+    api: &Arc<PolymarketApi>,
+    feed: &Arc<feed::MarketFeed>,
+    config: &Config,
+) -> Result<Vec<Arc<PreLimitStrategy>>> {
+    let model = match config.strategy.signal.model.enabled {
+        true => Some(Arc::new(model::SideModel::load(&config.strategy.signal.model)?)),
+        false => None,
+    };
+    let with_model = |strategy: PreLimitStrategy| match &model {
+        Some(model) => strategy.with_model(Arc::clone(model)),
+        None => strategy,
+    };
+    if config.accounts.is_empty() {
+        return Ok(vec![Arc::new(with_model(PreLimitStrategy::new(Arc::clone(api), Arc::clone(feed), config.clone())))]);
+    }
+    let mut strategies = Vec::new();
+    for account in &config.accounts {
+        let account_config = config.for_account(account);
+        let mut account_api = build_api(&account_config)?;
+        if let Some(rate) = account.max_requests_per_sec {
+            account_api = account_api.with_rate_limit(rate);
+        }
+        if account_config.polymarket.can_sign() {
+            account_api
+                .authenticate()
+                .await
+                .map_err(|e| anyhow::anyhow!("Authentication failed for account {}: {}", account.name, e))?;
+        }
+        eprintln!("👤 Account {}: {} at {} shares per side",
+            account.name, account_config.traded_assets().join(", "), account_config.strategy.shares);
+        let strategy = with_model(PreLimitStrategy::new(Arc::new(account_api), Arc::clone(feed), account_config).with_account(&account.name));
+        strategies.push(Arc::new(strategy));
+    }
+    Ok(strategies)
+}
+
+/// Ledger rows selected by `rows`, their filter and how many files were read.
+fn read_ledger_rows(rows: &LedgerRows, config: &Config) -> Result<(Vec<ledger::LedgerEntry>, analyze::AnalysisFilter, usize)> {
+    let paths = if rows.ledgers.is_empty() { vec![config.ledger.path.clone()] } else { rows.ledgers.clone() };
+    let files = analyze::ledger_files(&paths, &rows.pattern)?;
+    if files.is_empty() {
+        anyhow::bail!("No ledger files matching {:?} in {:?}", rows.pattern, paths);
+    }
+    let tz = config.profit.tz();
+    let filter = analyze::AnalysisFilter {
+        account: rows.account.clone(),
+        since: rows.since.as_deref().map(|s| analyze::parse_time(s, tz)).transpose()?,
+        until: rows.until.as_deref().map(|s| analyze::parse_time(s, tz)).transpose()?,
+    };
+    Ok((analyze::read_ledgers(&files)?, filter, files.len()))
+}
+
+fn account_note(rows: &LedgerRows) -> String {
+    rows.account.as_ref().map(|a| format!(" for account {}", a)).unwrap_or_default()
+}
+
+async fn run_command(command: &Command, config: &Config, passphrase_fd: Option<i32>) -> Result<()> {
+    match command {
+        Command::Ledger { action: LedgerCommand::Export { format, output } } => {
+            let entries = ledger::read_entries(&config.ledger.path)?;
+            match format {
+                ExportFormat::Csv => ledger::export_csv(&entries, output)?,
+                ExportFormat::Parquet => ledger::export_parquet(&entries, output)?,
+            }
+            eprintln!("Exported {} ledger entries to {}", entries.len(), output.display());
+            Ok(())
+        }
+        Command::Ledger { action: LedgerCommand::Analyze { rows } } => {
+            let (entries, filter, files) = read_ledger_rows(rows, config)?;
+            let analysis = analyze::analyze(&entries, &filter);
+            if analysis.rows == 0 {
+                anyhow::bail!("No trades{} in the selected window of {} ledger file(s)", account_note(rows), files);
+            }
+            analysis.print();
+            Ok(())
+        }
+        Command::Ledger { action: LedgerCommand::Rules { rows } } => {
+            let (entries, filter, files) = read_ledger_rows(rows, config)?;
+            let attribution = analyze::attribute_rules(&entries, &filter);
+            if attribution.is_empty() {
+                anyhow::bail!("No realized PnL{} in the selected window of {} ledger file(s)", account_note(rows), files);
+            }
+            attribution.print();
+            Ok(())
+        }
+        Command::Backtest { action: Some(BacktestCommand::Compare { baseline, candidate, top, periods }), .. } => {
+            let comparison = compare::Comparison::new(backtest::BacktestReport::load(baseline)?, backtest::BacktestReport::load(candidate)?);
+            comparison.print(&baseline.display().to_string(), &candidate.display().to_string(), *top, *periods);
+            Ok(())
+        }
+        Command::Backtest { action: None, data, slippage, output } => {
+            let mut snapshots = backtest::load_snapshots(data)?;
+            if snapshots.is_empty() {
+                anyhow::bail!("No snapshots found in {:?}", data);
+            }
+            let mut fill_model = config.strategy.fill_model.clone();
+            if let Some(slippage) = slippage {
+                fill_model.fallback_slippage = *slippage;
+            }
+            let settings = backtest::BacktestSettings {
+                fill_model,
+                fees: config.strategy.fees.clone(),
+                slippage_tolerance: config.strategy.slippage_tolerance,
+            };
+            backtest::warn_unmodelled(&config.strategy);
+            let mut model = backtest::PreLimitModel::new(config.strategy.clone());
+            let report = backtest::run(&mut model, &mut snapshots, &settings);
+            report.print();
+            if let Some(output) = output {
+                report.save(output)?;
+                eprintln!("💾 Result written to {}", output.display());
+            }
+            Ok(())
+        }
+        Command::Replay { data, output, golden } => {
+            replay::run(build_api(config)?, config, data, output, golden.as_deref()).await
+        }
+        Command::Optimize { data, params, random, seed, slippage, top, output, walk_forward } => {
+            let ranges = params.iter().map(|p| optimize::ParamRange::parse(p)).collect::<Result<Vec<_>>>()?;
+            let mut snapshots = backtest::load_snapshots(data)?;
+            if snapshots.is_empty() {
+                anyhow::bail!("No snapshots found in {:?}", data);
+            }
+            let mode = match random {
+                Some(samples) => optimize::SearchMode::Random { samples: *samples, seed: *seed },
+                None => optimize::SearchMode::Grid,
+            };
+            let mut base = config.strategy.clone();
+            if let Some(slippage) = slippage {
+                base.fill_model.fallback_slippage = *slippage;
+            }
+            backtest::warn_unmodelled(&base);
+            if let Some(windows) = walk_forward {
+                let folds = optimize::walk_forward(&base, &ranges, mode, snapshots, *windows)?;
+                optimize::print_walk_forward(&folds);
+                if let Some(output) = output {
+                    optimize::write_walk_forward_csv(&folds, output)?;
+                    eprintln!("Wrote {} folds to {}", folds.len(), output.display());
+                }
+                return Ok(());
+            }
+            let trials = optimize::run(&base, &ranges, mode, &mut snapshots);
+            optimize::print_trials(&trials, *top);
+            if let Some(output) = output {
+                optimize::write_csv(&trials, output)?;
+                eprintln!("Wrote {} results to {}", trials.len(), output.display());
+            }
+            Ok(())
+        }
+        Command::MonteCarlo { runs, periods, bankroll, seed } => {
+            let entries = ledger::read_entries(&config.ledger.path)?;
+            let samples = montecarlo::period_pnl_per_share(&entries);
+            let settings = montecarlo::MonteCarloSettings {
+                runs: *runs,
+                periods: *periods,
+                bankroll: *bankroll,
+                shares: config.strategy.shares,
+                seed: *seed,
+            };
+            match montecarlo::run(&samples, &settings) {
+                Some(report) => report.print(),
+                None => anyhow::bail!("No resolved periods in {}", config.ledger.path.display()),
+            }
+            Ok(())
+        }
+        Command::Calibration { file, bins } => {
+            let path = file.as_ref().unwrap_or(&config.calibration.path);
+            let records = calibration::read_records(path)?;
+            if records.is_empty() {
+                anyhow::bail!("No resolved periods in {}", path.display());
+            }
+            calibration::print(&calibration::curves(&records, *bins), records.len());
+            Ok(())
+        }
+        Command::History { action: HistoryCommand::Fetch { assets, market, periods, interval, output } } => {
+            let assets = if assets.is_empty() { config.assets_for(*market) } else { assets.clone() };
+            if assets.is_empty() {
+                anyhow::bail!("No configured market lists the {} timeframe; pass --assets", market.label());
+            }
+            eprintln!("📥 Fetching {} {} periods of price history for {} into {}",
+                periods, market.label(), assets.join(","), output.display());
+            let summary = history::fetch(Arc::new(build_api(config)?), config, &assets, *market, *periods, interval, output).await?;
+            eprintln!("Fetched {} markets ({} snapshots); {} already present, {} unavailable",
+                summary.markets, summary.snapshots, summary.skipped_existing, summary.missing);
+            Ok(())
+        }
+        Command::Reconcile { account, apply } => {
+            let accounts: Vec<(Option<&str>, Config)> = if config.accounts.is_empty() {
+                vec![(None, config.clone())]
+            } else {
+                let selected: Vec<_> = config.accounts.iter()
+                    .filter(|a| account.as_ref().is_none_or(|name| a.name.eq_ignore_ascii_case(name)))
+                    .map(|a| (Some(a.name.as_str()), config.for_account(a)))
+                    .collect();
+                if selected.is_empty() {
+                    anyhow::bail!("No account named {:?} in accounts", account.as_deref().unwrap_or_default());
+                }
+                selected
+            };
+            let entries = ledger::read_entries(&config.ledger.path)?;
+            let ledger = ledger::Ledger::new(config.ledger.path.clone());
+            for (name, account_config) in accounts {
+                let Some(wallet) = account_config.polymarket.proxy_wallet_address.as_deref() else {
+                    warn!("Skipping {}: no proxy_wallet_address", name.unwrap_or("the configured wallet"));
+                    continue;
+                };
+                let positions = build_api(&account_config)?.get_wallet_positions(wallet).await?;
+                let discrepancies = reconcile::compare(&entries, name, &positions);
+                reconcile::print(&discrepancies, name);
+                let fixes: Vec<_> = discrepancies.iter().filter(|d| d.correctable()).collect();
+                if *apply && !fixes.is_empty() {
+                    for d in &fixes {
+                        ledger.record(&d.adjustment(name));
+                    }
+                    ledger.flush();
+                    eprintln!("✏️  Appended {} adjustment row(s) to {}", fixes.len(), config.ledger.path.display());
+                } else if !fixes.is_empty() {
+                    eprintln!("Run with --apply to append the {} ledger adjustment(s)", fixes.len());
+                }
+            }
+            Ok(())
+        }
+        Command::EncryptKey { output, from_config, account } => {
+            let private_key = if *from_config {
+                let key = match account {
+                    Some(name) => config
+                        .accounts
+                        .iter()
+                        .find(|a| a.name.eq_ignore_ascii_case(name))
+                        .ok_or_else(|| anyhow::anyhow!("No account named {:?} in accounts", name))?
+                        .private_key
+                        .clone(),
+                    None => config.polymarket.private_key.clone(),
+                };
+                key.ok_or_else(|| anyhow::anyhow!("No private_key in {} to encrypt", if account.is_some() { "that account" } else { "polymarket" }))?
+            } else {
+                rpassword::prompt_password("🔑 Private key (hex): ")?.trim().to_string()
+            };
+            let file = keystore::KeyFile::encrypt(&private_key, &keystore::new_passphrase(passphrase_fd)?)?;
+            file.save(output)?;
+            eprintln!("🔒 Encrypted the key of {} into {}", file.address, output.display());
+            eprintln!("   Set private_key_file to {} and delete private_key from the config", output.display());
+            Ok(())
+        }
+        Command::Status => {
+            control::fetch_status(&config.control).await?.print();
+            Ok(())
+        }
+        Command::Pause { asset, timeframe } | Command::Resume { asset, timeframe } => {
+            let paused = matches!(command, Command::Pause { .. });
+            control::set_paused(&config.control, asset, *timeframe, paused).await?;
+            eprintln!("{} {} {}", if paused { "⏸️  Paused new orders on" } else { "▶️  Resumed" },
+                asset.to_uppercase(), timeframe.label());
+            Ok(())
+        }
+    }
+}
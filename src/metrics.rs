@@ -0,0 +1,356 @@
+use crate::config::MetricsConfig;
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Process-wide metrics, rendered in the Prometheus text exposition format on `/metrics`.
+/// Per-account gauges are keyed by account name, with "" for the single default account.
+#[derive(Default)]
+pub struct Metrics {
+    orders_placed: Mutex<BTreeMap<String, u64>>,
+    orders_filled: AtomicU64,
+    orders_rejected: AtomicU64,
+    orders_throttled: Mutex<BTreeMap<String, u64>>,
+    reauth: Mutex<BTreeMap<String, u64>>,
+    task_panics: Mutex<BTreeMap<(String, String), u64>>,
+    feed_fallbacks: AtomicU64,
+    ws_reconnects: AtomicU64,
+    invalid_snapshots: Mutex<BTreeMap<String, u64>>,
+    positions: Mutex<BTreeMap<(String, String, String), f64>>,
+    realized_pnl: Mutex<BTreeMap<String, f64>>,
+    unrealized_pnl: Mutex<BTreeMap<String, f64>>,
+    drawdown: Mutex<BTreeMap<String, f64>>,
+    max_drawdown: Mutex<BTreeMap<String, f64>>,
+    usdc_balance: Mutex<BTreeMap<String, f64>>,
+    fill_mismatches: Mutex<BTreeMap<String, f64>>,
+    open_cost: Mutex<BTreeMap<String, f64>>,
+    worst_case_pnl: Mutex<BTreeMap<String, f64>>,
+    best_case_pnl: Mutex<BTreeMap<String, f64>>,
+    clock_skew: AtomicF64,
+    snapshot_latency_sum: AtomicF64,
+    snapshot_latency_count: AtomicU64,
+    api_requests: Mutex<BTreeMap<String, u64>>,
+    api_errors: Mutex<BTreeMap<String, u64>>,
+    latencies: Mutex<BTreeMap<&'static str, LatencySamples>>,
+}
+
+/// Samples kept per latency stage for percentiles.
+const LATENCY_WINDOW: usize = 1000;
+/// Quantiles exported and logged for each latency stage.
+const LATENCY_QUANTILES: [f64; 3] = [0.5, 0.9, 0.99];
+
+/// Recent samples of one latency stage, plus all-time sum and count.
+#[derive(Default)]
+struct LatencySamples {
+    recent: VecDeque<f64>,
+    sum: f64,
+    count: u64,
+}
+
+impl LatencySamples {
+    /// Nearest-rank `LATENCY_QUANTILES` of the recent samples, in seconds.
+    fn quantiles(&self) -> [f64; 3] {
+        let mut sorted: Vec<f64> = self.recent.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        LATENCY_QUANTILES.map(|q| {
+            let rank = ((q * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len());
+            sorted[rank - 1]
+        })
+    }
+}
+
+/// f64 gauge stored as raw bits.
+#[derive(Default)]
+struct AtomicF64(AtomicU64);
+
+impl AtomicF64 {
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    fn set(&self, v: f64) {
+        self.0.store(v.to_bits(), Ordering::Relaxed);
+    }
+
+    fn add(&self, v: f64) {
+        let _ = self.0.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+            Some((f64::from_bits(bits) + v).to_bits())
+        });
+    }
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::default)
+}
+
+impl Metrics {
+    pub fn inc_orders_placed(&self, side: &str) {
+        *self.orders_placed.lock().unwrap().entry(side.to_string()).or_default() += 1;
+    }
+
+    pub fn inc_orders_filled(&self) {
+        self.orders_filled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_orders_rejected(&self) {
+        self.orders_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_orders_throttled(&self, reason: &str) {
+        *self.orders_throttled.lock().unwrap().entry(reason.to_string()).or_default() += 1;
+    }
+
+    pub fn inc_reauth(&self, result: &str) {
+        *self.reauth.lock().unwrap().entry(result.to_string()).or_default() += 1;
+    }
+
+    /// A panic caught in `task`; `market` is empty for tasks not tied to one market.
+    pub fn inc_task_panic(&self, task: &str, market: &str) {
+        *self.task_panics.lock().unwrap().entry((task.to_string(), market.to_string())).or_default() += 1;
+    }
+
+    pub fn inc_feed_fallback(&self) {
+        self.feed_fallbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The market WebSocket dropped and a reconnect is scheduled.
+    pub fn inc_ws_reconnect(&self) {
+        self.ws_reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// A snapshot of `market` rejected as an impossible book.
+    pub fn inc_invalid_snapshot(&self, market: &str) {
+        *self.invalid_snapshots.lock().unwrap().entry(market.to_string()).or_default() += 1;
+    }
+
+    pub fn set_position(&self, account: Option<&str>, market: &str, outcome: &str, shares: f64) {
+        let key = (account.unwrap_or_default().to_string(), market.to_string(), outcome.to_string());
+        self.positions.lock().unwrap().insert(key, shares);
+    }
+
+    pub fn set_realized_pnl(&self, account: Option<&str>, pnl: f64) {
+        self.realized_pnl.lock().unwrap().insert(account.unwrap_or_default().to_string(), pnl);
+    }
+
+    pub fn set_unrealized_pnl(&self, account: Option<&str>, pnl: f64) {
+        self.unrealized_pnl.lock().unwrap().insert(account.unwrap_or_default().to_string(), pnl);
+    }
+
+    pub fn set_drawdown(&self, account: Option<&str>, drawdown: f64, max: f64) {
+        let account = account.unwrap_or_default().to_string();
+        self.drawdown.lock().unwrap().insert(account.clone(), drawdown);
+        self.max_drawdown.lock().unwrap().insert(account, max);
+    }
+
+    pub fn set_usdc_balance(&self, account: Option<&str>, balance: f64) {
+        self.usdc_balance.lock().unwrap().insert(account.unwrap_or_default().to_string(), balance);
+    }
+
+    pub fn set_fill_mismatches(&self, account: Option<&str>, count: usize) {
+        self.fill_mismatches.lock().unwrap().insert(account.unwrap_or_default().to_string(), count as f64);
+    }
+
+    pub fn set_exposure(&self, account: Option<&str>, open_cost: f64, worst_case: f64, best_case: f64) {
+        let account = account.unwrap_or_default().to_string();
+        self.open_cost.lock().unwrap().insert(account.clone(), open_cost);
+        self.worst_case_pnl.lock().unwrap().insert(account.clone(), worst_case);
+        self.best_case_pnl.lock().unwrap().insert(account, best_case);
+    }
+
+    pub fn set_clock_skew(&self, secs: f64) {
+        self.clock_skew.set(secs);
+    }
+
+    pub fn observe_snapshot_latency(&self, secs: f64) {
+        self.snapshot_latency_sum.add(secs);
+        self.snapshot_latency_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records how long `stage` took: `book_fetch` (market snapshot), `decision` (snapshot in hand
+    /// to first order sent), `order_round_trip` (order submitted to acknowledged) or
+    /// `snapshot_to_ack` (snapshot requested to the last order of a pair acknowledged).
+    pub fn observe_latency(&self, stage: &'static str, elapsed: std::time::Duration) {
+        let secs = elapsed.as_secs_f64();
+        let mut latencies = self.latencies.lock().unwrap();
+        let samples = latencies.entry(stage).or_default();
+        if samples.recent.len() == LATENCY_WINDOW {
+            samples.recent.pop_front();
+        }
+        samples.recent.push_back(secs);
+        samples.sum += secs;
+        samples.count += 1;
+    }
+
+    /// "stage p50/p90/p99 ms" for every stage with samples, for the periodic status log.
+    pub fn latency_summary(&self) -> Option<String> {
+        let latencies = self.latencies.lock().unwrap();
+        let parts: Vec<String> = latencies
+            .iter()
+            .filter(|(_, s)| !s.recent.is_empty())
+            .map(|(stage, s)| {
+                let [p50, p90, p99] = s.quantiles().map(|q| q * 1000.0);
+                format!("{} {:.0}/{:.0}/{:.0}", stage, p50, p90, p99)
+            })
+            .collect();
+        (!parts.is_empty()).then(|| format!("{} ms (p50/p90/p99)", parts.join(" | ")))
+    }
+
+    /// Counts one API call to `endpoint`, and an error if `result` failed.
+    pub fn observe_api<T, E>(&self, endpoint: &str, result: &std::result::Result<T, E>) {
+        *self.api_requests.lock().unwrap().entry(endpoint.to_string()).or_default() += 1;
+        if result.is_err() {
+            *self.api_errors.lock().unwrap().entry(endpoint.to_string()).or_default() += 1;
+        } else {
+            crate::watchdog::health().api_ok();
+        }
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "# HELP polymarket_orders_placed_total Orders accepted by the CLOB (or simulated).");
+        let _ = writeln!(out, "# TYPE polymarket_orders_placed_total counter");
+        for (side, n) in self.orders_placed.lock().unwrap().iter() {
+            let _ = writeln!(out, "polymarket_orders_placed_total{{side=\"{}\"}} {}", side, n);
+        }
+        let _ = writeln!(out, "# HELP polymarket_orders_filled_total Limit orders detected as filled.");
+        let _ = writeln!(out, "# TYPE polymarket_orders_filled_total counter");
+        let _ = writeln!(out, "polymarket_orders_filled_total {}", self.orders_filled.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP polymarket_orders_rejected_total Orders that failed or were rejected.");
+        let _ = writeln!(out, "# TYPE polymarket_orders_rejected_total counter");
+        let _ = writeln!(out, "polymarket_orders_rejected_total {}", self.orders_rejected.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP polymarket_orders_throttled_total Order decisions held back by strategy.rate_control.");
+        let _ = writeln!(out, "# TYPE polymarket_orders_throttled_total counter");
+        for (reason, n) in self.orders_throttled.lock().unwrap().iter() {
+            let _ = writeln!(out, "polymarket_orders_throttled_total{{reason=\"{}\"}} {}", reason, n);
+        }
+        let _ = writeln!(out, "# HELP polymarket_reauth_total CLOB re-authentications after a 401/403, by result.");
+        let _ = writeln!(out, "# TYPE polymarket_reauth_total counter");
+        for (result, n) in self.reauth.lock().unwrap().iter() {
+            let _ = writeln!(out, "polymarket_reauth_total{{result=\"{}\"}} {}", result, n);
+        }
+        let _ = writeln!(out, "# HELP polymarket_task_panics_total Panics caught in market processing and background tasks.");
+        let _ = writeln!(out, "# TYPE polymarket_task_panics_total counter");
+        for ((task, market), n) in self.task_panics.lock().unwrap().iter() {
+            let market = if market.is_empty() { String::new() } else { format!(",market=\"{}\"", market) };
+            let _ = writeln!(out, "polymarket_task_panics_total{{task=\"{}\"{}}} {}", task, market, n);
+        }
+        let _ = writeln!(out, "# HELP polymarket_feed_fallbacks_total Switches from the WebSocket feed to REST in hybrid mode.");
+        let _ = writeln!(out, "# TYPE polymarket_feed_fallbacks_total counter");
+        let _ = writeln!(out, "polymarket_feed_fallbacks_total {}", self.feed_fallbacks.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP polymarket_ws_reconnects_total Market WebSocket connections lost or refused, each followed by a reconnect.");
+        let _ = writeln!(out, "# TYPE polymarket_ws_reconnects_total counter");
+        let _ = writeln!(out, "polymarket_ws_reconnects_total {}", self.ws_reconnects.load(Ordering::Relaxed));
+        let _ = writeln!(out, "# HELP polymarket_invalid_snapshots_total Snapshots rejected as crossed, empty or implausibly priced.");
+        let _ = writeln!(out, "# TYPE polymarket_invalid_snapshots_total counter");
+        for (market, n) in self.invalid_snapshots.lock().unwrap().iter() {
+            let _ = writeln!(out, "polymarket_invalid_snapshots_total{{market=\"{}\"}} {}", market, n);
+        }
+
+        let _ = writeln!(out, "# HELP polymarket_position_shares Shares currently held per market and outcome.");
+        let _ = writeln!(out, "# TYPE polymarket_position_shares gauge");
+        for ((account, market, outcome), shares) in self.positions.lock().unwrap().iter() {
+            let account = if account.is_empty() { String::new() } else { format!(",account=\"{}\"", account) };
+            let _ = writeln!(out, "polymarket_position_shares{{market=\"{}\",outcome=\"{}\"{}}} {}", market, outcome, account, shares);
+        }
+
+        let gauges = [
+            ("polymarket_realized_pnl_usd", "Realized PnL since start.", &self.realized_pnl),
+            ("polymarket_unrealized_pnl_usd", "Mark-to-market PnL of held positions.", &self.unrealized_pnl),
+            ("polymarket_drawdown_usd", "Realized PnL below its running peak since start.", &self.drawdown),
+            ("polymarket_max_drawdown_usd", "Largest drawdown of realized PnL since start.", &self.max_drawdown),
+            ("polymarket_usdc_balance_usd", "USDC available to trade.", &self.usdc_balance),
+            ("polymarket_open_cost_usd", "Cost of shares held across open markets.", &self.open_cost),
+            ("polymarket_worst_case_pnl_usd", "PnL of held shares if every market resolves against them.", &self.worst_case_pnl),
+            ("polymarket_best_case_pnl_usd", "PnL of held shares if every market resolves in their favour.", &self.best_case_pnl),
+            (
+                "polymarket_fill_mismatches",
+                "Market, outcome and side totals where exchange fills disagree with the ledger at the last check.",
+                &self.fill_mismatches,
+            ),
+        ];
+        for (name, help, values) in gauges {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} gauge", name);
+            for (account, value) in values.lock().unwrap().iter() {
+                if account.is_empty() {
+                    let _ = writeln!(out, "{} {}", name, value);
+                } else {
+                    let _ = writeln!(out, "{}{{account=\"{}\"}} {}", name, account, value);
+                }
+            }
+        }
+
+        let _ = writeln!(out, "# HELP polymarket_clock_skew_seconds Exchange clock minus local clock at the last check.");
+        let _ = writeln!(out, "# TYPE polymarket_clock_skew_seconds gauge");
+        let _ = writeln!(out, "polymarket_clock_skew_seconds {}", self.clock_skew.get());
+
+        let _ = writeln!(out, "# HELP polymarket_snapshot_latency_seconds Time to fetch a market price snapshot.");
+        let _ = writeln!(out, "# TYPE polymarket_snapshot_latency_seconds summary");
+        let _ = writeln!(out, "polymarket_snapshot_latency_seconds_sum {}", self.snapshot_latency_sum.get());
+        let _ = writeln!(out, "polymarket_snapshot_latency_seconds_count {}", self.snapshot_latency_count.load(Ordering::Relaxed));
+
+        let _ = writeln!(out, "# HELP polymarket_latency_seconds Latency by stage; quantiles over the last {} samples.", LATENCY_WINDOW);
+        let _ = writeln!(out, "# TYPE polymarket_latency_seconds summary");
+        for (stage, samples) in self.latencies.lock().unwrap().iter() {
+            if !samples.recent.is_empty() {
+                for (q, v) in LATENCY_QUANTILES.iter().zip(samples.quantiles()) {
+                    let _ = writeln!(out, "polymarket_latency_seconds{{stage=\"{}\",quantile=\"{}\"}} {}", stage, q, v);
+                }
+            }
+            let _ = writeln!(out, "polymarket_latency_seconds_sum{{stage=\"{}\"}} {}", stage, samples.sum);
+            let _ = writeln!(out, "polymarket_latency_seconds_count{{stage=\"{}\"}} {}", stage, samples.count);
+        }
+
+        let _ = writeln!(out, "# HELP polymarket_api_requests_total Polymarket API calls by endpoint.");
+        let _ = writeln!(out, "# TYPE polymarket_api_requests_total counter");
+        for (endpoint, n) in self.api_requests.lock().unwrap().iter() {
+            let _ = writeln!(out, "polymarket_api_requests_total{{endpoint=\"{}\"}} {}", endpoint, n);
+        }
+        let _ = writeln!(out, "# HELP polymarket_api_errors_total Failed Polymarket API calls by endpoint.");
+        let _ = writeln!(out, "# TYPE polymarket_api_errors_total counter");
+        for (endpoint, n) in self.api_errors.lock().unwrap().iter() {
+            let _ = writeln!(out, "polymarket_api_errors_total{{endpoint=\"{}\"}} {}", endpoint, n);
+        }
+        out
+    }
+}
+
+/// Serves `GET /metrics` on `listen_addr` until the process exits.
+pub async fn serve(config: MetricsConfig) -> Result<()> {
+    let listener = TcpListener::bind(&config.listen_addr)
+        .await
+        .context(format!("Failed to bind metrics listener on {}", config.listen_addr))?;
+    eprintln!("📊 Metrics available at http://{}/metrics", config.listen_addr);
+    loop {
+        let (mut socket, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                log::warn!("Metrics listener accept failed: {}", e);
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let n = socket.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let response = if request.starts_with("GET /metrics") {
+                let body = metrics().render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+            };
+            let _ = socket.write_all(response.as_bytes()).await;
+        });
+    }
+}
@@ -0,0 +1,835 @@
+//! A local stand-in for the Gamma and CLOB endpoints the bot calls, so order flow can be
+//! exercised end to end without the real exchange: market by slug, books, prices, the SDK's
+//! tick-size / neg-risk / fee-rate lookups, API key derivation, orders, cancels, balance and
+//! market resolution. `/sign` stands in for a remote signer holding the test wallet.
+//!
+//! Authenticated endpoints check the L2 headers the way the CLOB does (API key, passphrase and
+//! the HMAC of timestamp, method, path and body) and answer 401 otherwise. Orders match against
+//! the configured books with the CLOB's time-in-force rules: FOK fills in full or is killed, FAK
+//! fills what the book holds at the limit price and cancels the rest, and the remainder of a GTC
+//! or GTD order stays open (outside the book).
+
+use crate::api::PolymarketApi;
+use crate::config::{NetworkConfig, RemoteSignerConfig};
+use alloy::primitives::B256;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::SignerSync;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Throwaway wallet for signing test orders (the first Hardhat development account).
+pub const TEST_PRIVATE_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+/// USDC and outcome token amounts in signed orders are fixed point with 6 decimals.
+const UNITS: Decimal = Decimal::from_parts(1_000_000, 0, 0, false, 0);
+
+pub struct MockServer {
+    url: String,
+    state: Arc<Mutex<MockState>>,
+}
+
+/// A request as the mock received it. Header names are lowercased.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(&name.to_ascii_lowercase()).map(String::as_str)
+    }
+}
+
+/// An order the mock accepted.
+#[derive(Debug, Clone)]
+pub struct MockOrder {
+    pub id: String,
+    pub token_id: String,
+    pub side: String,
+    pub order_type: String,
+    /// Signed expiration (unix seconds, 0 = none) and exchange nonce
+    pub expiration: i64,
+    pub nonce: u64,
+    pub price: Decimal,
+    pub size: Decimal,
+    pub filled: Decimal,
+    /// A GTC remainder still open
+    pub open: bool,
+}
+
+struct MockMarket {
+    slug: String,
+    condition_id: String,
+    up_token: String,
+    down_token: String,
+    closed: bool,
+    up_wins: Option<bool>,
+}
+
+/// Price levels, best first.
+#[derive(Default)]
+struct Book {
+    bids: Vec<(Decimal, Decimal)>,
+    asks: Vec<(Decimal, Decimal)>,
+}
+
+struct Credentials {
+    key: String,
+    secret: String,
+    passphrase: String,
+}
+
+impl Credentials {
+    fn generation(n: u32) -> Self {
+        Self {
+            key: format!("00000000-0000-4000-8000-{:012}", n),
+            secret: base64::engine::general_purpose::URL_SAFE.encode(format!("mock-secret-{}", n)),
+            passphrase: format!("mock-passphrase-{}", n),
+        }
+    }
+}
+
+struct MockState {
+    markets: Vec<MockMarket>,
+    books: HashMap<String, Book>,
+    /// Issued by `/auth/*` and the only ones L2 requests are accepted with
+    credentials: Credentials,
+    generation: u32,
+    balance_usd: Decimal,
+    orders: Vec<MockOrder>,
+    requests: Vec<RecordedRequest>,
+    /// Market orders are accepted as `delayed`, with nothing matched yet
+    delay_matching: bool,
+    /// Cancels are answered with a server error and leave the order open
+    fail_cancels: bool,
+}
+
+impl MockServer {
+    /// Listens on a free local port.
+    pub async fn start() -> anyhow::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}", listener.local_addr()?);
+        let state = Arc::new(Mutex::new(MockState {
+            markets: Vec::new(),
+            books: HashMap::new(),
+            credentials: Credentials::generation(1),
+            generation: 1,
+            balance_usd: Decimal::from(1000),
+            orders: Vec::new(),
+            requests: Vec::new(),
+            delay_matching: false,
+            fail_cancels: false,
+        }));
+        let shared = Arc::clone(&state);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let state = Arc::clone(&shared);
+                tokio::spawn(async move {
+                    let Some(request) = read_request(&mut socket).await else { return };
+                    let (status, body) = {
+                        let mut state = state.lock().unwrap();
+                        state.requests.push(request.clone());
+                        state.route(&request)
+                    };
+                    let response = format!(
+                        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        status,
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        Ok(Self { url, state })
+    }
+
+    /// A client pointed at the mock for every API, configured with the current credentials.
+    pub fn api(&self) -> PolymarketApi {
+        self.api_with_key(Some(TEST_PRIVATE_KEY))
+    }
+
+    /// Like `api`, signing with `private_key`; `None` leaves signing to a remote signer.
+    pub fn api_with_key(&self, private_key: Option<&str>) -> PolymarketApi {
+        let state = self.state.lock().unwrap();
+        PolymarketApi::new(
+            self.url.clone(),
+            self.url.clone(),
+            self.url.clone(),
+            Some(state.credentials.key.clone()),
+            Some(state.credentials.secret.clone()),
+            Some(state.credentials.passphrase.clone()),
+            private_key.map(str::to_string),
+            None,
+            None,
+            &NetworkConfig::default(),
+        )
+    }
+
+    /// A remote signer on the mock for `address`. The mock signs with the test wallet whatever
+    /// address is asked for, like a service set up with the wrong key would.
+    pub fn remote_signer(&self, address: &str) -> RemoteSignerConfig {
+        RemoteSignerConfig {
+            url: format!("{}/sign", self.url),
+            address: address.to_string(),
+            method: "sign_hash".to_string(),
+            timeout_ms: 5_000,
+        }
+    }
+
+    pub fn add_market(&self, slug: &str, condition_id: &str, up_token: &str, down_token: &str) {
+        self.state.lock().unwrap().markets.push(MockMarket {
+            slug: slug.to_string(),
+            condition_id: condition_id.to_string(),
+            up_token: up_token.to_string(),
+            down_token: down_token.to_string(),
+            closed: false,
+            up_wins: None,
+        });
+    }
+
+    /// Replaces the book of `token_id`; levels are (price, size), in any order.
+    pub fn set_book(&self, token_id: &str, bids: &[(f64, f64)], asks: &[(f64, f64)]) {
+        let levels = |levels: &[(f64, f64)]| -> Vec<(Decimal, Decimal)> {
+            levels.iter().map(|(p, s)| (decimal(*p), decimal(*s))).collect()
+        };
+        let mut book = Book { bids: levels(bids), asks: levels(asks) };
+        book.bids.sort_by_key(|(price, _)| std::cmp::Reverse(*price));
+        book.asks.sort_by_key(|(price, _)| *price);
+        self.state.lock().unwrap().books.insert(token_id.to_string(), book);
+    }
+
+    /// Closes the market and flags the winning outcome.
+    pub fn resolve(&self, condition_id: &str, up_wins: bool) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(market) = state.markets.iter_mut().find(|m| m.condition_id == condition_id) {
+            market.closed = true;
+            market.up_wins = Some(up_wins);
+        }
+    }
+
+    pub fn set_balance(&self, usd: f64) {
+        self.state.lock().unwrap().balance_usd = decimal(usd);
+    }
+
+    /// Accepts FOK and FAK orders without matching them, as the CLOB does when it delays a
+    /// marketable order: the response is a success with no shares or USDC exchanged.
+    pub fn delay_matching(&self) {
+        self.state.lock().unwrap().delay_matching = true;
+    }
+
+    /// While set, order cancels fail with a server error.
+    pub fn fail_cancels(&self, fail: bool) {
+        self.state.lock().unwrap().fail_cancels = fail;
+    }
+
+    /// Revokes the issued API key, as the CLOB does when one expires: L2 requests made with it
+    /// get 401 until a fresh set is derived.
+    pub fn rotate_credentials(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.generation += 1;
+        state.credentials = Credentials::generation(state.generation);
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    pub fn orders(&self) -> Vec<MockOrder> {
+        self.state.lock().unwrap().orders.clone()
+    }
+}
+
+impl MockState {
+    fn route(&mut self, request: &RecordedRequest) -> (&'static str, String) {
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        let query = request.query.as_str();
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", ["time"]) => ("200 OK", crate::clock::now().to_string()),
+            ("GET", ["events", "slug", slug]) => match self.markets.iter().find(|m| m.slug == *slug) {
+                Some(market) => ok(json!({ "markets": [gamma_market(market)] })),
+                None => ("404 Not Found", error_body("event not found")),
+            },
+            ("GET", ["markets", condition_id]) => match self.markets.iter().find(|m| m.condition_id == *condition_id) {
+                Some(market) => ok(clob_market(market)),
+                None => ("404 Not Found", error_body("market not found")),
+            },
+            ("GET", ["book"]) => {
+                let token_id = query_param(query, "token_id").unwrap_or("");
+                match self.books.get(token_id) {
+                    Some(book) => ok(book_json(token_id, book)),
+                    None => ("404 Not Found", error_body("No orderbook exists for the requested token id")),
+                }
+            }
+            ("POST", ["books"]) => {
+                let wanted: Vec<Value> = serde_json::from_str(&request.body).unwrap_or_default();
+                let books: Vec<Value> = wanted
+                    .iter()
+                    .filter_map(|w| w.get("token_id")?.as_str())
+                    .filter_map(|id| Some(book_json(id, self.books.get(id)?)))
+                    .collect();
+                ok(json!(books))
+            }
+            ("GET", ["price"]) => {
+                let token_id = query_param(query, "token_id").unwrap_or("");
+                let book = self.books.get(token_id);
+                // BUY quotes the best bid, SELL the best ask
+                let best = match query_param(query, "side") {
+                    Some("BUY") => book.and_then(|b| b.bids.first()),
+                    _ => book.and_then(|b| b.asks.first()),
+                };
+                match best {
+                    Some((price, _)) => ok(json!({ "price": price.normalize().to_string() })),
+                    None => ("404 Not Found", error_body("No orderbook exists for the requested token id")),
+                }
+            }
+            ("GET", ["tick-size"]) => ok(json!({ "minimum_tick_size": 0.01 })),
+            ("GET", ["neg-risk"]) => ok(json!({ "neg_risk": false })),
+            ("GET", ["fee-rate"]) => ok(json!({ "base_fee": 0 })),
+            ("POST", ["auth", "api-key"]) | ("GET", ["auth", "derive-api-key"]) => match check_l1(request) {
+                Ok(()) => ok(json!({
+                    "apiKey": self.credentials.key,
+                    "secret": self.credentials.secret,
+                    "passphrase": self.credentials.passphrase,
+                })),
+                Err(e) => ("401 Unauthorized", error_body(&e)),
+            },
+            ("POST", ["sign"]) => sign_hash(request),
+            (method, ["order" | "balance-allowance"]) => {
+                if let Err(e) = self.check_l2(request) {
+                    return ("401 Unauthorized", error_body(&e));
+                }
+                match (method, segments[0]) {
+                    ("POST", "order") => self.post_order(request),
+                    ("DELETE", "order") => self.cancel_order(request),
+                    ("GET", "balance-allowance") => ok(json!({
+                        "balance": (self.balance_usd * UNITS).trunc().to_string(),
+                        "allowances": {},
+                    })),
+                    _ => ("404 Not Found", error_body("not found")),
+                }
+            }
+            _ => ("404 Not Found", error_body("not found")),
+        }
+    }
+
+    /// The L2 headers must carry the issued API key and passphrase, and a signature over
+    /// timestamp, method, path and body made with the issued secret.
+    fn check_l2(&self, request: &RecordedRequest) -> Result<(), String> {
+        let header = |name: &str| request.header(name).ok_or_else(|| format!("missing {} header", name.to_uppercase()));
+        header("poly_address")?;
+        if header("poly_api_key")? != self.credentials.key || header("poly_passphrase")? != self.credentials.passphrase {
+            return Err("Unauthorized/Invalid api key".to_string());
+        }
+        let message = format!("{}{}{}{}", header("poly_timestamp")?, request.method, request.path, request.body);
+        let mut mac = Hmac::<Sha256>::new_from_slice(
+            &base64::engine::general_purpose::URL_SAFE.decode(&self.credentials.secret).map_err(|e| e.to_string())?,
+        )
+        .map_err(|e| e.to_string())?;
+        mac.update(message.as_bytes());
+        let expected = base64::engine::general_purpose::URL_SAFE.encode(mac.finalize().into_bytes());
+        if header("poly_signature")? != expected {
+            return Err("Unauthorized/Invalid signature".to_string());
+        }
+        Ok(())
+    }
+
+    fn post_order(&mut self, request: &RecordedRequest) -> (&'static str, String) {
+        let Ok(body) = serde_json::from_str::<Value>(&request.body) else {
+            return ("400 Bad Request", error_body("Invalid order payload"));
+        };
+        let order = &body["order"];
+        if body["owner"].as_str() != Some(self.credentials.key.as_str()) {
+            return ("400 Bad Request", error_body("the order owner has to be the owner of the API KEY"));
+        }
+        let amount = |field: &str| order[field].as_str().and_then(|a| Decimal::from_str(a).ok()).map(|a| a / UNITS);
+        let (Some(token_id), Some(side), Some(maker), Some(taker)) =
+            (order["tokenId"].as_str(), order["side"].as_str(), amount("makerAmount"), amount("takerAmount"))
+        else {
+            return ("400 Bad Request", error_body("Invalid order payload"));
+        };
+        let order_type = body["orderType"].as_str().unwrap_or("GTC").to_string();
+        let number = |field: &str| order[field].as_str().and_then(|v| v.parse().ok()).or_else(|| order[field].as_u64());
+        let (expiration, nonce) = (number("expiration").unwrap_or(0) as i64, number("nonce").unwrap_or(0));
+        if order_type == "GTD" && expiration < crate::clock::now() + 60 {
+            return ("400 Bad Request", error_body("invalid expiration value: GTD orders need an expiration at least a minute out"));
+        }
+        let buy = side == "BUY";
+        // Buys make USDC and take shares; sells the other way round
+        let (size, notional) = if buy { (taker, maker) } else { (maker, taker) };
+        if size.is_zero() {
+            return ("400 Bad Request", error_body("invalid order size"));
+        }
+        let price = (notional / size).round_dp(4);
+
+        if let Some(market) = self.markets.iter().find(|m| m.up_token == token_id || m.down_token == token_id) {
+            if market.closed {
+                return ("400 Bad Request", error_body("market is closed"));
+            }
+        }
+        if buy && notional > self.balance_usd {
+            return ("400 Bad Request", error_body("not enough balance / allowance"));
+        }
+        let Some(book) = self.books.get_mut(token_id) else {
+            return ("400 Bad Request", error_body("orderbook does not exist"));
+        };
+
+        let market_order = matches!(order_type.as_str(), "FOK" | "FAK");
+        let levels = if buy { &mut book.asks } else { &mut book.bids };
+        let crosses = |level: Decimal| if buy { level <= price } else { level >= price };
+        let available: Decimal = levels.iter().take_while(|(p, _)| crosses(*p)).map(|(_, s)| *s).sum();
+        let fillable = if market_order && self.delay_matching { Decimal::ZERO } else { available.min(size) };
+        match order_type.as_str() {
+            _ if market_order && self.delay_matching => {}
+            "FOK" if fillable < size => {
+                return ("400 Bad Request", error_body("order couldn't be fully filled. FOK orders are fully filled or killed."));
+            }
+            "FAK" if fillable.is_zero() => {
+                return ("400 Bad Request", error_body("no orders found to match with FAK order. FAK orders are partially filled or killed if no match is found."));
+            }
+            _ => {}
+        }
+
+        let mut remaining = fillable;
+        let mut cost = Decimal::ZERO;
+        for (level_price, level_size) in levels.iter_mut() {
+            if remaining.is_zero() {
+                break;
+            }
+            let take = (*level_size).min(remaining);
+            *level_size -= take;
+            remaining -= take;
+            cost += take * *level_price;
+        }
+        levels.retain(|(_, s)| !s.is_zero());
+        if buy {
+            self.balance_usd -= cost;
+        } else {
+            self.balance_usd += cost;
+        }
+
+        let open = matches!(order_type.as_str(), "GTC" | "GTD") && fillable < size;
+        let id = format!("0x{:064x}", self.orders.len() + 1);
+        self.orders.push(MockOrder {
+            id: id.clone(),
+            token_id: token_id.to_string(),
+            side: side.to_string(),
+            order_type,
+            expiration,
+            nonce,
+            price,
+            size,
+            filled: fillable,
+            open,
+        });
+        let (making, taking) = if buy { (cost, fillable) } else { (fillable, cost) };
+        ok(json!({
+            "success": true,
+            "errorMsg": "",
+            "orderID": id,
+            "status": if open { "live" } else if fillable.is_zero() { "delayed" } else { "matched" },
+            "makingAmount": if fillable.is_zero() { String::new() } else { making.normalize().to_string() },
+            "takingAmount": if fillable.is_zero() { String::new() } else { taking.normalize().to_string() },
+            "transactionsHashes": [],
+            "tradeIds": [],
+        }))
+    }
+
+    fn cancel_order(&mut self, request: &RecordedRequest) -> (&'static str, String) {
+        if self.fail_cancels {
+            return ("500 Internal Server Error", error_body("could not cancel the order"));
+        }
+        let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+        // The SDK sends `orderId`, the CLOB documents `orderID`
+        let id = body["orderID"].as_str().or(body["orderId"].as_str()).unwrap_or("");
+        match self.orders.iter_mut().find(|o| o.id == id && o.open) {
+            Some(order) => {
+                order.open = false;
+                ok(json!({ "canceled": [id], "not_canceled": {} }))
+            }
+            None => ok(json!({ "canceled": [], "not_canceled": { id: "order can't be found - already canceled or matched" } })),
+        }
+    }
+}
+
+/// The L1 headers of key creation and derivation. The EIP-712 signature itself is not checked.
+fn check_l1(request: &RecordedRequest) -> Result<(), String> {
+    for name in ["poly_address", "poly_signature", "poly_timestamp", "poly_nonce"] {
+        if request.header(name).is_none() {
+            return Err(format!("missing {} header", name.to_uppercase()));
+        }
+    }
+    Ok(())
+}
+
+/// JSON-RPC `sign_hash`: the hash in `params[1]` signed with the test wallet. Other methods
+/// get a JSON-RPC error.
+fn sign_hash(request: &RecordedRequest) -> (&'static str, String) {
+    let body: Value = serde_json::from_str(&request.body).unwrap_or_default();
+    if body["method"] != "sign_hash" {
+        return ok(json!({ "jsonrpc": "2.0", "id": body["id"], "error": { "code": -32601, "message": "method not found" } }));
+    }
+    let hash = body["params"][1].as_str().and_then(|h| B256::from_str(h).ok());
+    let signer = PrivateKeySigner::from_str(TEST_PRIVATE_KEY).expect("test key");
+    match hash.map(|hash| signer.sign_hash_sync(&hash)) {
+        Some(Ok(signature)) => ok(json!({ "jsonrpc": "2.0", "id": body["id"], "result": signature.to_string() })),
+        _ => ok(json!({ "jsonrpc": "2.0", "id": body["id"], "error": { "code": -32602, "message": "invalid params" } })),
+    }
+}
+
+fn gamma_market(market: &MockMarket) -> Value {
+    json!({
+        "conditionId": market.condition_id,
+        "id": "1",
+        "question": market.slug,
+        "slug": market.slug,
+        "active": !market.closed,
+        "closed": market.closed,
+        "volume24hr": 0.0,
+    })
+}
+
+fn clob_market(market: &MockMarket) -> Value {
+    let token = |outcome: &str, token_id: &str, wins: bool| json!({ "outcome": outcome, "token_id": token_id, "winner": wins });
+    json!({
+        "condition_id": market.condition_id,
+        "question": market.slug,
+        "tokens": [
+            token("Up", &market.up_token, market.up_wins == Some(true)),
+            token("Down", &market.down_token, market.up_wins == Some(false)),
+        ],
+        "active": !market.closed,
+        "closed": market.closed,
+        "end_date_iso": "",
+    })
+}
+
+/// A book as the CLOB sends it: best bid and best ask last.
+fn book_json(token_id: &str, book: &Book) -> Value {
+    let levels = |levels: &[(Decimal, Decimal)]| -> Vec<Value> {
+        levels
+            .iter()
+            .rev()
+            .map(|(p, s)| json!({ "price": p.normalize().to_string(), "size": s.normalize().to_string() }))
+            .collect()
+    };
+    json!({
+        "asset_id": token_id,
+        "bids": levels(&book.bids),
+        "asks": levels(&book.asks),
+        "tick_size": "0.01",
+        "min_order_size": "5",
+    })
+}
+
+/// Reads one request: the head, then as much body as `Content-Length` announces.
+async fn read_request(socket: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_len = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        let n = socket.read(&mut chunk).await.ok().filter(|n| *n > 0)?;
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = String::from_utf8_lossy(&buf[..head_len]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let body_len: usize = headers.get("content-length").and_then(|l| l.parse().ok()).unwrap_or(0);
+    while buf.len() < head_len + body_len {
+        let n = socket.read(&mut chunk).await.ok().filter(|n| *n > 0)?;
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    Some(RecordedRequest {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body: String::from_utf8_lossy(&buf[head_len..head_len + body_len]).to_string(),
+    })
+}
+
+fn query_param<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').find_map(|kv| kv.strip_prefix(name)?.strip_prefix('='))
+}
+
+fn ok(value: Value) -> (&'static str, String) {
+    ("200 OK", value.to_string())
+}
+
+fn error_body(message: &str) -> String {
+    json!({ "error": message }).to_string()
+}
+
+fn decimal(value: f64) -> Decimal {
+    Decimal::from_str(&value.to_string()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apierror::ApiError;
+    use crate::models::OrderRequest;
+
+    const SLUG: &str = "btc-updown-15m-1767726000";
+    const CONDITION_ID: &str = "0xc0ffee";
+    const UP: &str = "1001";
+    const DOWN: &str = "1002";
+
+    async fn server() -> MockServer {
+        let server = MockServer::start().await.unwrap();
+        server.add_market(SLUG, CONDITION_ID, UP, DOWN);
+        server.set_book(UP, &[(0.48, 100.0)], &[(0.50, 6.0), (0.52, 100.0)]);
+        server.set_book(DOWN, &[(0.47, 100.0)], &[(0.49, 100.0)]);
+        server
+    }
+
+    fn limit_buy(token_id: &str, size: &str, price: &str) -> OrderRequest {
+        OrderRequest {
+            token_id: token_id.to_string(),
+            side: "BUY".to_string(),
+            size: size.to_string(),
+            price: price.to_string(),
+            order_type: "GTC".to_string(),
+            expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_market_by_slug_and_books() {
+        let server = server().await;
+        let api = server.api();
+
+        let market = api.get_market_by_slug(SLUG).await.unwrap();
+        assert_eq!(market.condition_id, CONDITION_ID);
+        assert!(market.active && !market.closed);
+
+        let books = api.get_order_books(&[UP.to_string(), DOWN.to_string()]).await.unwrap();
+        assert_eq!(books.len(), 2);
+        let up = &books[UP];
+        assert_eq!(up.asks.len(), 2);
+        assert_eq!(up.asks.last().unwrap().price, decimal(0.50));
+        assert_eq!(up.tick_size, Some(decimal(0.01)));
+    }
+
+    #[tokio::test]
+    async fn caches_market_metadata_until_invalidated() {
+        let server = server().await;
+        let api = server.api().with_market_cache(std::time::Duration::from_secs(300));
+        let fetches = |path: &str| server.requests().iter().filter(|r| r.path.starts_with(path)).count();
+
+        for _ in 0..3 {
+            api.get_market_by_slug(SLUG).await.unwrap();
+            api.get_market_metadata(CONDITION_ID).await.unwrap();
+        }
+        assert_eq!((fetches("/events/slug/"), fetches("/markets/")), (1, 1));
+
+        // Resolution checks skip the cache
+        api.get_market(CONDITION_ID).await.unwrap();
+        assert_eq!(fetches("/markets/"), 2);
+
+        api.invalidate_market(CONDITION_ID);
+        api.get_market_by_slug(SLUG).await.unwrap();
+        api.get_market_metadata(CONDITION_ID).await.unwrap();
+        assert_eq!((fetches("/events/slug/"), fetches("/markets/")), (2, 3));
+    }
+
+    #[tokio::test]
+    async fn finds_pinned_event_markets_outside_any_period() {
+        use crate::config::{Config, MarketConfig, MarketDuration, MarketFamily};
+        use crate::discovery::MarketDiscovery;
+        let server = server().await;
+        let mut event = MarketConfig::new("FINALS", "finals");
+        event.family = MarketFamily::Event;
+        event.slug = Some(SLUG.to_string());
+        let config = Config { markets: vec![event], ..Config::default() };
+        assert!(config.validate().is_ok());
+        assert_eq!((config.event_assets(), config.assets_for(MarketDuration::M15)), (vec!["FINALS".to_string()], vec![]));
+
+        let discovery = MarketDiscovery::new(Arc::new(server.api()), &config);
+        let market = discovery.find_event_market("finals").await.unwrap();
+        assert_eq!(market.condition_id, CONDITION_ID);
+        let tokens = discovery.get_event_tokens(CONDITION_ID).await.unwrap();
+        assert_eq!(tokens, (UP.to_string(), DOWN.to_string()));
+    }
+
+    #[tokio::test]
+    async fn posts_orders_with_l2_auth_headers() {
+        let server = server().await;
+        let api = server.api();
+
+        let response = api.place_order(&limit_buy(DOWN, "10", "0.45")).await.unwrap();
+        let orders = server.orders();
+        assert_eq!(orders.len(), 1);
+        assert_eq!(response.order_id.as_deref(), Some(orders[0].id.as_str()));
+        assert_eq!((orders[0].token_id.as_str(), orders[0].side.as_str()), (DOWN, "BUY"));
+        assert_eq!((orders[0].price, orders[0].size, orders[0].filled), (decimal(0.45), decimal(10.0), Decimal::ZERO));
+        assert!(orders[0].open);
+
+        let post = server.requests().into_iter().find(|r| r.method == "POST" && r.path == "/order").unwrap();
+        for header in ["POLY_ADDRESS", "POLY_API_KEY", "POLY_PASSPHRASE", "POLY_SIGNATURE", "POLY_TIMESTAMP"] {
+            assert!(post.header(header).is_some(), "{} missing", header);
+        }
+
+        api.cancel_order(&orders[0].id).await.unwrap();
+        assert!(!server.orders()[0].open);
+    }
+
+    #[tokio::test]
+    async fn signs_orders_through_a_remote_signer() {
+        use crate::api::RemoteSigner;
+        let server = server().await;
+        let wallet = PrivateKeySigner::from_str(TEST_PRIVATE_KEY).unwrap().address().to_string();
+        let signer = |address: &str| RemoteSigner::new(&server.remote_signer(address)).unwrap();
+
+        let api = server.api_with_key(None).with_remote_signer(signer(&wallet));
+        api.place_order(&limit_buy(DOWN, "10", "0.45")).await.unwrap();
+        assert_eq!(server.orders().len(), 1);
+        let post = server.requests().into_iter().find(|r| r.path == "/order").unwrap();
+        let order: Value = serde_json::from_str(&post.body).unwrap();
+        assert_eq!(order["order"]["signer"].as_str().map(str::to_lowercase), Some(wallet.to_lowercase()));
+        assert_eq!(server.requests().iter().filter(|r| r.path == "/sign").count(), 1);
+
+        // Signatures by another key than the configured wallet's never reach the exchange
+        let api = server.api_with_key(None).with_remote_signer(signer("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"));
+        let result = api.place_order(&limit_buy(DOWN, "10", "0.45")).await;
+        assert!(format!("{:?}", result).contains("instead of"), "{:?}", result);
+        assert_eq!(server.orders().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn fok_is_killed_and_fak_fills_what_the_book_holds() {
+        let server = server().await;
+        let api = server.api();
+
+        // 6 shares rest at the 0.50 ask: a FOK for 10 can't fill in full
+        let fok = api.place_market_order(UP, 10.0, "BUY", Some("FOK"), None).await;
+        assert!(matches!(fok, Err(ApiError::InvalidOrder(_))), "{:?}", fok);
+        assert!(server.orders().is_empty());
+
+        api.place_market_order(UP, 10.0, "BUY", Some("FAK"), None).await.unwrap();
+        let orders = server.orders();
+        assert_eq!(orders.len(), 1);
+        assert_eq!((orders[0].order_type.as_str(), orders[0].filled, orders[0].open), ("FAK", decimal(6.0), false));
+
+        // The 0.50 level was taken, so the next one buys at 0.52
+        api.place_market_order(UP, 10.0, "BUY", Some("FAK"), None).await.unwrap();
+        let last = server.orders().pop().unwrap();
+        assert_eq!((last.price, last.filled), (decimal(0.52), decimal(10.0)));
+    }
+
+    #[tokio::test]
+    async fn worst_price_caps_a_market_order() {
+        let server = server().await;
+        let api = server.api();
+
+        // Only the 6 shares at 0.50 are within the cap; the 0.52 level is left alone
+        let response = api.place_market_order(UP, 10.0, "BUY", Some("FAK"), Some(0.50)).await.unwrap();
+        assert_eq!((response.matched_shares, response.matched_usd), (Some(6.0), Some(3.0)));
+        let order = server.orders().pop().unwrap();
+        assert_eq!((order.price, order.filled), (decimal(0.50), decimal(6.0)));
+        let fok = api.place_market_order(UP, 10.0, "BUY", Some("FOK"), Some(0.50)).await;
+        assert!(matches!(fok, Err(ApiError::InvalidOrder(_))), "{:?}", fok);
+    }
+
+    #[tokio::test]
+    async fn a_partly_matched_sell_reports_only_what_filled() {
+        let server = server().await;
+        server.set_book(DOWN, &[(0.47, 4.0), (0.40, 100.0)], &[(0.49, 100.0)]);
+        let api = server.api();
+
+        let response = api.place_market_order(DOWN, 10.0, "SELL", Some("FAK"), Some(0.45)).await.unwrap();
+        assert_eq!(response.matched(0.47), (4.0, 0.47));
+        assert_eq!(server.orders().pop().unwrap().filled, decimal(4.0));
+    }
+
+    #[tokio::test]
+    async fn an_accepted_order_that_matched_nothing_sold_nothing() {
+        let server = server().await;
+        server.delay_matching();
+        let api = server.api();
+
+        let response = api.place_market_order(DOWN, 10.0, "SELL", Some("FAK"), Some(0.45)).await.unwrap();
+        assert_eq!(response.matched_shares, Some(0.0));
+        assert_eq!(response.matched(0.47), (0.0, 0.47));
+        assert_eq!(server.orders().pop().unwrap().filled, Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn gtd_orders_carry_the_expiry_and_nonce() {
+        let server = server().await;
+        let api = server.api().with_order_nonce(7);
+        let period_end = crate::clock::now() + 300;
+
+        api.place_order(&OrderRequest { expires_at: Some(period_end), ..limit_buy(DOWN, "10", "0.45") }).await.unwrap();
+        let order = server.orders().pop().unwrap();
+        // Signed a minute past the period end, the CLOB's threshold for GTD orders
+        assert_eq!((order.order_type.as_str(), order.expiration, order.nonce, order.open), ("GTD", period_end + 60, 7, true));
+
+        api.place_order(&limit_buy(DOWN, "10", "0.45")).await.unwrap();
+        let order = server.orders().pop().unwrap();
+        assert_eq!((order.order_type.as_str(), order.expiration), ("GTC", 0));
+    }
+
+    #[tokio::test]
+    async fn rederives_credentials_after_401() {
+        let server = server().await;
+        let api = server.api();
+        server.rotate_credentials();
+
+        api.place_order(&limit_buy(DOWN, "10", "0.45")).await.unwrap();
+        let requests = server.requests();
+        assert!(requests.iter().any(|r| r.path == "/auth/derive-api-key"));
+        assert_eq!(requests.iter().filter(|r| r.path == "/order").count(), 2);
+        assert_eq!(server.orders().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_buys_beyond_the_balance() {
+        let server = server().await;
+        let api = server.api();
+        server.set_balance(2.0);
+
+        assert!((api.get_usdc_balance().await.unwrap() - 2.0).abs() < 1e-9);
+        let result = api.place_order(&limit_buy(DOWN, "10", "0.45")).await;
+        assert!(matches!(result, Err(ApiError::InsufficientBalance(_))), "{:?}", result);
+    }
+
+    #[tokio::test]
+    async fn reports_market_resolution() {
+        let server = server().await;
+        let api = server.api();
+
+        let open = api.get_market(CONDITION_ID).await.unwrap();
+        assert!(!open.closed && open.tokens.iter().all(|t| !t.winner));
+
+        server.resolve(CONDITION_ID, false);
+        let resolved = api.get_market(CONDITION_ID).await.unwrap();
+        assert!(resolved.closed);
+        let winner = resolved.tokens.iter().find(|t| t.winner).unwrap();
+        assert_eq!((winner.outcome.as_str(), winner.token_id.as_str()), ("Down", DOWN));
+
+        let order = api.place_order(&limit_buy(UP, "10", "0.45")).await;
+        assert!(matches!(order, Err(ApiError::MarketClosed(_))), "{:?}", order);
+    }
+}
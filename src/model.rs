@@ -0,0 +1,171 @@
+use crate::api::{self, PolymarketApi};
+use crate::config::{Config, MarketFamily, ModelConfig};
+use crate::dataset::{FeatureSampler, Features};
+use crate::feed::MarketFeed;
+use crate::momentum::SpotCandles;
+use crate::monitor::MarketMonitor;
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+/// A user-provided ONNX model scoring the probability that Up wins from `features`.
+pub struct SideModel {
+    features: Vec<String>,
+    #[cfg(feature = "onnx")]
+    output_index: usize,
+    #[cfg(feature = "onnx")]
+    session: Mutex<ort::session::Session>,
+}
+
+impl SideModel {
+    /// Loads `cfg.path`. The ONNX Runtime library is found through `ORT_DYLIB_PATH`.
+    #[cfg(feature = "onnx")]
+    pub fn load(cfg: &ModelConfig) -> Result<Self> {
+        let session = ort::session::Session::builder()
+            .and_then(|b| b.commit_from_file(&cfg.path))
+            .map_err(|e| anyhow::anyhow!("Failed to load model {}: {}", cfg.path.display(), e))?;
+        Ok(Self { features: cfg.features.clone(), output_index: cfg.output_index, session: Mutex::new(session) })
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    pub fn load(_cfg: &ModelConfig) -> Result<Self> {
+        anyhow::bail!("strategy.signal.model requires building with `--features onnx`")
+    }
+
+    pub fn needs(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+
+    /// Up probability for `features`, clamped to 0..1; `None` when a feature the model needs is
+    /// missing.
+    pub fn up_probability(&self, features: &Features) -> Result<Option<f64>> {
+        let Some(inputs) = inputs(&self.features, features) else { return Ok(None) };
+        Ok(Some(self.run(inputs)?.clamp(0.0, 1.0)))
+    }
+
+    /// Feeds `inputs` as one `[1, n]` float row and reads `output_index` of the first float output.
+    #[cfg(feature = "onnx")]
+    fn run(&self, inputs: Vec<f32>) -> Result<f64> {
+        use anyhow::Context;
+        let n = inputs.len();
+        let tensor = ort::value::Tensor::from_array(([1usize, n], inputs)).map_err(|e| anyhow::anyhow!("{}", e))?;
+        let mut session = self.session.lock().unwrap();
+        let outputs = session.run(ort::inputs![tensor]).map_err(|e| anyhow::anyhow!("Model run failed: {}", e))?;
+        let values = outputs
+            .values()
+            .find_map(|v| v.try_extract_tensor::<f32>().ok().map(|(_, values)| values.to_vec()))
+            .context("Model has no float output")?;
+        values
+            .get(self.output_index)
+            .map(|p| *p as f64)
+            .with_context(|| format!("Model output has {} element(s), no output_index {}", values.len(), self.output_index))
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    fn run(&self, _inputs: Vec<f32>) -> Result<f64> {
+        unreachable!("SideModel can't be loaded without the onnx feature")
+    }
+}
+
+/// The values of the features called `names`, in order; `None` when one of them is missing.
+fn inputs(names: &[String], features: &Features) -> Option<Vec<f32>> {
+    names.iter().map(|f| features.value(f).map(|v| v as f32)).collect()
+}
+
+/// The side model with what it reads its features from: a monitor for full snapshots, the
+/// samples of each period and, when the model uses `spot_progress`, the spot candles.
+pub struct ModelGate {
+    model: Arc<SideModel>,
+    monitor: MarketMonitor,
+    sampler: Mutex<FeatureSampler>,
+    spot: Option<SpotCandles>,
+    config: Config,
+}
+
+impl ModelGate {
+    pub fn new(model: Arc<SideModel>, api: Arc<PolymarketApi>, feed: Arc<MarketFeed>, config: &Config) -> Self {
+        let spot = model.needs("spot_progress").then(|| {
+            SpotCandles::new(api::http_client(&config.network).expect("Failed to create HTTP client"), config.strategy.signal.momentum.clone())
+        });
+        Self {
+            monitor: MarketMonitor::new(api, feed, config),
+            sampler: Mutex::new(FeatureSampler::new(config.dataset.sample_secs, config.strategy.signal.trend.clone())),
+            model,
+            spot,
+            config: config.clone(),
+        }
+    }
+
+    /// Takes a sample of `asset`'s period when one is due, so the change, trend and volatility
+    /// features are read over the period as they were for the dataset.
+    pub async fn sample(&self, asset: &str, period_start: i64) {
+        let now = crate::clock::now();
+        if !self.sampler.lock().unwrap().sample_due(asset, period_start, now) {
+            return;
+        }
+        self.features(asset, period_start, now, None).await;
+    }
+
+    /// The model's Up probability for `asset` now; `None` (logged) when the snapshot, a feature
+    /// or the model run fails.
+    pub async fn up_probability(&self, asset: &str, period_start: i64) -> Option<f64> {
+        let now = crate::clock::now();
+        let market = self.config.markets.iter().find(|m| m.asset == asset && m.family == MarketFamily::UpDown);
+        let spot_progress = match (&self.spot, market) {
+            (Some(spot), Some(market)) => spot.progress(&market.spot_symbol(), period_start).await,
+            _ => None,
+        };
+        let features = self.features(asset, period_start, now, spot_progress).await?;
+        match self.model.up_probability(&features) {
+            Ok(Some(p)) => Some(p),
+            Ok(None) => {
+                log::debug!("{} | Model skipped: a feature it needs is missing", asset);
+                None
+            }
+            Err(e) => {
+                log::warn!("{} | Model failed: {:#}", asset, e);
+                None
+            }
+        }
+    }
+
+    async fn features(&self, asset: &str, period_start: i64, now: i64, spot_progress: Option<f64>) -> Option<Features> {
+        let snapshot = match self.monitor.snapshot(asset, period_start).await {
+            Ok(snapshot) if snapshot.invalid.is_none() => snapshot,
+            Ok(_) => return None,
+            Err(e) => {
+                log::debug!("{} | No snapshot for model features: {}", asset, e);
+                return None;
+            }
+        };
+        let mut sampler = self.sampler.lock().unwrap();
+        sampler.prune(now);
+        Some(sampler.observe(&snapshot, now, spot_progress))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trend::Trend;
+
+    #[test]
+    fn inputs_follow_the_configured_order_and_need_every_feature() {
+        let features = Features {
+            secs_into_period: 420,
+            up_price: 0.62,
+            down_price: 0.4,
+            up_mid: 0.61,
+            implied_up: 0.6,
+            up_spread: Some(0.02),
+            up_change: 0.1,
+            trend: Trend::Falling,
+            up_std_dev: None,
+            imbalance: Some(-0.25),
+            volume_24h: None,
+            spot_progress: None,
+        };
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(inputs(&names(&["trend", "imbalance", "secs_into_period"]), &features), Some(vec![-1.0, -0.25, 420.0]));
+        assert_eq!(inputs(&names(&["implied_up", "spot_progress"]), &features), None);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ledger::{Decision, Rationale};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
@@ -13,6 +14,69 @@ pub struct Market {
     pub end_date_iso: Option<String>,
     pub active: bool,
     pub closed: bool,
+    /// Volume traded over the last 24h in USD, both outcomes together
+    #[serde(rename = "volume24hr", default)]
+    pub volume_24h: Option<f64>,
+}
+
+impl Market {
+    /// End time in unix seconds; `None` when missing or given as a date only.
+    pub fn end_timestamp(&self) -> Option<i64> {
+        let end = self.end_date_iso.as_deref()?;
+        chrono::DateTime::parse_from_rfc3339(end).ok().map(|t| t.timestamp())
+    }
+}
+
+/// What an "<asset> above $X" market resolves Yes on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Threshold {
+    pub strike: f64,
+    /// Yes when the price ends above the strike; false for "below" markets
+    pub above: bool,
+}
+
+impl Threshold {
+    /// Reads the strike after "above" or "below" in a question such as "Will the price of
+    /// Bitcoin be above $110,000 on October 16?" or "Ethereum below $3,500.50 at 2PM ET?". A
+    /// `k` or `m` suffix scales the number.
+    pub fn parse(question: &str) -> Option<Self> {
+        let lower = question.to_lowercase();
+        let (at, above) = [("above", true), ("below", false)]
+            .into_iter()
+            .filter_map(|(word, above)| lower.find(&format!("{} ", word)).map(|i| (i + word.len() + 1, above)))
+            .min_by_key(|(i, _)| *i)?;
+        let rest = lower[at..].trim_start().trim_start_matches('$');
+        let number: String = rest.chars().take_while(|c| c.is_ascii_digit() || *c == ',' || *c == '.').collect();
+        let number = number.trim_end_matches(['.', ',']);
+        let mut strike: f64 = number.replace(',', "").parse().ok()?;
+        match rest[number.len()..].chars().next() {
+            Some('k') => strike *= 1e3,
+            Some('m') => strike *= 1e6,
+            _ => {}
+        }
+        (strike > 0.0).then_some(Threshold { strike, above })
+    }
+}
+
+/// Shares held of one outcome of a market. A trade holds one leg per outcome it bought, so
+/// binary Up/Down markets have two and multi-outcome markets one per token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeLeg {
+    pub outcome: String,
+    pub token_id: Option<String>,
+    pub shares: f64,
+    /// Average price paid per share; 0 when the bot doesn't know it
+    pub avg_price: f64,
+}
+
+impl TradeLeg {
+    pub fn new(outcome: &str, token_id: Option<String>, shares: f64, avg_price: f64) -> Self {
+        TradeLeg { outcome: outcome.to_string(), token_id, shares, avg_price }
+    }
+
+    pub fn cost(&self) -> f64 {
+        self.shares * self.avg_price
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +103,10 @@ pub struct MarketToken {
 pub struct OrderBook {
     pub bids: Vec<OrderBookEntry>,
     pub asks: Vec<OrderBookEntry>,
+    #[serde(default)]
+    pub tick_size: Option<Decimal>,
+    #[serde(default)]
+    pub min_order_size: Option<Decimal>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,6 +123,9 @@ pub struct OrderRequest {
     pub price: String,
     #[serde(rename = "type")]
     pub order_type: String,
+    /// Unix seconds after which the order must no longer rest on the book; sent as a GTD order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +133,23 @@ pub struct OrderResponse {
     pub order_id: Option<String>,
     pub status: String,
     pub message: Option<String>,
+    /// Shares and USDC that changed hands as the order was posted (market orders)
+    #[serde(default)]
+    pub matched_shares: Option<f64>,
+    #[serde(default)]
+    pub matched_usd: Option<f64>,
+}
+
+impl OrderResponse {
+    /// Shares a market order matched and their average price, `quoted` when none matched. An
+    /// order accepted without a match (e.g. delayed) sold or bought nothing yet.
+    pub fn matched(&self, quoted: f64) -> (f64, f64) {
+        match (self.matched_shares, self.matched_usd) {
+            (Some(shares), Some(usd)) if shares > 0.0 => (shares, usd / shares),
+            (Some(shares), None) if shares > 0.0 => (shares, quoted),
+            _ => (0.0, quoted),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -83,17 +171,51 @@ pub struct PreLimitOrderState {
     pub down_order_id: Option<String>,
     pub up_order_price: f64,
     pub down_order_price: f64,
+    /// Shares per side, sized when the orders were placed
+    pub shares: f64,
     pub up_matched: bool,
     pub down_matched: bool,
     pub merged: bool,
     pub expiry: i64,
     pub risk_sold: bool,
+    #[allow(dead_code)]
     pub order_placed_at: i64,
     pub market_period_start: i64,
     /// Timestamp when we first had only one side matched (for sell_after_danger_time_passed)
     pub one_side_matched_at: Option<i64>,
+    /// Unfilled orders were canceled by the kill switch; no further fills expected
+    pub resting_cancelled: bool,
+    /// Side sold before expiry (loser on sell-opposite, or the lone fill on a danger exit)
+    pub sold_side: Option<String>,
+    /// Shares of `sold_side` still held because its sell filled only in part
+    pub unsold: f64,
+    /// A sell of this position that has filled only in part so far
+    pub partial_sell: Option<PartialSell>,
+    /// Shares of the held winner sold into a late price spike (`strategy.spike_sell`); set once
+    /// the single attempt has been made, to 0 if nothing sold
+    pub spike_sold: Option<f64>,
+    /// Rule and inputs behind the pair, copied onto every ledger row of the position
+    pub decision: Option<Decision>,
 }
 
+/// Progress of a market sell that has filled only in part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialSell {
+    pub outcome: String,
+    /// Rule that decided the sell
+    pub rationale: Rationale,
+    /// Shares still to sell
+    pub remaining: f64,
+    /// Notional and fees of the shares sold so far
+    pub notional: f64,
+    pub fees: f64,
+    /// When the first part filled
+    pub first_fill_at: i64,
+    /// Lowest price the remainder may sell at: the first attempt's floor
+    pub floor: f64,
+}
+
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenPrice {
     pub token_id: String,
@@ -101,6 +223,7 @@ pub struct TokenPrice {
     pub ask: Option<Decimal>,
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Fill {
     #[serde(rename = "tokenID")]
@@ -112,3 +235,130 @@ pub struct Fill {
     #[serde(rename = "conditionId")]
     pub condition_id: Option<String>,
 }
+
+/// Point-in-time view of one 15m market, as recorded for backtesting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketSnapshot {
+    /// Unix milliseconds when the snapshot was taken
+    pub timestamp_ms: i64,
+    pub asset: String,
+    pub condition_id: String,
+    pub period_start: i64,
+    pub up: TokenSnapshot,
+    pub down: TokenSnapshot,
+    /// Market volume over the last 24h in USD (Gamma reports it per market, not per outcome)
+    #[serde(default)]
+    pub volume_24h: Option<f64>,
+    /// Why the prices can't be real (an API glitch), if so; such snapshots are never traded on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invalid: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenSnapshot {
+    pub token_id: String,
+    /// Quoted price the strategy trades on (CLOB /price, SELL side)
+    pub price: f64,
+    #[serde(default)]
+    pub best_bid: Option<f64>,
+    #[serde(default)]
+    pub best_ask: Option<f64>,
+    /// Quoted price on the other side (CLOB /price, BUY side)
+    #[serde(default)]
+    pub bid: Option<f64>,
+    /// Midpoint of `bid` and `price`
+    #[serde(default)]
+    pub mid: Option<f64>,
+    #[serde(default)]
+    pub last_trade_price: Option<f64>,
+    #[serde(default)]
+    pub bids: Vec<PriceLevel>,
+    #[serde(default)]
+    pub asks: Vec<PriceLevel>,
+    /// Price increment the CLOB accepts for this token (0.01, or 0.001 near 0 and 1)
+    #[serde(default)]
+    pub tick_size: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    pub price: f64,
+    pub size: f64,
+}
+
+/// One row of the data API `/positions` response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataApiPosition {
+    /// Outcome token ID
+    pub asset: String,
+    pub condition_id: String,
+    pub size: f64,
+    #[serde(default)]
+    pub avg_price: f64,
+    /// "Up" or "Down"
+    #[serde(default)]
+    pub outcome: String,
+    /// Market question
+    #[serde(default)]
+    pub title: String,
+    /// The market has resolved and the shares can be redeemed
+    #[serde(default)]
+    pub redeemable: bool,
+}
+
+/// One point of a CLOB `/prices-history` series.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PricePoint {
+    /// Unix seconds
+    pub t: i64,
+    pub p: f64,
+}
+
+/// A resting order of the account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountOrder {
+    pub order_id: String,
+    pub condition_id: String,
+    /// Unix seconds the order was placed
+    pub created_at: i64,
+}
+
+/// One of the account's own fills as the CLOB reports it (`/data/trades`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountFill {
+    pub trade_id: String,
+    pub condition_id: String,
+    pub token_id: String,
+    /// "Up" or "Down"
+    pub outcome: String,
+    /// "BUY" or "SELL"
+    pub side: String,
+    pub size: f64,
+    pub price: f64,
+    /// Unix seconds the trade matched
+    pub timestamp: i64,
+    /// "MATCHED", "MINED", "CONFIRMED", "RETRYING" or "FAILED"
+    pub status: String,
+}
+
+impl MarketSnapshot {
+    pub fn timestamp_secs(&self) -> i64 {
+        self.timestamp_ms / 1000
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_strikes_from_threshold_questions() {
+        let parse = |q| Threshold::parse(q).map(|t| (t.strike, t.above));
+        assert_eq!(parse("Will the price of Bitcoin be above $110,000 on October 16?"), Some((110_000.0, true)));
+        assert_eq!(parse("Ethereum below $3,500.50 at 2PM ET?"), Some((3_500.5, false)));
+        assert_eq!(parse("Bitcoin above 120k on October 17?"), Some((120_000.0, true)));
+        assert_eq!(parse("Bitcoin Up or Down - October 16, 2PM ET"), None);
+        assert_eq!(parse("Will Solana be above $ on Friday?"), None);
+    }
+}
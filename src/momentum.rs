@@ -0,0 +1,145 @@
+use crate::config::MomentumConfig;
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// One 15m spot candle; `open_time` in unix seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candle {
+    pub open_time: i64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+/// How far the candle opening at `period_start` has moved from its open, as a multiple of the
+/// median high-low range of the closed candles before it: positive up, negative down. `None`
+/// if the newest candle isn't that one or there is nothing to compare it with.
+pub fn candle_progress(candles: &[Candle], period_start: i64) -> Option<f64> {
+    let (current, closed) = candles.split_last()?;
+    if current.open_time != period_start || closed.is_empty() {
+        return None;
+    }
+    let mut ranges: Vec<f64> = closed.iter().map(|c| c.high - c.low).collect();
+    ranges.sort_by(f64::total_cmp);
+    let typical = ranges[ranges.len() / 2];
+    (typical > 0.0).then(|| (current.close - current.open) / typical)
+}
+
+/// Whether `progress` backs Up (or Down) winning by at least `min_range_fraction`; unknown
+/// progress confirms nothing.
+pub fn confirms(min_range_fraction: f64, progress: Option<f64>, up_wins: bool) -> bool {
+    progress.is_some_and(|p| if up_wins { p >= min_range_fraction } else { p <= -min_range_fraction })
+}
+
+/// 15m candles of the underlyings from a Binance-compatible klines endpoint, reused for
+/// `refresh_ms` per symbol.
+pub struct SpotCandles {
+    client: Client,
+    cfg: MomentumConfig,
+    /// Symbol -> when its candles were fetched, and the candles oldest first
+    cache: Mutex<HashMap<String, (Instant, Vec<Candle>)>>,
+}
+
+impl SpotCandles {
+    pub fn new(client: Client, cfg: MomentumConfig) -> Self {
+        Self { client, cfg, cache: Mutex::new(HashMap::new()) }
+    }
+
+    /// `candle_progress` of `symbol` for the period starting at `period_start`; `None` when the
+    /// candles can't be fetched.
+    pub async fn progress(&self, symbol: &str, period_start: i64) -> Option<f64> {
+        let max_age = Duration::from_millis(self.cfg.refresh_ms);
+        let cached = self.cache.lock().unwrap().get(symbol).filter(|(at, _)| at.elapsed() < max_age).map(|(_, c)| c.clone());
+        let candles = match cached {
+            Some(candles) => candles,
+            None => match self.fetch(symbol).await {
+                Ok(candles) => {
+                    self.cache.lock().unwrap().insert(symbol.to_string(), (Instant::now(), candles.clone()));
+                    candles
+                }
+                Err(e) => {
+                    log::warn!("Failed to fetch {} spot candles: {:#}", symbol, e);
+                    return None;
+                }
+            },
+        };
+        candle_progress(&candles, period_start)
+    }
+
+    /// `symbol`'s spot price at `time` (unix seconds, on a minute): the open of the 1m candle
+    /// starting then. `None` until that candle exists.
+    pub async fn price_at(&self, symbol: &str, time: i64) -> Result<Option<f64>> {
+        let url = format!("{}/api/v3/klines", self.cfg.spot_url.trim_end_matches('/'));
+        let start = (time * 1000).to_string();
+        let rows: Vec<Vec<Value>> = self
+            .client
+            .get(&url)
+            .query(&[("symbol", symbol), ("interval", "1m"), ("startTime", start.as_str()), ("limit", "1")])
+            .send()
+            .await
+            .context("Klines request failed")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Invalid klines response")?;
+        let candle = rows.first().map(|row| parse_kline(row).context("Malformed kline")).transpose()?;
+        Ok(candle.filter(|c| c.open_time == time).map(|c| c.open))
+    }
+
+    async fn fetch(&self, symbol: &str) -> Result<Vec<Candle>> {
+        let url = format!("{}/api/v3/klines", self.cfg.spot_url.trim_end_matches('/'));
+        let limit = (self.cfg.lookback + 1).to_string();
+        let rows: Vec<Vec<Value>> = self
+            .client
+            .get(&url)
+            .query(&[("symbol", symbol), ("interval", "15m"), ("limit", limit.as_str())])
+            .send()
+            .await
+            .context("Klines request failed")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Invalid klines response")?;
+        rows.iter().map(|row| parse_kline(row).context("Malformed kline")).collect()
+    }
+}
+
+/// `[open time ms, "open", "high", "low", "close", ...]`
+fn parse_kline(row: &[Value]) -> Option<Candle> {
+    let number = |i: usize| row.get(i)?.as_str()?.parse::<f64>().ok();
+    Some(Candle {
+        open_time: row.first()?.as_i64()? / 1000,
+        open: number(1)?,
+        high: number(2)?,
+        low: number(3)?,
+        close: number(4)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_candles_do_not_confirm_a_winner() {
+        let closed = |t: i64, range: f64| Candle { open_time: t, open: 100.0, high: 100.0 + range, low: 100.0, close: 100.0 };
+        let mut candles = vec![closed(0, 10.0), closed(900, 20.0), closed(1800, 40.0)];
+        let parsed = parse_kline(&serde_json::from_str::<Vec<Value>>(r#"[2700000, "100.0", "107.0", "99.0", "106.0", "1.5"]"#).unwrap());
+        candles.push(parsed.unwrap());
+
+        // 6 up against a typical range of 20
+        let progress = candle_progress(&candles, 2700);
+        assert!((progress.unwrap() - 0.3).abs() < 1e-9);
+        assert!(confirms(0.3, progress, true));
+        assert!(!confirms(0.3, progress, false));
+        assert!(!confirms(0.5, progress, true));
+        // Stale feed: the newest candle is from another period
+        assert_eq!(candle_progress(&candles, 3600), None);
+        assert!(!confirms(0.3, None, true));
+    }
+}
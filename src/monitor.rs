@@ -0,0 +1,268 @@
+use crate::api::PolymarketApi;
+use crate::bookdiff::BookEncoder;
+use crate::config::{Config, MarketDuration, MonitorConfig};
+use crate::discovery::MarketDiscovery;
+use crate::feed::{MarketFeed, Quote};
+use crate::metrics::metrics;
+use crate::models::{MarketSnapshot, OrderBook, PriceLevel, TokenSnapshot};
+use crate::scheduler::{PeriodEvent, PeriodEventKind, PeriodScheduler};
+use crate::watchdog::health;
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::error::TryRecvError;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration};
+
+/// Name the recorder reports its liveness under.
+pub const RECORDER: &str = "recorder";
+
+/// Builds `MarketSnapshot`s (quoted price, top of book and depth) for the 15m markets and,
+/// in recorder mode, writes them out for the backtester.
+pub struct MarketMonitor {
+    api: Arc<PolymarketApi>,
+    feed: Arc<MarketFeed>,
+    discovery: MarketDiscovery,
+    config: MonitorConfig,
+    /// token_id -> last tick size its book reported, kept for snapshots taken without a book
+    tick_sizes: Mutex<HashMap<String, f64>>,
+}
+
+impl MarketMonitor {
+    pub fn new(api: Arc<PolymarketApi>, feed: Arc<MarketFeed>, config: &Config) -> Self {
+        let discovery = MarketDiscovery::new(api.clone(), config);
+        Self { api, feed, discovery, config: config.monitor.clone(), tick_sizes: Mutex::new(HashMap::new()) }
+    }
+
+    pub async fn snapshot(&self, asset: &str, period_start: i64) -> Result<MarketSnapshot> {
+        let market = self.discovery.find_market(asset, MarketDuration::M15, period_start).await?;
+        let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&market.condition_id).await?;
+        let tokens = [up_token_id.clone(), down_token_id.clone()];
+        let (up_price, down_price, up_quote, down_quote, books) = tokio::join!(
+            self.feed.price(&up_token_id),
+            self.feed.price(&down_token_id),
+            self.feed.quote(&up_token_id),
+            self.feed.quote(&down_token_id),
+            self.feed.books(&tokens)
+        );
+        let (up_book, down_book) = match books {
+            Ok(mut books) => {
+                let down = books.pop();
+                (books.pop(), down)
+            }
+            Err(e) => {
+                log::debug!("Order books unavailable for {}: {}", asset, e);
+                (None, None)
+            }
+        };
+        let up_tick = self.tick_size(asset, "Up", &up_token_id, up_book.as_ref());
+        let down_tick = self.tick_size(asset, "Down", &down_token_id, down_book.as_ref());
+        let up = token_snapshot(&up_token_id, up_price?, up_quote, up_book.as_ref(), up_tick)?;
+        let down = token_snapshot(&down_token_id, down_price?, down_quote, down_book.as_ref(), down_tick)?;
+        let invalid = implausible_asks(&self.config, up.price, down.price)
+            .or_else(|| crossed("Up", &up))
+            .or_else(|| crossed("Down", &down));
+        if let Some(reason) = &invalid {
+            log::warn!("🧪 {} | Implausible snapshot, tagged invalid: {}", asset, reason);
+            metrics().inc_invalid_snapshot(asset);
+        }
+        Ok(MarketSnapshot {
+            timestamp_ms: crate::clock::now_ms(),
+            asset: asset.to_string(),
+            condition_id: market.condition_id,
+            period_start,
+            up,
+            down,
+            volume_24h: market.volume_24h,
+            invalid,
+        })
+    }
+
+    /// Current tick size of a token: from its book when there is one, which also refreshes the
+    /// order builder's rules, otherwise the last one seen. The tick narrows to 0.001 once a token
+    /// trades above 0.96 or below 0.04; a change is logged.
+    fn tick_size(&self, asset: &str, outcome: &str, token_id: &str, book: Option<&OrderBook>) -> Option<f64> {
+        let mut ticks = self.tick_sizes.lock().unwrap();
+        let Some(book) = book.filter(|b| b.tick_size.is_some()) else {
+            return ticks.get(token_id).copied();
+        };
+        let tick: f64 = self.api.observe_book(token_id, book).tick_size.to_string().parse().ok()?;
+        if let Some(old) = ticks.insert(token_id.to_string(), tick).filter(|old| *old != tick) {
+            log::info!("📏 {} {} tick size changed from {} to {}", asset, outcome, old, tick);
+        }
+        Some(tick)
+    }
+
+    /// Recorder mode: snapshot every asset's current market each interval until the process
+    /// exits. Periods come from `scheduler`; a file is finished when its period rolls over.
+    pub async fn run_recorder(&self, config: MonitorConfig, assets: Vec<String>, scheduler: &PeriodScheduler, mut shutdown: watch::Receiver<bool>) {
+        let mut recorder = SnapshotRecorder::new(config.snapshot_dir.clone(), config.full_book_every);
+        let mut rollovers = scheduler.subscribe();
+        eprintln!("🎥 Recording snapshots to {} every {}ms", config.snapshot_dir.display(), config.snapshot_interval_ms);
+        health().watch(RECORDER, &assets);
+        while !*shutdown.borrow() {
+            // Markets are snapshotted concurrently so a slow one doesn't delay the rest
+            let due: Vec<(&String, i64)> =
+                assets.iter().filter_map(|a| Some((a, scheduler.current(a, MarketDuration::M15)?))).collect();
+            let snapshots = futures_util::future::join_all(due.iter().map(|(asset, period_start)| self.snapshot(asset, *period_start))).await;
+            for ((asset, period_start), snapshot) in due.into_iter().zip(snapshots) {
+                match snapshot {
+                    Ok(snapshot) => {
+                        health().snapshot(RECORDER, asset);
+                        if let Err(e) = recorder.write(&snapshot) {
+                            log::warn!("Failed to record {} snapshot: {}", asset, e);
+                        }
+                    }
+                    Err(e) => {
+                        log::debug!("No {} snapshot for period {}: {}", asset, period_start, e);
+                        // An unlisted market isn't the recorder's fault; restarting it won't help
+                        if self.discovery.missing(asset, MarketDuration::M15, period_start).is_some() {
+                            health().snapshot(RECORDER, asset);
+                        }
+                    }
+                }
+            }
+            loop {
+                match rollovers.try_recv() {
+                    Ok(PeriodEvent { timeframe: MarketDuration::M15, kind: PeriodEventKind::Started, period_start, .. }) => {
+                        recorder.close_before(period_start);
+                    }
+                    Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+                    Err(_) => break,
+                }
+            }
+            tokio::select! {
+                _ = sleep(Duration::from_millis(config.snapshot_interval_ms)) => {}
+                _ = shutdown.changed() => break,
+            }
+        }
+        // Dropping the recorder finishes the open gzip files
+    }
+}
+
+/// Why Up and Down asks can't come from a real market: a zero ask, or a sum outside
+/// `[min_ask_sum, max_ask_sum]` (the two outcomes pay $1 between them).
+pub fn implausible_asks(config: &MonitorConfig, up_ask: f64, down_ask: f64) -> Option<String> {
+    if up_ask <= 0.0 || down_ask <= 0.0 {
+        return Some(format!("zero ask (Up {}, Down {})", up_ask, down_ask));
+    }
+    let sum = up_ask + down_ask;
+    (sum < config.min_ask_sum || sum > config.max_ask_sum).then(|| {
+        format!("Up + Down asks sum to {:.3}, outside {}-{}", sum, config.min_ask_sum, config.max_ask_sum)
+    })
+}
+
+/// A token quoting or booking its ask below its bid.
+fn crossed(outcome: &str, token: &TokenSnapshot) -> Option<String> {
+    [(Some(token.price), token.bid), (token.best_ask, token.best_bid)]
+        .into_iter()
+        .find_map(|pair| match pair {
+            (Some(ask), Some(bid)) if ask < bid => Some(format!("{} ask {} below bid {}", outcome, ask, bid)),
+            _ => None,
+        })
+}
+
+/// One side of a snapshot; a missing book leaves the depth empty.
+fn token_snapshot(
+    token_id: &str,
+    price: rust_decimal::Decimal,
+    quote: Quote,
+    book: Option<&OrderBook>,
+    tick_size: Option<f64>,
+) -> Result<TokenSnapshot> {
+    let to_f64 = |d: rust_decimal::Decimal| d.to_string().parse::<f64>().ok();
+    let price: f64 = price.to_string().parse()?;
+    let bid = quote.bid.and_then(to_f64);
+    let (bids, asks) = book.map(book_levels).unwrap_or_default();
+    Ok(TokenSnapshot {
+        token_id: token_id.to_string(),
+        price,
+        best_bid: bids.first().map(|l| l.price),
+        best_ask: asks.first().map(|l| l.price),
+        bid,
+        mid: bid.map(|bid| (bid + price) / 2.0),
+        last_trade_price: quote.last_trade.and_then(to_f64),
+        bids,
+        asks,
+        tick_size,
+    })
+}
+
+/// Bids best-first (highest price), asks best-first (lowest price).
+pub fn book_levels(book: &OrderBook) -> (Vec<PriceLevel>, Vec<PriceLevel>) {
+    let to_level = |e: &crate::models::OrderBookEntry| PriceLevel {
+        price: e.price.to_string().parse().unwrap_or(0.0),
+        size: e.size.to_string().parse().unwrap_or(0.0),
+    };
+    let mut bids: Vec<PriceLevel> = book.bids.iter().map(to_level).collect();
+    let mut asks: Vec<PriceLevel> = book.asks.iter().map(to_level).collect();
+    bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+    asks.sort_by(|a, b| a.price.total_cmp(&b.price));
+    (bids, asks)
+}
+
+/// Gzip NDJSON writer partitioned as `{dir}/{asset}/{period_start}.ndjson.gz`.
+/// Reopening an existing file appends a new gzip member, which multi-member readers handle.
+/// Books are written in full every `full_book_every` lines and as changes in between (see
+/// `bookdiff`); each writer starts with a full one.
+pub struct SnapshotRecorder {
+    dir: PathBuf,
+    full_book_every: u32,
+    writers: HashMap<(String, i64), (GzEncoder<std::fs::File>, BookEncoder)>,
+}
+
+impl SnapshotRecorder {
+    pub fn new(dir: PathBuf, full_book_every: u32) -> Self {
+        Self { dir, full_book_every, writers: HashMap::new() }
+    }
+
+    pub fn path_for(dir: &Path, asset: &str, period_start: i64) -> PathBuf {
+        dir.join(asset).join(format!("{}.ndjson.gz", period_start))
+    }
+
+    pub fn write(&mut self, snapshot: &MarketSnapshot) -> Result<()> {
+        let key = (snapshot.asset.clone(), snapshot.period_start);
+        if !self.writers.contains_key(&key) {
+            let path = Self::path_for(&self.dir, &snapshot.asset, snapshot.period_start);
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?;
+            self.writers.insert(key.clone(), (GzEncoder::new(file, Compression::default()), BookEncoder::new(self.full_book_every)));
+        }
+        let (writer, books) = self.writers.get_mut(&key).unwrap();
+        serde_json::to_writer(&mut *writer, &books.encode(snapshot))?;
+        writer.write_all(b"\n")?;
+        // Sync-flush so a crash loses at most the gzip trailer; the loader tolerates that
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Finishes files for periods that started before `period_start`.
+    pub fn close_before(&mut self, period_start: i64) {
+        let done: Vec<(String, i64)> = self.writers.keys().filter(|(_, p)| *p < period_start).cloned().collect();
+        for key in done {
+            if let Some((writer, _)) = self.writers.remove(&key) {
+                if let Err(e) = writer.finish() {
+                    log::warn!("Failed to finish snapshot file for {} {}: {}", key.0, key.1, e);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for SnapshotRecorder {
+    fn drop(&mut self) {
+        for (_, (writer, _)) in self.writers.drain() {
+            let _ = writer.finish();
+        }
+    }
+}
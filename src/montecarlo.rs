@@ -0,0 +1,120 @@
+use crate::ledger::LedgerEntry;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone)]
+pub struct MonteCarloSettings {
+    pub runs: usize,
+    /// Periods per simulated path; defaults to the number of resolved periods in the ledger
+    pub periods: Option<usize>,
+    /// Equity at which a path counts as ruined
+    pub bankroll: f64,
+    /// Current `strategy.shares`; ledger PnL is rescaled from the size actually traded
+    pub shares: f64,
+    pub seed: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct MonteCarloReport {
+    pub observed_periods: usize,
+    pub periods_per_run: usize,
+    pub runs: usize,
+    pub mean_period_pnl: f64,
+    pub pnl: Percentiles,
+    pub max_drawdown: Percentiles,
+    pub risk_of_ruin: f64,
+    pub bankroll: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Percentiles {
+    pub p5: f64,
+    pub p50: f64,
+    pub p95: f64,
+}
+
+impl Percentiles {
+    fn from_sorted(values: &[f64]) -> Self {
+        let at = |q: f64| values[((values.len() - 1) as f64 * q).round() as usize];
+        Self { p5: at(0.05), p50: at(0.50), p95: at(0.95) }
+    }
+}
+
+/// Realized PnL per share for every period in the ledger that has a sell or resolution row.
+/// Each value carries that period's outcome and fill prices together, so resampling them keeps
+/// the two consistent.
+pub fn period_pnl_per_share(entries: &[LedgerEntry]) -> Vec<f64> {
+    let mut periods: BTreeMap<(String, i64), (f64, f64, bool)> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| e.kind != "rollup") {
+        let (pnl, size, realized) = periods
+            .entry((entry.condition_id.clone(), entry.period_timestamp))
+            .or_insert((0.0, 0.0, false));
+        if entry.kind == "buy" {
+            *size = f64::max(*size, entry.size);
+        }
+        if let Some(p) = entry.pnl {
+            *pnl += p;
+            *realized = true;
+        }
+    }
+    periods
+        .into_values()
+        .filter(|(_, size, realized)| *realized && *size > 0.0)
+        .map(|(pnl, size, _)| pnl / size)
+        .collect()
+}
+
+/// Bootstraps PnL paths by resampling resolved periods with replacement.
+pub fn run(samples: &[f64], settings: &MonteCarloSettings) -> Option<MonteCarloReport> {
+    if samples.is_empty() || settings.runs == 0 {
+        return None;
+    }
+    let periods = settings.periods.unwrap_or(samples.len()).max(1);
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+    let mut finals = Vec::with_capacity(settings.runs);
+    let mut drawdowns = Vec::with_capacity(settings.runs);
+    let mut ruined = 0usize;
+
+    for _ in 0..settings.runs {
+        let (mut equity, mut peak, mut max_dd, mut is_ruined) = (0.0, 0.0, 0.0, false);
+        for _ in 0..periods {
+            equity += samples[rng.gen_range(0..samples.len())] * settings.shares;
+            peak = f64::max(peak, equity);
+            max_dd = f64::max(max_dd, peak - equity);
+            if equity <= -settings.bankroll {
+                is_ruined = true;
+            }
+        }
+        finals.push(equity);
+        drawdowns.push(max_dd);
+        if is_ruined {
+            ruined += 1;
+        }
+    }
+    finals.sort_by(f64::total_cmp);
+    drawdowns.sort_by(f64::total_cmp);
+
+    Some(MonteCarloReport {
+        observed_periods: samples.len(),
+        periods_per_run: periods,
+        runs: settings.runs,
+        mean_period_pnl: samples.iter().sum::<f64>() / samples.len() as f64 * settings.shares,
+        pnl: Percentiles::from_sorted(&finals),
+        max_drawdown: Percentiles::from_sorted(&drawdowns),
+        risk_of_ruin: ruined as f64 / settings.runs as f64,
+        bankroll: settings.bankroll,
+    })
+}
+
+impl MonteCarloReport {
+    pub fn print(&self) {
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("🎲 Monte Carlo ({} runs × {} periods, from {} resolved periods)", self.runs, self.periods_per_run, self.observed_periods);
+        eprintln!("   mean PnL per period   ${:.2}", self.mean_period_pnl);
+        eprintln!("   PnL        P5 ${:.2} | P50 ${:.2} | P95 ${:.2}", self.pnl.p5, self.pnl.p50, self.pnl.p95);
+        eprintln!("   max DD     P5 ${:.2} | P50 ${:.2} | P95 ${:.2}", self.max_drawdown.p5, self.max_drawdown.p50, self.max_drawdown.p95);
+        eprintln!("   risk of ruin (-${:.0})  {:.2}%", self.bankroll, self.risk_of_ruin * 100.0);
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    }
+}
@@ -0,0 +1,442 @@
+use crate::backtest::{self, BacktestReport, BacktestSettings, PreLimitModel};
+use crate::compare::period_et;
+use crate::config::StrategyConfig;
+use crate::models::MarketSnapshot;
+use anyhow::{Context, Result};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Strategy settings that can be swept, by their config.json path under `strategy`.
+pub const PARAMS: &[&str] = &[
+    "price_limit",
+    "shares",
+    "place_order_before_mins",
+    "sell_opposite_above",
+    "sell_opposite_time_remaining",
+    "signal.stable_min",
+    "signal.stable_max",
+    "signal.clear_threshold",
+    "signal.clear_remaining_mins",
+    "signal.danger_price",
+    "signal.danger_time_passed",
+    "signal.trend.window",
+    "signal.trend.threshold",
+    "signal.trend.min_t_stat",
+];
+
+/// Candidate values for one parameter, parsed from `name=v1,v2,...` or `name=start:end:step`.
+#[derive(Debug, Clone)]
+pub struct ParamRange {
+    pub name: String,
+    pub values: Vec<f64>,
+}
+
+impl ParamRange {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, values) = spec
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid --param '{}': expected name=values", spec))?;
+        let name = name.trim().to_string();
+        if !PARAMS.contains(&name.as_str()) {
+            anyhow::bail!("Unknown parameter '{}'. Supported: {}", name, PARAMS.join(", "));
+        }
+        let values = if let [start, end, step] = values.split(':').collect::<Vec<_>>()[..] {
+            let (start, end, step): (f64, f64, f64) = (start.trim().parse()?, end.trim().parse()?, step.trim().parse()?);
+            if step <= 0.0 || end < start {
+                anyhow::bail!("Invalid range for {}: start:end:step needs end >= start and step > 0", name);
+            }
+            let n = ((end - start) / step + 1e-9).floor() as usize;
+            (0..=n).map(|i| round4(start + i as f64 * step)).collect()
+        } else {
+            values
+                .split(',')
+                .map(|v| v.trim().parse::<f64>().context(format!("Invalid value '{}' for {}", v, name)))
+                .collect::<Result<Vec<_>>>()?
+        };
+        if values.is_empty() {
+            anyhow::bail!("No values given for {}", name);
+        }
+        Ok(Self { name, values })
+    }
+}
+
+fn round4(v: f64) -> f64 {
+    (v * 10_000.0).round() / 10_000.0
+}
+
+fn apply(cfg: &mut StrategyConfig, name: &str, value: f64) {
+    match name {
+        "price_limit" => cfg.price_limit = value,
+        "shares" => cfg.shares = value,
+        "place_order_before_mins" => cfg.place_order_before_mins = value as u64,
+        "sell_opposite_above" => cfg.sell_opposite_above = value,
+        "sell_opposite_time_remaining" => cfg.sell_opposite_time_remaining = value as u64,
+        "signal.stable_min" => cfg.signal.stable_min = value,
+        "signal.stable_max" => cfg.signal.stable_max = value,
+        "signal.clear_threshold" => cfg.signal.clear_threshold = value,
+        "signal.clear_remaining_mins" => cfg.signal.clear_remaining_mins = value as u64,
+        "signal.danger_price" => cfg.signal.danger_price = value,
+        "signal.danger_time_passed" => cfg.signal.danger_time_passed = value as u64,
+        "signal.trend.window" => cfg.signal.trend.window = value as usize,
+        "signal.trend.threshold" => cfg.signal.trend.threshold = value,
+        "signal.trend.min_t_stat" => cfg.signal.trend.min_t_stat = value,
+        _ => unreachable!("parameter names are validated by ParamRange::parse"),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SearchMode {
+    Grid,
+    /// Sample this many distinct combinations from the grid
+    Random { samples: usize, seed: u64 },
+}
+
+pub struct Trial {
+    pub params: Vec<(String, f64)>,
+    pub report: BacktestReport,
+}
+
+/// Backtests every combination (grid) or a random sample of them against `snapshots`.
+/// Trials come back sorted by total PnL, best first.
+pub fn run(
+    base: &StrategyConfig,
+    ranges: &[ParamRange],
+    mode: SearchMode,
+    snapshots: &mut [MarketSnapshot],
+) -> Vec<Trial> {
+    let combinations: Vec<Vec<f64>> = match mode {
+        SearchMode::Grid => grid(ranges),
+        SearchMode::Random { samples, seed } => {
+            let total: usize = ranges.iter().map(|r| r.values.len()).product();
+            if samples >= total {
+                grid(ranges)
+            } else {
+                let mut rng = StdRng::seed_from_u64(seed);
+                rand::seq::index::sample(&mut rng, total, samples)
+                    .into_iter()
+                    .map(|index| combination_at(ranges, index))
+                    .collect()
+            }
+        }
+    };
+
+    snapshots.sort_by_key(|s| s.timestamp_ms);
+    let mut trials: Vec<Trial> = combinations
+        .into_iter()
+        .map(|values| {
+            let params: Vec<(String, f64)> = ranges.iter().map(|r| r.name.clone()).zip(values).collect();
+            let report = evaluate(base, &params, snapshots);
+            Trial { params, report }
+        })
+        .collect();
+    trials.sort_by(|a, b| b.report.total_pnl.total_cmp(&a.report.total_pnl));
+    trials
+}
+
+/// Backtests `base` with `params` applied against time-sorted `snapshots`.
+fn evaluate(base: &StrategyConfig, params: &[(String, f64)], snapshots: &mut [MarketSnapshot]) -> BacktestReport {
+    let mut cfg = base.clone();
+    for (name, value) in params {
+        apply(&mut cfg, name, *value);
+    }
+    let settings = BacktestSettings {
+        fill_model: cfg.fill_model.clone(),
+        fees: cfg.fees.clone(),
+        slippage_tolerance: cfg.slippage_tolerance,
+    };
+    backtest::run(&mut PreLimitModel::new(cfg), snapshots, &settings)
+}
+
+/// The `index`-th grid combination, counting with the last parameter varying fastest.
+fn combination_at(ranges: &[ParamRange], mut index: usize) -> Vec<f64> {
+    let mut combo = vec![0.0; ranges.len()];
+    for (slot, range) in combo.iter_mut().zip(ranges).rev() {
+        *slot = range.values[index % range.values.len()];
+        index /= range.values.len();
+    }
+    combo
+}
+
+fn grid(ranges: &[ParamRange]) -> Vec<Vec<f64>> {
+    ranges.iter().fold(vec![Vec::new()], |acc, range| {
+        acc.iter()
+            .flat_map(|prefix| {
+                range.values.iter().map(move |v| {
+                    let mut combo = prefix.clone();
+                    combo.push(*v);
+                    combo
+                })
+            })
+            .collect()
+    })
+}
+
+pub fn print_trials(trials: &[Trial], top: usize) {
+    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    eprintln!("🔬 Optimization: {} combinations (top {} by PnL)", trials.len(), top.min(trials.len()));
+    for (rank, trial) in trials.iter().take(top).enumerate() {
+        let params: Vec<String> = trial.params.iter().map(|(n, v)| format!("{}={}", n, v)).collect();
+        eprintln!(
+            "   #{:<3} PnL ${:>9.2}  DD ${:>8.2}  win {:>5.1}%  trades {:>4}  {}",
+            rank + 1,
+            trial.report.total_pnl,
+            trial.report.max_drawdown,
+            trial.report.win_rate() * 100.0,
+            trial.report.periods_traded,
+            params.join(" ")
+        );
+    }
+    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+}
+
+/// One CSV row per trial: the parameter values followed by the report metrics.
+pub fn write_csv(trials: &[Trial], output: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(output).context(format!("Failed to create {}", output.display()))?;
+    if let Some(first) = trials.first() {
+        let mut header: Vec<String> = first.params.iter().map(|(n, _)| n.clone()).collect();
+        header.extend(["total_pnl", "max_drawdown", "win_rate", "periods_traded", "both_filled", "one_side_exits"].map(String::from));
+        writer.write_record(&header)?;
+    }
+    for trial in trials {
+        let mut row: Vec<String> = trial.params.iter().map(|(_, v)| v.to_string()).collect();
+        let r = &trial.report;
+        row.extend([
+            format!("{:.4}", r.total_pnl),
+            format!("{:.4}", r.max_drawdown),
+            format!("{:.4}", r.win_rate()),
+            r.periods_traded.to_string(),
+            r.both_filled.to_string(),
+            r.one_side_exits.to_string(),
+        ]);
+        writer.write_record(&row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// One walk-forward step: the best combination on a training window, and how it and the base
+/// config did on the window right after it.
+pub struct Fold {
+    /// First and last period start of each window
+    pub train: (i64, i64),
+    pub test: (i64, i64),
+    /// Best combination in the training window, with its in-sample report
+    pub best: Trial,
+    pub out_of_sample: BacktestReport,
+    /// The base config, unswept, on the test window
+    pub base: BacktestReport,
+}
+
+/// Walk-forward analysis: splits the recorded periods into `windows` consecutive windows of
+/// equal period count, optimizes on each window but the last and evaluates the winner on the
+/// next one, so every test window is unseen by the search that chose its parameters.
+pub fn walk_forward(
+    base: &StrategyConfig,
+    ranges: &[ParamRange],
+    mode: SearchMode,
+    snapshots: Vec<MarketSnapshot>,
+    windows: usize,
+) -> Result<Vec<Fold>> {
+    let mut windows = split_windows(snapshots, windows)?;
+    let mut folds = Vec::new();
+    for i in 0..windows.len() - 1 {
+        let (head, tail) = windows.split_at_mut(i + 1);
+        let (train, test) = (&mut head[i], &mut tail[0]);
+        let best = run(base, ranges, mode, train).into_iter().next().context("no combinations to try")?;
+        let out_of_sample = evaluate(base, &best.params, test);
+        folds.push(Fold {
+            train: period_span(train),
+            test: period_span(test),
+            base: evaluate(base, &[], test),
+            best,
+            out_of_sample,
+        });
+    }
+    Ok(folds)
+}
+
+/// `snapshots` grouped by period into `windows` consecutive windows, each sorted by time. A
+/// period's snapshots always land in the same window.
+fn split_windows(mut snapshots: Vec<MarketSnapshot>, windows: usize) -> Result<Vec<Vec<MarketSnapshot>>> {
+    anyhow::ensure!(windows >= 2, "walk-forward needs at least 2 windows (got {})", windows);
+    let periods: Vec<i64> = snapshots.iter().map(|s| s.period_start).collect::<BTreeSet<_>>().into_iter().collect();
+    anyhow::ensure!(
+        periods.len() >= windows,
+        "{} recorded period(s) can't be split into {} walk-forward windows",
+        periods.len(),
+        windows
+    );
+    snapshots.sort_by_key(|s| s.timestamp_ms);
+    let mut split: Vec<Vec<MarketSnapshot>> = (0..windows).map(|_| Vec::new()).collect();
+    for snapshot in snapshots {
+        let index = periods.binary_search(&snapshot.period_start).unwrap();
+        split[index * windows / periods.len()].push(snapshot);
+    }
+    Ok(split)
+}
+
+fn period_span(snapshots: &[MarketSnapshot]) -> (i64, i64) {
+    let starts = snapshots.iter().map(|s| s.period_start);
+    (starts.clone().min().unwrap_or(0), starts.max().unwrap_or(0))
+}
+
+/// Out-of-sample totals over all folds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WalkForwardSummary {
+    pub folds: usize,
+    pub profitable_folds: usize,
+    pub in_sample_pnl: f64,
+    pub out_of_sample_pnl: f64,
+    pub base_pnl: f64,
+    /// Out-of-sample PnL as a share of in-sample PnL; `None` unless the in-sample PnL is positive
+    pub efficiency: Option<f64>,
+    /// Max drawdown of the test windows' periods played back to back
+    pub max_drawdown: f64,
+    pub wins: usize,
+    pub losses: usize,
+}
+
+impl WalkForwardSummary {
+    pub fn new(folds: &[Fold]) -> Self {
+        let in_sample_pnl: f64 = folds.iter().map(|f| f.best.report.total_pnl).sum();
+        let out_of_sample_pnl: f64 = folds.iter().map(|f| f.out_of_sample.total_pnl).sum();
+        let (mut equity, mut peak, mut max_drawdown) = (0.0, 0.0, 0.0);
+        for period in folds.iter().flat_map(|f| &f.out_of_sample.periods) {
+            equity += period.pnl;
+            peak = f64::max(peak, equity);
+            max_drawdown = f64::max(max_drawdown, peak - equity);
+        }
+        Self {
+            folds: folds.len(),
+            profitable_folds: folds.iter().filter(|f| f.out_of_sample.total_pnl > 0.0).count(),
+            in_sample_pnl,
+            out_of_sample_pnl,
+            base_pnl: folds.iter().map(|f| f.base.total_pnl).sum(),
+            efficiency: (in_sample_pnl > 0.0).then(|| out_of_sample_pnl / in_sample_pnl),
+            max_drawdown,
+            wins: folds.iter().map(|f| f.out_of_sample.wins).sum(),
+            losses: folds.iter().map(|f| f.out_of_sample.losses).sum(),
+        }
+    }
+}
+
+pub fn print_walk_forward(folds: &[Fold]) {
+    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    eprintln!("🚶 Walk-forward: {} folds", folds.len());
+    for (i, fold) in folds.iter().enumerate() {
+        let params: Vec<String> = fold.best.params.iter().map(|(n, v)| format!("{}={}", n, v)).collect();
+        eprintln!(
+            "   #{:<3} train {} → {}  test {} → {}",
+            i + 1,
+            period_et(fold.train.0),
+            period_et(fold.train.1),
+            period_et(fold.test.0),
+            period_et(fold.test.1)
+        );
+        eprintln!(
+            "        in ${:>9.2}  out ${:>9.2}  base ${:>9.2}  out DD ${:>8.2}  {}",
+            fold.best.report.total_pnl,
+            fold.out_of_sample.total_pnl,
+            fold.base.total_pnl,
+            fold.out_of_sample.max_drawdown,
+            params.join(" ")
+        );
+    }
+    let summary = WalkForwardSummary::new(folds);
+    let win_rate = if summary.wins + summary.losses == 0 { 0.0 } else { summary.wins as f64 / (summary.wins + summary.losses) as f64 };
+    eprintln!("📈 Out of sample");
+    eprintln!("   total PnL             ${:.2} (base config ${:.2})", summary.out_of_sample_pnl, summary.base_pnl);
+    eprintln!("   in-sample PnL         ${:.2}", summary.in_sample_pnl);
+    match summary.efficiency {
+        Some(efficiency) => eprintln!("   efficiency             {:.0}% of in-sample", efficiency * 100.0),
+        None => eprintln!("   efficiency             -"),
+    }
+    eprintln!("   profitable folds       {} / {}", summary.profitable_folds, summary.folds);
+    eprintln!("   wins / losses          {} / {} ({:.1}%)", summary.wins, summary.losses, win_rate * 100.0);
+    eprintln!("   max drawdown          ${:.2}", summary.max_drawdown);
+    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+}
+
+/// One CSV row per fold: the windows, the chosen parameter values and the in- and out-of-sample metrics.
+pub fn write_walk_forward_csv(folds: &[Fold], output: &Path) -> Result<()> {
+    let mut writer = csv::Writer::from_path(output).context(format!("Failed to create {}", output.display()))?;
+    if let Some(first) = folds.first() {
+        let mut header: Vec<String> = ["train_start", "train_end", "test_start", "test_end"].map(String::from).to_vec();
+        header.extend(first.best.params.iter().map(|(n, _)| n.clone()));
+        header.extend(["in_sample_pnl", "out_of_sample_pnl", "base_pnl", "out_of_sample_max_drawdown", "out_of_sample_win_rate", "out_of_sample_periods_traded"].map(String::from));
+        writer.write_record(&header)?;
+    }
+    for fold in folds {
+        let mut row: Vec<String> = [fold.train.0, fold.train.1, fold.test.0, fold.test.1].map(|t| t.to_string()).to_vec();
+        row.extend(fold.best.params.iter().map(|(_, v)| v.to_string()));
+        let r = &fold.out_of_sample;
+        row.extend([
+            format!("{:.4}", fold.best.report.total_pnl),
+            format!("{:.4}", r.total_pnl),
+            format!("{:.4}", fold.base.total_pnl),
+            format!("{:.4}", r.max_drawdown),
+            format!("{:.4}", r.win_rate()),
+            r.periods_traded.to_string(),
+        ]);
+        writer.write_record(&row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::PeriodResult;
+
+    fn snapshot(timestamp_ms: i64, period_start: i64) -> MarketSnapshot {
+        serde_json::from_value(serde_json::json!({
+            "timestamp_ms": timestamp_ms, "asset": "BTC", "condition_id": "0xa", "period_start": period_start,
+            "up": { "token_id": "up", "price": 0.5 }, "down": { "token_id": "down", "price": 0.5 },
+        }))
+        .unwrap()
+    }
+
+    fn report(pnls: &[f64]) -> BacktestReport {
+        let periods: Vec<PeriodResult> = pnls
+            .iter()
+            .enumerate()
+            .map(|(i, &pnl)| PeriodResult { asset: "BTC".to_string(), period_start: i as i64 * 900, pnl, both_filled: true, exited: false })
+            .collect();
+        BacktestReport {
+            wins: pnls.iter().filter(|p| **p > 0.0).count(),
+            losses: pnls.iter().filter(|p| **p <= 0.0).count(),
+            total_pnl: pnls.iter().sum(),
+            periods,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn windows_keep_periods_together_and_folds_sum_out_of_sample() {
+        // Five periods, the last recorded out of order
+        let snapshots: Vec<MarketSnapshot> = [0, 900, 1800, 2700, 0, 3600, 900].iter().enumerate().map(|(i, &p)| snapshot(p * 1000 + i as i64, p)).collect();
+        let windows = split_windows(snapshots.clone(), 2).unwrap();
+        let periods = |w: &[MarketSnapshot]| w.iter().map(|s| s.period_start).collect::<BTreeSet<_>>().into_iter().collect::<Vec<_>>();
+        assert_eq!(periods(&windows[0]), [0, 900, 1800]);
+        assert_eq!(periods(&windows[1]), [2700, 3600]);
+        assert_eq!(windows[0].len(), 5);
+        assert!(split_windows(snapshots.clone(), 6).is_err() && split_windows(snapshots, 1).is_err());
+
+        let fold = |in_sample: &[f64], out: &[f64]| Fold {
+            train: (0, 0),
+            test: (0, 0),
+            best: Trial { params: vec![("price_limit".to_string(), 0.45)], report: report(in_sample) },
+            out_of_sample: report(out),
+            base: report(&[-1.0]),
+        };
+        let summary = WalkForwardSummary::new(&[fold(&[4.0], &[1.0, -3.0]), fold(&[4.0], &[2.0, 1.0])]);
+        assert_eq!(summary.out_of_sample_pnl, 1.0);
+        assert_eq!(summary.efficiency, Some(0.125));
+        assert_eq!((summary.profitable_folds, summary.wins, summary.losses), (1, 3, 1));
+        assert_eq!(summary.max_drawdown, 3.0);
+        assert_eq!(summary.base_pnl, -2.0);
+    }
+}
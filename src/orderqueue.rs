@@ -0,0 +1,166 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// What an order does to the account's risk; the earlier variant goes first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OrderKind {
+    /// Sells that lock in or cut a position
+    Lock,
+    /// Buys that open or add to a position
+    Expansion,
+}
+
+/// Place of an order in the submission queue: locks before expansions, then the market that
+/// ends first, then first come.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderPriority {
+    pub kind: OrderKind,
+    /// Unix seconds the order's market ends
+    pub deadline: i64,
+}
+
+impl OrderPriority {
+    pub fn lock(deadline: i64) -> Self {
+        Self { kind: OrderKind::Lock, deadline }
+    }
+
+    pub fn expansion(deadline: i64) -> Self {
+        Self { kind: OrderKind::Expansion, deadline }
+    }
+}
+
+#[derive(PartialEq, Eq)]
+struct Ticket {
+    priority: OrderPriority,
+    seq: u64,
+}
+
+impl Ord for Ticket {
+    // The heap pops its greatest entry, so the most urgent ticket compares greatest
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |t: &Ticket| (t.priority.kind, t.priority.deadline, t.seq);
+        key(other).cmp(&key(self))
+    }
+}
+
+impl PartialOrd for Ticket {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    waiting: BinaryHeap<Ticket>,
+    in_flight: usize,
+    next_seq: u64,
+}
+
+/// Lets at most `slots` order submissions through a client at once and hands free slots to the
+/// most urgent waiting order, instead of whichever request the runtime happens to poll first.
+pub struct OrderQueue {
+    slots: usize,
+    state: Mutex<QueueState>,
+    notify: Notify,
+}
+
+/// A submission slot, given back when dropped.
+pub struct Slot<'a> {
+    queue: &'a OrderQueue,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        self.queue.state.lock().unwrap().in_flight -= 1;
+        self.queue.notify.notify_waiters();
+    }
+}
+
+/// A ticket still waiting; taken out of the queue if the wait is abandoned.
+struct Waiting<'a> {
+    queue: &'a OrderQueue,
+    seq: u64,
+    granted: bool,
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        if !self.granted {
+            self.queue.state.lock().unwrap().waiting.retain(|t| t.seq != self.seq);
+            self.queue.notify.notify_waiters();
+        }
+    }
+}
+
+impl OrderQueue {
+    pub fn new(slots: usize) -> Self {
+        Self { slots: slots.max(1), state: Mutex::new(QueueState::default()), notify: Notify::new() }
+    }
+
+    /// Waits for a slot, behind every more urgent order.
+    pub async fn acquire(&self, priority: OrderPriority) -> Slot<'_> {
+        let seq = {
+            let mut state = self.state.lock().unwrap();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiting.push(Ticket { priority, seq });
+            seq
+        };
+        let mut waiting = Waiting { queue: self, seq, granted: false };
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            // Registered before checking, so a slot freed in between still wakes us
+            notified.as_mut().enable();
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.in_flight < self.slots && state.waiting.peek().is_some_and(|t| t.seq == seq) {
+                    state.waiting.pop();
+                    state.in_flight += 1;
+                    waiting.granted = true;
+                    return Slot { queue: self };
+                }
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn hands_slots_to_locks_then_nearest_deadline() {
+        let queue = Arc::new(OrderQueue::new(1));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = queue.acquire(OrderPriority::expansion(0)).await;
+
+        let mut tasks = Vec::new();
+        for (name, priority) in [
+            ("early buy", OrderPriority::expansion(900)),
+            ("late buy", OrderPriority::expansion(300)),
+            ("early sell", OrderPriority::lock(900)),
+            ("late sell", OrderPriority::lock(300)),
+        ] {
+            let (queue, order) = (queue.clone(), order.clone());
+            tasks.push(tokio::spawn(async move {
+                let _slot = queue.acquire(priority).await;
+                order.lock().unwrap().push(name);
+            }));
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        // An abandoned wait gives up its place
+        let abandoned = tokio::time::timeout(std::time::Duration::from_millis(10), queue.acquire(OrderPriority::lock(0))).await;
+        assert!(abandoned.is_err());
+
+        drop(held);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), ["late sell", "early sell", "late buy", "early buy"]);
+    }
+}
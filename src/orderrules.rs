@@ -0,0 +1,95 @@
+use anyhow::Result;
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+
+/// Sizes are accepted to this many decimal places (the CLOB's lot size).
+const LOT_SIZE_DP: u32 = 2;
+
+/// A token's price tick and minimum order size, from its CLOB order book. The tick narrows
+/// from 0.01 to 0.001 once a token trades above 0.96 or below 0.04, so it is looked up per order.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrderRules {
+    pub tick_size: Decimal,
+    pub min_order_size: Decimal,
+}
+
+impl Default for OrderRules {
+    /// The usual rules for 15m markets, used when the book doesn't report them.
+    fn default() -> Self {
+        Self { tick_size: dec!(0.01), min_order_size: Decimal::ZERO }
+    }
+}
+
+impl OrderRules {
+    /// Snaps `price` onto the tick grid within [tick, 1 - tick]. `aggressive` rounds toward
+    /// crossing the spread (buys up, sells down), as market orders want; otherwise a buy never
+    /// pays more and a sell never asks less than requested.
+    pub fn snap_price(&self, price: Decimal, buy: bool, aggressive: bool) -> Decimal {
+        let strategy = if buy == aggressive { RoundingStrategy::ToPositiveInfinity } else { RoundingStrategy::ToNegativeInfinity };
+        let ticks = (price / self.tick_size).round_dp_with_strategy(0, strategy);
+        (ticks * self.tick_size)
+            .clamp(self.tick_size, Decimal::ONE - self.tick_size)
+            .round_dp(self.tick_size.scale())
+            .normalize()
+    }
+
+    /// Truncates `size` to the lot size and rejects it if that leaves less than the minimum.
+    pub fn snap_size(&self, size: Decimal) -> Result<Decimal> {
+        let snapped = size.round_dp_with_strategy(LOT_SIZE_DP, RoundingStrategy::ToZero).normalize();
+        if snapped <= Decimal::ZERO || snapped < self.min_order_size {
+            anyhow::bail!("Order size {} is below the market minimum of {} shares", size, self.min_order_size.normalize());
+        }
+        Ok(snapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FINE: OrderRules = OrderRules { tick_size: dec!(0.001), min_order_size: Decimal::ZERO };
+
+    #[test]
+    fn prices_round_in_favour_of_the_order_unless_aggressive() {
+        let rules = OrderRules::default();
+        assert_eq!(rules.snap_price(dec!(0.457), true, false), dec!(0.45));
+        assert_eq!(rules.snap_price(dec!(0.453), false, false), dec!(0.46));
+        assert_eq!(rules.snap_price(dec!(0.453), true, true), dec!(0.46));
+        assert_eq!(rules.snap_price(dec!(0.457), false, true), dec!(0.45));
+        // On the grid already: unchanged either way
+        assert_eq!(rules.snap_price(dec!(0.45), true, false), dec!(0.45));
+        assert_eq!(rules.snap_price(dec!(0.45), false, true), dec!(0.45));
+    }
+
+    #[test]
+    fn prices_stay_one_tick_inside_zero_and_one() {
+        let rules = OrderRules::default();
+        assert_eq!(rules.snap_price(dec!(0.004), true, false), dec!(0.01));
+        assert_eq!(rules.snap_price(Decimal::ZERO, false, true), dec!(0.01));
+        assert_eq!(rules.snap_price(dec!(0.996), false, false), dec!(0.99));
+        assert_eq!(rules.snap_price(dec!(1.2), true, true), dec!(0.99));
+        assert_eq!(FINE.snap_price(dec!(0.9996), true, true), dec!(0.999));
+        assert_eq!(FINE.snap_price(dec!(0.0002), false, false), dec!(0.001));
+    }
+
+    #[test]
+    fn the_fine_tick_keeps_a_third_decimal() {
+        assert_eq!(FINE.snap_price(dec!(0.9734), true, false), dec!(0.973));
+        assert_eq!(FINE.snap_price(dec!(0.9734), false, false), dec!(0.974));
+        assert_eq!(FINE.snap_price(dec!(0.0317), false, true), dec!(0.031));
+        assert_eq!(FINE.snap_price(dec!(0.0317), true, true), dec!(0.032));
+    }
+
+    #[test]
+    fn sizes_truncate_to_the_lot_and_respect_the_minimum() {
+        let rules = OrderRules { tick_size: dec!(0.01), min_order_size: dec!(5) };
+        assert_eq!(rules.snap_size(dec!(10.129)).unwrap(), dec!(10.12));
+        assert_eq!(rules.snap_size(dec!(5.999)).unwrap(), dec!(5.99));
+        assert_eq!(rules.snap_size(dec!(5)).unwrap(), dec!(5));
+        let error = rules.snap_size(dec!(4.999)).unwrap_err().to_string();
+        assert!(error.contains("below the market minimum of 5"), "{}", error);
+        // Nothing left after truncation is refused even without a minimum
+        assert!(OrderRules::default().snap_size(dec!(0.009)).is_err());
+        assert_eq!(OrderRules::default().snap_size(dec!(0.01)).unwrap(), dec!(0.01));
+    }
+}
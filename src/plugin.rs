@@ -0,0 +1,211 @@
+use crate::api::PolymarketApi;
+use crate::config::{Config, PluginConfig};
+use crate::external::Bias;
+use crate::feed::MarketFeed;
+use crate::models::MarketSnapshot;
+use crate::monitor::MarketMonitor;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+use tokio::time::{timeout, Duration};
+
+/// One line written to the plugin's stdin.
+#[derive(Debug, Serialize)]
+pub struct PluginRequest<'a> {
+    /// Unix seconds
+    pub time: i64,
+    pub account: Option<&'a str>,
+    pub seconds_remaining: i64,
+    /// Bias of a live signal posted to the control server for the market
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bias: Option<Bias>,
+    pub snapshot: &'a MarketSnapshot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginAction {
+    BuyUp,
+    BuyDown,
+    None,
+}
+
+/// One line read back from the plugin: what to buy, how many shares and at what limit price.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PluginReply {
+    pub action: PluginAction,
+    /// Shares; `strategy.shares` when left out
+    #[serde(default)]
+    pub size: Option<f64>,
+    /// Limit price; the side's quoted price when left out
+    #[serde(default)]
+    pub price: Option<f64>,
+}
+
+impl PluginReply {
+    const NONE: PluginReply = PluginReply { action: PluginAction::None, size: None, price: None };
+
+    fn check(self) -> Result<Self> {
+        if let Some(size) = self.size {
+            anyhow::ensure!(size.is_finite() && size > 0.0, "size must be positive (got {})", size);
+        }
+        if let Some(price) = self.price {
+            anyhow::ensure!(price > 0.0 && price < 1.0, "price must be within (0, 1) (got {})", price);
+        }
+        Ok(self)
+    }
+}
+
+struct Running {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+}
+
+/// Strategy plugin: an external command, started on first use, that is sent a snapshot of a
+/// market as a JSON line and answers with a `PluginReply` line. A late, malformed or failed
+/// answer counts as `none`, and the command is restarted on the next snapshot so a stuck or
+/// out-of-step plugin can't answer for the wrong market.
+pub struct Plugin {
+    cfg: PluginConfig,
+    monitor: MarketMonitor,
+    process: Mutex<Option<Running>>,
+    /// Asset -> when its last snapshot was sent
+    last_sent: std::sync::Mutex<HashMap<String, i64>>,
+}
+
+impl Plugin {
+    pub fn new(api: Arc<PolymarketApi>, feed: Arc<MarketFeed>, config: &Config) -> Self {
+        Self {
+            cfg: config.strategy.plugin.clone(),
+            monitor: MarketMonitor::new(api, feed, config),
+            process: Mutex::new(None),
+            last_sent: std::sync::Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether `asset` is due another snapshot at `now`.
+    pub fn due(&self, asset: &str, now: i64) -> bool {
+        self.last_sent.lock().unwrap().get(asset).is_none_or(|t| now - t >= self.cfg.interval_secs as i64)
+    }
+
+    /// Sends `asset`'s market for the period starting at `period_start` to the plugin and
+    /// returns the snapshot with the plugin's answer; `None` without a usable snapshot.
+    pub async fn decide(&self, asset: &str, period_start: i64, period_end: i64, account: Option<&str>, bias: Option<Bias>) -> Option<(MarketSnapshot, PluginReply)> {
+        let now = crate::clock::now();
+        self.last_sent.lock().unwrap().insert(asset.to_string(), now);
+        let snapshot = match self.monitor.snapshot(asset, period_start).await {
+            Ok(snapshot) if snapshot.invalid.is_none() => snapshot,
+            Ok(_) => return None,
+            Err(e) => {
+                log::debug!("{} | No snapshot for the plugin: {}", asset, e);
+                return None;
+            }
+        };
+        let request = PluginRequest { time: now, account, seconds_remaining: period_end - now, bias, snapshot: &snapshot };
+        let reply = self.ask(&request).await;
+        Some((snapshot, reply))
+    }
+
+    /// The plugin's answer to `request`, or `none` when it can't give a valid one in time.
+    pub async fn ask(&self, request: &PluginRequest<'_>) -> PluginReply {
+        let mut process = self.process.lock().await;
+        let result = async {
+            if process.is_none() {
+                *process = Some(self.spawn()?);
+            }
+            let running = process.as_mut().unwrap();
+            let line = serde_json::to_string(request)?;
+            timeout(Duration::from_millis(self.cfg.timeout_ms), Self::exchange(running, &line))
+                .await
+                .map_err(|_| anyhow::anyhow!("no answer within {}ms", self.cfg.timeout_ms))?
+        }
+        .await;
+        match result {
+            Ok(reply) => reply,
+            Err(e) => {
+                log::warn!("🔌 {} | Plugin failed: {:#} — doing nothing and restarting it", request.snapshot.asset, e);
+                if let Some(mut running) = process.take() {
+                    let _ = running.child.start_kill();
+                }
+                PluginReply::NONE
+            }
+        }
+    }
+
+    fn spawn(&self) -> Result<Running> {
+        let (program, args) = self.cfg.command.split_first().context("strategy.plugin.command is empty")?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .kill_on_drop(true)
+            .spawn()
+            .with_context(|| format!("Failed to start plugin {:?}", self.cfg.command))?;
+        let stdin = child.stdin.take().context("Plugin stdin unavailable")?;
+        let stdout = BufReader::new(child.stdout.take().context("Plugin stdout unavailable")?).lines();
+        log::info!("🔌 Started plugin {}", self.cfg.command.join(" "));
+        Ok(Running { child, stdin, stdout })
+    }
+
+    async fn exchange(running: &mut Running, line: &str) -> Result<PluginReply> {
+        running.stdin.write_all(line.as_bytes()).await?;
+        running.stdin.write_all(b"\n").await?;
+        running.stdin.flush().await?;
+        let reply = running.stdout.next_line().await?.context("plugin exited")?;
+        serde_json::from_str::<PluginReply>(&reply)
+            .with_context(|| format!("invalid answer {:?}", reply))?
+            .check()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn plugin(script: &str, timeout_ms: u64) -> Plugin {
+        let mut config = Config::default();
+        config.strategy.plugin = PluginConfig {
+            enabled: true,
+            command: vec!["sh".to_string(), "-c".to_string(), script.to_string()],
+            timeout_ms,
+            ..PluginConfig::default()
+        };
+        let api = Arc::new(PolymarketApi::new(String::new(), String::new(), String::new(), None, None, None, None, None, None, &config.network));
+        let feed = Arc::new(MarketFeed::new(Arc::clone(&api), &config));
+        Plugin::new(api, feed, &config)
+    }
+
+    fn snapshot() -> MarketSnapshot {
+        serde_json::from_value(serde_json::json!({
+            "timestamp_ms": 0, "asset": "BTC", "condition_id": "0xc", "period_start": 0,
+            "up": { "token_id": "up", "price": 0.55 }, "down": { "token_id": "down", "price": 0.47 },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn answers_are_read_back_and_failures_do_nothing() {
+        let snapshot = snapshot();
+        let request = PluginRequest { time: 100, account: None, seconds_remaining: 800, bias: None, snapshot: &snapshot };
+
+        let echo = plugin(r#"while read line; do echo '{"action": "buy_down", "size": 5}'; done"#, 2000);
+        let expected = PluginReply { action: PluginAction::BuyDown, size: Some(5.0), price: None };
+        assert_eq!(echo.ask(&request).await, expected);
+        // The same process answers the next snapshot
+        assert_eq!(echo.ask(&request).await, expected);
+
+        let slow = plugin("read line; sleep 5", 100);
+        assert_eq!(slow.ask(&request).await, PluginReply::NONE);
+        let garbage = plugin(r#"while read line; do echo '{"action": "buy_up", "price": 1.5}'; done"#, 2000);
+        assert_eq!(garbage.ask(&request).await, PluginReply::NONE);
+        let missing = plugin("exit 1", 2000);
+        assert_eq!(missing.ask(&request).await, PluginReply::NONE);
+    }
+}
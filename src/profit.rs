@@ -0,0 +1,162 @@
+use crate::config::ProfitWindow;
+use chrono::{LocalResult, NaiveDateTime, TimeZone, Timelike};
+use chrono_tz::Tz;
+
+/// Realized PnL accumulated over the current 15m period, hour and day. Hours and days follow
+/// `timezone`; a window's total is handed back as a `Rollup` once its boundary passes.
+pub struct ProfitTracker {
+    timezone: Tz,
+    windows: Vec<WindowTotal>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WindowTotal {
+    window: ProfitWindow,
+    start: i64,
+    pnl: f64,
+    trades: u32,
+}
+
+/// Total of a window that has closed.
+#[derive(Debug, Clone, Copy)]
+pub struct Rollup {
+    pub window: ProfitWindow,
+    /// Unix seconds, inclusive
+    pub start: i64,
+    /// Unix seconds, exclusive
+    pub end: i64,
+    pub pnl: f64,
+    /// Realized PnL events (sells and resolutions) in the window
+    pub trades: u32,
+}
+
+impl ProfitTracker {
+    pub fn new(timezone: Tz, now: i64) -> Self {
+        let windows = [ProfitWindow::Period, ProfitWindow::Hourly, ProfitWindow::Daily]
+            .into_iter()
+            .map(|window| WindowTotal { window, start: window_start(window, timezone, now), pnl: 0.0, trades: 0 })
+            .collect();
+        Self { timezone, windows }
+    }
+
+    /// Closes every window whose boundary has passed and starts the next one at zero.
+    pub fn roll(&mut self, now: i64) -> Vec<Rollup> {
+        let mut closed = Vec::new();
+        for w in &mut self.windows {
+            let start = window_start(w.window, self.timezone, now);
+            if start != w.start {
+                closed.push(Rollup { window: w.window, start: w.start, end: start, pnl: w.pnl, trades: w.trades });
+                *w = WindowTotal { window: w.window, start, pnl: 0.0, trades: 0 };
+            }
+        }
+        closed
+    }
+
+    /// Adds realized PnL to the open windows, rolling first so it lands in the right ones.
+    pub fn record(&mut self, pnl: f64, now: i64) -> Vec<Rollup> {
+        let closed = self.roll(now);
+        for w in &mut self.windows {
+            w.pnl += pnl;
+            w.trades += 1;
+        }
+        closed
+    }
+
+    /// Realized PnL so far in the open `window`.
+    pub fn current(&self, window: ProfitWindow) -> f64 {
+        self.windows.iter().find(|w| w.window == window).map_or(0.0, |w| w.pnl)
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.timezone
+    }
+}
+
+/// Start of the `window` containing `ts`. Periods are the 15m market periods; hours and days
+/// start on local boundaries in `tz`, so days are 23 or 25 hours across DST changes.
+fn window_start(window: ProfitWindow, tz: Tz, ts: i64) -> i64 {
+    let local = tz.timestamp_opt(ts, 0).unwrap().naive_local();
+    let boundary = match window {
+        ProfitWindow::Period => return ts - ts.rem_euclid(900),
+        ProfitWindow::Hourly => local.date().and_hms_opt(local.hour(), 0, 0).unwrap(),
+        ProfitWindow::Daily => local.date().and_hms_opt(0, 0, 0).unwrap(),
+    };
+    local_to_unix(tz, boundary, ts)
+}
+
+/// Latest instant at or before `ts` showing `local` on the clock; a boundary skipped by a DST
+/// jump starts when the clock resumes.
+fn local_to_unix(tz: Tz, local: NaiveDateTime, ts: i64) -> i64 {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(t) => t.timestamp(),
+        LocalResult::Ambiguous(early, late) => {
+            if late.timestamp() <= ts { late.timestamp() } else { early.timestamp() }
+        }
+        LocalResult::None => tz
+            .from_local_datetime(&(local + chrono::Duration::hours(1)))
+            .earliest()
+            .map_or(ts, |t| t.timestamp()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::New_York;
+
+    const HOUR: i64 = 3600;
+
+    fn closed(rollups: &[Rollup], window: ProfitWindow) -> Option<(i64, i64, f64, u32)> {
+        rollups.iter().find(|r| r.window == window).map(|r| (r.start, r.end, r.pnl, r.trades))
+    }
+
+    #[test]
+    fn the_day_clocks_go_forward_is_23_hours() {
+        // 2026-03-08 00:00 EST; clocks jump from 02:00 EST to 03:00 EDT at 07:00 UTC
+        let midnight = 1772946000;
+        let mut tracker = ProfitTracker::new(New_York, midnight + 60);
+        tracker.record(1.5, midnight + 90 * 60);
+
+        // 01:00 EST is followed by 03:00 EDT one real hour later
+        let jump = midnight + 2 * HOUR;
+        assert_eq!(closed(&tracker.roll(jump), ProfitWindow::Hourly), Some((jump - HOUR, jump, 1.5, 1)));
+        assert_eq!(closed(&tracker.roll(midnight + 23 * HOUR - 1), ProfitWindow::Daily), None);
+        let day = tracker.roll(midnight + 23 * HOUR);
+        assert_eq!(closed(&day, ProfitWindow::Daily), Some((midnight, midnight + 23 * HOUR, 1.5, 1)));
+    }
+
+    #[test]
+    fn the_day_clocks_go_back_is_25_hours_and_the_repeated_hour_counts_twice() {
+        // 2026-11-01 00:00 EDT; clocks fall back from 02:00 EDT to 01:00 EST at 06:00 UTC
+        let midnight = 1793505600;
+        let mut tracker = ProfitTracker::new(New_York, midnight);
+        let first_one_am = midnight + HOUR;
+        tracker.record(1.0, first_one_am + 30 * 60);
+        let rollups = tracker.record(2.0, first_one_am + 90 * 60);
+        assert_eq!(closed(&rollups, ProfitWindow::Hourly), Some((first_one_am, first_one_am + HOUR, 1.0, 1)));
+        let rollups = tracker.roll(first_one_am + 2 * HOUR);
+        assert_eq!(closed(&rollups, ProfitWindow::Hourly), Some((first_one_am + HOUR, first_one_am + 2 * HOUR, 2.0, 1)));
+
+        assert_eq!(closed(&tracker.roll(midnight + 25 * HOUR - 1), ProfitWindow::Daily), None);
+        let day = tracker.roll(midnight + 25 * HOUR);
+        assert_eq!(closed(&day, ProfitWindow::Daily), Some((midnight, midnight + 25 * HOUR, 3.0, 2)));
+    }
+
+    #[test]
+    fn days_turn_over_at_midnight_in_the_configured_timezone() {
+        // 2026-01-15 00:00 EST is 05:00 UTC
+        let midnight = 1768453200;
+        let mut tracker = ProfitTracker::new(New_York, midnight - 5 * HOUR - 600);
+        // UTC midnight passes without closing the day
+        let rollups = tracker.record(1.0, midnight - 5 * HOUR + 600);
+        assert_eq!(closed(&rollups, ProfitWindow::Daily), None);
+        assert!(closed(&rollups, ProfitWindow::Hourly).is_some());
+
+        tracker.record(0.5, midnight - 300);
+        let rollups = tracker.record(-2.0, midnight + 300);
+        assert_eq!(closed(&rollups, ProfitWindow::Daily), Some((midnight - 24 * HOUR, midnight, 1.5, 2)));
+        assert_eq!(closed(&rollups, ProfitWindow::Hourly), Some((midnight - HOUR, midnight, 0.5, 1)));
+        assert_eq!(closed(&rollups, ProfitWindow::Period), Some((midnight - 900, midnight, 0.5, 1)));
+        assert_eq!(tracker.current(ProfitWindow::Daily), -2.0);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::config::RateControlConfig;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Limits how often one strategy opens positions: a minimum gap between buys on the same market
+/// and outcome, a minimum gap between any two order decisions, and a cap on orders per 15m
+/// period. Exits (sells, cancels) are never held back.
+pub struct RateControl {
+    config: RateControlConfig,
+    state: Mutex<RateState>,
+}
+
+#[derive(Default)]
+struct RateState {
+    /// Unix milliseconds of the last decision that sent orders
+    last_order_ms: Option<i64>,
+    /// (market, outcome) -> unix milliseconds of the last buy
+    last_buy_ms: HashMap<(String, String), i64>,
+    period: i64,
+    period_orders: u32,
+}
+
+/// Why orders were held back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Throttled {
+    /// A buy on this market and outcome went out `secs` ago
+    SameSide { secs: f64 },
+    /// Another order went out `secs` ago
+    OrderGap { secs: f64 },
+    /// `max_orders_per_period` orders already went out this period
+    PeriodCap { orders: u32 },
+}
+
+impl Throttled {
+    /// Metrics label
+    pub fn label(&self) -> &'static str {
+        match self {
+            Throttled::SameSide { .. } => "same_side",
+            Throttled::OrderGap { .. } => "order_gap",
+            Throttled::PeriodCap { .. } => "period_cap",
+        }
+    }
+}
+
+impl std::fmt::Display for Throttled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Throttled::SameSide { secs } => write!(f, "bought the same side {:.1}s ago", secs),
+            Throttled::OrderGap { secs } => write!(f, "last order went out {:.1}s ago", secs),
+            Throttled::PeriodCap { orders } => write!(f, "{} orders already placed this period", orders),
+        }
+    }
+}
+
+impl RateControl {
+    pub fn new(config: RateControlConfig) -> Self {
+        Self { config, state: Mutex::new(RateState::default()) }
+    }
+
+    /// Whether buys on each of `outcomes` of `market` may go out now. Both legs of a pair are
+    /// checked together so a pair is never split by the limits.
+    pub fn check(&self, market: &str, outcomes: &[&str], period: i64, now_ms: i64) -> Result<(), Throttled> {
+        let cfg = &self.config;
+        let state = self.state.lock().unwrap();
+        let since = |at: i64| (now_ms - at) as f64 / 1000.0;
+        if let Some(max) = cfg.max_orders_per_period {
+            let placed = if state.period == period { state.period_orders } else { 0 };
+            if placed + outcomes.len() as u32 > max {
+                return Err(Throttled::PeriodCap { orders: placed });
+            }
+        }
+        if let Some(last) = state.last_order_ms.filter(|at| since(*at) < cfg.order_gap_secs) {
+            return Err(Throttled::OrderGap { secs: since(last) });
+        }
+        for outcome in outcomes {
+            let key = (market.to_string(), outcome.to_string());
+            if let Some(last) = state.last_buy_ms.get(&key).filter(|at| since(**at) < cfg.same_side_gap_secs) {
+                return Err(Throttled::SameSide { secs: since(*last) });
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts buys on `outcomes` of `market` that went out.
+    pub fn record(&self, market: &str, outcomes: &[&str], period: i64, now_ms: i64) {
+        let mut state = self.state.lock().unwrap();
+        if state.period != period {
+            state.period = period;
+            state.period_orders = 0;
+        }
+        state.period_orders += outcomes.len() as u32;
+        state.last_order_ms = Some(now_ms);
+        for outcome in outcomes {
+            state.last_buy_ms.insert((market.to_string(), outcome.to_string()), now_ms);
+        }
+        // Buys older than the same-side gap can't hold anything back any more
+        let horizon = (self.config.same_side_gap_secs * 1000.0) as i64;
+        state.last_buy_ms.retain(|_, at| now_ms - *at < horizon);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock;
+
+    const PERIOD: i64 = 1767726000;
+
+    fn rate_control(max_orders_per_period: Option<u32>) -> RateControl {
+        RateControl::new(RateControlConfig { same_side_gap_secs: 30.0, order_gap_secs: 5.0, max_orders_per_period })
+    }
+
+    /// Moves the replay clock to `secs` into the period.
+    fn at(secs: f64) -> i64 {
+        clock::set_replay(PERIOD * 1000 + (secs * 1000.0) as i64);
+        clock::now_ms()
+    }
+
+    #[test]
+    fn gaps_free_up_as_the_clock_moves_on() {
+        let limits = rate_control(None);
+        limits.record("BTC", &["Up"], PERIOD, at(0.0));
+
+        assert_eq!(limits.check("ETH", &["Up"], PERIOD, at(2.0)), Err(Throttled::OrderGap { secs: 2.0 }));
+        assert_eq!(limits.check("ETH", &["Up"], PERIOD, at(5.0)), Ok(()));
+        assert_eq!(limits.check("BTC", &["Up", "Down"], PERIOD, at(10.0)), Err(Throttled::SameSide { secs: 10.0 }));
+        assert_eq!(limits.check("BTC", &["Down"], PERIOD, at(10.0)), Ok(()));
+        assert_eq!(limits.check("BTC", &["Up"], PERIOD, at(30.0)), Ok(()));
+        clock::set_replay(0);
+    }
+
+    #[test]
+    fn the_period_cap_holds_until_the_next_period() {
+        let limits = rate_control(Some(3));
+        limits.record("BTC", &["Up", "Down"], PERIOD, at(0.0));
+
+        // A pair would take the period to 4: neither leg goes out
+        assert_eq!(limits.check("ETH", &["Up", "Down"], PERIOD, at(60.0)), Err(Throttled::PeriodCap { orders: 2 }));
+        assert_eq!(limits.check("ETH", &["Up"], PERIOD, at(60.0)), Ok(()));
+        limits.record("ETH", &["Up"], PERIOD, at(60.0));
+        assert_eq!(limits.check("SOL", &["Up"], PERIOD, at(120.0)), Err(Throttled::PeriodCap { orders: 3 }));
+
+        let next = PERIOD + 900;
+        assert_eq!(limits.check("SOL", &["Up", "Down"], next, at(900.0)), Ok(()));
+        limits.record("SOL", &["Up", "Down"], next, at(900.0));
+        assert_eq!(limits.check("ETH", &["Up", "Down"], next, at(960.0)), Err(Throttled::PeriodCap { orders: 2 }));
+        clock::set_replay(0);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::clock;
+use crate::ledger::LedgerEntry;
+use crate::models::DataApiPosition;
+use std::collections::BTreeMap;
+
+/// Shares the ledger says an account holds in one outcome of one market.
+#[derive(Debug, Clone, Default)]
+struct Recorded {
+    market: String,
+    period_timestamp: i64,
+    shares: f64,
+    /// Set once a resolution row settled the market
+    resolved: bool,
+}
+
+/// One outcome where the wallet and the ledger disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    /// Market name from the ledger, or the data API title for markets the ledger never saw
+    pub market: String,
+    pub condition_id: String,
+    pub outcome: String,
+    pub ledger_shares: f64,
+    pub wallet_shares: f64,
+    /// Data API average entry price of the wallet's shares
+    pub wallet_avg_price: f64,
+    /// The market has resolved and the wallet's shares are waiting for redemption
+    pub redeemable: bool,
+    /// The ledger has rows for this market
+    pub tracked: bool,
+    period_timestamp: i64,
+}
+
+impl Discrepancy {
+    /// What to do about it; `--apply` carries out the ledger corrections.
+    pub fn suggestion(&self) -> String {
+        let short = &self.condition_id[..self.condition_id.len().min(18)];
+        if self.redeemable && self.ledger_shares.abs() < 0.01 {
+            format!("resolved and settled in the ledger; redeem with --redeem --condition-id {}", short)
+        } else if !self.tracked {
+            "not traded by the bot; ignore if held manually".to_string()
+        } else {
+            format!("add an adjustment of {:+.2} shares to the ledger", self.wallet_shares - self.ledger_shares)
+        }
+    }
+
+    /// Whether `--apply` corrects this one: markets the bot traded that aren't just waiting for
+    /// redemption.
+    pub fn correctable(&self) -> bool {
+        self.tracked && !(self.redeemable && self.ledger_shares.abs() < 0.01)
+    }
+
+    /// Ledger row that brings the recorded holding in line with the wallet.
+    pub fn adjustment(&self, account: Option<&str>) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: clock::now(),
+            kind: "adjustment".to_string(),
+            market: self.market.clone(),
+            condition_id: self.condition_id.clone(),
+            period_timestamp: self.period_timestamp,
+            side: self.outcome.clone(),
+            price: self.wallet_avg_price,
+            size: self.wallet_shares - self.ledger_shares,
+            account: account.map(str::to_string),
+            ..LedgerEntry::default()
+        }
+    }
+}
+
+/// Net shares per (condition ID, outcome) from `account`'s buy, sell, resolution and adjustment
+/// rows. A resolution settles everything held in its market.
+fn recorded_holdings(entries: &[LedgerEntry], account: Option<&str>) -> BTreeMap<(String, String), Recorded> {
+    let mut rows: Vec<&LedgerEntry> = entries
+        .iter()
+        .filter(|e| matches!(e.kind.as_str(), "buy" | "sell" | "resolution" | "adjustment"))
+        .filter(|e| e.account.as_deref() == account)
+        .collect();
+    rows.sort_by_key(|e| e.timestamp);
+    let mut holdings: BTreeMap<(String, String), Recorded> = BTreeMap::new();
+    for entry in rows {
+        let condition_id = entry.condition_id.to_lowercase();
+        if entry.kind == "resolution" {
+            for outcome in ["Up", "Down"] {
+                let held = holdings.entry((condition_id.clone(), outcome.to_string())).or_default();
+                held.shares = 0.0;
+                held.resolved = true;
+            }
+            continue;
+        }
+        let held = holdings.entry((condition_id, entry.side.clone())).or_default();
+        held.market = entry.market.clone();
+        held.period_timestamp = entry.period_timestamp;
+        held.shares += match entry.kind.as_str() {
+            "sell" => -entry.size,
+            _ => entry.size,
+        };
+    }
+    holdings
+}
+
+/// Compares the wallet's positions with the holdings `account`'s ledger rows add up to and
+/// returns every outcome that differs by 0.01 shares or more.
+pub fn compare(entries: &[LedgerEntry], account: Option<&str>, wallet: &[DataApiPosition]) -> Vec<Discrepancy> {
+    let recorded = recorded_holdings(entries, account);
+    let mut held: BTreeMap<(String, String), &DataApiPosition> = BTreeMap::new();
+    for position in wallet {
+        held.insert((position.condition_id.to_lowercase(), position.outcome.clone()), position);
+    }
+    let mut keys: Vec<&(String, String)> = recorded.keys().chain(held.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut discrepancies = Vec::new();
+    for key in keys {
+        let ledger = recorded.get(key);
+        let wallet = held.get(key);
+        let ledger_shares = ledger.map_or(0.0, |r| r.shares);
+        let wallet_shares = wallet.map_or(0.0, |p| p.size);
+        if (ledger_shares - wallet_shares).abs() < 0.01 {
+            continue;
+        }
+        let tracked = recorded.keys().any(|(condition_id, _)| *condition_id == key.0);
+        // A resolution row has no market name; take it from a row on the other outcome
+        let named = recorded.iter().find(|((c, _), r)| *c == key.0 && !r.market.is_empty()).map(|(_, r)| r);
+        discrepancies.push(Discrepancy {
+            market: named.map(|r| r.market.clone()).or_else(|| wallet.map(|p| p.title.clone())).unwrap_or_default(),
+            condition_id: key.0.clone(),
+            outcome: key.1.clone(),
+            ledger_shares,
+            wallet_shares,
+            wallet_avg_price: wallet.map_or(0.0, |p| p.avg_price),
+            redeemable: wallet.is_some_and(|p| p.redeemable) || ledger.is_some_and(|r| r.resolved),
+            tracked,
+            period_timestamp: named.map_or(0, |r| r.period_timestamp),
+        });
+    }
+    discrepancies
+}
+
+pub fn print(discrepancies: &[Discrepancy], account: Option<&str>) {
+    let label = account.map(|a| format!(" [{}]", a)).unwrap_or_default();
+    if discrepancies.is_empty() {
+        eprintln!("✅ Wallet and ledger agree{}", label);
+        return;
+    }
+    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+    eprintln!("🔄 {} discrepanc{} between wallet and ledger{}", discrepancies.len(),
+        if discrepancies.len() == 1 { "y" } else { "ies" }, label);
+    eprintln!("   {:<24} {:<18} {:<5} {:>9} {:>9}", "market", "condition", "side", "ledger", "wallet");
+    for d in discrepancies {
+        eprintln!("   {:<24} {:<18} {:<5} {:>9.2} {:>9.2}  → {}",
+            d.market.chars().take(24).collect::<String>(), &d.condition_id[..d.condition_id.len().min(18)],
+            d.outcome, d.ledger_shares, d.wallet_shares, d.suggestion());
+    }
+    eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(kind: &str, condition_id: &str, side: &str, size: f64) -> LedgerEntry {
+        LedgerEntry {
+            kind: kind.to_string(),
+            market: "BTC".to_string(),
+            condition_id: condition_id.to_string(),
+            side: side.to_string(),
+            size,
+            ..LedgerEntry::default()
+        }
+    }
+
+    fn position(condition_id: &str, outcome: &str, size: f64, redeemable: bool) -> DataApiPosition {
+        DataApiPosition {
+            asset: String::new(),
+            condition_id: condition_id.to_string(),
+            size,
+            avg_price: 0.45,
+            outcome: outcome.to_string(),
+            title: "Other market".to_string(),
+            redeemable,
+        }
+    }
+
+    #[test]
+    fn flags_outcomes_where_wallet_and_ledger_disagree() {
+        let entries = vec![
+            // Pair bought, loser sold: 10 Up held and the wallet agrees
+            row("buy", "0xA", "Up", 10.0),
+            row("buy", "0xA", "Down", 10.0),
+            row("sell", "0xA", "Down", 10.0),
+            // Partly sold on the exchange but not in the ledger
+            row("buy", "0xb", "Up", 10.0),
+            // Resolved; the wallet hasn't redeemed yet
+            row("buy", "0xc", "Down", 10.0),
+            row("resolution", "0xc", "Down", 10.0),
+        ];
+        let wallet = vec![
+            position("0xa", "Up", 10.0, false),
+            position("0xb", "Up", 4.0, false),
+            position("0xc", "Down", 10.0, true),
+            position("0xd", "Up", 5.0, false),
+        ];
+        let found = compare(&entries, None, &wallet);
+        let summary: Vec<(&str, f64, f64, bool)> = found
+            .iter()
+            .map(|d| (d.condition_id.as_str(), d.ledger_shares, d.wallet_shares, d.correctable()))
+            .collect();
+        assert_eq!(summary, vec![("0xb", 10.0, 4.0, true), ("0xc", 0.0, 10.0, false), ("0xd", 0.0, 5.0, false)]);
+        assert_eq!(found[2].market, "Other market");
+
+        // Applying the correction settles the disagreement
+        let mut corrected = entries.clone();
+        corrected.push(found[0].adjustment(None));
+        assert_eq!(compare(&corrected, None, &wallet).len(), 2);
+    }
+}
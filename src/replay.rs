@@ -0,0 +1,305 @@
+use crate::api::PolymarketApi;
+use crate::apierror::{ApiError, ApiResult};
+use crate::backtest::{self, DEFAULT_FILL_SEED};
+use crate::clock;
+use crate::config::Config;
+use crate::feed::MarketFeed;
+use crate::ledger;
+use crate::models::{Market, MarketDetails, MarketSnapshot, MarketToken, OrderBook, OrderBookEntry, PriceLevel, TokenSnapshot};
+use crate::strategy::{PreLimitStrategy, MARKET_DURATION_SECS};
+use anyhow::Result;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Recorded snapshots served back as market data at the replay clock's time, in place of the
+/// Gamma and CLOB APIs. Each read answers from the market's latest snapshot at or before
+/// `clock::now_ms()`, so the same fixture and config always lead to the same decisions.
+pub struct Replay {
+    /// Condition ID -> the market's snapshots, oldest first
+    markets: HashMap<String, Vec<MarketSnapshot>>,
+    /// (uppercase asset, period start) -> condition ID
+    periods: HashMap<(String, i64), String>,
+    /// Token ID -> (condition ID, is the Up token)
+    tokens: HashMap<String, (String, bool)>,
+    start_ms: i64,
+    end_ms: i64,
+}
+
+impl Replay {
+    /// Loads snapshot files or directories as recorded by the monitor (NDJSON, optionally gzipped).
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let mut snapshots = backtest::load_snapshots(paths)?;
+        if snapshots.is_empty() {
+            anyhow::bail!("No snapshots found in {:?}", paths);
+        }
+        snapshots.sort_by_key(|s| s.timestamp_ms);
+        let start_ms = snapshots[0].timestamp_ms;
+        let end_ms = snapshots.iter().map(|s| (s.period_start + MARKET_DURATION_SECS) * 1000).max().unwrap_or(start_ms);
+        let mut replay = Self { markets: HashMap::new(), periods: HashMap::new(), tokens: HashMap::new(), start_ms, end_ms };
+        for s in snapshots {
+            replay.periods.insert((s.asset.to_uppercase(), s.period_start), s.condition_id.clone());
+            replay.tokens.insert(s.up.token_id.clone(), (s.condition_id.clone(), true));
+            replay.tokens.insert(s.down.token_id.clone(), (s.condition_id.clone(), false));
+            replay.markets.entry(s.condition_id.clone()).or_default().push(s);
+        }
+        Ok(replay)
+    }
+
+    /// Time of the first snapshot, Unix milliseconds.
+    pub fn start_ms(&self) -> i64 {
+        self.start_ms
+    }
+
+    /// End of the last recorded period, Unix milliseconds.
+    pub fn end_ms(&self) -> i64 {
+        self.end_ms
+    }
+
+    /// The recorded market for `asset` in the period starting at `period_start`. Markets are
+    /// known for the whole recording, as Gamma lists them before they open.
+    pub fn market(&self, asset: &str, period_start: i64) -> ApiResult<Market> {
+        let condition_id = self
+            .periods
+            .get(&(asset.to_uppercase(), period_start))
+            .ok_or_else(|| ApiError::Other(format!("No {} market recorded for period {}", asset, period_start)))?;
+        let first = &self.markets[condition_id][0];
+        let closed = self.ended(first);
+        Ok(Market {
+            condition_id: condition_id.clone(),
+            market_id: None,
+            question: format!("{} Up or Down", first.asset),
+            slug: String::new(),
+            end_date_iso: chrono::DateTime::from_timestamp(first.period_start + MARKET_DURATION_SECS, 0).map(|t| t.to_rfc3339()),
+            active: !closed,
+            closed,
+            volume_24h: self.latest(condition_id, clock::now_ms()).and_then(|s| s.volume_24h),
+        })
+    }
+
+    /// CLOB market details. Once the period is over the market is closed, and the outcome with
+    /// the higher final recorded price wins, as in the backtester.
+    pub fn market_details(&self, condition_id: &str) -> ApiResult<MarketDetails> {
+        let snapshots = self
+            .markets
+            .get(condition_id)
+            .ok_or_else(|| ApiError::Other(format!("Market {} is not in the replay", condition_id)))?;
+        let first = &snapshots[0];
+        let closed = self.ended(first);
+        let up_wins = self.final_up_wins(condition_id);
+        let token = |outcome: &str, t: &TokenSnapshot, wins: Option<bool>| MarketToken {
+            outcome: outcome.to_string(),
+            token_id: t.token_id.clone(),
+            winner: closed && wins.unwrap_or(false),
+        };
+        Ok(MarketDetails {
+            condition_id: condition_id.to_string(),
+            question: format!("{} Up or Down", first.asset),
+            tokens: vec![token("Up", &first.up, up_wins), token("Down", &first.down, up_wins.map(|up| !up))],
+            active: !closed,
+            closed,
+            end_date_iso: chrono::DateTime::from_timestamp(first.period_start + MARKET_DURATION_SECS, 0)
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Whether Up is quoted at or above Down in the market's last recorded snapshot, the
+    /// winner once its period is over; `None` for a market not in the replay.
+    pub fn final_up_wins(&self, condition_id: &str) -> Option<bool> {
+        self.markets.get(condition_id)?.last().map(|s| s.up.price >= s.down.price)
+    }
+
+    /// Quoted price of a token: SELL is the recorded price, BUY the recorded bid.
+    pub fn price(&self, token_id: &str, side: &str) -> ApiResult<Decimal> {
+        self.price_at(token_id, side, clock::now_ms())
+    }
+
+    /// `price` as recorded at `at_ms` (Unix milliseconds).
+    pub fn price_at(&self, token_id: &str, side: &str, at_ms: i64) -> ApiResult<Decimal> {
+        let token = self.token(token_id, at_ms)?;
+        let price = if side == "BUY" { token.bid } else { Some(token.price) };
+        price.map(decimal).ok_or_else(|| ApiError::Other(format!("No {} price recorded for token {}", side, token_id)))
+    }
+
+    pub fn last_trade_price(&self, token_id: &str) -> ApiResult<Decimal> {
+        self.token(token_id, clock::now_ms())?
+            .last_trade_price
+            .map(decimal)
+            .ok_or_else(|| ApiError::Other(format!("No last trade recorded for token {}", token_id)))
+    }
+
+    /// Recorded books of `token_ids`; tokens without a snapshot yet are missing from the map.
+    pub fn books(&self, token_ids: &[String]) -> HashMap<String, OrderBook> {
+        self.books_at(token_ids, clock::now_ms())
+    }
+
+    /// `books` as recorded at `at_ms` (Unix milliseconds).
+    pub fn books_at(&self, token_ids: &[String], at_ms: i64) -> HashMap<String, OrderBook> {
+        let levels = |levels: &[PriceLevel]| {
+            levels.iter().map(|l| OrderBookEntry { price: decimal(l.price), size: decimal(l.size) }).collect()
+        };
+        token_ids
+            .iter()
+            .filter_map(|id| {
+                let token = self.token(id, at_ms).ok()?;
+                let book = OrderBook {
+                    bids: levels(&token.bids),
+                    asks: levels(&token.asks),
+                    tick_size: token.tick_size.map(decimal),
+                    min_order_size: None,
+                };
+                Some((id.clone(), book))
+            })
+            .collect()
+    }
+
+    fn ended(&self, snapshot: &MarketSnapshot) -> bool {
+        clock::now() >= snapshot.period_start + MARKET_DURATION_SECS
+    }
+
+    fn latest(&self, condition_id: &str, at_ms: i64) -> Option<&MarketSnapshot> {
+        let snapshots = self.markets.get(condition_id)?;
+        let recorded = snapshots.partition_point(|s| s.timestamp_ms <= at_ms);
+        recorded.checked_sub(1).map(|i| &snapshots[i])
+    }
+
+    fn token(&self, token_id: &str, at_ms: i64) -> ApiResult<&TokenSnapshot> {
+        let (condition_id, up) = self
+            .tokens
+            .get(token_id)
+            .ok_or_else(|| ApiError::Other(format!("Token {} is not in the replay", token_id)))?;
+        let snapshot = self
+            .latest(condition_id, at_ms)
+            .ok_or_else(|| ApiError::Other(format!("Nothing recorded for token {} yet", token_id)))?;
+        Ok(if *up { &snapshot.up } else { &snapshot.down })
+    }
+}
+
+fn decimal(value: f64) -> Decimal {
+    value.to_string().parse().unwrap_or_default()
+}
+
+/// Runs the strategy in simulation over recorded snapshots on the replay clock: every
+/// `check_interval_ms` of recorded time is one tick, with market closure checks every
+/// `market_closure_check_interval_seconds`. The decisions (the ledger's buys, sells and
+/// resolutions) are written to `output`; with `golden`, they must match that file's or the
+/// replay fails.
+pub async fn run(api: PolymarketApi, config: &Config, data: &[PathBuf], output: &Path, golden: Option<&Path>) -> Result<()> {
+    let replay = Arc::new(Replay::load(data)?);
+    let mut config = config.clone();
+    config.strategy.simulation_mode = true;
+    config.strategy.paper_live = false;
+    config.strategy.fill_model.seed = config.strategy.fill_model.seed.or(Some(DEFAULT_FILL_SEED));
+    config.ledger.enabled = true;
+    config.ledger.path = output.to_path_buf();
+    config.report.enabled = false;
+    // Keep clear of a running bot's kill switch
+    config.strategy.risk.kill_switch_file = output.with_extension("kill_switch");
+    if output.exists() {
+        std::fs::remove_file(output)?;
+    }
+
+    clock::set_replay(replay.start_ms());
+    let api = Arc::new(api.with_replay(Arc::clone(&replay)));
+    let feed = Arc::new(MarketFeed::new(Arc::clone(&api), &config));
+    let strategies: Vec<PreLimitStrategy> = if config.accounts.is_empty() {
+        vec![PreLimitStrategy::new(Arc::clone(&api), Arc::clone(&feed), config.clone())]
+    } else {
+        config
+            .accounts
+            .iter()
+            .map(|a| PreLimitStrategy::new(Arc::clone(&api), Arc::clone(&feed), config.for_account(a)).with_account(&a.name))
+            .collect()
+    };
+
+    let closure_interval_ms = config.strategy.market_closure_check_interval_seconds as i64 * 1000;
+    // Run past the last period's end long enough for its resolution to be checked
+    let end_ms = replay.end_ms() + closure_interval_ms;
+    eprintln!("⏪ Replaying {} to {} in {}ms ticks",
+        timestamp(replay.start_ms()), timestamp(replay.end_ms()), config.strategy.check_interval_ms);
+    let mut now_ms = replay.start_ms();
+    let mut next_closure_ms = now_ms + closure_interval_ms;
+    while now_ms <= end_ms {
+        clock::set_replay(now_ms);
+        for strategy in &strategies {
+            strategy.tick().await;
+        }
+        if now_ms >= next_closure_ms {
+            for strategy in &strategies {
+                if let Err(e) = strategy.check_market_closure().await {
+                    log::warn!("Error checking market closure: {}", e);
+                }
+            }
+            next_closure_ms += closure_interval_ms;
+        }
+        now_ms += config.strategy.check_interval_ms as i64;
+    }
+    for strategy in &strategies {
+        strategy.shutdown().await;
+    }
+
+    // Nothing is written when no order was placed
+    let decisions = if output.exists() { read_decisions(output)? } else { Vec::new() };
+    eprintln!("📒 {} decisions written to {}", decisions.len(), output.display());
+    let Some(golden) = golden else {
+        return Ok(());
+    };
+    let expected = read_decisions(golden)?;
+    if let Some(i) = (0..decisions.len().max(expected.len())).find(|&i| decisions.get(i) != expected.get(i)) {
+        let show = |d: Option<&String>| d.map_or("(none)".to_string(), |d| d.clone());
+        anyhow::bail!(
+            "Replay decisions differ from {} at decision {} of {}:\n  expected {}\n  got      {}",
+            golden.display(),
+            i + 1,
+            expected.len(),
+            show(expected.get(i)),
+            show(decisions.get(i))
+        );
+    }
+    eprintln!("✅ All {} decisions match {}", decisions.len(), golden.display());
+    Ok(())
+}
+
+/// Ledger rows as JSON lines, ordered by time and then content: positions settled in the same
+/// closure check are written in no particular order.
+fn read_decisions(path: &Path) -> Result<Vec<String>> {
+    let mut rows = ledger::read_entries(path)?
+        .iter()
+        .map(|e| Ok((e.timestamp, serde_json::to_string(e)?)))
+        .collect::<Result<Vec<_>>>()?;
+    rows.sort();
+    Ok(rows.into_iter().map(|(_, row)| row).collect())
+}
+
+fn timestamp(ms: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(ms).map(|t| t.to_rfc3339()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{MarketConfig, MarketDuration};
+    use crate::mockapi::MockServer;
+
+    /// fixtures/btc.ndjson records three BTC periods: a flat one, then one where both pre-orders
+    /// fill, then one where only Up fills before it collapses and is sold on the danger price.
+    #[tokio::test]
+    async fn replay_matches_the_golden_ledger() {
+        let server = MockServer::start().await.unwrap();
+        let mut config = Config::default();
+        let mut btc = MarketConfig::new("BTC", "bitcoin");
+        btc.timeframes = vec![MarketDuration::M15];
+        config.markets = vec![btc];
+        // Signal gating and the danger exit on, at the defaults of a `signal` block
+        config.strategy.signal = serde_json::from_str("{}").unwrap();
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let output = std::env::temp_dir().join(format!("polymarket-bot-replay-{}.jsonl", std::process::id()));
+
+        let result = run(server.api(), &config, &[fixtures.join("btc.ndjson")], &output, Some(&fixtures.join("btc.golden.jsonl"))).await;
+        let _ = std::fs::remove_file(output.with_extension("kill_switch"));
+        let _ = std::fs::remove_file(&output);
+        result.unwrap();
+    }
+}
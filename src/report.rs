@@ -0,0 +1,321 @@
+use crate::config::ReportFormat;
+use crate::display;
+use crate::ledger::LedgerEntry;
+use crate::profit::Rollup;
+use anyhow::{Context, Result};
+use chrono::TimeZone;
+use chrono_tz::Tz;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// What happened in a closed profit window, rendered to `report.dir` as Markdown or HTML.
+pub struct Report {
+    pub rollup: Rollup,
+    pub tz: Tz,
+    pub account: Option<String>,
+    /// Buy, sell and resolution rows in the window, in order
+    pub trades: Vec<LedgerEntry>,
+    /// Markets with a row in the window, by period
+    pub markets: Vec<MarketResult>,
+    /// (timestamp, market, pair cost) per buy in the window
+    pub pair_costs: Vec<(i64, String, f64)>,
+    /// (timestamp, cumulative realized PnL) per row with PnL, starting from zero
+    pub cumulative_pnl: Vec<(i64, f64)>,
+}
+
+/// Average entry per outcome of one market period against how it resolved.
+pub struct MarketResult {
+    pub market: String,
+    pub period_timestamp: i64,
+    /// (average price, shares) bought per outcome
+    pub up: Option<(f64, f64)>,
+    pub down: Option<(f64, f64)>,
+    /// Winning outcome; None while unresolved
+    pub winner: Option<String>,
+    pub pnl: f64,
+}
+
+impl Report {
+    /// Builds the report for `rollup` from `entries`, reading only rows written before the window
+    /// ended so entries and resolutions match what was known at the time.
+    pub fn build(entries: &[LedgerEntry], rollup: Rollup, tz: Tz, account: Option<String>) -> Self {
+        let known: Vec<&LedgerEntry> = entries
+            .iter()
+            .filter(|e| matches!(e.kind.as_str(), "buy" | "sell" | "resolution" | "pending_resolution"))
+            .filter(|e| e.account == account && e.timestamp < rollup.end)
+            .collect();
+        let in_window: Vec<&LedgerEntry> = known.iter().copied().filter(|e| e.timestamp >= rollup.start).collect();
+
+        // (period, condition ID) -> result
+        let mut markets: BTreeMap<(i64, &str), MarketResult> = BTreeMap::new();
+        for entry in &in_window {
+            markets.entry((entry.period_timestamp, &entry.condition_id)).or_insert_with(|| MarketResult {
+                market: entry.market.clone(),
+                period_timestamp: entry.period_timestamp,
+                up: None,
+                down: None,
+                winner: None,
+                pnl: 0.0,
+            });
+        }
+        for entry in &known {
+            let Some(result) = markets.get_mut(&(entry.period_timestamp, entry.condition_id.as_str())) else { continue };
+            match entry.kind.as_str() {
+                "buy" => {
+                    let leg = if entry.side == "Up" { &mut result.up } else { &mut result.down };
+                    let (price, size) = leg.unwrap_or((0.0, 0.0));
+                    let total = size + entry.size;
+                    if total > 0.0 {
+                        *leg = Some(((price * size + entry.price * entry.size) / total, total));
+                    }
+                }
+                "resolution" => result.winner = Some(entry.side.clone()),
+                _ => {}
+            }
+            result.pnl += entry.pnl.unwrap_or(0.0);
+        }
+
+        // Rows from several strategies can land slightly out of order
+        let mut trades: Vec<LedgerEntry> = in_window.into_iter().cloned().collect();
+        trades.sort_by_key(|e| e.timestamp);
+        let pair_costs = trades
+            .iter()
+            .filter(|e| e.kind == "buy")
+            .map(|e| (e.timestamp, e.market.clone(), e.cost_per_pair))
+            .collect();
+        let mut cumulative_pnl = vec![(rollup.start, 0.0)];
+        let mut total = 0.0;
+        for entry in trades.iter().filter(|e| e.pnl.is_some()) {
+            total += entry.pnl.unwrap();
+            cumulative_pnl.push((entry.timestamp, total));
+        }
+
+        Self {
+            rollup,
+            tz,
+            account,
+            trades,
+            markets: markets.into_values().collect(),
+            pair_costs,
+            cumulative_pnl,
+        }
+    }
+
+    /// Writes the report under `dir` as `<window>-<start>[-<account>].<md|html>` and returns its path.
+    pub fn write(&self, dir: &Path, format: ReportFormat) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir).context(format!("Failed to create report directory {}", dir.display()))?;
+        let mut name = format!("{}-{}", self.rollup.window.label(), self.time(self.rollup.start, "%Y%m%d-%H%M"));
+        if let Some(account) = &self.account {
+            name = format!("{}-{}", name, account);
+        }
+        let (body, extension) = match format {
+            ReportFormat::Markdown => (self.markdown(), "md"),
+            ReportFormat::Html => (self.html(), "html"),
+        };
+        let path = dir.join(format!("{}.{}", name, extension));
+        std::fs::write(&path, body).context(format!("Failed to write report {}", path.display()))?;
+        Ok(path)
+    }
+
+    fn time(&self, ts: i64, format: &str) -> String {
+        self.tz.timestamp_opt(ts, 0).unwrap().format(format).to_string()
+    }
+
+    fn title(&self) -> String {
+        let mut title = format!(
+            "{} report {} – {}",
+            capitalize(self.rollup.window.label()),
+            self.time(self.rollup.start, "%Y-%m-%d %H:%M"),
+            self.time(self.rollup.end, "%Y-%m-%d %H:%M %Z"),
+        );
+        if let Some(account) = &self.account {
+            let _ = write!(title, " ({})", account);
+        }
+        title
+    }
+
+    fn trade_rows(&self) -> Vec<[String; 8]> {
+        self.trades
+            .iter()
+            .map(|e| {
+                [
+                    self.time(e.timestamp, "%H:%M:%S"),
+                    e.kind.clone(),
+                    e.market.clone(),
+                    self.time(e.period_timestamp, "%H:%M"),
+                    e.side.clone(),
+                    display::price(e.price),
+                    display::size(e.size, e.price),
+                    e.pnl.map_or_else(String::new, |p| display::pnl(p, e.size)),
+                ]
+            })
+            .collect()
+    }
+
+    fn market_rows(&self) -> Vec<[String; 6]> {
+        let leg = |l: Option<(f64, f64)>| {
+            l.map_or("-".to_string(), |(price, size)| format!("{} × {}", display::price(price), display::shares(size)))
+        };
+        self.markets
+            .iter()
+            .map(|m| {
+                let pair = match (m.up, m.down) {
+                    (Some((up, _)), Some((down, _))) => display::price(up + down),
+                    _ => "-".to_string(),
+                };
+                [
+                    format!("{} {}", m.market, self.time(m.period_timestamp, "%Y-%m-%d %H:%M")),
+                    leg(m.up),
+                    leg(m.down),
+                    pair,
+                    m.winner.clone().unwrap_or_else(|| "unresolved".to_string()),
+                    // Per share of the larger leg, i.e. per pair when both legs are full
+                    display::pnl(m.pnl, m.up.map_or(0.0, |l| l.1).max(m.down.map_or(0.0, |l| l.1))),
+                ]
+            })
+            .collect()
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# {}\n", self.title());
+        let _ = writeln!(
+            out,
+            "Realized PnL **{}** over {} realized trade(s); {} ledger row(s).\n",
+            display::usd(self.rollup.pnl),
+            self.rollup.trades,
+            self.trades.len()
+        );
+        let table = |out: &mut String, header: &[&str], rows: Vec<Vec<String>>| {
+            let _ = writeln!(out, "| {} |", header.join(" | "));
+            let _ = writeln!(out, "|{}", "---|".repeat(header.len()));
+            for row in rows {
+                let _ = writeln!(out, "| {} |", row.join(" | "));
+            }
+            out.push('\n');
+        };
+
+        out.push_str("## Entries vs resolution\n\n");
+        table(
+            &mut out,
+            &["Market", "Up entry", "Down entry", "Pair cost", "Winner", "PnL"],
+            self.market_rows().into_iter().map(Vec::from).collect(),
+        );
+        out.push_str("## Trades\n\n");
+        table(
+            &mut out,
+            &["Time", "Kind", "Market", "Period", "Side", "Price", "Size", "PnL"],
+            self.trade_rows().into_iter().map(Vec::from).collect(),
+        );
+        out.push_str("## Cost per pair\n\n```csv\ntimestamp,market,cost_per_pair\n");
+        for (ts, market, cost) in &self.pair_costs {
+            let _ = writeln!(out, "{},{},{:.4}", ts, market, cost);
+        }
+        out.push_str("```\n\n## Cumulative PnL\n\n```csv\ntimestamp,cumulative_pnl\n");
+        for (ts, pnl) in &self.cumulative_pnl {
+            let _ = writeln!(out, "{},{:.4}", ts, pnl);
+        }
+        out.push_str("```\n");
+        out
+    }
+
+    fn html(&self) -> String {
+        let mut out = String::new();
+        let title = escape(&self.title());
+        let _ = writeln!(
+            out,
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>\n\
+             <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse;margin-bottom:2em}}\
+             td,th{{border:1px solid #ccc;padding:2px 8px;text-align:right}}svg{{border:1px solid #ccc}}</style>\n\
+             </head><body>\n<h1>{}</h1>",
+            title, title
+        );
+        let _ = writeln!(
+            out,
+            "<p>Realized PnL <b>{}</b> over {} realized trade(s); {} ledger row(s).</p>",
+            display::usd(self.rollup.pnl),
+            self.rollup.trades,
+            self.trades.len()
+        );
+        let table = |out: &mut String, header: &[&str], rows: Vec<Vec<String>>| {
+            out.push_str("<table>\n<tr>");
+            for h in header {
+                let _ = write!(out, "<th>{}</th>", h);
+            }
+            out.push_str("</tr>\n");
+            for row in rows {
+                out.push_str("<tr>");
+                for cell in row {
+                    let _ = write!(out, "<td>{}</td>", escape(&cell));
+                }
+                out.push_str("</tr>\n");
+            }
+            out.push_str("</table>\n");
+        };
+
+        out.push_str("<h2>Cumulative PnL</h2>\n");
+        out.push_str(&svg_line(&self.cumulative_pnl));
+        out.push_str("<h2>Cost per pair</h2>\n");
+        let costs: Vec<(i64, f64)> = self.pair_costs.iter().map(|(ts, _, cost)| (*ts, *cost)).collect();
+        out.push_str(&svg_line(&costs));
+        out.push_str("<h2>Entries vs resolution</h2>\n");
+        table(
+            &mut out,
+            &["Market", "Up entry", "Down entry", "Pair cost", "Winner", "PnL"],
+            self.market_rows().into_iter().map(Vec::from).collect(),
+        );
+        out.push_str("<h2>Trades</h2>\n");
+        table(
+            &mut out,
+            &["Time", "Kind", "Market", "Period", "Side", "Price", "Size", "PnL"],
+            self.trade_rows().into_iter().map(Vec::from).collect(),
+        );
+        // Chart data for tools that want to redraw it
+        let data = serde_json::json!({
+            "cumulative_pnl": self.cumulative_pnl,
+            "cost_per_pair": self.pair_costs,
+        });
+        let _ = writeln!(out, "<script type=\"application/json\" id=\"chart-data\">{}</script>", data);
+        out.push_str("</body></html>\n");
+        out
+    }
+}
+
+/// Inline SVG line chart of (timestamp, value) points, scaled to fit; empty without two points.
+fn svg_line(points: &[(i64, f64)]) -> String {
+    const W: f64 = 600.0;
+    const H: f64 = 200.0;
+    if points.len() < 2 {
+        return "<p>Not enough data to chart.</p>\n".to_string();
+    }
+    let (t0, t1) = (points[0].0, points[points.len() - 1].0);
+    let (lo, hi) = points.iter().fold((f64::MAX, f64::MIN), |(lo, hi), (_, v)| (lo.min(*v), hi.max(*v)));
+    let span_t = (t1 - t0).max(1) as f64;
+    let span_v = if hi > lo { hi - lo } else { 1.0 };
+    let coords: Vec<String> = points
+        .iter()
+        .map(|(t, v)| format!("{:.1},{:.1}", (*t - t0) as f64 / span_t * W, H - (v - lo) / span_v * H))
+        .collect();
+    format!(
+        "<svg width=\"{}\" height=\"{}\" viewBox=\"-5 -5 {} {}\"><polyline fill=\"none\" stroke=\"#36c\" stroke-width=\"2\" points=\"{}\"/>\
+         <text x=\"0\" y=\"10\" font-size=\"10\">{:.3}</text><text x=\"0\" y=\"{}\" font-size=\"10\">{:.3}</text></svg>\n",
+        W + 10.0,
+        H + 10.0,
+        W + 10.0,
+        H + 10.0,
+        coords.join(" "),
+        hi,
+        H,
+        lo
+    )
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    chars.next().map_or_else(String::new, |c| c.to_uppercase().chain(chars).collect())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
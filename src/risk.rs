@@ -0,0 +1,350 @@
+use crate::apierror::ApiError;
+use crate::config::RiskConfig;
+use anyhow::{Context, Result};
+use chrono::{NaiveDate, TimeZone, Utc};
+use chrono_tz::America::New_York;
+use tokio::sync::Mutex;
+
+/// Daily loss and drawdown tracking and kill switch.
+///
+/// Realized PnL is accumulated per ET calendar day. When realized + unrealized
+/// losses for the day exceed `daily_loss_limit_usd`, or realized PnL falls
+/// `max_drawdown_usd` below its running peak, the kill switch trips and
+/// writes `kill_switch_file`. Trading stays halted until the bot is restarted
+/// or `--resume` removes that file.
+pub struct RiskManager {
+    config: RiskConfig,
+    state: Mutex<RiskState>,
+}
+
+#[derive(Debug)]
+struct RiskState {
+    day: NaiveDate,
+    realized_pnl: f64,
+    /// Realized PnL since start, and its highest value so far
+    cumulative_pnl: f64,
+    peak_pnl: f64,
+    max_drawdown: f64,
+    halted: bool,
+    /// A buy was refused for lack of USDC; new buys wait for the next balance read
+    short_of_funds: bool,
+}
+
+fn current_day_et() -> NaiveDate {
+    Utc.timestamp_opt(crate::clock::now(), 0).unwrap().with_timezone(&New_York).date_naive()
+}
+
+impl RiskManager {
+    pub fn new(config: RiskConfig) -> Self {
+        // A restart clears any previous halt
+        if config.kill_switch_file.exists() {
+            match std::fs::remove_file(&config.kill_switch_file) {
+                Ok(_) => log::warn!("Removed kill switch file {} from a previous run", config.kill_switch_file.display()),
+                Err(e) => log::warn!("Failed to remove kill switch file {}: {}", config.kill_switch_file.display(), e),
+            }
+        }
+        Self {
+            config,
+            state: Mutex::new(RiskState {
+                day: current_day_et(),
+                realized_pnl: 0.0,
+                cumulative_pnl: 0.0,
+                peak_pnl: 0.0,
+                max_drawdown: 0.0,
+                halted: false,
+                short_of_funds: false,
+            }),
+        }
+    }
+
+    pub fn daily_loss_limit(&self) -> Option<f64> {
+        self.config.daily_loss_limit_usd.filter(|l| *l > 0.0)
+    }
+
+    pub fn max_drawdown_limit(&self) -> Option<f64> {
+        self.config.max_drawdown_usd.filter(|l| *l > 0.0)
+    }
+
+    pub async fn is_halted(&self) -> bool {
+        self.state.lock().await.halted
+    }
+
+    /// Reacts to a failed order. A buy refused for insufficient balance holds back new buys
+    /// until `balance_read` reports funds again; other classes are the caller's to handle
+    /// (auth and rate limits are retried inside the API client).
+    pub async fn note_order_error(&self, side: &str, e: &ApiError) {
+        if side == "BUY" && matches!(e, ApiError::InsufficientBalance(_)) {
+            let mut state = self.state.lock().await;
+            if !state.short_of_funds {
+                log::warn!("💸 Buy refused for insufficient balance — holding new orders until the next balance read");
+                state.short_of_funds = true;
+            }
+        }
+    }
+
+    /// New buys are held back after an insufficient-balance refusal.
+    pub async fn is_short_of_funds(&self) -> bool {
+        self.state.lock().await.short_of_funds
+    }
+
+    /// A fresh USDC balance lifts the insufficient-balance hold once it covers `min_order_usd`.
+    pub async fn balance_read(&self, balance: f64, min_order_usd: f64) {
+        let mut state = self.state.lock().await;
+        if state.short_of_funds && balance >= min_order_usd {
+            log::info!("💵 USDC balance ${:.2} — resuming new orders", balance);
+            state.short_of_funds = false;
+        }
+    }
+
+    /// Realized PnL for the current ET day.
+    pub async fn daily_realized_pnl(&self) -> f64 {
+        let mut state = self.state.lock().await;
+        Self::roll_day(&mut state);
+        state.realized_pnl
+    }
+
+    pub async fn record_realized(&self, pnl: f64) {
+        let mut state = self.state.lock().await;
+        Self::roll_day(&mut state);
+        state.realized_pnl += pnl;
+        state.cumulative_pnl += pnl;
+        state.peak_pnl = state.peak_pnl.max(state.cumulative_pnl);
+        state.max_drawdown = state.max_drawdown.max(state.peak_pnl - state.cumulative_pnl);
+    }
+
+    /// How far realized PnL since start is below its peak, now and at worst.
+    pub async fn drawdown(&self) -> Drawdown {
+        let state = self.state.lock().await;
+        Drawdown { current: state.peak_pnl - state.cumulative_pnl, peak: state.peak_pnl, max: state.max_drawdown }
+    }
+
+    /// Returns true if this call tripped the kill switch.
+    pub async fn check_daily_loss(&self, unrealized_pnl: f64) -> bool {
+        let Some(limit) = self.daily_loss_limit() else {
+            return false;
+        };
+        let mut state = self.state.lock().await;
+        Self::roll_day(&mut state);
+        if state.halted {
+            return false;
+        }
+        let daily_pnl = state.realized_pnl + unrealized_pnl;
+        if daily_pnl > -limit {
+            return false;
+        }
+        let reason = format!(
+            "Daily loss limit hit on {}: realized ${:.2} + unrealized ${:.2} = ${:.2} (limit -${:.2})",
+            state.day, state.realized_pnl, unrealized_pnl, daily_pnl, limit
+        );
+        tracing::error!(
+            target: crate::logging::EVENTS,
+            event = "kill_switch",
+            day = %state.day,
+            realized_pnl = state.realized_pnl,
+            unrealized_pnl,
+            limit,
+        );
+        self.halt(&mut state, &reason);
+        true
+    }
+
+    /// Returns true if this call tripped the kill switch on `max_drawdown_usd`.
+    pub async fn check_drawdown(&self) -> bool {
+        let Some(limit) = self.max_drawdown_limit() else {
+            return false;
+        };
+        let mut state = self.state.lock().await;
+        let drawdown = state.peak_pnl - state.cumulative_pnl;
+        if state.halted || drawdown < limit {
+            return false;
+        }
+        let reason = format!(
+            "Max drawdown hit: realized PnL ${:.2} is ${:.2} below its ${:.2} peak (limit ${:.2})",
+            state.cumulative_pnl, drawdown, state.peak_pnl, limit
+        );
+        tracing::error!(
+            target: crate::logging::EVENTS,
+            event = "kill_switch",
+            realized_pnl = state.cumulative_pnl,
+            peak_pnl = state.peak_pnl,
+            drawdown,
+            limit,
+        );
+        self.halt(&mut state, &reason);
+        true
+    }
+
+    fn halt(&self, state: &mut RiskState, reason: &str) {
+        state.halted = true;
+        if let Err(e) = std::fs::write(&self.config.kill_switch_file, format!("{}\n", reason)) {
+            log::error!("Failed to write kill switch file {}: {}", self.config.kill_switch_file.display(), e);
+        }
+        log::error!("🛑 KILL SWITCH: {}", reason);
+        log::error!("   New orders halted. Run with --resume (or restart) to continue trading.");
+    }
+
+    /// Resumes trading once the kill switch file has been removed by `--resume`.
+    /// The day's loss counter restarts from zero and the drawdown peak from the current PnL, so
+    /// the limits apply to losses after the resume.
+    pub async fn poll_resume(&self) -> bool {
+        let mut state = self.state.lock().await;
+        if !state.halted || self.config.kill_switch_file.exists() {
+            return false;
+        }
+        state.halted = false;
+        state.realized_pnl = 0.0;
+        state.peak_pnl = state.cumulative_pnl;
+        log::warn!("▶️  Kill switch cleared — resuming trading");
+        true
+    }
+
+    /// Whether a new Up/Down pair on `asset`, risking `leg_cost` on whichever leg fills alone,
+    /// keeps every correlated group containing `asset` within its limit in both directions.
+    /// `exposure(asset)` gives the (Up, Down) USD already exposed on that asset's market.
+    pub fn check_correlated(&self, asset: &str, leg_cost: f64, exposure: impl Fn(&str) -> (f64, f64)) -> Result<(), CorrelationBreach> {
+        for group in &self.config.correlation_groups {
+            if !group.assets.iter().any(|a| a.eq_ignore_ascii_case(asset)) {
+                continue;
+            }
+            let (up, down) = group.assets.iter().map(|a| exposure(a)).fold((0.0, 0.0), |(u, d), (au, ad)| (u + au, d + ad));
+            for (direction, held) in [("Up", up), ("Down", down)] {
+                if held + leg_cost > group.max_same_side_usd {
+                    return Err(CorrelationBreach {
+                        group: group.assets.join("+"),
+                        direction,
+                        exposure: held,
+                        added: leg_cost,
+                        limit: group.max_same_side_usd,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn roll_day(state: &mut RiskState) {
+        let today = current_day_et();
+        if today != state.day {
+            log::info!("New trading day {} (previous day realized PnL: ${:.2})", today, state.realized_pnl);
+            state.day = today;
+            state.realized_pnl = 0.0;
+        }
+    }
+}
+
+/// Realized PnL since start against its running peak.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Drawdown {
+    pub current: f64,
+    pub peak: f64,
+    /// Largest drawdown so far
+    pub max: f64,
+}
+
+/// A correlated group that a new pair would take over its same-direction limit.
+#[derive(Debug, Clone)]
+pub struct CorrelationBreach {
+    pub group: String,
+    pub direction: &'static str,
+    pub exposure: f64,
+    pub added: f64,
+    pub limit: f64,
+}
+
+impl std::fmt::Display for CorrelationBreach {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} exposure ${:.2} + ${:.2} would exceed ${:.2}",
+            self.group, self.direction, self.exposure, self.added, self.limit
+        )
+    }
+}
+
+/// Admin side of `--resume`: removes the kill switch file so a halted bot resumes on its next tick.
+pub fn request_resume(config: &RiskConfig) -> Result<bool> {
+    if !config.kill_switch_file.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_file(&config.kill_switch_file)
+        .context(format!("Failed to remove kill switch file {}", config.kill_switch_file.display()))?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CorrelationGroup;
+
+    #[tokio::test]
+    async fn drawdown_from_the_running_peak_trips_the_kill_switch() {
+        let kill_switch_file = std::env::temp_dir().join(format!("polymarket-bot-drawdown-{}.kill", std::process::id()));
+        let risk = RiskManager::new(RiskConfig { max_drawdown_usd: Some(10.0), kill_switch_file: kill_switch_file.clone(), ..RiskConfig::default() });
+        for pnl in [5.0, 3.0, -6.0, 2.0, -5.0] {
+            risk.record_realized(pnl).await;
+            assert!(!risk.check_drawdown().await);
+        }
+        assert_eq!(risk.drawdown().await, Drawdown { current: 9.0, peak: 8.0, max: 9.0 });
+        risk.record_realized(-1.0).await;
+        assert!(risk.check_drawdown().await);
+        assert!(risk.is_halted().await && kill_switch_file.exists());
+
+        std::fs::remove_file(&kill_switch_file).unwrap();
+        assert!(risk.poll_resume().await);
+        assert_eq!(risk.drawdown().await.current, 0.0);
+    }
+
+    #[tokio::test]
+    async fn daily_loss_trips_until_resumed_and_resets_with_the_day() {
+        let kill_switch_file = std::env::temp_dir().join(format!("polymarket-bot-daily-loss-{}.kill", std::process::id()));
+        let risk = RiskManager::new(RiskConfig { daily_loss_limit_usd: Some(20.0), kill_switch_file: kill_switch_file.clone(), ..RiskConfig::default() });
+        risk.record_realized(-12.0).await;
+        assert!(!risk.check_daily_loss(-7.0).await);
+        // Unrealized losses count toward the limit
+        assert!(risk.check_daily_loss(-8.0).await);
+        assert!(risk.is_halted().await && kill_switch_file.exists());
+        assert!(!risk.check_daily_loss(-50.0).await, "trips once");
+
+        // Still halted while the file is there; --resume removes it
+        assert!(!risk.poll_resume().await);
+        assert!(request_resume(&risk.config).unwrap());
+        assert!(risk.poll_resume().await);
+        assert!(!risk.is_halted().await);
+        assert_eq!(risk.daily_realized_pnl().await, 0.0);
+
+        // A new ET day starts the loss counter over
+        risk.record_realized(-15.0).await;
+        risk.state.lock().await.day -= chrono::TimeDelta::days(1);
+        assert_eq!(risk.daily_realized_pnl().await, 0.0);
+        assert!(!risk.check_daily_loss(-19.0).await);
+        assert!(!kill_switch_file.exists());
+    }
+
+    #[test]
+    fn correlated_groups_are_capped_per_direction_up_to_the_limit() {
+        let group = |assets: &[&str], max_same_side_usd: f64| CorrelationGroup { assets: assets.iter().map(|a| a.to_string()).collect(), max_same_side_usd };
+        let risk = RiskManager::new(RiskConfig {
+            correlation_groups: vec![group(&["BTC", "ETH"], 20.0), group(&["SOL"], 5.0)],
+            ..RiskConfig::default()
+        });
+        // (Up, Down) USD already exposed per asset
+        let held = |asset: &str| match asset {
+            "BTC" => (8.0, 2.0),
+            "ETH" => (7.0, 0.0),
+            _ => (0.0, 0.0),
+        };
+        let breach = |asset: &str, leg_cost: f64| risk.check_correlated(asset, leg_cost, held).err().map(|b| (b.group, b.direction, b.exposure));
+
+        // Exactly at the limit is allowed
+        assert_eq!(breach("ETH", 5.0), None);
+        assert_eq!(breach("btc", 5.01), Some(("BTC+ETH".to_string(), "Up", 15.0)));
+        assert_eq!(breach("SOL", 5.0), None);
+        assert_eq!(breach("SOL", 5.5), Some(("SOL".to_string(), "Up", 0.0)));
+        // Assets outside every group are not capped
+        assert_eq!(breach("XRP", 1000.0), None);
+
+        let down_heavy = |asset: &str| if asset == "BTC" { (1.0, 18.0) } else { (0.0, 0.0) };
+        let breach = risk.check_correlated("ETH", 3.0, down_heavy).unwrap_err();
+        assert_eq!((breach.direction, breach.exposure), ("Down", 18.0));
+    }
+}
@@ -0,0 +1,303 @@
+use crate::clock;
+use crate::config::{Config, MarketDuration};
+use crate::discovery::MarketDiscovery;
+use crate::watchdog::panic_message;
+use futures_util::FutureExt;
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Mutex;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, watch};
+use tokio::time::{sleep, Duration};
+
+/// Longest the scheduler sleeps between polls, so a clock offset correction is picked up.
+const MAX_WAIT_MS: i64 = 60_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeriodEventKind {
+    /// The period starts within the lookahead: time to discover its market
+    Upcoming,
+    /// The period has begun (the first poll reports every market's current period)
+    Started,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeriodEvent {
+    pub asset: String,
+    pub timeframe: MarketDuration,
+    pub period_start: i64,
+    pub kind: PeriodEventKind,
+}
+
+/// Where a market is: its current period, and the next one once announced as upcoming.
+#[derive(Clone, Copy)]
+struct Tracked {
+    current: i64,
+    announced: Option<i64>,
+}
+
+/// Tracks the ET-aligned periods of every configured (asset, timeframe) market and broadcasts
+/// rollovers: `Upcoming` `lookahead_secs` before a period starts, then `Started` at its start.
+/// Waits until the next such moment instead of polling every tick.
+pub struct PeriodScheduler {
+    markets: Vec<(String, MarketDuration)>,
+    lookahead_secs: i64,
+    tracked: Mutex<HashMap<(String, MarketDuration), Tracked>>,
+    events: broadcast::Sender<PeriodEvent>,
+}
+
+impl PeriodScheduler {
+    pub fn new(markets: Vec<(String, MarketDuration)>, lookahead_secs: i64) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self { markets, lookahead_secs, tracked: Mutex::new(HashMap::new()), events }
+    }
+
+    /// Every configured market on each of its timeframes.
+    pub fn for_config(config: &Config, lookahead_secs: i64) -> Self {
+        let markets = config
+            .markets
+            .iter()
+            .flat_map(|m| m.timeframes.iter().map(|tf| (m.asset.to_uppercase(), *tf)))
+            .collect();
+        Self::new(markets, lookahead_secs)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PeriodEvent> {
+        self.events.subscribe()
+    }
+
+    /// Start of the current period of `asset` on `timeframe`, once the scheduler has polled.
+    pub fn current(&self, asset: &str, timeframe: MarketDuration) -> Option<i64> {
+        let tracked = self.tracked.lock().unwrap();
+        tracked.get(&(asset.to_uppercase(), timeframe)).map(|t| t.current)
+    }
+
+    /// Events due at unix time `now`, each reported once. After a gap (a suspended host, a
+    /// clock jump) only the latest period is reported, not every one missed.
+    pub fn poll(&self, now: i64) -> Vec<PeriodEvent> {
+        let mut tracked = self.tracked.lock().unwrap();
+        let mut events = Vec::new();
+        for (asset, timeframe) in &self.markets {
+            let event = |period_start, kind| PeriodEvent { asset: asset.clone(), timeframe: *timeframe, period_start, kind };
+            let current = MarketDiscovery::period_start_et(*timeframe, now);
+            let next = MarketDiscovery::next_period_start_et(*timeframe, current);
+            let market = tracked.entry((asset.clone(), *timeframe)).or_insert(Tracked { current: i64::MIN, announced: None });
+            if market.current != current {
+                *market = Tracked { current, announced: None };
+                events.push(event(current, PeriodEventKind::Started));
+            }
+            if now >= next - self.lookahead_secs && market.announced != Some(next) {
+                market.announced = Some(next);
+                events.push(event(next, PeriodEventKind::Upcoming));
+            }
+        }
+        events
+    }
+
+    /// Unix time of the next event after a poll at `now`; `now` if a market hasn't been polled.
+    pub fn next_event_at(&self, now: i64) -> i64 {
+        let tracked = self.tracked.lock().unwrap();
+        self.markets
+            .iter()
+            .map(|key| match tracked.get(key) {
+                Some(market) => {
+                    let next = MarketDiscovery::next_period_start_et(key.1, market.current);
+                    if market.announced == Some(next) { next } else { next - self.lookahead_secs }
+                }
+                None => now,
+            })
+            .min()
+            .unwrap_or(now + MAX_WAIT_MS / 1000)
+            .max(now)
+    }
+
+    /// Broadcasts events on the bot's clock until `shutdown` flips.
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        while !*shutdown.borrow() {
+            let now = clock::now();
+            for event in self.poll(now) {
+                log::debug!("🗓️  {} {} period {} {:?}", event.asset, event.timeframe.label(), event.period_start, event.kind);
+                // No subscribers is fine
+                let _ = self.events.send(event);
+            }
+            let wait_ms = (self.next_event_at(now) * 1000 - clock::now_ms()).clamp(0, MAX_WAIT_MS);
+            tokio::select! {
+                _ = sleep(Duration::from_millis(wait_ms as u64)) => {}
+                _ = shutdown.changed() => break,
+            }
+        }
+    }
+}
+
+/// Runs `handle` on the events from `events`, on a task per market so a slow handler (an order
+/// going out, a lookup timing out) holds up only its own market. Each market's queue holds one
+/// event: one arriving while the handler is busy replaces any still waiting, so the handler
+/// always picks up the newest and never works through a backlog of stale ones.
+pub async fn dispatch_latest<F, Fut>(mut events: broadcast::Receiver<PeriodEvent>, mut shutdown: watch::Receiver<bool>, handle: F)
+where
+    F: Fn(PeriodEvent) -> Fut + Clone + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut slots: HashMap<(String, MarketDuration), watch::Sender<Option<PeriodEvent>>> = HashMap::new();
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => event,
+            _ = shutdown.changed() => break,
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                log::warn!("Period events fell behind; {} dropped", missed);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let slot = slots.entry((event.asset.clone(), event.timeframe)).or_insert_with(|| {
+            let (slot, mut latest) = watch::channel::<Option<PeriodEvent>>(None);
+            let handle = handle.clone();
+            tokio::spawn(async move {
+                // Ends once the dispatcher, and with it the sender, is gone
+                while latest.changed().await.is_ok() {
+                    let event = latest.borrow_and_update().clone();
+                    if let Some(event) = event {
+                        let market = event.asset.clone();
+                        // A panicking handler would otherwise leave the market's slot with no worker
+                        if let Err(panic) = AssertUnwindSafe(handle(event)).catch_unwind().await {
+                            log::error!("💥 {} | Period event handler panicked: {}", market, panic_message(panic.as_ref()));
+                            crate::metrics::metrics().inc_task_panic("period event", &market);
+                        }
+                    }
+                }
+            });
+            slot
+        });
+        slot.send_replace(Some(event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PeriodEventKind::{Started, Upcoming};
+
+    /// 2026-01-06 14:00:00 ET, on the hour
+    const HOUR: i64 = 1767726000;
+
+    fn scheduler() -> PeriodScheduler {
+        PeriodScheduler::new(vec![("BTC".to_string(), MarketDuration::M15), ("BTC".to_string(), MarketDuration::H1)], 240)
+    }
+
+    fn kinds(events: &[PeriodEvent]) -> Vec<(MarketDuration, i64, PeriodEventKind)> {
+        events.iter().map(|e| (e.timeframe, e.period_start, e.kind)).collect()
+    }
+
+    #[test]
+    fn first_poll_reports_current_periods() {
+        let s = scheduler();
+        assert_eq!(s.current("btc", MarketDuration::M15), None);
+        let events = s.poll(HOUR + 100);
+        assert_eq!(kinds(&events), vec![(MarketDuration::M15, HOUR, Started), (MarketDuration::H1, HOUR, Started)]);
+        assert_eq!(s.current("btc", MarketDuration::M15), Some(HOUR));
+        assert!(s.poll(HOUR + 200).is_empty());
+    }
+
+    #[test]
+    fn announces_upcoming_periods_once_within_the_lookahead() {
+        let s = scheduler();
+        s.poll(HOUR);
+        assert!(s.poll(HOUR + 900 - 241).is_empty());
+        assert_eq!(kinds(&s.poll(HOUR + 900 - 240)), vec![(MarketDuration::M15, HOUR + 900, Upcoming)]);
+        assert!(s.poll(HOUR + 900 - 1).is_empty());
+        assert_eq!(kinds(&s.poll(HOUR + 900)), vec![(MarketDuration::M15, HOUR + 900, Started)]);
+    }
+
+    #[test]
+    fn rolls_each_timeframe_on_its_own_boundary() {
+        let s = scheduler();
+        s.poll(HOUR);
+        let started: Vec<_> = (1..=3600)
+            .flat_map(|t| s.poll(HOUR + t))
+            .filter(|e| e.kind == Started)
+            .map(|e| (e.timeframe, e.period_start))
+            .collect();
+        assert_eq!(
+            started,
+            vec![
+                (MarketDuration::M15, HOUR + 900),
+                (MarketDuration::M15, HOUR + 1800),
+                (MarketDuration::M15, HOUR + 2700),
+                (MarketDuration::M15, HOUR + 3600),
+                (MarketDuration::H1, HOUR + 3600),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_only_the_latest_period_after_a_gap() {
+        let s = scheduler();
+        s.poll(HOUR);
+        let events = s.poll(HOUR + 2 * 3600 + 60);
+        assert_eq!(
+            kinds(&events),
+            vec![(MarketDuration::M15, HOUR + 7200, Started), (MarketDuration::H1, HOUR + 7200, Started)]
+        );
+    }
+
+    #[test]
+    fn sleeps_until_the_next_lookahead_or_boundary() {
+        let s = scheduler();
+        assert_eq!(s.next_event_at(HOUR + 10), HOUR + 10);
+        s.poll(HOUR + 10);
+        assert_eq!(s.next_event_at(HOUR + 10), HOUR + 900 - 240);
+        s.poll(HOUR + 900 - 240);
+        assert_eq!(s.next_event_at(HOUR + 900 - 240), HOUR + 900);
+    }
+
+    #[tokio::test]
+    async fn dispatch_keeps_only_the_newest_event_per_busy_market() {
+        let (tx, rx) = broadcast::channel(16);
+        let (_shutdown_tx, shutdown) = watch::channel(false);
+        let handled = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let log = std::sync::Arc::clone(&handled);
+        tokio::spawn(dispatch_latest(rx, shutdown, move |event: PeriodEvent| {
+            let log = std::sync::Arc::clone(&log);
+            async move {
+                log.lock().unwrap().push((event.asset.clone(), event.period_start));
+                if event.asset == "BTC" {
+                    sleep(Duration::from_millis(200)).await;
+                }
+            }
+        }));
+        let event = |asset: &str, period_start| PeriodEvent {
+            asset: asset.to_string(),
+            timeframe: MarketDuration::M15,
+            period_start,
+            kind: Upcoming,
+        };
+
+        tx.send(event("BTC", HOUR)).unwrap();
+        sleep(Duration::from_millis(50)).await;
+        for period in 1..=3 {
+            tx.send(event("BTC", HOUR + period * 900)).unwrap();
+        }
+        tx.send(event("ETH", HOUR)).unwrap();
+        // ETH goes ahead while BTC's handler is still busy
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(handled.lock().unwrap().last(), Some(&("ETH".to_string(), HOUR)));
+
+        sleep(Duration::from_millis(400)).await;
+        let btc: Vec<i64> = handled.lock().unwrap().iter().filter(|(a, _)| a == "BTC").map(|(_, p)| *p).collect();
+        assert_eq!(btc, vec![HOUR, HOUR + 2700]);
+    }
+
+    #[test]
+    fn next_period_spans_dst_changes() {
+        // Noon 2026-03-07 to noon 03-08 ET is 23 hours, across spring-forward
+        let noon = MarketDiscovery::period_start_et(MarketDuration::D1, 1772906400);
+        let next = MarketDiscovery::next_period_start_et(MarketDuration::D1, noon);
+        assert_eq!(next - noon, 23 * 3600);
+        let four_h = MarketDiscovery::period_start_et(MarketDuration::H4, HOUR);
+        assert_eq!(MarketDiscovery::next_period_start_et(MarketDuration::H4, four_h), four_h + 4 * 3600);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::SignalConfig;
+use crate::trend::Trend;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MarketSignal {
@@ -42,3 +43,52 @@ pub fn is_danger_signal(cfg: &SignalConfig, matched_token_price: f64) -> bool {
     }
     matched_token_price <= cfg.danger_price
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OneSideRiskMode {
+    /// Sell the matched side once its price falls to danger_price
+    Price,
+    /// Sell the matched side after danger_time_passed minutes
+    Time,
+    None,
+}
+
+pub fn one_side_risk_mode(cfg: &SignalConfig) -> OneSideRiskMode {
+    match cfg.one_side_buy_risk_management.to_lowercase().as_str() {
+        "price" | "sell_at_danger_price" => OneSideRiskMode::Price,
+        "time" | "sell_after_danger_time_passed" => OneSideRiskMode::Time,
+        _ => OneSideRiskMode::None,
+    }
+}
+
+/// Mid-market limit prices (unrounded): the cheaper side at its current price, the other at
+/// `mid_market_pair_total` minus it.
+pub fn mid_market_prices(cfg: &SignalConfig, up_price: f64, down_price: f64) -> (f64, f64) {
+    let total = cfg.mid_market_pair_total;
+    if up_price <= down_price {
+        (up_price, total - up_price)
+    } else {
+        (total - down_price, down_price)
+    }
+}
+
+/// Win probabilities of Up and Down implied by their prices, scaled to sum to 1.
+pub fn implied_probabilities(up_price: f64, down_price: f64) -> (f64, f64) {
+    let total = up_price + down_price;
+    if up_price < 0.0 || down_price < 0.0 || total <= 0.0 {
+        return EVEN_ODDS;
+    }
+    (up_price / total, down_price / total)
+}
+
+/// Odds for a market that hasn't started trading yet.
+pub const EVEN_ODDS: (f64, f64) = (0.5, 0.5);
+
+/// A Good signal turns Bad while the market is trending.
+pub fn with_trend(signal: MarketSignal, trend: Trend) -> MarketSignal {
+    if signal == MarketSignal::Good && trend != Trend::Flat {
+        MarketSignal::Bad
+    } else {
+        signal
+    }
+}
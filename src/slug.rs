@@ -0,0 +1,166 @@
+use crate::config::{MarketConfig, MarketDuration};
+use crate::discovery::MarketDiscovery;
+use chrono::{Datelike, TimeZone, Timelike};
+use chrono_tz::America::New_York;
+
+/// Polymarket's slug format for each timeframe.
+pub fn default_template(duration: MarketDuration) -> &'static str {
+    match duration {
+        MarketDuration::M5 => "{asset}-updown-5m-{ts}",
+        MarketDuration::M15 => "{asset}-updown-15m-{ts}",
+        MarketDuration::H1 => "{name}-up-or-down-{month}-{day}-{hour}{ampm}-et",
+        MarketDuration::H4 => "{asset}-updown-4h-{ts}",
+        // Daily markets are named after the day they resolve, at noon ET
+        MarketDuration::D1 => "{name}-up-or-down-on-{end_month}-{end_day}",
+    }
+}
+
+/// The market's own template for `duration` (`slug_templates`), else the built-in one.
+pub fn template(market: &MarketConfig, duration: MarketDuration) -> &str {
+    market
+        .slug_templates
+        .get(&duration)
+        .map(String::as_str)
+        .unwrap_or_else(|| default_template(duration))
+}
+
+/// Fills a slug template. Dates and hours are ET; `{end_month}`/`{end_day}` are the date the
+/// period ends.
+pub fn render(template: &str, market: &MarketConfig, duration: MarketDuration, period_start_et: i64) -> String {
+    let start = New_York.timestamp_opt(period_start_et, 0).single().unwrap();
+    let end = New_York.timestamp_opt(period_start_et + duration.nominal_secs(), 0).single().unwrap();
+    let (hour12, am_pm) = match start.hour() {
+        0 => (12, "am"),
+        h @ 1..=11 => (h, "am"),
+        12 => (12, "pm"),
+        h => (h - 12, "pm"),
+    };
+    template
+        .replace("{asset}", &market.asset.to_lowercase())
+        .replace("{name}", &market.name())
+        .replace("{ts}", &period_start_et.to_string())
+        .replace("{month}", month_name(start.month()))
+        .replace("{day}", &start.day().to_string())
+        .replace("{hour}", &hour12.to_string())
+        .replace("{ampm}", am_pm)
+        .replace("{end_month}", month_name(end.month()))
+        .replace("{end_day}", &end.day().to_string())
+}
+
+/// Start of the period within two days of `near` whose slug under `template` is `slug`. `None`
+/// if no period renders to it, or if several do: the hour repeated when clocks fall back has
+/// two periods with the same 1h slug.
+pub fn period_start(template: &str, market: &MarketConfig, duration: MarketDuration, slug: &str, near: i64) -> Option<i64> {
+    let window = 2 * 24 * 60 * 60;
+    let mut period = MarketDiscovery::period_start_et(duration, near - window);
+    let mut found = None;
+    while period <= near + window {
+        if render(template, market, duration, period) == slug {
+            if found.is_some() {
+                return None;
+            }
+            found = Some(period);
+        }
+        period = MarketDiscovery::next_period_start_et(duration, period);
+    }
+    found
+}
+
+fn month_name(month: u32) -> &'static str {
+    match month {
+        1 => "january",
+        2 => "february",
+        3 => "march",
+        4 => "april",
+        5 => "may",
+        6 => "june",
+        7 => "july",
+        8 => "august",
+        9 => "september",
+        10 => "october",
+        11 => "november",
+        12 => "december",
+        _ => "january",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    /// Unix seconds of a wall-clock time in ET; the earlier one when it occurs twice.
+    fn et(y: i32, m: u32, d: u32, h: u32) -> i64 {
+        let local = NaiveDate::from_ymd_opt(y, m, d).unwrap().and_hms_opt(h, 0, 0).unwrap();
+        New_York.from_local_datetime(&local).earliest().unwrap().timestamp()
+    }
+
+    fn hourly(market: &MarketConfig, from: i64, hours: i64) -> Vec<String> {
+        (0..hours)
+            .map(|h| render(default_template(MarketDuration::H1), market, MarketDuration::H1, from + h * 3600))
+            .collect()
+    }
+
+    #[test]
+    fn hourly_slugs_follow_et_across_dst_and_date_boundaries() {
+        let btc = MarketConfig::new("BTC", "bitcoin");
+        // Spring forward: 2am doesn't exist
+        assert_eq!(hourly(&btc, et(2026, 3, 8, 0), 3), [
+            "bitcoin-up-or-down-march-8-12am-et",
+            "bitcoin-up-or-down-march-8-1am-et",
+            "bitcoin-up-or-down-march-8-3am-et",
+        ]);
+        // Fall back: 1am happens twice
+        assert_eq!(hourly(&btc, et(2026, 11, 1, 0), 4), [
+            "bitcoin-up-or-down-november-1-12am-et",
+            "bitcoin-up-or-down-november-1-1am-et",
+            "bitcoin-up-or-down-november-1-1am-et",
+            "bitcoin-up-or-down-november-1-2am-et",
+        ]);
+        assert_eq!(hourly(&btc, et(2026, 6, 15, 11), 2), [
+            "bitcoin-up-or-down-june-15-11am-et",
+            "bitcoin-up-or-down-june-15-12pm-et",
+        ]);
+        assert_eq!(hourly(&btc, et(2026, 12, 31, 23), 2), [
+            "bitcoin-up-or-down-december-31-11pm-et",
+            "bitcoin-up-or-down-january-1-12am-et",
+        ]);
+    }
+
+    #[test]
+    fn daily_slugs_name_the_day_the_period_ends() {
+        let eth = MarketConfig::new("ETH", "ethereum");
+        let daily = |start| render(default_template(MarketDuration::D1), &eth, MarketDuration::D1, start);
+        assert_eq!(daily(et(2026, 12, 31, 12)), "ethereum-up-or-down-on-january-1");
+        // 23h and 25h periods still end on the next day
+        assert_eq!(daily(et(2026, 3, 7, 12)), "ethereum-up-or-down-on-march-8");
+        assert_eq!(daily(et(2026, 10, 31, 12)), "ethereum-up-or-down-on-november-1");
+    }
+
+    #[test]
+    fn slugs_round_trip_to_their_period() {
+        let mut sol = MarketConfig::new("SOL", "solana");
+        sol.slug_templates.insert(MarketDuration::H1, "{asset}-{month}-{day}-{hour}{ampm}".to_string());
+        let fall_back_1am = et(2026, 11, 1, 1);
+        for (duration, from, to) in [
+            (MarketDuration::H1, et(2026, 3, 7, 20), et(2026, 3, 9, 4)),
+            (MarketDuration::H1, et(2026, 10, 31, 20), et(2026, 11, 2, 4)),
+            (MarketDuration::H1, et(2026, 12, 31, 20), et(2027, 1, 1, 4)),
+            (MarketDuration::D1, et(2026, 3, 1, 12), et(2026, 3, 14, 12)),
+            (MarketDuration::D1, et(2026, 12, 25, 12), et(2027, 1, 3, 12)),
+            (MarketDuration::M15, et(2026, 11, 1, 0), et(2026, 11, 1, 3)),
+            (MarketDuration::H4, et(2026, 3, 7, 20), et(2026, 3, 9, 4)),
+        ] {
+            let template = template(&sol, duration);
+            let mut period = from;
+            while period < to {
+                let slug = render(template, &sol, duration, period);
+                let ambiguous = duration == MarketDuration::H1 && (period == fall_back_1am || period == fall_back_1am + 3600);
+                let expected = (!ambiguous).then_some(period);
+                assert_eq!(period_start(template, &sol, duration, &slug, period), expected, "{}", slug);
+                period = MarketDiscovery::next_period_start_et(duration, period);
+            }
+        }
+        assert_eq!(render(template(&sol, MarketDuration::H1), &sol, MarketDuration::H1, et(2026, 7, 4, 0)), "sol-july-4-12am");
+    }
+}
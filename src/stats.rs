@@ -0,0 +1,156 @@
+use crate::config::{MarketDuration, StreakSizingConfig};
+use crate::display;
+use std::collections::{BTreeMap, HashMap};
+
+/// Traded periods of one market and timeframe, counted as each one resolves.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MarketStats {
+    pub periods: u32,
+    /// Periods that ended with positive realized PnL
+    pub won: u32,
+    pub pnl: f64,
+    /// Losing (or flat) periods since the last win
+    pub loss_streak: u32,
+}
+
+impl MarketStats {
+    pub fn win_rate(&self) -> f64 {
+        if self.periods == 0 { 0.0 } else { self.won as f64 / self.periods as f64 }
+    }
+
+    pub fn avg_pnl(&self) -> f64 {
+        if self.periods == 0 { 0.0 } else { self.pnl / self.periods as f64 }
+    }
+}
+
+/// Realized PnL per period, by condition ID until the period resolves, then folded into
+/// its market's `MarketStats`.
+pub struct StatsTracker {
+    sizing: StreakSizingConfig,
+    open: HashMap<String, f64>,
+    markets: BTreeMap<(String, String), MarketStats>,
+    /// Market -> fraction of `shares` it trades under `strategy.streak_sizing`; absent at full size
+    scales: HashMap<String, f64>,
+}
+
+impl StatsTracker {
+    pub fn new(sizing: StreakSizingConfig) -> Self {
+        Self { sizing, open: HashMap::new(), markets: BTreeMap::new(), scales: HashMap::new() }
+    }
+
+    /// Fraction of `shares` to trade on `market`: 1 unless `strategy.streak_sizing` has cut it.
+    pub fn size_scale(&self, market: &str) -> f64 {
+        self.scales.get(market).copied().unwrap_or(1.0)
+    }
+
+    /// Adds PnL realized before resolution (sells) to the period of `condition_id`.
+    pub fn realize(&mut self, condition_id: &str, pnl: f64) {
+        *self.open.entry(condition_id.to_string()).or_default() += pnl;
+    }
+
+    /// Closes the period of `condition_id` with the PnL of its resolution. Returns the market's
+    /// size scale before and after when the period changed it.
+    pub fn resolve(&mut self, market: &str, timeframe: &str, condition_id: &str, pnl: f64) -> Option<(f64, f64)> {
+        let pnl = pnl + self.open.remove(condition_id).unwrap_or(0.0);
+        let stats = self.markets.entry((market.to_string(), timeframe.to_string())).or_default();
+        stats.periods += 1;
+        stats.pnl += pnl;
+        if pnl > 0.0 {
+            stats.won += 1;
+            stats.loss_streak = 0;
+        } else {
+            stats.loss_streak += 1;
+        }
+        let loss_streak = stats.loss_streak;
+        if !self.sizing.enabled {
+            return None;
+        }
+        let before = self.size_scale(market);
+        let after = if pnl > 0.0 {
+            (before + self.sizing.restore_step).min(1.0)
+        } else if loss_streak.is_multiple_of(self.sizing.losses) {
+            (before * self.sizing.cut).max(self.sizing.min_scale)
+        } else {
+            before
+        };
+        if after >= 1.0 {
+            self.scales.remove(market);
+        } else {
+            self.scales.insert(market.to_string(), after);
+        }
+        (after != before).then_some((before, after))
+    }
+
+    pub fn print(&self, account: Option<&str>) {
+        if self.markets.is_empty() {
+            return;
+        }
+        eprintln!("📊 Market stats{}", account.map(|a| format!(" — account {}", a)).unwrap_or_default());
+        eprintln!("   {:<6} {:<5} {:>7} {:>5} {:>9} {:>10} {:>8}", "market", "tf", "periods", "won", "win rate", "avg PnL", "streak");
+        for ((market, timeframe), s) in &self.markets {
+            eprintln!("   {:<6} {:<5} {:>7} {:>5} {:>8.0}% {:>10} {:>8}",
+                market, timeframe, s.periods, s.won, s.win_rate() * 100.0, display::usd(s.avg_pnl()),
+                if s.loss_streak > 0 { format!("L{}", s.loss_streak) } else { "-".to_string() });
+        }
+    }
+}
+
+/// Timeframe label of a period lasting `secs`, within 5% to allow for DST days; `event` for
+/// anything that isn't a rolling timeframe.
+pub fn timeframe(secs: u64) -> &'static str {
+    [MarketDuration::M5, MarketDuration::M15, MarketDuration::H1, MarketDuration::H4, MarketDuration::D1]
+        .into_iter()
+        .find(|d| (d.nominal_secs() - secs as i64).abs() <= d.nominal_secs() / 20)
+        .map_or("event", |d| d.label())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sells_and_resolution_make_one_period() {
+        let mut stats = StatsTracker::new(StreakSizingConfig::default());
+        stats.realize("0xa", -1.5);
+        stats.resolve("BTC", "15m", "0xa", 1.0);
+        stats.resolve("BTC", "15m", "0xb", -0.2);
+        let btc = stats.markets[&("BTC".to_string(), "15m".to_string())];
+        assert_eq!((btc.periods, btc.won, btc.loss_streak), (2, 0, 2));
+        assert!((btc.avg_pnl() + 0.35).abs() < 1e-9);
+
+        stats.realize("0xc", 2.0);
+        stats.resolve("BTC", "15m", "0xc", 0.0);
+        let btc = stats.markets[&("BTC".to_string(), "15m".to_string())];
+        assert_eq!((btc.won, btc.loss_streak), (1, 0));
+
+        // Sizing off: streaks are counted but never cut the size
+        assert_eq!(stats.size_scale("BTC"), 1.0);
+
+        assert_eq!(timeframe(900), "15m");
+        assert_eq!(timeframe(23 * 3600), "1d");
+        assert_eq!(timeframe(3 * 86400), "event");
+    }
+
+    #[test]
+    fn loss_streaks_halve_the_size_and_wins_restore_it() {
+        let mut stats = StatsTracker::new(StreakSizingConfig { enabled: true, losses: 2, ..StreakSizingConfig::default() });
+        let mut period = 0;
+        let mut resolve = |stats: &mut StatsTracker, pnl: f64| {
+            period += 1;
+            stats.resolve("ETH", "15m", &period.to_string(), pnl)
+        };
+        assert_eq!(resolve(&mut stats, -1.0), None);
+        assert_eq!(resolve(&mut stats, -1.0), Some((1.0, 0.5)));
+        assert_eq!(resolve(&mut stats, -1.0), None);
+        assert_eq!(resolve(&mut stats, -1.0), Some((0.5, 0.25)));
+        // Floored at min_scale
+        resolve(&mut stats, -1.0);
+        assert_eq!(resolve(&mut stats, -1.0), None);
+        assert_eq!(resolve(&mut stats, 1.0), Some((0.25, 0.5)));
+        assert_eq!(stats.size_scale("ETH"), 0.5);
+        assert_eq!(stats.size_scale("BTC"), 1.0);
+        resolve(&mut stats, 1.0);
+        assert_eq!(resolve(&mut stats, 1.0), Some((0.75, 1.0)));
+        assert_eq!(resolve(&mut stats, 1.0), None);
+    }
+}
@@ -1,23 +1,55 @@
-use crate::api::PolymarketApi;
-use crate::config::Config;
+use crate::allocation::Allocator;
+use crate::api::{self, PolymarketApi};
+use crate::apierror::ApiError;
+use crate::blackout::Blackouts;
+use crate::clock;
+use crate::config::{Config, MarketDuration, MarketFamily, ProfitWindow, SimulatedWinner};
+use crate::control::{MarketError, MarketStatus, PositionStatus, StatusReport};
 use crate::discovery::MarketDiscovery;
+use crate::display;
+use crate::feed::MarketFeed;
+use crate::instance;
+use crate::exposure::{self, Exposure, Leg};
+use crate::external::{Bias, ExternalSignal, ExternalSignals, SignalPost};
+use crate::fees::FeeSchedule;
+use crate::fill::FillModel;
+use crate::ledger::{self, Decision, Ledger, LedgerEntry, Rationale};
+use crate::logging::EVENTS;
+use crate::metrics::metrics;
+use crate::model::{ModelGate, SideModel};
+use crate::profit::{ProfitTracker, Rollup};
+use crate::ratecontrol::{RateControl, Throttled};
+use crate::report::Report;
 use crate::models::*;
-use crate::signals::{self, MarketSignal};
+use crate::momentum::{self, SpotCandles};
+use crate::monitor::{self, book_levels};
+use crate::orderqueue::OrderPriority;
+use crate::plugin::{Plugin, PluginAction};
+use crate::risk::{Drawdown, RiskManager};
+use crate::signals::{self, MarketSignal, OneSideRiskMode};
+use crate::stats::{self, StatsTracker};
+use crate::trend::{self, PriceHistory, Trend};
+use crate::watchdog::{health, panic_message};
 use anyhow::Result;
-use chrono::Utc;
-use chrono_tz::America::New_York;
-use std::collections::HashMap;
+use serde::Serialize;
+use chrono::{TimeZone, Utc};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{watch, Mutex};
 use tokio::time::{sleep, Duration};
-use log::{warn, info, error, debug};
+use futures_util::FutureExt;
+use std::any::Any;
+use std::panic::AssertUnwindSafe;
+use tracing::Instrument;
+use log::warn;
 
 /// 15-minute market duration in seconds
-const MARKET_DURATION_SECS: i64 = 900;
-const MARKET_DURATION_SECS_U64: u64 = 900;
+pub const MARKET_DURATION_SECS: i64 = 900;
 
 pub struct PreLimitStrategy {
     api: Arc<PolymarketApi>,
+    /// Prices and order books, shared with the other accounts' strategies
+    feed: Arc<MarketFeed>,
     config: Config,
     discovery: MarketDiscovery,
     states: Arc<Mutex<HashMap<String, PreLimitOrderState>>>,
@@ -25,27 +57,200 @@ pub struct PreLimitStrategy {
     total_profit: Arc<Mutex<f64>>,
     trades: Arc<Mutex<HashMap<String, CycleTrade>>>,
     closure_checked: Arc<Mutex<HashMap<String, bool>>>,
-    period_profit: Arc<Mutex<f64>>,
+    profit: std::sync::Mutex<ProfitTracker>,
+    risk: RiskManager,
+    rate_control: RateControl,
+    allocator: Allocator,
+    blackouts: Blackouts,
+    /// Asset -> last period whose blackout was logged
+    blackout_noted: std::sync::Mutex<HashMap<String, i64>>,
+    ledger: Option<Ledger>,
+    fills: std::sync::Mutex<FillModel>,
+    fees: FeeSchedule,
+    price_history: Mutex<HashMap<String, PriceHistory>>,
+    /// Spot candles for `strategy.signal.momentum` and reference resolution; None when both are off
+    spot: Option<SpotCandles>,
+    last_errors: std::sync::Mutex<HashMap<String, MarketError>>,
+    /// Markets (asset, timeframe) where new orders are suspended through the control API
+    paused: std::sync::Mutex<HashSet<(String, MarketDuration)>>,
+    /// Markets whose processing panicked with `watchdog.restart_on_panic` off; skipped until resumed
+    crashed: std::sync::Mutex<HashSet<String>>,
+    /// Serializes `check_market_closure` between the closure loop and manual redeems
+    closure_running: Mutex<()>,
+    /// Resolved winners waiting for the next redemption batch
+    redeem_queue: Mutex<Vec<PendingRedeem>>,
+    /// Ended markets that haven't resolved yet, by condition ID
+    awaiting_resolution: std::sync::Mutex<HashMap<String, ResolutionWait>>,
+    /// Name from `accounts` when this instance trades one of several accounts
+    account: Option<String>,
+    /// Last USDC balance read by `refresh_balance`
+    usdc_balance: std::sync::Mutex<Option<f64>>,
+    /// Simulated account balance when `strategy.starting_balance_usd` is set in simulation mode
+    sim_balance: std::sync::Mutex<Option<f64>>,
+    /// When each asset's latest market snapshot was requested and when it arrived
+    last_snapshot: std::sync::Mutex<HashMap<String, (std::time::Instant, std::time::Instant)>>,
+    /// When `reconcile_fills` last ran
+    last_fill_check: std::sync::Mutex<Option<std::time::Instant>>,
+    /// (condition ID, outcome, side) totals already reported as not matching the ledger
+    reported_fill_mismatches: std::sync::Mutex<HashSet<(String, String, String)>>,
+    /// The balance is above `treasury.target_bankroll_usd` and that has been alerted on
+    treasury_alerted: std::sync::Mutex<bool>,
+    /// Win rate, average PnL and loss streak of each market's resolved periods
+    stats: std::sync::Mutex<StatsTracker>,
+    /// Up-probability model for the mid-market EV gate (`strategy.signal.model`)
+    model: Option<ModelGate>,
+    /// External command deciding mid-market buys in place of the pair (`strategy.plugin`)
+    plugin: Option<Plugin>,
+    /// Biases posted to the control server (`control.signals`)
+    signals: std::sync::Mutex<ExternalSignals>,
+}
+
+/// One consistent snapshot of a strategy's markets, taken under its state locks at one instant.
+/// The control API, the TUI and anything built on top of the strategy read positions through
+/// this instead of the strategy's internals.
+#[derive(Debug, Clone, Serialize)]
+pub struct TraderState {
+    pub account: Option<String>,
+    /// Unix seconds
+    pub taken_at: i64,
+    pub simulated: bool,
+    pub trading_halted: bool,
+    pub realized_pnl: f64,
+    pub daily_realized_pnl: f64,
+    /// One per market with orders or a position, in configured order
+    pub positions: Vec<PositionView>,
+    /// Up-price trend of every configured market over the current period
+    pub trends: BTreeMap<String, Trend>,
+}
+
+/// A market's orders and position as of a `TraderState`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionView {
+    pub asset: String,
+    pub condition_id: String,
+    pub period_start: i64,
+    pub expiry: i64,
+    pub up_token_id: String,
+    pub down_token_id: String,
+    /// Shares per side, sized when the orders were placed
+    pub shares: f64,
+    pub up_filled: bool,
+    pub down_filled: bool,
+    /// Limit price per side, which is also the average fill price
+    pub up_price: f64,
+    pub down_price: f64,
+    /// Up + Down price including fees
+    pub cost_per_pair: f64,
+    pub sold_side: Option<String>,
+    /// Shares of the held winner sold into a late spike
+    pub spike_sold: f64,
+    /// Merged, sold or otherwise finished; only waiting on resolution
+    pub closed: bool,
+    /// Sold early by the one-side or sell-opposite logic
+    pub risk_sold: bool,
+    /// Unix seconds when only one side had filled
+    pub one_side_matched_at: Option<i64>,
+    pub trend: Trend,
+}
+
+/// Retry state for an ended market with no winner yet.
+#[derive(Debug, Default)]
+struct ResolutionWait {
+    attempts: u32,
+    /// Unix seconds of the next `get_market` check
+    next_check: u64,
+    /// A "pending_resolution" ledger row has been written
+    marked: bool,
+    alerted: bool,
+}
+
+/// A resolved winning position waiting to be redeemed on-chain.
+#[derive(Debug, Clone)]
+struct PendingRedeem {
+    condition_id: String,
+    token_id: String,
+    outcome: String,
+    /// Payout in USD
+    value: f64,
+    resolved_at: i64,
+}
+
+/// A side closed out by `sell_side`, over all its fills.
+struct SideSale {
+    shares: f64,
+    avg_price: f64,
+    fees: f64,
 }
 
 #[derive(Debug, Clone)]
 struct CycleTrade {
+    market: String,
     condition_id: String,
     period_timestamp: u64,
     market_duration_secs: u64,
-    up_token_id: Option<String>,
-    down_token_id: Option<String>,
-    up_shares: f64,
-    down_shares: f64,
-    up_avg_price: f64,
-    down_avg_price: f64,
+    /// One leg per outcome; Up/Down markets have an "Up" and a "Down" leg
+    legs: Vec<TradeLeg>,
+    decision: Option<Decision>,
+}
+
+impl CycleTrade {
+    fn leg(&self, outcome: &str) -> Option<&TradeLeg> {
+        self.legs.iter().find(|l| l.outcome == outcome)
+    }
+
+    fn leg_mut(&mut self, outcome: &str) -> Option<&mut TradeLeg> {
+        self.legs.iter_mut().find(|l| l.outcome == outcome)
+    }
+
+    fn shares(&self, outcome: &str) -> f64 {
+        self.leg(outcome).map_or(0.0, |l| l.shares)
+    }
+
+    fn avg_price(&self, outcome: &str) -> f64 {
+        self.leg(outcome).map_or(0.0, |l| l.avg_price)
+    }
+
+    fn total_shares(&self) -> f64 {
+        self.legs.iter().map(|l| l.shares).sum()
+    }
+
+    /// Cost per Up/Down pair, the binary case of the ledger's `cost_per_pair`.
+    fn pair_cost(&self, fees: &FeeSchedule) -> f64 {
+        fees.pair_cost(self.avg_price("Up"), self.avg_price("Down"))
+    }
 }
 
 impl PreLimitStrategy {
-    pub fn new(api: Arc<PolymarketApi>, config: Config) -> Self {
-        let discovery = MarketDiscovery::new(api.clone());
+    pub fn new(api: Arc<PolymarketApi>, feed: Arc<MarketFeed>, config: Config) -> Self {
+        let discovery = MarketDiscovery::new(api.clone(), &config);
+        let risk = RiskManager::new(config.strategy.risk.clone());
+        let rate_control = RateControl::new(config.strategy.rate_control.clone());
+        let allocated: Vec<(String, String)> = config
+            .traded_assets()
+            .into_iter()
+            .map(|a| (a.clone(), format!("{}:{}", a, MarketDuration::M15.label())))
+            .chain(config.event_assets().into_iter().map(|a| (a.clone(), a)))
+            .collect();
+        let allocator = Allocator::new(config.strategy.allocation.clone(), allocated);
+        // Validated with the config
+        let blackouts = Blackouts::new(&config.strategy.blackouts).unwrap_or_default();
+        let ledger = config.ledger.enabled.then(|| Ledger::new(config.ledger.path.clone()));
+        let fees = FeeSchedule::new(config.strategy.fees.clone());
+        let profit = std::sync::Mutex::new(ProfitTracker::new(config.profit.tz(), clock::now()));
+        let fills = std::sync::Mutex::new(FillModel::new(config.strategy.fill_model.clone(), fees.clone()));
+        let sim_balance = config.strategy.starting_balance_usd.filter(|_| config.strategy.simulation_mode);
+        let stats = std::sync::Mutex::new(StatsTracker::new(config.strategy.streak_sizing.clone()));
+        let signals = std::sync::Mutex::new(ExternalSignals::new(&config.control.signals));
+        let plugin = config.strategy.plugin.enabled.then(|| Plugin::new(api.clone(), feed.clone(), &config));
+        let momentum = &config.strategy.signal.momentum;
+        let reference = config.strategy.resolution.simulated_winner == SimulatedWinner::Reference
+            && (config.strategy.simulation_mode || config.strategy.paper_live);
+        let spot = (momentum.enabled || reference).then(|| {
+            SpotCandles::new(api::http_client(&config.network).expect("Failed to create HTTP client"), momentum.clone())
+        });
         Self {
             api,
+            feed,
             config,
             discovery,
             states: Arc::new(Mutex::new(HashMap::new())),
@@ -53,25 +258,218 @@ impl PreLimitStrategy {
             total_profit: Arc::new(Mutex::new(0.0)),
             trades: Arc::new(Mutex::new(HashMap::new())),
             closure_checked: Arc::new(Mutex::new(HashMap::new())),
-            period_profit: Arc::new(Mutex::new(0.0)),
+            profit,
+            risk,
+            rate_control,
+            allocator,
+            blackouts,
+            blackout_noted: std::sync::Mutex::new(HashMap::new()),
+            ledger,
+            fills,
+            fees,
+            price_history: Mutex::new(HashMap::new()),
+            spot,
+            last_errors: std::sync::Mutex::new(HashMap::new()),
+            paused: std::sync::Mutex::new(HashSet::new()),
+            crashed: std::sync::Mutex::new(HashSet::new()),
+            closure_running: Mutex::new(()),
+            redeem_queue: Mutex::new(Vec::new()),
+            awaiting_resolution: std::sync::Mutex::new(HashMap::new()),
+            account: None,
+            usdc_balance: std::sync::Mutex::new(sim_balance),
+            sim_balance: std::sync::Mutex::new(sim_balance),
+            last_snapshot: std::sync::Mutex::new(HashMap::new()),
+            last_fill_check: std::sync::Mutex::new(None),
+            reported_fill_mismatches: std::sync::Mutex::new(HashSet::new()),
+            treasury_alerted: std::sync::Mutex::new(false),
+            stats,
+            model: None,
+            plugin,
+            signals,
+        }
+    }
+
+    /// Gates mid-market pairs on `model`'s Up probability instead of the market's odds.
+    pub fn with_model(mut self, model: Arc<SideModel>) -> Self {
+        self.model = Some(ModelGate::new(model, Arc::clone(&self.api), Arc::clone(&self.feed), &self.config));
+        self
+    }
+
+    /// Labels this instance's status, metrics and ledger rows with an account from `accounts`.
+    pub fn with_account(mut self, name: &str) -> Self {
+        self.account = Some(name.to_string());
+        self
+    }
+
+    pub fn account(&self) -> Option<&str> {
+        self.account.as_deref()
+    }
+
+    /// Name this strategy's loop reports its liveness under.
+    fn loop_name(&self) -> String {
+        match &self.account {
+            Some(account) => format!("strategy[{}]", account),
+            None => "strategy".to_string(),
+        }
+    }
+
+    /// Fills, sells and resolutions are simulated: in simulation mode, and with `paper_live`,
+    /// where only order placement goes through the (non-posting) API.
+    fn simulated(&self) -> bool {
+        self.config.strategy.simulation_mode || self.config.strategy.paper_live
+    }
+
+    /// Positions are settled at resolution: live, in a replay, and in simulation when a bankroll
+    /// is tracked so payouts can be credited to it.
+    fn settles_resolutions(&self) -> bool {
+        !self.simulated() || self.api.replay().is_some() || self.sim_balance.lock().unwrap().is_some()
+    }
+
+    /// Reads the account's USDC balance for status and metrics. Live mode with a private key only;
+    /// in simulation mode it reports the simulated balance, if one is tracked.
+    pub async fn refresh_balance(&self) {
+        if self.config.strategy.simulation_mode {
+            if let Some(balance) = *self.sim_balance.lock().unwrap() {
+                metrics().set_usdc_balance(self.account.as_deref(), balance);
+            }
+            return;
+        }
+        if !self.config.polymarket.can_sign() {
+            return;
+        }
+        match self.api.get_usdc_balance().await {
+            Ok(balance) => {
+                *self.usdc_balance.lock().unwrap() = Some(balance);
+                metrics().set_usdc_balance(self.account.as_deref(), balance);
+                // One pair at the configured limit price
+                let pair_cost = self.config.strategy.price_limit * self.config.strategy.shares * 2.0;
+                self.risk.balance_read(balance, pair_cost).await;
+                self.check_treasury(balance).await;
+            }
+            Err(e) => warn!("Failed to read USDC balance{}: {}",
+                self.account.as_ref().map(|a| format!(" for account {}", a)).unwrap_or_default(), e),
+        }
+    }
+
+    /// Applies `strategy.treasury` to a fresh balance read. An excess over `target_bankroll_usd` of
+    /// at least `min_excess_usd` is transferred to `cold_address` when `transfer` is on, and
+    /// alerted on once per crossing otherwise or when the transfer fails.
+    async fn check_treasury(&self, balance: f64) {
+        let cfg = &self.config.strategy.treasury;
+        let Some(target) = cfg.target_bankroll_usd else { return };
+        let excess = balance - target;
+        if excess < cfg.min_excess_usd.max(0.01) {
+            *self.treasury_alerted.lock().unwrap() = false;
+            return;
+        }
+        let account = self.account.as_ref().map(|a| format!(" [{}]", a)).unwrap_or_default();
+        if let (true, Some(cold)) = (cfg.transfer, cfg.cold_address.as_deref()) {
+            if self.config.strategy.paper_live {
+                log::warn!("📝 PAPER-LIVE{}: would transfer ${:.2} above the ${:.2} bankroll target to {}", account, excess, target, cold);
+                return;
+            }
+            log::warn!("🏦 Balance{} ${:.2} is above the ${:.2} bankroll target — transferring ${:.2} to {}", account, balance, target, excess, cold);
+            match self.api.transfer_usdc(cold, excess).await {
+                Ok(tx) => {
+                    log::info!("✅ Transferred ${:.2} to {} (tx {})", excess, cold, tx);
+                    tracing::info!(target: EVENTS, event = "treasury_transfer", amount_usd = excess, to = cold, tx = %tx);
+                    *self.usdc_balance.lock().unwrap() = Some(balance - excess);
+                    metrics().set_usdc_balance(self.account.as_deref(), balance - excess);
+                    return;
+                }
+                Err(e) => log::error!("🚨 Treasury transfer of ${:.2}{} to {} failed: {}", excess, account, cold, e),
+            }
         }
+        if std::mem::replace(&mut *self.treasury_alerted.lock().unwrap(), true) {
+            return;
+        }
+        log::warn!("🏦 Balance{} ${:.2} is ${:.2} above the ${:.2} bankroll target — consider withdrawing the excess", account, balance, excess, target);
+        tracing::warn!(target: EVENTS, event = "bankroll_above_target", balance_usd = balance, target_usd = target, excess_usd = excess);
     }
 
     pub async fn get_total_profit(&self) -> f64 {
         *self.total_profit.lock().await
     }
 
+    /// Realized PnL in the current 15m period.
     pub async fn get_period_profit(&self) -> f64 {
-        *self.period_profit.lock().await
+        self.profit.lock().unwrap().current(ProfitWindow::Period)
+    }
+
+    /// Realized PnL below its peak since start, and the largest such drawdown.
+    pub async fn drawdown(&self) -> Drawdown {
+        self.risk.drawdown().await
+    }
+
+    /// Suspends or resumes new orders for `asset` on `duration`; open positions, monitoring and
+    /// closure checks carry on. Resuming also restarts a market stopped by a panic. Returns
+    /// false if no configured market trades that pair. Event markets pause as `15m`, the
+    /// trading loop they run in.
+    pub fn set_paused(&self, asset: &str, duration: MarketDuration, paused: bool) -> bool {
+        let events = if duration == MarketDuration::M15 { self.config.event_assets() } else { Vec::new() };
+        let Some(asset) = self.config.assets_for(duration)
+            .into_iter()
+            .chain(events)
+            .find(|a| a.eq_ignore_ascii_case(asset))
+        else {
+            return false;
+        };
+        let mut set = self.paused.lock().unwrap();
+        if paused {
+            set.insert((asset, duration));
+        } else {
+            if duration == MarketDuration::M15 {
+                self.crashed.lock().unwrap().remove(&asset);
+            }
+            set.remove(&(asset, duration));
+        }
+        true
+    }
+
+    /// Stores a signal posted to the control server; `None` when this strategy doesn't trade
+    /// the market it is for.
+    pub fn post_signal(&self, post: &SignalPost) -> Result<Option<ExternalSignal>> {
+        let Some(asset) = [self.config.traded_assets(), self.config.event_assets()]
+            .concat()
+            .into_iter()
+            .find(|a| a.eq_ignore_ascii_case(&post.asset))
+        else {
+            return Ok(None);
+        };
+        self.signals.lock().unwrap().post(&asset, post, clock::now()).map(Some)
+    }
+
+    /// Withdraws the signal posted for `asset`; whether there was one.
+    pub fn clear_signal(&self, asset: &str) -> bool {
+        let mut signals = self.signals.lock().unwrap();
+        [self.config.traded_assets(), self.config.event_assets()]
+            .concat()
+            .iter()
+            .filter(|a| a.eq_ignore_ascii_case(asset))
+            .any(|a| signals.clear(a))
+    }
+
+    pub fn external_signals(&self) -> Vec<ExternalSignal> {
+        self.signals.lock().unwrap().active(clock::now())
+    }
+
+    /// Bias of the live external signal for `asset`, if any.
+    fn bias(&self, asset: &str) -> Option<Bias> {
+        self.signals.lock().unwrap().get(asset, clock::now()).map(|s| s.bias)
     }
 
-    pub async fn run(&self) -> Result<()> {
+    fn is_paused(&self, asset: &str) -> bool {
+        self.paused.lock().unwrap().contains(&(asset.to_string(), MarketDuration::M15))
+    }
+
+    /// Trades until `shutdown` flips, then winds down (see `shutdown`).
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) -> Result<()> {
         self.display_market_status().await?;
         
-        loop {
+        while !*shutdown.borrow() {
             let should_display = {
                 let mut last = self.last_status_display.lock().await;
-                if last.elapsed().as_secs() >= 10 {
+                if last.elapsed().as_secs() >= self.config.strategy.status_interval_secs {
                     *last = std::time::Instant::now();
                     true
                 } else {
@@ -85,22 +483,164 @@ impl PreLimitStrategy {
                 }
             }
             
-            if let Err(e) = self.process_markets().await {
-                log::error!("Error processing markets: {}", e);
+            self.tick().await;
+            health().tick(&self.loop_name());
+            tokio::select! {
+                _ = sleep(Duration::from_millis(self.config.strategy.check_interval_ms)) => {}
+                _ = shutdown.changed() => break,
+            }
+        }
+        self.shutdown().await;
+        Ok(())
+    }
+
+    /// One pass of the trading loop: risk limits, profit windows, then every market.
+    pub async fn tick(&self) {
+        self.enforce_risk_limits().await;
+        self.roll_profit_windows();
+        if let Err(e) = self.process_markets().await {
+            log::error!("Error processing markets: {}", e);
+        }
+    }
+
+    /// Cancels every resting order, sells one-sided positions if `flatten_on_shutdown` is set,
+    /// flushes the ledger and prints a final summary.
+    pub async fn shutdown(&self) {
+        log::warn!("🛑 Shutting down — canceling resting orders");
+        self.cancel_resting_orders().await;
+        if self.config.strategy.flatten_on_shutdown {
+            self.flatten_one_sided().await;
+        }
+        if let Some(ledger) = &self.ledger {
+            ledger.flush();
+        }
+        self.print_final_summary().await;
+    }
+
+    /// Market-sells the filled side of every position where only one side filled.
+    async fn flatten_one_sided(&self) {
+        let mut states = self.states.lock().await;
+        for s in states.values_mut() {
+            if s.merged || s.risk_sold || s.up_matched == s.down_matched {
+                continue;
             }
-            sleep(Duration::from_millis(self.config.strategy.check_interval_ms)).await;
+            let (outcome, token_id, purchase_price) = if s.up_matched {
+                ("Up", s.up_token_id.clone(), s.up_order_price)
+            } else {
+                ("Down", s.down_token_id.clone(), s.down_order_price)
+            };
+            let Some(quoted) = self.feed.price(&token_id).await.ok()
+                .and_then(|p| p.to_string().parse::<f64>().ok())
+            else {
+                log::error!("{}: no {} price — leaving the position open", s.asset, outcome);
+                continue;
+            };
+
+            let Some(sale) = self.sell_side(s, outcome, quoted, Rationale::Shutdown).await else {
+                log::warn!("{} {} position not fully sold on shutdown", s.asset, outcome);
+                continue;
+            };
+            log::warn!("💸 {}: sold {:.2} {} shares at ${:.4} on shutdown (purchased at ${:.2})",
+                s.asset, sale.shares, outcome, sale.avg_price, purchase_price);
+            self.finish_sale(s, outcome, Rationale::Shutdown, sale).await;
+        }
+    }
+
+    async fn print_final_summary(&self) {
+        let unrealized = self.unrealized_pnl().await;
+        let total = self.get_total_profit().await;
+        let daily = self.risk.daily_realized_pnl().await;
+        let pending_redemption = self.trades.lock().await.len();
+        let states = self.states.lock().await;
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
+        eprintln!("📋 Final summary{}", self.account.as_ref().map(|a| format!(" — account {}", a)).unwrap_or_default());
+        eprintln!("   realized PnL          {} (today {})", display::usd(total), display::usd(daily));
+        eprintln!("   unrealized PnL        {}", display::usd(unrealized));
+        eprintln!("   max drawdown          {}", display::usd(self.risk.drawdown().await.max));
+        if let (Some(balance), Some(start)) = (*self.sim_balance.lock().unwrap(), self.config.strategy.starting_balance_usd) {
+            eprintln!("   simulated balance     {} (started with {})", display::usd(balance), display::usd(start));
+        }
+        if let Some(latency) = metrics().latency_summary() {
+            eprintln!("   latency               {}", latency);
+        }
+        for s in states.values().filter(|s| !s.merged && (s.up_matched || s.down_matched)) {
+            eprintln!("   {:<5} period {} | Up:{} Down:{} | {} shares per side",
+                s.asset, s.market_period_start,
+                if s.up_matched { "✓" } else { "✗" },
+                if s.down_matched { "✓" } else { "✗" },
+                display::shares(s.shares));
         }
+        if pending_redemption > 0 {
+            eprintln!("   {} position(s) awaiting resolution — redeem later with --redeem", pending_redemption);
+        }
+        let queue = self.redeem_queue.lock().await;
+        if !queue.is_empty() {
+            eprintln!("   {} resolved position(s) worth {} not yet redeemed — run --redeem",
+                queue.len(), display::usd(queue.iter().map(|r| r.value).sum::<f64>()));
+        }
+        self.stats.lock().unwrap().print(None);
+        eprintln!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
     }
 
     async fn process_markets(&self) -> Result<()> {
-        let assets = vec!["BTC", "ETH", "SOL", "XRP"];
         let current_period_et = Self::get_current_15m_period_et();
         
-        for asset in assets {
-            self.process_asset(asset, current_period_et).await?;
+        let events = self.config.event_assets();
+        for asset in self.config.traded_assets().iter().chain(&events) {
+            let asset = asset.as_str();
+            if self.crashed.lock().unwrap().contains(asset) {
+                continue;
+            }
+            let event = events.iter().any(|e| e == asset);
+            let span = tracing::info_span!(
+                "market",
+                market = asset,
+                timeframe = if event { "event" } else { MarketDuration::M15.label() },
+                condition_id = tracing::field::Empty,
+                period_timestamp = tracing::field::Empty,
+            );
+            // A panic in one market must not take down the loop trading the others
+            let result = if event {
+                AssertUnwindSafe(self.process_event(asset).instrument(span)).catch_unwind().await
+            } else {
+                AssertUnwindSafe(self.process_asset(asset, current_period_et).instrument(span)).catch_unwind().await
+            };
+            let message = match result {
+                Ok(Ok(())) => continue,
+                Ok(Err(e)) => {
+                    match e.downcast_ref::<ApiError>() {
+                        Some(api) if api.is_transient() => warn!("{} | {} error, retrying next tick: {}", asset, api.label(), e),
+                        _ => log::error!("Error processing {}: {}", asset, e),
+                    }
+                    format!("{:#}", e)
+                }
+                Err(panic) => self.market_panicked(asset, panic),
+            };
+            self.last_errors.lock().unwrap().insert(asset.to_string(), MarketError {
+                at: Utc::now().timestamp(),
+                message,
+            });
         }
         Ok(())
     }
+
+    /// Records a panic caught while processing `asset` and, unless `watchdog.restart_on_panic`
+    /// is set, stops the market until it is resumed. Returns the panic message.
+    fn market_panicked(&self, asset: &str, panic: Box<dyn Any + Send>) -> String {
+        let message = panic_message(panic.as_ref());
+        let restart = self.config.watchdog.restart_on_panic;
+        log::error!("💥 {} | Market processing panicked: {}", asset, message);
+        tracing::error!(target: EVENTS, event = "market_panic", market = asset, message = %message, restart);
+        metrics().inc_task_panic("market", asset);
+        if restart {
+            warn!("🔄 {} | Retrying the market next tick", asset);
+        } else {
+            self.crashed.lock().unwrap().insert(asset.to_string());
+            self.set_paused(asset, MarketDuration::M15, true);
+            warn!("⏸️  {} | Market stopped; resume it through the control API", asset);
+        }
+        format!("panic: {}", message)
+    }
     
     /// Current 15-minute period start timestamp (ET)
     fn get_current_15m_period_et() -> i64 {
@@ -108,41 +648,87 @@ impl PreLimitStrategy {
     }
     
     fn get_current_time_et() -> i64 {
-        let now_utc = Utc::now();
-        let now_et = now_utc.with_timezone(&New_York);
-        now_et.timestamp()
+        clock::now()
     }
 
     async fn process_asset(&self, asset: &str, current_period_et: i64) -> Result<()> {
+        if let Some(model) = &self.model {
+            model.sample(asset, current_period_et).await;
+        }
         let mut states = self.states.lock().await;
         let state = states.get(asset).cloned();
+        if let Some(s) = &state {
+            tracing::Span::current()
+                .record("condition_id", s.condition_id.as_str())
+                .record("period_timestamp", s.market_period_start);
+        }
         
         let current_time_et = Self::get_current_time_et();
         let next_period_start = current_period_et + MARKET_DURATION_SECS;
         let time_until_next = next_period_start - current_time_et;
 
-        let needs_danger_handling = state.as_ref().map_or(false, |s| {
+        let needs_danger_handling = state.as_ref().is_some_and(|s| {
             !s.merged && !s.risk_sold &&
             ((s.up_matched && !s.down_matched) || (s.down_matched && !s.up_matched))
         });
+        // Positions already held are still managed below
+        let no_market = self.no_active_market(asset, current_period_et);
 
         if time_until_next <= (self.config.strategy.place_order_before_mins * 60) as i64 {
-            let is_next_market_prepared = state.as_ref().map_or(false, |s| s.expiry == next_period_start + MARKET_DURATION_SECS);
+            let is_next_market_prepared = state.as_ref().is_some_and(|s| s.expiry == next_period_start + MARKET_DURATION_SECS);
             
-            if !is_next_market_prepared && !needs_danger_handling {
+            if self.config.strategy.pre_orders_enabled && !is_next_market_prepared && !needs_danger_handling && !no_market && !self.is_paused(asset) && !self.risk.is_halted().await
+                && !self.risk.is_short_of_funds().await {
                 // Signal check: evaluate current market before placing pre-orders for next
-                let signal = self.get_place_signal(asset, current_period_et).await;
+                let (signal, inputs) = self.get_place_signal(asset, current_period_et).await;
+                let price_limit = self.config.strategy.price_limit;
+                let base = self.allocated_shares(asset);
                 if signal != MarketSignal::Good {
                     if signal == MarketSignal::Bad {
                         log::info!("{} | Bad signal for current market — skipping pre-orders for next 15m", asset);
                     }
+                } else if self.in_blackout(asset, next_period_start, "pre-orders") {
+                } else if base <= 0.0 {
+                    log::debug!("{} | Balance allocation leaves nothing for pre-orders", asset);
+                } else if let Some(ev) = self.ev_short(signals::EVEN_ODDS, price_limit, price_limit, base) {
+                    log::debug!("{} | Pre-order pair expects ${:.4} after fees (pair cost ${:.4}), not above ev_min_usd — skipping pre-orders",
+                        asset, ev, self.fees.pair_cost(price_limit, price_limit));
+                } else if let Err(throttled) = self.rate_control.check(asset, &["Up", "Down"], current_period_et, clock::now_ms()) {
+                    self.note_throttled(asset, "pre-orders", throttled);
+                } else if let Err(breach) = self.risk.check_correlated(asset, price_limit * base, |a| Self::asset_exposure(&states, a)) {
+                    log::debug!("{} | Holding back pre-orders: {}", asset, breach);
+                } else if let Some(available) = self.sim_funds_short(&states, 2.0 * self.buy_cost(price_limit, base)) {
+                    log::info!("🎮 SIMULATION: {} | ${:.2} of the simulated balance available, less than the pair costs — skipping pre-orders",
+                        asset, available.max(0.0));
                 } else if let Some(next_market) = self.discover_next_market(asset, next_period_start).await? {
+                    tracing::Span::current()
+                        .record("condition_id", next_market.condition_id.as_str())
+                        .record("period_timestamp", next_period_start);
                     log::info!("Preparing orders for next 15m {} market (starts in {}s)", asset, time_until_next);
                     let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&next_market.condition_id).await?;
+                    let shares = self.order_shares(asset).await;
+                    let decision = inputs.map(|(up_price, down_price, trend)| Decision {
+                        rule: Rationale::PreOrder,
+                        trend,
+                        up_price,
+                        down_price,
+                        ev: self.fees.pair_ev(signals::EVEN_ODDS, price_limit, price_limit, shares),
+                    });
 
-                    let price_limit = self.config.strategy.price_limit;
-                    let up_order = self.place_limit_order(&up_token_id, "BUY", price_limit).await?;
-                    let down_order = self.place_limit_order(&down_token_id, "BUY", price_limit).await?;
+                    if self.snapshot_stale(asset, "pre-orders") {
+                        return Ok(());
+                    }
+                    let snapshot = self.last_snapshot.lock().unwrap().get(asset).copied();
+                    if let Some((_, fetched)) = snapshot {
+                        metrics().observe_latency("decision", fetched.elapsed());
+                    }
+                    self.rate_control.record(asset, &["Up", "Down"], current_period_et, clock::now_ms());
+                    let period_end = next_period_start + MARKET_DURATION_SECS;
+                    let up_order = self.place_limit_order(&up_token_id, "BUY", price_limit, shares, period_end).await?;
+                    let down_order = self.place_limit_order(&down_token_id, "BUY", price_limit, shares, period_end).await?;
+                    if let Some((requested, _)) = snapshot.filter(|_| !self.simulated()) {
+                        metrics().observe_latency("snapshot_to_ack", requested.elapsed());
+                    }
                     
                     let new_state = PreLimitOrderState {
                         asset: asset.to_string(),
@@ -153,6 +739,7 @@ impl PreLimitStrategy {
                         down_order_id: down_order.order_id,
                         up_order_price: price_limit,
                         down_order_price: price_limit,
+                        shares,
                         up_matched: false,
                         down_matched: false,
                         merged: false,
@@ -161,6 +748,12 @@ impl PreLimitStrategy {
                         order_placed_at: current_time_et,
                         market_period_start: next_period_start,
                         one_side_matched_at: None,
+                        resting_cancelled: false,
+                        sold_side: None,
+                        unsold: 0.0,
+                        partial_sell: None,
+                        spike_sold: None,
+                        decision,
                     };
                     states.insert(asset.to_string(), new_state);
                     
@@ -171,280 +764,25 @@ impl PreLimitStrategy {
             }
         }
 
-        if let Some(mut s) = state {
-            self.check_order_matches(&mut s).await?;
-
-            if s.up_matched && s.down_matched && !s.merged {
-                let threshold = self.config.strategy.sell_opposite_above;
-                let (up_price, down_price) = (
-                    self.api.get_price(&s.up_token_id, "SELL").await.ok()
-                        .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0),
-                    self.api.get_price(&s.down_token_id, "SELL").await.ok()
-                        .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0),
-                );
-
-                // Calculate time remaining in the current market period
-                let current_time_et = Self::get_current_time_et();
-                let market_end_time = s.market_period_start + MARKET_DURATION_SECS;
-                let time_remaining_seconds = market_end_time - current_time_et;
-                let time_remaining_mins = time_remaining_seconds / 60;
-                let required_time_remaining_mins = self.config.strategy.sell_opposite_time_remaining as i64;
-
-                let sell_opposite = if up_price >= threshold {
-                    Some(("Up", "Down", &s.down_token_id, s.down_order_price))
-                } else if down_price >= threshold {
-                    Some(("Down", "Up", &s.up_token_id, s.up_order_price))
-                } else {
-                    None
-                };
-
-                // Only sell if BOTH conditions are met: price threshold AND time remaining is low enough
-                if let Some((winner, loser, token_to_sell, purchase_price)) = sell_opposite {
-                    if time_remaining_mins <= required_time_remaining_mins {
-                        log::info!("{}: Both filled, {} price ${:.2} >= {:.2} AND {}min remaining <= {}min — selling {} to reduce loss", 
-                            asset, winner, if winner == "Up" { up_price } else { down_price }, threshold, 
-                            time_remaining_mins, required_time_remaining_mins, loser);
-                        let sell_price_result = self.api.get_price(token_to_sell, "SELL").await;
-                        let sell_price = sell_price_result.ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0);
-                        if self.config.strategy.simulation_mode {
-                            let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                            let mut total = self.total_profit.lock().await;
-                            *total -= loss;
-                            let current_total = *total;
-                            drop(total);
-                            log::info!("🎮 SIMULATION: Would sell {} {} shares at ${:.4} (purchased at ${:.2})", 
-                                self.config.strategy.shares, loser, sell_price, purchase_price);
-                            log::info!("   Holding {} to expiry (pays $1). Loss on {}: ${:.2} | Total Profit: ${:.2}", 
-                                winner, loser, loss, current_total);
-                        } else {
-                            if let Err(e) = self.api.place_market_order(&token_to_sell, self.config.strategy.shares, "SELL", None).await {
-                                log::error!("Failed to sell {} token for {}: {}", loser, asset, e);
-                            } else {
-                                let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                                let mut total = self.total_profit.lock().await;
-                                *total -= loss;
-                                let current_total = *total;
-                                drop(total);
-                                log::info!("   Sold {} {} shares at ${:.2}. Holding {} to expiry (pays $1). Loss: ${:.2} | Total Profit: ${:.2}", 
-                                    self.config.strategy.shares, loser, sell_price, winner, loss, current_total);
-                            }
-                        }
-                        s.merged = true;
-                        // Register for redemption (production only): holding winner, check_market_closure will redeem when market resolves
-                        if !self.config.strategy.simulation_mode {
-                            let trade = Self::cycle_trade_holding_winner(&s, winner, self.config.strategy.shares);
-                            let mut t = self.trades.lock().await;
-                            t.insert(s.condition_id.clone(), trade);
-                            log::info!("   Registered position for redemption when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
-                        }
-                    } else {
-                        log::debug!("{}: {} price ${:.2} >= {:.2}, but {}min remaining > {}min threshold — holding both positions", 
-                            asset, winner, if winner == "Up" { up_price } else { down_price }, threshold,
-                            time_remaining_mins, required_time_remaining_mins);
-                    }
-                }
-                // When both filled but neither side >= sell_opposite_above: do nothing.
-                // Hold both until one side hits threshold (re-check next tick) or expiry (redeem).
-            }
-
-            let current_time_et = Self::get_current_time_et();
-
-            // Track when we first had only one side matched (for danger_time_passed)
-            let only_one_matched = (s.up_matched && !s.down_matched) || (s.down_matched && !s.up_matched);
-            if only_one_matched && s.one_side_matched_at.is_none() {
-                s.one_side_matched_at = Some(current_time_et);
-            }
-
-            // One-side risk management: "price" = sell when matched token <= danger_price; "time" = sell after danger_time_passed mins
-            let mode = match self.config.strategy.signal.one_side_buy_risk_management.to_lowercase().as_str() {
-                "price" | "sell_at_danger_price" => "price",
-                "time" | "sell_after_danger_time_passed" => "time",
-                _ => "none",
-            };
-            let mut should_sell_early = if !only_one_matched {
-                false
-            } else if mode == "price" {
-                if s.up_matched && !s.down_matched {
-                    self.api.get_price(&s.up_token_id, "SELL").await
-                        .ok()
-                        .and_then(|p| p.to_string().parse::<f64>().ok())
-                        .map(|p| signals::is_danger_signal(&self.config.strategy.signal, p))
-                        .unwrap_or(false)
-                } else {
-                    self.api.get_price(&s.down_token_id, "SELL").await
-                        .ok()
-                        .and_then(|p| p.to_string().parse::<f64>().ok())
-                        .map(|p| signals::is_danger_signal(&self.config.strategy.signal, p))
-                        .unwrap_or(false)
-                }
-            } else if mode == "time" {
-                let danger_mins = self.config.strategy.signal.danger_time_passed as i64;
-                s.one_side_matched_at.map_or(false, |t| current_time_et - t >= danger_mins * 60)
-            } else {
-                false
-            };
-
-            // Production only: when danger would trigger, verify both orders via API first.
-            // If both filled, don't sell — update state and let "both matched" logic handle next tick.
-            if !self.config.strategy.simulation_mode && should_sell_early {
-                if let (Some(up_id), Some(down_id)) = (&s.up_order_id, &s.down_order_id) {
-                    match self.api.are_both_orders_filled(up_id, down_id).await {
-                        Ok((true, true)) => {
-                            log::info!("{}: Danger signal but both orders filled (verified via API) — skipping sell", asset);
-                            s.up_matched = true;
-                            s.down_matched = true;
-                            should_sell_early = false;
-                        }
-                        Ok(_) => { /* one or both not filled, proceed with sell */ }
-                        Err(e) => {
-                            log::warn!("{}: Failed to verify order status: {} — proceeding with danger sell", asset, e);
-                        }
-                    }
-                }
-            }
-
-            let should_sell = !s.merged && !s.risk_sold && should_sell_early;
-
-            if should_sell {
-                let reason = if mode == "time" {
-                    format!("Danger time passed ({}min since match)", self.config.strategy.signal.danger_time_passed)
-                } else {
-                    "Danger signal (price collapsed)".to_string()
-                };
-                if s.up_matched && !s.down_matched {
-                    log::warn!("{}: {} — only Up token matched. Selling Up token and canceling Down order", asset, reason.as_str());
-                    
-                    let sell_price_result = self.api.get_price(&s.up_token_id, "SELL").await;
-                    let purchase_price = s.up_order_price;
-                    
-                    if self.config.strategy.simulation_mode {
-                        let sell_price = sell_price_result
-                            .ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        
-                        let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                        
-                        let mut total = self.total_profit.lock().await;
-                        *total -= loss;
-                        let current_total = *total;
-                        drop(total);
-                        
-                        log::warn!("🎮 SIMULATION: Would sell {} Up token shares at ${:.4} (purchased at ${:.2})", 
-                            self.config.strategy.shares, sell_price, purchase_price);
-                        if let Some(down_order_id) = &s.down_order_id {
-                            log::warn!("🎮 SIMULATION: Would cancel Down order {}", down_order_id);
-                        }
-                        log::warn!("   💸 SIMULATION: Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
-                    } else {
-                        let sell_price = sell_price_result
-                            .ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        
-                        // Sell the Up token
-                        if let Err(e) = self.api.place_market_order(&s.up_token_id, self.config.strategy.shares, "SELL", None).await {
-                            log::error!("Failed to sell Up token for {}: {}", asset, e);
-                        } else {
-                            if let Some(down_order_id) = &s.down_order_id {
-                                if let Err(e) = self.api.cancel_order(down_order_id).await {
-                                    log::error!("Failed to cancel Down order for {}: {}", asset, e);
-                                } else {
-                                    log::info!("✅ Canceled Down order {} for {}", down_order_id, asset);
-                                }
-                            }
-                            
-                            let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                            
-                            let mut total = self.total_profit.lock().await;
-                            *total -= loss;
-                            let current_total = *total;
-                            drop(total);
-                            
-                            log::warn!("   💸 Sold {} Up token shares at ${:.2} (purchased at ${:.2})", 
-                                self.config.strategy.shares, sell_price, purchase_price);
-                            log::warn!("   💸 Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
-                        }
-                    }
-                    s.risk_sold = true;
-                    s.merged = true;
-                } else if s.down_matched && !s.up_matched {
-                    log::warn!("{}: {} — only Down token matched. Selling Down token and canceling Up order", asset, reason.as_str());
-                    
-                    // Get current sell price for Down token
-                    let sell_price_result = self.api.get_price(&s.down_token_id, "SELL").await;
-                    let purchase_price = s.down_order_price;
-                    
-                    if self.config.strategy.simulation_mode {
-                        let sell_price = sell_price_result
-                            .ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        
-                        let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                        
-                        let mut total = self.total_profit.lock().await;
-                        *total -= loss;
-                        let current_total = *total;
-                        drop(total);
-                        
-                        log::warn!("🎮 SIMULATION: Would sell {} Down token shares at ${:.4} (purchased at ${:.2})", 
-                            self.config.strategy.shares, sell_price, purchase_price);
-                        if let Some(up_order_id) = &s.up_order_id {
-                            log::warn!("🎮 SIMULATION: Would cancel Up order {}", up_order_id);
-                        }
-                        log::warn!("   💸 SIMULATION: Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
-                    } else {
-                        let sell_price = sell_price_result
-                            .ok()
-                            .and_then(|p| p.to_string().parse::<f64>().ok())
-                            .unwrap_or(0.0);
-                        
-                        if let Err(e) = self.api.place_market_order(&s.down_token_id, self.config.strategy.shares, "SELL", None).await {
-                            log::error!("Failed to sell Down token for {}: {}", asset, e);
-                        } else {
-                            if let Some(up_order_id) = &s.up_order_id {
-                                if let Err(e) = self.api.cancel_order(up_order_id).await {
-                                    log::error!("Failed to cancel Up order for {}: {}", asset, e);
-                                } else {
-                                    log::info!("✅ Canceled Up order {} for {}", up_order_id, asset);
-                                }
-                            }
-                            
-                            let loss = (purchase_price - sell_price) * self.config.strategy.shares;
-                            
-                            let mut total = self.total_profit.lock().await;
-                            *total -= loss;
-                            let current_total = *total;
-                            drop(total);
-                            
-                            log::warn!("   💸 Sold {} Down token shares at ${:.2} (purchased at ${:.2})", 
-                                self.config.strategy.shares, sell_price, purchase_price);
-                            log::warn!("   💸 Loss: ${:.2} | Total Profit: ${:.2}", loss, current_total);
-                        }
-                    }
-                    s.risk_sold = true;
-                    s.merged = true;
-                }
-            }
-
-            let current_time_et = Self::get_current_time_et();
-            if current_time_et > s.expiry {
-                // Register for redemption (production only) if we held both until expiry (sold opposite already registered)
-                if !self.config.strategy.simulation_mode && s.up_matched && s.down_matched && !s.risk_sold && !s.merged {
-                    let trade = Self::cycle_trade_holding_both(&s, self.config.strategy.shares);
-                    let mut t = self.trades.lock().await;
-                    t.insert(s.condition_id.clone(), trade);
-                    log::info!("   Registered position for redemption when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
-                }
-                log::info!("Market expired for {}. Clearing state.", asset);
-                states.remove(asset);
-            } else {
-                states.insert(asset.to_string(), s);
+        if let Some(s) = state {
+            self.manage_position(&mut states, asset, s).await?;
+        } else if let Some(plugin) = &self.plugin {
+            if time_until_next > (self.config.strategy.place_order_before_mins * 60) as i64
+                && plugin.due(asset, current_time_et)
+                && !no_market
+                && !self.is_paused(asset)
+                && !self.risk.is_halted().await
+                && !self.risk.is_short_of_funds().await
+                && !self.in_blackout(asset, current_period_et, "plugin orders")
+            {
+                self.plugin_entry(&mut states, plugin, asset, current_period_et).await?;
             }
-            } else if time_until_next > (self.config.strategy.place_order_before_mins * 60) as i64
+        } else if time_until_next > (self.config.strategy.place_order_before_mins * 60) as i64
             && self.config.strategy.signal.mid_market_enabled
+            && !no_market
+            && !self.is_paused(asset)
+            && !self.risk.is_halted().await
+            && !self.risk.is_short_of_funds().await
         {
             // Don't place mid-market orders if too little time remains — we'd hit danger_time_passed and sell at a loss.
             let time_remaining_in_current_market = (current_period_et + MARKET_DURATION_SECS) - current_time_et;
@@ -452,23 +790,62 @@ impl PreLimitStrategy {
             if time_remaining_in_current_market < min_remaining_to_place {
                 log::debug!("{} | Skipping mid-market orders: only {}s left (need {}s for danger_time_passed)",
                     asset, time_remaining_in_current_market, min_remaining_to_place);
-            } else {
-            let signal = self.get_place_signal(asset, current_period_et).await;
+            } else if !self.in_blackout(asset, current_period_et, "mid-market orders") {
+            let (signal, inputs) = self.get_place_signal(asset, current_period_et).await;
             if signal == MarketSignal::Good {
                 if let Some(current_market) = self.discover_next_market(asset, current_period_et).await? {
                     let Some((up_price, down_price, _)) = self.get_market_snapshot(asset, current_period_et).await else {
                         return Ok(());
                     };
-                    let (up_order_price, down_order_price) = if up_price <= down_price {
-                        (Self::round_price(up_price), Self::round_price(0.98 - up_price))
-                    } else {
-                        (Self::round_price(0.98 - down_price), Self::round_price(down_price))
-                    };
+                    let (up_order_price, down_order_price) = signals::mid_market_prices(&self.config.strategy.signal, up_price, down_price);
+                    let (up_order_price, down_order_price) = (Self::round_price(up_order_price), Self::round_price(down_order_price));
+                    let odds = self.model_odds(asset, current_period_et, signals::implied_probabilities(up_price, down_price)).await;
+                    let base = self.allocated_shares(asset);
+                    if base <= 0.0 {
+                        log::debug!("{} | Balance allocation leaves nothing for mid-market orders", asset);
+                        return Ok(());
+                    }
+                    if let Some(ev) = self.ev_short(odds, up_order_price, down_order_price, base) {
+                        log::debug!("{} | Mid-market pair ${:.2} + ${:.2} expects ${:.4} after fees, not above ev_min_usd — not placing",
+                            asset, up_order_price, down_order_price, ev);
+                        return Ok(());
+                    }
+                    if let Err(throttled) = self.rate_control.check(asset, &["Up", "Down"], current_period_et, clock::now_ms()) {
+                        self.note_throttled(asset, "mid-market orders", throttled);
+                        return Ok(());
+                    }
+                    let leg_cost = up_order_price.max(down_order_price) * base;
+                    if let Err(breach) = self.risk.check_correlated(asset, leg_cost, |a| Self::asset_exposure(&states, a)) {
+                        log::debug!("{} | Holding back mid-market orders: {}", asset, breach);
+                        return Ok(());
+                    }
+                    let pair_cost = self.buy_cost(up_order_price, base) + self.buy_cost(down_order_price, base);
+                    if let Some(available) = self.sim_funds_short(&states, pair_cost) {
+                        log::info!("🎮 SIMULATION: {} | ${:.2} of the simulated balance available, mid-market pair costs ${:.2} — not placing",
+                            asset, available.max(0.0), pair_cost);
+                        return Ok(());
+                    }
+                    tracing::Span::current()
+                        .record("condition_id", current_market.condition_id.as_str())
+                        .record("period_timestamp", current_period_et);
                     log::info!("{} | Good signal — placing mid-market orders: Up @ ${:.2}, Down @ ${:.2} (current Up ${:.2}, Down ${:.2})", 
                         asset, up_order_price, down_order_price, up_price, down_price);
                     let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&current_market.condition_id).await?;
-                    let up_order = self.place_limit_order(&up_token_id, "BUY", up_order_price).await?;
-                    let down_order = self.place_limit_order(&down_token_id, "BUY", down_order_price).await?;
+                    let shares = self.order_shares(asset).await;
+                    let decision = Some(Decision {
+                        rule: Rationale::MidMarket,
+                        trend: inputs.map_or(Trend::Flat, |(_, _, trend)| trend),
+                        up_price,
+                        down_price,
+                        ev: self.fees.pair_ev(odds, up_order_price, down_order_price, shares),
+                    });
+                    if self.snapshot_stale(asset, "mid-market orders") {
+                        return Ok(());
+                    }
+                    self.rate_control.record(asset, &["Up", "Down"], current_period_et, clock::now_ms());
+                    let period_end = current_period_et + MARKET_DURATION_SECS;
+                    let up_order = self.place_limit_order(&up_token_id, "BUY", up_order_price, shares, period_end).await?;
+                    let down_order = self.place_limit_order(&down_token_id, "BUY", down_order_price, shares, period_end).await?;
                     let new_state = PreLimitOrderState {
                         asset: asset.to_string(),
                         condition_id: current_market.condition_id,
@@ -478,6 +855,7 @@ impl PreLimitStrategy {
                         down_order_id: down_order.order_id,
                         up_order_price,
                         down_order_price,
+                        shares,
                         up_matched: false,
                         down_matched: false,
                         merged: false,
@@ -486,6 +864,12 @@ impl PreLimitStrategy {
                         order_placed_at: current_time_et,
                         market_period_start: current_period_et,
                         one_side_matched_at: None,
+                        resting_cancelled: false,
+                        sold_side: None,
+                        unsold: 0.0,
+                        partial_sell: None,
+                        spike_sold: None,
+                        decision,
                     };
                     states.insert(asset.to_string(), new_state);
                     return Ok(());
@@ -493,171 +877,1875 @@ impl PreLimitStrategy {
             }
             }
         }
-
-        Ok(())
+
+        Ok(())
+    }
+
+    /// Buys the side `strategy.plugin` answers with on `asset`'s current market. The position
+    /// holds that side alone and is held to resolution: the pair's lone-fill exits don't apply.
+    async fn plugin_entry(&self, states: &mut HashMap<String, PreLimitOrderState>, plugin: &Plugin, asset: &str, period_start: i64) -> Result<()> {
+        let period_end = period_start + MARKET_DURATION_SECS;
+        let Some((snapshot, reply)) = plugin.decide(asset, period_start, period_end, self.account.as_deref(), self.bias(asset)).await else {
+            return Ok(());
+        };
+        let (outcome, quote) = match reply.action {
+            PluginAction::BuyUp => ("Up", &snapshot.up),
+            PluginAction::BuyDown => ("Down", &snapshot.down),
+            PluginAction::None => return Ok(()),
+        };
+        // The plugin sizes within what the bot would trade, never beyond it
+        let max_shares = self.order_shares(asset).await;
+        let shares = (reply.size.map_or(max_shares, |size| size.min(max_shares)) * 100.0).floor() / 100.0;
+        if shares <= 0.0 {
+            log::debug!("{} | Plugin order rounds to no shares — not placing", asset);
+            return Ok(());
+        }
+        let price = Self::round_price(reply.price.unwrap_or(quote.price));
+        if let Err(throttled) = self.rate_control.check(asset, &[outcome], period_start, clock::now_ms()) {
+            self.note_throttled(asset, "plugin orders", throttled);
+            return Ok(());
+        }
+        if let Err(breach) = self.risk.check_correlated(asset, price * shares, |a| Self::asset_exposure(states, a)) {
+            log::debug!("{} | Holding back plugin orders: {}", asset, breach);
+            return Ok(());
+        }
+        let cost = self.buy_cost(price, shares);
+        if let Some(available) = self.sim_funds_short(states, cost) {
+            log::info!("🎮 SIMULATION: {} | ${:.2} of the simulated balance available, plugin order costs ${:.2} — not placing",
+                asset, available.max(0.0), cost);
+            return Ok(());
+        }
+        tracing::Span::current()
+            .record("condition_id", snapshot.condition_id.as_str())
+            .record("period_timestamp", period_start);
+        let (up_price, down_price) = (snapshot.up.price, snapshot.down.price);
+        log::info!("🔌 {} | Plugin buys {} — {} shares @ ${:.2} (current Up ${:.2}, Down ${:.2})",
+            asset, outcome, shares, price, up_price, down_price);
+        let (p_up, p_down) = signals::implied_probabilities(up_price, down_price);
+        let decision = Some(Decision {
+            rule: Rationale::Plugin,
+            trend: Trend::Flat,
+            up_price,
+            down_price,
+            ev: self.fees.buy_ev(if outcome == "Up" { p_up } else { p_down }, price, shares),
+        });
+        self.rate_control.record(asset, &[outcome], period_start, clock::now_ms());
+        let order = self.place_limit_order(&quote.token_id, "BUY", price, shares, period_end).await?;
+        let (up_order_id, down_order_id) = if outcome == "Up" { (order.order_id, None) } else { (None, order.order_id) };
+        let (up_order_price, down_order_price) = if outcome == "Up" { (price, 0.0) } else { (0.0, price) };
+        let new_state = PreLimitOrderState {
+            asset: asset.to_string(),
+            condition_id: snapshot.condition_id.clone(),
+            up_token_id: snapshot.up.token_id.clone(),
+            down_token_id: snapshot.down.token_id.clone(),
+            up_order_id,
+            down_order_id,
+            up_order_price,
+            down_order_price,
+            shares,
+            up_matched: false,
+            down_matched: false,
+            merged: false,
+            expiry: period_end,
+            risk_sold: false,
+            order_placed_at: Self::get_current_time_et(),
+            market_period_start: period_start,
+            one_side_matched_at: None,
+            resting_cancelled: false,
+            sold_side: None,
+            unsold: 0.0,
+            partial_sell: None,
+            spike_sold: None,
+            decision,
+        };
+        states.insert(asset.to_string(), new_state);
+        Ok(())
+    }
+
+    /// An `event` market: one pinned binary market traded as a single period that runs until
+    /// the market ends. The pair goes out at mid-market prices while the market is open, and is
+    /// then managed like any other position. Nothing rolls over, so each market is entered once.
+    async fn process_event(&self, asset: &str) -> Result<()> {
+        let mut states = self.states.lock().await;
+        if let Some(s) = states.get(asset).cloned() {
+            tracing::Span::current()
+                .record("condition_id", s.condition_id.as_str())
+                .record("period_timestamp", s.market_period_start);
+            return self.manage_position(&mut states, asset, s).await;
+        }
+        if !self.config.strategy.signal.mid_market_enabled
+            || self.is_paused(asset)
+            || self.risk.is_halted().await
+            || self.risk.is_short_of_funds().await
+        {
+            return Ok(());
+        }
+
+        let market = self.discovery.find_event_market(asset).await?;
+        if !market.active || market.closed {
+            return Ok(());
+        }
+        let Some(end) = market.end_timestamp() else {
+            anyhow::bail!("event market {} has no end time", market.condition_id);
+        };
+        let current_time_et = Self::get_current_time_et();
+        let current_period_et = Self::get_current_15m_period_et();
+        // Same margin as mid-market orders: a pair placed this late would hit danger_time_passed
+        let min_remaining_to_place = (self.config.strategy.signal.danger_time_passed * 60) as i64;
+        if end - current_time_et < min_remaining_to_place {
+            log::debug!("{} | Skipping event orders: {}s left (need {}s for danger_time_passed)",
+                asset, end - current_time_et, min_remaining_to_place);
+            return Ok(());
+        }
+        if self.in_blackout(asset, current_period_et, "event orders") {
+            return Ok(());
+        }
+
+        let started = std::time::Instant::now();
+        let (up_token_id, down_token_id) = self.discovery.get_event_tokens(&market.condition_id).await?;
+        let Some((up_price, down_price)) = self.quote_pair(asset, &up_token_id, &down_token_id, started).await else {
+            return Ok(());
+        };
+        let (up_order_price, down_order_price) = signals::mid_market_prices(&self.config.strategy.signal, up_price, down_price);
+        let (up_order_price, down_order_price) = (Self::round_price(up_order_price), Self::round_price(down_order_price));
+        let odds = signals::implied_probabilities(up_price, down_price);
+        let base = self.allocated_shares(asset);
+        if base <= 0.0 {
+            log::debug!("{} | Balance allocation leaves nothing for event orders", asset);
+            return Ok(());
+        }
+        if let Some(ev) = self.ev_short(odds, up_order_price, down_order_price, base) {
+            log::debug!("{} | Event pair ${:.2} + ${:.2} expects ${:.4} after fees, not above ev_min_usd — not placing",
+                asset, up_order_price, down_order_price, ev);
+            return Ok(());
+        }
+        if let Err(throttled) = self.rate_control.check(asset, &["Up", "Down"], current_period_et, clock::now_ms()) {
+            self.note_throttled(asset, "event orders", throttled);
+            return Ok(());
+        }
+        let leg_cost = up_order_price.max(down_order_price) * base;
+        if let Err(breach) = self.risk.check_correlated(asset, leg_cost, |a| Self::asset_exposure(&states, a)) {
+            log::debug!("{} | Holding back event orders: {}", asset, breach);
+            return Ok(());
+        }
+        let pair_cost = self.buy_cost(up_order_price, base) + self.buy_cost(down_order_price, base);
+        if let Some(available) = self.sim_funds_short(&states, pair_cost) {
+            log::info!("🎮 SIMULATION: {} | ${:.2} of the simulated balance available, event pair costs ${:.2} — not placing",
+                asset, available.max(0.0), pair_cost);
+            return Ok(());
+        }
+        if self.snapshot_stale(asset, "event orders") {
+            return Ok(());
+        }
+
+        tracing::Span::current()
+            .record("condition_id", market.condition_id.as_str())
+            .record("period_timestamp", current_time_et);
+        log::info!("🏟️  {} | Placing event orders on \"{}\": first outcome @ ${:.2}, second @ ${:.2} (current ${:.2} / ${:.2})",
+            asset, market.question, up_order_price, down_order_price, up_price, down_price);
+        let shares = self.order_shares(asset).await;
+        let decision = Some(Decision {
+            rule: Rationale::MidMarket,
+            trend: Trend::Flat,
+            up_price,
+            down_price,
+            ev: self.fees.pair_ev(odds, up_order_price, down_order_price, shares),
+        });
+        self.rate_control.record(asset, &["Up", "Down"], current_period_et, clock::now_ms());
+        let up_order = self.place_limit_order(&up_token_id, "BUY", up_order_price, shares, end).await?;
+        let down_order = self.place_limit_order(&down_token_id, "BUY", down_order_price, shares, end).await?;
+        let new_state = PreLimitOrderState {
+            asset: asset.to_string(),
+            condition_id: market.condition_id,
+            up_token_id,
+            down_token_id,
+            up_order_id: up_order.order_id,
+            down_order_id: down_order.order_id,
+            up_order_price,
+            down_order_price,
+            shares,
+            up_matched: false,
+            down_matched: false,
+            merged: false,
+            expiry: end,
+            risk_sold: false,
+            order_placed_at: current_time_et,
+            market_period_start: current_time_et,
+            one_side_matched_at: None,
+            resting_cancelled: false,
+            sold_side: None,
+            unsold: 0.0,
+            partial_sell: None,
+            spike_sold: None,
+            decision,
+        };
+        states.insert(asset.to_string(), new_state);
+        Ok(())
+    }
+
+    /// Checks fills on `asset`'s pair and applies the exits (sell-opposite, spike sell, danger
+    /// exit) until the market expires, when the state is cleared.
+    async fn manage_position(&self, states: &mut HashMap<String, PreLimitOrderState>, asset: &str, mut s: PreLimitOrderState) -> Result<()> {
+        self.check_order_matches(&mut s).await?;
+
+        if let Some(partial) = s.partial_sell.clone() {
+            self.follow_up_sell(&mut s, &partial).await;
+        }
+
+        if self.config.strategy.sell_opposite_enabled && s.up_matched && s.down_matched && !s.merged && s.partial_sell.is_none() {
+            let threshold = self.config.strategy.sell_opposite_above;
+            let (up_price, down_price) = (
+                self.feed.price(&s.up_token_id).await.ok()
+                    .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0),
+                self.feed.price(&s.down_token_id).await.ok()
+                    .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0),
+            );
+
+            // Calculate time remaining in the current market period
+            let current_time_et = Self::get_current_time_et();
+            let market_end_time = s.expiry;
+            let time_remaining_seconds = market_end_time - current_time_et;
+            let time_remaining_mins = time_remaining_seconds / 60;
+            let required_time_remaining_mins = self.config.strategy.sell_opposite_time_remaining as i64;
+
+            let sell_opposite = if up_price >= threshold {
+                Some(("Up", "Down", s.down_token_id.clone()))
+            } else if down_price >= threshold {
+                Some(("Down", "Up", s.up_token_id.clone()))
+            } else {
+                None
+            };
+
+            // Only sell if the price threshold is met AND time remaining is low enough (and, with
+            // strategy.signal.momentum, the spot candle backs the winner)
+            if let Some((winner, loser, token_to_sell)) = sell_opposite {
+                if time_remaining_mins > required_time_remaining_mins {
+                    log::debug!("{}: {} price ${:.2} >= {:.2}, but {}min remaining > {}min threshold — holding both positions", 
+                        asset, winner, if winner == "Up" { up_price } else { down_price }, threshold,
+                        time_remaining_mins, required_time_remaining_mins);
+                } else if let Some(progress) = self.unconfirmed_momentum(&s, winner == "Up").await {
+                    log::debug!("{}: {} price ${:.2} >= {:.2}, but the spot candle is {} — holding both positions",
+                        asset, winner, if winner == "Up" { up_price } else { down_price }, threshold,
+                        progress.map_or("unknown".to_string(), |p| format!("at {:+.2}× a typical range", p)));
+                } else if let Some(bias) = self.bias(asset).filter(|b| b.against(winner == "Up")) {
+                    log::debug!("{}: {} price ${:.2} >= {:.2}, but an external signal has a {:?} bias — holding both positions",
+                        asset, winner, if winner == "Up" { up_price } else { down_price }, threshold, bias);
+                } else {
+                    log::info!("{}: Both filled, {} price ${:.2} >= {:.2} AND {}min remaining <= {}min — selling {} to reduce loss", 
+                        asset, winner, if winner == "Up" { up_price } else { down_price }, threshold, 
+                        time_remaining_mins, required_time_remaining_mins, loser);
+                    let sell_price = self.feed.price(&token_to_sell).await.ok()
+                        .and_then(|p| p.to_string().parse::<f64>().ok()).unwrap_or(0.0);
+                    if let Some(sale) = self.sell_side(&mut s, loser, sell_price, Rationale::SellOpposite).await {
+                        log::info!("   Sold {:.2} {} shares at ${:.4}. Holding {} to expiry (pays $1)", sale.shares, loser, sale.avg_price, winner);
+                        self.finish_sale(&mut s, loser, Rationale::SellOpposite, sale).await;
+                    }
+                }
+            }
+            // When both filled but neither side >= sell_opposite_above: do nothing.
+            // Hold both until one side hits threshold (re-check next tick) or expiry (redeem).
+        }
+
+        if s.merged && !s.risk_sold && s.spike_sold.is_none() && self.config.strategy.spike_sell.enabled {
+            self.spike_sell(&mut s).await;
+        }
+
+        let current_time_et = Self::get_current_time_et();
+
+        // Track when we first had only one side matched (for danger_time_passed)
+        let only_one_matched = (s.up_matched && !s.down_matched) || (s.down_matched && !s.up_matched);
+        if only_one_matched && s.one_side_matched_at.is_none() {
+            s.one_side_matched_at = Some(current_time_et);
+        }
+
+        // One-side risk management: "price" = sell when matched token <= danger_price; "time" = sell after danger_time_passed mins
+        let mode = signals::one_side_risk_mode(&self.config.strategy.signal);
+        let mut should_sell_early = if !only_one_matched || Self::is_plugin(&s) {
+            false
+        } else if mode == OneSideRiskMode::Price {
+            if s.up_matched && !s.down_matched {
+                self.feed.price(&s.up_token_id).await
+                    .ok()
+                    .and_then(|p| p.to_string().parse::<f64>().ok())
+                    .map(|p| signals::is_danger_signal(&self.config.strategy.signal, p))
+                    .unwrap_or(false)
+            } else {
+                self.feed.price(&s.down_token_id).await
+                    .ok()
+                    .and_then(|p| p.to_string().parse::<f64>().ok())
+                    .map(|p| signals::is_danger_signal(&self.config.strategy.signal, p))
+                    .unwrap_or(false)
+            }
+        } else if mode == OneSideRiskMode::Time {
+            let danger_mins = self.config.strategy.signal.danger_time_passed as i64;
+            s.one_side_matched_at.is_some_and(|t| current_time_et - t >= danger_mins * 60)
+        } else {
+            false
+        };
+
+        // Production only: when danger would trigger, verify both orders via API first.
+        // If both filled, don't sell — update state and let "both matched" logic handle next tick.
+        if !self.simulated() && should_sell_early {
+            if let (Some(up_id), Some(down_id)) = (&s.up_order_id, &s.down_order_id) {
+                match self.api.are_both_orders_filled(up_id, down_id).await {
+                    Ok((true, true)) => {
+                        log::info!("{}: Danger signal but both orders filled (verified via API) — skipping sell", asset);
+                        if !s.up_matched {
+                            self.record_fill(&s, "Up", "api");
+                        }
+                        if !s.down_matched {
+                            self.record_fill(&s, "Down", "api");
+                        }
+                        s.up_matched = true;
+                        s.down_matched = true;
+                        should_sell_early = false;
+                    }
+                    Ok(_) => { /* one or both not filled, proceed with sell */ }
+                    Err(e) => {
+                        log::warn!("{}: Failed to verify order status: {} — proceeding with danger sell", asset, e);
+                    }
+                }
+            }
+        }
+
+        let should_sell = !s.merged && !s.risk_sold && s.partial_sell.is_none() && should_sell_early;
+
+        if should_sell {
+            let reason = if mode == OneSideRiskMode::Time {
+                format!("Danger time passed ({}min since match)", self.config.strategy.signal.danger_time_passed)
+            } else {
+                "Danger signal (price collapsed)".to_string()
+            };
+            let (outcome, other, token_id) = if s.up_matched {
+                ("Up", "Down", s.up_token_id.clone())
+            } else {
+                ("Down", "Up", s.down_token_id.clone())
+            };
+            log::warn!("{}: {} — only {} token matched. Selling {} token and canceling {} order", asset, reason, outcome, outcome, other);
+            let sell_price = self.feed.price(&token_id).await
+                .ok()
+                .and_then(|p| p.to_string().parse::<f64>().ok())
+                .unwrap_or(0.0);
+            let sale = self.sell_side(&mut s, outcome, sell_price, Rationale::Danger).await;
+            // Once anything has sold, the other side's order must not fill
+            if sale.is_some() || s.partial_sell.is_some() {
+                self.cancel_other_order(&s, other).await;
+            }
+            if let Some(sale) = sale {
+                self.finish_sale(&mut s, outcome, Rationale::Danger, sale).await;
+            }
+        }
+
+        let current_time_et = Self::get_current_time_et();
+        if current_time_et > s.expiry {
+            // Register for redemption (production, or a simulated bankroll) if we held both until expiry (sold opposite already registered)
+            // A sell still waiting for a follow-up leaves its remainder held
+            if let Some(partial) = s.partial_sell.clone() {
+                let sale = self.close_partial_sell(&mut s, &partial, "market expired");
+                self.finish_sale(&mut s, &partial.outcome, partial.rationale, sale).await;
+            } else if self.settles_resolutions() && !s.risk_sold && !s.merged
+                && ((s.up_matched && s.down_matched) || (Self::is_plugin(&s) && (s.up_matched || s.down_matched)))
+            {
+                let trade = Self::held_trade(&s);
+                let mut t = self.trades.lock().await;
+                t.insert(s.condition_id.clone(), trade);
+                log::info!("   Registered position for redemption when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
+            }
+            log::info!("Market expired for {}. Clearing state.", asset);
+            self.api.invalidate_market(&s.condition_id);
+            states.remove(asset);
+            self.publish_position(asset, None);
+        } else {
+            self.publish_position(asset, Some(&s));
+            states.insert(asset.to_string(), s);
+        }
+        Ok(())
+    }
+
+    /// (Up, Down) USD exposed on `asset`'s market if it resolves against that side: the cost of
+    /// the shares held or still resting on one side beyond those held on the other. Sold shares
+    /// are settled; what a sale left held (the sell-opposite winner, an unsold remainder) is not.
+    fn asset_exposure(states: &HashMap<String, PreLimitOrderState>, asset: &str) -> (f64, f64) {
+        let Some(s) = states.iter().find(|(a, _)| a.eq_ignore_ascii_case(asset)).map(|(_, s)| s) else {
+            return (0.0, 0.0);
+        };
+        // Once a side is sold the other side's order has been cancelled
+        let resting = |matched: bool, order: &Option<String>| {
+            !matched && order.is_some() && !s.resting_cancelled && !s.merged && !s.risk_sold
+        };
+        let (up_held, down_held) = (Self::held_shares(s, "Up"), Self::held_shares(s, "Down"));
+        let up = if resting(s.up_matched, &s.up_order_id) { s.shares } else { up_held };
+        let down = if resting(s.down_matched, &s.down_order_id) { s.shares } else { down_held };
+        ((up - down_held).max(0.0) * s.up_order_price, (down - up_held).max(0.0) * s.down_order_price)
+    }
+
+    /// With a simulated bankroll: `Some(available)` when what's left of the balance once the
+    /// resting buys fill can't pay for `cost` more.
+    fn sim_funds_short(&self, states: &HashMap<String, PreLimitOrderState>, cost: f64) -> Option<f64> {
+        let balance = (*self.sim_balance.lock().unwrap())?;
+        let resting: f64 = states
+            .values()
+            .filter(|s| !s.merged && !s.risk_sold && !s.resting_cancelled)
+            .map(|s| {
+                let leg = |matched: bool, order: &Option<String>, price: f64| {
+                    if matched || order.is_none() { 0.0 } else { self.buy_cost(price, s.shares) }
+                };
+                leg(s.up_matched, &s.up_order_id, s.up_order_price) + leg(s.down_matched, &s.down_order_id, s.down_order_price)
+            })
+            .sum();
+        let available = balance - resting;
+        (available < cost).then_some(available)
+    }
+
+    /// What a buy of `shares` at `price` costs once it fills, maker fee included.
+    fn buy_cost(&self, price: f64, shares: f64) -> f64 {
+        price * shares + self.fees.maker_fee(price, shares)
+    }
+
+    /// Whether the market period starting at `period_start` overlaps a blackout window; logged
+    /// once per market and period.
+    fn in_blackout(&self, asset: &str, period_start: i64, what: &str) -> bool {
+        let Some(label) = self.blackouts.overlapping(period_start, period_start + MARKET_DURATION_SECS) else {
+            return false;
+        };
+        if self.blackout_noted.lock().unwrap().insert(asset.to_string(), period_start) != Some(period_start) {
+            log::info!("🚫 {} | {} blackout — no {} for the period starting {}", asset, label, what, period_start);
+        }
+        true
+    }
+
+    /// Whether the current period's market has gone unfound and the next lookup isn't due yet;
+    /// no new orders go out for `asset` meanwhile. Raises one alert once the period has run
+    /// `discovery.alert_after_secs` without its market.
+    fn no_active_market(&self, asset: &str, period_start: i64) -> bool {
+        let Some(missing) = self.discovery.missing(asset, MarketDuration::M15, period_start) else {
+            return false;
+        };
+        let waited_secs = clock::now() - period_start;
+        if waited_secs >= self.config.discovery.alert_after_secs as i64
+            && self.discovery.flag_missing_alert(asset, MarketDuration::M15, period_start)
+        {
+            log::error!("🚨 {} | No market listed for the period starting {} after {}s ({} lookups) — not trading it",
+                asset, period_start, waited_secs, missing.attempts);
+            tracing::warn!(
+                target: EVENTS,
+                event = "market_missing",
+                market = asset,
+                period_timestamp = period_start,
+                waited_secs,
+                attempts = missing.attempts,
+            );
+        }
+        !missing.retry_due()
+    }
+
+    /// Whether `asset`'s latest snapshot was requested more than `strategy.max_snapshot_age_ms`
+    /// ago, so `what` priced from it must not go out. The next tick reads fresh prices.
+    fn snapshot_stale(&self, asset: &str, what: &str) -> bool {
+        let max_age = self.config.strategy.max_snapshot_age_ms;
+        if max_age == 0 {
+            return false;
+        }
+        let Some((requested, _)) = self.last_snapshot.lock().unwrap().get(asset).copied() else {
+            return false;
+        };
+        let age_ms = requested.elapsed().as_millis() as u64;
+        if age_ms <= max_age {
+            return false;
+        }
+        warn!("⌛ {} | Prices are {}ms old, over max_snapshot_age_ms {} — not placing {}", asset, age_ms, max_age, what);
+        metrics().inc_orders_throttled("stale_snapshot");
+        true
+    }
+
+    /// (Up, Down) odds for the EV gate: the side model's when `strategy.signal.model` is on and
+    /// it could score the market, otherwise `market_odds`.
+    async fn model_odds(&self, asset: &str, period_start: i64, market_odds: (f64, f64)) -> (f64, f64) {
+        let Some(model) = &self.model else { return market_odds };
+        match model.up_probability(asset, period_start).await {
+            Some(p) => {
+                log::info!("🤖 {} | Model gives Up {:.3} (market {:.3})", asset, p, market_odds.0);
+                (p, 1.0 - p)
+            }
+            None => market_odds,
+        }
+    }
+
+    /// Expected profit of a pair buy at these prices when it isn't above `strategy.ev_min_usd`.
+    fn ev_short(&self, odds: (f64, f64), up_price: f64, down_price: f64, shares: f64) -> Option<f64> {
+        let ev = self.fees.pair_ev(odds, up_price, down_price, shares);
+        (ev <= self.config.strategy.ev_min_usd).then_some(ev)
+    }
+
+    fn note_throttled(&self, asset: &str, what: &str, throttled: Throttled) {
+        log::debug!("{} | Holding back {}: {}", asset, what, throttled);
+        metrics().inc_orders_throttled(throttled.label());
+    }
+
+    async fn get_market_snapshot(&self, asset: &str, period_start: i64) -> Option<(f64, f64, i64)> {
+        let started = std::time::Instant::now();
+        let market = self.discovery.find_market(asset, MarketDuration::M15, period_start).await.ok()?;
+        if !market.active || market.closed {
+            return None;
+        }
+        let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&market.condition_id).await.ok()?;
+        let (up_price, down_price) = self.quote_pair(asset, &up_token_id, &down_token_id, started).await?;
+        self.price_history
+            .lock()
+            .await
+            .entry(asset.to_string())
+            .or_default()
+            .push(period_start, clock::now_ms(), up_price, self.history_capacity());
+        let current_time_et = Self::get_current_time_et();
+        let market_end = period_start + MARKET_DURATION_SECS;
+        let time_remaining = market_end - current_time_et;
+        Some((up_price, down_price, time_remaining.max(0)))
+    }
+
+    /// Current Up and Down prices, or `None` when either is missing or implausible. Marks the
+    /// time the quote was requested (`started`) for `snapshot_stale`.
+    async fn quote_pair(&self, asset: &str, up_token_id: &str, down_token_id: &str, started: std::time::Instant) -> Option<(f64, f64)> {
+        let (up_res, down_res) = tokio::join!(
+            self.feed.price(up_token_id),
+            self.feed.price(down_token_id)
+        );
+        let up_price = up_res.ok()?.to_string().parse::<f64>().ok()?;
+        let down_price = down_res.ok()?.to_string().parse::<f64>().ok()?;
+        if let Some(reason) = monitor::implausible_asks(&self.config.monitor, up_price, down_price) {
+            warn!("🧪 {} | Ignoring implausible prices: {}", asset, reason);
+            metrics().inc_invalid_snapshot(asset);
+            return None;
+        }
+        metrics().observe_snapshot_latency(started.elapsed().as_secs_f64());
+        metrics().observe_latency("book_fetch", started.elapsed());
+        self.last_snapshot.lock().unwrap().insert(asset.to_string(), (started, std::time::Instant::now()));
+        Some((up_price, down_price))
+    }
+
+    fn history_capacity(&self) -> usize {
+        self.config.strategy.signal.trend.window.max(self.config.strategy.volatility_sizing.window)
+    }
+
+    /// Signal for placing a pair, with the Up and Down prices and Up trend it was read from.
+    async fn get_place_signal(&self, asset: &str, period_start: i64) -> (MarketSignal, Option<(f64, f64, Trend)>) {
+        let Some((up_price, down_price, time_remaining)) = self.get_market_snapshot(asset, period_start).await else {
+            return (MarketSignal::Unknown, None);
+        };
+        let signal = signals::evaluate_place_signal(
+            &self.config.strategy.signal,
+            up_price,
+            down_price,
+            time_remaining,
+        );
+        let measured = match self.price_history.lock().await.get(asset) {
+            Some(history) => history.trend(&self.config.strategy.signal.trend),
+            None => Trend::Flat,
+        };
+        // A posted signal overrides the measured trend either way
+        let trend = match self.bias(asset) {
+            Some(bias) if bias.trend() != measured => {
+                log::debug!("{} | External {:?} bias overrides the measured {:?} trend", asset, bias, measured);
+                bias.trend()
+            }
+            _ => measured,
+        };
+        if signal == MarketSignal::Good && trend != Trend::Flat {
+            log::debug!("{} | Up price {:?} — treating signal as Bad", asset, trend);
+        }
+        (signals::with_trend(signal, trend), Some((up_price, down_price, trend)))
+    }
+
+    /// `strategy.shares`, scaled down by the recent volatility of `asset`'s Up price when
+    /// volatility sizing is enabled.
+    /// Shares per side for `asset` before volatility sizing: `strategy.shares`, or less when
+    /// `strategy.allocation` splits a balance that can't fund every market at full size.
+    fn allocated_shares(&self, asset: &str) -> f64 {
+        let shares = self.config.strategy.shares;
+        let bankroll = if self.config.strategy.simulation_mode {
+            *self.sim_balance.lock().unwrap()
+        } else {
+            *self.usdc_balance.lock().unwrap()
+        };
+        let Some(bankroll) = bankroll else { return shares };
+        let pair_cost = 2.0 * self.buy_cost(self.config.strategy.price_limit, shares);
+        self.allocator.shares(asset, shares, pair_cost, bankroll)
+    }
+
+    async fn order_shares(&self, asset: &str) -> f64 {
+        let cfg = &self.config.strategy.volatility_sizing;
+        let base = self.allocated_shares(asset);
+        if base < self.config.strategy.shares {
+            log::info!("💼 {} | Balance split by allocation weights — sizing down to {} shares (from {})",
+                asset, base, self.config.strategy.shares);
+        }
+        let std_dev = self.price_history.lock().await.get(asset).and_then(|h| h.std_dev(cfg.window));
+        let shares = trend::volatility_scaled_shares(cfg, base, std_dev);
+        if shares < base {
+            log::info!("{} | Up price std dev {:.4} — sizing down to {} shares (from {})",
+                asset, std_dev.unwrap_or_default(), shares, base);
+        }
+        let scale = self.stats.lock().unwrap().size_scale(asset);
+        if scale >= 1.0 {
+            return shares;
+        }
+        let scaled = ((shares * scale * 100.0).floor() / 100.0).max(0.01);
+        log::info!("📉 {} | Losing streak — sizing down to {} shares (from {}, scale {:.2})", asset, scaled, shares, scale);
+        scaled
+    }
+
+    async fn discover_next_market(&self, asset_name: &str, next_timestamp: i64) -> Result<Option<Market>> {
+        match self.discovery.find_market(asset_name, MarketDuration::M15, next_timestamp).await {
+            Ok(m) => {
+                if m.active && !m.closed {
+                    Ok(Some(m))
+                } else {
+                    Ok(None)
+                }
+            }
+            Err(e) => {
+                log::debug!("Failed to find {} market for period {}: {}", asset_name, next_timestamp, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Looks up the market of an upcoming period ahead of its pre-order window, so the Gamma
+    /// events search is done (and cached by discovery) before orders need to go out.
+    pub async fn prediscover(&self, asset: &str, period_start: i64) {
+        if !self.config.traded_assets().iter().any(|a| a.eq_ignore_ascii_case(asset)) {
+            return;
+        }
+        match self.discover_next_market(asset, period_start).await {
+            Ok(Some(market)) => log::info!("🔭 {} | Next market found ahead of period {}: {}", asset, period_start, market.condition_id),
+            _ => log::debug!("{} | Next market for period {} not listed yet", asset, period_start),
+        }
+    }
+
+    /// Settles positions whose markets have resolved and redeems the queue when it's due.
+    pub async fn check_market_closure(&self) -> Result<()> {
+        let _running = self.closure_running.lock().await;
+        self.resolve_closed_markets().await?;
+        self.process_redeem_queue(false).await;
+        Ok(())
+    }
+
+    async fn resolve_closed_markets(&self) -> Result<()> {
+        let trades: Vec<(String, CycleTrade)> = {
+            let t = self.trades.lock().await;
+            t.iter()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect()
+        };
+        if trades.is_empty() {
+            return Ok(());
+        }
+        let current_time = clock::now() as u64;
+
+        for (market_key, trade) in trades {
+            let market_end = trade.period_timestamp + trade.market_duration_secs;
+            if current_time < market_end {
+                continue;
+            }
+
+            let checked = self.closure_checked.lock().await;
+            if checked.get(&trade.condition_id).copied().unwrap_or(false) {
+                drop(checked);
+                continue;
+            }
+            drop(checked);
+
+            let due = self
+                .awaiting_resolution
+                .lock()
+                .unwrap()
+                .get(&trade.condition_id)
+                .is_none_or(|w| current_time >= w.next_check);
+            if !due {
+                continue;
+            }
+            let reference = match self.config.strategy.resolution.simulated_winner {
+                SimulatedWinner::Reference if self.simulated() => self.reference_winner(&trade).await,
+                _ => None,
+            };
+            let winner = if let Some(up_wins) = reference {
+                let outcome = if up_wins { "Up" } else { "Down" };
+                log::info!("🧪 Market {} settled on the reference price: {} wins", &trade.condition_id[..16], outcome);
+                trade.leg(outcome)
+            } else {
+                let market = match self.api.get_market(&trade.condition_id).await {
+                    Ok(m) => m,
+                    Err(e) => {
+                        warn!("Failed to fetch market {}: {}", &trade.condition_id[..16], e);
+                        self.defer_resolution(&trade, current_time, false);
+                        continue;
+                    }
+                };
+
+                let flagged = |leg: &TradeLeg| {
+                    market.closed
+                        && leg
+                            .token_id
+                            .as_ref()
+                            .is_some_and(|id| market.tokens.iter().any(|t| t.token_id == *id && t.winner))
+                };
+                let mut winners: Vec<&TradeLeg> = trade.legs.iter().filter(|l| flagged(l)).collect();
+                if winners.is_empty() && self.config.strategy.resolution.onchain_fallback {
+                    // Payout numerators are read as [Up, Down], so this only settles binary markets
+                    let (up_wins, down_wins) = self.onchain_winner(&trade.condition_id).await;
+                    winners = trade
+                        .legs
+                        .iter()
+                        .filter(|l| (up_wins && l.outcome == "Up") || (down_wins && l.outcome == "Down"))
+                        .collect();
+                }
+                if winners.is_empty() {
+                    // Closed with no winner flagged means the outcome is disputed or not reported yet
+                    self.defer_resolution(&trade, current_time, market.closed);
+                    continue;
+                }
+                winners.first().copied()
+            };
+            let Some(winner) = winner else { continue };
+            self.awaiting_resolution.lock().unwrap().remove(&trade.condition_id);
+
+            let fees: f64 = trade.legs.iter().map(|l| self.fees.maker_fee(l.avg_price, l.shares)).sum();
+            let total_cost = trade.legs.iter().map(TradeLeg::cost).sum::<f64>() + fees;
+            let payout = winner.shares * 1.0;
+            let pnl = payout - total_cost;
+
+            tracing::info!(
+                target: EVENTS,
+                event = "market_resolved",
+                condition_id = %trade.condition_id,
+                period_timestamp = trade.period_timestamp,
+                winner = %winner.outcome,
+                up_shares = trade.shares("Up"),
+                down_shares = trade.shares("Down"),
+                cost = total_cost,
+                payout,
+                pnl,
+            );
+            let held: Vec<String> = trade
+                .legs
+                .iter()
+                .map(|l| format!("{} {} @ {}", l.outcome, display::size(l.shares, l.avg_price), display::price(l.avg_price)))
+                .collect();
+            eprintln!("=== Market resolved ===");
+            eprintln!(
+                "Market closed | condition {} | Winner: {} | {} | Cost {} | Payout {} | Actual PnL {}",
+                &trade.condition_id[..16],
+                winner.outcome,
+                held.join(" | "),
+                display::usd(total_cost),
+                display::usd(payout),
+                display::pnl(pnl, trade.total_shares())
+            );
+
+            if let Some(ledger) = &self.ledger {
+                ledger.record(&LedgerEntry {
+                    timestamp: clock::now(),
+                    kind: "resolution".to_string(),
+                    market: trade.market.clone(),
+                    condition_id: trade.condition_id.clone(),
+                    period_timestamp: trade.period_timestamp as i64,
+                    side: winner.outcome.clone(),
+                    price: 1.0,
+                    size: winner.shares,
+                    fees,
+                    cost_per_pair: trade.pair_cost(&self.fees),
+                    pnl: Some(pnl),
+                    account: self.account.clone(),
+                    ..Default::default()
+                }.tagged(Rationale::Resolution, trade.decision.as_ref()));
+            }
+
+            if !self.simulated() {
+                // Nothing of the winner held: the redeem still burns the losing tokens
+                let redeemed = if winner.shares > 0.001 {
+                    winner
+                } else {
+                    trade.legs.iter().find(|l| l.outcome != winner.outcome).unwrap_or(winner)
+                };
+                self.redeem_queue.lock().await.push(PendingRedeem {
+                    condition_id: trade.condition_id.clone(),
+                    token_id: redeemed.token_id.clone().unwrap_or_default(),
+                    outcome: redeemed.outcome.clone(),
+                    value: payout,
+                    resolved_at: current_time as i64,
+                });
+            } else {
+                self.adjust_sim_balance(payout);
+            }
+
+            let total_actual_pnl = self.record_realized_pnl(pnl).await;
+            let timeframe = stats::timeframe(trade.market_duration_secs);
+            let rescaled = self.stats.lock().unwrap().resolve(&trade.market, timeframe, &trade.condition_id, pnl);
+            if let Some((before, after)) = rescaled {
+                let why = if after < before { "losing streak" } else { "winning period" };
+                log::info!("📉 {} | {} — size scale {:.2} -> {:.2}", trade.market, why, before, after);
+            }
+            eprintln!(
+                "  -> Actual PnL this market: {} | Total actual PnL (all time): {}",
+                display::pnl(pnl, trade.total_shares()),
+                display::usd(total_actual_pnl)
+            );
+            {
+                let mut c = self.closure_checked.lock().await;
+                c.insert(trade.condition_id.clone(), true);
+            }
+            let mut t = self.trades.lock().await;
+            t.remove(&market_key);
+        }
+        Ok(())
+    }
+
+    /// Whether Up won `trade`'s period by the reference price, for `resolution.simulated_winner
+    /// = "reference"`: in a replay by the final recorded quotes, otherwise by the underlying's
+    /// spot price at the period's end against its start (a tie goes to Up, as on Polymarket).
+    /// `None` while either price can't be read, and for markets that aren't Up/Down.
+    async fn reference_winner(&self, trade: &CycleTrade) -> Option<bool> {
+        if let Some(replay) = self.api.replay() {
+            return replay.final_up_wins(&trade.condition_id);
+        }
+        let market = self.config.markets.iter().find(|m| m.asset == trade.market && m.family == MarketFamily::UpDown)?;
+        let spot = self.spot.as_ref()?;
+        let (start, end) = (trade.period_timestamp as i64, (trade.period_timestamp + trade.market_duration_secs) as i64);
+        let symbol = market.spot_symbol();
+        match tokio::try_join!(spot.price_at(&symbol, start), spot.price_at(&symbol, end)) {
+            Ok((Some(open), Some(close))) => Some(close >= open),
+            Ok(_) => None,
+            Err(e) => {
+                log::debug!("Reference price of {} for period {} unavailable: {:#}", symbol, start, e);
+                None
+            }
+        }
+    }
+
+    /// Winner from the ConditionalTokens payout numerators, for when the CLOB metadata lags
+    /// the on-chain resolution. Returns `(up_wins, down_wins)`; both false while unreported,
+    /// on RPC errors, and for split payouts, which are left for the API to settle.
+    async fn onchain_winner(&self, condition_id: &str) -> (bool, bool) {
+        let short = &condition_id[..condition_id.len().min(16)];
+        match self.api.get_onchain_payouts(condition_id).await {
+            Ok(Some([up, down])) if (up > 0.0 && down == 0.0) || (down > 0.0 && up == 0.0) => {
+                log::info!("🔗 Market {} resolved on-chain: {} wins (CLOB hasn't flagged it yet)",
+                    short, if up > 0.0 { "Up" } else { "Down" });
+                (up > 0.0, down > 0.0)
+            }
+            Ok(Some([up, down])) => {
+                warn!("Market {} resolved on-chain with split payouts (Up {:.2}, Down {:.2})", short, up, down);
+                (false, false)
+            }
+            Ok(None) => (false, false),
+            Err(e) => {
+                log::debug!("On-chain payout lookup for {} failed: {:#}", short, e);
+                (false, false)
+            }
+        }
+    }
+
+    /// Schedules the next resolution check for an ended market with exponential backoff. The
+    /// trade is marked pending-resolution in the ledger once the market is seen closed without
+    /// a winner (`disputed`) or runs past `alert_after_mins`, which also raises an alert.
+    fn defer_resolution(&self, trade: &CycleTrade, now: u64, disputed: bool) {
+        let cfg = &self.config.strategy.resolution;
+        let market_end = trade.period_timestamp + trade.market_duration_secs;
+        let overdue = now.saturating_sub(market_end) >= cfg.alert_after_mins * 60;
+        let (attempts, delay, mark, alert) = {
+            let mut waits = self.awaiting_resolution.lock().unwrap();
+            let w = waits.entry(trade.condition_id.clone()).or_default();
+            let delay = cfg.retry_secs.saturating_mul(1 << w.attempts.min(16)).min(cfg.max_retry_secs);
+            w.attempts += 1;
+            w.next_check = now + delay;
+            let mark = !w.marked && (disputed || overdue);
+            let alert = !w.alerted && overdue;
+            w.marked |= mark;
+            w.alerted |= alert;
+            (w.attempts, delay, mark, alert)
+        };
+        let state = if disputed { "closed without a winner" } else { "not resolved" };
+        log::debug!(
+            "Market {} {} (attempt {}), checking again in {}s",
+            &trade.condition_id[..16], state, attempts, delay
+        );
+
+        if mark {
+            warn!("⏳ Market {} {} — marked pending resolution", &trade.condition_id[..16], state);
+            if let Some(ledger) = &self.ledger {
+                ledger.record(&LedgerEntry {
+                    timestamp: clock::now(),
+                    kind: "pending_resolution".to_string(),
+                    market: trade.market.clone(),
+                    condition_id: trade.condition_id.clone(),
+                    period_timestamp: trade.period_timestamp as i64,
+                    side: String::new(),
+                    price: 0.0,
+                    size: trade.legs.iter().map(|l| l.shares).fold(0.0, f64::max),
+                    fees: 0.0,
+                    cost_per_pair: trade.pair_cost(&self.fees),
+                    pnl: None,
+                    account: self.account.clone(),
+                    ..Default::default()
+                }.tagged(Rationale::Resolution, trade.decision.as_ref()));
+            }
+        }
+        if alert {
+            let waited_mins = now.saturating_sub(market_end) / 60;
+            log::error!(
+                "🚨 Market {} ({}) still {} {} min after it ended — check for a dispute on Polymarket",
+                &trade.condition_id[..16], trade.market, state, waited_mins
+            );
+            tracing::warn!(
+                target: EVENTS,
+                event = "resolution_delayed",
+                condition_id = %trade.condition_id,
+                period_timestamp = trade.period_timestamp,
+                disputed,
+                waited_mins,
+            );
+        }
+    }
+
+    /// Corrects the shares recorded for pending trades to what the wallet actually holds. Trades
+    /// are keyed by condition ID and built from the bot's own view of its fills, so a restart or
+    /// an amended order mid-period can leave them out of step with on-chain holdings, and
+    /// resolution would then settle the wrong size. Live mode with a proxy wallet only.
+    pub async fn reconcile_positions(&self) -> Result<()> {
+        if self.simulated() {
+            return Ok(());
+        }
+        let Some(wallet) = self.config.polymarket.proxy_wallet_address.as_deref() else {
+            return Ok(());
+        };
+        let trades: Vec<(String, CycleTrade)> = {
+            let t = self.trades.lock().await;
+            t.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
+        };
+
+        for (market_key, trade) in trades {
+            let positions = match self.api.get_positions(wallet, &trade.condition_id).await {
+                Ok(p) => p,
+                Err(e) => {
+                    warn!("Failed to fetch positions for {}: {}", &trade.condition_id[..trade.condition_id.len().min(16)], e);
+                    continue;
+                }
+            };
+            let held: Vec<Option<&DataApiPosition>> = trade
+                .legs
+                .iter()
+                .map(|l| l.token_id.as_ref().and_then(|id| positions.iter().find(|p| &p.asset == id)))
+                .collect();
+            let in_step = trade.legs.iter().zip(&held).all(|(l, p)| (p.map_or(0.0, |p| p.size) - l.shares).abs() < 0.01);
+            if in_step {
+                continue;
+            }
+
+            let recorded: Vec<String> = trade.legs.iter().map(|l| format!("{} {:.2}", l.outcome, l.shares)).collect();
+            let wallet_holds: Vec<String> = trade
+                .legs
+                .iter()
+                .zip(&held)
+                .map(|(l, p)| format!("{} {:.2}", l.outcome, p.map_or(0.0, |p| p.size)))
+                .collect();
+            warn!("🔄 {} {}: recorded {} shares, wallet holds {} — correcting",
+                trade.market, &trade.condition_id[..trade.condition_id.len().min(16)],
+                recorded.join(" / "), wallet_holds.join(" / "));
+            let mut t = self.trades.lock().await;
+            let Some(entry) = t.get_mut(&market_key) else { continue };
+            for (leg, position) in entry.legs.iter_mut().zip(&held) {
+                leg.shares = position.map_or(0.0, |p| p.size);
+                // A side we didn't know we held has no recorded price; take the data API's average
+                if let Some(p) = position.filter(|_| leg.avg_price == 0.0) {
+                    leg.avg_price = p.avg_price;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the account's fills on the exchange with the ledger's buy and sell rows, per
+    /// market, outcome and side, and raises an alert for every total that disagrees. Runs every
+    /// `fill_reconciliation.interval_mins` in live mode. Markets with activity near either end of
+    /// the lookback window are left out, so fills still being detected don't count as missing.
+    pub async fn reconcile_fills(&self) {
+        let cfg = &self.config.strategy.fill_reconciliation;
+        if !cfg.enabled || self.simulated() || !self.config.polymarket.can_sign() {
+            return;
+        }
+        let Some(ledger) = &self.ledger else { return };
+        {
+            let mut last = self.last_fill_check.lock().unwrap();
+            if last.is_some_and(|t| t.elapsed() < Duration::from_secs(cfg.interval_mins * 60)) {
+                return;
+            }
+            *last = Some(std::time::Instant::now());
+        }
+
+        let now = clock::now();
+        let since = now - cfg.lookback_hours as i64 * 3600;
+        let fills = match self.api.get_trades(since).await {
+            Ok(fills) => fills,
+            Err(e) => {
+                warn!("Failed to fetch trades for fill reconciliation: {}", e);
+                return;
+            }
+        };
+        ledger.flush();
+        let entries = match ledger::read_entries(&self.config.ledger.path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to read ledger for fill reconciliation: {}", e);
+                return;
+            }
+        };
+
+        // (condition ID, outcome, side) -> (exchange shares, ledger shares)
+        let mut totals: BTreeMap<(String, String, String), (f64, f64)> = BTreeMap::new();
+        // condition ID -> (first, last) activity on either side
+        let mut activity: HashMap<String, (i64, i64)> = HashMap::new();
+        let mut names: HashMap<String, String> = HashMap::new();
+        let mut touch = |condition_id: &str, at: i64| {
+            let span = activity.entry(condition_id.to_string()).or_insert((at, at));
+            *span = (span.0.min(at), span.1.max(at));
+        };
+        for fill in fills.iter().filter(|f| f.status != "FAILED") {
+            let condition_id = fill.condition_id.to_lowercase();
+            touch(&condition_id, fill.timestamp);
+            totals.entry((condition_id, fill.outcome.clone(), fill.side.to_uppercase())).or_default().0 += fill.size;
+        }
+        for entry in &entries {
+            let side = match entry.kind.as_str() {
+                "buy" => "BUY",
+                "sell" => "SELL",
+                _ => continue,
+            };
+            if entry.timestamp < since || entry.account != self.account {
+                continue;
+            }
+            let condition_id = entry.condition_id.to_lowercase();
+            touch(&condition_id, entry.timestamp);
+            names.insert(condition_id.clone(), entry.market.clone());
+            totals.entry((condition_id, entry.side.clone(), side.to_string())).or_default().1 += entry.size;
+        }
+
+        const SETTLE_SECS: i64 = 300;
+        let settled = |condition_id: &str| {
+            activity.get(condition_id).is_some_and(|(first, last)| *first >= since + SETTLE_SECS && *last <= now - SETTLE_SECS)
+        };
+        let mut mismatches = 0;
+        let mut reported = self.reported_fill_mismatches.lock().unwrap();
+        for ((condition_id, outcome, side), (exchange, recorded)) in &totals {
+            if (exchange - recorded).abs() < 0.01 || !settled(condition_id) {
+                continue;
+            }
+            mismatches += 1;
+            if !reported.insert((condition_id.clone(), outcome.clone(), side.clone())) {
+                continue;
+            }
+            let market = names.get(condition_id).map(String::as_str).unwrap_or("untracked market");
+            log::error!("🚨 Fill mismatch{}: {} {} {} {} — exchange {:.2} shares, ledger {:.2}",
+                self.account.as_ref().map(|a| format!(" [{}]", a)).unwrap_or_default(),
+                market, &condition_id[..condition_id.len().min(16)], side, outcome, exchange, recorded);
+            tracing::warn!(
+                target: EVENTS,
+                event = "fill_mismatch",
+                condition_id = %condition_id,
+                outcome = %outcome,
+                side = %side,
+                exchange_shares = exchange,
+                ledger_shares = recorded,
+            );
+        }
+        metrics().set_fill_mismatches(self.account.as_deref(), mismatches);
+        if mismatches == 0 {
+            log::debug!("Fill reconciliation: {} fills match the ledger", fills.len());
+        }
+    }
+
+    /// Adds realized PnL to the running total, the profit windows and the daily and drawdown risk counters.
+    /// Returns the new total.
+    async fn record_realized_pnl(&self, pnl: f64) -> f64 {
+        self.risk.record_realized(pnl).await;
+        let drawdown = self.risk.drawdown().await;
+        metrics().set_drawdown(self.account.as_deref(), drawdown.current, drawdown.max);
+        let closed = self.profit.lock().unwrap().record(pnl, clock::now());
+        self.publish_rollups(closed);
+        let mut total = self.total_profit.lock().await;
+        *total += pnl;
+        metrics().set_realized_pnl(self.account.as_deref(), *total);
+        *total
+    }
+
+    /// Closes profit windows whose boundary has passed, even when nothing traded.
+    fn roll_profit_windows(&self) {
+        let closed = self.profit.lock().unwrap().roll(clock::now());
+        self.publish_rollups(closed);
+    }
+
+    /// Logs and records closed windows listed in `profit.rollups`. Quiet periods and hours are
+    /// skipped; every day gets a line.
+    fn publish_rollups(&self, closed: Vec<Rollup>) {
+        let tz = self.profit.lock().unwrap().timezone();
+        for r in closed {
+            self.write_report(&r, tz);
+            if r.window == ProfitWindow::Hourly {
+                self.stats.lock().unwrap().print(self.account.as_deref());
+            }
+            if !self.config.profit.rollups.contains(&r.window) || (r.trades == 0 && r.window != ProfitWindow::Daily) {
+                continue;
+            }
+            let start = tz.timestamp_opt(r.start, 0).unwrap();
+            let end = tz.timestamp_opt(r.end, 0).unwrap();
+            log::info!("📆 {} PnL {} – {}: ${:.2} over {} realized trade(s)",
+                r.window.label(), start.format("%Y-%m-%d %H:%M"), end.format("%H:%M %Z"), r.pnl, r.trades);
+            tracing::info!(
+                target: EVENTS,
+                event = "profit_rollup",
+                window = r.window.label(),
+                start = r.start,
+                end = r.end,
+                pnl = r.pnl,
+                trades = r.trades,
+            );
+            if let Some(ledger) = &self.ledger {
+                ledger.record(&LedgerEntry {
+                    timestamp: r.end,
+                    kind: "rollup".to_string(),
+                    market: r.window.label().to_string(),
+                    condition_id: String::new(),
+                    period_timestamp: r.start,
+                    side: String::new(),
+                    price: 0.0,
+                    size: r.trades as f64,
+                    fees: 0.0,
+                    cost_per_pair: 0.0,
+                    pnl: Some(r.pnl),
+                    account: self.account.clone(),
+                    ..Default::default()
+                });
+            }
+        }
     }
 
-    async fn get_market_snapshot(&self, asset: &str, period_start: i64) -> Option<(f64, f64, i64)> {
-        let slug = MarketDiscovery::build_15m_slug(asset, period_start);
-        let market = self.api.get_market_by_slug(&slug).await.ok()?;
-        if !market.active || market.closed {
-            return None;
+    /// Writes the `report` for a closed window from the ledger. Periods with no ledger rows are
+    /// skipped; every day gets a report.
+    fn write_report(&self, r: &Rollup, tz: chrono_tz::Tz) {
+        let config = &self.config.report;
+        let Some(ledger) = &self.ledger else { return };
+        if !config.enabled || !config.windows.contains(&r.window) {
+            return;
+        }
+        ledger.flush();
+        let entries = match ledger::read_entries(&self.config.ledger.path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!("Failed to read ledger for the {} report: {}", r.window.label(), e);
+                return;
+            }
+        };
+        let report = Report::build(&entries, *r, tz, self.account.clone());
+        if report.trades.is_empty() && r.window != ProfitWindow::Daily {
+            return;
+        }
+        match report.write(&config.dir, config.format) {
+            Ok(path) => log::info!("📝 {} report written to {}", r.window.label(), path.display()),
+            Err(e) => warn!("Failed to write {} report: {}", r.window.label(), e),
         }
-        let (up_token_id, down_token_id) = self.discovery.get_market_tokens(&market.condition_id).await.ok()?;
-        let (up_res, down_res) = tokio::join!(
-            self.api.get_price(&up_token_id, "SELL"),
-            self.api.get_price(&down_token_id, "SELL")
-        );
-        let up_price = up_res.ok()?.to_string().parse::<f64>().ok()?;
-        let down_price = down_res.ok()?.to_string().parse::<f64>().ok()?;
-        let current_time_et = Self::get_current_time_et();
-        let market_end = period_start + MARKET_DURATION_SECS;
-        let time_remaining = market_end - current_time_et;
-        Some((up_price, down_price, time_remaining.max(0)))
     }
 
-    async fn get_place_signal(&self, asset: &str, period_start: i64) -> MarketSignal {
-        let Some((up_price, down_price, time_remaining)) = self.get_market_snapshot(asset, period_start).await else {
-            return MarketSignal::Unknown;
+    /// Trips the kill switch when the daily loss limit or max drawdown is exceeded, and resumes
+    /// once `--resume` clears it.
+    async fn enforce_risk_limits(&self) {
+        if self.risk.daily_loss_limit().is_none() && self.risk.max_drawdown_limit().is_none() {
+            return;
+        }
+        if self.risk.is_halted().await {
+            // Retry cancels that failed when the switch tripped
+            self.cancel_resting_orders().await;
+            self.risk.poll_resume().await;
+            return;
+        }
+        let daily_loss_hit = if self.risk.daily_loss_limit().is_some() {
+            let unrealized = self.unrealized_pnl().await;
+            metrics().set_unrealized_pnl(self.account.as_deref(), unrealized);
+            self.risk.check_daily_loss(unrealized).await
+        } else {
+            false
         };
-        signals::evaluate_place_signal(
-            &self.config.strategy.signal,
-            up_price,
-            down_price,
-            time_remaining,
-        )
+        if daily_loss_hit || self.risk.check_drawdown().await {
+            self.cancel_resting_orders().await;
+        }
     }
 
-    async fn discover_next_market(&self, asset_name: &str, next_timestamp: i64) -> Result<Option<Market>> {
-        let slug = MarketDiscovery::build_15m_slug(asset_name, next_timestamp);
-        match self.api.get_market_by_slug(&slug).await {
-            Ok(m) => {
-                if m.active && !m.closed {
-                    Ok(Some(m))
-                } else {
-                    Ok(None)
+    /// Mark-to-market PnL of filled sides we still hold, at current sell prices.
+    async fn unrealized_pnl(&self) -> f64 {
+        let state = self.get_state().await;
+        let mut unrealized = 0.0;
+        for p in state.positions.iter().filter(|p| !p.closed && (p.up_filled || p.down_filled)) {
+            unrealized += self.position_unrealized_pnl(p).await;
+        }
+        unrealized
+    }
+
+    async fn position_unrealized_pnl(&self, p: &PositionView) -> f64 {
+        let mut unrealized = 0.0;
+        let shares = p.shares;
+        let sides = [
+            (p.up_filled, &p.up_token_id, p.up_price),
+            (p.down_filled, &p.down_token_id, p.down_price),
+        ];
+        for (matched, token_id, purchase_price) in sides {
+            if !matched {
+                continue;
+            }
+            if let Some(price) = self.feed.price(token_id).await.ok()
+                .and_then(|p| p.to_string().parse::<f64>().ok())
+            {
+                unrealized += (price - purchase_price) * shares
+                    - self.fees.taker_fee(price, shares)
+                    - self.fees.maker_fee(purchase_price, shares);
+            }
+        }
+        unrealized
+    }
+
+    /// Redeems the queued positions in one batch once their payouts reach `min_redeem_usd` or
+    /// the oldest has waited `max_redeem_age_mins`; `force` skips both checks. Failed
+    /// redemptions stay queued for the next attempt.
+    async fn process_redeem_queue(&self, force: bool) {
+        let now = clock::now();
+        let batch = {
+            let mut queue = self.redeem_queue.lock().await;
+            if queue.is_empty() {
+                return;
+            }
+            let cfg = &self.config.strategy.redeem;
+            let total: f64 = queue.iter().map(|r| r.value).sum();
+            let oldest = queue.iter().map(|r| r.resolved_at).min().unwrap_or(now);
+            let due = force || total >= cfg.min_redeem_usd || now - oldest >= (cfg.max_redeem_age_mins * 60) as i64;
+            if !due {
+                log::debug!("{} redemption(s) worth ${:.2} queued (threshold ${:.2})", queue.len(), total, cfg.min_redeem_usd);
+                return;
+            }
+            log::info!("💰 Redeeming {} resolved position(s) worth ${:.2}", queue.len(), total);
+            std::mem::take(&mut *queue)
+        };
+
+        let mut failed = Vec::new();
+        for r in batch {
+            match self.api.redeem_tokens(&r.condition_id, &r.token_id, &r.outcome).await {
+                Ok(_) => log::info!("   ✅ Redeemed {} {} (${:.2})", &r.condition_id[..r.condition_id.len().min(16)], r.outcome, r.value),
+                Err(e) => {
+                    warn!("Redeem failed for {}: {} — keeping it queued", &r.condition_id[..r.condition_id.len().min(16)], e);
+                    failed.push(r);
                 }
             }
-            Err(e) => {
-                log::debug!("Failed to find market with slug {}: {}", slug, e);
-                Ok(None)
+        }
+        self.redeem_queue.lock().await.extend(failed);
+    }
+
+    /// Manual redeem from the control API: settles resolved positions and redeems the whole
+    /// queue now, or redeems `condition_id` directly like `--redeem --condition-id`.
+    pub async fn redeem_now(&self, condition_id: Option<&str>) -> Result<()> {
+        let Some(cid) = condition_id else {
+            let _running = self.closure_running.lock().await;
+            self.resolve_closed_markets().await?;
+            self.process_redeem_queue(true).await;
+            return Ok(());
+        };
+        if self.simulated() {
+            anyhow::bail!("Redeeming a condition is not available in simulation mode");
+        }
+        let cid = if cid.starts_with("0x") { cid.to_string() } else { format!("0x{}", cid) };
+        log::warn!("Redeeming condition {} through the control API", cid);
+        self.api.redeem_tokens(&cid, "", "Up").await.map(|_| ())
+    }
+
+    /// Resolution PnL of the filled sides if `winner` wins, after maker fees.
+    fn pnl_if_wins(&self, p: &PositionView, winner: &str) -> f64 {
+        let mut pnl = 0.0;
+        for (outcome, filled, price) in [("Up", p.up_filled, p.up_price), ("Down", p.down_filled, p.down_price)] {
+            if filled {
+                let payout = if outcome == winner { p.shares } else { 0.0 };
+                pnl += payout - price * p.shares - self.fees.maker_fee(price, p.shares);
             }
         }
+        pnl
     }
 
-    pub async fn check_market_closure(&self) -> Result<()> {
-        let trades: Vec<(String, CycleTrade)> = {
-            let t = self.trades.lock().await;
-            t.iter()
-                .map(|(k, v)| (k.clone(), v.clone()))
+    /// Snapshot of every market's orders, position and trend, read under one lock of each.
+    pub async fn get_state(&self) -> TraderState {
+        let assets = [self.config.traded_assets(), self.config.event_assets()].concat();
+        let (positions, trends) = {
+            let states = self.states.lock().await;
+            let history = self.price_history.lock().await;
+            let trends: BTreeMap<String, Trend> = assets
+                .iter()
+                .map(|a| (a.clone(), history.get(a).map_or(Trend::Flat, |h| h.trend(&self.config.strategy.signal.trend))))
+                .collect();
+            let positions: Vec<PositionView> = assets
+                .iter()
+                .filter_map(|a| states.get(a))
+                .map(|s| PositionView {
+                    asset: s.asset.clone(),
+                    condition_id: s.condition_id.clone(),
+                    period_start: s.market_period_start,
+                    expiry: s.expiry,
+                    up_token_id: s.up_token_id.clone(),
+                    down_token_id: s.down_token_id.clone(),
+                    shares: s.shares,
+                    up_filled: s.up_matched,
+                    down_filled: s.down_matched,
+                    up_price: s.up_order_price,
+                    down_price: s.down_order_price,
+                    cost_per_pair: self.fees.pair_cost(s.up_order_price, s.down_order_price),
+                    sold_side: s.sold_side.clone(),
+                    spike_sold: s.spike_sold.unwrap_or(0.0),
+                    closed: s.merged,
+                    risk_sold: s.risk_sold,
+                    one_side_matched_at: s.one_side_matched_at,
+                    trend: trends[&s.asset],
+                })
+                .collect();
+            (positions, trends)
+        };
+        TraderState {
+            account: self.account.clone(),
+            taken_at: clock::now(),
+            simulated: self.simulated(),
+            trading_halted: self.risk.is_halted().await,
+            realized_pnl: self.get_total_profit().await,
+            daily_realized_pnl: self.risk.daily_realized_pnl().await,
+            positions,
+            trends,
+        }
+    }
+
+    /// Shares held across every open market: positions still in play plus markets registered
+    /// for redemption and waiting on resolution.
+    pub async fn exposure(&self) -> Exposure {
+        let state = self.get_state().await;
+        let trades: Vec<CycleTrade> = self.trades.lock().await.values().cloned().collect();
+        let mut legs = Vec::new();
+        for t in &trades {
+            for l in &t.legs {
+                legs.push(Leg {
+                    asset: t.market.clone(),
+                    condition_id: t.condition_id.clone(),
+                    outcome: l.outcome.clone(),
+                    shares: l.shares,
+                    price: l.avg_price,
+                });
+            }
+        }
+        // A market sold down to its winner is already registered as a trade
+        for p in state.positions.iter().filter(|p| !trades.iter().any(|t| t.condition_id == p.condition_id)) {
+            for (outcome, filled, price) in [("Up", p.up_filled, p.up_price), ("Down", p.down_filled, p.down_price)] {
+                if filled && p.sold_side.as_deref() != Some(outcome) {
+                    let shares = p.shares - p.spike_sold;
+                    legs.push(Leg { asset: p.asset.clone(), condition_id: p.condition_id.clone(), outcome: outcome.to_string(), shares, price });
+                }
+            }
+        }
+        exposure::aggregate(&legs, &self.fees)
+    }
+
+    /// Point-in-time view of every traded market, served to the `status` subcommand.
+    pub async fn status(&self) -> StatusReport {
+        let now = Self::get_current_time_et();
+        let next_period_start = Self::get_current_15m_period_et() + MARKET_DURATION_SECS;
+        let orders_open_at = next_period_start - (self.config.strategy.place_order_before_mins * 60) as i64;
+        let time_mode = signals::one_side_risk_mode(&self.config.strategy.signal) == OneSideRiskMode::Time;
+        let danger_secs = (self.config.strategy.signal.danger_time_passed * 60) as i64;
+        let state = self.get_state().await;
+        let last_errors = self.last_errors.lock().unwrap().clone();
+
+        let mut markets = Vec::new();
+        let mut unrealized_total = 0.0;
+        for asset in [self.config.traded_assets(), self.config.event_assets()].concat() {
+            let p = state.positions.iter().find(|p| p.asset == asset);
+            let unrealized = match p {
+                Some(p) if !p.closed => self.position_unrealized_pnl(p).await,
+                _ => 0.0,
+            };
+            unrealized_total += unrealized;
+            let danger_exit_in_secs = p
+                .filter(|p| time_mode && !p.closed && !p.risk_sold && p.up_filled != p.down_filled)
+                .and_then(|p| p.one_side_matched_at)
+                .map(|t| (t + danger_secs - now).max(0));
+            markets.push(MarketStatus {
+                paused: self.is_paused(&asset),
+                signal: self.signals.lock().unwrap().get(&asset, now).cloned(),
+                position: p.map(|p| PositionStatus {
+                    condition_id: p.condition_id.clone(),
+                    period_start: p.period_start,
+                    shares: p.shares,
+                    up_filled: p.up_filled,
+                    down_filled: p.down_filled,
+                    up_price: p.up_price,
+                    down_price: p.down_price,
+                    cost_per_pair: p.cost_per_pair,
+                    sold_side: p.sold_side.clone(),
+                    closed: p.closed,
+                    pnl_if_up_wins: (!p.closed).then(|| self.pnl_if_wins(p, "Up")),
+                    pnl_if_down_wins: (!p.closed).then(|| self.pnl_if_wins(p, "Down")),
+                }),
+                unrealized_pnl: unrealized,
+                danger_exit_in_secs,
+                next_orders_in_secs: (orders_open_at - now).max(0),
+                last_error: last_errors.get(&asset).cloned(),
+                account: self.account.clone(),
+                asset,
+            });
+        }
+        metrics().set_unrealized_pnl(self.account.as_deref(), unrealized_total);
+
+        let queue = self.redeem_queue.lock().await.clone();
+        StatusReport {
+            generated_at: Utc::now().timestamp(),
+            simulation_mode: state.simulated,
+            trading_halted: state.trading_halted,
+            realized_pnl: state.realized_pnl,
+            daily_realized_pnl: state.daily_realized_pnl,
+            unrealized_pnl: unrealized_total,
+            pending_redemptions: self.trades.lock().await.len(),
+            redeem_queue: queue.len(),
+            redeem_queue_usd: queue.iter().fold(0.0, |total, r| total + r.value),
+            usdc_balance: *self.usdc_balance.lock().unwrap(),
+            accounts: Vec::new(),
+            markets,
+        }
+    }
+
+    /// Kill switch: cancel every unfilled order. Markets with no fills are dropped; one-sided
+    /// positions stay under the normal danger logic so the filled side can still be sold. An
+    /// order whose cancel fails stays tracked, and checked for fills, until a retry succeeds.
+    async fn cancel_resting_orders(&self) {
+        // Cancel outside the lock so the network calls don't hold up the rest of the strategy
+        let resting: Vec<(String, &str, String)> = {
+            let states = self.states.lock().await;
+            states
+                .iter()
+                .filter(|(_, s)| !s.merged)
+                .flat_map(|(asset, s)| {
+                    [("Up", s.up_matched, &s.up_order_id), ("Down", s.down_matched, &s.down_order_id)]
+                        .into_iter()
+                        .filter(|(_, matched, _)| !matched)
+                        .filter_map(|(side, _, id)| id.clone().map(|id| (asset.clone(), side, id)))
+                })
                 .collect()
         };
-        if trades.is_empty() {
-            return Ok(());
+        if resting.is_empty() {
+            return;
         }
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
 
-        for (market_key, trade) in trades {
-            let market_end = trade.period_timestamp + trade.market_duration_secs;
-            if current_time < market_end {
+        let mut cancelled = Vec::new();
+        for (asset, side, order_id) in resting {
+            if self.simulated() || order_id.starts_with("SIM-") {
+                log::warn!("🎮 SIMULATION: Would cancel {} order {} for {}", side, order_id, asset);
+            } else if let Err(e) = self.api.cancel_order(&order_id).await {
+                log::error!("Failed to cancel {} order for {}: {} — still tracking it, retrying next tick", side, asset, e);
                 continue;
+            } else {
+                log::warn!("✅ Canceled {} order {} for {}", side, order_id, asset);
             }
+            cancelled.push((asset, side, order_id));
+        }
 
-            let checked = self.closure_checked.lock().await;
-            if checked.get(&trade.condition_id).copied().unwrap_or(false) {
-                drop(checked);
+        let mut states = self.states.lock().await;
+        for (asset, side, order_id) in cancelled {
+            let Some(s) = states.get_mut(&asset) else { continue };
+            let id = if side == "Up" { &mut s.up_order_id } else { &mut s.down_order_id };
+            if id.as_deref() == Some(order_id.as_str()) {
+                *id = None;
+            }
+            let still_resting = (!s.up_matched && s.up_order_id.is_some()) || (!s.down_matched && s.down_order_id.is_some());
+            if still_resting {
                 continue;
             }
-            drop(checked);
+            s.resting_cancelled = true;
+            if !s.up_matched && !s.down_matched {
+                states.remove(&asset);
+            }
+        }
+    }
+
+    /// Counts a newly detected fill and emits an `order_filled` event.
+    fn record_fill(&self, state: &PreLimitOrderState, outcome: &str, source: &str) {
+        metrics().inc_orders_filled();
+        let price = if outcome == "Up" { state.up_order_price } else { state.down_order_price };
+        let fees = self.fees.maker_fee(price, state.shares);
+        let rule = state.decision.map_or(Rationale::PreOrder, |d| d.rule);
+        self.record_ledger(state, "buy", rule, outcome, price, fees, None);
+        self.adjust_sim_balance(-(price * state.shares + fees));
+        tracing::info!(
+            target: EVENTS,
+            event = "order_filled",
+            market = %state.asset,
+            condition_id = %state.condition_id,
+            period_timestamp = state.market_period_start,
+            outcome,
+            price,
+            source,
+        );
+    }
+
+    /// Pays `usd` into (negative: out of) the simulated balance, when one is tracked.
+    fn adjust_sim_balance(&self, usd: f64) {
+        let mut balance = self.sim_balance.lock().unwrap();
+        if let Some(b) = balance.as_mut() {
+            *b += usd;
+            *self.usdc_balance.lock().unwrap() = Some(*b);
+            metrics().set_usdc_balance(self.account.as_deref(), *b);
+        }
+    }
+
+    /// `Some` when `strategy.signal.momentum` is on and the spot candle of `s`'s underlying
+    /// hasn't moved far enough toward Up (`up_wins`) or Down to back committing to it, with the
+    /// candle's progress if it could be read. Only Up/Down markets resolve on that candle.
+    async fn unconfirmed_momentum(&self, s: &PreLimitOrderState, up_wins: bool) -> Option<Option<f64>> {
+        let spot = self.spot.as_ref().filter(|_| self.config.strategy.signal.momentum.enabled)?;
+        let market = self.config.markets.iter().find(|m| m.asset == s.asset && m.family == MarketFamily::UpDown)?;
+        let progress = spot.progress(&market.spot_symbol(), s.market_period_start).await;
+        let min = self.config.strategy.signal.momentum.min_range_fraction;
+        (!momentum::confirms(min, progress, up_wins)).then_some(progress)
+    }
+
+    /// Sells `spike_sell.fraction` of the winner held after sell-opposite once it trades at or
+    /// above `spike_sell.sell_threshold` in the final `final_secs` of the period, realizing that
+    /// part now rather than at resolution and redemption. One attempt per position; a miss or a
+    /// failed order leaves the whole position held.
+    async fn spike_sell(&self, s: &mut PreLimitOrderState) {
+        let cfg = &self.config.strategy.spike_sell;
+        let (outcome, token_id, purchase_price) = match s.sold_side.as_deref() {
+            Some("Down") if s.up_matched => ("Up", s.up_token_id.clone(), s.up_order_price),
+            Some("Up") if s.down_matched => ("Down", s.down_token_id.clone(), s.down_order_price),
+            _ => return,
+        };
+        let time_remaining = s.expiry - Self::get_current_time_et();
+        if time_remaining <= 0 || time_remaining > cfg.final_secs as i64 {
+            return;
+        }
+        let Some(price) = self.feed.price(&token_id).await.ok().and_then(|p| p.to_string().parse::<f64>().ok()) else {
+            return;
+        };
+        if price < cfg.sell_threshold {
+            return;
+        }
+        let shares = (s.shares * cfg.fraction * 100.0).floor() / 100.0;
+        if shares <= 0.0 {
+            return;
+        }
+        log::info!("🎯 {}: {} at ${:.2} >= {:.2} with {}s left — selling {:.2} of {:.2} shares into the spike",
+            s.asset, outcome, price, cfg.sell_threshold, time_remaining, shares, s.shares);
 
-            let market = match self.api.get_market(&trade.condition_id).await {
-                Ok(m) => m,
+        let (sold, sell_price, sell_fees) = if self.simulated() {
+            let (bids, quoted) = self.sim_sell_book(&s.asset, &token_id, price).await;
+            let fill = self.fills.lock().unwrap().market_sell(&bids, quoted, shares, price - self.config.strategy.slippage_tolerance);
+            match fill {
+                Some(fill) => {
+                    self.adjust_sim_balance(fill.proceeds());
+                    (fill.shares, fill.avg_price, fill.fees)
+                }
+                None => {
+                    log::warn!("🎮 SIMULATION: {} spike sell of {:.2} {} shares got no fill — holding to resolution", s.asset, shares, outcome);
+                    (0.0, price, 0.0)
+                }
+            }
+        } else {
+            let sell = self.api.place_market_order(&token_id, shares, "SELL", Some("FAK"), self.sell_floor(price));
+            match self.api.queued(OrderPriority::lock(s.expiry), sell).await {
+                Ok(response) => {
+                    let (sold, avg_price) = response.matched(price);
+                    let sold = sold.min(shares);
+                    (sold, avg_price, self.fees.taker_fee(avg_price, sold))
+                }
                 Err(e) => {
-                    warn!("Failed to fetch market {}: {}", &trade.condition_id[..16], e);
-                    continue;
+                    log::error!("Failed to spike-sell {} token for {}: {} — holding to resolution", outcome, s.asset, e);
+                    (0.0, price, 0.0)
                 }
-            };
-            if !market.closed {
-                continue;
             }
+        };
+        // Don't retry: the spike is a one-off and the rest of the position resolves at $1 anyway
+        s.spike_sold = Some(sold);
+        if sold <= 0.0 {
+            return;
+        }
 
-            let up_wins = trade
-                .up_token_id
-                .as_ref()
-                .map(|id| market.tokens.iter().any(|t| t.token_id == *id && t.winner))
-                .unwrap_or(false);
-            let down_wins = trade
-                .down_token_id
-                .as_ref()
-                .map(|id| market.tokens.iter().any(|t| t.token_id == *id && t.winner))
-                .unwrap_or(false);
-
-            let total_cost = (trade.up_shares * trade.up_avg_price) + (trade.down_shares * trade.down_avg_price);
-            let payout = if up_wins {
-                trade.up_shares * 1.0
-            } else if down_wins {
-                trade.down_shares * 1.0
-            } else {
-                0.0
+        let pnl = self.sell_pnl(sold, purchase_price, sell_price, sell_fees);
+        let current_total = self.record_realized_pnl(pnl).await;
+        self.stats.lock().unwrap().realize(&s.condition_id, pnl);
+        log::info!("   Sold {} {} at ${:.4}; holding {} to resolution. PnL: {} | Total Profit: {}",
+            display::size(sold, sell_price), outcome, sell_price, display::shares(s.shares - sold),
+            display::pnl(pnl, sold), display::usd(current_total));
+        self.record_ledger_sized(s, "sell", Rationale::SpikeSell, outcome, sell_price, sold, sell_fees, Some(pnl));
+        tracing::info!(
+            target: EVENTS,
+            event = "position_sold",
+            market = %s.asset,
+            condition_id = %s.condition_id,
+            period_timestamp = s.market_period_start,
+            outcome,
+            reason = "spike",
+            price = sell_price,
+        );
+        if let Some(trade) = self.trades.lock().await.get_mut(&s.condition_id) {
+            if let Some(leg) = trade.leg_mut(outcome) {
+                leg.shares = (leg.shares - sold).max(0.0);
+            }
+        }
+    }
+
+    /// Market-sells what is left of `outcome`, a fill-and-kill order in production or the fill
+    /// model in simulation, at no less than `slippage_tolerance` below `quoted`. Each fill is
+    /// realized as it lands, with a `sell` ledger row for the shares it took. Returns the whole
+    /// sale once the side is closed out: fully sold, or after a partial fill once follow-ups are
+    /// off or out of time, with the rest held to resolution. None while nothing has sold or the
+    /// remainder waits for a follow-up.
+    async fn sell_side(&self, s: &mut PreLimitOrderState, outcome: &str, quoted: f64, rationale: Rationale) -> Option<SideSale> {
+        let (token_id, purchase_price) = if outcome == "Up" {
+            (s.up_token_id.clone(), s.up_order_price)
+        } else {
+            (s.down_token_id.clone(), s.down_order_price)
+        };
+        let progress = s.partial_sell.clone().filter(|p| p.outcome == outcome);
+        let floor = progress.as_ref().map_or(quoted - self.config.strategy.slippage_tolerance, |p| p.floor);
+        let remaining = progress.as_ref().map_or(s.shares, |p| p.remaining);
+        let (shares, avg_price, fees) = if self.simulated() {
+            let (bids, quoted) = self.sim_sell_book(&s.asset, &token_id, quoted).await;
+            let fill = self.fills.lock().unwrap().market_sell(&bids, quoted, remaining, floor);
+            let Some(fill) = fill else {
+                log::warn!("🎮 SIMULATION: {} sell of {:.2} {} shares got no fill — retrying next tick", s.asset, remaining, outcome);
+                return None;
             };
-            let pnl = payout - total_cost;
+            self.adjust_sim_balance(fill.proceeds());
+            (fill.shares, fill.avg_price, fill.fees)
+        } else {
+            let floor = (quoted > 0.0).then_some(floor);
+            let sell = self.api.place_market_order(&token_id, remaining, "SELL", Some("FAK"), floor);
+            match self.api.queued(OrderPriority::lock(s.expiry), sell).await {
+                Ok(response) => {
+                    let (shares, avg_price) = response.matched(quoted);
+                    let shares = shares.min(remaining);
+                    (shares, avg_price, self.fees.taker_fee(avg_price, shares))
+                }
+                Err(e) => {
+                    log::error!("Failed to sell {} token for {}: {} — retrying next tick", outcome, s.asset, e);
+                    return None;
+                }
+            }
+        };
+        if shares <= 0.0 {
+            log::warn!("{}: {} sell of {:.2} shares matched nothing at or above ${:.4} — retrying next tick", s.asset, outcome, remaining, floor);
+            return None;
+        }
 
-            let winner = if up_wins { "Up" } else if down_wins { "Down" } else { "Unknown" };
-            eprintln!("=== Market resolved ===");
-            eprintln!(
-                "Market closed | condition {} | Winner: {} | Up {:.2} @ {:.4} | Down {:.2} @ {:.4} | Cost ${:.2} | Payout ${:.2} | Actual PnL ${:.2}",
-                &trade.condition_id[..16],
-                winner,
-                trade.up_shares,
-                trade.up_avg_price,
-                trade.down_shares,
-                trade.down_avg_price,
-                total_cost,
-                payout,
-                pnl
-            );
+        let pnl = self.sell_pnl(shares, purchase_price, avg_price, fees);
+        let current_total = self.record_realized_pnl(pnl).await;
+        self.stats.lock().unwrap().realize(&s.condition_id, pnl);
+        log::warn!("   💸 Sold {}/{} {} shares at avg ${:.4} (fees ${:.4}, purchased at {}) | PnL: {} | Total Profit: {}",
+            display::shares(shares), display::shares(remaining), outcome, avg_price, fees, display::price(purchase_price),
+            display::pnl(pnl, shares), display::usd(current_total));
+        self.record_ledger_sized(s, "sell", rationale, outcome, avg_price, shares, fees, Some(pnl));
 
-            if !self.config.strategy.simulation_mode && (up_wins || down_wins) {
-                let (token_id, outcome) = if up_wins && trade.up_shares > 0.001 {
-                    (trade.up_token_id.as_deref().unwrap_or(""), "Up")
-                } else {
-                    (trade.down_token_id.as_deref().unwrap_or(""), "Down")
-                };
-                let _units = if up_wins { trade.up_shares } else { trade.down_shares };
-                if let Err(e) = self
-                    .api
-                    .redeem_tokens(&trade.condition_id, token_id, outcome)
-                    .await
-                {
-                    warn!("Redeem failed: {}", e);
+        let (notional, fees_so_far) = progress.as_ref().map_or((0.0, 0.0), |p| (p.notional, p.fees));
+        let partial = PartialSell {
+            outcome: outcome.to_string(),
+            rationale,
+            remaining: remaining - shares,
+            notional: notional + shares * avg_price,
+            fees: fees_so_far + fees,
+            first_fill_at: progress.as_ref().map_or_else(Self::get_current_time_et, |p| p.first_fill_at),
+            floor,
+        };
+        if partial.remaining <= 1e-9 {
+            s.partial_sell = None;
+            return Some(SideSale { shares: s.shares, avg_price: partial.notional / s.shares, fees: partial.fees });
+        }
+        log::warn!("🧩 {}: {} sell filled {:.2} of {:.2} shares, {:.2} left", s.asset, outcome, shares, remaining, partial.remaining);
+        tracing::warn!(
+            target: EVENTS,
+            event = "partial_fill",
+            market = %s.asset,
+            condition_id = %s.condition_id,
+            period_timestamp = s.market_period_start,
+            outcome,
+            reason = rationale.label(),
+            filled = shares,
+            remaining = partial.remaining,
+        );
+        if !self.config.strategy.partial_fill.retry {
+            return Some(self.close_partial_sell(s, &partial, "follow-ups are off"));
+        }
+        s.partial_sell = Some(partial);
+        None
+    }
+
+    /// Re-offers the remainder of a partially filled sell while `partial_fill.window_secs` lasts
+    /// and the quote is still at or above the first attempt's floor; closes the sale out once
+    /// the window has passed.
+    async fn follow_up_sell(&self, s: &mut PreLimitOrderState, partial: &PartialSell) {
+        let sale = if Self::get_current_time_et() - partial.first_fill_at > self.config.strategy.partial_fill.window_secs as i64 {
+            Some(self.close_partial_sell(s, partial, "follow-up window passed"))
+        } else {
+            let token_id = if partial.outcome == "Up" { &s.up_token_id } else { &s.down_token_id };
+            match self.feed.price(token_id).await.ok().and_then(|p| p.to_string().parse::<f64>().ok()) {
+                Some(quoted) if quoted >= partial.floor => {
+                    log::info!("🧩 {}: retrying {:.2} {} shares at ${:.4} (floor ${:.4})", s.asset, partial.remaining, partial.outcome, quoted, partial.floor);
+                    self.sell_side(s, &partial.outcome, quoted, partial.rationale).await
+                }
+                quoted => {
+                    log::debug!("{}: {} quote {:?} below the ${:.4} floor — not retrying the remainder yet", s.asset, partial.outcome, quoted, partial.floor);
+                    None
                 }
             }
+        };
+        if let Some(sale) = sale {
+            self.finish_sale(s, &partial.outcome, partial.rationale, sale).await;
+        }
+    }
 
-            {
-                let mut total = self.total_profit.lock().await;
-                *total += pnl;
+    /// Bids and quote a simulated sell of `token_id` decided at `quoted` fills against. With
+    /// `fill_model.latency_ms_simulated` both are read that much later (in a replay, from the
+    /// recording at that time), as a live order reaches a book that has moved on.
+    async fn sim_sell_book(&self, asset: &str, token_id: &str, quoted: f64) -> (Vec<PriceLevel>, f64) {
+        let cfg = &self.config.strategy.fill_model;
+        let latency_ms = cfg.latency_ms_simulated;
+        let parse = |price: rust_decimal::Decimal| price.to_string().parse::<f64>().ok();
+        let (bids, fill_quote) = match self.api.replay() {
+            Some(replay) if latency_ms > 0 => {
+                let at_ms = clock::now_ms() + latency_ms as i64;
+                let book = replay.books_at(&[token_id.to_string()], at_ms).remove(token_id);
+                let bids = book.filter(|_| cfg.enabled).map(|book| book_levels(&book).0).unwrap_or_default();
+                (bids, replay.price_at(token_id, "SELL", at_ms).ok().and_then(parse).unwrap_or(quoted))
             }
-            {
-                let mut period = self.period_profit.lock().await;
-                *period += pnl;
+            _ => {
+                let quote = if latency_ms > 0 {
+                    sleep(Duration::from_millis(latency_ms)).await;
+                    self.feed.price(token_id).await.ok().and_then(parse).unwrap_or(quoted)
+                } else {
+                    quoted
+                };
+                let bids = if !cfg.enabled {
+                    Vec::new()
+                } else {
+                    match self.feed.book(token_id).await {
+                        Ok(book) => book_levels(&book).0,
+                        Err(e) => {
+                            log::debug!("{}: order book unavailable for simulated sell ({}), using quote", asset, e);
+                            Vec::new()
+                        }
+                    }
+                };
+                (bids, quote)
             }
-            let total_actual_pnl = *self.total_profit.lock().await;
-            eprintln!(
-                "  -> Actual PnL this market: ${:.2} | Total actual PnL (all time): ${:.2}",
-                pnl,
-                total_actual_pnl
-            );
-            {
-                let mut c = self.closure_checked.lock().await;
-                c.insert(trade.condition_id.clone(), true);
+        };
+        if (fill_quote - quoted).abs() > 1e-9 {
+            log::debug!("🎮 SIMULATION: {} quote moved from ${:.4} to ${:.4} in the {}ms before the sell reached the book",
+                asset, quoted, fill_quote, latency_ms);
+        }
+        (bids, fill_quote)
+    }
+
+    /// Cancels the unfilled `other` side's order after a danger exit.
+    async fn cancel_other_order(&self, s: &PreLimitOrderState, other: &str) {
+        let order_id = if other == "Up" { &s.up_order_id } else { &s.down_order_id };
+        if let Some(order_id) = order_id {
+            if self.simulated() {
+                log::warn!("🎮 SIMULATION: Would cancel {} order {}", other, order_id);
+            } else if let Err(e) = self.api.cancel_order(order_id).await {
+                log::error!("Failed to cancel {} order for {}: {}", other, s.asset, e);
+            } else {
+                log::info!("✅ Canceled {} order {} for {}", other, order_id, s.asset);
             }
-            let mut t = self.trades.lock().await;
-            t.remove(&market_key);
         }
-        Ok(())
+    }
+
+    /// Stops selling a partially filled side; what is left is held to resolution.
+    fn close_partial_sell(&self, s: &mut PreLimitOrderState, partial: &PartialSell, why: &str) -> SideSale {
+        s.partial_sell = None;
+        let sold = s.shares - partial.remaining;
+        log::warn!("🧩 {}: {} — holding the last {:.2} {} shares to resolution", s.asset, why, partial.remaining, partial.outcome);
+        SideSale { shares: sold, avg_price: partial.notional / sold, fees: partial.fees }
+    }
+
+    /// Books a side closed out by `sell_side`: marks the position sold and registers whatever
+    /// is still held for resolution.
+    async fn finish_sale(&self, s: &mut PreLimitOrderState, outcome: &str, rationale: Rationale, sale: SideSale) {
+        s.merged = true;
+        s.risk_sold |= rationale != Rationale::SellOpposite;
+        s.sold_side = Some(outcome.to_string());
+        s.unsold = s.shares - sale.shares;
+        self.record_sold(s, outcome, rationale, &sale);
+        // Production, or a simulated bankroll to credit: check_market_closure settles what is held
+        let trade = Self::held_trade(s);
+        if self.settles_resolutions() && rationale != Rationale::Shutdown && trade.total_shares() > 0.0 {
+            self.trades.lock().await.insert(s.condition_id.clone(), trade);
+            log::info!("   Registered position for redemption when market resolves (condition {})", &s.condition_id[..s.condition_id.len().min(20)]);
+        }
+        self.publish_position(&s.asset, Some(s));
+    }
+
+    /// Lowest price a market sell decided at `quoted` may fill at (`strategy.slippage_tolerance`);
+    /// None when there was no quote to decide on.
+    fn sell_floor(&self, quoted: f64) -> Option<f64> {
+        (quoted > 0.0).then_some(quoted - self.config.strategy.slippage_tolerance)
+    }
+
+    /// PnL of selling the full position: proceeds less taker fees, against the purchase cost
+    /// including its maker fee.
+    fn sell_pnl(&self, shares: f64, purchase_price: f64, sell_price: f64, sell_fees: f64) -> f64 {
+        (sell_price - purchase_price) * shares - sell_fees - self.fees.maker_fee(purchase_price, shares)
+    }
+
+    /// Emits `position_sold` for a closed-out side; its ledger rows were written per fill.
+    fn record_sold(&self, state: &PreLimitOrderState, outcome: &str, reason: Rationale, sale: &SideSale) {
+        tracing::info!(
+            target: EVENTS,
+            event = "position_sold",
+            market = %state.asset,
+            condition_id = %state.condition_id,
+            period_timestamp = state.market_period_start,
+            outcome,
+            reason = reason.label(),
+            price = sale.avg_price,
+            shares = sale.shares,
+            fees = sale.fees,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record_ledger(&self, state: &PreLimitOrderState, kind: &str, rationale: Rationale, side: &str, price: f64, fees: f64, pnl: Option<f64>) {
+        self.record_ledger_sized(state, kind, rationale, side, price, state.shares, fees, pnl);
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record_ledger_sized(&self, state: &PreLimitOrderState, kind: &str, rationale: Rationale, side: &str, price: f64, size: f64, fees: f64, pnl: Option<f64>) {
+        if let Some(ledger) = &self.ledger {
+            ledger.record(&LedgerEntry {
+                timestamp: clock::now(),
+                kind: kind.to_string(),
+                market: state.asset.clone(),
+                condition_id: state.condition_id.clone(),
+                period_timestamp: state.market_period_start,
+                side: side.to_string(),
+                price,
+                size,
+                fees,
+                cost_per_pair: self.fees.pair_cost(state.up_order_price, state.down_order_price),
+                pnl,
+                account: self.account.clone(),
+                ..Default::default()
+            }.tagged(rationale, state.decision.as_ref()));
+        }
+    }
+
+    /// Exports shares held per outcome for `asset` (zero once sold or the market is cleared).
+    fn publish_position(&self, asset: &str, state: Option<&PreLimitOrderState>) {
+        for outcome in ["Up", "Down"] {
+            let shares = state.map_or(0.0, |s| Self::held_shares(s, outcome));
+            metrics().set_position(self.account.as_deref(), asset, outcome, shares);
+        }
     }
 
     fn round_price(price: f64) -> f64 {
@@ -665,62 +2753,97 @@ impl PreLimitStrategy {
         rounded.clamp(0.01, 0.99)
     }
 
-    fn cycle_trade_holding_winner(s: &PreLimitOrderState, winner: &str, shares: f64) -> CycleTrade {
-        let (up_shares, down_shares, up_avg, down_avg) = if winner == "Up" {
-            (shares, 0.0, s.up_order_price, 0.0)
+    /// Shares of `outcome` still held: none if it never filled, what a partial sell left of it,
+    /// or the position less any spike sell.
+    fn held_shares(s: &PreLimitOrderState, outcome: &str) -> f64 {
+        let matched = if outcome == "Up" { s.up_matched } else { s.down_matched };
+        if !matched {
+            0.0
+        } else if s.sold_side.as_deref() == Some(outcome) {
+            s.unsold
+        } else if let Some(partial) = s.partial_sell.as_ref().filter(|p| p.outcome == outcome) {
+            partial.remaining
         } else {
-            (0.0, shares, 0.0, s.down_order_price)
-        };
-        CycleTrade {
-            condition_id: s.condition_id.clone(),
-            period_timestamp: s.market_period_start as u64,
-            market_duration_secs: MARKET_DURATION_SECS_U64,
-            up_token_id: Some(s.up_token_id.clone()),
-            down_token_id: Some(s.down_token_id.clone()),
-            up_shares,
-            down_shares,
-            up_avg_price: up_avg,
-            down_avg_price: down_avg,
+            s.shares - s.spike_sold.unwrap_or(0.0)
         }
     }
 
-    fn cycle_trade_holding_both(s: &PreLimitOrderState, shares: f64) -> CycleTrade {
+    /// Whether `s` was opened by `strategy.plugin`, holding one side only.
+    fn is_plugin(s: &PreLimitOrderState) -> bool {
+        s.decision.is_some_and(|d| d.rule == Rationale::Plugin)
+    }
+
+    /// Resolution entry for whatever is still held of the position.
+    fn held_trade(s: &PreLimitOrderState) -> CycleTrade {
+        let (up_shares, down_shares) = (Self::held_shares(s, "Up"), Self::held_shares(s, "Down"));
         CycleTrade {
+            market: s.asset.clone(),
             condition_id: s.condition_id.clone(),
             period_timestamp: s.market_period_start as u64,
-            market_duration_secs: MARKET_DURATION_SECS_U64,
-            up_token_id: Some(s.up_token_id.clone()),
-            down_token_id: Some(s.down_token_id.clone()),
-            up_shares: shares,
-            down_shares: shares,
-            up_avg_price: s.up_order_price,
-            down_avg_price: s.down_order_price,
+            market_duration_secs: (s.expiry - s.market_period_start).max(0) as u64,
+            legs: vec![
+                TradeLeg::new("Up", Some(s.up_token_id.clone()), up_shares, if up_shares > 0.0 { s.up_order_price } else { 0.0 }),
+                TradeLeg::new("Down", Some(s.down_token_id.clone()), down_shares, if down_shares > 0.0 { s.down_order_price } else { 0.0 }),
+            ],
+            decision: s.decision,
         }
     }
 
-    async fn place_limit_order(&self, token_id: &str, side: &str, price: f64) -> Result<OrderResponse> {
+    /// `period_end` is when the order's market period ends; with `expire_orders_at_period_end`
+    /// the order is good until then only.
+    async fn place_limit_order(&self, token_id: &str, side: &str, price: f64, shares: f64, period_end: i64) -> Result<OrderResponse> {
+        let expires_at = self.config.strategy.expire_orders_at_period_end.then_some(period_end);
         let price = Self::round_price(price);
-        if self.config.strategy.simulation_mode {
+        let response = if self.config.strategy.simulation_mode {
             log::info!("🎮 SIMULATION: Would place {} order for token {}: {} shares @ ${:.2}", 
-                side, token_id, self.config.strategy.shares, price);
+                side, token_id, shares, price);
             
-            let fake_order_id = format!("SIM-{}-{}", side, chrono::Utc::now().timestamp());
+            let fake_order_id = format!("SIM-{}-{}", side, clock::now());
+            metrics().inc_orders_placed(side);
             
-            Ok(OrderResponse {
+            OrderResponse {
                 order_id: Some(fake_order_id),
                 status: "SIMULATED".to_string(),
                 message: Some("Order simulated (not placed)".to_string()),
-            })
+                matched_shares: None,
+                matched_usd: None,
+            }
         } else {
             let order = OrderRequest {
                 token_id: token_id.to_string(),
                 side: side.to_string(),
-                size: self.config.strategy.shares.to_string(),
+                size: shares.to_string(),
                 price: price.to_string(),
-                order_type: "LIMIT".to_string(),
+                order_type: if expires_at.is_some() { "GTD" } else { "GTC" }.to_string(),
+                expires_at,
             };
-            self.api.place_order(&order).await
-        }
+            let sent = std::time::Instant::now();
+            let priority = if side == "BUY" { OrderPriority::expansion(period_end) } else { OrderPriority::lock(period_end) };
+            let response = match self.api.queued(priority, self.api.place_order(&order)).await {
+                Ok(response) => response,
+                Err(e) => {
+                    self.risk.note_order_error(side, &e).await;
+                    return Err(e.into());
+                }
+            };
+            metrics().observe_latency("order_round_trip", sent.elapsed());
+            if let Some(order_id) = &response.order_id {
+                instance::record_order(order_id);
+            }
+            log::debug!("{} {} order acknowledged in {}ms", side, token_id, sent.elapsed().as_millis());
+            response
+        };
+        tracing::info!(
+            target: EVENTS,
+            event = "order_placed",
+            token_id,
+            side,
+            price,
+            shares,
+            order_id = response.order_id.as_deref().unwrap_or(""),
+            simulated = self.simulated(),
+        );
+        Ok(response)
     }
 
     async fn check_order_matches(&self, state: &mut PreLimitOrderState) -> Result<()> {
@@ -733,8 +2856,18 @@ impl PreLimitStrategy {
             return Ok(());
         }
 
+        if state.resting_cancelled {
+            return Ok(());
+        }
+
+        // A simulated order can't fill before it would have reached the book
+        let latency_ms = self.config.strategy.fill_model.latency_ms_simulated as i64;
+        if self.simulated() && clock::now_ms() < state.order_placed_at * 1000 + latency_ms {
+            return Ok(());
+        }
+
         // Production: verify fill status via CLOB API (ground truth). Simulation: infer from price.
-        if !self.config.strategy.simulation_mode {
+        if !self.simulated() {
             if let (Some(up_id), Some(down_id)) = (&state.up_order_id, &state.down_order_id) {
                 // Skip API for simulation-style fake order IDs
                 if !up_id.starts_with("SIM-") && !down_id.starts_with("SIM-") {
@@ -743,10 +2876,12 @@ impl PreLimitStrategy {
                             if up_filled && !state.up_matched {
                                 log::info!("✅ Up order filled for {} (verified via API)", state.asset);
                                 state.up_matched = true;
+                                self.record_fill(state, "Up", "api");
                             }
                             if down_filled && !state.down_matched {
                                 log::info!("✅ Down order filled for {} (verified via API)", state.asset);
                                 state.down_matched = true;
+                                self.record_fill(state, "Down", "api");
                             }
                             return Ok(());
                         }
@@ -759,14 +2894,16 @@ impl PreLimitStrategy {
         }
 
         // Simulation or API fallback: infer matched from current price vs limit
-        let up_price_result = self.api.get_price(&state.up_token_id, "SELL").await;
-        let down_price_result = self.api.get_price(&state.down_token_id, "SELL").await;
+        let up_price_result = self.feed.price(&state.up_token_id).await;
+        let down_price_result = self.feed.price(&state.down_token_id).await;
         
+        // No order rests on a side a plugin position didn't buy, or whose order the kill switch cancelled
+        let (up_absent, down_absent) = (state.up_order_id.is_none(), state.down_order_id.is_none());
         if let Ok(up_price) = up_price_result {
             let up_price_f64: f64 = up_price.to_string().parse().unwrap_or(0.0);
             let limit = state.up_order_price;
-            if (up_price_f64 <= limit || (up_price_f64 - limit).abs() < 0.001) && !state.up_matched {
-                if self.config.strategy.simulation_mode {
+            if (up_price_f64 <= limit || (up_price_f64 - limit).abs() < 0.001) && !state.up_matched && !up_absent {
+                if self.simulated() {
                     log::info!("🎮 SIMULATION: Up order matched for {} (price hit ${:.4} <= ${:.2})", 
                         state.asset, up_price_f64, limit);
                 } else {
@@ -774,6 +2911,7 @@ impl PreLimitStrategy {
                         state.asset, up_price_f64, limit);
                 }
                 state.up_matched = true;
+                self.record_fill(state, "Up", "price");
             }
         }
         
@@ -783,8 +2921,8 @@ impl PreLimitStrategy {
             let price_matches = down_price_f64 <= limit || (down_price_f64 - limit).abs() < 0.001;
             log::debug!("Checking Down order for {}: price=${:.2}, limit=${:.2}, matches={}", 
                 state.asset, down_price_f64, limit, price_matches);
-            if price_matches && !state.down_matched {
-                if self.config.strategy.simulation_mode {
+            if price_matches && !state.down_matched && !down_absent {
+                if self.simulated() {
                     log::info!("🎮 SIMULATION: Down order matched for {} (price hit ${:.2} <= ${:.2})", 
                         state.asset, down_price_f64, limit);
                 } else {
@@ -792,6 +2930,7 @@ impl PreLimitStrategy {
                         state.asset, down_price_f64, limit);
                 }
                 state.down_matched = true;
+                self.record_fill(state, "Down", "price");
             }
         } else {
             log::debug!("Failed to get Down price for {}: {:?}", state.asset, down_price_result);
@@ -800,7 +2939,6 @@ impl PreLimitStrategy {
     }
 
     async fn display_market_status(&self) -> Result<()> {
-        let assets = vec!["BTC", "ETH", "SOL", "XRP"];
         let current_time_et = Self::get_current_time_et();
         
         let total_profit = {
@@ -809,22 +2947,38 @@ impl PreLimitStrategy {
         };
         
         log::info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
-        log::info!("📊 Market Status Update | 💰 Total Profit: ${:.2}", total_profit);
+        log::info!("📊 Market Status Update | 💰 Total Profit: {}", display::usd(total_profit));
+        if self.config.metrics.enabled {
+            metrics().set_unrealized_pnl(self.account.as_deref(), self.unrealized_pnl().await);
+        }
+        if let Some(limit) = self.risk.daily_loss_limit() {
+            let daily = self.risk.daily_realized_pnl().await;
+            let halted = if self.risk.is_halted().await { " | 🛑 HALTED" } else { "" };
+            log::info!("🛡️  Daily realized PnL: {} (limit -{}){}", display::usd(daily), display::usd(limit), halted);
+        }
+        if let Some(limit) = self.risk.max_drawdown_limit() {
+            let drawdown = self.risk.drawdown().await;
+            log::info!("🛡️  Drawdown: {} below the {} peak (max {}, limit {})",
+                display::usd(drawdown.current), display::usd(drawdown.peak), display::usd(drawdown.max), display::usd(limit));
+        }
+        if let Some(latency) = metrics().latency_summary() {
+            log::info!("⏱️  Latency: {}", latency);
+        }
         log::info!("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━");
         
         let mut states = self.states.lock().await;
         let mut states_to_check: Vec<String> = Vec::new();
         
-        for asset in &assets {
-            if let Some(state) = states.get_mut(*asset) {
+        for asset in self.config.traded_assets() {
+            let asset = asset.as_str();
+            if let Some(state) = states.get_mut(asset) {
                 let market_period = state.market_period_start;
-                let slug = MarketDiscovery::build_15m_slug(asset, market_period);
                 
-                match self.api.get_market_by_slug(&slug).await {
+                match self.discovery.find_market(asset, MarketDuration::M15, market_period).await {
                     Ok(market) => {
                         if market.active && !market.closed {
-                            let up_price_result = self.api.get_price(&state.up_token_id, "SELL").await;
-                            let down_price_result = self.api.get_price(&state.down_token_id, "SELL").await;
+                            let up_price_result = self.feed.price(&state.up_token_id).await;
+                            let down_price_result = self.feed.price(&state.down_token_id).await;
                             
                             let market_end = market_period + MARKET_DURATION_SECS;
                             let time_remaining = market_end - current_time_et;
@@ -855,13 +3009,15 @@ impl PreLimitStrategy {
                                 .map(|p| p <= down_limit || (p - down_limit).abs() < 0.001)
                                 .unwrap_or(false);
 
-                            if up_price_matched && !state.up_matched {
+                            if up_price_matched && !state.up_matched && !state.resting_cancelled && state.up_order_id.is_some() {
                                 state.up_matched = true;
+                                self.record_fill(state, "Up", "price");
                                 states_to_check.push(asset.to_string());
                                 log::debug!("Display: Up order matched for {} (price hit limit)", asset);
                             }
-                            if down_price_matched && !state.down_matched {
+                            if down_price_matched && !state.down_matched && !state.resting_cancelled && state.down_order_id.is_some() {
                                 state.down_matched = true;
+                                self.record_fill(state, "Down", "price");
                                 states_to_check.push(asset.to_string());
                                 log::debug!("Display: Down order matched for {} (price hit limit)", asset);
                             }
@@ -890,20 +3046,19 @@ impl PreLimitStrategy {
                 }
             } else {
                 let current_period_et = Self::get_current_15m_period_et();
-                let slug = MarketDiscovery::build_15m_slug(asset, current_period_et);
-                log::debug!("Trying to find {} market with slug: {}", asset, slug);
+                log::debug!("Trying to find {} market for period {}", asset, current_period_et);
                 
-                match self.api.get_market_by_slug(&slug).await {
+                match self.discovery.find_market(asset, MarketDuration::M15, current_period_et).await {
                     Ok(market) => {
                         if market.active && !market.closed {
-                            match self.api.get_market(&market.condition_id).await {
+                            match self.api.get_market_metadata(&market.condition_id).await {
                                 Ok(_) => {
                                     match self.discovery.get_market_tokens(&market.condition_id).await {
                                         Ok((up_token_id, down_token_id)) => {
                                             // Get prices via REST API
                                             let (up_price_result, down_price_result) = tokio::join!(
-                                                self.api.get_price(&up_token_id, "SELL"),
-                                                self.api.get_price(&down_token_id, "SELL")
+                                                self.feed.price(&up_token_id),
+                                                self.feed.price(&down_token_id)
                                             );
                                             
                                             let market_end = current_period_et + MARKET_DURATION_SECS;
@@ -935,7 +3090,7 @@ impl PreLimitStrategy {
                         }
                     }
                     Err(e) => {
-                        log::info!("{} | Current market not found (slug: {}, error: {})", asset, slug, e);
+                        log::info!("{} | Current market not found (period: {}, error: {})", asset, current_period_et, e);
                     }
                 }
             }
@@ -947,13 +3102,13 @@ impl PreLimitStrategy {
 
         for asset in states_to_check {
             let mut states = self.states.lock().await;
-            if let Some(mut state) = states.get_mut(&asset) {
+            if let Some(state) = states.get_mut(&asset) {
                 // Check and update matches based on current prices
                 // Note: get_mut gives us a mutable reference, so changes are already in the HashMap
                 let before_up = state.up_matched;
                 let before_down = state.down_matched;
                 
-                if let Err(e) = self.check_order_matches(&mut state).await {
+                if let Err(e) = self.check_order_matches(state).await {
                     log::debug!("Error checking order matches for {}: {}", asset, e);
                 }
 
@@ -967,3 +3122,123 @@ impl PreLimitStrategy {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mockapi::MockServer;
+
+    const UP: &str = "1001";
+    const DOWN: &str = "1002";
+
+    fn position(up_order_id: Option<String>, down_order_id: Option<String>) -> PreLimitOrderState {
+        PreLimitOrderState {
+            asset: "BTC".to_string(),
+            condition_id: "0xc0ffee".to_string(),
+            up_token_id: UP.to_string(),
+            down_token_id: DOWN.to_string(),
+            up_order_id,
+            down_order_id,
+            up_order_price: 0.45,
+            down_order_price: 0.45,
+            shares: 10.0,
+            up_matched: false,
+            down_matched: false,
+            merged: false,
+            expiry: 1767726900,
+            risk_sold: false,
+            order_placed_at: 1767725820,
+            market_period_start: 1767726000,
+            one_side_matched_at: None,
+            resting_cancelled: false,
+            sold_side: None,
+            unsold: 0.0,
+            partial_sell: None,
+            spike_sold: None,
+            decision: None,
+        }
+    }
+
+    async fn server() -> MockServer {
+        let server = MockServer::start().await.unwrap();
+        server.add_market("btc-updown-15m-1767726000", "0xc0ffee", UP, DOWN);
+        server.set_book(UP, &[(0.40, 100.0)], &[(0.50, 100.0)]);
+        server.set_book(DOWN, &[(0.40, 100.0)], &[(0.50, 100.0)]);
+        server
+    }
+
+    fn strategy(server: &MockServer) -> PreLimitStrategy {
+        let mut config = Config::default();
+        config.ledger.enabled = false;
+        config.strategy.risk.kill_switch_file = std::env::temp_dir().join(format!("polymarket-bot-strategy-{}.kill", std::process::id()));
+        let api = Arc::new(server.api());
+        let feed = Arc::new(MarketFeed::new(Arc::clone(&api), &config));
+        PreLimitStrategy::new(api, feed, config)
+    }
+
+    #[tokio::test]
+    async fn a_failed_cancel_keeps_the_order_tracked_until_a_retry_succeeds() {
+        let server = server().await;
+        let strategy = strategy(&server);
+        let buy = |token_id: &str| OrderRequest {
+            token_id: token_id.to_string(),
+            side: "BUY".to_string(),
+            size: "10".to_string(),
+            price: "0.45".to_string(),
+            order_type: "GTC".to_string(),
+            expires_at: None,
+        };
+        let up = strategy.api.place_order(&buy(UP)).await.unwrap().order_id;
+        let down = strategy.api.place_order(&buy(DOWN)).await.unwrap().order_id;
+        strategy.states.lock().await.insert("BTC".to_string(), position(up.clone(), down.clone()));
+
+        server.fail_cancels(true);
+        strategy.cancel_resting_orders().await;
+        {
+            let states = strategy.states.lock().await;
+            let s = &states["BTC"];
+            assert_eq!((&s.up_order_id, &s.down_order_id), (&up, &down));
+            assert!(!s.resting_cancelled);
+        }
+        assert!(server.orders().iter().all(|o| o.open));
+
+        server.fail_cancels(false);
+        strategy.cancel_resting_orders().await;
+        assert!(strategy.states.lock().await.is_empty());
+        assert!(server.orders().iter().all(|o| !o.open));
+    }
+
+    #[test]
+    fn exposure_counts_what_a_sale_left_held() {
+        let exposure = |s: &PreLimitOrderState| {
+            let (up, down) = PreLimitStrategy::asset_exposure(&HashMap::from([("BTC".to_string(), s.clone())]), "btc");
+            ((up * 100.0).round() / 100.0, (down * 100.0).round() / 100.0)
+        };
+        let mut s = position(Some("up-1".to_string()), Some("down-1".to_string()));
+        assert_eq!(exposure(&s), (4.5, 4.5), "both resting");
+        s.up_matched = true;
+        assert_eq!(exposure(&s), (4.5, 0.0), "Up held, Down resting");
+        s.down_matched = true;
+        assert_eq!(exposure(&s), (0.0, 0.0), "a full pair is hedged");
+
+        // Sell-opposite sold Down: the Up winner is still held, less a spike sell
+        s.merged = true;
+        s.sold_side = Some("Down".to_string());
+        assert_eq!(exposure(&s), (4.5, 0.0));
+        s.spike_sold = Some(4.0);
+        assert_eq!(exposure(&s), (2.7, 0.0));
+        s.unsold = 4.0;
+        assert_eq!(exposure(&s), (0.9, 0.0), "part of Down went unsold");
+
+        // A danger exit of a lone Up fill that sold all but 2 shares; Down's order was cancelled
+        let mut s = position(Some("up-1".to_string()), Some("down-1".to_string()));
+        s.up_matched = true;
+        s.merged = true;
+        s.risk_sold = true;
+        s.sold_side = Some("Up".to_string());
+        s.unsold = 2.0;
+        assert_eq!(exposure(&s), (0.9, 0.0));
+        s.unsold = 0.0;
+        assert_eq!(exposure(&s), (0.0, 0.0));
+    }
+}
@@ -0,0 +1,293 @@
+use crate::config::{OutlierFilter, TrendConfig, TrendMode, VolatilitySizingConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trend {
+    Rising,
+    Falling,
+    /// No significant trend, or not enough samples yet
+    Flat,
+}
+
+/// Recent Up prices for one market period, oldest first. Pushing a sample from a new period
+/// starts over.
+#[derive(Debug, Clone, Default)]
+pub struct PriceHistory {
+    period_start: i64,
+    /// (unix milliseconds, price)
+    points: VecDeque<(i64, f64)>,
+}
+
+impl PriceHistory {
+    pub fn push(&mut self, period_start: i64, timestamp_ms: i64, price: f64, capacity: usize) {
+        if period_start != self.period_start {
+            self.period_start = period_start;
+            self.points.clear();
+        }
+        self.points.push_back((timestamp_ms, price));
+        while self.points.len() > capacity.max(2) {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn trend(&self, cfg: &TrendConfig) -> Trend {
+        detect(cfg, &filter_outliers(cfg, &self.latest(cfg.window)))
+    }
+
+    /// Sample standard deviation of the last `window` prices; `None` until there are two.
+    pub fn std_dev(&self, window: usize) -> Option<f64> {
+        let prices: Vec<f64> = self.latest(window).iter().map(|(_, p)| *p).collect();
+        let n = prices.len();
+        if n < 2 {
+            return None;
+        }
+        let mean = prices.iter().sum::<f64>() / n as f64;
+        let var = prices.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        Some(var.sqrt())
+    }
+
+    fn latest(&self, window: usize) -> Vec<(i64, f64)> {
+        let skip = self.points.len().saturating_sub(window);
+        self.points.iter().skip(skip).copied().collect()
+    }
+}
+
+/// Order size for `shares` given the recent standard deviation of the price: full size up to
+/// `reference_std`, then inversely proportional to it, never below `min_scale`. Scaled sizes are
+/// rounded down to hundredths of a share.
+pub fn volatility_scaled_shares(cfg: &VolatilitySizingConfig, shares: f64, std_dev: Option<f64>) -> f64 {
+    match std_dev {
+        Some(std) if cfg.enabled && std > cfg.reference_std => {
+            let scale = (cfg.reference_std / std).max(cfg.min_scale);
+            ((shares * scale * 100.0).floor() / 100.0).max(0.01)
+        }
+        _ => shares,
+    }
+}
+
+/// `points` with isolated bad ticks tamed so one can't fake a trend: under `median`, each price
+/// becomes the median of the `median_span` samples around it (shifted inward at the ends, so
+/// the newest sample is filtered too); under `winsorize`, prices are clamped to the window's
+/// `winsor_quantile` quantiles.
+pub fn filter_outliers(cfg: &TrendConfig, points: &[(i64, f64)]) -> Vec<(i64, f64)> {
+    let n = points.len();
+    match cfg.outlier_filter {
+        OutlierFilter::Off => points.to_vec(),
+        OutlierFilter::Median => {
+            let span = cfg.median_span.min(n);
+            if span < 3 {
+                return points.to_vec();
+            }
+            (0..n)
+                .map(|i| {
+                    let start = i.saturating_sub(span / 2).min(n - span);
+                    let mut prices: Vec<f64> = points[start..start + span].iter().map(|(_, p)| *p).collect();
+                    prices.sort_by(f64::total_cmp);
+                    (points[i].0, prices[span / 2])
+                })
+                .collect()
+        }
+        OutlierFilter::Winsorize => {
+            if n < 3 {
+                return points.to_vec();
+            }
+            let mut sorted: Vec<f64> = points.iter().map(|(_, p)| *p).collect();
+            sorted.sort_by(f64::total_cmp);
+            // Below n/2 for a quantile below 0.5, so low <= high
+            let cut = (cfg.winsor_quantile * n as f64).floor() as usize;
+            let (low, high) = (sorted[cut], sorted[n - 1 - cut]);
+            points.iter().map(|(t, p)| (*t, p.clamp(low, high))).collect()
+        }
+    }
+}
+
+/// Classifies `points` (oldest first) by their slope in price per minute.
+pub fn detect(cfg: &TrendConfig, points: &[(i64, f64)]) -> Trend {
+    let slope = match cfg.trend_mode {
+        TrendMode::Off => return Trend::Flat,
+        TrendMode::Endpoints => endpoints_slope(points),
+        TrendMode::Ema => ema_slope(points, cfg.window),
+        TrendMode::Regression => regression_slope(points, cfg.min_t_stat),
+    };
+    match slope {
+        Some(s) if s >= cfg.threshold => Trend::Rising,
+        Some(s) if s <= -cfg.threshold => Trend::Falling,
+        _ => Trend::Flat,
+    }
+}
+
+fn minutes_between(a: i64, b: i64) -> f64 {
+    (b - a) as f64 / 60_000.0
+}
+
+/// Last price minus first price over the elapsed time. Cheap but dominated by noise at either end.
+fn endpoints_slope(points: &[(i64, f64)]) -> Option<f64> {
+    let (first, last) = (points.first()?, points.last()?);
+    let dt = minutes_between(first.0, last.0);
+    (dt > 0.0).then(|| (last.1 - first.1) / dt)
+}
+
+/// Exponential moving average of the per-sample slopes, weighting recent moves more.
+fn ema_slope(points: &[(i64, f64)], window: usize) -> Option<f64> {
+    let alpha = 2.0 / (window.max(1) as f64 + 1.0);
+    let mut ema = None;
+    for pair in points.windows(2) {
+        let dt = minutes_between(pair[0].0, pair[1].0);
+        if dt <= 0.0 {
+            continue;
+        }
+        let slope = (pair[1].1 - pair[0].1) / dt;
+        ema = Some(match ema {
+            None => slope,
+            Some(prev) => alpha * slope + (1.0 - alpha) * prev,
+        });
+    }
+    ema
+}
+
+/// Least-squares slope, reported only if its t-statistic reaches `min_t_stat`.
+fn regression_slope(points: &[(i64, f64)], min_t_stat: f64) -> Option<f64> {
+    let n = points.len();
+    if n < 3 {
+        return None;
+    }
+    let t0 = points[0].0;
+    let xs: Vec<f64> = points.iter().map(|(t, _)| minutes_between(t0, *t)).collect();
+    let ys: Vec<f64> = points.iter().map(|(_, p)| *p).collect();
+    let mean_x = xs.iter().sum::<f64>() / n as f64;
+    let mean_y = ys.iter().sum::<f64>() / n as f64;
+    let sxx: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    if sxx <= 0.0 {
+        return None;
+    }
+    let sxy: f64 = xs.iter().zip(&ys).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let ssr: f64 = xs.iter().zip(&ys).map(|(x, y)| (y - (intercept + slope * x)).powi(2)).sum();
+    let std_err = (ssr / (n - 2) as f64 / sxx).sqrt();
+    // A perfect fit has zero error and is as significant as it gets
+    let significant = std_err == 0.0 || (slope / std_err).abs() >= min_t_stat;
+    significant.then_some(slope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flat at 0.50 for five minutes, then one glitch tick at 0.95.
+    fn spiked() -> Vec<(i64, f64)> {
+        let mut points: Vec<(i64, f64)> = (0..5).map(|m| (m * 60_000, 0.50)).collect();
+        points.push((5 * 60_000, 0.95));
+        points
+    }
+
+    #[test]
+    fn a_single_bad_tick_does_not_make_a_trend() {
+        let cfg = |outlier_filter| TrendConfig { trend_mode: TrendMode::Endpoints, outlier_filter, ..TrendConfig::default() };
+        assert_eq!(detect(&cfg(OutlierFilter::Off), &spiked()), Trend::Rising);
+        for filter in [OutlierFilter::Median, OutlierFilter::Winsorize] {
+            let filtered = filter_outliers(&cfg(filter), &spiked());
+            assert_eq!(filtered.last(), Some(&(5 * 60_000, 0.50)), "{:?}", filter);
+            assert_eq!(detect(&cfg(filter), &filtered), Trend::Flat, "{:?}", filter);
+        }
+    }
+
+    #[test]
+    fn median_filter_keeps_a_sustained_move() {
+        let cfg = TrendConfig { trend_mode: TrendMode::Endpoints, ..TrendConfig::default() };
+        let points: Vec<(i64, f64)> = (0..6).map(|m| (m * 60_000, 0.40 + 0.05 * m as f64)).collect();
+        // The ends lag by a sample, the slope survives
+        assert_eq!(detect(&cfg, &filter_outliers(&cfg, &points)), Trend::Rising);
+    }
+
+    /// Nine one-minute samples around 0.50 moving `step` a minute, zigzagging by `wiggle`.
+    fn series(step: f64, wiggle: f64) -> Vec<(i64, f64)> {
+        (0..9).map(|m| (m * 60_000, 0.50 + step * (m - 4) as f64 + if m % 2 == 0 { wiggle } else { -wiggle })).collect()
+    }
+
+    fn mode(trend_mode: TrendMode) -> TrendConfig {
+        TrendConfig { trend_mode, ..TrendConfig::default() }
+    }
+
+    #[test]
+    fn every_mode_reads_up_down_and_flat_series() {
+        for trend_mode in [TrendMode::Endpoints, TrendMode::Ema, TrendMode::Regression] {
+            let cfg = mode(trend_mode);
+            assert_eq!(detect(&cfg, &series(0.04, 0.005)), Trend::Rising, "{:?}", trend_mode);
+            assert_eq!(detect(&cfg, &series(-0.04, 0.005)), Trend::Falling, "{:?}", trend_mode);
+            assert_eq!(detect(&cfg, &series(0.0, 0.005)), Trend::Flat, "{:?}", trend_mode);
+            assert_eq!(detect(&cfg, &series(0.04, 0.005)[..1]), Trend::Flat, "{:?}", trend_mode);
+        }
+        assert_eq!(detect(&mode(TrendMode::Off), &series(0.04, 0.0)), Trend::Flat);
+    }
+
+    #[test]
+    fn a_slope_exactly_at_the_threshold_is_a_trend() {
+        // 1/32 a minute, exact in binary so every mode computes it without rounding
+        let step = 0.03125;
+        let rising: Vec<(i64, f64)> = (0..5).map(|m| (m * 60_000, 0.25 + step * m as f64)).collect();
+        let falling: Vec<(i64, f64)> = rising.iter().map(|&(t, p)| (t, 1.0 - p)).collect();
+        for trend_mode in [TrendMode::Endpoints, TrendMode::Ema, TrendMode::Regression] {
+            let at = TrendConfig { threshold: step, ..mode(trend_mode) };
+            assert_eq!((detect(&at, &rising), detect(&at, &falling)), (Trend::Rising, Trend::Falling), "{:?}", trend_mode);
+            let above = TrendConfig { threshold: step + 1e-9, ..mode(trend_mode) };
+            assert_eq!((detect(&above, &rising), detect(&above, &falling)), (Trend::Flat, Trend::Flat), "{:?}", trend_mode);
+        }
+    }
+
+    #[test]
+    fn regression_needs_a_significant_slope() {
+        // Slope 0.03 a minute under heavy zigzag: t is about 2.58
+        let noisy = series(0.03, 0.08);
+        let cfg = |min_t_stat| TrendConfig { min_t_stat, ..mode(TrendMode::Regression) };
+        assert_eq!(detect(&cfg(2.5), &noisy), Trend::Rising);
+        assert_eq!(detect(&cfg(2.6), &noisy), Trend::Flat);
+        // The endpoints see the same slope whatever the noise
+        assert_eq!(detect(&mode(TrendMode::Endpoints), &noisy), Trend::Rising);
+        // A perfect fit passes any bar
+        assert_eq!(detect(&cfg(1e9), &series(0.03, 0.0)), Trend::Rising);
+    }
+
+    #[test]
+    fn size_shrinks_with_volatility_between_full_and_the_floor() {
+        let cfg = VolatilitySizingConfig { enabled: true, window: 30, reference_std: 0.02, min_scale: 0.25 };
+        let sized = |std_dev| volatility_scaled_shares(&cfg, 10.0, std_dev);
+        // (standard deviation, shares); a NaN deviation is as unknown as none
+        let cases = [
+            (None, 10.0),
+            (Some(0.0), 10.0),
+            (Some(f64::NAN), 10.0),
+            (Some(0.01), 10.0),
+            (Some(0.02), 10.0),
+            (Some(0.03), 6.66),
+            (Some(0.04), 5.0),
+            (Some(0.08), 2.5),
+            (Some(0.5), 2.5),
+        ];
+        for (std_dev, shares) in cases {
+            assert_eq!(sized(std_dev), shares, "{:?}", std_dev);
+        }
+        let steps: Vec<f64> = [0.02, 0.025, 0.03, 0.05, 0.07].into_iter().map(|s| sized(Some(s))).collect();
+        assert!(steps.windows(2).all(|w| w[1] < w[0]), "{:?}", steps);
+
+        // A tiny order never rounds to nothing; off, volatility is ignored
+        assert_eq!(volatility_scaled_shares(&cfg, 0.02, Some(0.5)), 0.01);
+        assert_eq!(volatility_scaled_shares(&VolatilitySizingConfig { enabled: false, ..cfg.clone() }, 10.0, Some(0.5)), 10.0);
+    }
+
+    #[test]
+    fn volatility_is_the_sample_deviation_of_the_latest_prices() {
+        let mut history = PriceHistory::default();
+        history.push(900, 0, 0.50, 10);
+        assert_eq!(history.std_dev(5), None);
+        for (i, price) in [0.90, 0.40, 0.60, 0.40, 0.60].into_iter().enumerate() {
+            history.push(900, i as i64 + 1, price, 10);
+        }
+        // The last four: mean 0.50, squared deviations 0.04 over 3
+        assert!((history.std_dev(4).unwrap() - (0.04f64 / 3.0).sqrt()).abs() < 1e-12);
+        history.push(1800, 0, 0.60, 10);
+        assert_eq!(history.std_dev(4), None, "a new period starts over");
+    }
+}
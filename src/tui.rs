@@ -0,0 +1,323 @@
+use crate::api::PolymarketApi;
+use crate::config::Config;
+use crate::control::{MarketStatus, StatusReport};
+use crate::discovery::MarketDiscovery;
+use crate::display;
+use crate::logging::ConsoleBuffer;
+use crate::models::MarketSnapshot;
+use crate::feed::MarketFeed;
+use crate::monitor::MarketMonitor;
+use crate::strategy::{PreLimitStrategy, MARKET_DURATION_SECS};
+use anyhow::{Context, Result};
+use futures_util::future::join_all;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, Paragraph, Sparkline};
+use ratatui::Frame;
+use std::collections::{HashMap, VecDeque};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration, Instant};
+
+/// How often prices and positions are re-fetched.
+const REFRESH: Duration = Duration::from_secs(1);
+/// How often keys are polled and the screen redrawn.
+const TICK: Duration = Duration::from_millis(200);
+/// Sparkline points kept per market (one per refresh), reset each period.
+const SPARKLINE_POINTS: usize = 900;
+
+/// `--tui`: live panels per 15m market (prices, fills, pair cost, PnL if each side wins,
+/// timers) above the console log.
+pub struct Dashboard {
+    strategies: Vec<Arc<PreLimitStrategy>>,
+    monitor: MarketMonitor,
+    assets: Vec<String>,
+    console: ConsoleBuffer,
+    console_file: PathBuf,
+    markets: HashMap<String, MarketView>,
+    status: Option<StatusReport>,
+}
+
+#[derive(Default)]
+struct MarketView {
+    period_start: i64,
+    snapshot: Option<MarketSnapshot>,
+    /// Up price in cents
+    up_prices: VecDeque<u64>,
+}
+
+impl Dashboard {
+    pub fn new(api: Arc<PolymarketApi>, feed: Arc<MarketFeed>, config: &Config, strategies: Vec<Arc<PreLimitStrategy>>, console: ConsoleBuffer) -> Self {
+        Self {
+            strategies,
+            monitor: MarketMonitor::new(api, feed, config),
+            assets: config.traded_assets(),
+            console,
+            console_file: config.logging.tui_console_file.clone(),
+            markets: HashMap::new(),
+            status: None,
+        }
+    }
+
+    /// Owns the terminal until `q` / Ctrl-C (which triggers `shutdown`) or an external shutdown.
+    /// The terminal and stderr are restored before `strategy_shutdown` is flipped, so the final
+    /// summary prints normally.
+    pub async fn run(mut self, shutdown: watch::Sender<bool>, strategy_shutdown: watch::Sender<bool>) -> Result<()> {
+        let mut external = shutdown.subscribe();
+        let result = match StderrRedirect::to(&self.console_file) {
+            Ok(redirect) => {
+                let result = self.draw_loop(&shutdown, &mut external).await;
+                ratatui::restore();
+                drop(redirect);
+                result
+            }
+            Err(e) => Err(e),
+        };
+        let _ = shutdown.send(true);
+        let _ = strategy_shutdown.send(true);
+        result
+    }
+
+    async fn draw_loop(&mut self, shutdown: &watch::Sender<bool>, external: &mut watch::Receiver<bool>) -> Result<()> {
+        let mut terminal = ratatui::try_init().context("Failed to start the terminal dashboard")?;
+        let mut next_refresh = Instant::now();
+        while !*external.borrow() {
+            if Instant::now() >= next_refresh {
+                self.refresh().await;
+                next_refresh = Instant::now() + REFRESH;
+            }
+            terminal.draw(|frame| self.draw(frame))?;
+            if quit_requested()? {
+                let _ = shutdown.send(true);
+                break;
+            }
+            tokio::select! {
+                _ = sleep(TICK) => {}
+                _ = external.changed() => break,
+            }
+        }
+        Ok(())
+    }
+
+    async fn refresh(&mut self) {
+        let period_start = MarketDiscovery::current_15m_period_start_et();
+        let monitor = &self.monitor;
+        let snapshots = join_all(self.assets.iter().map(|asset| monitor.snapshot(asset, period_start))).await;
+        for (asset, snapshot) in self.assets.iter().zip(snapshots) {
+            let view = self.markets.entry(asset.clone()).or_default();
+            if view.period_start != period_start {
+                *view = MarketView { period_start, ..Default::default() };
+            }
+            view.snapshot = snapshot.ok();
+            if let Some(s) = &view.snapshot {
+                if view.up_prices.len() == SPARKLINE_POINTS {
+                    view.up_prices.pop_front();
+                }
+                view.up_prices.push_back((s.up.price * 100.0).round() as u64);
+            }
+        }
+        self.status = Some(StatusReport::collect(&self.strategies).await);
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, markets, log] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Min(6),
+            Constraint::Length(10),
+        ])
+        .areas(frame.area());
+
+        self.draw_header(frame, header);
+        let rows = Layout::vertical(vec![Constraint::Ratio(1, self.assets.len().max(1) as u32); self.assets.len()])
+            .split(markets);
+        for (asset, area) in self.assets.iter().zip(rows.iter()) {
+            let statuses: Vec<&MarketStatus> = self
+                .status
+                .as_ref()
+                .map(|r| r.markets.iter().filter(|m| &m.asset == asset).collect())
+                .unwrap_or_default();
+            self.draw_market(frame, *area, asset, &statuses);
+        }
+
+        let lines = self.console.tail(log.height.saturating_sub(2) as usize);
+        frame.render_widget(
+            List::new(lines).block(Block::default().borders(Borders::ALL).title(" Log ")),
+            log,
+        );
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        let now = crate::clock::now();
+        let remaining = (MarketDiscovery::current_15m_period_start_et() + MARKET_DURATION_SECS - now).max(0);
+        let mut lines = vec![Line::from(format!(
+            "Period ends in {:02}:{:02}   |   q to quit",
+            remaining / 60,
+            remaining % 60
+        ))];
+        if let Some(r) = &self.status {
+            lines.push(Line::from(vec![
+                pnl_span("Realized ", r.realized_pnl, display::usd(r.realized_pnl)),
+                pnl_span("   today ", r.daily_realized_pnl, display::usd(r.daily_realized_pnl)),
+                pnl_span("   Unrealized ", r.unrealized_pnl, display::usd(r.unrealized_pnl)),
+                Span::raw(format!("   Pending redemptions {}", r.pending_redemptions)),
+                if r.trading_halted {
+                    Span::styled("   🛑 KILL SWITCH", Style::default().fg(Color::Red))
+                } else {
+                    Span::raw("")
+                },
+            ]));
+        }
+        let simulation = self.status.as_ref().is_some_and(|r| r.simulation_mode);
+        let title = if simulation { " Polymarket pre-limit bot — 🎮 SIMULATION " } else { " Polymarket pre-limit bot " };
+        frame.render_widget(Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+
+    /// `statuses` has one entry per account trading `asset`.
+    fn draw_market(&self, frame: &mut Frame, area: Rect, asset: &str, statuses: &[&MarketStatus]) {
+        let view = self.markets.get(asset);
+        let paused = statuses.iter().any(|m| m.paused);
+        let title = format!(" {}{} ", asset, if paused { " ⏸ paused" } else { "" });
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let [text, chart] = Layout::horizontal([Constraint::Length(48), Constraint::Min(10)]).areas(inner);
+
+        let mut lines = Vec::new();
+        match view.and_then(|v| v.snapshot.as_ref()) {
+            Some(s) => {
+                lines.push(Line::from(format!(
+                    "Ask  Up {}   Down {}",
+                    display::price(s.up.best_ask.unwrap_or(s.up.price)),
+                    display::price(s.down.best_ask.unwrap_or(s.down.price))
+                )));
+                if let Some(reason) = &s.invalid {
+                    lines.push(Line::styled(format!("Invalid book: {}", reason), Style::default().fg(Color::Red)));
+                }
+            }
+            None => lines.push(Line::from("No market for the current period")),
+        }
+        for m in statuses {
+            if let Some(account) = &m.account {
+                lines.push(Line::styled(format!("[{}]", account), Style::default().fg(Color::Cyan)));
+            }
+            Self::market_lines(&mut lines, m);
+        }
+        if statuses.is_empty() {
+            lines.push(Line::from("No position"));
+        }
+        frame.render_widget(Paragraph::new(lines), text);
+
+        let points: Vec<u64> = view.map(|v| v.up_prices.iter().copied().collect()).unwrap_or_default();
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::default().title("Up price"))
+                .data(&points)
+                .max(100)
+                .style(Style::default().fg(Color::Cyan)),
+            chart,
+        );
+    }
+
+    /// Position, PnL-if-wins, timers and last error of one account's market.
+    fn market_lines(lines: &mut Vec<Line<'static>>, m: &MarketStatus) {
+        match &m.position {
+            Some(p) => {
+                let filled = |f: bool| if f { p.shares } else { 0.0 };
+                lines.push(Line::from(format!(
+                    "Held Up {} @ {}   Down {} @ {}",
+                    display::shares(filled(p.up_filled)),
+                    display::price(p.up_price),
+                    display::shares(filled(p.down_filled)),
+                    display::price(p.down_price)
+                )));
+                lines.push(Line::from(format!("Pair cost ${:.4}", p.cost_per_pair)));
+                if let (Some(up), Some(down)) = (p.pnl_if_up_wins, p.pnl_if_down_wins) {
+                    lines.push(Line::from(vec![
+                        pnl_span("If Up wins ", up, display::pnl(up, p.shares)),
+                        pnl_span("   If Down wins ", down, display::pnl(down, p.shares)),
+                    ]));
+                } else if let Some(side) = &p.sold_side {
+                    lines.push(Line::from(format!("Closed (sold {})", side)));
+                } else {
+                    lines.push(Line::from("Closed"));
+                }
+            }
+            None => lines.push(Line::from("No position")),
+        }
+        if let Some(secs) = m.danger_exit_in_secs {
+            lines.push(Line::styled(format!("Danger exit in {}s", secs), Style::default().fg(Color::Yellow)));
+        } else if m.next_orders_in_secs > 0 {
+            lines.push(Line::from(format!("Next pre-orders in {}s", m.next_orders_in_secs)));
+        }
+        if let Some(e) = &m.last_error {
+            lines.push(Line::styled(format!("Error: {}", e.message), Style::default().fg(Color::Red)));
+        }
+    }
+}
+
+/// `shown` colored by the sign of `pnl`.
+fn pnl_span(label: &str, pnl: f64, shown: String) -> Span<'static> {
+    let color = if pnl > 0.0 { Color::Green } else if pnl < 0.0 { Color::Red } else { Color::Reset };
+    Span::styled(format!("{}{}", label, shown), Style::default().fg(color))
+}
+
+/// Raw mode swallows Ctrl-C, so it is handled as a key here along with `q`.
+fn quit_requested() -> Result<bool> {
+    while event::poll(std::time::Duration::ZERO)? {
+        if let Event::Key(key) = event::read()? {
+            let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if key.kind == KeyEventKind::Press && (key.code == KeyCode::Char('q') || ctrl_c) {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+/// Points stderr at a file while the dashboard owns the terminal, so `eprintln!` output
+/// doesn't tear the screen; the original stderr comes back on drop.
+struct StderrRedirect {
+    saved: Option<i32>,
+}
+
+impl StderrRedirect {
+    fn to(path: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context(format!("Failed to open console file {}", path.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+            // SAFETY: plain fd duplication; `file` stays open for the duration of the calls
+            let saved = unsafe { libc::dup(libc::STDERR_FILENO) };
+            if saved < 0 || unsafe { libc::dup2(file.as_raw_fd(), libc::STDERR_FILENO) } < 0 {
+                anyhow::bail!("Failed to redirect stderr to {}", path.display());
+            }
+            Ok(Self { saved: Some(saved) })
+        }
+        #[cfg(not(unix))]
+        {
+            drop(file);
+            Ok(Self { saved: None })
+        }
+    }
+}
+
+impl Drop for StderrRedirect {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(saved) = self.saved.take() {
+            // SAFETY: `saved` is the duplicate of the original stderr taken in `to`
+            unsafe {
+                libc::dup2(saved, libc::STDERR_FILENO);
+                libc::close(saved);
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use crate::config::WatchdogConfig;
+use serde::Serialize;
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration};
+
+/// Liveness of the running bot: when each monitor last produced a snapshot of each of its
+/// markets, when each strategy loop last ticked and when an API call last succeeded.
+pub struct Health {
+    started: Instant,
+    api_ok: Mutex<Option<Instant>>,
+    /// (monitor, asset) -> last snapshot, or when the monitor started watching the market
+    snapshots: Mutex<BTreeMap<(String, String), Instant>>,
+    /// Strategy loop -> last tick
+    ticks: Mutex<BTreeMap<String, Instant>>,
+    /// Monitor -> signal that restarts it
+    restarts: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+/// What `GET /healthz` returns.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    pub healthy: bool,
+    pub uptime_secs: u64,
+    /// Seconds since an API call last succeeded; None if none has yet
+    pub last_api_ok_secs: Option<u64>,
+    pub api_stale: bool,
+    pub loops: Vec<Beat>,
+    pub markets: Vec<Beat>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Beat {
+    /// Strategy loop, or the monitor watching the market
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset: Option<String>,
+    pub last_secs: u64,
+    pub stale: bool,
+}
+
+static HEALTH: OnceLock<Health> = OnceLock::new();
+
+pub fn health() -> &'static Health {
+    HEALTH.get_or_init(|| Health {
+        started: Instant::now(),
+        api_ok: Mutex::new(None),
+        snapshots: Mutex::new(BTreeMap::new()),
+        ticks: Mutex::new(BTreeMap::new()),
+        restarts: Mutex::new(HashMap::new()),
+    })
+}
+
+impl Health {
+    pub fn api_ok(&self) {
+        *self.api_ok.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Starts (or restarts) the clock on `monitor`'s markets, so a market that never produces a
+    /// snapshot goes stale too.
+    pub fn watch(&self, monitor: &str, assets: &[String]) {
+        let now = Instant::now();
+        let mut snapshots = self.snapshots.lock().unwrap();
+        for asset in assets {
+            snapshots.insert((monitor.to_string(), asset.clone()), now);
+        }
+    }
+
+    pub fn snapshot(&self, monitor: &str, asset: &str) {
+        self.snapshots.lock().unwrap().insert((monitor.to_string(), asset.to_string()), Instant::now());
+    }
+
+    pub fn tick(&self, strategy: &str) {
+        self.ticks.lock().unwrap().insert(strategy.to_string(), Instant::now());
+    }
+
+    /// Notified when the watchdog finds one of `monitor`'s markets silent.
+    pub fn restart_signal(&self, monitor: &str) -> Arc<Notify> {
+        Arc::clone(self.restarts.lock().unwrap().entry(monitor.to_string()).or_default())
+    }
+
+    pub fn report(&self, stale_secs: u64) -> HealthReport {
+        let beat = |name: &str, asset: Option<&str>, at: Instant| {
+            let last_secs = at.elapsed().as_secs();
+            Beat { name: name.to_string(), asset: asset.map(str::to_string), last_secs, stale: last_secs >= stale_secs }
+        };
+        let loops: Vec<Beat> = self.ticks.lock().unwrap().iter().map(|(name, at)| beat(name, None, *at)).collect();
+        let markets: Vec<Beat> = self
+            .snapshots
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|((monitor, asset), at)| beat(monitor, Some(asset), *at))
+            .collect();
+        let api_ok = *self.api_ok.lock().unwrap();
+        let last_api_ok_secs = api_ok.map(|at| at.elapsed().as_secs());
+        let api_stale = api_ok.unwrap_or(self.started).elapsed().as_secs() >= stale_secs;
+        HealthReport {
+            healthy: !api_stale && !loops.iter().chain(&markets).any(|b| b.stale),
+            uptime_secs: self.started.elapsed().as_secs(),
+            last_api_ok_secs,
+            api_stale,
+            loops,
+            markets,
+        }
+    }
+}
+
+/// Checks liveness every `check_interval_secs` until shutdown. A monitor with a silent market is
+/// restarted. A stuck strategy loop or an API that stopped answering can't be fixed from inside
+/// the process: with `exit_on_stale` the bot exits with status 1 for its supervisor to restart it,
+/// otherwise it raises one alert per outage.
+pub async fn run(config: WatchdogConfig, mut shutdown: watch::Receiver<bool>) {
+    let mut alerted = false;
+    while !*shutdown.borrow() {
+        tokio::select! {
+            _ = sleep(Duration::from_secs(config.check_interval_secs)) => {}
+            _ = shutdown.changed() => break,
+        }
+        let report = health().report(config.stale_secs);
+
+        let mut silent: Vec<&Beat> = report.markets.iter().filter(|b| b.stale).collect();
+        silent.dedup_by(|a, b| a.name == b.name);
+        for beat in silent {
+            log::warn!("🐕 {} has had no {} snapshot for {}s — restarting it",
+                beat.name, beat.asset.as_deref().unwrap_or_default(), beat.last_secs);
+            health().restart_signal(&beat.name).notify_one();
+        }
+
+        let stuck: Vec<String> = report
+            .loops
+            .iter()
+            .filter(|b| b.stale)
+            .map(|b| format!("{} silent for {}s", b.name, b.last_secs))
+            .chain(report.api_stale.then(|| match report.last_api_ok_secs {
+                Some(secs) => format!("no successful API call for {}s", secs),
+                None => "no successful API call since startup".to_string(),
+            }))
+            .collect();
+        if stuck.is_empty() {
+            if alerted {
+                log::info!("🐕 Watchdog: strategy loops and the API are live again");
+            }
+            alerted = false;
+            continue;
+        }
+        if alerted && !config.exit_on_stale {
+            continue;
+        }
+        tracing::error!(
+            target: crate::logging::EVENTS,
+            event = "watchdog",
+            stuck = %stuck.join("; "),
+            exit = config.exit_on_stale,
+        );
+        if config.exit_on_stale {
+            log::error!("🚨 Watchdog: {} — exiting for the supervisor to restart the bot", stuck.join("; "));
+            std::process::exit(1);
+        }
+        log::error!("🚨 Watchdog: {}", stuck.join("; "));
+        alerted = true;
+    }
+}
+
+/// Text of a panic payload: the message of `panic!`, `unwrap` and friends.
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Spawns the task built by `make` and watches it. A panic is logged, counted in
+/// `polymarket_task_panics_total` and, with `restart`, the task is spawned afresh; a task that
+/// returns normally is done.
+pub fn spawn_supervised<F, Fut>(name: &'static str, restart: bool, make: F) -> JoinHandle<()>
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        loop {
+            let Err(e) = tokio::spawn(make()).await else { return };
+            let Ok(panic) = e.try_into_panic() else { return };
+            let message = panic_message(panic.as_ref());
+            log::error!("💥 {} panicked: {}", name, message);
+            tracing::error!(target: crate::logging::EVENTS, event = "task_panic", task = name, message = %message, restart);
+            crate::metrics::metrics().inc_task_panic(name, "");
+            if !restart {
+                log::error!("🚨 {} stays down (watchdog.restart_on_panic is off)", name);
+                return;
+            }
+            log::warn!("🔄 Restarting {}", name);
+            sleep(Duration::from_secs(1)).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[tokio::test]
+    async fn supervised_task_is_restarted_after_a_panic() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&runs);
+        let task = spawn_supervised("test task", true, move || {
+            let runs = Arc::clone(&counter);
+            async move {
+                if runs.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("first run fails");
+                }
+            }
+        });
+        tokio::time::timeout(Duration::from_secs(5), task).await.unwrap().unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert!(crate::metrics::metrics().render().contains("polymarket_task_panics_total{task=\"test task\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn supervised_task_stays_down_without_restart() {
+        let runs = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&runs);
+        let task = spawn_supervised("test task once", false, move || {
+            let runs = Arc::clone(&counter);
+            async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                panic!("always fails");
+            }
+        });
+        task.await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,312 @@
+use crate::config::FeedConfig;
+use crate::metrics::metrics;
+use crate::models::{OrderBook, OrderBookEntry};
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::sync::Notify;
+use tokio::time::{interval, sleep, Duration};
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+
+/// The market channel expects a `PING` at least this often and answers each with `PONG`.
+const PING_SECS: u64 = 10;
+const MAX_BACKOFF_SECS: u64 = 30;
+/// Tokens nobody has read for this long are left out of the next subscription.
+const IDLE_SECS: u64 = 60;
+/// New tokens tend to arrive together at a period rollover; wait for the rest before reconnecting.
+const RESUBSCRIBE_DEBOUNCE_MS: u64 = 250;
+
+/// Order books kept current from the CLOB market WebSocket channel: a `book` snapshot per token
+/// on subscribing, then `price_change` level updates and `last_trade_price` trades. The channel
+/// takes its token list when connecting, so a newly read token triggers a reconnect.
+pub struct WsFeed {
+    url: String,
+    stale: Duration,
+    books: Mutex<HashMap<String, Book>>,
+    /// Tokens to subscribe to -> last read
+    wanted: Mutex<HashMap<String, Instant>>,
+    /// When the socket last delivered a frame (a `PONG` counts), or when the first token was
+    /// wanted if it hasn't connected yet
+    last_frame: Mutex<Option<Instant>>,
+    resubscribe: Notify,
+}
+
+/// Top of a token's book.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Top {
+    pub bid: Option<Decimal>,
+    pub ask: Option<Decimal>,
+    pub last_trade: Option<Decimal>,
+}
+
+#[derive(Default)]
+struct Book {
+    /// Price -> size
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+    last_trade: Option<Decimal>,
+    tick_size: Option<Decimal>,
+}
+
+#[derive(Deserialize)]
+struct Level {
+    price: Decimal,
+    size: Decimal,
+}
+
+#[derive(Deserialize)]
+struct Change {
+    #[serde(default)]
+    asset_id: Option<String>,
+    price: Decimal,
+    size: Decimal,
+    side: String,
+}
+
+#[derive(Deserialize)]
+struct Event {
+    event_type: String,
+    #[serde(default)]
+    asset_id: Option<String>,
+    #[serde(default, alias = "buys")]
+    bids: Vec<Level>,
+    #[serde(default, alias = "sells")]
+    asks: Vec<Level>,
+    /// Level updates, possibly for several tokens of the market
+    #[serde(default)]
+    price_changes: Vec<Change>,
+    /// Older form of `price_changes`, all for `asset_id`
+    #[serde(default)]
+    changes: Vec<Change>,
+    #[serde(default)]
+    price: Option<Decimal>,
+    #[serde(default)]
+    new_tick_size: Option<Decimal>,
+}
+
+impl WsFeed {
+    pub fn new(config: &FeedConfig) -> Self {
+        Self {
+            url: config.ws_url.clone(),
+            stale: Duration::from_millis(config.ws_stale_ms),
+            books: Mutex::new(HashMap::new()),
+            wanted: Mutex::new(HashMap::new()),
+            last_frame: Mutex::new(None),
+            resubscribe: Notify::new(),
+        }
+    }
+
+    /// Keeps `token_id` in the subscription, adding it (with a reconnect) if it is new.
+    pub fn watch(&self, token_id: &str) {
+        let new = self.wanted.lock().unwrap().insert(token_id.to_string(), Instant::now()).is_none();
+        if new {
+            self.last_frame.lock().unwrap().get_or_insert_with(Instant::now);
+            self.resubscribe.notify_one();
+        }
+    }
+
+    /// How long the socket has been silent; None before any token is wanted.
+    pub fn silent_for(&self) -> Option<Duration> {
+        self.last_frame.lock().unwrap().map(|at| at.elapsed())
+    }
+
+    /// Whether the socket has delivered a frame within `ws_stale_ms`.
+    pub fn is_live(&self) -> bool {
+        self.silent_for().is_some_and(|silent| silent < self.stale)
+    }
+
+    pub fn top(&self, token_id: &str) -> Option<Top> {
+        self.books.lock().unwrap().get(token_id).map(Book::top)
+    }
+
+    /// Tops of every wanted token the socket has a book for.
+    pub fn tops(&self) -> Vec<(String, Top)> {
+        let wanted = self.wanted.lock().unwrap();
+        let books = self.books.lock().unwrap();
+        wanted.keys().filter_map(|id| books.get(id).map(|b| (id.clone(), b.top()))).collect()
+    }
+
+    /// The token's book in CLOB order (best level last), as `GET /book` returns it.
+    pub fn book(&self, token_id: &str) -> Option<OrderBook> {
+        let books = self.books.lock().unwrap();
+        let book = books.get(token_id)?;
+        let entry = |(price, size): (&Decimal, &Decimal)| OrderBookEntry { price: *price, size: *size };
+        Some(OrderBook {
+            bids: book.bids.iter().map(entry).collect(),
+            asks: book.asks.iter().rev().map(entry).collect(),
+            tick_size: book.tick_size,
+            min_order_size: None,
+        })
+    }
+
+    /// Connects, subscribes and reconnects until the process exits.
+    pub async fn run(self: Arc<Self>) {
+        let mut backoff = 1;
+        loop {
+            let assets: Vec<String> = {
+                let mut wanted = self.wanted.lock().unwrap();
+                wanted.retain(|_, read| read.elapsed() < Duration::from_secs(IDLE_SECS));
+                wanted.keys().cloned().collect()
+            };
+            if assets.is_empty() {
+                self.resubscribe.notified().await;
+                sleep(Duration::from_millis(RESUBSCRIBE_DEBOUNCE_MS)).await;
+                continue;
+            }
+            self.books.lock().unwrap().retain(|id, _| assets.contains(id));
+            match self.session(&assets).await {
+                Ok(()) => {
+                    backoff = 1;
+                    sleep(Duration::from_millis(RESUBSCRIBE_DEBOUNCE_MS)).await;
+                }
+                Err(e) => {
+                    metrics().inc_ws_reconnect();
+                    log::warn!("📡 Market WebSocket: {} — reconnecting in {}s", e, backoff);
+                    sleep(Duration::from_secs(backoff)).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF_SECS);
+                }
+            }
+        }
+    }
+
+    /// One connection, until it fails or the token list changes (Ok).
+    async fn session(&self, assets: &[String]) -> Result<()> {
+        let (socket, _) = connect_async(self.url.as_str()).await?;
+        let (mut tx, mut rx) = socket.split();
+        let subscribe = serde_json::json!({ "assets_ids": assets, "type": "market" });
+        tx.send(Message::Text(subscribe.to_string())).await?;
+        log::debug!("📡 Market WebSocket subscribed to {} tokens", assets.len());
+        let mut ping = interval(Duration::from_secs(PING_SECS));
+        loop {
+            tokio::select! {
+                frame = rx.next() => match frame {
+                    Some(Ok(Message::Text(text))) => {
+                        self.frame();
+                        self.apply(&text);
+                    }
+                    Some(Ok(Message::Close(_))) | None => anyhow::bail!("closed by the server"),
+                    Some(Ok(_)) => self.frame(),
+                    Some(Err(e)) => return Err(e.into()),
+                },
+                _ = ping.tick() => tx.send(Message::Text("PING".to_string())).await?,
+                _ = self.resubscribe.notified() => return Ok(()),
+            }
+        }
+    }
+
+    fn frame(&self) {
+        *self.last_frame.lock().unwrap() = Some(Instant::now());
+    }
+
+    /// Applies one text frame: `PONG`, or one event or an array of them.
+    fn apply(&self, text: &str) {
+        if text == "PONG" {
+            return;
+        }
+        let events: Vec<serde_json::Value> = match serde_json::from_str(text) {
+            Ok(serde_json::Value::Array(events)) => events,
+            Ok(event) => vec![event],
+            Err(e) => {
+                log::debug!("Unreadable market WebSocket frame ({}): {}", e, text);
+                return;
+            }
+        };
+        let mut books = self.books.lock().unwrap();
+        for event in events {
+            let event: Event = match serde_json::from_value(event) {
+                Ok(event) => event,
+                Err(e) => {
+                    log::debug!("Unreadable market WebSocket event: {}", e);
+                    continue;
+                }
+            };
+            match event.event_type.as_str() {
+                "book" => {
+                    let Some(id) = event.asset_id else { continue };
+                    let levels = |levels: Vec<Level>| levels.into_iter().filter(|l| !l.size.is_zero()).map(|l| (l.price, l.size)).collect();
+                    let book = books.entry(id).or_default();
+                    book.bids = levels(event.bids);
+                    book.asks = levels(event.asks);
+                }
+                "price_change" => {
+                    let changes = event.price_changes.into_iter().chain(event.changes);
+                    for change in changes {
+                        // Levels only make sense on top of a snapshot
+                        let Some(book) = change.asset_id.as_ref().or(event.asset_id.as_ref()).and_then(|id| books.get_mut(id)) else {
+                            continue;
+                        };
+                        let side = if change.side.eq_ignore_ascii_case("BUY") { &mut book.bids } else { &mut book.asks };
+                        if change.size.is_zero() {
+                            side.remove(&change.price);
+                        } else {
+                            side.insert(change.price, change.size);
+                        }
+                    }
+                }
+                "last_trade_price" => {
+                    if let (Some(book), Some(price)) = (event.asset_id.and_then(|id| books.get_mut(&id)), event.price) {
+                        book.last_trade = Some(price);
+                    }
+                }
+                "tick_size_change" => {
+                    if let Some(book) = event.asset_id.and_then(|id| books.get_mut(&id)) {
+                        book.tick_size = event.new_tick_size.or(book.tick_size);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Book {
+    fn top(&self) -> Top {
+        Top {
+            bid: self.bids.keys().next_back().copied(),
+            ask: self.asks.keys().next().copied(),
+            last_trade: self.last_trade,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn applies_snapshots_level_changes_and_trades() {
+        let feed = WsFeed::new(&FeedConfig::default());
+        feed.apply(r#"[{"event_type":"book","asset_id":"up","bids":[{"price":"0.48","size":"30"},{"price":"0.47","size":"10"}],"asks":[{"price":"0.52","size":"25"},{"price":"0.50","size":"5"}]}]"#);
+        assert_eq!(feed.top("up"), Some(Top { bid: Some(dec!(0.48)), ask: Some(dec!(0.50)), last_trade: None }));
+
+        feed.apply(r#"{"event_type":"price_change","market":"0xc","price_changes":[{"asset_id":"up","price":"0.50","size":"0","side":"SELL"},{"asset_id":"up","price":"0.49","size":"12","side":"BUY"},{"asset_id":"down","price":"0.5","size":"1","side":"BUY"}]}"#);
+        feed.apply(r#"{"event_type":"last_trade_price","asset_id":"up","price":"0.51","side":"BUY","size":"4"}"#);
+        feed.apply("PONG");
+        assert_eq!(feed.top("up"), Some(Top { bid: Some(dec!(0.49)), ask: Some(dec!(0.52)), last_trade: Some(dec!(0.51)) }));
+        // No snapshot for the other token yet
+        assert_eq!(feed.top("down"), None);
+
+        let book = feed.book("up").unwrap();
+        let prices = |levels: &[OrderBookEntry]| levels.iter().map(|l| l.price).collect::<Vec<_>>();
+        assert_eq!(prices(&book.bids), vec![dec!(0.47), dec!(0.48), dec!(0.49)]);
+        assert_eq!(prices(&book.asks), vec![dec!(0.52)]);
+    }
+
+    #[test]
+    fn is_stalled_until_a_frame_arrives_within_the_stale_window() {
+        let config = FeedConfig { ws_stale_ms: 50, ..FeedConfig::default() };
+        let feed = WsFeed::new(&config);
+        assert!(!feed.is_live());
+        feed.watch("up");
+        assert!(feed.is_live());
+        std::thread::sleep(std::time::Duration::from_millis(60));
+        assert!(!feed.is_live());
+        feed.frame();
+        assert!(feed.is_live());
+    }
+}